@@ -0,0 +1,99 @@
+//! Buy message compilation: a freshly assembled compute-budget prefix vs the reused
+//! buffer, each compiled into a v0 message as `TransactionBuilder` does.
+//!
+//! Besides timing, prints heap allocations per build for both paths (counted by a
+//! wrapping global allocator).
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use solana_sdk::hash::Hash;
+use solana_sdk::instruction::{AccountMeta, Instruction};
+use solana_sdk::message::VersionedMessage;
+use solana_sdk::pubkey::Pubkey;
+use sniffer_bot_light::tx_builder::{
+    compile_message, compile_message_with_tables, compute_budget_instructions, InstructionBuffer,
+};
+
+struct CountingAllocator;
+
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        System.realloc(ptr, layout, new_size)
+    }
+}
+
+#[global_allocator]
+static GLOBAL: CountingAllocator = CountingAllocator;
+
+const CU_LIMIT: u32 = 200_000;
+const CU_PRICE: u64 = 10_000;
+
+fn program_instruction(program_id: Pubkey, accounts: &[AccountMeta]) -> Instruction {
+    Instruction::new_with_bytes(program_id, &[1, 2, 3, 4, 5, 6, 7, 8], accounts.to_vec())
+}
+
+/// Build without the buffer (the builder's fallback under contention): the
+/// compute-budget prefix assembled for every build, then compiled.
+fn build_fresh(payer: &Pubkey, program_id: Pubkey, accounts: &[AccountMeta], blockhash: Hash) -> VersionedMessage {
+    let mut instructions = compute_budget_instructions(CU_LIMIT, CU_PRICE);
+    instructions.push(program_instruction(program_id, accounts));
+    compile_message(payer, &instructions, blockhash, false).unwrap()
+}
+
+/// Build through the reused buffer holding the compute-budget prefix.
+fn build_buffered(
+    buffer: &mut InstructionBuffer,
+    payer: &Pubkey,
+    program_id: Pubkey,
+    accounts: &[AccountMeta],
+    blockhash: Hash,
+) -> VersionedMessage {
+    buffer.with_tail(vec![program_instruction(program_id, accounts)], |instructions| {
+        compile_message_with_tables(payer, instructions, blockhash, false, &[]).unwrap()
+    })
+}
+
+fn allocations_per_build<T>(iterations: usize, mut build: impl FnMut() -> T) -> f64 {
+    let before = ALLOCATIONS.load(Ordering::Relaxed);
+    for _ in 0..iterations {
+        black_box(build());
+    }
+    (ALLOCATIONS.load(Ordering::Relaxed) - before) as f64 / iterations as f64
+}
+
+fn bench_instruction_buffer(c: &mut Criterion) {
+    let payer = Pubkey::new_unique();
+    let program_id = Pubkey::new_unique();
+    let accounts: Vec<AccountMeta> = (0..8).map(|_| AccountMeta::new(Pubkey::new_unique(), false)).collect();
+    let blockhash = Hash::new_unique();
+    let mut buffer = InstructionBuffer::new(CU_LIMIT, CU_PRICE);
+
+    let fresh = allocations_per_build(10_000, || build_fresh(&payer, program_id, &accounts, blockhash));
+    let buffered =
+        allocations_per_build(10_000, || build_buffered(&mut buffer, &payer, program_id, &accounts, blockhash));
+    println!("allocations per build: fresh={:.1} buffered={:.1}", fresh, buffered);
+
+    let mut group = c.benchmark_group("instruction_buffer");
+    group.bench_function("fresh", |b| b.iter(|| build_fresh(&payer, black_box(program_id), &accounts, blockhash)));
+    group.bench_function("buffered", |b| {
+        b.iter(|| build_buffered(&mut buffer, &payer, black_box(program_id), &accounts, blockhash))
+    });
+    group.finish();
+}
+
+criterion_group!(benches, bench_instruction_buffer);
+criterion_main!(benches);
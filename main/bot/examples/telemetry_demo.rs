@@ -33,7 +33,7 @@ async fn main() {
     
     // Record some latencies
     for latency in [50, 75, 100, 150, 200] {
-        metrics().record_histogram("buy_latency_seconds", Duration::from_millis(latency));
+        metrics().record_histogram("buy_latency_seconds", Duration::from_millis(latency).as_secs_f64());
     }
 
     // Test security features
@@ -47,6 +47,9 @@ async fn main() {
             .duration_since(std::time::UNIX_EPOCH)
             .unwrap()
             .as_secs(),
+        instruction_summary: None,
+        is_jito_bundle: None,
+        deadline_ms: None,
     };
     
     let validation = validator().validate_candidate(&test_candidate);
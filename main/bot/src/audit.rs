@@ -0,0 +1,112 @@
+//! Append-only JSON Lines audit trail.
+//!
+//! Records every operator/engine command, mode transition and successful
+//! broadcast with a timestamp and correlation id, one JSON object per line.
+//! Separate from tracing output so it can be kept and parsed on its own.
+
+use std::fs::{File, OpenOptions};
+use std::io::{BufRead, BufReader, Write};
+use std::path::Path;
+use std::sync::Mutex;
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use tracing::warn;
+
+use crate::time_utils::now_ms;
+use crate::types::Mode;
+
+/// What happened.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum AuditEvent {
+    /// A buy/sell command was issued (`percent` only for sells).
+    Command {
+        command: String,
+        mint: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        percent: Option<f64>,
+    },
+    /// The engine changed mode.
+    ModeTransition { from: String, to: String },
+    /// A transaction was accepted by an RPC endpoint.
+    Broadcast {
+        kind: String,
+        mint: String,
+        signature: String,
+        endpoint: String,
+    },
+}
+
+/// One line of the audit log.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AuditEntry {
+    pub timestamp_ms: u64,
+    pub correlation_id: u64,
+    #[serde(flatten)]
+    pub event: AuditEvent,
+}
+
+/// Short, stable label for a mode in audit entries.
+pub fn mode_label(mode: &Mode) -> String {
+    match mode {
+        Mode::Sniffing => "sniffing".to_string(),
+        Mode::PassiveToken(mint) => format!("passive_token:{}", mint),
+        Mode::QuantumManual => "quantum_manual".to_string(),
+    }
+}
+
+/// Audit log writer; a disabled log drops every entry.
+#[derive(Debug, Default)]
+pub struct AuditLog {
+    file: Option<Mutex<File>>,
+}
+
+impl AuditLog {
+    /// Open (or create) `path` for appending.
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let path = path.as_ref();
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .with_context(|| format!("open audit log {}", path.display()))?;
+        Ok(Self { file: Some(Mutex::new(file)) })
+    }
+
+    pub fn disabled() -> Self {
+        Self::default()
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.file.is_some()
+    }
+
+    /// Append an entry. Write failures are logged, never propagated: auditing must
+    /// not stop trading.
+    pub fn record(&self, correlation_id: u64, event: AuditEvent) {
+        let Some(file) = &self.file else { return };
+        let entry = AuditEntry { timestamp_ms: now_ms(), correlation_id, event };
+        let line = match serde_json::to_string(&entry) {
+            Ok(line) => line,
+            Err(e) => {
+                warn!(error = %e, "Failed to encode audit entry");
+                return;
+            }
+        };
+        let mut file = file.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        if let Err(e) = writeln!(file, "{}", line).and_then(|_| file.flush()) {
+            warn!(error = %e, "Failed to write audit entry");
+        }
+    }
+
+    /// Read all entries from an audit log file, oldest first.
+    pub fn read_entries<P: AsRef<Path>>(path: P) -> Result<Vec<AuditEntry>> {
+        let file = File::open(path.as_ref()).context("open audit log")?;
+        BufReader::new(file)
+            .lines()
+            .filter(|line| !matches!(line, Ok(l) if l.trim().is_empty()))
+            .map(|line| Ok(serde_json::from_str(&line?)?))
+            .collect()
+    }
+}
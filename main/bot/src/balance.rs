@@ -0,0 +1,122 @@
+//! Wallet SOL balance and the minimum reserve buys must leave untouched.
+//!
+//! `BalancePoller` keeps `SolBalance` current from `getBalance`. Before each buy the
+//! engine checks the last polled balance: a buy that would take the wallet below
+//! `min_sol_reserve_lamports` is downsized to what is spendable, or skipped when
+//! that would not even cover the buy's own costs. The balance is only as fresh as
+//! the last poll, so the reserve should leave room for one poll interval of spend.
+
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_sdk::pubkey::Pubkey;
+use tracing::warn;
+
+use crate::metrics::metrics;
+use crate::tx_builder::TransactionConfig;
+
+/// Base fee per signature.
+pub const SIGNATURE_FEE_LAMPORTS: u64 = 5_000;
+/// Rent-exempt deposit of the token account a first buy of a mint creates.
+pub const TOKEN_ACCOUNT_RENT_LAMPORTS: u64 = 2_039_280;
+
+/// Last polled SOL balance of the wallet.
+#[derive(Debug, Default)]
+pub struct SolBalance {
+    lamports: AtomicU64,
+    known: AtomicBool,
+}
+
+impl SolBalance {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn set(&self, lamports: u64) {
+        self.lamports.store(lamports, Ordering::Relaxed);
+        self.known.store(true, Ordering::Relaxed);
+        metrics().set_gauge("wallet_balance_lamports", lamports);
+    }
+
+    /// Balance in lamports, or `None` before the first poll.
+    pub fn get(&self) -> Option<u64> {
+        self.known
+            .load(Ordering::Relaxed)
+            .then(|| self.lamports.load(Ordering::Relaxed))
+    }
+}
+
+/// Lamports a buy spends besides its buy amount: fees and the token account deposit.
+pub fn buy_overhead_lamports(config: &TransactionConfig) -> u64 {
+    let priority_fee = config.priority_fee_lamports as u128 * config.compute_unit_limit as u128 / 1_000_000;
+    (priority_fee as u64)
+        .saturating_add(SIGNATURE_FEE_LAMPORTS)
+        .saturating_add(TOKEN_ACCOUNT_RENT_LAMPORTS)
+}
+
+/// Buy amount that keeps `balance` at or above `reserve` after `overhead`: the full
+/// `buy_amount` if it fits, else whatever is spendable, or `None` when the
+/// spendable rest would not even cover the overhead.
+pub fn buy_amount_within_reserve(balance: u64, buy_amount: u64, overhead: u64, reserve: u64) -> Option<u64> {
+    let spendable = balance.saturating_sub(reserve).saturating_sub(overhead);
+    if spendable >= buy_amount {
+        Some(buy_amount)
+    } else if spendable > overhead {
+        Some(spendable)
+    } else {
+        None
+    }
+}
+
+/// Periodically refreshes `SolBalance` for the wallet.
+pub struct BalancePoller {
+    rpc: Arc<RpcClient>,
+    owner: Pubkey,
+    balance: Arc<SolBalance>,
+    interval: Duration,
+}
+
+impl BalancePoller {
+    pub fn new(rpc: Arc<RpcClient>, owner: Pubkey, balance: Arc<SolBalance>, interval: Duration) -> Self {
+        Self { rpc, owner, balance, interval }
+    }
+
+    /// Run until the task is aborted; the first poll is immediate.
+    pub async fn run(self) {
+        let mut ticker = tokio::time::interval(self.interval);
+        loop {
+            ticker.tick().await;
+            match self.rpc.get_balance(&self.owner).await {
+                Ok(lamports) => self.balance.set(lamports),
+                Err(e) => warn!(error = %e, "Wallet balance poll failed"),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn buys_are_downsized_or_skipped_to_keep_the_reserve() {
+        let overhead = buy_overhead_lamports(&TransactionConfig::default());
+        let reserve = 50_000_000;
+        let buy = 10_000_000;
+
+        // Plenty of SOL: full buy
+        assert_eq!(buy_amount_within_reserve(1_000_000_000, buy, overhead, reserve), Some(buy));
+
+        // Only 6M above reserve and overhead: downsized so the reserve is kept
+        let balance = reserve + overhead + 6_000_000;
+        let downsized = buy_amount_within_reserve(balance, buy, overhead, reserve).unwrap();
+        assert_eq!(downsized, 6_000_000);
+        assert!(balance - downsized - overhead >= reserve);
+
+        // Spendable rest no larger than the overhead, or below the reserve already: skipped
+        assert_eq!(buy_amount_within_reserve(reserve + 2 * overhead, buy, overhead, reserve), None);
+        assert_eq!(buy_amount_within_reserve(reserve / 2, buy, overhead, reserve), None);
+    }
+}
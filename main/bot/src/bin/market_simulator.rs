@@ -16,7 +16,7 @@ use tracing::{info, warn, error};
 use sniffer_bot_light::market_maker::{MarketMaker, MarketMakerConfig};
 use sniffer_bot_light::test_environment::{TestEnvironment, TestValidatorConfig};
 // Removed unused import: use sniffer_bot_light::types::TokenProfile;
-use sniffer_bot_light::token_generator::{TokenProfile as GenTokenProfile, GeneratedToken};
+use sniffer_bot_light::token_generator::{DexWeights, TokenProfile as GenTokenProfile, GeneratedToken};
 use solana_sdk::signature::{Keypair, Signer};
 
 /// Configuration for market simulation
@@ -26,6 +26,8 @@ pub struct SimulationConfig {
     pub duration_secs: u64,
     /// Number of tokens to simulate
     pub token_count: usize,
+    /// Weights for the DEX each simulated token is launched on
+    pub dex_weights: DexWeights,
     /// MarketMaker configuration
     pub market_maker: MarketMakerConfig,
     /// Test environment configuration
@@ -37,6 +39,7 @@ impl Default for SimulationConfig {
         Self {
             duration_secs: 300, // 5 minutes
             token_count: 15,
+            dex_weights: DexWeights::default(),
             market_maker: MarketMakerConfig {
                 loop_interval_ms: 1000,
                 trader_wallet_count: 8,
@@ -110,6 +113,7 @@ impl MarketSimulator {
             let generated_token = GeneratedToken {
                 mint,
                 profile: GenTokenProfile::Gem,
+                dex: self.config.dex_weights.random(),
                 creator: Keypair::new().pubkey(),
                 created_at: chrono::Utc::now().timestamp() as u64,
                 initial_supply: 1_000_000,
@@ -126,6 +130,7 @@ impl MarketSimulator {
             let generated_token = GeneratedToken {
                 mint,
                 profile: GenTokenProfile::Rug,
+                dex: self.config.dex_weights.random(),
                 creator: Keypair::new().pubkey(),
                 created_at: chrono::Utc::now().timestamp() as u64,
                 initial_supply: 1_000_000,
@@ -142,6 +147,7 @@ impl MarketSimulator {
             let generated_token = GeneratedToken {
                 mint,
                 profile: GenTokenProfile::Trash,
+                dex: self.config.dex_weights.random(),
                 creator: Keypair::new().pubkey(),
                 created_at: chrono::Utc::now().timestamp() as u64,
                 initial_supply: 1_000_000,
@@ -373,6 +379,7 @@ async fn main() -> Result<()> {
             trash_transaction_count: 3,
         },
         test_env: TestValidatorConfig::default(),
+        dex_weights: DexWeights::default(),
     };
     
     // Create and run simulator
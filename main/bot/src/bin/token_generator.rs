@@ -21,9 +21,9 @@ use solana_sdk::{
     pubkey::Pubkey,
     rent::Rent,
     signature::{Keypair, Signer},
-    system_instruction,
     transaction::VersionedTransaction,
 };
+use solana_system_interface::instruction as system_instruction;
 use spl_associated_token_account::{
     get_associated_token_address,
     instruction::create_associated_token_account,
@@ -39,7 +39,8 @@ use tracing::{debug, error, info, warn};
 // These would be imported from the bot crate in a real workspace setup
 use sniffer_bot_light::rpc_manager::RpcBroadcaster;
 use sniffer_bot_light::wallet::WalletManager;
-use sniffer_bot_light::token_generator::{TokenProfile, GeneratedToken};
+use sniffer_bot_light::token_generator::{DexWeights, TokenProfile, GeneratedToken};
+use sniffer_bot_light::types::DexProgram;
 
 
 /// Configuration for the token generator
@@ -49,6 +50,8 @@ pub struct SimulatorConfig {
     pub interval_min: Duration,
     /// Maximum interval between token generations
     pub interval_max: Duration,
+    /// Weights for the DEX each generated token is launched on
+    pub dex_weights: DexWeights,
 }
 
 /// Thread-safe storage for generated tokens
@@ -147,6 +150,11 @@ impl TokenGenerator {
         // Select random token profile based on probabilities
         let profile = self.select_random_profile();
         debug!("Selected profile: {}", profile.description());
+        let dex = {
+            let mut rng = self.rng.lock().unwrap();
+            self.config.dex_weights.pick(&mut rng)
+        };
+        debug!("Selected DEX: {}", dex.canonical_name());
 
         // Generate new mint keypair
         let mint_keypair = Keypair::new();
@@ -158,6 +166,7 @@ impl TokenGenerator {
         let transaction = self.create_initialization_transaction(
             &mint_keypair,
             &profile,
+            &dex,
         ).await?;
 
         // Submit transaction
@@ -176,6 +185,7 @@ impl TokenGenerator {
         let token = GeneratedToken {
             mint: mint_pubkey,
             profile,
+            dex,
             creator: self.wallet.pubkey(),
             created_at: std::time::SystemTime::now()
                 .duration_since(std::time::UNIX_EPOCH)?
@@ -217,6 +227,7 @@ impl TokenGenerator {
         &self,
         mint_keypair: &Keypair,
         profile: &TokenProfile,
+        dex: &DexProgram,
     ) -> Result<VersionedTransaction> {
         let mint_pubkey = mint_keypair.pubkey();
         let wallet_pubkey = self.wallet.pubkey();
@@ -279,7 +290,7 @@ impl TokenGenerator {
         }
 
         // 6. Create liquidity pool (placeholder)
-        instructions.push(self.create_liquidity_pool_instruction(&mint_pubkey, profile)?);
+        instructions.push(self.create_liquidity_pool_instruction(&mint_pubkey, profile, dex)?);
 
         // Create versioned transaction
         let message = MessageV0::try_compile(
@@ -310,7 +321,7 @@ impl TokenGenerator {
         // This would submit via the RpcBroadcaster trait.
         // For simulation, we assume it succeeds and return a placeholder.
         self.rpc.send_on_many_rpc(vec![transaction.clone()], None).await
-            .map(|sig| sig.to_string())
+            .map(|outcome| outcome.signature.to_string())
     }
 
     /// Create metadata instruction based on profile
@@ -373,15 +384,16 @@ impl TokenGenerator {
         ))
     }
 
-    /// Create liquidity pool instruction (placeholder for pump.fun integration)
+    /// Create liquidity pool instruction (placeholder for per-DEX integration)
     fn create_liquidity_pool_instruction(
         &self,
         mint_pubkey: &Pubkey,
         _profile: &TokenProfile,
+        dex: &DexProgram,
     ) -> Result<Instruction> {
-        // This is a placeholder for creating a liquidity pool on a cloned pump.fun program.
-        // For simulation purposes, it just needs to emit a specific log.
-        let memo_data = format!("CREATE_POOL:{}", mint_pubkey);
+        // This is a placeholder for creating a liquidity pool on a cloned DEX program.
+        // For simulation purposes, it just needs to emit a specific log naming the DEX.
+        let memo_data = format!("CREATE_POOL:{}:{}", dex.canonical_name(), mint_pubkey);
         Ok(Instruction::new_with_bytes(
             solana_sdk::pubkey!("MemoSq4gqABAXKb96qnH8TysNcWxMyWCqXgDLGmfcHr"), // Memo program
             memo_data.as_bytes(),
@@ -1,35 +1,61 @@
-//! Core logic for auto-buy and one-token state machine.
+//! Core logic for auto-buy and the positions it holds.
 //!
 //! Responsibilities:
-//! - Consume candidates from an mpsc receiver while in Sniffing mode.
-//! - Filter candidates by simple heuristics (e.g., program == "pump.fun").
+//! - Consume candidates from an mpsc receiver while fewer than `max_concurrent_positions`
+//!   positions are open, or scored candidates from the quantum selector, buying only
+//!   those scoring `min_buy_score`.
+//! - Filter candidates through a pluggable `CandidateFilter` (by default the `buy_programs` allowlist, pump.fun).
 //! - Acquire up to N nonces, build N distinct transactions (skeleton), and broadcast via RpcBroadcaster.
-//! - On first success, switch to PassiveToken mode (one-token mode) and hold until sold.
-//! - Provide a sell(percent) API that reduces holdings and returns to Sniffing when 100% sold.
+//! - On success, open a position in the mint (up to `max_concurrent_positions` at once)
+//!   and focus it in PassiveToken mode.
+//! - Provide a `sell(mint, percent)` API that reduces that position, closing it when 100%
+//!   is sold; with no position left the engine returns to Sniffing.
+//! - With `paper_trading`, simulate every broadcast instead of sending it.
 
-use std::{sync::{Arc, atomic::{AtomicBool, AtomicU32, Ordering}}, time::{Duration, Instant}};
+use std::{sync::{Arc, atomic::{AtomicBool, AtomicU32, AtomicU64, Ordering}}, time::{Duration, Instant}};
+use std::collections::HashMap;
+use std::str::FromStr;
 
 use anyhow::{anyhow, Context, Result};
+use chrono::{DateTime, Utc};
+use solana_client::nonblocking::rpc_client::RpcClient;
 use solana_sdk::{
+    hash::hashv,
     pubkey::Pubkey,
     signature::Signature,
     transaction::VersionedTransaction,
 };
-use tokio::sync::Mutex;
+use thiserror::Error;
+use tokio::sync::{mpsc, oneshot, Mutex, OwnedSemaphorePermit, Semaphore};
+use tokio::task::JoinHandle;
 use tokio::time::{sleep, timeout};
 use tracing::{debug, error, info, warn};
+use crate::audit::{mode_label, AuditEvent, AuditLog};
+use crate::balance::{buy_amount_within_reserve, buy_overhead_lamports, SolBalance};
+use crate::candidate_filter::{self, CandidateFilter};
 use crate::config::Config;
 
 use crate::endpoints::endpoint_server;
-use crate::metrics::{metrics, Timer};
+use crate::metrics::{funnel, metrics, CandidateFunnel, FunnelStage, Timer};
 use crate::nonce_manager::NonceManager;
+use crate::quantum_selector::ScoredCandidate;
+use crate::reconciliation::TokenBalances;
 
-use crate::rpc_manager::RpcBroadcaster;
-use crate::security::validator;
-use crate::structured_logging::PipelineContext;
+use crate::rpc_manager::{
+    classify_rpc_error, is_blockhash_not_found_error, is_frozen_account_error, is_unsupported_version_error,
+    ConfirmationStatus, RpcBroadcaster, RpcErrorType, RpcHealth, SendOutcome,
+};
+use crate::schedule::buying_allowed;
+use crate::security::{validator, AuthorityReport, SellabilityReport};
+use crate::simulation::{BuyParams, BuySimulation};
+use crate::structured_logging::{DecisionTrace, PipelineContext};
+use crate::time_utils::now_ms;
 use crate::observability::CorrelationId;
-use crate::tx_builder::{TransactionBuilder, TransactionConfig};
-use crate::types::{AppState, CandidateReceiver, Mode, PremintCandidate};
+use crate::tx_builder::{
+    fraction_of, BundleStatus, PriceSource, SellAmount, TransactionBuilder, TransactionConfig, WsolUnwrapper,
+};
+use crate::types::{AppState, CandidateReceiver, DexProgram, Mode, NotificationLevel, Position, PremintCandidate};
+use crate::user_messages::{engine_error_message, user_message};
 
 /// Exponential backoff state for failure handling
 #[derive(Debug)]
@@ -52,7 +78,6 @@ impl BackoffState {
         }
     }
 
-    #[allow(dead_code)]
     async fn record_failure(&self) {
         let failures = self.consecutive_failures.fetch_add(1, Ordering::Relaxed) + 1;
         let mut last_failure = self.last_failure.lock().await;
@@ -69,6 +94,38 @@ impl BackoffState {
         *last_failure = None;
     }
 
+    /// Decay the failure count on partial progress (e.g. some endpoints accepted,
+    /// some failed): `success_ratio` of the recorded failures is forgiven.
+    async fn record_partial_success(&self, success_ratio: f64) {
+        let ratio = if success_ratio.is_finite() { success_ratio.clamp(0.0, 1.0) } else { 0.0 };
+        if ratio >= 1.0 {
+            return self.record_success().await;
+        }
+
+        let prev_failures = self
+            .consecutive_failures
+            .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |failures| {
+                Some((failures as f64 * (1.0 - ratio)).floor() as u32)
+            })
+            .unwrap_or_default();
+        let failures = self.get_failure_count();
+        debug!("BackoffState: partial success ({:.2}), failures {} -> {}", ratio, prev_failures, failures);
+        if failures == 0 {
+            *self.last_failure.lock().await = None;
+        }
+    }
+
+    /// A sent buy: a full success once it landed, partial progress while it is
+    /// unconfirmed (accepted, but not seen to land).
+    async fn record_buy(&self, buy: &SentBuy) {
+        if buy.unconfirmed {
+            self.record_partial_success(UNCONFIRMED_BUY_PROGRESS).await;
+        } else {
+            self.record_success().await;
+        }
+    }
+
+    /// Time left to wait since the last failure, `None` once it has passed.
     async fn should_backoff(&self) -> Option<Duration> {
         let failures = self.consecutive_failures.load(Ordering::Relaxed);
         if failures == 0 {
@@ -77,8 +134,8 @@ impl BackoffState {
 
         let delay_ms = (self.base_delay_ms as f64 * self.backoff_multiplier.powi((failures - 1) as i32))
             .min(self.max_delay_ms as f64) as u64;
-        
-        Some(Duration::from_millis(delay_ms))
+        let elapsed = self.last_failure.lock().await.map(|at| at.elapsed()).unwrap_or_default();
+        Some(Duration::from_millis(delay_ms).saturating_sub(elapsed)).filter(|left| !left.is_zero())
     }
 
     fn get_failure_count(&self) -> u32 {
@@ -86,15 +143,390 @@ impl BackoffState {
     }
 }
 
+/// Delay between status polls of a buy bundle (about one slot).
+const BUNDLE_STATUS_POLL_INTERVAL: Duration = Duration::from_millis(400);
+
+/// Longest the read of the wallet's pre-buy token balance may take; a slower read
+/// is dropped and the buy goes on without it.
+const BALANCE_SNAPSHOT_TIMEOUT: Duration = Duration::from_millis(300);
+
+/// Share of the recorded buy failures forgiven when a buy is accepted but not
+/// confirmed in time.
+const UNCONFIRMED_BUY_PROGRESS: f64 = 0.5;
+
+/// Endpoint reported for broadcasts simulated under `paper_trading`.
+pub const PAPER_ENDPOINT: &str = "paper";
+
+/// Bound on buy attempts in flight at once. Clones share the bound, so engines
+/// built with the same limiter are limited together.
+#[derive(Debug, Clone)]
+pub struct InflightBuyLimiter {
+    permits: Arc<Semaphore>,
+}
+
+impl InflightBuyLimiter {
+    pub fn new(max_inflight: usize) -> Self {
+        Self { permits: Arc::new(Semaphore::new(max_inflight.max(1))) }
+    }
+
+    /// Start a buy if under the limit; the buy counts as in flight until the permit drops.
+    pub fn try_start(&self) -> Option<OwnedSemaphorePermit> {
+        self.permits.clone().try_acquire_owned().ok()
+    }
+
+    pub fn available(&self) -> usize {
+        self.permits.available_permits()
+    }
+}
+
 pub struct BuyEngine {
     pub rpc: Arc<dyn RpcBroadcaster>,
     pub nonce_manager: Arc<NonceManager>,
     pub candidate_rx: CandidateReceiver,
+    /// Candidates scored by the quantum selector; replaces `candidate_rx` when set
+    scored_rx: Option<mpsc::Receiver<ScoredCandidate>>,
     pub app_state: Arc<Mutex<AppState>>,
     pub config: Config,
-    pub tx_builder: Option<TransactionBuilder>,
+    pub tx_builder: Option<Arc<TransactionBuilder>>,
+    /// Decides which candidates are buy targets
+    candidate_filter: Box<dyn CandidateFilter>,
     backoff_state: BackoffState,
     pending_buy: Arc<AtomicBool>,
+    funnel: Arc<CandidateFunnel>,
+    audit: Arc<AuditLog>,
+    inflight: InflightBuyLimiter,
+    /// Last observed buy-schedule state, to report transitions once
+    buy_window_open: Option<bool>,
+    rpc_health: Option<Arc<RpcHealth>>,
+    /// Auto-buying is paused because too few RPC endpoints are healthy
+    rpc_paused: bool,
+    sol_balance: Option<Arc<SolBalance>>,
+    token_balances: Option<Arc<dyn TokenBalances>>,
+    wsol_unwrapper: Option<Arc<dyn WsolUnwrapper>>,
+    price_source: Option<Arc<dyn PriceSource>>,
+    /// Reads candidate mints for `sellability_check` and `require_renounced_authorities`
+    mint_rpc: Option<Arc<RpcClient>>,
+    /// When the held position was last checked against the exit thresholds
+    last_exit_check: Option<tokio::time::Instant>,
+    /// `sell_ladder` rungs already fired for each held position
+    ladder: std::sync::Mutex<LadderProgress>,
+    /// Broadcasts simulated under `paper_trading`, numbering their signatures
+    paper_sequence: AtomicU64,
+    /// Operator commands, handled between candidates
+    commands: Option<mpsc::Receiver<EngineCommand>>,
+    /// Candidates seen lately, so an operator buy of a suggested mint buys the real candidate
+    recent_candidates: RecentCandidates,
+    /// Fill checks running in the background, acted on by `poll_fill_checks`
+    fill_checks: std::sync::Mutex<Vec<(Pubkey, JoinHandle<Option<FillCheck>>)>>,
+}
+
+/// Operator command for the running engine, so it trades through the engine's
+/// transaction builder and buy guards. The result is sent back on `reply`.
+#[derive(Debug)]
+pub enum EngineCommand {
+    /// Buy `amount_lamports` worth of the recently seen candidate `mint`
+    Buy { mint: Pubkey, amount_lamports: u64, reply: oneshot::Sender<Result<SendOutcome, EngineError>> },
+    /// Sell `percent` of the focused position
+    Sell { percent: f64, reply: oneshot::Sender<Result<(), EngineError>> },
+    /// Report what the engine would do with `candidate`, without acting on it
+    Simulate { candidate: PremintCandidate, reply: oneshot::Sender<BuySimulation> },
+}
+
+/// Next thing for the engine to act on.
+enum EngineInput {
+    Candidate(PremintCandidate, Option<u8>),
+    Command(EngineCommand),
+}
+
+/// Candidates the engine saw most recently, keyed by mint.
+const RECENT_CANDIDATES_CAPACITY: usize = 512;
+
+#[derive(Debug, Default)]
+struct RecentCandidates {
+    by_mint: HashMap<Pubkey, PremintCandidate>,
+    order: std::collections::VecDeque<Pubkey>,
+}
+
+impl RecentCandidates {
+    fn remember(&mut self, candidate: &PremintCandidate) {
+        if self.by_mint.insert(candidate.mint, candidate.clone()).is_none() {
+            self.order.push_back(candidate.mint);
+        }
+        while self.order.len() > RECENT_CANDIDATES_CAPACITY {
+            if let Some(oldest) = self.order.pop_front() {
+                self.by_mint.remove(&oldest);
+            }
+        }
+    }
+
+    fn get(&self, mint: &Pubkey) -> Option<PremintCandidate> {
+        self.by_mint.get(mint).cloned()
+    }
+}
+
+/// A buy an endpoint accepted, and whether it is known to have landed.
+#[derive(Debug, Clone)]
+struct SentBuy {
+    outcome: SendOutcome,
+    /// Its bundle or signature was not seen to land in time; it may still land
+    unconfirmed: bool,
+    /// The quote's minimum token output, for the fill check
+    min_out: Option<u64>,
+    /// Wallet balance of the mint when the buy was sent, for the fill check
+    balance_before: Option<u64>,
+}
+
+impl SentBuy {
+    fn landed(outcome: SendOutcome) -> Self {
+        Self { outcome, unconfirmed: false, min_out: None, balance_before: None }
+    }
+
+    fn unconfirmed(outcome: SendOutcome) -> Self {
+        Self { unconfirmed: true, ..Self::landed(outcome) }
+    }
+}
+
+/// A sell taking the position from `from_holdings` down to `target_holdings`.
+#[derive(Debug, Clone, Copy)]
+struct SellPlan {
+    amount: SellAmount,
+    from_holdings: f64,
+    target_holdings: f64,
+}
+
+/// One position's part of a `sell_many`, waiting for the transaction carrying it.
+struct BatchedSell {
+    mint: Pubkey,
+    program: String,
+    pct: f64,
+    current_pct: f64,
+    new_holdings: f64,
+    sell_price: Option<f64>,
+    ctx: PipelineContext,
+}
+
+/// Which `sell_ladder` rungs fired, per held mint.
+#[derive(Debug, Default)]
+struct LadderProgress {
+    fired: HashMap<Pubkey, Vec<bool>>,
+}
+
+impl LadderProgress {
+    /// Unfired rungs of `ladder` reached at `multiple` x the buy price of `mint`.
+    fn due(&mut self, mint: Pubkey, ladder: &[(f64, f64)], multiple: f64) -> Vec<usize> {
+        let fired = self.fired.entry(mint).or_default();
+        if fired.len() != ladder.len() {
+            *fired = vec![false; ladder.len()];
+        }
+        ladder
+            .iter()
+            .enumerate()
+            .filter(|(i, (rung_multiple, _))| !fired[*i] && multiple >= *rung_multiple)
+            .map(|(i, _)| i)
+            .collect()
+    }
+
+    fn mark_fired(&mut self, mint: &Pubkey, rungs: &[usize]) {
+        if let Some(fired) = self.fired.get_mut(mint) {
+            for &i in rungs {
+                fired[i] = true;
+            }
+        }
+    }
+
+    /// Forget the rungs fired for `mint`, for a position opened or closed in it.
+    fn reset(&mut self, mint: &Pubkey) {
+        self.fired.remove(mint);
+    }
+}
+
+/// Why a sell or operator-triggered buy failed, for command handlers to match on.
+///
+/// Broadcast and other unclassified failures are `Other`; typed errors raised
+/// deeper in the engine are recovered from it when converting.
+#[derive(Debug, Error)]
+pub enum EngineError {
+    #[error("buying halted: {0}")]
+    Halted(String),
+    #[error("rate limited: {0}")]
+    RateLimited(String),
+    #[error("insufficient funds: {0}")]
+    InsufficientFunds(String),
+    #[error("no transaction builder available in production mode")]
+    NoBuilder,
+    #[error("buy operation in progress")]
+    BuyInProgress,
+    #[error("not accepting buys (position limit reached or manual mode)")]
+    NotSniffing,
+    #[error("no position held in that token")]
+    NotHolding,
+    #[error("already holding {0}")]
+    AlreadyHolding(Pubkey),
+    #[error("{0} is not a recently seen candidate")]
+    UnknownCandidate(Pubkey),
+    #[error("invalid amount: {0}")]
+    InvalidAmount(String),
+    #[error("sell failed ({reason}): {error:#}")]
+    SellFailed { reason: SellFailureReason, error: anyhow::Error },
+    #[error(transparent)]
+    Other(anyhow::Error),
+}
+
+impl From<anyhow::Error> for EngineError {
+    fn from(error: anyhow::Error) -> Self {
+        error.downcast::<EngineError>().unwrap_or_else(EngineError::Other)
+    }
+}
+
+/// Why a sell that was built or sent failed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SellFailureReason {
+    /// The price moved past the slippage limit
+    Slippage,
+    /// No pool or curve liquidity to sell into (e.g. migrated or drained)
+    NoLiquidity,
+    /// The token account is frozen by the mint's freeze authority
+    AccountFrozen,
+    /// Not enough SOL for fees
+    InsufficientFunds,
+    /// Sent, but never confirmed even after escalating
+    Unconfirmed,
+    /// The transaction could not be built or quoted
+    BuildFailed,
+    /// Rejected or unreachable RPC endpoints
+    Rpc(RpcErrorType),
+    Other,
+}
+
+/// Error text fragments (lowercase) of sell-specific failures, checked in order.
+const SELL_FAILURE_MARKERS: [(&str, SellFailureReason); 9] = [
+    ("slippage", SellFailureReason::Slippage),
+    ("toolittlesolreceived", SellFailureReason::Slippage),
+    ("too little sol received", SellFailureReason::Slippage),
+    ("no liquidity", SellFailureReason::NoLiquidity),
+    ("insufficient liquidity", SellFailureReason::NoLiquidity),
+    ("bondingcurvecomplete", SellFailureReason::NoLiquidity),
+    ("insufficient funds", SellFailureReason::InsufficientFunds),
+    ("insufficient lamports", SellFailureReason::InsufficientFunds),
+    ("not confirmed after", SellFailureReason::Unconfirmed),
+];
+
+impl SellFailureReason {
+    /// Classify a failed sell: frozen accounts and typed RPC/builder errors first,
+    /// then known sell failure texts; anything else sent-related counts as RPC.
+    pub fn classify(error: &anyhow::Error) -> Self {
+        if is_frozen_account_error(error) {
+            return SellFailureReason::AccountFrozen;
+        }
+        let text = format!("{:#}", error).to_lowercase();
+        if let Some((_, reason)) = SELL_FAILURE_MARKERS.iter().find(|(marker, _)| text.contains(marker)) {
+            return reason.clone();
+        }
+        for cause in error.chain() {
+            if cause.downcast_ref::<crate::tx_builder::TransactionBuilderError>().is_some() {
+                return SellFailureReason::BuildFailed;
+            }
+            if let Some(e) = cause.downcast_ref::<solana_client::client_error::ClientError>() {
+                return SellFailureReason::Rpc(classify_rpc_error(e));
+            }
+        }
+        if is_unsupported_version_error(error) {
+            return SellFailureReason::Rpc(RpcErrorType::UnsupportedVersion);
+        }
+        if text.contains("transaction build failed") {
+            return SellFailureReason::BuildFailed;
+        }
+        if text.contains("all sends failed") || text.contains("no endpoints") || text.contains("timeout") {
+            return SellFailureReason::Rpc(RpcErrorType::Other(error.to_string()));
+        }
+        SellFailureReason::Other
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SellFailureReason::Slippage => "slippage",
+            SellFailureReason::NoLiquidity => "no_liquidity",
+            SellFailureReason::AccountFrozen => "account_frozen",
+            SellFailureReason::InsufficientFunds => "insufficient_funds",
+            SellFailureReason::Unconfirmed => "unconfirmed",
+            SellFailureReason::BuildFailed => "build_failed",
+            SellFailureReason::Rpc(_) => "rpc",
+            SellFailureReason::Other => "other",
+        }
+    }
+
+    /// Per-reason counter, next to the `sell_failures_total` aggregate.
+    pub fn counter_name(&self) -> &'static str {
+        match self {
+            SellFailureReason::Slippage => "sell_failures_slippage_total",
+            SellFailureReason::NoLiquidity => "sell_failures_no_liquidity_total",
+            SellFailureReason::AccountFrozen => "sell_failures_account_frozen_total",
+            SellFailureReason::InsufficientFunds => "sell_failures_insufficient_funds_total",
+            SellFailureReason::Unconfirmed => "sell_failures_unconfirmed_total",
+            SellFailureReason::BuildFailed => "sell_failures_build_failed_total",
+            SellFailureReason::Rpc(_) => "sell_failures_rpc_total",
+            SellFailureReason::Other => "sell_failures_other_total",
+        }
+    }
+}
+
+impl std::fmt::Display for SellFailureReason {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Exit threshold a held position's price crossed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExitTrigger {
+    TakeProfit,
+    StopLoss,
+    /// This many `sell_ladder` rungs, sold together
+    Ladder { rungs: usize },
+}
+
+impl ExitTrigger {
+    /// Threshold `price` crossed relative to `buy_price`, with both thresholds in
+    /// percent (0 disables one).
+    pub fn check(buy_price: f64, price: f64, take_profit_pct: f64, stop_loss_pct: f64) -> Option<Self> {
+        if buy_price <= 0.0 || !price.is_finite() {
+            return None;
+        }
+        // Compare prices against the thresholds, not a percent change, so a price
+        // exactly at a threshold crosses it despite rounding
+        if take_profit_pct > 0.0 && price >= buy_price * (1.0 + take_profit_pct / 100.0) {
+            Some(ExitTrigger::TakeProfit)
+        } else if stop_loss_pct > 0.0 && price <= buy_price * (1.0 - stop_loss_pct / 100.0) {
+            Some(ExitTrigger::StopLoss)
+        } else {
+            None
+        }
+    }
+
+    pub fn counter_name(&self) -> &'static str {
+        match self {
+            ExitTrigger::TakeProfit => "exit_take_profit_total",
+            ExitTrigger::StopLoss => "exit_stop_loss_total",
+            ExitTrigger::Ladder { .. } => "exit_ladder_total",
+        }
+    }
+}
+
+impl std::fmt::Display for ExitTrigger {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ExitTrigger::TakeProfit => f.write_str("take-profit"),
+            ExitTrigger::StopLoss => f.write_str("stop-loss"),
+            ExitTrigger::Ladder { rungs } => write!(f, "sell ladder ({} rungs)", rungs),
+        }
+    }
+}
+
+/// Result of comparing a landed buy's received tokens with its quoted minimum output.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FillCheck {
+    Filled { received: u64, min_out: u64 },
+    Underfilled { received: u64, min_out: u64 },
 }
 
 impl BuyEngine {
@@ -110,20 +542,111 @@ impl BuyEngine {
             rpc,
             nonce_manager,
             candidate_rx,
+            scored_rx: None,
             app_state,
-            config,
-            tx_builder,
+            tx_builder: tx_builder.map(Arc::new),
+            candidate_filter: Box::new(candidate_filter::from_config(&config)),
             backoff_state: BackoffState::new(),
             pending_buy: Arc::new(AtomicBool::new(false)),
+            funnel: funnel(),
+            audit: Arc::new(AuditLog::disabled()),
+            inflight: InflightBuyLimiter::new(config.max_inflight_buys),
+            buy_window_open: None,
+            rpc_health: None,
+            rpc_paused: false,
+            sol_balance: None,
+            token_balances: None,
+            wsol_unwrapper: None,
+            price_source: None,
+            mint_rpc: None,
+            last_exit_check: None,
+            ladder: std::sync::Mutex::new(LadderProgress::default()),
+            paper_sequence: AtomicU64::new(0),
+            commands: None,
+            recent_candidates: RecentCandidates::default(),
+            fill_checks: std::sync::Mutex::new(Vec::new()),
+            config,
         }
     }
 
+    /// Take operator commands (GUI buys and sells) from `commands`.
+    pub fn with_commands(mut self, commands: mpsc::Receiver<EngineCommand>) -> Self {
+        self.commands = Some(commands);
+        self
+    }
+
+    /// Take candidates scored by the quantum selector from `scored` instead of
+    /// `candidate_rx`; only those scoring at least `min_buy_score` are bought.
+    pub fn with_scored_candidates(mut self, scored: mpsc::Receiver<ScoredCandidate>) -> Self {
+        self.scored_rx = Some(scored);
+        self
+    }
+
+    /// Pick buy targets with `filter` instead of the filters the config enables.
+    pub fn with_candidate_filter(mut self, filter: Box<dyn CandidateFilter>) -> Self {
+        self.candidate_filter = filter;
+        self
+    }
+
+    /// Record commands, mode transitions and broadcasts to `audit`.
+    pub fn with_audit_log(mut self, audit: Arc<AuditLog>) -> Self {
+        self.audit = audit;
+        self
+    }
+
+    /// Share an in-flight buy limit with other engines.
+    pub fn with_inflight_limiter(mut self, limiter: InflightBuyLimiter) -> Self {
+        self.inflight = limiter;
+        self
+    }
+
+    /// Pause auto-buying while `health` reports too few healthy endpoints.
+    pub fn with_rpc_health(mut self, health: Arc<RpcHealth>) -> Self {
+        self.rpc_health = Some(health);
+        self
+    }
+
+    /// Size buys from the polled wallet `balance` so it stays at or above
+    /// `min_sol_reserve_lamports`.
+    pub fn with_sol_balance(mut self, balance: Arc<SolBalance>) -> Self {
+        self.sol_balance = Some(balance);
+        self
+    }
+
+    /// Read token balances from `balances` to verify buys were filled.
+    pub fn with_token_balances(mut self, balances: Arc<dyn TokenBalances>) -> Self {
+        self.token_balances = Some(balances);
+        self
+    }
+
+    /// Unwrap WSOL after a full sell through `unwrapper` instead of the transaction builder.
+    pub fn with_wsol_unwrapper(mut self, unwrapper: Arc<dyn WsolUnwrapper>) -> Self {
+        self.wsol_unwrapper = Some(unwrapper);
+        self
+    }
+
+    /// Price held positions through `source` for take-profit/stop-loss instead of
+    /// the transaction builder.
+    pub fn with_price_source(mut self, source: Arc<dyn PriceSource>) -> Self {
+        self.price_source = Some(source);
+        self
+    }
+
+    /// Read candidate mints through `rpc` for `sellability_check` and
+    /// `require_renounced_authorities`; without it neither check runs.
+    pub fn with_mint_rpc(mut self, rpc: Arc<RpcClient>) -> Self {
+        self.mint_rpc = Some(rpc);
+        self
+    }
+
     pub async fn run(&mut self) {
         info!("BuyEngine started");
         loop {
+            self.poll_exit().await;
+            self.poll_fill_checks().await;
             let sniffing = {
                 let st = self.app_state.lock().await;
-                st.is_sniffing()
+                st.accepts_buys(self.config.max_concurrent_positions)
             };
 
             if sniffing {
@@ -136,82 +659,96 @@ impl BuyEngine {
                     continue;
                 }
 
-                match timeout(Duration::from_millis(1000), self.candidate_rx.recv()).await {
-                    Ok(Some(candidate)) => {
-                        // Validate candidate for security issues
-                        let validation = validator().validate_candidate(&candidate);
-                        if !validation.is_valid() {
-                            metrics().increment_counter("buy_attempts_security_rejected");
-                            warn!(mint=%candidate.mint, issues=?validation.issues, "Candidate rejected due to security validation");
-                            continue;
-                        }
-
-                        // Check rate limiting to prevent spam
-                        if !validator().check_mint_rate_limit(&candidate.mint, 60, 5) {
-                            metrics().increment_counter("buy_attempts_rate_limited");
-                            debug!(mint=%candidate.mint, "Candidate rate limited");
-                            continue;
-                        }
-
-                        if !self.is_candidate_interesting(&candidate) {
-                            metrics().increment_counter("buy_attempts_filtered");
-                            debug!(mint=%candidate.mint, program=%candidate.program, "Candidate filtered out");
-                            continue;
+                match timeout(Duration::from_millis(1000), self.next_input()).await {
+                    Ok(Some(EngineInput::Command(command))) => self.handle_command(command).await,
+                    Ok(Some(EngineInput::Candidate(mut candidate, score))) => {
+                        self.funnel.record(FunnelStage::Seen);
+                        if self.config.candidate_deadline_ms > 0 {
+                            candidate.stamp_deadline(self.config.candidate_deadline_ms);
                         }
-                        
 
                         // Create pipeline context for correlation tracking
                         let ctx = PipelineContext::new("buy_engine");
+                        let mut trace = DecisionTrace::new(&candidate);
+                        let Some(_inflight) = self.admit_candidate(&candidate, score, &mut trace).await else {
+                            trace.emit(&ctx.logger);
+                            // Passed on, but an operator may still buy it on command: have that buy only sign
+                            if let Some(score) = score {
+                                self.prewarm(&candidate, score);
+                            }
+                            continue;
+                        };
                         ctx.logger.log_candidate_processed(&candidate.mint.to_string(), &candidate.program, true);
                         
                         info!(mint=%candidate.mint, program=%candidate.program, correlation_id=ctx.correlation_id, "Attempting BUY for candidate");
                         metrics().increment_counter("buy_attempts_total");
+                        self.funnel.record(FunnelStage::Attempted);
+                        self.audit.record(ctx.correlation_id, AuditEvent::Command {
+                            command: "buy".to_string(),
+                            mint: candidate.mint.to_string(),
+                            percent: None,
+                        });
 
                         let buy_timer = Timer::new("buy_latency_seconds");
-                        match self.try_buy(candidate.clone(), ctx.clone()).await {
-                            Ok(sig) => {
+                        let started = Instant::now();
+                        self.app_state.lock().await.start_operation(ctx.correlation_id, "buy", candidate.mint);
+                        let result = self.buy_and_land(&candidate, &ctx, None).await;
+                        self.app_state.lock().await.finish_operation(ctx.correlation_id);
+                        match result {
+                            Ok(buy) => {
+                                let outcome = &buy.outcome;
+                                let sig = outcome.signature;
                                 buy_timer.finish();
-                                let latency_ms = std::time::Instant::now().elapsed().as_millis() as u64;
-                                
+                                let latency_ms = outcome.latency_ms;
+                                if outcome.was_duplicate {
+                                    metrics().increment_counter("buy_duplicate_sends_total");
+                                }
+
                                 metrics().increment_counter("buy_success_total");
+                                self.funnel.record(FunnelStage::Succeeded);
                                 ctx.logger.log_buy_success(&candidate.mint.to_string(), &sig.to_string(), latency_ms);
                                 
                                 // Update scoreboard
                                 endpoint_server().update_scoreboard(&candidate.mint.to_string(), &candidate.program, true, latency_ms).await;
                                 
-                                info!(mint=%candidate.mint, sig=%sig, correlation_id=ctx.correlation_id, "BUY success, entering PassiveToken mode");
-
-
-                                info!(mint=%candidate.mint, sig=%sig, correlation_id=ctx.correlation_id, "BUY success, entering PassiveToken mode");
+                                info!(mint=%candidate.mint, sig=%sig, endpoint=%outcome.endpoint, correlation_id=ctx.correlation_id, "BUY success, entering PassiveToken mode");
+                                self.audit.record(ctx.correlation_id, AuditEvent::Broadcast {
+                                    kind: "buy".to_string(),
+                                    mint: candidate.mint.to_string(),
+                                    signature: sig.to_string(),
+                                    endpoint: outcome.endpoint.clone(),
+                                });
 
-                                let exec_price = self.get_execution_price_mock(&candidate).await;
-                                self.backoff_state.record_success().await;
+                                let exec_price = self.execution_price(&candidate).await;
+                                self.backoff_state.record_buy(&buy).await;
 
-                                {
-                                    let mut st = self.app_state.lock().await;
-                                    st.mode = Mode::PassiveToken(candidate.mint);
-                                    st.active_token = Some(candidate.clone());
-                                    st.last_buy_price = Some(exec_price);
-                                    st.holdings_percent = 1.0;
-                                }
+                                self.enter_position(&candidate, exec_price, &ctx, None, &buy).await;
 
-                                info!(mint=%candidate.mint, price=%exec_price, "Recorded buy price and entered PassiveToken");
+                                info!(mint=%candidate.mint, price=?exec_price, "Recorded buy price and entered PassiveToken");
+                                trace.finish("buy", format!("bought via {}: {}", outcome.endpoint, sig));
+                                self.spawn_fill_check(candidate.mint, &buy, &ctx);
                             }
                             Err(e) => {
 
                                 buy_timer.finish();
-                                let latency_ms = std::time::Instant::now().elapsed().as_millis() as u64;
+                                let latency_ms = started.elapsed().as_millis() as u64;
                                 
                                 metrics().increment_counter("buy_failure_total");
+                                self.backoff_state.record_failure().await;
                                 ctx.logger.log_buy_failure(&candidate.mint.to_string(), &e.to_string(), latency_ms);
                                 
                                 // Update scoreboard with failure
                                 endpoint_server().update_scoreboard(&candidate.mint.to_string(), &candidate.program, false, latency_ms).await;
                                 
                                 warn!(error=%e, correlation_id=ctx.correlation_id, "BUY attempt failed; staying in Sniffing");
-
+                                self.app_state.lock().await.notify(
+                                    NotificationLevel::Error,
+                                    format!("Buy failed: {}", user_message(&e)),
+                                );
+                                trace.finish("buy_failed", user_message(&e));
                             }
                         }
+                        trace.emit(&ctx.logger);
                     }
                     Ok(None) => {
                         warn!("Candidate channel closed; BuyEngine exiting");
@@ -222,9 +759,10 @@ impl BuyEngine {
                     }
                 }
             } else {
-                match timeout(Duration::from_millis(500), self.candidate_rx.recv()).await {
-                    Ok(Some(c)) => {
-                        debug!(mint=%c.mint, "Passive mode: ignoring candidate");
+                match timeout(Duration::from_millis(500), self.next_input()).await {
+                    Ok(Some(EngineInput::Command(command))) => self.handle_command(command).await,
+                    Ok(Some(EngineInput::Candidate(c, _))) => {
+                        debug!(mint=%c.mint, "Not accepting buys: ignoring candidate");
                     }
                     Ok(None) => {
                         warn!("Candidate channel closed; BuyEngine exiting");
@@ -239,309 +777,4023 @@ impl BuyEngine {
         info!("BuyEngine stopped");
     }
 
-    pub async fn sell(&self, percent: f64) -> Result<()> {
-        let ctx = PipelineContext::new("buy_engine_sell");
+    /// Next candidate with its quantum selector score (scored candidates when the
+    /// engine was given them, else unscored ones from `candidate_rx`), or the next
+    /// operator command. `None` once the candidate channel closed.
+    async fn next_input(&mut self) -> Option<EngineInput> {
+        loop {
+            let Self { scored_rx, candidate_rx, commands, .. } = self;
+            let candidate = async {
+                match scored_rx {
+                    Some(scored_rx) => scored_rx.recv().await.map(|scored| (scored.candidate, Some(scored.predicted_score))),
+                    None => candidate_rx.recv().await.map(|candidate| (candidate, None)),
+                }
+            };
+            let command = async {
+                match commands {
+                    Some(commands) => commands.recv().await,
+                    None => std::future::pending().await,
+                }
+            };
+            let next = tokio::select! {
+                candidate = candidate => Ok(candidate),
+                command = command => Err(command),
+            };
+            match next {
+                Ok(Some((candidate, score))) => {
+                    self.recent_candidates.remember(&candidate);
+                    return Some(EngineInput::Candidate(candidate, score));
+                }
+                Ok(None) => return None,
+                Err(Some(command)) => return Some(EngineInput::Command(command)),
+                // Nobody sends commands any more; keep serving candidates
+                Err(None) => self.commands = None,
+            }
+        }
+    }
 
-        // Validate holdings percentage for overflow protection
-        let pct = match validator().validate_holdings_percent(percent.clamp(0.0, 1.0)) {
-            Ok(validated_pct) => validated_pct,
-            Err(e) => {
-                ctx.logger.error("Invalid sell percentage", serde_json::json!({"error": e, "percent": percent}));
-                return Err(anyhow!("Invalid sell percentage: {}", e));
+    async fn handle_command(&mut self, command: EngineCommand) {
+        match command {
+            EngineCommand::Buy { mint, amount_lamports, reply } => {
+                let result = match self.recent_candidates.get(&mint) {
+                    Some(candidate) => self.buy_now_with_amount(&candidate, amount_lamports).await,
+                    None => Err(EngineError::UnknownCandidate(mint)),
+                };
+                let _ = reply.send(result);
             }
-        };
+            EngineCommand::Sell { percent, reply } => {
+                let mint = self.app_state.lock().await.active_token.as_ref().map(|t| t.mint);
+                let result = match mint {
+                    Some(mint) => self.sell(mint, percent).await,
+                    None => Err(EngineError::NotHolding),
+                };
+                let _ = reply.send(result);
+            }
+            EngineCommand::Simulate { candidate, reply } => {
+                let _ = reply.send(self.simulate_candidate(&candidate).await);
+            }
+        }
+    }
 
-        // Check if there's a pending buy operation
-        if self.pending_buy.load(Ordering::Relaxed) {
-            warn!("Sell requested while buy is pending; rejecting to avoid race condition");
-            return Err(anyhow!("buy operation in progress"));
+    /// `check_exit` every open position at most once per `exit_check_interval_ms`;
+    /// a failed exit sell is reported and retried on a later check.
+    async fn poll_exit(&mut self) {
+        let interval = Duration::from_millis(self.config.exit_check_interval_ms);
+        if self.last_exit_check.is_some_and(|at| at.elapsed() < interval) {
+            return;
+        }
+        self.last_exit_check = Some(tokio::time::Instant::now());
+        let held: Vec<Pubkey> = self.app_state.lock().await.open_positions().iter().map(|p| p.token.mint).collect();
+        for mint in held {
+            if let Err(e) = self.check_exit(mint).await {
+                warn!(mint=%mint, error=%e, "Automatic exit sell failed");
+                self.app_state.lock().await.notify(
+                    NotificationLevel::Error,
+                    format!("Automatic exit of {} failed: {}", mint, engine_error_message(&e)),
+                );
+            }
         }
+    }
 
-        let (mode, candidate_opt, current_pct) = {
+    /// Sell the rungs of `sell_ladder` the current price reached, or the whole
+    /// position in `mint` if it crossed `take_profit_pct` or `stop_loss_pct` from
+    /// the buy price; returns what fired. The price is recorded for unrealized PnL
+    /// either way. Does nothing while a buy is pending, when `mint` is not held or
+    /// its buy is unconfirmed, or without a price.
+    pub async fn check_exit(&self, mint: Pubkey) -> Result<Option<ExitTrigger>, EngineError> {
+        if self.pending_buy.load(Ordering::Relaxed) {
+            return Ok(None);
+        }
+        let (buy_price, holdings) = {
             let st = self.app_state.lock().await;
-            (st.mode.clone(), st.active_token.clone(), st.holdings_percent)
-        };
-
-        let mint = match mode {
-            Mode::PassiveToken(m) => m,
-            Mode::Sniffing => {
-                ctx.logger.warn("Sell requested in Sniffing mode; ignoring", serde_json::json!({"action": "sell_rejected"}));
-                warn!(correlation_id=ctx.correlation_id, "Sell requested in Sniffing mode; ignoring");
-                return Err(anyhow!("not in PassiveToken mode"));
-            }
-            Mode::QuantumManual => {
-                ctx.logger.warn("Sell requested in QuantumManual mode; ignoring", serde_json::json!({"action": "sell_rejected"}));
-                warn!(correlation_id=ctx.correlation_id, "Sell requested in QuantumManual mode; ignoring");
-                return Err(anyhow!("not in PassiveToken mode"));
+            match st.position(&mint) {
+                Some(Position { buy_price: Some(price), holdings_percent, unconfirmed_signature: None, .. })
+                    if holdings_percent > f64::EPSILON =>
+                {
+                    (price, holdings_percent)
+                }
+                _ => return Ok(None),
             }
         };
-
-        let _candidate = candidate_opt.ok_or_else(|| anyhow!("no active token in AppState"))?;
-        
-        // Validate the new holdings calculation
-        let new_holdings = match validator().validate_holdings_percent((current_pct * (1.0 - pct)).max(0.0)) {
-            Ok(validated_holdings) => validated_holdings,
+        let Some(source) = self.pricing() else {
+            return Ok(None);
+        };
+        let price = match source.current_price(&mint).await {
+            Ok(price) => price,
             Err(e) => {
-                ctx.logger.error("Holdings calculation overflow", serde_json::json!({"error": e, "current": current_pct, "sell": pct}));
-                return Err(anyhow!("Holdings calculation error: {}", e));
+                debug!(mint=%mint, error=%e, "No price for exit check");
+                return Ok(None);
+            }
+        };
+        self.app_state.lock().await.mark_price(mint, price);
+        if self.config.take_profit_pct <= 0.0 && self.config.stop_loss_pct <= 0.0 && self.config.sell_ladder.is_empty() {
+            return Ok(None);
+        }
+        if buy_price > 0.0 && price.is_finite() {
+            let due = self.ladder.lock().unwrap().due(mint, &self.config.sell_ladder, price / buy_price);
+            if !due.is_empty() {
+                return self.sell_ladder_rungs(mint, &due, holdings).await.map(Some);
             }
+        }
+        let Some(trigger) =
+            ExitTrigger::check(buy_price, price, self.config.take_profit_pct, self.config.stop_loss_pct)
+        else {
+            return Ok(None);
         };
 
-        ctx.logger.log_sell_operation(&mint.to_string(), pct, new_holdings);
-        info!(mint=%mint, sell_percent=pct, correlation_id=ctx.correlation_id, "Composing SELL transaction");
+        info!(mint=%mint, buy_price, price, %trigger, "Exit threshold crossed; selling the position");
+        metrics().increment_counter(trigger.counter_name());
+        self.app_state.lock().await.notify(
+            NotificationLevel::Info,
+            format!("{} reached for {}; selling", trigger, mint),
+        );
+        self.sell(mint, 1.0).await?;
+        Ok(Some(trigger))
+    }
 
-        let sell_tx = self.create_sell_transaction(&mint, pct).await?;
+    /// Where held positions are priced: `price_source`, else the transaction builder.
+    fn pricing(&self) -> Option<&dyn PriceSource> {
+        match (&self.price_source, &self.tx_builder) {
+            (Some(source), _) => Some(source.as_ref()),
+            (None, Some(builder)) => Some(builder.as_ref()),
+            (None, None) => None,
+        }
+    }
 
-        match self.rpc.send_on_many_rpc(vec![sell_tx], None).await {
-            Ok(sig) => {
-                // Check for duplicate signatures
-                let sig_str = sig.to_string();
-                if !validator().check_duplicate_signature(&sig_str) {
-                    warn!(mint=%mint, sig=%sig, correlation_id=ctx.correlation_id, "Duplicate signature detected for SELL");
-                    metrics().increment_counter("duplicate_signatures_detected");
-                }
-                
-                info!(mint=%mint, sig=%sig, correlation_id=ctx.correlation_id, "SELL broadcasted");
-                let mut st = self.app_state.lock().await;
-                st.holdings_percent = new_holdings;
-                if st.holdings_percent <= f64::EPSILON {
-                    info!(mint=%mint, correlation_id=ctx.correlation_id, "Sold 100%; returning to Sniffing mode");
-                    st.mode = Mode::Sniffing;
-                    st.active_token = None;
-                    st.last_buy_price = None;
-                }
-                Ok(())
-            }
-            Err(e) => {
-                error!(mint=%mint, error=%e, correlation_id=ctx.correlation_id, "SELL failed to broadcast");
-                Err(e)
+    /// Sell the `sell_ladder` rungs `due` together, as fractions of the original
+    /// position, out of the `holdings` still held. Rungs count as fired once sold.
+    async fn sell_ladder_rungs(&self, mint: Pubkey, due: &[usize], holdings: f64) -> Result<ExitTrigger, EngineError> {
+        let fraction: f64 = due.iter().map(|&i| self.config.sell_ladder[i].1).sum();
+        let pct = (fraction / holdings).min(1.0);
+        info!(mint=%mint, rungs=?due, fraction, holdings, "Sell ladder rungs reached; scaling out");
+        self.sell(mint, pct).await?;
+
+        self.ladder.lock().unwrap().mark_fired(&mint, due);
+        let trigger = ExitTrigger::Ladder { rungs: due.len() };
+        metrics().increment_counter(trigger.counter_name());
+        Ok(trigger)
+    }
+
+    /// Record a sent buy of `candidate` as a new position; it becomes the focused one.
+    /// `amount_lamports` is the buy's requested amount, `None` for the configured one.
+    /// An unconfirmed buy is recorded with its signature, so exits stay off until
+    /// reconciliation sees it land.
+    async fn enter_position(
+        &self,
+        candidate: &PremintCandidate,
+        exec_price: Option<f64>,
+        ctx: &PipelineContext,
+        amount_lamports: Option<u64>,
+        buy: &SentBuy,
+    ) {
+        let mut st = self.app_state.lock().await;
+        self.audit.record(ctx.correlation_id, AuditEvent::ModeTransition {
+            from: mode_label(&st.mode),
+            to: mode_label(&Mode::PassiveToken(candidate.mint)),
+        });
+        // The amount the buy was built with; the reserve only ever downsizes it
+        let cost_lamports = self
+            .reserved_buy_config(false, amount_lamports)
+            .map(|config| config.buy_amount_lamports)
+            .or(amount_lamports)
+            .unwrap_or_else(|| buy_transaction_config(&self.config, false).buy_amount_lamports);
+        let unconfirmed_signature = buy.unconfirmed.then_some(buy.outcome.signature);
+        st.open_position(Position {
+            cost_lamports,
+            unconfirmed_signature,
+            balance_before_buy: buy.balance_before,
+            ..Position::new(candidate.clone(), exec_price, 1.0)
+        });
+        st.record_buy(cost_lamports);
+        match exec_price {
+            Some(price) => st.mark_price(candidate.mint, price),
+            None => {
+                // Exits compare against the buy price; without a real one they stay unarmed
+                metrics().increment_counter("buy_price_unknown_total");
+                warn!(mint=%candidate.mint, "No fill price for buy; take-profit and stop-loss not armed");
+                st.notify(NotificationLevel::Warn, format!("No fill price for {}; exits not armed", candidate.mint));
             }
         }
+        if buy.unconfirmed {
+            metrics().increment_counter("buy_unconfirmed_positions_total");
+            st.notify(
+                NotificationLevel::Warn,
+                format!("Buy of {} not confirmed yet; exits held until it lands", candidate.mint),
+            );
+        } else {
+            st.notify(NotificationLevel::Info, format!("Bought {}", candidate.mint));
+        }
+        self.ladder.lock().unwrap().reset(&candidate.mint);
     }
 
-    /// Protected buy operation with atomic guards and proper lease management
-    #[allow(dead_code)]
-    async fn try_buy_with_guards(&self, candidate: PremintCandidate, _correlation_id: CorrelationId) -> Result<Signature> {
-        // Set pending flag atomically
-        if self.pending_buy.compare_exchange(false, true, Ordering::Relaxed, Ordering::Relaxed).is_err() {
-            return Err(anyhow!("buy operation already in progress"));
+    /// Pre-build an unsigned buy of `candidate` for a quantum suggestion scoring
+    /// `score`, so a later buy only has to sign it. Called for scored candidates the
+    /// engine passed on while sniffing, which an operator may still buy on command.
+    /// The build runs in the background so the engine loop never waits on it; the
+    /// task yields whether one was built. `None` when nothing is pre-built.
+    pub fn prewarm(&self, candidate: &PremintCandidate, score: u8) -> Option<JoinHandle<bool>> {
+        if !self.config.warm_pool_enabled || score < self.config.warm_pool_min_score {
+            return None;
         }
+        let builder = self.tx_builder.clone()?;
+        let config = self.reserved_buy_config(false, None)?;
+        let candidate = candidate.clone();
+        Some(tokio::spawn(async move {
+            match builder.prebuild_buy(&candidate, &config).await {
+                Ok(()) => true,
+                Err(e) => {
+                    debug!(mint=%candidate.mint, error=%e, "Pre-building buy failed; it will be built at trigger time");
+                    false
+                }
+            }
+        }))
+    }
+
+    /// Buy `candidate` on operator request (GUI/HTTP command). Skips the auto-buy
+    /// filter, but not the halts, rate limit or SOL reserve.
+    pub async fn buy_now(&self, candidate: &PremintCandidate) -> Result<SendOutcome, EngineError> {
+        self.manual_buy(candidate, None).await
+    }
+
+    /// `buy_now` spending `amount_lamports` instead of the configured buy amount; the
+    /// SOL reserve can still downsize it.
+    pub async fn buy_now_with_amount(
+        &self,
+        candidate: &PremintCandidate,
+        amount_lamports: u64,
+    ) -> Result<SendOutcome, EngineError> {
+        self.manual_buy(candidate, Some(amount_lamports)).await
+    }
 
-        // Ensure we clear the pending flag on exit
+    async fn manual_buy(&self, candidate: &PremintCandidate, amount_lamports: Option<u64>) -> Result<SendOutcome, EngineError> {
+        if self.pending_buy.compare_exchange(false, true, Ordering::Relaxed, Ordering::Relaxed).is_err() {
+            return Err(EngineError::BuyInProgress);
+        }
         let _guard = scopeguard::guard((), |_| {
             self.pending_buy.store(false, Ordering::Relaxed);
         });
 
-        // Call the actual buy logic
-        self.try_buy(candidate, PipelineContext::new("buy_engine_guard")).await
-    }
-
-    async fn try_buy(&self, candidate: PremintCandidate, ctx: PipelineContext) -> Result<Signature> {
-        let mut acquired_indices: Vec<usize> = Vec::new();
+        {
+            let st = self.app_state.lock().await;
+            if st.position(&candidate.mint).is_some() {
+                return Err(EngineError::AlreadyHolding(candidate.mint));
+            }
+            if !st.accepts_buys(self.config.max_concurrent_positions) {
+                return Err(EngineError::NotSniffing);
+            }
+        }
+        if self.rpc_paused {
+            return Err(EngineError::Halted("too few healthy RPC endpoints".to_string()));
+        }
+        if !buying_allowed(&self.config.buy_schedule, Utc::now()) {
+            return Err(EngineError::Halted("outside the buy schedule".to_string()));
+        }
+        if !validator().check_mint_rate_limit(&candidate.mint, 60, 5) {
+            metrics().increment_counter("buy_attempts_rate_limited");
+            return Err(EngineError::RateLimited(format!("{} seen too often in the last minute", candidate.mint)));
+        }
+        if self.reserved_buy_config(false, amount_lamports).is_none() {
+            metrics().increment_counter("buy_reserve_skipped_total");
+            return Err(EngineError::InsufficientFunds("buy would breach the minimum SOL reserve".to_string()));
+        }
 
-        let mut txs: Vec<VersionedTransaction> = Vec::new();
+        let ctx = PipelineContext::new("buy_engine_manual");
+        info!(mint=%candidate.mint, ?amount_lamports, correlation_id=ctx.correlation_id, "Operator-triggered BUY");
+        self.audit.record(ctx.correlation_id, AuditEvent::Command {
+            command: "buy".to_string(),
+            mint: candidate.mint.to_string(),
+            percent: None,
+        });
+        self.app_state.lock().await.start_operation(ctx.correlation_id, "buy", candidate.mint);
+        let result = self.buy_and_land(candidate, &ctx, amount_lamports).await;
+        self.app_state.lock().await.finish_operation(ctx.correlation_id);
+        let buy = result?;
+        let outcome = buy.outcome.clone();
 
-        // Get recent blockhash once for all transactions
-        let recent_blockhash = self.get_recent_blockhash().await;
+        metrics().increment_counter("buy_success_total");
+        self.audit.record(ctx.correlation_id, AuditEvent::Broadcast {
+            kind: "buy".to_string(),
+            mint: candidate.mint.to_string(),
+            signature: outcome.signature.to_string(),
+            endpoint: outcome.endpoint.clone(),
+        });
+        let exec_price = self.execution_price(candidate).await;
+        self.enter_position(candidate, exec_price, &ctx, amount_lamports, &buy).await;
+        self.spawn_fill_check(candidate.mint, &buy, &ctx);
+        Ok(outcome)
+    }
 
-        for _ in 0..self.config.nonce_count {
-            match self.nonce_manager.acquire_nonce().await {
+    /// Sell `percent` of what is left of the position in `mint`.
+    pub async fn sell(&self, mint: Pubkey, percent: f64) -> Result<(), EngineError> {
+        self.sell_with(mint, SellAmount::Fraction(percent)).await
+    }
 
-                Ok((_nonce_pubkey, idx)) => {
-                    ctx.logger.log_nonce_operation("acquire", Some(idx), true);
-                    acquired_indices.push(idx);
+    /// Sell tokens of `mint` worth `lamports` at the current quote (e.g. take out the initial SOL).
+    pub async fn sell_sol_value(&self, mint: Pubkey, lamports: u64) -> Result<(), EngineError> {
+        self.sell_with(mint, SellAmount::SolValue(lamports)).await
+    }
 
-                    let tx = self.create_buy_transaction(&candidate, recent_blockhash).await?;
-                    txs.push(tx);
-                }
-                Err(e) => {
+    async fn sell_with(&self, mint: Pubkey, amount: SellAmount) -> Result<(), EngineError> {
+        let ctx = PipelineContext::new("buy_engine_sell");
 
-                    ctx.logger.log_nonce_operation("acquire_failed", None, false);
-                    warn!(error=%e, correlation_id=ctx.correlation_id, "Failed to acquire nonce; proceeding with fewer");
+        // Check if there's a pending buy operation
+        if self.pending_buy.load(Ordering::Relaxed) {
+            warn!("Sell requested while buy is pending; rejecting to avoid race condition");
+            return Err(EngineError::BuyInProgress);
+        }
 
-                    break;
+        let Some(position) = self.app_state.lock().await.position(&mint) else {
+            ctx.logger.warn("Sell requested for a token not held; ignoring", serde_json::json!({"action": "sell_rejected", "mint": mint.to_string()}));
+            warn!(mint=%mint, correlation_id=ctx.correlation_id, "Sell requested for a token not held; ignoring");
+            return Err(EngineError::NotHolding);
+        };
+        let current_pct = position.holdings_percent;
+        let candidate = position.token;
+
+        let percent = match amount {
+            SellAmount::Fraction(percent) => percent,
+            SellAmount::SolValue(lamports) => self.sol_value_fraction(&mint, lamports).await?,
+        };
+
+        let (pct, new_holdings, sells_dust) = self.sell_fraction(&mint, current_pct, percent, &ctx)?;
+
+        ctx.logger.log_sell_operation(&mint.to_string(), pct, new_holdings);
+        info!(mint=%mint, sell_percent=pct, correlation_id=ctx.correlation_id, "Composing SELL transaction");
+        self.audit.record(ctx.correlation_id, AuditEvent::Command {
+            command: "sell".to_string(),
+            mint: mint.to_string(),
+            percent: Some(pct),
+        });
+
+        let amount = match amount {
+            SellAmount::Fraction(_) => SellAmount::Fraction(pct),
+            _ if sells_dust => SellAmount::Fraction(1.0),
+            sol_value => sol_value,
+        };
+        let plan = SellPlan { amount, from_holdings: current_pct, target_holdings: new_holdings };
+        let sell_price = self.sell_price(&mint).await.or(position.buy_price);
+        self.app_state.lock().await.start_operation(ctx.correlation_id, "sell", mint);
+        let result = self.broadcast_sell(&mint, &candidate.program, plan, &ctx).await;
+        self.app_state.lock().await.finish_operation(ctx.correlation_id);
+        match result {
+            Ok(outcome) => {
+                let sig = outcome.signature;
+                // Same signature again within the window = same tx; holdings were already reduced
+                let window = Duration::from_millis(self.config.duplicate_sell_window_ms);
+                if !validator().check_duplicate_signature_within(&sig.to_string(), window) {
+                    warn!(mint=%mint, sig=%sig, correlation_id=ctx.correlation_id, "Duplicate signature detected for SELL; holdings unchanged");
+                    metrics().increment_counter("duplicate_signatures_detected");
+                    return Ok(());
                 }
+
+                self.book_sell(&mint, current_pct, new_holdings, sell_price, &outcome, &ctx).await;
+                Ok(())
             }
+            Err(e) => match EngineError::from(e) {
+                EngineError::Other(e) => {
+                    let reason = SellFailureReason::classify(&e);
+                    metrics().increment_counter("sell_failures_total");
+                    metrics().increment_counter(reason.counter_name());
+                    error!(mint=%mint, error=%e, %reason, correlation_id=ctx.correlation_id, "SELL failed");
+                    Err(EngineError::SellFailed { reason, error: e })
+                }
+                typed => {
+                    error!(mint=%mint, error=%typed, correlation_id=ctx.correlation_id, "SELL failed");
+                    Err(typed)
+                }
+            },
         }
+    }
 
-        if txs.is_empty() {
+    /// Validated fraction of a position holding `current_pct` to sell for a requested
+    /// `percent`, the holdings it leaves, and whether dust was promoted to a full sell.
+    fn sell_fraction(
+        &self,
+        mint: &Pubkey,
+        current_pct: f64,
+        percent: f64,
+        ctx: &PipelineContext,
+    ) -> Result<(f64, f64, bool), EngineError> {
+        // Validate holdings percentage for overflow protection
+        let mut pct = match validator().validate_holdings_percent(percent.clamp(0.0, 1.0)) {
+            Ok(validated_pct) => validated_pct,
+            Err(e) => {
+                ctx.logger.error("Invalid sell percentage", serde_json::json!({"error": e, "percent": percent}));
+                return Err(EngineError::InvalidAmount(format!("sell percentage: {}", e)));
+            }
+        };
+
+        // Validate the new holdings calculation
+        let mut new_holdings = match validator().validate_holdings_percent((current_pct * (1.0 - pct)).max(0.0)) {
+            Ok(validated_holdings) => validated_holdings,
+            Err(e) => {
+                ctx.logger.error("Holdings calculation overflow", serde_json::json!({"error": e, "current": current_pct, "sell": pct}));
+                return Err(EngineError::InvalidAmount(format!("holdings calculation: {}", e)));
+            }
+        };
+
+        // A sliver below the dust fraction (e.g. residue from repeated partial sells)
+        // would keep the engine in PassiveToken; sell it along with the rest
+        let sells_dust = new_holdings > 0.0 && new_holdings < self.config.sell_dust_fraction;
+        if sells_dust {
+            debug!(mint=%mint, residue=new_holdings, correlation_id=ctx.correlation_id, "Sell would leave dust; selling the whole position");
+            metrics().increment_counter("sell_dust_promoted_total");
+            pct = 1.0;
+            new_holdings = 0.0;
+        }
+        Ok((pct, new_holdings, sells_dust))
+    }
+
+    /// Book a sell of `mint` broadcast as `outcome`: realize PnL on what was still
+    /// held down to `new_holdings` at `sell_price`, and close the position once empty.
+    async fn book_sell(
+        &self,
+        mint: &Pubkey,
+        current_pct: f64,
+        new_holdings: f64,
+        sell_price: Option<f64>,
+        outcome: &SendOutcome,
+        ctx: &PipelineContext,
+    ) {
+        let mint = *mint;
+        let sig = outcome.signature;
+        info!(mint=%mint, sig=%sig, endpoint=%outcome.endpoint, correlation_id=ctx.correlation_id, "SELL broadcasted");
+        self.audit.record(ctx.correlation_id, AuditEvent::Broadcast {
+            kind: "sell".to_string(),
+            mint: mint.to_string(),
+            signature: sig.to_string(),
+            endpoint: outcome.endpoint.clone(),
+        });
+        let closed = {
+            let mut st = self.app_state.lock().await;
+            let from = mode_label(&st.mode);
+            // Only what was still held when this sell went out is booked
+            let sold_from = st.position(&mint).map_or(0.0, |p| p.holdings_percent.min(current_pct));
+            if let Some(price) = sell_price {
+                let realized = st.record_sell(&mint, (sold_from - new_holdings).max(0.0), price);
+                debug!(mint=%mint, price, realized, correlation_id=ctx.correlation_id, "Booked sell PnL");
+            }
+            let closed = st.set_position_holdings(&mint, new_holdings);
+            if closed {
+                info!(mint=%mint, correlation_id=ctx.correlation_id, "Sold 100%; position closed");
+                let to = mode_label(&st.mode);
+                if to != from {
+                    self.audit.record(ctx.correlation_id, AuditEvent::ModeTransition { from, to });
+                }
+            }
+            closed
+        };
+        if closed {
+            self.ladder.lock().unwrap().reset(&mint);
+            self.unwrap_wsol_after_close(&mint, ctx).await;
+        }
+    }
+
+    /// Sell `percent` of each of several positions at once, packing the sells into as
+    /// few transactions as fit (see `TransactionBuilder::build_batch_sell_transactions`).
+    ///
+    /// Each transaction is sent once, without the escalation of `sell`, and every
+    /// position it carries is booked when it goes through. Returns one result per mint.
+    pub async fn sell_many(&self, sells: &[(Pubkey, f64)]) -> Vec<(Pubkey, Result<(), EngineError>)> {
+        if self.pending_buy.load(Ordering::Relaxed) {
+            warn!("Batch sell requested while buy is pending; rejecting to avoid race condition");
+            return sells.iter().map(|(mint, _)| (*mint, Err(EngineError::BuyInProgress))).collect();
+        }
+
+        let mut results = Vec::with_capacity(sells.len());
+        let mut planned = Vec::with_capacity(sells.len());
+        for &(mint, percent) in sells {
+            let ctx = PipelineContext::new("buy_engine_sell");
+            let Some(position) = self.app_state.lock().await.position(&mint) else {
+                warn!(mint=%mint, correlation_id=ctx.correlation_id, "Sell requested for a token not held; ignoring");
+                results.push((mint, Err(EngineError::NotHolding)));
+                continue;
+            };
+            let current_pct = position.holdings_percent;
+            let (pct, new_holdings) = match self.sell_fraction(&mint, current_pct, percent, &ctx) {
+                Ok((pct, new_holdings, _)) => (pct, new_holdings),
+                Err(e) => {
+                    results.push((mint, Err(e)));
+                    continue;
+                }
+            };
+            ctx.logger.log_sell_operation(&mint.to_string(), pct, new_holdings);
+            self.audit.record(ctx.correlation_id, AuditEvent::Command {
+                command: "sell".to_string(),
+                mint: mint.to_string(),
+                percent: Some(pct),
+            });
+            let sell_price = self.sell_price(&mint).await.or(position.buy_price);
+            self.app_state.lock().await.start_operation(ctx.correlation_id, "sell", mint);
+            let program = position.token.program;
+            planned.push(BatchedSell { mint, program, pct, current_pct, new_holdings, sell_price, ctx });
+        }
+        if planned.is_empty() {
+            return results;
+        }
+        info!(sells = planned.len(), "Composing batched SELL transactions");
+
+        let sent: Vec<(Vec<Pubkey>, Result<SendOutcome>)> = if self.config.paper_trading {
+            planned
+                .iter()
+                .map(|sell| (vec![sell.mint], Ok(self.paper_outcome("sell", &sell.mint, &sell.ctx))))
+                .collect()
+        } else {
+            let batch: Vec<_> = planned
+                .iter()
+                .map(|sell| (sell.mint, sell.program.clone(), SellAmount::Fraction(sell.pct)))
+                .collect();
+            match self.create_batch_sell_transactions(&batch).await {
+                Ok(txs) => {
+                    let mut sent = Vec::with_capacity(txs.len());
+                    for (tx, mints) in txs {
+                        sent.push((mints, self.send_batch_sell(tx).await));
+                    }
+                    sent
+                }
+                Err(e) => vec![(batch.iter().map(|(mint, ..)| *mint).collect(), Err(e))],
+            }
+        };
+
+        for (mints, result) in sent {
+            // One failure is shared by every sell its transaction carried
+            let result = result.map_err(|e| (SellFailureReason::classify(&e), format!("{:#}", e)));
+            for mint in mints {
+                let Some(at) = planned.iter().position(|sell| sell.mint == mint) else {
+                    continue;
+                };
+                let sell = planned.swap_remove(at);
+                let ctx = &sell.ctx;
+                self.app_state.lock().await.finish_operation(ctx.correlation_id);
+                match &result {
+                    Ok(outcome) => {
+                        self.book_sell(&mint, sell.current_pct, sell.new_holdings, sell.sell_price, outcome, ctx).await;
+                        results.push((mint, Ok(())));
+                    }
+                    Err((reason, error)) => {
+                        metrics().increment_counter("sell_failures_total");
+                        metrics().increment_counter(reason.counter_name());
+                        error!(mint=%mint, error=%error, %reason, correlation_id=ctx.correlation_id, "SELL failed");
+                        let error = anyhow!("{}", error);
+                        results.push((mint, Err(EngineError::SellFailed { reason: reason.clone(), error })));
+                    }
+                }
+            }
+        }
+        results
+    }
+
+    /// Batched sell transactions for `sells`, each with the mints it sells.
+    async fn create_batch_sell_transactions(
+        &self,
+        sells: &[(Pubkey, String, SellAmount)],
+    ) -> Result<Vec<(VersionedTransaction, Vec<Pubkey>)>> {
+        match &self.tx_builder {
+            Some(builder) => {
+                let config = sell_transaction_config(&self.config, 0);
+                builder.build_batch_sell_transactions(sells, &config, false).await
+                    .context("Transaction build failed")
+            }
+            None => {
+                // Fallback to placeholder for testing/mock mode
+                #[cfg(any(test, feature = "mock-mode"))]
+                {
+                    Ok(sells
+                        .iter()
+                        .map(|(mint, ..)| (Self::create_placeholder_tx(mint, "sell", true), vec![*mint]))
+                        .collect())
+                }
+                #[cfg(not(any(test, feature = "mock-mode")))]
+                {
+                    Err(EngineError::NoBuilder.into())
+                }
+            }
+        }
+    }
+
+    /// Send one batched sell transaction and, with `sell_confirm_timeout_ms`, wait for it to land.
+    async fn send_batch_sell(&self, tx: VersionedTransaction) -> Result<SendOutcome> {
+        let outcome = self.rpc.send_sell_on_many_rpc(vec![tx], None).await?;
+        if outcome.was_duplicate && !self.config.sell_already_processed_as_success {
+            return Err(anyhow!("sell {} was already processed", outcome.signature));
+        }
+        let confirm_timeout = Duration::from_millis(self.config.sell_confirm_timeout_ms);
+        if outcome.was_duplicate || confirm_timeout.is_zero() || self.wait_for_landing(&outcome, confirm_timeout).await {
+            return Ok(outcome);
+        }
+        metrics().increment_counter("sell_unconfirmed_total");
+        Err(anyhow!("sell {} not confirmed in time", outcome.signature))
+    }
+
+    /// Price a sell of `mint` is booked at: the one last recorded for it (exit
+    /// checks refresh it), so the sell is not delayed by a quote; else the current one.
+    async fn sell_price(&self, mint: &Pubkey) -> Option<f64> {
+        if let Some(price) = self.app_state.lock().await.pnl.price(mint) {
+            return Some(price);
+        }
+        match self.pricing()?.current_price(mint).await {
+            Ok(price) if price.is_finite() && price > 0.0 => Some(price),
+            Ok(_) => None,
+            Err(e) => {
+                debug!(mint=%mint, error=%e, "No price for sell PnL");
+                None
+            }
+        }
+    }
+
+    /// With `auto_unwrap_wsol`, return leftover wrapped SOL to the wallet once a
+    /// position is closed. A failure is logged and counted; the sell still stands.
+    async fn unwrap_wsol_after_close(&self, mint: &Pubkey, ctx: &PipelineContext) {
+        if !self.config.auto_unwrap_wsol || self.config.paper_trading {
+            return;
+        }
+        let unwrapper: &dyn WsolUnwrapper = match (&self.wsol_unwrapper, &self.tx_builder) {
+            (Some(unwrapper), _) => unwrapper.as_ref(),
+            (None, Some(builder)) => builder.as_ref(),
+            (None, None) => {
+                debug!(mint=%mint, "No transaction builder; skipping WSOL unwrap");
+                return;
+            }
+        };
+        let config = buy_transaction_config(&self.config, false);
+        match unwrapper.unwrap_wsol(&config).await {
+            Ok(sig) => {
+                metrics().increment_counter("wsol_unwrap_total");
+                info!(mint=%mint, sig=%sig, correlation_id=ctx.correlation_id, "Unwrapped WSOL after closing position");
+            }
+            Err(e) => {
+                metrics().increment_counter("wsol_unwrap_failures_total");
+                warn!(mint=%mint, error=%e, correlation_id=ctx.correlation_id, "WSOL unwrap after close failed");
+            }
+        }
+    }
+
+    /// `try_buy_with_retry`, then, for a buy sent as a Jito bundle, wait for it to land
+    /// and, with `confirm_buys`, for the buy to reach `buy_confirm_commitment`.
+    async fn buy_and_land(
+        &self,
+        candidate: &PremintCandidate,
+        ctx: &PipelineContext,
+        amount_lamports: Option<u64>,
+    ) -> Result<SentBuy> {
+        let buy = async {
+            let outcome = self.try_buy_with_retry(candidate, ctx, amount_lamports).await?;
+            let bundle = self.await_bundle_landing(outcome, ctx).await?;
+            if bundle.unconfirmed {
+                return Ok(bundle);
+            }
+            self.await_buy_confirmation(bundle.outcome, ctx).await
+        };
+        // The balance read is polled first, so its request is out before the buy is
+        // even built, without holding up the send. A buy lands a slot after its send
+        // at the earliest, so the fill check and reconciliation count only what it added.
+        let (balance_before, result) = tokio::join!(self.balance_before_buy(&candidate.mint, ctx), buy);
+        // Taken on every exit, so failed buys leave no quote behind
+        let min_out = self.tx_builder.as_ref().and_then(|b| b.take_buy_min_out(&candidate.mint));
+        let mut buy = result?;
+        buy.min_out = min_out;
+        buy.balance_before = balance_before;
+        Ok(buy)
+    }
+
+    /// The wallet's balance of `mint` ahead of a buy, waited for at most
+    /// `BALANCE_SNAPSHOT_TIMEOUT`; `None` in paper trading, without a balance reader,
+    /// or when the read fails.
+    async fn balance_before_buy(&self, mint: &Pubkey, ctx: &PipelineContext) -> Option<u64> {
+        if self.config.paper_trading {
+            return None;
+        }
+        let balances = self.token_balances.as_ref()?;
+        match timeout(BALANCE_SNAPSHOT_TIMEOUT, balances.token_balance(mint)).await {
+            Ok(Ok(balance)) => Some(balance),
+            Ok(Err(e)) => {
+                debug!(mint=%mint, error=%e, correlation_id=ctx.correlation_id, "Pre-buy balance read failed");
+                None
+            }
+            Err(_) => {
+                debug!(mint=%mint, correlation_id=ctx.correlation_id, "Pre-buy balance read timed out");
+                None
+            }
+        }
+    }
+
+    /// Wait up to `buy_confirm_timeout_ms` for the buy to reach `buy_confirm_commitment`.
+    /// A buy that failed on chain fails. One that did not confirm in time is not
+    /// retried, since it may still land and a retry could buy twice; it comes back
+    /// unconfirmed, to be tracked until reconciliation settles it.
+    async fn await_buy_confirmation(&self, outcome: SendOutcome, ctx: &PipelineContext) -> Result<SentBuy> {
+        if !self.config.confirm_buys || self.config.paper_trading {
+            return Ok(SentBuy::landed(outcome));
+        }
 
+        let limit = Duration::from_millis(self.config.buy_confirm_timeout_ms);
+        let commitment = self.config.buy_confirm_commitment_config();
+        match self.rpc.confirm_signature(&outcome.signature, commitment, limit).await? {
+            ConfirmationStatus::Confirmed { slot } => {
+                metrics().increment_counter("buy_confirmed_total");
+                info!(sig=%outcome.signature, slot, correlation_id=ctx.correlation_id, "BUY confirmed");
+                Ok(SentBuy::landed(outcome))
+            }
+            ConfirmationStatus::Failed(err) => {
+                metrics().increment_counter("buy_confirm_failed_total");
+                Err(anyhow!("buy {} failed on chain: {}", outcome.signature, err))
+            }
+            ConfirmationStatus::TimedOut => {
+                metrics().increment_counter("buy_confirm_timeout_total");
+                warn!(
+                    sig=%outcome.signature,
+                    timeout_ms=self.config.buy_confirm_timeout_ms,
+                    correlation_id=ctx.correlation_id,
+                    "BUY not confirmed in time; holding it as unconfirmed"
+                );
+                Ok(SentBuy::unconfirmed(outcome))
+            }
+        }
+    }
+
+    /// Poll the status of the bundle `outcome` was sent in for up to
+    /// `jito_bundle_status_timeout_ms`; a bundle that failed fails the buy. One still
+    /// pending or unknown then may yet land, so it comes back unconfirmed rather than
+    /// failed. Outcomes without a bundle pass through.
+    async fn await_bundle_landing(&self, outcome: SendOutcome, ctx: &PipelineContext) -> Result<SentBuy> {
+        let (Some(bundle_id), Some(builder), Some(url)) =
+            (&outcome.bundle_id, &self.tx_builder, &self.config.jito_block_engine_url)
+        else {
+            return Ok(SentBuy::landed(outcome));
+        };
+        if self.config.jito_bundle_status_timeout_ms == 0 {
+            return Ok(SentBuy::landed(outcome));
+        }
 
-            for idx in acquired_indices.drain(..) {
+        let deadline = Instant::now() + Duration::from_millis(self.config.jito_bundle_status_timeout_ms);
+        loop {
+            match builder.get_bundle_status(bundle_id, url).await {
+                Ok(BundleStatus::Landed { slot }) => {
+                    metrics().increment_counter("buy_bundle_landed_total");
+                    info!(bundle_id=%bundle_id, slot, correlation_id=ctx.correlation_id, "BUY bundle landed");
+                    return Ok(SentBuy::landed(outcome));
+                }
+                Ok(BundleStatus::Failed) => {
+                    metrics().increment_counter("buy_bundle_failed_total");
+                    return Err(anyhow!("buy bundle {} failed on chain", bundle_id));
+                }
+                Ok(status) => debug!(bundle_id=%bundle_id, ?status, "BUY bundle not landed yet"),
+                Err(e) => debug!(bundle_id=%bundle_id, error=%e, "BUY bundle status poll failed"),
+            }
+            let now = Instant::now();
+            if now >= deadline {
+                metrics().increment_counter("buy_bundle_timeout_total");
+                warn!(
+                    bundle_id=%bundle_id,
+                    timeout_ms=self.config.jito_bundle_status_timeout_ms,
+                    correlation_id=ctx.correlation_id,
+                    "BUY bundle not landed in time; holding it as unconfirmed"
+                );
+                return Ok(SentBuy::unconfirmed(outcome));
+            }
+            sleep(BUNDLE_STATUS_POLL_INTERVAL.min(deadline - now)).await;
+        }
+    }
 
-                ctx.logger.log_nonce_operation("release", Some(idx), true);
-                self.nonce_manager.release_nonce(idx);
+    /// Whole-buy retry: each attempt gets fresh nonces and blockhash, bounded by
+    /// `buy_retry_attempts` and the candidate deadline.
+    async fn try_buy_with_retry(
+        &self,
+        candidate: &PremintCandidate,
+        ctx: &PipelineContext,
+        amount_lamports: Option<u64>,
+    ) -> Result<SendOutcome> {
+        let mut retries = 0;
+        loop {
+            let err = match self.try_buy(candidate.clone(), ctx.clone(), amount_lamports).await {
+                Ok(outcome) => return Ok(outcome),
+                Err(e) => e,
+            };
 
+            let retry_delay = Duration::from_millis(self.config.buy_retry_backoff_ms);
+            let within_deadline = !candidate.is_past_deadline(now_ms() + retry_delay.as_millis() as u64);
+            if retries >= self.config.buy_retry_attempts || !is_recoverable_buy_error(&err) || !within_deadline {
+                return Err(err);
             }
 
+            retries += 1;
+            metrics().increment_counter("buy_retries_total");
+            warn!(mint=%candidate.mint, attempt=retries, error=%err, correlation_id=ctx.correlation_id, "Recoverable BUY failure; retrying whole buy");
+            sleep(retry_delay).await;
+        }
+    }
+
+    /// One buy attempt of `amount_lamports` (`None`: the configured amount).
+    async fn try_buy(&self, candidate: PremintCandidate, ctx: PipelineContext, amount_lamports: Option<u64>) -> Result<SendOutcome> {
+        let mut acquired_indices: Vec<usize> = Vec::new();
+
+        let mut txs: Vec<VersionedTransaction> = Vec::new();
+
+        // Get recent blockhash once for all transactions
+        let recent_blockhash = self.get_recent_blockhash().await;
+
+        // Copies of a buy on different durable nonces are distinct transactions that
+        // could all land, so a durable buy is built once, on one slot
+        let builds = if self.nonce_manager.is_durable() { 1 } else { self.config.nonce_count };
+        for _ in 0..builds {
+            match self.nonce_manager.acquire_nonce().await {
+
+                Ok((_nonce_pubkey, idx)) => {
+                    ctx.logger.log_nonce_operation("acquire", Some(idx), true);
+                    acquired_indices.push(idx);
+
+                    if self.deadline_exceeded(&candidate, "build") {
+                        self.release_nonces(acquired_indices, &ctx);
+                        return Err(anyhow!("candidate deadline exceeded before build"));
+                    }
+
+                    match self.create_buy_transaction(&candidate, idx, recent_blockhash, false, amount_lamports).await {
+                        Ok(tx) => txs.push(tx),
+                        Err(e) => {
+                            self.release_nonces(acquired_indices, &ctx);
+                            return Err(e);
+                        }
+                    }
+                }
+                Err(e) => {
+
+                    ctx.logger.log_nonce_operation("acquire_failed", None, false);
+                    warn!(error=%e, correlation_id=ctx.correlation_id, "Failed to acquire nonce; proceeding with fewer");
+
+                    break;
+                }
+            }
+        }
 
+        if txs.is_empty() {
+            self.release_nonces(acquired_indices, &ctx);
             return Err(anyhow!("no transactions prepared (no nonces acquired)"));
         }
 
+        if let Err(e) = self.simulate_buy(&txs[0], &candidate, &ctx).await {
+            self.release_nonces(acquired_indices, &ctx);
+            return Err(e);
+        }
 
         ctx.logger.log_buy_attempt(&candidate.mint.to_string(), txs.len());
+        let tx_count = txs.len();
         
-        let res = self
-            .rpc
-            .send_on_many_rpc(txs, Some(CorrelationId::new()))
-            .await
-            .context("broadcast BUY failed");
+        let mut res = self.dispatch_buy(txs, &candidate, &ctx).await;
+        let mut legacy = false;
+
+        // Some nodes reject v0 transactions; retry once with legacy messages
+        if let Err(e) = &res {
+            if self.config.legacy_tx_fallback && is_unsupported_version_error(e) {
+                warn!(mint=%candidate.mint, error=%e, correlation_id=ctx.correlation_id, "v0 BUY rejected; retrying with legacy transactions");
+                metrics().increment_counter("legacy_tx_fallback_total");
+                legacy = true;
+
+                let mut legacy_txs = Vec::with_capacity(tx_count);
+                for &idx in &acquired_indices {
+                    match self.create_buy_transaction(&candidate, idx, recent_blockhash, true, amount_lamports).await {
+                        Ok(tx) => legacy_txs.push(tx),
+                        Err(build_err) => {
+                            res = Err(build_err.context("legacy BUY rebuild failed"));
+                            break;
+                        }
+                    }
+                }
+                if legacy_txs.len() == tx_count {
+                    res = self
+                        .dispatch_buy(legacy_txs, &candidate, &ctx)
+                        .await
+                        .context("legacy BUY failed");
+                }
+            }
+        }
+
+        // The blockhash (or durable nonce value) went stale before landing: rebuild
+        // with a fresh one and resend, a bounded number of times
+        let mut refreshes = 0;
+        while let Err(e) = &res {
+            if refreshes >= self.config.blockhash_refresh_retries
+                || !is_blockhash_not_found_error(e)
+                || self.deadline_exceeded(&candidate, "blockhash_refresh")
+            {
+                break;
+            }
+            refreshes += 1;
+            warn!(mint=%candidate.mint, attempt=refreshes, error=%e, correlation_id=ctx.correlation_id, "BUY blockhash not found; rebuilding with a fresh blockhash");
+            metrics().increment_counter("buy_blockhash_refresh_total");
+
+            let recent_blockhash = self.refresh_blockhash().await;
+            let mut fresh_txs = Vec::with_capacity(tx_count);
+            for &idx in &acquired_indices {
+                match self.create_buy_transaction(&candidate, idx, recent_blockhash, legacy, amount_lamports).await {
+                    Ok(tx) => fresh_txs.push(tx),
+                    Err(build_err) => {
+                        res = Err(build_err.context("BUY rebuild after blockhash refresh failed"));
+                        break;
+                    }
+                }
+            }
+            if fresh_txs.len() == tx_count {
+                res = self.dispatch_buy(fresh_txs, &candidate, &ctx).await;
+            }
+        }
+
+        self.release_nonces(acquired_indices, &ctx);
+        res
+    }
 
-        for idx in acquired_indices {
+    /// Return the nonce slots a buy attempt acquired.
+    fn release_nonces(&self, indices: Vec<usize>, ctx: &PipelineContext) {
+        for idx in indices {
             ctx.logger.log_nonce_operation("release", Some(idx), true);
             self.nonce_manager.release_nonce(idx);
         }
+    }
 
-        res
+    /// Send prepared buy transactions: as a paper trade, a Jito bundle or over RPC.
+    async fn dispatch_buy(
+        &self,
+        mut txs: Vec<VersionedTransaction>,
+        candidate: &PremintCandidate,
+        ctx: &PipelineContext,
+    ) -> Result<SendOutcome> {
+        if self.nonce_manager.is_durable() && txs.len() == 1 {
+            // The one durable-nonce build goes to as many endpoints as `nonce_count` copies would
+            txs = vec![txs.swap_remove(0); self.config.nonce_count.max(1)];
+        }
+        match (&self.tx_builder, &self.config.jito_block_engine_url) {
+            _ if self.config.paper_trading => Ok(self.paper_outcome("buy", &candidate.mint, ctx)),
+            // A bundle carries one copy of the buy; the other nonce variants are not sent
+            (Some(builder), Some(url)) => self.send_buy_bundle(builder, url, txs.swap_remove(0)).await,
+            _ => self
+                .rpc
+                .send_on_many_rpc(txs, Some(CorrelationId::new()))
+                .await
+                .context("broadcast BUY failed"),
+        }
+    }
+
+    /// Stand-in for a broadcast under `paper_trading`: nothing is sent, and the
+    /// signature is derived from `kind`, `mint` and the engine's paper sequence.
+    fn paper_outcome(&self, kind: &str, mint: &Pubkey, ctx: &PipelineContext) -> SendOutcome {
+        let sequence = self.paper_sequence.fetch_add(1, Ordering::Relaxed);
+        let digest = hashv(&[kind.as_bytes(), mint.as_ref(), &sequence.to_le_bytes()]);
+        let mut bytes = [0u8; 64];
+        bytes[..32].copy_from_slice(digest.as_ref());
+        bytes[32..].copy_from_slice(hashv(&[digest.as_ref()]).as_ref());
+        let signature = Signature::from(bytes);
 
+        metrics().increment_counter("paper_trades_total");
+        info!(mint=%mint, kind, sig=%signature, correlation_id=ctx.correlation_id, "Paper trade; nothing broadcast");
+        SendOutcome {
+            signature,
+            endpoint: PAPER_ENDPOINT.to_string(),
+            latency_ms: 0,
+            was_duplicate: false,
+            bundle_id: None,
+        }
+    }
+
+    /// Send `tx` through the block engine at `url` as a single-transaction bundle
+    /// tipped `jito_tip_lamports`.
+    async fn send_buy_bundle(&self, builder: &TransactionBuilder, url: &str, tx: VersionedTransaction) -> Result<SendOutcome> {
+        let config = TransactionConfig { jito_bundle_enabled: true, ..buy_transaction_config(&self.config, false) };
+        let bundle = builder
+            .prepare_jito_bundle(vec![tx], self.config.jito_tip_lamports, None, &config)
+            .await
+            .context("prepare BUY bundle failed")?;
+        let started = Instant::now();
+        let bundle_id = builder.submit_jito_bundle(&bundle, url).await.context("submit BUY bundle failed")?;
+        Ok(SendOutcome {
+            signature: bundle.transactions[0].signatures[0],
+            endpoint: url.to_string(),
+            latency_ms: started.elapsed().as_millis() as u64,
+            was_duplicate: false,
+            bundle_id: Some(bundle_id),
+        })
+    }
+
+    /// With `simulate_before_send`, simulate `tx` and fail when the node rejects it,
+    /// logging its program logs. Placeholder buys (no builder) are not simulated.
+    async fn simulate_buy(&self, tx: &VersionedTransaction, candidate: &PremintCandidate, ctx: &PipelineContext) -> Result<()> {
+        if !self.config.simulate_before_send {
+            return Ok(());
+        }
+        let Some(builder) = &self.tx_builder else {
+            return Ok(());
+        };
+        let outcome = builder
+            .simulate_transaction(tx, &buy_transaction_config(&self.config, false))
+            .await
+            .context("buy simulation failed")?;
+        let Some(error) = &outcome.error else {
+            debug!(mint=%candidate.mint, units_consumed=?outcome.units_consumed, correlation_id=ctx.correlation_id, "Buy simulation succeeded");
+            return Ok(());
+        };
+        metrics().increment_counter("buy_simulation_failed_total");
+        ctx.logger.error("Buy simulation failed", serde_json::json!({
+            "mint": candidate.mint.to_string(),
+            "error": error,
+            "units_consumed": outcome.units_consumed,
+            "logs": outcome.logs,
+        }));
+        Err(anyhow!("buy simulation failed: {}", error))
     }
 
+    /// Build a buy on nonce slot `nonce_slot`, held by this engine; the builder
+    /// shares the engine's nonce manager, so a durable slot's nonce is used.
     async fn create_buy_transaction(
         &self,
         candidate: &PremintCandidate,
+        nonce_slot: usize,
         _recent_blockhash: Option<solana_sdk::hash::Hash>,
+        legacy: bool,
+        amount_lamports: Option<u64>,
     ) -> Result<VersionedTransaction> {
         match &self.tx_builder {
             Some(builder) => {
-                let config = TransactionConfig::default();
-                builder.build_buy_transaction(candidate, &config, false).await
-                    .map_err(|e| anyhow!("Transaction build failed: {}", e))
+                let config = self.reserved_buy_config(legacy, amount_lamports).ok_or_else(|| {
+                    EngineError::InsufficientFunds("buy would breach the minimum SOL reserve".to_string())
+                })?;
+                builder.build_buy_transaction_in_slot(candidate, &config, false, nonce_slot).await
+                    .context("Transaction build failed")
             }
             None => {
                 // Fallback to placeholder for testing/mock mode
                 #[cfg(any(test, feature = "mock-mode"))]
                 {
-                    Ok(Self::create_placeholder_tx(&candidate.mint, "buy"))
+                    Ok(Self::create_placeholder_tx(&candidate.mint, "buy", legacy))
                 }
                 #[cfg(not(any(test, feature = "mock-mode")))]
                 {
-                    Err(anyhow!("No transaction builder available in production mode"))
+                    Err(EngineError::NoBuilder.into())
                 }
             }
         }
     }
 
-    async fn create_sell_transaction(
+    /// Buy settings for `amount_lamports` (`None`: the configured amount), downsized
+    /// to keep the wallet at or above `min_sol_reserve_lamports`; `None` when the buy
+    /// would breach the reserve or no balance was polled yet. Without a reserve or
+    /// balance source buys are not sized.
+    fn reserved_buy_config(&self, legacy: bool, amount_lamports: Option<u64>) -> Option<TransactionConfig> {
+        let mut config = buy_transaction_config(&self.config, legacy);
+        if let Some(amount) = amount_lamports {
+            config.buy_amount_lamports = amount;
+            cap_buy_priority_fees(&mut config, &self.config);
+        }
+        let reserve = self.config.min_sol_reserve_lamports;
+        let Some(balance) = self.sol_balance.as_ref().filter(|_| reserve > 0) else {
+            return Some(config);
+        };
+        let amount = buy_amount_within_reserve(
+            balance.get()?,
+            config.buy_amount_lamports,
+            buy_overhead_lamports(&config),
+            reserve,
+        )?;
+        if amount < config.buy_amount_lamports {
+            metrics().increment_counter("buy_downsized_for_reserve_total");
+            debug!(from = config.buy_amount_lamports, to = amount, reserve, "Buy downsized to keep the SOL reserve");
+            config.buy_amount_lamports = amount;
+            cap_buy_priority_fees(&mut config, &self.config);
+        }
+        Some(config)
+    }
+
+    /// Fraction of the current balance worth `lamports` at the builder's sell quote.
+    async fn sol_value_fraction(&self, mint: &Pubkey, lamports: u64) -> Result<f64> {
+        let builder = self
+            .tx_builder
+            .as_ref()
+            .ok_or_else(|| anyhow!("SOL-value sell needs a transaction builder for quotes"))?;
+        let quote = builder.quote_sell(mint).await?;
+        Ok(quote.fraction_for_sol(lamports))
+    }
+
+    /// Build and send a sell, then wait up to `sell_confirm_timeout_ms` for it to land.
+    ///
+    /// A sell that does not land in time is rebuilt with a higher priority fee and
+    /// wider slippage and sent again, at most `sell_max_escalations` times: getting
+    /// out matters more than the price. Each send is clamped to what is still held
+    /// above the plan's target, so a sell that landed meanwhile is not sold again.
+    ///
+    /// A frozen token account fails right away with its own error, metric and
+    /// notification: no fee or slippage gets the sell through until it is thawed.
+    async fn broadcast_sell(
         &self,
         mint: &Pubkey,
-        sell_percent: f64,
-    ) -> Result<VersionedTransaction> {
-        match &self.tx_builder {
-            Some(builder) => {
-                let config = TransactionConfig::default();
-                builder.build_sell_transaction(mint, "pump.fun", sell_percent, &config, false).await
-                    .map_err(|e| anyhow!("Transaction build failed: {}", e))
+        program: &str,
+        plan: SellPlan,
+        ctx: &PipelineContext,
+    ) -> Result<SendOutcome> {
+        let confirm_timeout = Duration::from_millis(self.config.sell_confirm_timeout_ms);
+        let mut escalation = 0;
+        loop {
+            let Some(amount) = self.unsold_amount(mint, plan).await else {
+                debug!(mint=%mint, target=plan.target_holdings, correlation_id=ctx.correlation_id, "Nothing left to sell above the target");
+                return Err(EngineError::NotHolding.into());
+            };
+            if self.config.paper_trading {
+                // Nothing to build from: a paper position has no tokens on chain
+                return Ok(self.paper_outcome("sell", mint, ctx));
             }
-            None => {
-                // Fallback to placeholder for testing/mock mode
-                #[cfg(any(test, feature = "mock-mode"))]
-                {
-                    Ok(Self::create_placeholder_tx(mint, "sell"))
+            let sell_tx = self.create_sell_transaction(mint, program, amount, escalation).await?;
+            let outcome = match self.rpc.send_sell_on_many_rpc(vec![sell_tx], None).await {
+                Ok(outcome) => outcome,
+                Err(e) if is_frozen_account_error(&e) => {
+                    metrics().increment_counter("sell_frozen_account_total");
+                    error!(mint=%mint, correlation_id=ctx.correlation_id, "SELL rejected: token account is frozen by the mint's freeze authority");
+                    self.app_state.lock().await.notify(
+                        NotificationLevel::Error,
+                        format!("Token account for {} is frozen; position cannot be sold until thawed", mint),
+                    );
+                    return Err(e.context(format!("token account for {} is frozen", mint)));
                 }
-                #[cfg(not(any(test, feature = "mock-mode")))]
-                {
-                    Err(anyhow!("No transaction builder available in production mode"))
+                Err(e) => return Err(e),
+            };
+            if outcome.was_duplicate {
+                if !self.config.sell_already_processed_as_success {
+                    return Err(anyhow!("sell {} was already processed", outcome.signature));
                 }
+                // An earlier send of this exact tx landed; nothing left to confirm or escalate
+                metrics().increment_counter("sell_already_processed_total");
+                info!(mint=%mint, sig=%outcome.signature, correlation_id=ctx.correlation_id, "SELL already processed; treating as landed");
+                return Ok(outcome);
+            }
+            if confirm_timeout.is_zero() {
+                return Ok(outcome);
             }
+            self.app_state.lock().await.set_operation_stage(ctx.correlation_id, "confirming");
+            if self.wait_for_landing(&outcome, confirm_timeout).await {
+                return Ok(outcome);
+            }
+
+            if escalation >= self.config.sell_max_escalations {
+                metrics().increment_counter("sell_unconfirmed_total");
+                return Err(anyhow!(
+                    "sell {} not confirmed after {} escalations",
+                    outcome.signature,
+                    escalation
+                ));
+            }
+            escalation += 1;
+            metrics().increment_counter("sell_escalations_total");
+            warn!(mint=%mint, sig=%outcome.signature, escalation, correlation_id=ctx.correlation_id, "SELL not confirmed in time; escalating fee and slippage");
+        }
+    }
+
+    /// What `plan` still has to sell of the tokens held in `mint` now, read under the
+    /// state lock: another sell may have brought the holdings closer to (or below) the
+    /// target since the plan was made. `None` once nothing is left above the target.
+    async fn unsold_amount(&self, mint: &Pubkey, plan: SellPlan) -> Option<SellAmount> {
+        let st = self.app_state.lock().await;
+        let held = st.position(mint)?.holdings_percent;
+        if held - plan.target_holdings <= f64::EPSILON {
+            return None;
+        }
+        match plan.amount {
+            // Unchanged holdings: the plan's own amount (e.g. a SOL value) still applies
+            amount if (held - plan.from_holdings).abs() <= f64::EPSILON => Some(amount),
+            _ => Some(SellAmount::Fraction(((held - plan.target_holdings) / held).clamp(0.0, 1.0))),
         }
     }
 
-    #[cfg(any(test, feature = "mock-mode"))]
-    fn create_placeholder_tx(_token_mint: &Pubkey, _action: &str) -> VersionedTransaction {
-        use solana_sdk::{message::Message, system_instruction, transaction::Transaction};
-        
-        let from = Pubkey::new_unique();
-        let to = Pubkey::new_unique();
-        let ix = system_instruction::transfer(&from, &to, 1);
-        let msg = Message::new(&[ix], None);
-        let tx = Transaction::new_unsigned(msg);
-        VersionedTransaction::from(tx)
-    }
+    /// Check the fill of `buy` in the background (see `check_fill`), unless it is
+    /// unconfirmed (reconciliation settles those) or has no quote or pre-buy balance
+    /// to compare with. `poll_fill_checks` acts on the result.
+    fn spawn_fill_check(&self, mint: Pubkey, buy: &SentBuy, ctx: &PipelineContext) {
+        if !self.config.post_buy_verify_fill || self.config.paper_trading || buy.unconfirmed {
+            return;
+        }
+        let (Some(balances), Some(min_out), Some(balance_before)) =
+            (self.token_balances.clone(), buy.min_out, buy.balance_before)
+        else {
+            return;
+        };
+        let rpc = self.rpc.clone();
+        let app_state = self.app_state.clone();
+        let outcome = buy.outcome.clone();
+        let poll = Duration::from_millis(self.config.sell_confirm_poll_ms.max(1));
+        let limit = Duration::from_millis(self.config.post_buy_verify_timeout_ms);
+        let correlation_id = ctx.correlation_id;
+        let check = tokio::spawn(async move {
+            if !wait_for_landing(rpc.as_ref(), &outcome, poll, limit).await {
+                debug!(mint=%mint, sig=%outcome.signature, correlation_id, "Buy not landed in time; skipping fill check");
+                return None;
+            }
+            let balance_after = match balances.token_balance(&mint).await {
+                Ok(balance) => balance,
+                Err(e) => {
+                    warn!(mint=%mint, error=%e, correlation_id, "Fill check failed to read token balance");
+                    return None;
+                }
+            };
+            Some(check_fill(&app_state, &mint, balance_before, balance_after, min_out).await)
+        });
+        self.fill_checks.lock().unwrap_or_else(|e| e.into_inner()).push((mint, check));
+    }
+
+    /// Collect finished fill checks; with `post_buy_exit_on_underfill` an underfilled
+    /// position is sold right away.
+    async fn poll_fill_checks(&self) {
+        let finished: Vec<_> = {
+            let mut checks = self.fill_checks.lock().unwrap_or_else(|e| e.into_inner());
+            let (finished, running) = checks.drain(..).partition(|(_, check)| check.is_finished());
+            *checks = running;
+            finished
+        };
+        for (mint, check) in finished {
+            let Ok(Some(FillCheck::Underfilled { .. })) = check.await else {
+                continue;
+            };
+            if self.config.post_buy_exit_on_underfill {
+                metrics().increment_counter("buy_underfill_exits_total");
+                if let Err(e) = self.sell(mint, 1.0).await {
+                    error!(mint=%mint, error=%e, "Exit after underfilled buy failed");
+                }
+            }
+        }
+    }
+
+    /// Poll until `outcome` lands or `limit` passes (see `wait_for_landing`).
+    async fn wait_for_landing(&self, outcome: &SendOutcome, limit: Duration) -> bool {
+        let poll = Duration::from_millis(self.config.sell_confirm_poll_ms.max(1));
+        wait_for_landing(self.rpc.as_ref(), outcome, poll, limit).await
+    }
+
+    async fn create_sell_transaction(
+        &self,
+        mint: &Pubkey,
+        program: &str,
+        amount: SellAmount,
+        escalation: u32,
+    ) -> Result<VersionedTransaction> {
+        match &self.tx_builder {
+            Some(builder) => {
+                let config = sell_transaction_config(&self.config, escalation);
+                builder.build_sell_transaction(mint, program, amount, &config, false).await
+                    .context("Transaction build failed")
+            }
+            None => {
+                // Fallback to placeholder for testing/mock mode
+                #[cfg(any(test, feature = "mock-mode"))]
+                {
+                    Ok(Self::create_placeholder_tx(mint, "sell", true))
+                }
+                #[cfg(not(any(test, feature = "mock-mode")))]
+                {
+                    Err(EngineError::NoBuilder.into())
+                }
+            }
+        }
+    }
+
+    #[cfg(any(test, feature = "mock-mode"))]
+    fn create_placeholder_tx(_token_mint: &Pubkey, _action: &str, legacy: bool) -> VersionedTransaction {
+        use solana_sdk::{hash::Hash, signature::Signature};
+        use solana_system_interface::instruction as system_instruction;
+        use crate::tx_builder::compile_message;
+        
+        let from = Pubkey::new_unique();
+        let to = Pubkey::new_unique();
+        let ix = system_instruction::transfer(&from, &to, 1);
+        let message = compile_message(&from, &[ix], Hash::default(), legacy)
+            .expect("placeholder message compiles");
+        let required = message.header().num_required_signatures as usize;
+        VersionedTransaction {
+            signatures: vec![Signature::default(); required],
+            message,
+        }
+    }
+
+    /// Whether the buy schedule allows buying at `now`; reports window changes via
+    /// the `buy_window_open` gauge and a notification.
+    async fn buy_window_allows(&mut self, now: DateTime<Utc>) -> bool {
+        let open = buying_allowed(&self.config.buy_schedule, now);
+        if self.buy_window_open != Some(open) {
+            metrics().set_gauge("buy_window_open", open as u64);
+            if self.buy_window_open.is_some() || !open {
+                let message = if open { "Buy window opened; auto-buy resumed" } else { "Outside buy window; auto-buy paused" };
+                info!(open, "{}", message);
+                self.app_state.lock().await.notify(NotificationLevel::Info, message);
+            }
+            self.buy_window_open = Some(open);
+        }
+        open
+    }
+
+    /// Whether at least `rpc_min_healthy_fraction` of the RPC endpoints are healthy.
+    fn rpc_healthy(&self) -> bool {
+        self.rpc_health
+            .as_ref()
+            .is_none_or(|health| health.healthy_fraction() >= self.config.rpc_min_healthy_fraction)
+    }
+
+    /// Whether enough RPC endpoints are healthy to buy; reports pause/resume via the
+    /// `buy_rpc_health_paused` gauge and a notification.
+    async fn rpc_health_allows(&mut self) -> bool {
+        let Some(health) = self.rpc_health.clone() else { return true };
+        let paused = !self.rpc_healthy();
+        if paused != self.rpc_paused {
+            metrics().set_gauge("buy_rpc_health_paused", paused as u64);
+            let (healthy, total) = (health.healthy_count(), health.endpoint_count());
+            let mut st = self.app_state.lock().await;
+            if paused {
+                warn!(healthy, total, "Too few healthy RPC endpoints; auto-buy paused");
+                st.notify(
+                    NotificationLevel::Warn,
+                    format!("RPC endpoints unhealthy ({}/{} up); auto-buy paused", healthy, total),
+                );
+            } else {
+                info!(healthy, total, "RPC endpoints recovered; auto-buy resumed");
+                st.notify(NotificationLevel::Info, "RPC endpoints recovered; auto-buy resumed");
+            }
+            self.rpc_paused = paused;
+        }
+        !paused
+    }
+
+    /// Run `candidate` through every check before a buy, recording each in `trace`.
+    /// Returns the in-flight permit the buy holds, or `None` when a check rejected it.
+    async fn admit_candidate(
+        &mut self,
+        candidate: &PremintCandidate,
+        score: Option<u8>,
+        trace: &mut DecisionTrace,
+    ) -> Option<OwnedSemaphorePermit> {
+        if !self.passes_buy_checks(candidate, score, trace, false).await {
+            return None;
+        }
+
+        let Some(permit) = self.inflight.try_start() else {
+            metrics().increment_counter("buy_inflight_throttled_total");
+            warn!(mint=%candidate.mint, max_inflight=self.config.max_inflight_buys, "In-flight buy limit reached; dropping candidate");
+            trace.reject("inflight", "In-flight buy limit reached");
+            return None;
+        };
+        trace.pass("inflight");
+        Some(permit)
+    }
+
+    /// What the engine would do with `candidate` right now (`/simulate`): the checks
+    /// of `admit_candidate`, without buying or recording anything.
+    pub async fn simulate_candidate(&mut self, candidate: &PremintCandidate) -> BuySimulation {
+        let mut trace = DecisionTrace::new(candidate);
+        let accepts_buys = self.app_state.lock().await.accepts_buys(self.config.max_concurrent_positions);
+        if !accepts_buys {
+            trace.reject("mode", "Not taking new candidates (position limit reached or manual mode)");
+        } else if self.passes_buy_checks(candidate, None, &mut trace, true).await {
+            if self.inflight.available() == 0 {
+                trace.reject("inflight", "In-flight buy limit reached");
+            } else {
+                trace.pass("inflight");
+            }
+        }
+        let params = match trace.rejected_at {
+            None => self.reserved_buy_config(false, None).map(|config| BuyParams::new(&candidate.program, &config)),
+            Some(_) => None,
+        };
+        BuySimulation::from_trace(&trace, params)
+    }
+
+    /// The checks of `admit_candidate` short of taking an in-flight permit; true when
+    /// all passed. A `dry_run` records no metrics, funnel stages, rate-limit sightings
+    /// or pause notifications.
+    async fn passes_buy_checks(
+        &mut self,
+        candidate: &PremintCandidate,
+        score: Option<u8>,
+        trace: &mut DecisionTrace,
+        dry_run: bool,
+    ) -> bool {
+        let count = |name: &str| {
+            if !dry_run {
+                metrics().increment_counter(name);
+            }
+        };
+        // Validate candidate for security issues
+        let validation = validator().validate_candidate(candidate);
+        if !validation.is_valid() {
+            count("buy_attempts_security_rejected");
+            warn!(mint=%candidate.mint, issues=?validation.issues, "Candidate rejected due to security validation");
+            trace.reject("security", validation.issues.join("; "));
+            return false;
+        }
+        if !dry_run {
+            self.funnel.record(FunnelStage::SecurityPassed);
+        }
+        trace.pass("security");
+
+        // Check rate limiting to prevent spam
+        let within_rate_limit = if dry_run {
+            !validator().mint_rate_limited(&candidate.mint, 60, 5)
+        } else {
+            validator().check_mint_rate_limit(&candidate.mint, 60, 5)
+        };
+        if !within_rate_limit {
+            count("buy_attempts_rate_limited");
+            debug!(mint=%candidate.mint, "Candidate rate limited");
+            trace.reject("rate_limit", "Mint seen too often in the last minute");
+            return false;
+        }
+        trace.pass("rate_limit");
+
+        if self.app_state.lock().await.position(&candidate.mint).is_some() {
+            debug!(mint=%candidate.mint, "Candidate already held");
+            trace.reject("held", "A position in this token is already open");
+            return false;
+        }
+        trace.pass("held");
+
+        if let Err(reason) = self.candidate_filter.check(candidate) {
+            count("buy_attempts_filtered");
+            debug!(mint=%candidate.mint, program=%candidate.program, %reason, "Candidate filtered out");
+            trace.reject("filter", reason);
+            return false;
+        }
+        if !dry_run {
+            self.funnel.record(FunnelStage::FilterPassed);
+        }
+        trace.pass("filter");
+
+        // Cheap pre-checks, so nonces and builds are only spent on viable buys
+        if let Err(reason) = self.precheck_buy(candidate) {
+            count("buy_precheck_rejected_total");
+            debug!(mint=%candidate.mint, program=%candidate.program, %reason, "Candidate failed buy pre-check");
+            trace.reject("precheck", reason);
+            return false;
+        }
+        trace.pass("precheck");
+
+        let past_deadline = if dry_run {
+            candidate.is_past_deadline(now_ms())
+        } else {
+            self.deadline_exceeded(candidate, "filter")
+        };
+        if past_deadline {
+            trace.reject("deadline", "Candidate deadline exceeded");
+            return false;
+        }
+        trace.pass("deadline");
+
+        match score {
+            Some(score) if score < self.config.min_buy_score => {
+                count("buy_score_rejected_total");
+                debug!(mint=%candidate.mint, score, min_score=self.config.min_buy_score, "Candidate scored below min_buy_score");
+                trace.reject("score", format!("Score {} is below min_buy_score {}", score, self.config.min_buy_score));
+                return false;
+            }
+            Some(_) => trace.pass("score"),
+            // No score gate in the direct pipeline: every filtered candidate counts as scored
+            None => {}
+        }
+        if !dry_run {
+            self.funnel.record(FunnelStage::Scored);
+        }
+
+        let in_window = if dry_run {
+            buying_allowed(&self.config.buy_schedule, Utc::now())
+        } else {
+            self.buy_window_allows(Utc::now()).await
+        };
+        if !in_window {
+            count("buy_outside_schedule_total");
+            debug!(mint=%candidate.mint, "Outside buy schedule; not buying");
+            trace.reject("schedule", "Outside the configured buy schedule");
+            return false;
+        }
+        trace.pass("schedule");
+
+        let rpc_healthy = if dry_run { self.rpc_healthy() } else { self.rpc_health_allows().await };
+        if !rpc_healthy {
+            count("buy_rpc_unhealthy_skipped_total");
+            debug!(mint=%candidate.mint, "Too few healthy RPC endpoints; not buying");
+            trace.reject("rpc_health", "Too few healthy RPC endpoints");
+            return false;
+        }
+        trace.pass("rpc_health");
+
+        if self.reserved_buy_config(false, None).is_none() {
+            count("buy_reserve_skipped_total");
+            warn!(mint=%candidate.mint, reserve=self.config.min_sol_reserve_lamports, "Buy would breach the minimum SOL reserve; not buying");
+            trace.reject("reserve", "Buy would breach the minimum SOL reserve");
+            return false;
+        }
+        trace.pass("reserve");
+
+        let mint_checks = self.config.sellability_check || self.config.require_renounced_authorities;
+        let mint_account = match self.mint_rpc.as_ref().filter(|_| mint_checks) {
+            Some(rpc) => {
+                let limit = Duration::from_millis(self.config.mint_read_timeout_ms);
+                let read = timeout(limit, validator().read_mint(&candidate.mint, rpc)).await;
+                Some(read.unwrap_or_else(|_| Err(format!("Mint read timed out after {}ms", limit.as_millis()))))
+            }
+            None => None,
+        };
+
+        if let Some(account) = mint_account.as_ref().filter(|_| self.config.sellability_check) {
+            let report = SellabilityReport::from_mint(account);
+            if !report.is_sellable() {
+                count("buy_unsellable_rejected_total");
+                warn!(mint=%candidate.mint, risk_score=report.risk_score, issues=?report.issues, "Candidate looks unsellable; not buying");
+                trace.reject("sellability", report.issues.join("; "));
+                return false;
+            }
+            if !report.mint_checked {
+                count("buy_sellability_unchecked_total");
+                if self.config.sellability_fail_closed {
+                    debug!(mint=%candidate.mint, issues=?report.issues, "Mint not checked for sellability; not buying");
+                    trace.reject("sellability", report.issues.join("; "));
+                    return false;
+                }
+                debug!(mint=%candidate.mint, issues=?report.issues, "Mint not checked for sellability; buying anyway");
+            }
+            trace.pass("sellability");
+        }
+
+        if let Some(account) = mint_account.as_ref().filter(|_| self.config.require_renounced_authorities) {
+            let mut report = AuthorityReport::from_mint(account);
+            // A pool's liquidity can be pulled unless its LP is burned; bonding curves have no LP
+            let on_raydium = DexProgram::normalize(&candidate.program, &self.config.program_aliases) == DexProgram::Raydium;
+            if let Some(rpc) = self.mint_rpc.as_ref().filter(|_| on_raydium) {
+                let limit = Duration::from_millis(self.config.mint_read_timeout_ms);
+                let read = timeout(limit, validator().read_raydium_lp_mint(&candidate.mint, rpc)).await;
+                report.check_lp(&read.unwrap_or_else(|_| Err(format!("LP mint read timed out after {}ms", limit.as_millis()))));
+            }
+            if !report.rug_resistant() {
+                count("buy_authorities_rejected_total");
+                debug!(mint=%candidate.mint, issues=?report.issues, "Candidate authorities not renounced or LP not burned; not buying");
+                trace.reject("authorities", report.issues.join("; "));
+                return false;
+            }
+            trace.pass("authorities");
+        }
+        true
+    }
+
+    /// Drop check for stale candidates; counts `candidate_deadline_exceeded` when past due.
+    fn deadline_exceeded(&self, candidate: &PremintCandidate, stage: &str) -> bool {
+        if candidate.is_past_deadline(now_ms()) {
+            metrics().increment_counter("candidate_deadline_exceeded");
+            warn!(mint=%candidate.mint, stage, "Candidate deadline exceeded; dropping stale snipe");
+            return true;
+        }
+        false
+    }
+
+    /// Checks that need no RPC or nonce: the target program must be allowed.
+    fn precheck_buy(&self, candidate: &PremintCandidate) -> std::result::Result<(), String> {
+        let dex = DexProgram::normalize(&candidate.program, &self.config.program_aliases);
+        let allowed = self.config.allowed_program_ids();
+        if !allowed.is_empty() && !dex.program_id().is_some_and(|id| allowed.contains(&id)) {
+            return Err(format!("Program '{}' is not in allowed_programs", candidate.program));
+        }
+        Ok(())
+    }
+
+    /// Price a buy of `candidate` filled at: the mint's curve or quote price right
+    /// after the buy landed. `None` when no usable price is available.
+    async fn execution_price(&self, candidate: &PremintCandidate) -> Option<f64> {
+        let source = self.pricing()?;
+        match source.current_price(&candidate.mint).await {
+            Ok(price) if price.is_finite() && price > 0.0 => Some(price),
+            Ok(price) => {
+                debug!(mint=%candidate.mint, price, "Unusable fill price");
+                None
+            }
+            Err(e) => {
+                debug!(mint=%candidate.mint, error=%e, "No fill price");
+                None
+            }
+        }
+    }
+
+    async fn get_recent_blockhash(&self) -> Option<solana_sdk::hash::Hash> {
+        None // Simplified implementation
+    }
+
+    /// Drop the builder's cached blockhash and nonce values and fetch a fresh blockhash,
+    /// which the following builds pick up. `None` without a builder or when the fetch fails.
+    async fn refresh_blockhash(&self) -> Option<solana_sdk::hash::Hash> {
+        let builder = self.tx_builder.as_ref()?;
+        builder.invalidate_blockhash().await;
+        builder
+            .get_recent_blockhash(&buy_transaction_config(&self.config, false))
+            .await
+            .map_err(|e| warn!(error=%e, "Blockhash refresh failed"))
+            .ok()
+    }
+}
+
+/// Buy filter: the candidate passes the filters `config` enables (see
+/// `candidate_filter::from_config`).
+pub fn passes_buy_filter(config: &Config, candidate: &PremintCandidate) -> bool {
+    candidate_filter::from_config(config).check(candidate).is_ok()
+}
+
+/// Transaction settings buys are built with. The priority fee, static or dynamic,
+/// is capped by `max_priority_fee_fraction` of the buy amount.
+pub fn buy_transaction_config(config: &Config, legacy: bool) -> TransactionConfig {
+    let mut tx_config = TransactionConfig {
+        legacy_message: legacy,
+        slippage_model: config.slippage_model,
+        // Validated with the config; an unparsable key is skipped here
+        referral_account: config.pumpfun_referral_account.as_deref().and_then(|k| Pubkey::from_str(k).ok()),
+        dexes: config.dexes.clone(),
+        allowed_programs: config.allowed_program_ids(),
+        address_lookup_tables: config.address_lookup_table_keys(),
+        dynamic_priority_fee: config.dynamic_priority_fee,
+        priority_fee_percentile: config.priority_fee_percentile,
+        ..TransactionConfig::default()
+    };
+    cap_buy_priority_fees(&mut tx_config, config);
+    tx_config
+}
+
+/// Cap the static price and the dynamic estimate limit of `tx_config` to
+/// `max_priority_fee_fraction` of its `buy_amount_lamports`. Applied again
+/// whenever the amount changes, so a smaller buy pays a smaller fee.
+fn cap_buy_priority_fees(tx_config: &mut TransactionConfig, config: &Config) {
+    let (limit, amount, fraction) =
+        (tx_config.compute_unit_limit, tx_config.buy_amount_lamports, config.max_priority_fee_fraction);
+    tx_config.priority_fee_lamports =
+        cap_priority_fee(TransactionConfig::default().priority_fee_lamports, limit, amount, fraction);
+    tx_config.max_priority_fee_lamports = match (config.max_priority_fee_lamports, max_unit_price(limit, amount, fraction)) {
+        (0, fraction_cap) => fraction_cap.unwrap_or(0),
+        (max, fraction_cap) => fraction_cap.map_or(max, |cap| max.min(cap)),
+    };
+}
+
+/// Clamp a compute-unit price (micro-lamports per CU) so the total priority fee at
+/// `compute_unit_limit` is at most `max_fraction` of `buy_amount_lamports`
+/// (0 = uncapped). Paying more to land a buy than the buy is worth is never sensible.
+pub fn cap_priority_fee(unit_price: u64, compute_unit_limit: u32, buy_amount_lamports: u64, max_fraction: f64) -> u64 {
+    let Some(max_unit_price) = max_unit_price(compute_unit_limit, buy_amount_lamports, max_fraction) else {
+        return unit_price;
+    };
+    if unit_price > max_unit_price {
+        metrics().increment_counter("buy_priority_fee_capped_total");
+        debug!(unit_price, max_unit_price, "Priority fee capped relative to buy amount");
+    }
+    unit_price.min(max_unit_price)
+}
+
+/// Highest compute-unit price `cap_priority_fee` allows; `None` when uncapped.
+fn max_unit_price(compute_unit_limit: u32, buy_amount_lamports: u64, max_fraction: f64) -> Option<u64> {
+    if max_fraction <= 0.0 || compute_unit_limit == 0 {
+        return None;
+    }
+    let max_fee_lamports = fraction_of(buy_amount_lamports, max_fraction) as u128;
+    Some((max_fee_lamports * 1_000_000 / compute_unit_limit as u128).min(u64::MAX as u128) as u64)
+}
+
+/// Compare the tokens a landed buy of `mint` added to the wallet (`balance_after`
+/// less `balance_before`) with the quote's `min_out`. An underfill (e.g. the buy was
+/// sandwiched) is logged, counted and notified.
+pub async fn check_fill(
+    app_state: &Mutex<AppState>,
+    mint: &Pubkey,
+    balance_before: u64,
+    balance_after: u64,
+    min_out: u64,
+) -> FillCheck {
+    let received = balance_after.saturating_sub(balance_before);
+    if received >= min_out {
+        debug!(mint=%mint, received, min_out, "Buy filled");
+        return FillCheck::Filled { received, min_out };
+    }
+
+    metrics().increment_counter("buy_underfilled_total");
+    warn!(mint=%mint, received, min_out, "Buy received fewer tokens than its minimum output");
+    app_state.lock().await.notify(
+        NotificationLevel::Warn,
+        format!("Buy of {} underfilled: received {} tokens, expected at least {}", mint, received, min_out),
+    );
+    FillCheck::Underfilled { received, min_out }
+}
+
+/// Poll `rpc` every `poll` until `outcome` lands or `limit` passes. Check errors
+/// (unreachable endpoints, a landed-but-failed tx) count as not landed.
+async fn wait_for_landing(rpc: &dyn RpcBroadcaster, outcome: &SendOutcome, poll: Duration, limit: Duration) -> bool {
+    let deadline = Instant::now() + limit;
+    loop {
+        match rpc.is_landed(outcome).await {
+            Ok(true) => return true,
+            Ok(false) => {}
+            Err(e) => debug!(sig=%outcome.signature, error=%e, "Confirmation check failed"),
+        }
+        if Instant::now() + poll > deadline {
+            return false;
+        }
+        sleep(poll).await;
+    }
+}
+
+/// Transaction settings for a sell after `escalation` unconfirmed attempts: the
+/// priority fee grows by `sell_escalation_fee_multiplier` and slippage by
+/// `sell_escalation_slippage_bps` per escalation, on top of the DEX's own slippage.
+pub fn sell_transaction_config(config: &Config, escalation: u32) -> TransactionConfig {
+    let base = TransactionConfig::default();
+    let fee_factor = config.sell_escalation_fee_multiplier.max(1.0).powi(escalation as i32);
+    TransactionConfig {
+        slippage_model: config.slippage_model,
+        sell_dust_tokens: config.sell_dust_tokens,
+        dexes: config.dexes.clone(),
+        priority_fee_lamports: (base.priority_fee_lamports as f64 * fee_factor).min(u64::MAX as f64) as u64,
+        slippage_escalation_bps: config.sell_escalation_slippage_bps * escalation as u64,
+        ..base
+    }
+}
+
+/// Failures worth a fresh whole-buy attempt: transient RPC/network conditions, not
+/// stale candidates, build errors or insufficient funds.
+fn is_recoverable_buy_error(error: &anyhow::Error) -> bool {
+    if is_unsupported_version_error(error) || is_frozen_account_error(error) {
+        return false;
+    }
+    let text = format!("{:#}", error).to_lowercase();
+    const RECOVERABLE: [&str; 6] = [
+        "all sends failed",
+        "timeout",
+        "blockhash not found",
+        "rate limit",
+        "too many requests",
+        "no nonces acquired",
+    ];
+    RECOVERABLE.iter().any(|pattern| text.contains(pattern))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{
+        mock_outcome, offline_builder, rpc_result, rpc_with_mint, serve_block_engine, CountingBroadcaster,
+        UniqueSigBroadcaster,
+    };
+    use solana_sdk::signature::Signature;
+    use std::future::Future;
+    use std::pin::Pin;
+    use tokio::sync::mpsc;
+
+    #[derive(Debug)]
+    struct AlwaysOkBroadcaster;
+    impl RpcBroadcaster for AlwaysOkBroadcaster {
+        fn send_on_many_rpc<'a>(
+            &'a self,
+            _txs: Vec<VersionedTransaction>,
+            _correlation_id: Option<CorrelationId>,
+        ) -> Pin<Box<dyn Future<Output = Result<SendOutcome>> + Send + 'a>> {
+            Box::pin(async { Ok(mock_outcome(7)) })
+        }
+    }
+
+    #[tokio::test]
+    async fn buy_enters_passive_and_sell_returns_to_sniffing() {
+        let (tx, rx): (mpsc::Sender<PremintCandidate>, mpsc::Receiver<PremintCandidate>) =
+            mpsc::channel(8);
+
+        let app_state = Arc::new(Mutex::new(AppState::sniffing()));
+
+        let mut engine = BuyEngine::new(
+            Arc::new(AlwaysOkBroadcaster),
+            Arc::new(NonceManager::new(2)),
+            rx,
+            app_state.clone(),
+            Config {
+                nonce_count: 1,
+                ..Config::default()
+            },
+            None, // No transaction builder for tests
+        );
+
+        let mint = Pubkey::new_unique();
+        let candidate = PremintCandidate {
+            mint,
+            creator: Pubkey::new_unique(),
+            program: "pump.fun".to_string(),
+            slot: 0,
+            timestamp: 0, instruction_summary: None, is_jito_bundle: None, deadline_ms: None,
+        };
+        tx.send(candidate).await.unwrap();
+        drop(tx);
+
+        engine.run().await;
+
+        {
+            let st = app_state.lock().await;
+            match st.mode {
+                Mode::PassiveToken(_) => {}
+                _ => panic!("Expected PassiveToken mode after buy"),
+            }
+            assert_eq!(st.holdings_percent, 1.0);
+            // No price source: the position opens without a buy price, so exits stay unarmed
+            assert!(st.last_buy_price.is_none());
+            assert!(st.active_token.is_some());
+        }
+
+        engine.sell(mint, 1.0).await.expect("sell should succeed");
+        let st = app_state.lock().await;
+        assert!(st.is_sniffing());
+        assert!(st.active_token.is_none());
+        assert!(st.last_buy_price.is_none());
+    }
+
+    #[tokio::test]
+    async fn unconfirmed_buy_is_held_without_exits() {
+        use crate::tx_builder::{PriceSource, TransactionBuilderError};
+
+        // Far below the stop-loss, so only the unconfirmed buy keeps the exit from firing
+        struct CrashedPrice;
+        impl PriceSource for CrashedPrice {
+            fn current_price<'a>(
+                &'a self,
+                _mint: &'a Pubkey,
+            ) -> Pin<Box<dyn Future<Output = std::result::Result<f64, TransactionBuilderError>> + Send + 'a>> {
+                Box::pin(async { Ok(0.1) })
+            }
+        }
+
+        #[derive(Debug)]
+        struct NeverConfirmsBroadcaster {
+            sends: Arc<AtomicU32>,
+        }
+        impl RpcBroadcaster for NeverConfirmsBroadcaster {
+            fn send_on_many_rpc<'a>(
+                &'a self,
+                _txs: Vec<VersionedTransaction>,
+                _correlation_id: Option<CorrelationId>,
+            ) -> Pin<Box<dyn Future<Output = Result<SendOutcome>> + Send + 'a>> {
+                self.sends.fetch_add(1, Ordering::SeqCst);
+                Box::pin(async { Ok(mock_outcome(7)) })
+            }
+
+            fn confirm_signature<'a>(
+                &'a self,
+                _signature: &'a Signature,
+                _commitment: solana_sdk::commitment_config::CommitmentConfig,
+                _limit: Duration,
+            ) -> Pin<Box<dyn Future<Output = Result<ConfirmationStatus>> + Send + 'a>> {
+                Box::pin(async { Ok(ConfirmationStatus::TimedOut) })
+            }
+        }
+
+        let (tx, rx) = mpsc::channel(8);
+        let app_state = Arc::new(Mutex::new(AppState::sniffing()));
+        let sends = Arc::new(AtomicU32::new(0));
+        let mut engine = BuyEngine::new(
+            Arc::new(NeverConfirmsBroadcaster { sends: sends.clone() }),
+            Arc::new(NonceManager::new(1)),
+            rx,
+            app_state.clone(),
+            Config {
+                nonce_count: 1,
+                confirm_buys: true,
+                buy_confirm_timeout_ms: 50,
+                stop_loss_pct: 10.0,
+                ..Config::default()
+            },
+            None,
+        )
+        .with_price_source(Arc::new(CrashedPrice));
+
+        let candidate = PremintCandidate {
+            mint: Pubkey::new_unique(),
+            creator: Pubkey::new_unique(),
+            program: "pump.fun".to_string(),
+            slot: 0,
+            timestamp: 0, instruction_summary: None, is_jito_bundle: None, deadline_ms: None,
+        };
+        let mint = candidate.mint;
+        tx.send(candidate).await.unwrap();
+        drop(tx);
+
+        engine.run().await;
+
+        {
+            let mut st = app_state.lock().await;
+            // The buy may still land: it is tracked, not forgotten
+            let position = st.position(&mint).expect("unconfirmed buy is held");
+            assert_eq!(position.unconfirmed_signature, Some(mock_outcome(7).signature));
+            assert!(st.notifications.iter().any(|n| n.message.contains("not confirmed yet")));
+            st.positions.get_mut(&mint).unwrap().buy_price = Some(1.0);
+        }
+        // A buy that may still land is not resent
+        assert_eq!(sends.load(Ordering::SeqCst), 1);
+        assert!(engine.check_exit(mint).await.unwrap().is_none());
+
+        // Once it is seen to land, the stop-loss is armed
+        assert!(app_state.lock().await.confirm_position(&mint));
+        assert!(engine.check_exit(mint).await.unwrap().is_some());
+    }
+
+    #[tokio::test]
+    async fn live_buy_records_the_quoted_fill_price() {
+        use crate::tx_builder::{PriceSource, TransactionBuilderError};
+
+        struct FixedPrice;
+        impl PriceSource for FixedPrice {
+            fn current_price<'a>(
+                &'a self,
+                _mint: &'a Pubkey,
+            ) -> Pin<Box<dyn Future<Output = std::result::Result<f64, TransactionBuilderError>> + Send + 'a>> {
+                Box::pin(async { Ok(2.8e-5) })
+            }
+        }
+
+        let app_state = Arc::new(Mutex::new(AppState::sniffing()));
+        let (_tx, rx) = mpsc::channel(1);
+        let engine = BuyEngine::new(
+            Arc::new(AlwaysOkBroadcaster),
+            Arc::new(NonceManager::new(1)),
+            rx,
+            app_state.clone(),
+            Config { nonce_count: 1, take_profit_pct: 50.0, ..Config::default() },
+            None,
+        )
+        .with_price_source(Arc::new(FixedPrice));
+        let candidate = PremintCandidate {
+            mint: Pubkey::new_unique(),
+            creator: Pubkey::new_unique(),
+            program: "pump.fun".to_string(),
+            slot: 0,
+            timestamp: 0, instruction_summary: None, is_jito_bundle: None, deadline_ms: None,
+        };
+
+        engine.buy_now(&candidate).await.unwrap();
+
+        assert_eq!(app_state.lock().await.position(&candidate.mint).unwrap().buy_price, Some(2.8e-5));
+        // Priced at the fill, an unchanged price crosses no threshold
+        assert_eq!(engine.check_exit(candidate.mint).await.unwrap(), None);
+    }
+
+    #[tokio::test]
+    async fn operator_commands_buy_recently_seen_candidates() {
+        let app_state = Arc::new(Mutex::new(AppState::sniffing()));
+        let (tx, rx) = mpsc::channel(1);
+        let (command_tx, command_rx) = mpsc::channel(1);
+        let mut engine = BuyEngine::new(
+            Arc::new(AlwaysOkBroadcaster),
+            Arc::new(NonceManager::new(1)),
+            rx,
+            app_state.clone(),
+            Config { nonce_count: 1, ..Config::default() },
+            None,
+        )
+        .with_commands(command_rx);
+        let candidate = PremintCandidate {
+            mint: Pubkey::new_unique(),
+            creator: Pubkey::new_unique(),
+            program: "letsbonk".to_string(),
+            slot: 3,
+            timestamp: 0, instruction_summary: None, is_jito_bundle: None, deadline_ms: None,
+        };
+        tx.send(candidate.clone()).await.unwrap();
+        assert!(matches!(engine.next_input().await, Some(EngineInput::Candidate(c, None)) if c.mint == candidate.mint));
+
+        // A suggestion the engine never saw cannot be bought blind
+        let (reply, result) = oneshot::channel();
+        let unknown = Pubkey::new_unique();
+        command_tx.send(EngineCommand::Buy { mint: unknown, amount_lamports: 1_000, reply }).await.unwrap();
+        let Some(EngineInput::Command(command)) = engine.next_input().await else { panic!("expected a command") };
+        engine.handle_command(command).await;
+        assert!(matches!(result.await.unwrap(), Err(EngineError::UnknownCandidate(m)) if m == unknown));
+
+        let (reply, result) = oneshot::channel();
+        command_tx.send(EngineCommand::Buy { mint: candidate.mint, amount_lamports: 1_000, reply }).await.unwrap();
+        let Some(EngineInput::Command(command)) = engine.next_input().await else { panic!("expected a command") };
+        engine.handle_command(command).await;
+        result.await.unwrap().unwrap();
+        // Bought as the sniffed candidate, not a placeholder
+        assert_eq!(app_state.lock().await.position(&candidate.mint).unwrap().token, candidate);
+    }
+
+    #[tokio::test]
+    async fn engine_keeps_sniffing_until_max_concurrent_positions_are_open() {
+        let (tx, rx) = mpsc::channel::<PremintCandidate>(8);
+        let app_state = Arc::new(Mutex::new(AppState::sniffing()));
+        let mut engine = BuyEngine::new(
+            Arc::new(UniqueSigBroadcaster),
+            Arc::new(NonceManager::new(2)),
+            rx,
+            app_state.clone(),
+            Config { nonce_count: 2, max_concurrent_positions: 2, ..Config::default() },
+            None,
+        );
+
+        let mints = [Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique()];
+        for (slot, mint) in mints.iter().enumerate() {
+            tx.send(PremintCandidate {
+                mint: *mint,
+                creator: Pubkey::new_unique(),
+                program: "pump.fun".to_string(),
+                slot: slot as u64 + 1,
+                timestamp: 0, instruction_summary: None, is_jito_bundle: None, deadline_ms: None,
+            })
+            .await
+            .unwrap();
+        }
+        drop(tx);
+        engine.run().await;
+
+        // Two positions opened; the third candidate arrived at the limit
+        {
+            let st = app_state.lock().await;
+            assert_eq!(st.position_count(), 2);
+            assert!(st.position(&mints[2]).is_none());
+            assert!(!st.accepts_buys(2));
+            // The single-token view shows the most recent position
+            assert!(matches!(st.mode, Mode::PassiveToken(m) if m == mints[1]));
+            assert_eq!(st.active_token.as_ref().unwrap().mint, mints[1]);
+        }
+
+        // Sells target their own mint and leave the other position alone
+        engine.sell(mints[0], 0.5).await.expect("partial sell of the older position");
+        {
+            let st = app_state.lock().await;
+            assert_eq!(st.position(&mints[0]).unwrap().holdings_percent, 0.5);
+            assert_eq!(st.holdings_percent, 1.0);
+        }
+
+        // Closing the focused position shows the one left and reopens a slot
+        engine.sell(mints[1], 1.0).await.expect("closing sell of the newer position");
+        let st = app_state.lock().await;
+        assert_eq!(st.position_count(), 1);
+        assert!(matches!(st.mode, Mode::PassiveToken(m) if m == mints[0]));
+        assert_eq!(st.holdings_percent, 0.5);
+        assert!(st.accepts_buys(2));
+    }
+
+    #[tokio::test]
+    async fn repeated_partial_sells_leave_no_stranding_residue() {
+        let mint = Pubkey::new_unique();
+        let (_tx, rx) = mpsc::channel::<PremintCandidate>(1);
+        let app_state = Arc::new(Mutex::new(AppState::holding(mint, 1.0)));
+        let engine = BuyEngine::new(
+            Arc::new(UniqueSigBroadcaster),
+            Arc::new(NonceManager::new(1)),
+            rx,
+            app_state.clone(),
+            Config::default(),
+            None,
+        );
+
+        // 0.3^4 = 0.0081 is still a position; 0.3^5 ~ 0.00243 (plus f64 residue) is dust
+        let mut sells = 0;
+        while !app_state.lock().await.is_sniffing() {
+            assert!(sells < 10, "stranded with holdings {}", app_state.lock().await.holdings_percent);
+            engine.sell(mint, 0.7).await.expect("partial sell should succeed");
+            sells += 1;
+        }
+        assert_eq!(sells, 5);
+        let st = app_state.lock().await;
+        assert_eq!(st.holdings_percent, 0.0);
+        assert!(st.active_token.is_none());
+    }
+
+    #[tokio::test]
+    async fn closing_position_unwraps_wsol_when_enabled() {
+        use crate::tx_builder::TransactionBuilderError;
+
+        #[derive(Default)]
+        struct CountingUnwrapper {
+            calls: AtomicU32,
+        }
+        impl WsolUnwrapper for CountingUnwrapper {
+            fn unwrap_wsol<'a>(
+                &'a self,
+                _config: &'a TransactionConfig,
+            ) -> Pin<Box<dyn Future<Output = std::result::Result<Signature, TransactionBuilderError>> + Send + 'a>> {
+                self.calls.fetch_add(1, Ordering::SeqCst);
+                Box::pin(async { Ok(Signature::new_unique()) })
+            }
+        }
+        let holding = |mint: Pubkey| {
+            Arc::new(Mutex::new(AppState::holding(mint, 1.0)))
+        };
+        let engine_with = |app_state: Arc<Mutex<AppState>>, auto_unwrap_wsol: bool, unwrapper: Arc<CountingUnwrapper>| {
+            let (_tx, rx) = mpsc::channel::<PremintCandidate>(1);
+            BuyEngine::new(
+                Arc::new(UniqueSigBroadcaster),
+                Arc::new(NonceManager::new(1)),
+                rx,
+                app_state,
+                Config { auto_unwrap_wsol, ..Config::default() },
+                None,
+            )
+            .with_wsol_unwrapper(unwrapper)
+        };
+
+        // A partial sell keeps the position: nothing to unwrap yet
+        let unwrapper = Arc::new(CountingUnwrapper::default());
+        let mint = Pubkey::new_unique();
+        let app_state = holding(mint);
+        let engine = engine_with(app_state.clone(), true, unwrapper.clone());
+        engine.sell(mint, 0.5).await.expect("partial sell should succeed");
+        assert_eq!(unwrapper.calls.load(Ordering::SeqCst), 0);
+
+        engine.sell(mint, 1.0).await.expect("closing sell should succeed");
+        assert!(app_state.lock().await.is_sniffing());
+        assert_eq!(unwrapper.calls.load(Ordering::SeqCst), 1);
+
+        // Disabled: the position closes without unwrapping
+        let unwrapper = Arc::new(CountingUnwrapper::default());
+        let mint = Pubkey::new_unique();
+        let engine = engine_with(holding(mint), false, unwrapper.clone());
+        engine.sell(mint, 1.0).await.expect("closing sell should succeed");
+        assert_eq!(unwrapper.calls.load(Ordering::SeqCst), 0);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn take_profit_sells_the_position_and_returns_to_sniffing() {
+        use crate::tx_builder::{PriceSource, TransactionBuilderError};
+
+        /// +20% per check from 1.0
+        #[derive(Default)]
+        struct RampingPrice {
+            checks: AtomicU32,
+        }
+        impl PriceSource for RampingPrice {
+            fn current_price<'a>(
+                &'a self,
+                _mint: &'a Pubkey,
+            ) -> Pin<Box<dyn Future<Output = std::result::Result<f64, TransactionBuilderError>> + Send + 'a>> {
+                let step = self.checks.fetch_add(1, Ordering::SeqCst);
+                Box::pin(async move { Ok(1.0 + 0.2 * step as f64) })
+            }
+        }
+        assert_eq!(ExitTrigger::check(1.0, 1.49, 50.0, 20.0), None);
+        assert_eq!(ExitTrigger::check(1.0, 1.5, 50.0, 20.0), Some(ExitTrigger::TakeProfit));
+        assert_eq!(ExitTrigger::check(1.0, 0.8, 50.0, 20.0), Some(ExitTrigger::StopLoss));
+        assert_eq!(ExitTrigger::check(1.0, 0.1, 50.0, 0.0), None);
+
+        let mint = Pubkey::new_unique();
+        let app_state = Arc::new(Mutex::new(AppState::holding(mint, 1.0)));
+        let prices = Arc::new(RampingPrice::default());
+        let (_cand_tx, rx) = mpsc::channel::<PremintCandidate>(1);
+        let mut engine = BuyEngine::new(
+            Arc::new(UniqueSigBroadcaster),
+            Arc::new(NonceManager::new(1)),
+            rx,
+            app_state.clone(),
+            Config { take_profit_pct: 50.0, stop_loss_pct: 20.0, exit_check_interval_ms: 100, ..Config::default() },
+            None,
+        )
+        .with_price_source(prices.clone());
+
+        // A pending buy holds the monitor off
+        engine.pending_buy.store(true, Ordering::Relaxed);
+        assert_eq!(engine.check_exit(mint).await.unwrap(), None);
+        assert_eq!(prices.checks.load(Ordering::SeqCst), 0);
+        engine.pending_buy.store(false, Ordering::Relaxed);
+
+        let before = metrics().get_counter("exit_take_profit_total");
+        let task = tokio::spawn(async move { engine.run().await });
+        timeout(Duration::from_secs(30), async {
+            while !app_state.lock().await.is_sniffing() {
+                sleep(Duration::from_millis(50)).await;
+            }
+        })
+        .await
+        .expect("take-profit should close the position");
+        task.abort();
+
+        // 1.0, 1.2, 1.4 hold; 1.6 is +60%
+        assert_eq!(prices.checks.load(Ordering::SeqCst), 4);
+        assert_eq!(metrics().get_counter("exit_take_profit_total"), before + 1);
+        let st = app_state.lock().await;
+        assert_eq!(st.holdings_percent, 0.0);
+        assert!(st.last_buy_price.is_none());
+    }
+
+    #[tokio::test]
+    async fn sell_ladder_fires_each_rung_once_including_gap_ups() {
+        use crate::tx_builder::{PriceSource, TransactionBuilderError};
+        use std::sync::atomic::AtomicU64;
+
+        /// Price set by the test, stored as f64 bits
+        struct SetPrice(AtomicU64);
+        impl SetPrice {
+            fn set(&self, price: f64) {
+                self.0.store(price.to_bits(), Ordering::SeqCst);
+            }
+        }
+        impl PriceSource for SetPrice {
+            fn current_price<'a>(
+                &'a self,
+                _mint: &'a Pubkey,
+            ) -> Pin<Box<dyn Future<Output = std::result::Result<f64, TransactionBuilderError>> + Send + 'a>> {
+                let price = f64::from_bits(self.0.load(Ordering::SeqCst));
+                Box::pin(async move { Ok(price) })
+            }
+        }
+        // Rungs may not add up to more than the whole position
+        let overcommitted = Config { sell_ladder: vec![(1.5, 0.5), (2.0, 0.6)], ..Config::default() };
+        assert!(overcommitted.validate().unwrap_err().contains("sell_ladder"));
+        let ladder = vec![(1.5, 0.25), (2.0, 0.25), (3.0, 0.5)];
+        assert!(Config { sell_ladder: ladder.clone(), ..Config::default() }.validate().is_ok());
+
+        let mint = Pubkey::new_unique();
+        let app_state = Arc::new(Mutex::new(AppState::holding(mint, 2.0)));
+        let price = Arc::new(SetPrice(AtomicU64::new(0)));
+        let (_tx, rx) = mpsc::channel::<PremintCandidate>(1);
+        let engine = BuyEngine::new(
+            Arc::new(UniqueSigBroadcaster),
+            Arc::new(NonceManager::new(1)),
+            rx,
+            app_state.clone(),
+            Config { sell_ladder: ladder, ..Config::default() },
+            None,
+        )
+        .with_price_source(price.clone());
+        let holdings = || {
+            let app_state = app_state.clone();
+            async move { app_state.lock().await.holdings_percent }
+        };
+
+        price.set(2.8); // 1.4x: below the first rung
+        assert_eq!(engine.check_exit(mint).await.unwrap(), None);
+        assert_eq!(holdings().await, 1.0);
+
+        price.set(3.2); // 1.6x: first rung
+        assert_eq!(engine.check_exit(mint).await.unwrap(), Some(ExitTrigger::Ladder { rungs: 1 }));
+        assert!((holdings().await - 0.75).abs() < 1e-9);
+
+        // A rung fires once, even when the price dips and comes back
+        assert_eq!(engine.check_exit(mint).await.unwrap(), None);
+        price.set(2.2);
+        assert_eq!(engine.check_exit(mint).await.unwrap(), None);
+        price.set(3.4);
+        assert_eq!(engine.check_exit(mint).await.unwrap(), None);
+        assert!((holdings().await - 0.75).abs() < 1e-9);
+
+        // Gap-up past 2x and 3x: both remaining rungs in one sell, closing the position
+        price.set(6.5);
+        assert_eq!(engine.check_exit(mint).await.unwrap(), Some(ExitTrigger::Ladder { rungs: 2 }));
+        let st = app_state.lock().await;
+        assert!(st.is_sniffing());
+        assert_eq!(st.holdings_percent, 0.0);
+    }
+
+    #[tokio::test]
+    async fn duplicate_sell_signature_does_not_double_reduce_holdings() {
+        #[derive(Debug)]
+        struct SameSigBroadcaster;
+        impl RpcBroadcaster for SameSigBroadcaster {
+            fn send_on_many_rpc<'a>(
+                &'a self,
+                _txs: Vec<VersionedTransaction>,
+                _correlation_id: Option<CorrelationId>,
+            ) -> Pin<Box<dyn Future<Output = Result<SendOutcome>> + Send + 'a>> {
+                Box::pin(async { Ok(mock_outcome(0x5e)) })
+            }
+        }
+
+        let (_tx, rx): (mpsc::Sender<PremintCandidate>, mpsc::Receiver<PremintCandidate>) =
+            mpsc::channel(8);
+        let mint = Pubkey::new_unique();
+        let app_state = Arc::new(Mutex::new(AppState::holding(mint, 1.0)));
+
+        let engine = BuyEngine::new(
+            Arc::new(SameSigBroadcaster),
+            Arc::new(NonceManager::new(1)),
+            rx,
+            app_state.clone(),
+            Config::default(),
+            None,
+        );
+
+        engine.sell(mint, 0.5).await.expect("first sell should succeed");
+        assert_eq!(app_state.lock().await.holdings_percent, 0.5);
+
+        // Same signature again: idempotent, holdings stay put
+        engine.sell(mint, 0.5).await.expect("duplicate sell is a no-op");
+        assert_eq!(app_state.lock().await.holdings_percent, 0.5);
+    }
+
+    #[tokio::test]
+    async fn rejected_candidate_trace_records_stage_and_reason() {
+        let (_tx, rx) = mpsc::channel::<PremintCandidate>(1);
+        let app_state = Arc::new(Mutex::new(AppState::sniffing()));
+        let mut engine = BuyEngine::new(
+            Arc::new(AlwaysOkBroadcaster),
+            Arc::new(NonceManager::new(1)),
+            rx,
+            app_state,
+            Config::default(),
+            None,
+        );
+
+        let candidate = PremintCandidate {
+            mint: Pubkey::new_unique(),
+            creator: Pubkey::new_unique(),
+            program: "orca".to_string(),
+            slot: 0,
+            timestamp: 0, instruction_summary: None, is_jito_bundle: None, deadline_ms: None,
+        };
+        let mut trace = DecisionTrace::new(&candidate);
+        assert!(engine.admit_candidate(&candidate, None, &mut trace).await.is_none());
+
+        assert_eq!(trace.rejected_at.as_deref(), Some("filter"));
+        assert_eq!(trace.action.as_deref(), Some("skip"));
+        assert_eq!(trace.reason.as_deref(), Some("Program 'orca' is not a buy target"));
+        let stages: Vec<_> = trace.steps.iter().map(|s| (s.stage.as_str(), s.passed)).collect();
+        assert_eq!(stages, vec![("security", true), ("rate_limit", true), ("held", true), ("filter", false)]);
+
+        // A buy target passes every check and holds an in-flight permit
+        let target = PremintCandidate { program: "pump.fun".to_string(), mint: Pubkey::new_unique(), ..candidate };
+        let mut trace = DecisionTrace::new(&target);
+        assert!(engine.admit_candidate(&target, None, &mut trace).await.is_some());
+        assert!(trace.rejected_at.is_none());
+        assert!(trace.steps.iter().all(|s| s.passed));
+    }
+
+    #[tokio::test]
+    async fn frozen_token_account_sell_fails_with_specific_error() {
+        #[derive(Debug, Default)]
+        struct FrozenAccountBroadcaster {
+            sends: AtomicU32,
+        }
+        impl RpcBroadcaster for FrozenAccountBroadcaster {
+            fn send_on_many_rpc<'a>(
+                &'a self,
+                _txs: Vec<VersionedTransaction>,
+                _correlation_id: Option<CorrelationId>,
+            ) -> Pin<Box<dyn Future<Output = Result<SendOutcome>> + Send + 'a>> {
+                self.sends.fetch_add(1, Ordering::SeqCst);
+                Box::pin(async { Err(anyhow!("RpcManager: all sends failed: token account is frozen (2 endpoints)")) })
+            }
+        }
+
+        let (_tx, rx) = mpsc::channel::<PremintCandidate>(1);
+        let mint = Pubkey::new_unique();
+        let app_state = Arc::new(Mutex::new(AppState::holding(mint, 1.0)));
+        let broadcaster = Arc::new(FrozenAccountBroadcaster::default());
+        let engine = BuyEngine::new(
+            broadcaster.clone(),
+            Arc::new(NonceManager::new(1)),
+            rx,
+            app_state.clone(),
+            Config { sell_confirm_timeout_ms: 10, sell_confirm_poll_ms: 1, ..Config::default() },
+            None,
+        );
+
+        let frozen_before = metrics().get_counter("sell_frozen_account_total");
+        let EngineError::SellFailed { reason, error: err } = engine.sell(mint, 1.0).await.unwrap_err() else {
+            panic!("frozen account should surface as a classified sell failure");
+        };
+        assert_eq!(reason, SellFailureReason::AccountFrozen);
+        assert!(is_frozen_account_error(&err));
+        assert_eq!(
+            user_message(&err),
+            "Token account is frozen by the mint's freeze authority; it cannot be sold until thawed"
+        );
+        assert!(!is_recoverable_buy_error(&err));
+        // Not escalated or resent; the position stays held
+        assert_eq!(broadcaster.sends.load(Ordering::SeqCst), 1);
+        assert!(metrics().get_counter("sell_frozen_account_total") > frozen_before);
+        let st = app_state.lock().await;
+        assert!(matches!(st.mode, Mode::PassiveToken(m) if m == mint));
+        assert_eq!(st.holdings_percent, 1.0);
+        assert!(st.notifications.iter().any(|n| n.level == NotificationLevel::Error));
+    }
+
+    #[test]
+    fn sell_errors_map_to_reasons() {
+        use solana_client::client_error::{ClientError, ClientErrorKind};
+        use solana_client::rpc_request::{RpcError, RpcResponseErrorData};
+
+        let rpc_error = |message: &str| {
+            anyhow::Error::new(ClientError::from(ClientErrorKind::RpcError(RpcError::RpcResponseError {
+                code: -32002,
+                message: message.to_string(),
+                data: RpcResponseErrorData::Empty,
+            })))
+        };
+        let cases = [
+            (anyhow!("Program log: Error: TooLittleSolReceived"), SellFailureReason::Slippage),
+            (anyhow!("Program log: BondingCurveComplete"), SellFailureReason::NoLiquidity),
+            (anyhow!("Program log: Error: account is frozen"), SellFailureReason::AccountFrozen),
+            (anyhow!("Transaction simulation failed: insufficient funds for fee"), SellFailureReason::InsufficientFunds),
+            (anyhow!("sell 1111 not confirmed after 2 escalations"), SellFailureReason::Unconfirmed),
+            (anyhow!("Transaction build failed: quote unavailable"), SellFailureReason::BuildFailed),
+            (rpc_error("Blockhash not found"), SellFailureReason::Rpc(RpcErrorType::BlockhashNotFound)),
+            (rpc_error("Too many requests"), SellFailureReason::Rpc(RpcErrorType::RateLimited)),
+            (anyhow!("something unexpected"), SellFailureReason::Other),
+        ];
+        for (error, expected) in cases {
+            assert_eq!(SellFailureReason::classify(&error), expected, "{:#}", error);
+        }
+
+        let err = anyhow!("RpcManager: all sends failed (fatal_errors: 0)");
+        assert_eq!(SellFailureReason::classify(&err).counter_name(), "sell_failures_rpc_total");
+    }
+
+    #[tokio::test]
+    async fn already_processed_sell_is_a_successful_exit() {
+        /// The sell landed via an earlier send; this one is answered "already processed".
+        #[derive(Debug, Default)]
+        struct AlreadyProcessedBroadcaster {
+            sends: AtomicU32,
+            landed_checks: AtomicU32,
+        }
+        impl RpcBroadcaster for AlreadyProcessedBroadcaster {
+            fn send_on_many_rpc<'a>(
+                &'a self,
+                _txs: Vec<VersionedTransaction>,
+                _correlation_id: Option<CorrelationId>,
+            ) -> Pin<Box<dyn Future<Output = Result<SendOutcome>> + Send + 'a>> {
+                let n = self.sends.fetch_add(1, Ordering::SeqCst) as u8;
+                Box::pin(async move { Ok(SendOutcome { was_duplicate: true, ..mock_outcome(0x3a + n) }) })
+            }
+
+            fn is_landed<'a>(
+                &'a self,
+                _outcome: &'a SendOutcome,
+            ) -> Pin<Box<dyn Future<Output = Result<bool>> + Send + 'a>> {
+                self.landed_checks.fetch_add(1, Ordering::SeqCst);
+                Box::pin(async { Ok(false) })
+            }
+        }
+
+        let held = |mint| {
+            Arc::new(Mutex::new(AppState::holding(mint, 1.0)))
+        };
+        let config = Config { sell_confirm_timeout_ms: 50, sell_confirm_poll_ms: 1, ..Config::default() };
+
+        let (_tx, rx) = mpsc::channel::<PremintCandidate>(1);
+        let mint = Pubkey::new_unique();
+        let app_state = held(mint);
+        let broadcaster = Arc::new(AlreadyProcessedBroadcaster::default());
+        let engine = BuyEngine::new(
+            broadcaster.clone(),
+            Arc::new(NonceManager::new(1)),
+            rx,
+            app_state.clone(),
+            config.clone(),
+            None,
+        );
+
+        let before = metrics().get_counter("sell_already_processed_total");
+        engine.sell(mint, 0.5).await.expect("already processed sell is a success");
+        // Sent once, not polled or escalated, holdings reduced once
+        assert_eq!(broadcaster.sends.load(Ordering::SeqCst), 1);
+        assert_eq!(broadcaster.landed_checks.load(Ordering::SeqCst), 0);
+        assert!(metrics().get_counter("sell_already_processed_total") > before);
+        assert_eq!(app_state.lock().await.holdings_percent, 0.5);
+
+        // With the strategy off the sell fails and the position is untouched
+        let (_tx, rx) = mpsc::channel::<PremintCandidate>(1);
+        let app_state = held(mint);
+        let engine = BuyEngine::new(
+            Arc::new(AlreadyProcessedBroadcaster::default()),
+            Arc::new(NonceManager::new(1)),
+            rx,
+            app_state.clone(),
+            Config { sell_already_processed_as_success: false, ..config },
+            None,
+        );
+        assert!(engine.sell(mint, 1.0).await.is_err());
+        assert_eq!(app_state.lock().await.holdings_percent, 1.0);
+    }
+
+    #[tokio::test]
+    async fn underfilled_buy_triggers_alert_and_exit() {
+        #[derive(Debug)]
+        struct ExitBroadcaster;
+        impl RpcBroadcaster for ExitBroadcaster {
+            fn send_on_many_rpc<'a>(
+                &'a self,
+                _txs: Vec<VersionedTransaction>,
+                _correlation_id: Option<CorrelationId>,
+            ) -> Pin<Box<dyn Future<Output = Result<SendOutcome>> + Send + 'a>> {
+                Box::pin(async { Ok(mock_outcome(0x6e)) })
+            }
+        }
+        /// The wallet holds 1_000 tokens after the buy, 600 of them from before it.
+        struct SandwichedBalance;
+        impl TokenBalances for SandwichedBalance {
+            fn token_balance<'a>(&'a self, _mint: &'a Pubkey) -> Pin<Box<dyn Future<Output = Result<u64>> + Send + 'a>> {
+                Box::pin(async { Ok(1_000) })
+            }
+        }
+
+        let mint = Pubkey::new_unique();
+        let app_state = Arc::new(Mutex::new(AppState::holding(mint, 1.0)));
+        let (_tx, rx) = mpsc::channel::<PremintCandidate>(1);
+        let engine = BuyEngine::new(
+            Arc::new(ExitBroadcaster),
+            Arc::new(NonceManager::new(1)),
+            rx,
+            app_state.clone(),
+            Config { post_buy_exit_on_underfill: true, sell_confirm_timeout_ms: 0, ..Config::default() },
+            None,
+        )
+        .with_token_balances(Arc::new(SandwichedBalance));
+        let ctx = PipelineContext::new("test");
+        let fill_checks = || engine.fill_checks.lock().unwrap().len();
+
+        // Only what the buy added counts, not tokens held before it
+        assert_eq!(
+            check_fill(&app_state, &mint, 0, 1_000, 1_000).await,
+            FillCheck::Filled { received: 1_000, min_out: 1_000 }
+        );
+        let before = metrics().get_counter("buy_underfilled_total");
+        assert_eq!(
+            check_fill(&app_state, &mint, 600, 1_000, 1_000).await,
+            FillCheck::Underfilled { received: 400, min_out: 1_000 }
+        );
+        assert!(metrics().get_counter("buy_underfilled_total") > before);
+        assert!(app_state.lock().await.notifications.iter().any(|n| n.level == NotificationLevel::Warn && n.message.contains("underfilled")));
+
+        // Nothing to compare against, or not landed yet: no check
+        let buy = SentBuy { min_out: Some(1_000), balance_before: Some(600), ..SentBuy::landed(mock_outcome(0x6d)) };
+        engine.spawn_fill_check(mint, &SentBuy { min_out: None, ..buy.clone() }, &ctx);
+        engine.spawn_fill_check(mint, &SentBuy { balance_before: None, ..buy.clone() }, &ctx);
+        engine.spawn_fill_check(mint, &SentBuy { unconfirmed: true, ..buy.clone() }, &ctx);
+        assert_eq!(fill_checks(), 0);
+
+        // The check runs in the background; the engine exits once it sees the result
+        engine.spawn_fill_check(mint, &buy, &ctx);
+        assert_eq!(fill_checks(), 1);
+        tokio::time::timeout(Duration::from_secs(5), async {
+            while fill_checks() > 0 {
+                engine.poll_fill_checks().await;
+                sleep(Duration::from_millis(5)).await;
+            }
+        })
+        .await
+        .expect("fill check finishes");
+        let st = app_state.lock().await;
+        assert!(st.is_sniffing());
+        assert_eq!(st.holdings_percent, 0.0);
+    }
+
+    #[tokio::test]
+    async fn pre_buy_balance_is_read_alongside_the_send_and_kept_on_the_position() {
+        type Events = Arc<std::sync::Mutex<Vec<&'static str>>>;
+        #[derive(Debug)]
+        struct LoggingBroadcaster(Events);
+        impl RpcBroadcaster for LoggingBroadcaster {
+            fn send_on_many_rpc<'a>(
+                &'a self,
+                _txs: Vec<VersionedTransaction>,
+                _correlation_id: Option<CorrelationId>,
+            ) -> Pin<Box<dyn Future<Output = Result<SendOutcome>> + Send + 'a>> {
+                self.0.lock().unwrap().push("send");
+                Box::pin(async { Ok(mock_outcome(0x5a)) })
+            }
+        }
+        /// 600 tokens held from before; the answer lags behind the send.
+        struct SlowBalance(Events);
+        impl TokenBalances for SlowBalance {
+            fn token_balance<'a>(&'a self, _mint: &'a Pubkey) -> Pin<Box<dyn Future<Output = Result<u64>> + Send + 'a>> {
+                Box::pin(async {
+                    self.0.lock().unwrap().push("balance_requested");
+                    sleep(Duration::from_millis(20)).await;
+                    self.0.lock().unwrap().push("balance");
+                    Ok(600)
+                })
+            }
+        }
+
+        let events = Events::default();
+        let app_state = Arc::new(Mutex::new(AppState::sniffing()));
+        let (_tx, rx) = mpsc::channel::<PremintCandidate>(1);
+        let engine = BuyEngine::new(
+            Arc::new(LoggingBroadcaster(events.clone())),
+            Arc::new(NonceManager::new(1)),
+            rx,
+            app_state.clone(),
+            Config { nonce_count: 1, ..Config::default() },
+            None,
+        )
+        .with_token_balances(Arc::new(SlowBalance(events.clone())));
+        let mint = Pubkey::new_unique();
+        let candidate = PremintCandidate {
+            mint,
+            creator: Pubkey::new_unique(),
+            program: "pump.fun".to_string(),
+            slot: 0,
+            timestamp: 0, instruction_summary: None, is_jito_bundle: None, deadline_ms: None,
+        };
+
+        engine.buy_now(&candidate).await.unwrap();
+
+        // Requested first, but the send does not wait for the answer
+        assert_eq!(events.lock().unwrap()[..3], ["balance_requested", "send", "balance"]);
+        assert_eq!(app_state.lock().await.position(&mint).unwrap().balance_before_buy, Some(600));
+    }
+
+    #[test]
+    fn estimated_priority_fee_is_capped_to_fraction_of_buy() {
+        // 0.05 SOL buy, 200k CU, fees capped at 10% = 5_000_000 lamports total
+        let buy = 50_000_000;
+        let cap = cap_priority_fee(u64::MAX / 2, 200_000, buy, 0.1);
+        assert_eq!(cap, 25_000_000);
+        assert_eq!(cap as u128 * 200_000 / 1_000_000, 5_000_000);
+
+        // A fee under the cap and an uncapped config are left alone
+        assert_eq!(cap_priority_fee(10_000, 200_000, buy, 0.1), 10_000);
+        assert_eq!(cap_priority_fee(u64::MAX / 2, 200_000, buy, 0.0), u64::MAX / 2);
+
+        let config = Config { max_priority_fee_fraction: 0.000_001, ..Config::default() };
+        let tx_config = buy_transaction_config(&config, false);
+        let fee_lamports = tx_config.priority_fee_lamports as u128 * tx_config.compute_unit_limit as u128 / 1_000_000;
+        assert!(fee_lamports <= (tx_config.buy_amount_lamports as f64 * 0.000_001) as u128);
+        assert!(tx_config.priority_fee_lamports < TransactionConfig::default().priority_fee_lamports);
+    }
+
+    #[tokio::test]
+    async fn downsized_buy_caps_the_priority_fee_to_its_own_amount() {
+        let engine = |config: Config| {
+            let (_tx, rx) = mpsc::channel(1);
+            let app_state = Arc::new(Mutex::new(AppState::sniffing()));
+            BuyEngine::new(Arc::new(AlwaysOkBroadcaster), Arc::new(NonceManager::new(1)), rx, app_state, config, None)
+        };
+        let fee_lamports = |unit_price: u64, tx: &TransactionConfig| unit_price as u128 * tx.compute_unit_limit as u128 / 1_000_000;
+        let within_cap = |tx: &TransactionConfig| {
+            let cap = fraction_of(tx.buy_amount_lamports, 0.000_1) as u128;
+            fee_lamports(tx.priority_fee_lamports, tx) <= cap && fee_lamports(tx.max_priority_fee_lamports, tx) <= cap
+        };
+        let config = Config { max_priority_fee_fraction: 0.000_1, ..Config::default() };
+        let full = buy_transaction_config(&config, false);
+
+        // Downsized to keep the SOL reserve
+        let balance = Arc::new(SolBalance::new());
+        balance.set(56_000_000);
+        let reserved = engine(Config { min_sol_reserve_lamports: 50_000_000, ..config.clone() }).with_sol_balance(balance);
+        let downsized = reserved.reserved_buy_config(false, None).unwrap();
+        assert!(downsized.buy_amount_lamports < full.buy_amount_lamports);
+        assert!(within_cap(&downsized), "{:?}", downsized);
+        assert!(downsized.priority_fee_lamports < full.priority_fee_lamports);
+
+        // A small operator-sized buy
+        let small = engine(config).reserved_buy_config(false, Some(1_000_000)).unwrap();
+        assert_eq!(small.buy_amount_lamports, 1_000_000);
+        assert!(within_cap(&small), "{:?}", small);
+        assert!(small.priority_fee_lamports < full.priority_fee_lamports);
+    }
+
+    #[tokio::test]
+    async fn unconfirmed_sell_is_escalated_and_resent() {
+        /// Every send gets a fresh signature; only the second one ever lands.
+        #[derive(Debug, Default)]
+        struct SlowFirstSellBroadcaster {
+            sends: AtomicU32,
+        }
+        impl RpcBroadcaster for SlowFirstSellBroadcaster {
+            fn send_on_many_rpc<'a>(
+                &'a self,
+                _txs: Vec<VersionedTransaction>,
+                _correlation_id: Option<CorrelationId>,
+            ) -> Pin<Box<dyn Future<Output = Result<SendOutcome>> + Send + 'a>> {
+                Box::pin(async move {
+                    let n = self.sends.fetch_add(1, Ordering::SeqCst) as u8;
+                    Ok(mock_outcome(0x2c + n))
+                })
+            }
+
+            fn is_landed<'a>(
+                &'a self,
+                outcome: &'a SendOutcome,
+            ) -> Pin<Box<dyn Future<Output = Result<bool>> + Send + 'a>> {
+                Box::pin(async move { Ok(outcome.signature == mock_outcome(0x2d).signature) })
+            }
+        }
+
+        let (_tx, rx) = mpsc::channel::<PremintCandidate>(1);
+        let mint = Pubkey::new_unique();
+        let app_state = Arc::new(Mutex::new(AppState::holding(mint, 1.0)));
+        let config = Config {
+            sell_confirm_timeout_ms: 30,
+            sell_confirm_poll_ms: 5,
+            sell_max_escalations: 2,
+            ..Config::default()
+        };
+        let broadcaster = Arc::new(SlowFirstSellBroadcaster::default());
+        let engine = BuyEngine::new(
+            broadcaster.clone(),
+            Arc::new(NonceManager::new(1)),
+            rx,
+            app_state.clone(),
+            config.clone(),
+            None,
+        );
+
+        let escalations_before = metrics().get_counter("sell_escalations_total");
+        engine.sell(mint, 1.0).await.expect("escalated resend confirms");
+        assert_eq!(broadcaster.sends.load(Ordering::SeqCst), 2);
+        assert!(metrics().get_counter("sell_escalations_total") > escalations_before);
+        assert!(app_state.lock().await.is_sniffing());
+
+        // The resend pays more and tolerates more slippage
+        let first = sell_transaction_config(&config, 0);
+        let resend = sell_transaction_config(&config, 1);
+        assert_eq!(resend.priority_fee_lamports, first.priority_fee_lamports * 2);
+        let dex = DexProgram::PumpFun;
+        assert_eq!(
+            resend.for_dex(&dex).unwrap().slippage_bps,
+            first.for_dex(&dex).unwrap().slippage_bps + config.sell_escalation_slippage_bps
+        );
+    }
+
+    #[tokio::test]
+    async fn buy_sell_cycle_writes_ordered_audit_entries() {
+        #[derive(Debug)]
+        struct AuditBroadcaster;
+        impl RpcBroadcaster for AuditBroadcaster {
+            fn send_on_many_rpc<'a>(
+                &'a self,
+                _txs: Vec<VersionedTransaction>,
+                _correlation_id: Option<CorrelationId>,
+            ) -> Pin<Box<dyn Future<Output = Result<SendOutcome>> + Send + 'a>> {
+                Box::pin(async { Ok(mock_outcome(0xa7)) })
+            }
+        }
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("audit.jsonl");
+
+        let (tx, rx): (mpsc::Sender<PremintCandidate>, mpsc::Receiver<PremintCandidate>) =
+            mpsc::channel(8);
+        let app_state = Arc::new(Mutex::new(AppState::sniffing()));
+        let mut engine = BuyEngine::new(
+            Arc::new(AuditBroadcaster),
+            Arc::new(NonceManager::new(2)),
+            rx,
+            app_state.clone(),
+            Config { nonce_count: 1, ..Config::default() },
+            None,
+        )
+        .with_audit_log(Arc::new(AuditLog::open(&path).unwrap()));
+        engine.funnel = Arc::new(CandidateFunnel::new());
+
+        let mint = Pubkey::new_unique();
+        tx.send(PremintCandidate {
+            mint,
+            creator: Pubkey::new_unique(),
+            program: "pump.fun".to_string(),
+            slot: 0,
+            timestamp: 0, instruction_summary: None, is_jito_bundle: None, deadline_ms: None,
+        })
+        .await
+        .unwrap();
+        drop(tx);
+        engine.run().await;
+        engine.sell(mint, 1.0).await.expect("sell should succeed");
+
+        let events: Vec<AuditEvent> = AuditLog::read_entries(&path)
+            .unwrap()
+            .into_iter()
+            .map(|entry| entry.event)
+            .collect();
+        let sig = Signature::from([0xa7u8; 64]).to_string();
+        let held = format!("passive_token:{}", mint);
+        assert_eq!(
+            events,
+            vec![
+                AuditEvent::Command { command: "buy".into(), mint: mint.to_string(), percent: None },
+                AuditEvent::Broadcast { kind: "buy".into(), mint: mint.to_string(), signature: sig.clone(), endpoint: "mock".into() },
+                AuditEvent::ModeTransition { from: "sniffing".into(), to: held.clone() },
+                AuditEvent::Command { command: "sell".into(), mint: mint.to_string(), percent: Some(1.0) },
+                AuditEvent::Broadcast { kind: "sell".into(), mint: mint.to_string(), signature: sig, endpoint: "mock".into() },
+                AuditEvent::ModeTransition { from: held, to: "sniffing".into() },
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn candidate_funnel_counts_each_stage() {
+        use std::sync::atomic::AtomicUsize;
+
+        #[derive(Debug, Default)]
+        struct FailOnceBroadcaster {
+            calls: AtomicUsize,
+        }
+        impl RpcBroadcaster for FailOnceBroadcaster {
+            fn send_on_many_rpc<'a>(
+                &'a self,
+                _txs: Vec<VersionedTransaction>,
+                _correlation_id: Option<CorrelationId>,
+            ) -> Pin<Box<dyn Future<Output = Result<SendOutcome>> + Send + 'a>> {
+                Box::pin(async move {
+                    if self.calls.fetch_add(1, Ordering::SeqCst) == 0 {
+                        Err(anyhow!("transient broadcast failure"))
+                    } else {
+                        Ok(mock_outcome(3))
+                    }
+                })
+            }
+        }
+
+        let (tx, rx): (mpsc::Sender<PremintCandidate>, mpsc::Receiver<PremintCandidate>) =
+            mpsc::channel(8);
+        let app_state = Arc::new(Mutex::new(AppState::sniffing()));
+        let mut engine = BuyEngine::new(
+            Arc::new(FailOnceBroadcaster::default()),
+            Arc::new(NonceManager::new(2)),
+            rx,
+            app_state.clone(),
+            Config {
+                nonce_count: 1,
+                ..Config::default()
+            },
+            None,
+        );
+        engine.funnel = Arc::new(CandidateFunnel::new());
+
+        let mk = |mint: Pubkey, program: &str| PremintCandidate {
+            mint,
+            creator: Pubkey::new_unique(),
+            program: program.to_string(),
+            slot: 0,
+            timestamp: 0, instruction_summary: None, is_jito_bundle: None, deadline_ms: None,
+        };
+        // Rejected by security (default mint)
+        tx.send(mk(Pubkey::default(), "pump.fun")).await.unwrap();
+        // Rejected by the program filter
+        tx.send(mk(Pubkey::new_unique(), "raydium")).await.unwrap();
+        // Attempted but broadcast fails
+        tx.send(mk(Pubkey::new_unique(), "pump.fun")).await.unwrap();
+        // Attempted and succeeds
+        tx.send(mk(Pubkey::new_unique(), "pump.fun")).await.unwrap();
+        drop(tx);
+
+        engine.run().await;
+
+        assert_eq!(engine.funnel.count(FunnelStage::Seen), 4);
+        assert_eq!(engine.funnel.count(FunnelStage::SecurityPassed), 3);
+        assert_eq!(engine.funnel.count(FunnelStage::FilterPassed), 2);
+        assert_eq!(engine.funnel.count(FunnelStage::Scored), 2);
+        assert_eq!(engine.funnel.count(FunnelStage::Attempted), 2);
+        assert_eq!(engine.funnel.count(FunnelStage::Succeeded), 1);
+
+        let snapshot = engine.funnel.snapshot();
+        assert_eq!(snapshot[5].conversion_from_previous, 0.5);
+        assert_eq!(snapshot[5].conversion_from_seen, 0.25);
+        // The failed buy backed off the next one; the landed buy cleared it
+        assert_eq!(engine.backoff_state.get_failure_count(), 0);
+    }
+
+    #[tokio::test]
+    async fn simulated_candidates_go_through_the_buy_checks_without_side_effects() {
+        use crate::simulation::simulation_candidate;
+
+        let app_state = Arc::new(Mutex::new(AppState::sniffing()));
+        let (_tx, rx) = mpsc::channel(1);
+        let mut engine = BuyEngine::new(
+            Arc::new(CountingBroadcaster::new(0)),
+            Arc::new(NonceManager::new(1)),
+            rx,
+            app_state.clone(),
+            Config { max_concurrent_positions: 1, ..Config::default() },
+            None,
+        );
+        engine.funnel = Arc::new(CandidateFunnel::new());
+        let mint = Pubkey::new_unique();
+
+        let candidate = simulation_candidate(mint, Some(Pubkey::new_unique()), "pump.fun");
+        let sim = engine.simulate_candidate(&candidate).await;
+        assert!(sim.would_buy, "{:?}", sim);
+        assert_eq!(sim.mint, mint.to_string());
+        let params = sim.params.unwrap();
+        assert_eq!(params.buy_amount_lamports, buy_transaction_config(&engine.config, false).buy_amount_lamports);
+        assert!(params.slippage_bps > 0);
+        // Nothing was counted or recorded against the mint
+        assert_eq!(engine.funnel.count(FunnelStage::SecurityPassed), 0);
+        assert!(!validator().mint_rate_limited(&mint, 60, 1));
+
+        // Unknown creator fails security validation
+        let sim = engine.simulate_candidate(&simulation_candidate(mint, None, "pump.fun")).await;
+        assert_eq!(sim.rejected_at.as_deref(), Some("security"));
+        assert!(sim.params.is_none());
+
+        // Non-target program is filtered; a held position blocks everything
+        let other = simulation_candidate(mint, Some(Pubkey::new_unique()), "orca");
+        assert_eq!(engine.simulate_candidate(&other).await.rejected_at.as_deref(), Some("filter"));
+        *app_state.lock().await = AppState::holding(Pubkey::new_unique(), 1.0);
+        assert_eq!(engine.simulate_candidate(&candidate).await.rejected_at.as_deref(), Some("mode"));
+    }
+
+    #[tokio::test]
+    async fn program_filter_accepts_ids_and_aliases() {
+        let (_tx, rx) = mpsc::channel::<PremintCandidate>(1);
+        let app_state = Arc::new(Mutex::new(AppState::sniffing()));
+        let mut config = Config::default();
+        config
+            .program_aliases
+            .insert("pump-amm".to_string(), "pump.fun".to_string());
+        let engine = BuyEngine::new(
+            Arc::new(AlwaysOkBroadcaster),
+            Arc::new(NonceManager::new(1)),
+            rx,
+            app_state,
+            config,
+            None,
+        );
+
+        let mk = |program: &str| PremintCandidate {
+            mint: Pubkey::new_unique(),
+            creator: Pubkey::new_unique(),
+            program: program.to_string(),
+            slot: 0,
+            timestamp: 0, instruction_summary: None, is_jito_bundle: None, deadline_ms: None,
+        };
+        assert!(engine.candidate_filter.check(&mk("pump.fun")).is_ok());
+        assert!(engine.candidate_filter.check(&mk("PumpFun")).is_ok());
+        assert!(engine.candidate_filter.check(&mk(crate::types::PUMP_FUN_PROGRAM_ID)).is_ok());
+        assert!(engine.candidate_filter.check(&mk("pump-amm")).is_ok());
+        assert!(engine.candidate_filter.check(&mk("raydium")).is_err());
+
+        // A disabled DEX is never bought, whatever name the candidate uses
+        let mut disabled = Config::default();
+        disabled.dexes.insert(
+            "pump.fun".to_string(),
+            crate::config::DexConfig { enabled: false, ..Default::default() },
+        );
+        assert!(!passes_buy_filter(&disabled, &mk("pump.fun")));
+        assert!(!passes_buy_filter(&disabled, &mk(crate::types::PUMP_FUN_PROGRAM_ID)));
+    }
+
+    #[tokio::test]
+    async fn stale_mock_candidates_fail_the_freshness_gate() {
+        use crate::sniffer::{run_mock_sniffer_with, MockSnifferOptions};
+
+        // Every candidate seen 5s ago against a 1s deadline budget
+        let options = MockSnifferOptions {
+            stale_every: 1,
+            stale_age_ms: 5_000,
+            deadline_budget_ms: 1_000,
+            ..MockSnifferOptions::default()
+        };
+        let (mock_tx, mut mock_rx) = mpsc::channel::<PremintCandidate>(4);
+        let sniffer = run_mock_sniffer_with(mock_tx, options);
+        let mut stale = Vec::new();
+        while stale.len() < 2 {
+            stale.push(mock_rx.recv().await.unwrap());
+        }
+        sniffer.abort();
+        assert!(stale.iter().all(|c| c.is_past_deadline(now_ms())));
+
+        let (tx, rx) = mpsc::channel::<PremintCandidate>(4);
+        let app_state = Arc::new(Mutex::new(AppState::sniffing()));
+        let broadcaster = Arc::new(CountingBroadcaster::new(0x7b));
+        let mut engine = BuyEngine::new(
+            broadcaster.clone(),
+            Arc::new(NonceManager::new(2)),
+            rx,
+            app_state.clone(),
+            Config { nonce_count: 2, ..Config::default() },
+            None,
+        );
+
+        let before = metrics().get_counter("candidate_deadline_exceeded");
+        for candidate in stale {
+            tx.send(candidate).await.unwrap();
+        }
+        drop(tx);
+        engine.run().await;
+
+        assert!(metrics().get_counter("candidate_deadline_exceeded") >= before + 2);
+        assert_eq!(broadcaster.calls(), 0, "stale mock candidates must not be bought");
+        assert!(app_state.lock().await.is_sniffing());
+    }
+
+    #[tokio::test]
+    async fn delayed_candidate_is_dropped_at_build_stage() {
+        let (tx, rx) = mpsc::channel::<PremintCandidate>(1);
+        let app_state = Arc::new(Mutex::new(AppState::sniffing()));
+        let broadcaster = Arc::new(CountingBroadcaster::new(9));
+        let nonce_manager = Arc::new(NonceManager::new(1));
+        let mut engine = BuyEngine::new(
+            broadcaster.clone(),
+            nonce_manager.clone(),
+            rx,
+            app_state.clone(),
+            Config {
+                nonce_count: 1,
+                candidate_deadline_ms: 30,
+                ..Config::default()
+            },
+            None,
+        );
+        engine.funnel = Arc::new(CandidateFunnel::new());
+
+        // Hold the only nonce so the candidate stalls between filtering and building
+        let (_, held) = nonce_manager.acquire_nonce().await.unwrap();
+        let releaser = nonce_manager.clone();
+        tokio::spawn(async move {
+            sleep(Duration::from_millis(100)).await;
+            releaser.release_nonce(held);
+        });
+
+        let before = metrics().get_counter("candidate_deadline_exceeded");
+        tx.send(PremintCandidate {
+            mint: Pubkey::new_unique(),
+            creator: Pubkey::new_unique(),
+            program: "pump.fun".to_string(),
+            slot: 0,
+            timestamp: 0, instruction_summary: None, is_jito_bundle: None, deadline_ms: None,
+        }).await.unwrap();
+        drop(tx);
+
+        engine.run().await;
+
+        assert_eq!(engine.funnel.count(FunnelStage::Attempted), 1);
+        assert_eq!(engine.funnel.count(FunnelStage::Succeeded), 0);
+        assert_eq!(broadcaster.calls(), 0, "stale candidate must not be broadcast");
+        assert!(metrics().get_counter("candidate_deadline_exceeded") > before);
+        assert_eq!(nonce_manager.available_permits(), 1, "nonce released after drop");
+        assert!(app_state.lock().await.is_sniffing());
+    }
+
+    #[tokio::test]
+    async fn disallowed_program_never_acquires_a_nonce() {
+        let (tx, rx) = mpsc::channel::<PremintCandidate>(1);
+        let app_state = Arc::new(Mutex::new(AppState::sniffing()));
+        let broadcaster = Arc::new(CountingBroadcaster::new(0x4d));
+        let nonce_manager = Arc::new(NonceManager::new(2));
+        // Only LetsBonk is allowed; pump.fun candidates pass the filter but not the pre-check
+        let config = Config {
+            nonce_count: 2,
+            allowed_programs: vec![crate::types::LETSBONK_PROGRAM_ID.to_string()],
+            ..Config::default()
+        };
+        let mut engine = BuyEngine::new(
+            broadcaster.clone(),
+            nonce_manager.clone(),
+            rx,
+            app_state.clone(),
+            config,
+            None,
+        );
+
+        let before = metrics().get_counter("buy_precheck_rejected_total");
+        tx.send(PremintCandidate {
+            mint: Pubkey::new_unique(),
+            creator: Pubkey::new_unique(),
+            program: "pump.fun".to_string(),
+            slot: 0,
+            timestamp: 0, instruction_summary: None, is_jito_bundle: None, deadline_ms: None,
+        }).await.unwrap();
+        drop(tx);
+
+        engine.run().await;
+
+        assert_eq!(nonce_manager.lease_counts(), (0, 0), "no nonce acquired for a disallowed program");
+        assert_eq!(broadcaster.calls(), 0);
+        assert!(metrics().get_counter("buy_precheck_rejected_total") > before);
+        assert!(app_state.lock().await.is_sniffing());
+
+        // Allowing pump.fun lets the same kind of candidate through
+        engine.config.allowed_programs = vec![crate::types::PUMP_FUN_PROGRAM_ID.to_string()];
+        let candidate = PremintCandidate {
+            mint: Pubkey::new_unique(),
+            creator: Pubkey::new_unique(),
+            program: "pump.fun".to_string(),
+            slot: 0,
+            timestamp: 0, instruction_summary: None, is_jito_bundle: None, deadline_ms: None,
+        };
+        assert!(engine.precheck_buy(&candidate).is_ok());
+    }
+
+    #[tokio::test]
+    async fn security_rejected_candidate_is_counted_and_never_bought() {
+        let (tx, rx) = mpsc::channel::<PremintCandidate>(1);
+        let app_state = Arc::new(Mutex::new(AppState::sniffing()));
+        let broadcaster = Arc::new(CountingBroadcaster::new(0x7a));
+        let nonce_manager = Arc::new(NonceManager::new(2));
+        let mut engine = BuyEngine::new(
+            broadcaster.clone(),
+            nonce_manager.clone(),
+            rx,
+            app_state.clone(),
+            Config { nonce_count: 2, ..Config::default() },
+            None,
+        );
+
+        let rejected_before = metrics().get_counter("buy_attempts_security_rejected");
+        // Passes the program filter; only the default mint is wrong
+        tx.send(PremintCandidate {
+            mint: Pubkey::default(),
+            creator: Pubkey::new_unique(),
+            program: "pump.fun".to_string(),
+            slot: 0,
+            timestamp: 0, instruction_summary: None, is_jito_bundle: None, deadline_ms: None,
+        }).await.unwrap();
+        drop(tx);
+
+        engine.run().await;
+
+        assert!(metrics().get_counter("buy_attempts_security_rejected") > rejected_before);
+        assert_eq!(broadcaster.calls(), 0, "no buy broadcast");
+        assert_eq!(nonce_manager.lease_counts(), (0, 0), "no nonce acquired");
+        let st = app_state.lock().await;
+        assert!(st.is_sniffing());
+        assert!(st.active_token.is_none());
+        assert!(st.active_operations().is_empty());
+    }
+
+    #[tokio::test]
+    async fn unsellable_candidate_is_never_bought() {
+        use solana_sdk::program_pack::Pack;
+
+        // A mint whose freeze authority can lock holders out of selling
+        let mut data = vec![0; spl_token::state::Mint::LEN];
+        spl_token::state::Mint {
+            decimals: 6,
+            is_initialized: true,
+            freeze_authority: Some(Pubkey::new_unique()).into(),
+            ..Default::default()
+        }
+        .pack_into_slice(&mut data);
+        let mint_rpc = rpc_with_mint(&spl_token::id(), &data);
+
+        let (tx, rx) = mpsc::channel::<PremintCandidate>(1);
+        let app_state = Arc::new(Mutex::new(AppState::sniffing()));
+        let broadcaster = Arc::new(CountingBroadcaster::new(0x7b));
+        let nonce_manager = Arc::new(NonceManager::new(2));
+        let mut engine = BuyEngine::new(
+            broadcaster.clone(),
+            nonce_manager.clone(),
+            rx,
+            app_state.clone(),
+            Config { nonce_count: 2, ..Config::default() },
+            None,
+        )
+        .with_mint_rpc(Arc::new(mint_rpc));
+
+        let rejected_before = metrics().get_counter("buy_unsellable_rejected_total");
+        tx.send(PremintCandidate {
+            mint: Pubkey::new_unique(),
+            creator: Pubkey::new_unique(),
+            program: "pump.fun".to_string(),
+            slot: 0,
+            timestamp: 0, instruction_summary: None, is_jito_bundle: None, deadline_ms: None,
+        }).await.unwrap();
+        drop(tx);
+
+        engine.run().await;
+
+        assert!(metrics().get_counter("buy_unsellable_rejected_total") > rejected_before);
+        assert_eq!(broadcaster.calls(), 0, "no buy broadcast");
+        assert_eq!(nonce_manager.lease_counts(), (0, 0), "no nonce acquired");
+        let st = app_state.lock().await;
+        assert!(st.is_sniffing());
+        assert!(st.positions.is_empty());
+    }
+
+    #[tokio::test]
+    async fn sellability_read_timeout_follows_the_fail_policy() {
+        // An RPC node that accepts connections and never answers
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(async move {
+            let mut held = Vec::new();
+            while let Ok((socket, _)) = listener.accept().await {
+                held.push(socket);
+            }
+        });
+
+        let engine_with = |fail_closed: bool| {
+            let (_tx, rx) = mpsc::channel::<PremintCandidate>(1);
+            let app_state = Arc::new(Mutex::new(AppState::sniffing()));
+            let config = Config { mint_read_timeout_ms: 50, sellability_fail_closed: fail_closed, ..Config::default() };
+            BuyEngine::new(Arc::new(AlwaysOkBroadcaster), Arc::new(NonceManager::new(1)), rx, app_state, config, None)
+                .with_mint_rpc(Arc::new(RpcClient::new(url.clone())))
+        };
+        let candidate = PremintCandidate {
+            mint: Pubkey::new_unique(),
+            creator: Pubkey::new_unique(),
+            program: "pump.fun".to_string(),
+            slot: 0,
+            timestamp: 0, instruction_summary: None, is_jito_bundle: None, deadline_ms: None,
+        };
+
+        let unchecked_before = metrics().get_counter("buy_sellability_unchecked_total");
+        let mut trace = DecisionTrace::new(&candidate);
+        let started = Instant::now();
+        assert!(engine_with(true).admit_candidate(&candidate, None, &mut trace).await.is_none());
+        assert!(started.elapsed() < Duration::from_secs(5), "mint read is bounded by the timeout");
+        assert_eq!(trace.rejected_at.as_deref(), Some("sellability"));
+        assert_eq!(trace.reason.as_deref(), Some("Mint read timed out after 50ms"));
+
+        let mut trace = DecisionTrace::new(&candidate);
+        assert!(engine_with(false).admit_candidate(&candidate, None, &mut trace).await.is_some());
+        assert!(trace.steps.iter().any(|s| s.stage == "sellability" && s.passed));
+        assert!(metrics().get_counter("buy_sellability_unchecked_total") >= unchecked_before + 2);
+    }
+
+    #[tokio::test]
+    async fn mint_checks_share_one_account_read() {
+        use base64::Engine;
+        use solana_sdk::program_pack::Pack;
+
+        let mut data = vec![0; spl_token::state::Mint::LEN];
+        spl_token::state::Mint { decimals: 6, is_initialized: true, ..Default::default() }.pack_into_slice(&mut data);
+        let account = serde_json::json!({
+            "context": { "slot": 1 },
+            "value": {
+                "lamports": 1_461_600,
+                "data": [base64::engine::general_purpose::STANDARD.encode(&data), "base64"],
+                "owner": spl_token::id().to_string(),
+                "executable": false,
+                "rentEpoch": 0,
+                "space": data.len(),
+            }
+        });
+        // The node answers a single request; a second read would find it gone
+        let (url, mut requests) = serve_block_engine(vec![rpc_result(account)]).await;
+
+        let (_tx, rx) = mpsc::channel::<PremintCandidate>(1);
+        let app_state = Arc::new(Mutex::new(AppState::sniffing()));
+        let config = Config { require_renounced_authorities: true, sellability_fail_closed: true, ..Config::default() };
+        let mut engine =
+            BuyEngine::new(Arc::new(AlwaysOkBroadcaster), Arc::new(NonceManager::new(1)), rx, app_state, config, None)
+                .with_mint_rpc(Arc::new(RpcClient::new(url)));
+        let candidate = PremintCandidate {
+            mint: Pubkey::new_unique(),
+            creator: Pubkey::new_unique(),
+            program: "pump.fun".to_string(),
+            slot: 0,
+            timestamp: 0, instruction_summary: None, is_jito_bundle: None, deadline_ms: None,
+        };
+
+        let mut trace = DecisionTrace::new(&candidate);
+        assert!(engine.admit_candidate(&candidate, None, &mut trace).await.is_some(), "{:?}", trace.reason);
+        assert!(trace.steps.iter().any(|s| s.stage == "sellability" && s.passed));
+        assert!(trace.steps.iter().any(|s| s.stage == "authorities" && s.passed));
+        assert!(requests.recv().await.unwrap().contains("getAccountInfo"));
+        assert!(requests.try_recv().is_err(), "the mint is read once");
+    }
+
+    #[tokio::test]
+    async fn raydium_candidate_with_unburned_lp_is_not_bought() {
+        use crate::test_support::AccountsRpc;
+        use solana_sdk::program_pack::Pack;
+
+        let mint_data = |supply: u64| {
+            let mut data = vec![0; spl_token::state::Mint::LEN];
+            spl_token::state::Mint { supply, decimals: 6, is_initialized: true, ..Default::default() }
+                .pack_into_slice(&mut data);
+            data
+        };
+        let (mint, lp_mint) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let engine_with_lp_supply = |lp_supply: u64| {
+            let (_, mut accounts) = crate::raydium::sol_pool_accounts(&mint, &lp_mint, 1_000_000, 1_000_000);
+            accounts.insert(mint, (spl_token::id(), mint_data(1_000_000_000)));
+            accounts.insert(lp_mint, (spl_token::id(), mint_data(lp_supply)));
+            let rpc = RpcClient::new_sender(AccountsRpc { accounts, token_balance: 0 }, Default::default());
+            let (_tx, rx) = mpsc::channel::<PremintCandidate>(1);
+            let app_state = Arc::new(Mutex::new(AppState::sniffing()));
+            let config = Config {
+                require_renounced_authorities: true,
+                buy_programs: vec!["raydium".to_string(), "pump.fun".to_string()],
+                ..Config::default()
+            };
+            BuyEngine::new(Arc::new(AlwaysOkBroadcaster), Arc::new(NonceManager::new(1)), rx, app_state, config, None)
+                .with_mint_rpc(Arc::new(rpc))
+        };
+        let candidate = |program: &str| PremintCandidate {
+            mint,
+            creator: Pubkey::new_unique(),
+            program: program.to_string(),
+            slot: 0,
+            timestamp: 0, instruction_summary: None, is_jito_bundle: None, deadline_ms: None,
+        };
+
+        let mut trace = DecisionTrace::new(&candidate("raydium"));
+        assert!(engine_with_lp_supply(5_000_000).admit_candidate(&candidate("raydium"), None, &mut trace).await.is_none());
+        assert_eq!(trace.rejected_at.as_deref(), Some("authorities"));
+        assert_eq!(trace.reason.as_deref(), Some("LP not burned"));
+
+        let mut trace = DecisionTrace::new(&candidate("raydium"));
+        assert!(engine_with_lp_supply(0).admit_candidate(&candidate("raydium"), None, &mut trace).await.is_some());
+        assert!(trace.steps.iter().any(|s| s.stage == "authorities" && s.passed));
+
+        // Bonding-curve tokens have no LP to check
+        let mut trace = DecisionTrace::new(&candidate("pump.fun"));
+        assert!(engine_with_lp_supply(5_000_000).admit_candidate(&candidate("pump.fun"), None, &mut trace).await.is_some());
+    }
+
+    #[tokio::test]
+    async fn buy_beyond_inflight_limit_is_throttled() {
+        let limiter = InflightBuyLimiter::new(2);
+        let (tx, rx) = mpsc::channel::<PremintCandidate>(1);
+        let app_state = Arc::new(Mutex::new(AppState::sniffing()));
+        let broadcaster = Arc::new(CountingBroadcaster::new(0x1b));
+        let mut engine = BuyEngine::new(
+            broadcaster.clone(),
+            Arc::new(NonceManager::new(1)),
+            rx,
+            app_state.clone(),
+            Config { nonce_count: 1, max_inflight_buys: 2, ..Config::default() },
+            None,
+        )
+        .with_inflight_limiter(limiter.clone());
+        engine.funnel = Arc::new(CandidateFunnel::new());
+
+        // Two buys already in flight elsewhere: the third is throttled
+        let first = limiter.try_start().unwrap();
+        let _second = limiter.try_start().unwrap();
+        assert!(limiter.try_start().is_none());
+
+        let before = metrics().get_counter("buy_inflight_throttled_total");
+        tx.send(PremintCandidate {
+            mint: Pubkey::new_unique(),
+            creator: Pubkey::new_unique(),
+            program: "pump.fun".to_string(),
+            slot: 0,
+            timestamp: 0, instruction_summary: None, is_jito_bundle: None, deadline_ms: None,
+        }).await.unwrap();
+        drop(tx);
+        engine.run().await;
+
+        assert_eq!(broadcaster.calls(), 0, "throttled buy must not be broadcast");
+        assert_eq!(engine.funnel.count(FunnelStage::Attempted), 0);
+        assert!(metrics().get_counter("buy_inflight_throttled_total") > before);
+        assert!(app_state.lock().await.is_sniffing());
+
+        // A finished buy frees its slot
+        drop(first);
+        assert_eq!(limiter.available(), 1);
+        assert!(limiter.try_start().is_some());
+    }
+
+    #[tokio::test]
+    async fn buy_schedule_pauses_and_resumes_with_notification() {
+        use chrono::TimeZone;
+
+        let (_tx, rx) = mpsc::channel::<PremintCandidate>(1);
+        let app_state = Arc::new(Mutex::new(AppState::sniffing()));
+        let mut engine = BuyEngine::new(
+            Arc::new(AlwaysOkBroadcaster),
+            Arc::new(NonceManager::new(1)),
+            rx,
+            app_state.clone(),
+            Config {
+                buy_schedule: vec![crate::schedule::BuyWindow {
+                    days: Vec::new(),
+                    start: "13:00".parse().unwrap(),
+                    end: "21:00".parse().unwrap(),
+                }],
+                ..Config::default()
+            },
+            None,
+        );
+
+        let morning = Utc.with_ymd_and_hms(2024, 1, 1, 9, 0, 0).unwrap();
+        let afternoon = Utc.with_ymd_and_hms(2024, 1, 1, 14, 0, 0).unwrap();
+        assert!(!engine.buy_window_allows(morning).await);
+        assert!(!engine.buy_window_allows(morning).await);
+        assert!(engine.buy_window_allows(afternoon).await);
+
+        let st = app_state.lock().await;
+        let messages: Vec<_> = st.notifications.iter().map(|n| n.message.as_str()).collect();
+        assert_eq!(messages, vec!["Outside buy window; auto-buy paused", "Buy window opened; auto-buy resumed"]);
+    }
+
+    #[tokio::test]
+    async fn failing_rpc_endpoints_pause_auto_buy_until_recovery() {
+        let endpoints: Vec<String> = ["http://a", "http://b", "http://c"].iter().map(|e| e.to_string()).collect();
+        let health = Arc::new(RpcHealth::new(endpoints.clone()));
+
+        let (_tx, rx) = mpsc::channel::<PremintCandidate>(1);
+        let app_state = Arc::new(Mutex::new(AppState::sniffing()));
+        let mut engine = BuyEngine::new(
+            Arc::new(AlwaysOkBroadcaster),
+            Arc::new(NonceManager::new(1)),
+            rx,
+            app_state.clone(),
+            Config { rpc_min_healthy_fraction: 0.5, ..Config::default() },
+            None,
+        )
+        .with_rpc_health(health.clone());
+        assert!(engine.rpc_health_allows().await);
+
+        // One endpoint down still leaves a healthy majority
+        for _ in 0..3 {
+            health.record_error("http://a");
+        }
+        assert!(engine.rpc_health_allows().await);
+
+        // Two of three down trips the pause
+        for _ in 0..3 {
+            health.record_error("http://b");
+        }
+        assert!(!engine.rpc_health_allows().await);
+        assert!(!engine.rpc_health_allows().await);
+
+        // A successful probe brings an endpoint back and resumes buying
+        health.record_success("http://b", 40);
+        assert!(engine.rpc_health_allows().await);
+
+        let st = app_state.lock().await;
+        let messages: Vec<_> = st.notifications.iter().map(|n| n.message.as_str()).collect();
+        assert_eq!(
+            messages,
+            vec!["RPC endpoints unhealthy (1/3 up); auto-buy paused", "RPC endpoints recovered; auto-buy resumed"]
+        );
+    }
+
+    #[tokio::test]
+    async fn unsupported_version_send_triggers_legacy_rebuild() {
+        use solana_sdk::message::VersionedMessage;
+
+        #[derive(Debug, Default)]
+        struct V0RejectingBroadcaster {
+            calls: AtomicU32,
+            legacy_sends: AtomicU32,
+        }
+        impl RpcBroadcaster for V0RejectingBroadcaster {
+            fn send_on_many_rpc<'a>(
+                &'a self,
+                txs: Vec<VersionedTransaction>,
+                _correlation_id: Option<CorrelationId>,
+            ) -> Pin<Box<dyn Future<Output = Result<SendOutcome>> + Send + 'a>> {
+                Box::pin(async move {
+                    self.calls.fetch_add(1, Ordering::SeqCst);
+                    if txs.iter().any(|tx| matches!(tx.message, VersionedMessage::V0(_))) {
+                        return Err(anyhow!("RpcManager: all sends failed: unsupported transaction version (1 endpoints)"));
+                    }
+                    self.legacy_sends.fetch_add(1, Ordering::SeqCst);
+                    Ok(mock_outcome(5))
+                })
+            }
+        }
+
+        let (_tx, rx) = mpsc::channel::<PremintCandidate>(1);
+        let app_state = Arc::new(Mutex::new(AppState::sniffing()));
+        let broadcaster = Arc::new(V0RejectingBroadcaster::default());
+        let engine = BuyEngine::new(
+            broadcaster.clone(),
+            Arc::new(NonceManager::new(2)),
+            rx,
+            app_state,
+            Config {
+                nonce_count: 2,
+                legacy_tx_fallback: true,
+                ..Config::default()
+            },
+            None,
+        );
+
+        let candidate = PremintCandidate {
+            mint: Pubkey::new_unique(),
+            creator: Pubkey::new_unique(),
+            program: "pump.fun".to_string(),
+            slot: 0,
+            timestamp: 0, instruction_summary: None, is_jito_bundle: None, deadline_ms: None,
+        };
+        let outcome = engine
+            .try_buy(candidate, PipelineContext::new("legacy_fallback_test"), None)
+            .await
+            .expect("legacy retry should succeed");
+
+        assert_eq!(outcome.signature, Signature::from([5u8; 64]));
+        assert_eq!(broadcaster.calls.load(Ordering::SeqCst), 2);
+        assert_eq!(broadcaster.legacy_sends.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn unsigned_builder_buy_passes_simulation_before_send() {
+        use solana_sdk::hash::Hash;
+
+        // The node answers the one simulateTransaction call with a clean run
+        let (rpc_url, mut requests) = serve_block_engine(vec![rpc_result(serde_json::json!({
+            "context": { "slot": 1 },
+            "value": { "err": null, "logs": [], "accounts": null, "unitsConsumed": 1_000, "returnData": null },
+        }))])
+        .await;
+        let config = Config {
+            nonce_count: 1,
+            simulate_before_send: true,
+            rpc_endpoints: vec![rpc_url],
+            ..Config::default()
+        };
+        let builder = TransactionBuilder::new(
+            Arc::new(crate::wallet::WalletManager::new_random()),
+            config.rpc_endpoints.clone(),
+            Arc::new(NonceManager::new(1)),
+            &buy_transaction_config(&config, false),
+        )
+        .await
+        .unwrap();
+        builder.inject_blockhash_for_tests(Hash::new_unique()).await;
+        let broadcaster = Arc::new(CountingBroadcaster::new(7));
+        let (_tx, rx) = mpsc::channel::<PremintCandidate>(1);
+        let engine = BuyEngine::new(
+            broadcaster.clone(),
+            Arc::new(NonceManager::new(1)),
+            rx,
+            Arc::new(Mutex::new(AppState::sniffing())),
+            config,
+            Some(builder),
+        );
+        let candidate = PremintCandidate {
+            mint: Pubkey::new_unique(),
+            creator: Pubkey::new_unique(),
+            program: "pump.fun".to_string(),
+            slot: 0,
+            timestamp: 0, instruction_summary: None, is_jito_bundle: None, deadline_ms: None,
+        };
+
+        engine
+            .try_buy(candidate, PipelineContext::new("simulate_before_send_test"), None)
+            .await
+            .expect("an unsigned buy simulates and is sent");
+        assert_eq!(broadcaster.calls(), 1);
+        let request = requests.recv().await.unwrap();
+        assert!(request.contains("simulateTransaction"), "{}", request);
+        assert!(request.contains("\"sigVerify\":false"), "{}", request);
+    }
+
+    #[tokio::test]
+    async fn blockhash_not_found_rebuilds_and_resends() {
+        use crate::rpc_manager::BroadcastFailure;
+        use solana_sdk::hash::Hash;
+
+        /// Fails the first send as stale; records the blockhash of every send.
+        #[derive(Debug, Default)]
+        struct StaleBlockhashOnceBroadcaster {
+            blockhashes: std::sync::Mutex<Vec<Hash>>,
+        }
+        impl StaleBlockhashOnceBroadcaster {
+            fn sends(&self) -> Vec<Hash> {
+                self.blockhashes.lock().unwrap().clone()
+            }
+        }
+        impl RpcBroadcaster for StaleBlockhashOnceBroadcaster {
+            fn send_on_many_rpc<'a>(
+                &'a self,
+                txs: Vec<VersionedTransaction>,
+                _correlation_id: Option<CorrelationId>,
+            ) -> Pin<Box<dyn Future<Output = Result<SendOutcome>> + Send + 'a>> {
+                Box::pin(async move {
+                    let mut blockhashes = self.blockhashes.lock().unwrap();
+                    blockhashes.push(*txs[0].message.recent_blockhash());
+                    if blockhashes.len() == 1 {
+                        return Err(BroadcastFailure::BlockhashNotFound(1).into());
+                    }
+                    Ok(mock_outcome(7))
+                })
+            }
+        }
+
+        let app_state = || Arc::new(Mutex::new(AppState::sniffing()));
+        let candidate = PremintCandidate {
+            mint: Pubkey::new_unique(),
+            creator: Pubkey::new_unique(),
+            program: "pump.fun".to_string(),
+            slot: 0,
+            timestamp: 0, instruction_summary: None, is_jito_bundle: None, deadline_ms: None,
+        };
+
+        // The refresh fetches the next blockhash from the RPC node
+        let (stale, fresh) = (Hash::new_unique(), Hash::new_unique());
+        let (rpc_url, _requests) = serve_block_engine(vec![rpc_result(serde_json::json!({
+            "context": { "slot": 1 },
+            "value": { "blockhash": fresh.to_string(), "lastValidBlockHeight": 100 },
+        }))])
+        .await;
+        let config = Config {
+            nonce_count: 1,
+            blockhash_refresh_retries: 2,
+            rpc_endpoints: vec![rpc_url],
+            ..Config::default()
+        };
+        let builder = TransactionBuilder::new(
+            Arc::new(crate::wallet::WalletManager::new_random()),
+            config.rpc_endpoints.clone(),
+            Arc::new(NonceManager::new(1)),
+            &buy_transaction_config(&config, false),
+        )
+        .await
+        .unwrap();
+        builder.inject_blockhash_for_tests(stale).await;
+        let broadcaster = Arc::new(StaleBlockhashOnceBroadcaster::default());
+        let (_tx, rx) = mpsc::channel::<PremintCandidate>(1);
+        let engine = BuyEngine::new(
+            broadcaster.clone(),
+            Arc::new(NonceManager::new(1)),
+            rx,
+            app_state(),
+            config,
+            Some(builder),
+        );
+        let outcome = engine
+            .try_buy(candidate.clone(), PipelineContext::new("blockhash_refresh_test"), None)
+            .await
+            .expect("rebuilt BUY should land");
+        assert_eq!(outcome.signature, Signature::from([7u8; 64]));
+        // The resend was rebuilt on the fresh blockhash, not the stale one
+        assert_eq!(broadcaster.sends(), vec![stale, fresh]);
+
+        // With refreshes disabled the error is returned as is
+        let broadcaster = Arc::new(StaleBlockhashOnceBroadcaster::default());
+        let (_tx, rx) = mpsc::channel::<PremintCandidate>(1);
+        let engine = BuyEngine::new(
+            broadcaster.clone(),
+            Arc::new(NonceManager::new(1)),
+            rx,
+            app_state(),
+            Config { nonce_count: 1, blockhash_refresh_retries: 0, ..Config::default() },
+            None,
+        );
+        let err = engine
+            .try_buy(candidate, PipelineContext::new("blockhash_refresh_test"), None)
+            .await
+            .unwrap_err();
+        assert!(is_blockhash_not_found_error(&err));
+        assert_eq!(broadcaster.sends().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn version_error_without_fallback_is_not_retried() {
+        #[derive(Debug, Default)]
+        struct VersionErrorBroadcaster {
+            calls: AtomicU32,
+        }
+        impl RpcBroadcaster for VersionErrorBroadcaster {
+            fn send_on_many_rpc<'a>(
+                &'a self,
+                _txs: Vec<VersionedTransaction>,
+                _correlation_id: Option<CorrelationId>,
+            ) -> Pin<Box<dyn Future<Output = Result<SendOutcome>> + Send + 'a>> {
+                self.calls.fetch_add(1, Ordering::SeqCst);
+                Box::pin(async { Err(anyhow!("unsupported transaction version")) })
+            }
+        }
+
+        let (_tx, rx) = mpsc::channel::<PremintCandidate>(1);
+        let app_state = Arc::new(Mutex::new(AppState::sniffing()));
+        let broadcaster = Arc::new(VersionErrorBroadcaster::default());
+        let engine = BuyEngine::new(
+            broadcaster.clone(),
+            Arc::new(NonceManager::new(1)),
+            rx,
+            app_state,
+            Config { nonce_count: 1, ..Config::default() },
+            None,
+        );
+
+        let candidate = PremintCandidate {
+            mint: Pubkey::new_unique(),
+            creator: Pubkey::new_unique(),
+            program: "pump.fun".to_string(),
+            slot: 0,
+            timestamp: 0, instruction_summary: None, is_jito_bundle: None, deadline_ms: None,
+        };
+        assert!(engine.try_buy(candidate, PipelineContext::new("no_fallback_test"), None).await.is_err());
+        assert_eq!(broadcaster.calls.load(Ordering::SeqCst), 1);
+    }
+
+    #[test]
+    fn recoverable_buy_errors() {
+        assert!(is_recoverable_buy_error(&anyhow!("RpcManager: all sends failed (fatal_errors: 0)").context("broadcast BUY failed")));
+        assert!(is_recoverable_buy_error(&anyhow!("RPC send timeout")));
+        assert!(!is_recoverable_buy_error(&anyhow!("candidate deadline exceeded before build")));
+        assert!(!is_recoverable_buy_error(&anyhow!("insufficient funds for fee")));
+        assert!(!is_recoverable_buy_error(&anyhow!("RpcManager: all sends failed: unsupported transaction version (1 endpoints)")));
+    }
+
+    #[tokio::test]
+    async fn whole_buy_is_retried_after_recoverable_failure() {
+        #[derive(Debug, Default)]
+        struct DownOnceBroadcaster {
+            calls: AtomicU32,
+        }
+        impl RpcBroadcaster for DownOnceBroadcaster {
+            fn send_on_many_rpc<'a>(
+                &'a self,
+                _txs: Vec<VersionedTransaction>,
+                _correlation_id: Option<CorrelationId>,
+            ) -> Pin<Box<dyn Future<Output = Result<SendOutcome>> + Send + 'a>> {
+                Box::pin(async move {
+                    if self.calls.fetch_add(1, Ordering::SeqCst) == 0 {
+                        Err(anyhow!("RpcManager: all sends failed (fatal_errors: 0)"))
+                    } else {
+                        Ok(mock_outcome(4))
+                    }
+                })
+            }
+        }
+
+        let (tx, rx) = mpsc::channel::<PremintCandidate>(1);
+        let app_state = Arc::new(Mutex::new(AppState::sniffing()));
+        let broadcaster = Arc::new(DownOnceBroadcaster::default());
+        let nonce_manager = Arc::new(NonceManager::new(1));
+        let mut engine = BuyEngine::new(
+            broadcaster.clone(),
+            nonce_manager.clone(),
+            rx,
+            app_state.clone(),
+            Config {
+                nonce_count: 1,
+                buy_retry_attempts: 2,
+                buy_retry_backoff_ms: 10,
+                ..Config::default()
+            },
+            None,
+        );
+        engine.funnel = Arc::new(CandidateFunnel::new());
+
+        let mint = Pubkey::new_unique();
+        tx.send(PremintCandidate {
+            mint,
+            creator: Pubkey::new_unique(),
+            program: "pump.fun".to_string(),
+            slot: 0,
+            timestamp: 0, instruction_summary: None, is_jito_bundle: None, deadline_ms: None,
+        }).await.unwrap();
+        drop(tx);
 
-    fn is_candidate_interesting(&self, candidate: &PremintCandidate) -> bool {
-        candidate.program == "pump.fun"
-    }
+        engine.run().await;
 
-    async fn get_execution_price_mock(&self, _candidate: &PremintCandidate) -> f64 {
-        0.000001 // Mock price for testing
+        assert_eq!(broadcaster.calls.load(Ordering::SeqCst), 2);
+        assert_eq!(engine.funnel.count(FunnelStage::Attempted), 1);
+        assert_eq!(engine.funnel.count(FunnelStage::Succeeded), 1);
+        assert_eq!(nonce_manager.available_permits(), 1);
+        assert!(matches!(app_state.lock().await.mode, Mode::PassiveToken(m) if m == mint));
     }
 
-    async fn get_recent_blockhash(&self) -> Option<solana_sdk::hash::Hash> {
-        None // Simplified implementation
-    }
-}
+    #[tokio::test]
+    async fn jito_buys_follow_their_bundle_status() {
+        fn bundle_status(confirmation: &str, err: serde_json::Value) -> serde_json::Value {
+            serde_json::json!({
+                "context": { "slot": 43 },
+                "value": [{ "bundle_id": "b", "transactions": [], "slot": 42, "confirmation_status": confirmation, "err": err }],
+            })
+        }
+        async fn jito_engine(status_timeout_ms: u64, results: Vec<serde_json::Value>) -> (BuyEngine, Arc<Mutex<AppState>>) {
+            let config = Config {
+                nonce_count: 1,
+                jito_block_engine_url: Some(serve_block_engine(results.into_iter().map(rpc_result).collect()).await.0),
+                jito_bundle_status_timeout_ms: status_timeout_ms,
+                ..Config::default()
+            };
+            let builder = offline_builder(&buy_transaction_config(&config, false)).await;
+            let app_state = Arc::new(Mutex::new(AppState::sniffing()));
+            let (_tx, rx) = mpsc::channel(1);
+            // The broadcaster is never used: buys go to the block engine
+            let engine = BuyEngine::new(
+                Arc::new(AlwaysOkBroadcaster),
+                Arc::new(NonceManager::new(1)),
+                rx,
+                app_state.clone(),
+                config,
+                Some(builder),
+            );
+            (engine, app_state)
+        }
+        let candidate = || PremintCandidate {
+            mint: Pubkey::new_unique(),
+            creator: Pubkey::new_unique(),
+            program: "pump.fun".to_string(),
+            slot: 0,
+            timestamp: 0, instruction_summary: None, is_jito_bundle: None, deadline_ms: None,
+        };
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use std::future::Future;
-    use std::pin::Pin;
-    use tokio::sync::mpsc;
+        let (engine, app_state) = jito_engine(5_000, vec![
+            serde_json::json!("bundle-landed"),
+            serde_json::json!({ "context": { "slot": 41 }, "value": [null] }),
+            bundle_status("processed", serde_json::json!({ "Ok": null })),
+            bundle_status("confirmed", serde_json::json!({ "Ok": null })),
+        ])
+        .await;
+        let bought = candidate();
+        let outcome = engine.buy_now(&bought).await.unwrap();
+        assert_eq!(outcome.bundle_id.as_deref(), Some("bundle-landed"));
+        let st = app_state.lock().await;
+        assert!(st.position(&bought.mint).is_some());
+        assert!(matches!(st.mode, Mode::PassiveToken(_)));
+        drop(st);
 
-    #[derive(Debug)]
-    struct AlwaysOkBroadcaster;
-    impl RpcBroadcaster for AlwaysOkBroadcaster {
-        fn send_on_many_rpc<'a>(
-            &'a self,
-            _txs: Vec<VersionedTransaction>,
-            _correlation_id: Option<CorrelationId>,
-        ) -> Pin<Box<dyn Future<Output = Result<Signature>> + Send + 'a>> {
-            Box::pin(async { Ok(Signature::from([7u8; 64])) })
-        }
+        let (engine, app_state) = jito_engine(5_000, vec![
+            serde_json::json!("bundle-failed"),
+            bundle_status("confirmed", serde_json::json!({ "Err": { "InstructionError": [2, { "Custom": 6002 }] } })),
+        ])
+        .await;
+        let rejected = candidate();
+        assert!(engine.buy_now(&rejected).await.is_err());
+        let st = app_state.lock().await;
+        assert!(st.position(&rejected.mint).is_none());
+        assert!(matches!(st.mode, Mode::Sniffing));
+        drop(st);
+
+        // Never seen landing in time: it may still land, so it is held unconfirmed
+        let (engine, app_state) = jito_engine(300, vec![
+            serde_json::json!("bundle-unknown"),
+            serde_json::json!({ "context": { "slot": 41 }, "value": [null] }),
+        ])
+        .await;
+        let pending = candidate();
+        let outcome = engine.buy_now(&pending).await.unwrap();
+        let position = app_state.lock().await.position(&pending.mint).expect("pending bundle is held");
+        assert_eq!(position.unconfirmed_signature, Some(outcome.signature));
     }
 
     #[tokio::test]
-    async fn buy_enters_passive_and_sell_returns_to_sniffing() {
-        let (tx, rx): (mpsc::Sender<PremintCandidate>, mpsc::Receiver<PremintCandidate>) =
-            mpsc::channel(8);
-
-        let app_state = Arc::new(Mutex::new(AppState {
-            mode: Mode::Sniffing,
-            active_token: None,
-            last_buy_price: None,
-            holdings_percent: 0.0, quantum_suggestions: Vec::new(),
-        }));
+    async fn suggestions_the_engine_passes_on_are_prewarmed() {
+        use crate::quantum_selector::ScoredCandidate;
+        use crate::warm_pool::WarmPool;
 
+        // Nothing scores high enough to be bought automatically
+        let config = Config {
+            nonce_count: 1,
+            min_buy_score: 99,
+            warm_pool_enabled: true,
+            warm_pool_min_score: 80,
+            ..Config::default()
+        };
+        let pool = Arc::new(WarmPool::new(4));
+        let builder = offline_builder(&buy_transaction_config(&config, false)).await.with_warm_pool(pool.clone());
+        let app_state = Arc::new(Mutex::new(AppState::sniffing()));
+        let (_tx, rx) = mpsc::channel(1);
+        let (scored_tx, scored_rx) = mpsc::channel(4);
         let mut engine = BuyEngine::new(
             Arc::new(AlwaysOkBroadcaster),
-            Arc::new(NonceManager::new(2)),
+            Arc::new(NonceManager::new(1)),
             rx,
-            app_state.clone(),
-            Config {
-                nonce_count: 1,
-                ..Config::default()
-            },
-            None, // No transaction builder for tests
-        );
+            app_state,
+            config,
+            Some(builder),
+        )
+        .with_scored_candidates(scored_rx);
+
+        let scored = |score: u8| {
+            let candidate = PremintCandidate {
+                mint: Pubkey::new_unique(),
+                creator: Pubkey::new_unique(),
+                program: "pump.fun".to_string(),
+                slot: 0,
+                timestamp: 0, instruction_summary: None, is_jito_bundle: None, deadline_ms: None,
+            };
+            ScoredCandidate {
+                mint: candidate.mint,
+                predicted_score: score,
+                feature_scores: Default::default(),
+                reason: String::new(),
+                timestamp: 0,
+                calculation_time: 0,
+                candidate,
+            }
+        };
+        let (hot, cold) = (scored(95), scored(40));
+        scored_tx.send(hot.clone()).await.unwrap();
+        scored_tx.send(cold.clone()).await.unwrap();
+        drop(scored_tx);
+
+        engine.run().await;
+
+        // Only the high-score suggestion is waiting to be signed, once its background build is done
+        timeout(Duration::from_secs(5), async {
+            while !pool.contains(&hot.mint) {
+                sleep(Duration::from_millis(5)).await;
+            }
+        })
+        .await
+        .expect("high-score suggestion pre-built");
+        assert!(!pool.contains(&cold.mint));
+        // An operator buy of it with the configured amount uses the pre-built transaction
+        let amount = buy_transaction_config(&engine.config, false).buy_amount_lamports;
+        engine.buy_now_with_amount(&hot.candidate, amount).await.unwrap();
+        assert!(pool.is_empty());
+    }
+
+    #[tokio::test]
+    async fn builder_errors_reach_the_caller_typed() {
+        use crate::config::DexConfig;
+        use crate::tx_builder::TransactionBuilderError;
 
+        let config = Config {
+            nonce_count: 1,
+            dexes: std::collections::BTreeMap::from([(
+                "pump.fun".to_string(),
+                DexConfig { enabled: false, ..DexConfig::default() },
+            )]),
+            ..Config::default()
+        };
+        let builder = offline_builder(&buy_transaction_config(&config, false)).await;
+        let (_tx, rx) = mpsc::channel(1);
+        let engine = BuyEngine::new(
+            Arc::new(AlwaysOkBroadcaster),
+            Arc::new(NonceManager::new(1)),
+            rx,
+            Arc::new(Mutex::new(AppState::sniffing())),
+            config,
+            Some(builder),
+        );
         let candidate = PremintCandidate {
             mint: Pubkey::new_unique(),
             creator: Pubkey::new_unique(),
             program: "pump.fun".to_string(),
             slot: 0,
-            timestamp: 0, instruction_summary: None, is_jito_bundle: None,
+            timestamp: 0, instruction_summary: None, is_jito_bundle: None, deadline_ms: None,
         };
-        tx.send(candidate).await.unwrap();
-        drop(tx);
 
-        engine.run().await;
+        let EngineError::Other(error) = engine.buy_now(&candidate).await.unwrap_err() else {
+            panic!("expected an unclassified engine error");
+        };
+        assert!(matches!(
+            error.downcast_ref::<TransactionBuilderError>(),
+            Some(TransactionBuilderError::DexDisabled(name)) if name == "pump.fun"
+        ));
+        assert_eq!(
+            crate::user_messages::user_message(&error),
+            "Trading on pump.fun is disabled in the DEX settings"
+        );
+    }
+
+    #[tokio::test]
+    async fn paper_trading_runs_a_buy_sell_cycle_without_broadcasting() {
+        use crate::tx_builder::{PriceSource, TransactionBuilderError};
+        struct FixedPrice;
+        impl PriceSource for FixedPrice {
+            fn current_price<'a>(
+                &'a self,
+                _mint: &'a Pubkey,
+            ) -> Pin<Box<dyn Future<Output = std::result::Result<f64, TransactionBuilderError>> + Send + 'a>> {
+                Box::pin(async { Ok(0.0025) })
+            }
+        }
+
+        let app_state = Arc::new(Mutex::new(AppState::sniffing()));
+        let broadcaster = Arc::new(CountingBroadcaster::new(0));
+        let (_tx, rx) = mpsc::channel(1);
+        let engine = BuyEngine::new(
+            broadcaster.clone(),
+            Arc::new(NonceManager::new(2)),
+            rx,
+            app_state.clone(),
+            Config { nonce_count: 2, paper_trading: true, ..Config::default() },
+            None,
+        )
+        .with_price_source(Arc::new(FixedPrice));
+        let candidate = PremintCandidate {
+            mint: Pubkey::new_unique(),
+            creator: Pubkey::new_unique(),
+            program: "pump.fun".to_string(),
+            slot: 0,
+            timestamp: 0, instruction_summary: None, is_jito_bundle: None, deadline_ms: None,
+        };
 
+        let outcome = engine.buy_now(&candidate).await.unwrap();
+        assert_eq!(outcome.endpoint, PAPER_ENDPOINT);
         {
             let st = app_state.lock().await;
-            match st.mode {
-                Mode::PassiveToken(_) => {}
-                _ => panic!("Expected PassiveToken mode after buy"),
-            }
-            assert_eq!(st.holdings_percent, 1.0);
-            assert!(st.last_buy_price.is_some());
-            assert!(st.active_token.is_some());
+            assert!(matches!(st.mode, Mode::PassiveToken(mint) if mint == candidate.mint));
+            // Filled at the price source's price
+            assert_eq!(st.position(&candidate.mint).unwrap().buy_price, Some(0.0025));
         }
 
-        engine.sell(1.0).await.expect("sell should succeed");
+        engine.sell(candidate.mint, 0.5).await.unwrap();
+        assert!((app_state.lock().await.holdings_percent - 0.5).abs() < 1e-9);
+        engine.sell(candidate.mint, 1.0).await.unwrap();
         let st = app_state.lock().await;
         assert!(st.is_sniffing());
-        assert!(st.active_token.is_none());
-        assert!(st.last_buy_price.is_none());
+        assert!(st.position(&candidate.mint).is_none());
+        assert_eq!(broadcaster.calls(), 0, "paper trades must never be broadcast");
+    }
+
+    #[tokio::test]
+    async fn operator_buy_amount_sizes_the_position() {
+        let engine = |config: Config| {
+            let app_state = Arc::new(Mutex::new(AppState::sniffing()));
+            let (_tx, rx) = mpsc::channel(1);
+            BuyEngine::new(Arc::new(AlwaysOkBroadcaster), Arc::new(NonceManager::new(1)), rx, app_state, config, None)
+        };
+        let candidate = || PremintCandidate {
+            mint: Pubkey::new_unique(),
+            creator: Pubkey::new_unique(),
+            program: "pump.fun".to_string(),
+            slot: 0,
+            timestamp: 0, instruction_summary: None, is_jito_bundle: None, deadline_ms: None,
+        };
+
+        let sized = engine(Config { nonce_count: 1, paper_trading: true, ..Config::default() });
+        let bought = candidate();
+        sized.buy_now_with_amount(&bought, 250_000_000).await.unwrap();
+        {
+            let st = sized.app_state.lock().await;
+            assert_eq!(st.position(&bought.mint).unwrap().cost_lamports, 250_000_000);
+            assert_eq!(st.pnl.spent_lamports, 250_000_000);
+        }
+
+        // The SOL reserve still downsizes an operator-sized buy
+        let balance = Arc::new(SolBalance::new());
+        balance.set(100_000_000);
+        let reserved = engine(Config {
+            nonce_count: 1,
+            paper_trading: true,
+            min_sol_reserve_lamports: 50_000_000,
+            ..Config::default()
+        })
+        .with_sol_balance(balance);
+        let bought = candidate();
+        reserved.buy_now_with_amount(&bought, 250_000_000).await.unwrap();
+        let cost = reserved.app_state.lock().await.position(&bought.mint).unwrap().cost_lamports;
+        assert!(cost > 0 && cost <= 50_000_000, "downsized to the spendable balance, got {}", cost);
     }
 
     #[tokio::test]
@@ -549,12 +4801,7 @@ mod tests {
         let (tx, rx): (mpsc::Sender<PremintCandidate>, mpsc::Receiver<PremintCandidate>) =
             mpsc::channel(8);
 
-        let app_state = Arc::new(Mutex::new(AppState {
-            mode: Mode::Sniffing,
-            active_token: None,
-            last_buy_price: None,
-            holdings_percent: 0.0, quantum_suggestions: Vec::new(),
-        }));
+        let app_state = Arc::new(Mutex::new(AppState::sniffing()));
 
         #[derive(Debug)]
         struct FailingBroadcaster;
@@ -563,7 +4810,7 @@ mod tests {
                 &'a self,
                 _txs: Vec<VersionedTransaction>,
                 _correlation_id: Option<CorrelationId>,
-            ) -> Pin<Box<dyn Future<Output = Result<Signature>> + Send + 'a>> {
+            ) -> Pin<Box<dyn Future<Output = Result<SendOutcome>> + Send + 'a>> {
                 Box::pin(async { Err(anyhow!("simulated failure")) })
             }
         }
@@ -588,7 +4835,7 @@ mod tests {
         
         let backoff_duration = engine.backoff_state.should_backoff().await;
         assert!(backoff_duration.is_some());
-        assert!(backoff_duration.unwrap().as_millis() >= 100);
+        assert!(backoff_duration.unwrap() <= Duration::from_millis(100));
         
         engine.backoff_state.record_success().await;
         assert_eq!(engine.backoff_state.get_failure_count(), 0);
@@ -597,17 +4844,57 @@ mod tests {
         assert!(no_backoff.is_none());
     }
 
+    #[tokio::test]
+    async fn partial_successes_gradually_reduce_backoff() {
+        let backoff = BackoffState::new();
+        for _ in 0..8 {
+            backoff.record_failure().await;
+        }
+        let max_delay = backoff.should_backoff().await.unwrap();
+
+        backoff.record_partial_success(0.5).await;
+        assert_eq!(backoff.get_failure_count(), 4);
+        let reduced = backoff.should_backoff().await.unwrap();
+        assert!(reduced < max_delay);
+
+        // No progress leaves the count alone
+        backoff.record_partial_success(0.0).await;
+        assert_eq!(backoff.get_failure_count(), 4);
+
+        backoff.record_partial_success(0.5).await;
+        backoff.record_partial_success(0.5).await;
+        assert_eq!(backoff.get_failure_count(), 1);
+        assert!(backoff.should_backoff().await.unwrap() < reduced);
+
+        backoff.record_partial_success(0.5).await;
+        assert_eq!(backoff.get_failure_count(), 0);
+        assert!(backoff.should_backoff().await.is_none());
+        assert!(backoff.last_failure.lock().await.is_none());
+    }
+
+    #[tokio::test]
+    async fn unconfirmed_buys_only_partly_reset_backoff() {
+        let backoff = BackoffState::new();
+        for _ in 0..4 {
+            backoff.record_failure().await;
+        }
+        backoff.record_buy(&SentBuy::unconfirmed(mock_outcome(1))).await;
+        assert_eq!(backoff.get_failure_count(), 2);
+        backoff.record_buy(&SentBuy::landed(mock_outcome(2))).await;
+        assert_eq!(backoff.get_failure_count(), 0);
+
+        // The wait runs from the last failure instead of restarting on every check
+        backoff.record_failure().await;
+        sleep(Duration::from_millis(120)).await;
+        assert!(backoff.should_backoff().await.is_none());
+    }
+
     #[tokio::test]
     async fn test_atomic_buy_protection() {
         let (tx, rx): (mpsc::Sender<PremintCandidate>, mpsc::Receiver<PremintCandidate>) =
             mpsc::channel(8);
 
-        let app_state = Arc::new(Mutex::new(AppState {
-            mode: Mode::Sniffing,
-            active_token: None,
-            last_buy_price: None,
-            holdings_percent: 0.0, quantum_suggestions: Vec::new(),
-        }));
+        let app_state = Arc::new(Mutex::new(AppState::sniffing()));
 
         let engine = BuyEngine::new(
             Arc::new(AlwaysOkBroadcaster),
@@ -626,20 +4913,17 @@ mod tests {
             creator: Pubkey::new_unique(),
             program: "pump.fun".to_string(),
             slot: 0,
-            timestamp: 0, instruction_summary: None, is_jito_bundle: None,
+            timestamp: 0, instruction_summary: None, is_jito_bundle: None, deadline_ms: None,
         };
 
         // First buy should succeed
-        let correlation_id = CorrelationId::new();
-        let result1 = engine.try_buy_with_guards(candidate.clone(), correlation_id).await;
+        let result1 = engine.buy_now(&candidate).await;
         assert!(result1.is_ok());
 
         // Immediate second buy should fail due to pending flag
         engine.pending_buy.store(true, Ordering::Relaxed);
-        let correlation_id2 = CorrelationId::new();
-        let result2 = engine.try_buy_with_guards(candidate, correlation_id2).await;
-        assert!(result2.is_err());
-        assert!(result2.unwrap_err().to_string().contains("already in progress"));
+        let result2 = engine.buy_now(&candidate).await;
+        assert!(matches!(result2, Err(EngineError::BuyInProgress)));
     }
 
     #[tokio::test]
@@ -647,18 +4931,8 @@ mod tests {
         let (_tx, rx): (mpsc::Sender<PremintCandidate>, mpsc::Receiver<PremintCandidate>) =
             mpsc::channel(8);
 
-        let app_state = Arc::new(Mutex::new(AppState {
-            mode: Mode::PassiveToken(Pubkey::new_unique()),
-            active_token: Some(PremintCandidate {
-                mint: Pubkey::new_unique(),
-                creator: Pubkey::new_unique(),
-                program: "pump.fun".to_string(),
-                slot: 0,
-                timestamp: 0, instruction_summary: None, is_jito_bundle: None,
-            }),
-            last_buy_price: Some(1.0),
-            holdings_percent: 1.0, quantum_suggestions: Vec::new(),
-        }));
+        let mint = Pubkey::new_unique();
+        let app_state = Arc::new(Mutex::new(AppState::holding(mint, 1.0)));
 
         let engine = BuyEngine::new(
             Arc::new(AlwaysOkBroadcaster),
@@ -673,9 +4947,178 @@ mod tests {
         engine.pending_buy.store(true, Ordering::Relaxed);
 
         // Sell should fail due to pending buy
-        let result = engine.sell(0.5).await;
-        assert!(result.is_err());
-        assert!(result.unwrap_err().to_string().contains("buy operation in progress"));
+        let result = engine.sell(mint, 0.5).await;
+        assert!(matches!(result, Err(EngineError::BuyInProgress)));
+    }
+
+    #[tokio::test]
+    async fn sells_are_clamped_to_what_is_still_held() {
+        let mint = Pubkey::new_unique();
+        let app_state = Arc::new(Mutex::new(AppState::sniffing()));
+        let candidate = PremintCandidate {
+            mint,
+            creator: Pubkey::new_unique(),
+            program: "pump.fun".to_string(),
+            slot: 0,
+            timestamp: 0, instruction_summary: None, is_jito_bundle: None, deadline_ms: None,
+        };
+        app_state.lock().await.open_position(Position::new(candidate, Some(1.0), 1.0));
+        let (_tx, rx) = mpsc::channel(1);
+        let engine = BuyEngine::new(
+            Arc::new(AlwaysOkBroadcaster),
+            Arc::new(NonceManager::new(1)),
+            rx,
+            app_state.clone(),
+            Config { nonce_count: 1, ..Config::default() },
+            None,
+        );
+        let plan = SellPlan { amount: SellAmount::SolValue(1_000), from_holdings: 1.0, target_holdings: 0.5 };
+
+        // Holdings as planned: the plan's own amount goes out
+        assert_eq!(engine.unsold_amount(&mint, plan).await, Some(SellAmount::SolValue(1_000)));
+
+        // Another sell took a quarter meanwhile: only the rest down to the target is sold
+        app_state.lock().await.set_position_holdings(&mint, 0.75);
+        match engine.unsold_amount(&mint, plan).await {
+            Some(SellAmount::Fraction(fraction)) => assert!((fraction - 1.0 / 3.0).abs() < 1e-9, "{}", fraction),
+            other => panic!("expected a fraction, got {:?}", other),
+        }
+
+        // Already at the target: nothing is sent
+        app_state.lock().await.set_position_holdings(&mint, 0.5);
+        assert_eq!(engine.unsold_amount(&mint, plan).await, None);
+    }
+
+    // Without the pumpfun feature, pump.fun sells build offline
+    #[cfg(not(feature = "pumpfun"))]
+    #[tokio::test]
+    async fn sell_many_packs_sells_and_books_each_position() {
+        let app_state = Arc::new(Mutex::new(AppState::sniffing()));
+        let mints: Vec<Pubkey> = (0..3).map(|_| Pubkey::new_unique()).collect();
+        {
+            let mut st = app_state.lock().await;
+            for mint in &mints {
+                let candidate = PremintCandidate {
+                    mint: *mint,
+                    creator: Pubkey::new_unique(),
+                    program: "pump.fun".to_string(),
+                    slot: 0,
+                    timestamp: 0, instruction_summary: None, is_jito_bundle: None, deadline_ms: None,
+                };
+                st.open_position(Position { cost_lamports: 100_000_000, ..Position::new(candidate, Some(1.0), 1.0) });
+                st.mark_price(*mint, 2.0);
+            }
+        }
+        let config = Config { nonce_count: 1, sell_confirm_timeout_ms: 0, ..Config::default() };
+        let builder = offline_builder(&buy_transaction_config(&config, false)).await;
+        let broadcaster = Arc::new(CountingBroadcaster::new(0x5e));
+        let (_tx, rx) = mpsc::channel(1);
+        let engine = BuyEngine::new(
+            broadcaster.clone(),
+            Arc::new(NonceManager::new(1)),
+            rx,
+            app_state.clone(),
+            config,
+            Some(builder),
+        );
+        let missing = Pubkey::new_unique();
+
+        let results = engine.sell_many(&[(mints[0], 1.0), (mints[1], 0.5), (missing, 1.0), (mints[2], 1.0)]).await;
+
+        assert_eq!(results.len(), 4);
+        for (mint, result) in &results {
+            if *mint == missing {
+                assert!(matches!(result, Err(EngineError::NotHolding)));
+            } else {
+                assert!(result.is_ok(), "{}: {:?}", mint, result);
+            }
+        }
+        assert_eq!(broadcaster.calls(), 1, "three small sells fit one transaction");
+        let st = app_state.lock().await;
+        assert!(st.position(&mints[0]).is_none());
+        assert!(st.position(&mints[2]).is_none());
+        let half = st.position(&mints[1]).unwrap();
+        assert!((half.holdings_percent - 0.5).abs() < 1e-9);
+        assert!((half.realized_pnl_lamports - 50_000_000.0).abs() < 1.0, "{}", half.realized_pnl_lamports);
+        assert!((st.pnl.realized_lamports - 250_000_000.0).abs() < 1.0, "{}", st.pnl.realized_lamports);
+    }
+
+    #[tokio::test]
+    async fn engine_failures_return_typed_errors() {
+        fn state(mode: Mode) -> Arc<Mutex<AppState>> {
+            Arc::new(Mutex::new(AppState { mode, ..AppState::sniffing() }))
+        }
+        fn engine(app_state: Arc<Mutex<AppState>>, config: Config) -> BuyEngine {
+            let (_tx, rx) = mpsc::channel::<PremintCandidate>(1);
+            let config = Config { nonce_count: 2, ..config };
+            BuyEngine::new(Arc::new(AlwaysOkBroadcaster), Arc::new(NonceManager::new(2)), rx, app_state, config, None)
+        }
+        let candidate = PremintCandidate {
+            mint: Pubkey::new_unique(),
+            creator: Pubkey::new_unique(),
+            program: "pump.fun".to_string(),
+            slot: 0,
+            timestamp: 0, instruction_summary: None, is_jito_bundle: None, deadline_ms: None,
+        };
+
+        // Nothing held: sells are refused
+        let idle = engine(state(Mode::Sniffing), Config::default());
+        assert!(matches!(idle.sell(candidate.mint, 0.5).await, Err(EngineError::NotHolding)));
+
+        // At the position limit: buys are refused, bad sell amounts are rejected
+        let held = state(Mode::PassiveToken(candidate.mint));
+        {
+            let mut st = held.lock().await;
+            st.active_token = Some(candidate.clone());
+            st.holdings_percent = 1.0;
+        }
+        let holding = engine(held, Config::default());
+        let other = PremintCandidate { mint: Pubkey::new_unique(), ..candidate.clone() };
+        assert!(matches!(holding.buy_now(&other).await, Err(EngineError::NotSniffing)));
+        assert!(matches!(holding.buy_now(&candidate).await, Err(EngineError::AlreadyHolding(m)) if m == candidate.mint));
+        assert!(matches!(holding.sell(other.mint, 0.5).await, Err(EngineError::NotHolding)));
+        assert!(matches!(holding.sell(candidate.mint, f64::NAN).await, Err(EngineError::InvalidAmount(_))));
+
+        // RPC health pause halts buying
+        let mut paused = engine(state(Mode::Sniffing), Config::default());
+        paused.rpc_paused = true;
+        assert!(matches!(paused.buy_now(&candidate).await, Err(EngineError::Halted(_))));
+
+        // The only buy window is tomorrow
+        let closed = engine(
+            state(Mode::Sniffing),
+            Config {
+                buy_schedule: vec![crate::schedule::BuyWindow {
+                    days: vec![chrono::Datelike::weekday(&Utc::now()).succ()],
+                    start: "00:00".parse().unwrap(),
+                    end: "00:00".parse().unwrap(),
+                }],
+                ..Config::default()
+            },
+        );
+        assert!(matches!(closed.buy_now(&candidate).await, Err(EngineError::Halted(_))));
+
+        // Balance below the reserve
+        let balance = Arc::new(SolBalance::new());
+        balance.set(1_000);
+        let broke = engine(
+            state(Mode::Sniffing),
+            Config { min_sol_reserve_lamports: 50_000_000, ..Config::default() },
+        )
+        .with_sol_balance(balance);
+        assert!(matches!(broke.buy_now(&candidate).await, Err(EngineError::InsufficientFunds(_))));
+
+        // The same mint hammered past the per-minute limit
+        let hammered = PremintCandidate { mint: Pubkey::new_unique(), ..candidate.clone() };
+        while validator().check_mint_rate_limit(&hammered.mint, 60, 5) {}
+        let busy = engine(state(Mode::Sniffing), Config::default());
+        assert!(matches!(busy.buy_now(&hammered).await, Err(EngineError::RateLimited(_))));
+
+        // Broadcast failures carry the underlying error
+        let err: EngineError = anyhow!("RpcManager: all sends failed").into();
+        assert!(matches!(err, EngineError::Other(_)));
+        let err: EngineError = anyhow::Error::new(EngineError::NoBuilder).into();
+        assert!(matches!(err, EngineError::NoBuilder));
     }
 
     #[tokio::test]
@@ -683,12 +5126,7 @@ mod tests {
         let (_tx, rx): (mpsc::Sender<PremintCandidate>, mpsc::Receiver<PremintCandidate>) =
             mpsc::channel(8);
 
-        let app_state = Arc::new(Mutex::new(AppState {
-            mode: Mode::Sniffing,
-            active_token: None,
-            last_buy_price: None,
-            holdings_percent: 0.0, quantum_suggestions: Vec::new(),
-        }));
+        let app_state = Arc::new(Mutex::new(AppState::sniffing()));
 
         let nonce_manager = Arc::new(NonceManager::new(2));
 
@@ -712,18 +5150,148 @@ mod tests {
             creator: Pubkey::new_unique(),
             program: "pump.fun".to_string(),
             slot: 0,
-            timestamp: 0, instruction_summary: None, is_jito_bundle: None,
+            timestamp: 0, instruction_summary: None, is_jito_bundle: None, deadline_ms: None,
         };
 
         // Perform buy operation - should acquire and release nonces automatically
-        let correlation_id = CorrelationId::new();
-        let result = engine.try_buy_with_guards(candidate, correlation_id).await;
+        let result = engine.buy_now(&candidate).await;
         assert!(result.is_ok());
 
-        // Give time for async cleanup
-        tokio::time::sleep(Duration::from_millis(50)).await;
-
-        // All permits should be available again after RAII cleanup
+        // Release is synchronous: everything is back as soon as the buy returns
         assert_eq!(nonce_manager.available_permits(), 2);
+        assert_eq!(nonce_manager.lease_counts(), (2, 2));
+    }
+
+    #[tokio::test]
+    async fn try_buy_releases_exactly_the_nonces_it_acquired() {
+        #[derive(Debug)]
+        struct RejectingBroadcaster;
+        impl RpcBroadcaster for RejectingBroadcaster {
+            fn send_on_many_rpc<'a>(
+                &'a self,
+                _txs: Vec<VersionedTransaction>,
+                _correlation_id: Option<CorrelationId>,
+            ) -> Pin<Box<dyn Future<Output = Result<SendOutcome>> + Send + 'a>> {
+                Box::pin(async { Err(anyhow!("all endpoints rejected")) })
+            }
+        }
+
+        let candidate = || PremintCandidate {
+            mint: Pubkey::new_unique(),
+            creator: Pubkey::new_unique(),
+            program: "pump.fun".to_string(),
+            slot: 0,
+            timestamp: 0, instruction_summary: None, is_jito_bundle: None, deadline_ms: None,
+        };
+        let app_state = Arc::new(Mutex::new(AppState::sniffing()));
+
+        let broadcasters: [Arc<dyn RpcBroadcaster>; 2] = [Arc::new(AlwaysOkBroadcaster), Arc::new(RejectingBroadcaster)];
+        for (broadcaster, should_succeed) in broadcasters.into_iter().zip([true, false]) {
+            let nonce_manager = Arc::new(NonceManager::new(3));
+            let (_tx, rx) = mpsc::channel(1);
+            let engine = BuyEngine::new(
+                broadcaster,
+                nonce_manager.clone(),
+                rx,
+                app_state.clone(),
+                Config { nonce_count: 3, ..Config::default() },
+                None,
+            );
+
+            let result = engine.try_buy(candidate(), PipelineContext::new("test"), None).await;
+            assert_eq!(result.is_ok(), should_succeed);
+
+            let (acquired, released) = nonce_manager.lease_counts();
+            assert_eq!(acquired, 3);
+            assert_eq!(released, acquired, "every acquired nonce is released");
+            assert_eq!(nonce_manager.available_permits(), 3);
+        }
+
+        // A failed build (here: the buy would breach the SOL reserve) releases its nonce too
+        let config = Config { nonce_count: 3, min_sol_reserve_lamports: 50_000_000, ..Config::default() };
+        let builder = offline_builder(&buy_transaction_config(&config, false)).await;
+        let balance = Arc::new(SolBalance::new());
+        balance.set(1_000);
+        let nonce_manager = Arc::new(NonceManager::new(3));
+        let (_tx, rx) = mpsc::channel(1);
+        let engine = BuyEngine::new(
+            Arc::new(AlwaysOkBroadcaster),
+            nonce_manager.clone(),
+            rx,
+            app_state,
+            config,
+            Some(builder),
+        )
+        .with_sol_balance(balance);
+
+        let err = engine.try_buy(candidate(), PipelineContext::new("test"), None).await.unwrap_err();
+        assert!(matches!(err.downcast_ref::<EngineError>(), Some(EngineError::InsufficientFunds(_))), "{:#}", err);
+        assert_eq!(nonce_manager.lease_counts(), (1, 1), "the nonce of the failed build is released");
+        assert_eq!(nonce_manager.available_permits(), 3);
+    }
+
+    #[tokio::test]
+    async fn durable_nonce_buy_sends_one_transaction_to_every_endpoint() {
+        use crate::nonce_manager::DurableNonce;
+        use solana_sdk::hash::Hash;
+
+        /// Records every transaction sent.
+        #[derive(Debug, Default)]
+        struct RecordingBroadcaster {
+            sent: std::sync::Mutex<Vec<VersionedTransaction>>,
+        }
+        impl RpcBroadcaster for RecordingBroadcaster {
+            fn send_on_many_rpc<'a>(
+                &'a self,
+                txs: Vec<VersionedTransaction>,
+                _correlation_id: Option<CorrelationId>,
+            ) -> Pin<Box<dyn Future<Output = Result<SendOutcome>> + Send + 'a>> {
+                self.sent.lock().unwrap().extend(txs);
+                Box::pin(async { Ok(mock_outcome(7)) })
+            }
+        }
+
+        let wallet = Arc::new(crate::wallet::WalletManager::new_random());
+        let nonces: Vec<_> = (0..2)
+            .map(|_| DurableNonce { account: Pubkey::new_unique(), authority: wallet.pubkey(), hash: Some(Hash::new_unique()) })
+            .collect();
+        // Shared like in main: the builder builds on the slot the engine holds
+        let nonce_manager = Arc::new(NonceManager::with_durable_nonces(nonces.clone()));
+        let config = Config { nonce_count: 2, ..Config::default() };
+        let builder = TransactionBuilder::new(
+            wallet,
+            vec!["http://127.0.0.1:1".to_string()],
+            nonce_manager.clone(),
+            &buy_transaction_config(&config, false),
+        )
+        .await
+        .unwrap();
+        let broadcaster = Arc::new(RecordingBroadcaster::default());
+        let (_tx, rx) = mpsc::channel(1);
+        let engine = BuyEngine::new(
+            broadcaster.clone(),
+            nonce_manager.clone(),
+            rx,
+            Arc::new(Mutex::new(AppState::sniffing())),
+            config,
+            Some(builder),
+        );
+        let candidate = PremintCandidate {
+            mint: Pubkey::new_unique(),
+            creator: Pubkey::new_unique(),
+            program: "pump.fun".to_string(),
+            slot: 0,
+            timestamp: 0, instruction_summary: None, is_jito_bundle: None, deadline_ms: None,
+        };
+
+        engine.try_buy(candidate, PipelineContext::new("durable_buy_test"), None).await.unwrap();
+
+        // One build on one nonce, sent as is to as many endpoints as nonce_count copies
+        let sent = broadcaster.sent.lock().unwrap().clone();
+        assert_eq!(sent.len(), 2);
+        assert!(sent.iter().all(|tx| tx.signatures == sent[0].signatures), "every copy has the same signature");
+        assert!(sent.iter().all(|tx| *tx == sent[0]), "every copy is the same transaction");
+        assert!(nonces.iter().any(|nonce| *sent[0].message.recent_blockhash() == nonce.hash.unwrap()));
+        assert_eq!(nonce_manager.lease_counts(), (1, 1), "one nonce slot per buy, released after it");
     }
 }
\ No newline at end of file
@@ -1,7 +1,9 @@
 //! Candidate buffer with TTL and de-duplication.
 //!
 //! Stores premint candidates keyed by mint Pubkey, prevents duplicates, and expires old entries.
-//! Provides simple selection policy for "best" candidate: the oldest (earliest inserted/seen).
+//! Provides simple selection policies for the "best" candidate: the oldest (earliest
+//! inserted/seen), the oldest Jito-bundled one first when `SelectionPolicy::PreferJito` is set,
+//! or the highest-scoring one (oldest first on ties) with `SelectionPolicy::HighestScore`.
 //!
 //! Typical usage (shared):
 //! let buf = new_shared(Duration::from_secs(30), 1024);
@@ -13,32 +15,77 @@
 //!
 //! Notes:
 //! - De-duplication is by candidate.mint.
+//! - Scores come from `push_scored` (plain `push` scores 0); a duplicate with a higher score
+//!   raises the buffered entry's score.
 //! - TTL is enforced on push/pop via cleanup, but callers can also call cleanup() periodically.
 //! - If the buffer is full on push, the oldest entry is evicted to make room.
+//! - Candidates past their ingest deadline are skipped on pop.
+//! - `save`/`load` keep buffered candidates across restarts; entries past TTL are dropped on load.
+//! - `forward_by_policy` runs a buffer as a stage between two channels, popping by policy
+//!   whenever the consumer has room.
 
 use crate::types::PremintCandidate;
 use crate::metrics::metrics;
+use crate::time_utils::now_ms;
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
 use solana_sdk::pubkey::Pubkey;
 use std::{
-    collections::{HashMap, VecDeque},
+    cmp::Reverse,
+    collections::{BTreeMap, HashMap, VecDeque},
+    fs,
+    path::Path,
     sync::Arc,
     time::{Duration, Instant},
 };
-use tokio::sync::Mutex;
+use tokio::sync::{
+    mpsc::{Receiver, Sender},
+    Mutex,
+};
+use tracing::warn;
+
+/// Which candidate `pop_best` returns.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SelectionPolicy {
+    /// Oldest candidate first
+    #[default]
+    Oldest,
+    /// Oldest Jito-bundled candidate first, then oldest of the rest
+    PreferJito,
+    /// Highest score first, oldest first among equal scores
+    HighestScore,
+}
+
+/// Score index key: ordering by it puts the best candidate last.
+type ScoreKey = (u8, Reverse<u64>);
+
+/// A buffered candidate as saved on disk.
+#[derive(Debug, Serialize, Deserialize)]
+struct SavedEntry {
+    candidate: PremintCandidate,
+    /// Wall-clock insertion time (ms since epoch)
+    inserted_ms: u64,
+    score: u8,
+}
 
 /// In-memory candidate buffer with optimized O(1) operations.
 #[derive(Debug)]
 pub struct CandidateBuffer {
-    /// Map by mint pubkey; value holds the candidate, insertion time, and sequence number.
-    pub map: HashMap<Pubkey, (PremintCandidate, Instant, u64)>,
+    /// Map by mint pubkey; value holds the candidate, insertion time, sequence number and score.
+    pub map: HashMap<Pubkey, (PremintCandidate, Instant, u64, u8)>,
     /// Insertion order tracking with sequence numbers for O(1) oldest lookup.
     pub insertion_order: VecDeque<(Pubkey, u64)>,
     /// Time-to-live for each entry.
     pub ttl: Duration,
     /// Maximum number of entries to store; oldest will be evicted when full.
     pub max_size: usize,
+    /// Selection policy used by `pop_best`.
+    pub policy: SelectionPolicy,
     /// Sequence counter for insertion order tracking.
     sequence: u64,
+    /// Mints by (score, older first); only maintained with `SelectionPolicy::HighestScore`.
+    by_score: BTreeMap<ScoreKey, Pubkey>,
 }
 
 impl CandidateBuffer {
@@ -56,27 +103,67 @@ impl CandidateBuffer {
             insertion_order: VecDeque::new(),
             ttl,
             max_size,
+            policy: SelectionPolicy::default(),
             sequence: 0,
+            by_score: BTreeMap::new(),
         }
     }
 
-    /// Insert a candidate if not present and not expired.
+    /// Use `policy` for `pop_best`.
+    pub fn with_policy(mut self, policy: SelectionPolicy) -> Self {
+        self.policy = policy;
+        self.by_score = if policy == SelectionPolicy::HighestScore {
+            self.map
+                .iter()
+                .map(|(mint, (_, _, seq, score))| ((*score, Reverse(*seq)), *mint))
+                .collect()
+        } else {
+            BTreeMap::new()
+        };
+        self
+    }
+
+    /// Insert a candidate with score 0 if not present and not expired.
     /// Returns true when inserted, false when duplicate or ignored.
     pub fn push(&mut self, c: PremintCandidate) -> bool {
+        self.push_scored(c, 0)
+    }
+
+    /// Insert a candidate with the given score (e.g. the quantum selector's prediction).
+    /// A duplicate is not inserted but raises the buffered entry's score when higher.
+    pub fn push_scored(&mut self, c: PremintCandidate, score: u8) -> bool {
         // Clean expired entries first.
         let _ = self.cleanup();
 
-        if self.map.contains_key(&c.mint) {
+        if let Some((_, _, seq, stored_score)) = self.map.get_mut(&c.mint) {
+            if score > *stored_score && self.policy == SelectionPolicy::HighestScore {
+                self.by_score.remove(&(*stored_score, Reverse(*seq)));
+                self.by_score.insert((score, Reverse(*seq)), c.mint);
+            }
+            *stored_score = (*stored_score).max(score);
             metrics().increment_counter("candidate_buffer_duplicates_total");
             return false;
         }
 
-        // Enforce capacity by evicting the oldest if at capacity.
+        self.insert(c, Instant::now(), score);
+        
+        // Update metrics
+        metrics().set_gauge("candidate_buffer_size", self.map.len() as u64);
+        metrics().increment_counter("candidate_buffer_inserts_total");
+        
+        true
+    }
+
+    /// Add a candidate not in the map yet, evicting the oldest when at capacity.
+    fn insert(&mut self, c: PremintCandidate, seen_at: Instant, score: u8) {
         if self.map.len() >= self.max_size && self.max_size > 0 {
-            if let Some((oldest_key, _seq)) = self.insertion_order.front().cloned() {
-                self.map.remove(&oldest_key);
-                self.insertion_order.pop_front();
-                metrics().increment_counter("candidate_dropped_due_capacity_total");
+            while let Some((oldest_key, seq)) = self.insertion_order.pop_front() {
+                // Skip entries already popped out of order
+                if self.map.get(&oldest_key).is_some_and(|(_, _, stored_seq, _)| *stored_seq == seq) {
+                    self.remove_entry(&oldest_key);
+                    metrics().increment_counter("candidate_dropped_due_capacity_total");
+                    break;
+                }
             }
         }
 
@@ -84,30 +171,108 @@ impl CandidateBuffer {
         self.sequence += 1;
         let seq = self.sequence;
         let mint = c.mint;
-        self.map.insert(mint, (c, Instant::now(), seq));
+        self.map.insert(mint, (c, seen_at, seq, score));
         self.insertion_order.push_back((mint, seq));
-        
-        // Update metrics
-        metrics().set_gauge("candidate_buffer_size", self.map.len() as u64);
-        metrics().increment_counter("candidate_buffer_inserts_total");
-        
-        true
+        if self.policy == SelectionPolicy::HighestScore {
+            self.by_score.insert((score, Reverse(seq)), mint);
+        }
+    }
+
+    /// Write the buffered candidates to `path` in insertion order, with wall-clock
+    /// insertion times. The file is replaced atomically.
+    pub fn save(&self, path: &Path) -> Result<()> {
+        let now = Instant::now();
+        let wall_now = now_ms();
+        let entries: Vec<SavedEntry> = self
+            .insertion_order
+            .iter()
+            .filter_map(|(key, seq)| match self.map.get(key) {
+                Some((cand, seen_at, stored_seq, score)) if stored_seq == seq => Some(SavedEntry {
+                    candidate: cand.clone(),
+                    inserted_ms: wall_now.saturating_sub(now.duration_since(*seen_at).as_millis() as u64),
+                    score: *score,
+                }),
+                _ => None,
+            })
+            .collect();
+        let json = serde_json::to_vec(&entries).context("encode candidate buffer")?;
+        let tmp = path.with_extension("tmp");
+        fs::write(&tmp, json).with_context(|| format!("write {}", tmp.display()))?;
+        fs::rename(&tmp, path).with_context(|| format!("replace {}", path.display()))
+    }
+
+    /// Rebuild a buffer saved with `save`, dropping entries already past `ttl` and
+    /// entries whose age cannot be placed (stamped in the future, or older than this
+    /// process's clock can represent). Entries keep their relative order and age;
+    /// the policy is the default, so apply `with_policy` afterwards (it reindexes scores).
+    pub fn load(path: &Path, ttl: Duration, max_size: usize) -> Result<Self> {
+        let bytes = fs::read(path).with_context(|| format!("read {}", path.display()))?;
+        let entries: Vec<SavedEntry> =
+            serde_json::from_slice(&bytes).with_context(|| format!("decode {}", path.display()))?;
+
+        let mut buf = Self::new(ttl, max_size);
+        let now = Instant::now();
+        let wall_now = now_ms();
+        let mut expired = 0u64;
+        let mut invalid = 0u64;
+        for entry in entries {
+            let Some(age) = wall_now.checked_sub(entry.inserted_ms).map(Duration::from_millis) else {
+                invalid += 1;
+                continue;
+            };
+            if age >= ttl {
+                expired += 1;
+                continue;
+            }
+            let Some(seen_at) = now.checked_sub(age) else {
+                invalid += 1;
+                continue;
+            };
+            if buf.map.contains_key(&entry.candidate.mint) {
+                continue;
+            }
+            buf.insert(entry.candidate, seen_at, entry.score);
+        }
+        if expired > 0 {
+            metrics().add_to_counter("candidate_dropped_due_ttl_total", expired);
+        }
+        if invalid > 0 {
+            warn!(path = %path.display(), dropped = invalid, "Saved candidates with an invalid insertion time were dropped");
+        }
+        metrics().set_gauge("candidate_buffer_size", buf.map.len() as u64);
+        Ok(buf)
     }
 
-    /// Pop the "best" candidate (oldest by insertion time).
+    /// Pop the "best" candidate according to the selection policy.
     /// Returns None if empty after cleanup or no item is eligible.
     pub fn pop_best(&mut self) -> Option<PremintCandidate> {
         // Remove expired first.
         let _ = self.cleanup();
 
+        if self.policy == SelectionPolicy::PreferJito {
+            if let Some(cand) = self.pop_oldest_jito() {
+                metrics().set_gauge("candidate_buffer_size", self.map.len() as u64);
+                return Some(cand);
+            }
+        }
+
+        if self.policy == SelectionPolicy::HighestScore {
+            let cand = self.pop_highest_score();
+            metrics().set_gauge("candidate_buffer_size", self.map.len() as u64);
+            return cand;
+        }
+
         // Get the oldest entry from front of insertion order
         while let Some((oldest_key, seq)) = self.insertion_order.pop_front() {
-            if let Some((cand, _time, stored_seq)) = self.map.remove(&oldest_key) {
-                // Verify sequence matches to handle cleanup race conditions
-                if stored_seq == seq {
-                    metrics().set_gauge("candidate_buffer_size", self.map.len() as u64);
-                    return Some(cand);
+            // Verify sequence matches to handle cleanup race conditions
+            if self.map.get(&oldest_key).is_some_and(|(_, _, stored_seq, _)| *stored_seq == seq) {
+                let (cand, _, _, _) = self.remove_entry(&oldest_key)?;
+                if cand.is_past_deadline(now_ms()) {
+                    metrics().increment_counter("candidate_deadline_exceeded");
+                    continue;
                 }
+                metrics().set_gauge("candidate_buffer_size", self.map.len() as u64);
+                return Some(cand);
             }
             // If sequence doesn't match, the entry was already removed, try next
         }
@@ -116,6 +281,57 @@ impl CandidateBuffer {
         None
     }
 
+    /// Remove and return the oldest eligible Jito-bundled candidate, if any.
+    /// Bundled candidates past their deadline are dropped along the way.
+    fn pop_oldest_jito(&mut self) -> Option<PremintCandidate> {
+        let now = now_ms();
+        let mut idx = 0;
+        while idx < self.insertion_order.len() {
+            let (key, seq) = self.insertion_order[idx];
+            let bundled = matches!(
+                self.map.get(&key),
+                Some((cand, _, stored_seq, _)) if *stored_seq == seq && cand.is_jito_bundle == Some(true)
+            );
+            if !bundled {
+                idx += 1;
+                continue;
+            }
+            self.insertion_order.remove(idx);
+            let (cand, _, _, _) = self.remove_entry(&key)?;
+            if cand.is_past_deadline(now) {
+                metrics().increment_counter("candidate_deadline_exceeded");
+                continue;
+            }
+            metrics().increment_counter("candidate_jito_preferred_total");
+            return Some(cand);
+        }
+        None
+    }
+
+    /// Remove and return the highest-scoring eligible candidate, oldest first on ties.
+    /// Its insertion-order slot is left behind and skipped later.
+    fn pop_highest_score(&mut self) -> Option<PremintCandidate> {
+        let now = now_ms();
+        while let Some((_, key)) = self.by_score.pop_last() {
+            let Some((cand, _, _, _)) = self.map.remove(&key) else {
+                continue;
+            };
+            if cand.is_past_deadline(now) {
+                metrics().increment_counter("candidate_deadline_exceeded");
+                continue;
+            }
+            return Some(cand);
+        }
+        None
+    }
+
+    /// Remove `key` from the map and the score index.
+    fn remove_entry(&mut self, key: &Pubkey) -> Option<(PremintCandidate, Instant, u64, u8)> {
+        let entry = self.map.remove(key)?;
+        self.by_score.remove(&(entry.3, Reverse(entry.2)));
+        Some(entry)
+    }
+
     /// Remove expired entries according to TTL.
     /// Returns the number of removed entries.
     pub fn cleanup(&mut self) -> usize {
@@ -124,6 +340,7 @@ impl CandidateBuffer {
             let removed = self.map.len();
             self.map.clear();
             self.insertion_order.clear();
+            self.by_score.clear();
             metrics().add_to_counter("candidate_dropped_due_ttl_total", removed as u64);
             metrics().set_gauge("candidate_buffer_size", 0);
             return removed;
@@ -135,12 +352,12 @@ impl CandidateBuffer {
         let expired_keys: Vec<Pubkey> = self
             .map
             .iter()
-            .filter(|(_, (_, seen_at, _))| now.duration_since(*seen_at) >= self.ttl)
+            .filter(|(_, (_, seen_at, _, _))| now.duration_since(*seen_at) >= self.ttl)
             .map(|(k, _)| *k)
             .collect();
             
         for key in &expired_keys {
-            self.map.remove(key);
+            self.remove_entry(key);
         }
         
         // Remove expired entries from insertion order
@@ -161,7 +378,83 @@ pub type SharedCandidateBuffer = Arc<Mutex<CandidateBuffer>>;
 
 /// Helper to create a shared CandidateBuffer wrapped in Arc<Mutex<...>>.
 pub fn new_shared(ttl: Duration, max_size: usize) -> SharedCandidateBuffer {
-    Arc::new(Mutex::new(CandidateBuffer::new(ttl, max_size)))
+    new_shared_with_policy(ttl, max_size, SelectionPolicy::default())
+}
+
+/// Like `new_shared`, with an explicit selection policy.
+pub fn new_shared_with_policy(ttl: Duration, max_size: usize, policy: SelectionPolicy) -> SharedCandidateBuffer {
+    Arc::new(Mutex::new(CandidateBuffer::new(ttl, max_size).with_policy(policy)))
+}
+
+/// Like `new_shared_with_policy`, restored from `path` when a buffer was saved there
+/// (see `CandidateBuffer::save`). A missing or unreadable file starts empty.
+pub fn restore_shared(path: &Path, ttl: Duration, max_size: usize, policy: SelectionPolicy) -> SharedCandidateBuffer {
+    if !path.exists() {
+        return new_shared_with_policy(ttl, max_size, policy);
+    }
+    match CandidateBuffer::load(path, ttl, max_size) {
+        Ok(buffer) => Arc::new(Mutex::new(buffer.with_policy(policy))),
+        Err(e) => {
+            warn!(path = %path.display(), error = %e, "Saved candidate buffer not restored; starting empty");
+            new_shared_with_policy(ttl, max_size, policy)
+        }
+    }
+}
+
+/// Forward items from `rx` to `out` through `buffer`, best first by its policy.
+///
+/// `entry` gives each item's candidate and score (`None` drops the item). Items wait
+/// in the buffer and one is popped with `pop_best` whenever `out` has room, so when
+/// the consumer falls behind it gets the best buffered item rather than the oldest.
+/// A mint already buffered is dropped, raising the buffered score when higher;
+/// expired and evicted items are never forwarded. Remaining items are flushed in
+/// policy order once `rx` closes.
+pub async fn forward_by_policy<T, F>(
+    mut rx: Receiver<T>,
+    buffer: SharedCandidateBuffer,
+    out: Sender<T>,
+    mut entry: F,
+) where
+    T: Send,
+    F: FnMut(&T) -> Option<(PremintCandidate, u8)>,
+{
+    let mut pending: HashMap<Pubkey, T> = HashMap::new();
+    loop {
+        tokio::select! {
+            // Take in everything already queued before picking the best
+            biased;
+            received = rx.recv() => {
+                let Some(item) = received else { break };
+                let Some((candidate, score)) = entry(&item) else { continue };
+                let mint = candidate.mint;
+                if buffer.lock().await.push_scored(candidate, score) {
+                    pending.insert(mint, item);
+                }
+            }
+            permit = out.reserve(), if !pending.is_empty() => {
+                let Ok(permit) = permit else { return };
+                if let Some(item) = pop_pending(&buffer, &mut pending).await {
+                    permit.send(item);
+                }
+            }
+        }
+    }
+    while !pending.is_empty() {
+        let Some(item) = pop_pending(&buffer, &mut pending).await else { break };
+        if out.send(item).await.is_err() {
+            return;
+        }
+    }
+}
+
+/// Pop the best buffered candidate's item, forgetting items whose candidate left the
+/// buffer without being popped (expired, evicted or past its deadline).
+async fn pop_pending<T>(buffer: &SharedCandidateBuffer, pending: &mut HashMap<Pubkey, T>) -> Option<T> {
+    let mut buffer = buffer.lock().await;
+    let best = buffer.pop_best();
+    let item = best.and_then(|candidate| pending.remove(&candidate.mint));
+    pending.retain(|mint, _| buffer.map.contains_key(mint));
+    item
 }
 
 #[cfg(test)]
@@ -186,6 +479,7 @@ mod tests {
             timestamp: ts,
             instruction_summary: None,
             is_jito_bundle: None,
+            deadline_ms: None,
         }
     }
 
@@ -272,6 +566,161 @@ mod tests {
         assert!(buf.pop_best().is_none());
     }
 
+    #[tokio::test]
+    async fn prefer_jito_pops_bundled_before_older_unbundled() {
+        let older = mk_candidate(20, 1);
+        let mut bundled = mk_candidate(21, 2);
+        bundled.is_jito_bundle = Some(true);
+
+        let mut buf = CandidateBuffer::new(Duration::from_secs(10), 10).with_policy(SelectionPolicy::PreferJito);
+        assert!(buf.push(older.clone()));
+        sleep(TokioDuration::from_millis(2)).await;
+        assert!(buf.push(bundled.clone()));
+
+        assert_eq!(buf.pop_best().unwrap().mint, bundled.mint);
+        assert_eq!(buf.pop_best().unwrap().mint, older.mint);
+        assert!(buf.pop_best().is_none());
+
+        // Default policy keeps plain oldest-first order
+        let mut buf = CandidateBuffer::new(Duration::from_secs(10), 10);
+        assert!(buf.push(older.clone()));
+        assert!(buf.push(bundled));
+        assert_eq!(buf.pop_best().unwrap().mint, older.mint);
+    }
+
+    #[tokio::test]
+    async fn highest_score_pops_max_and_breaks_ties_by_age() {
+        let low = mk_candidate(30, 1);
+        let tie_old = mk_candidate(31, 2);
+        let tie_new = mk_candidate(32, 3);
+        let unscored = mk_candidate(33, 4);
+
+        let mut buf = CandidateBuffer::new(Duration::from_secs(10), 10).with_policy(SelectionPolicy::HighestScore);
+        assert!(buf.push_scored(low.clone(), 10));
+        assert!(buf.push_scored(tie_old.clone(), 80));
+        assert!(buf.push_scored(tie_new.clone(), 80));
+        assert!(buf.push(unscored.clone()));
+
+        // A rescored duplicate is not inserted but moves up
+        assert!(!buf.push_scored(low.clone(), 90));
+        assert_eq!(buf.map.len(), 4);
+
+        let order: Vec<Pubkey> = std::iter::from_fn(|| buf.pop_best()).map(|c| c.mint).collect();
+        assert_eq!(order, vec![low.mint, tie_old.mint, tie_new.mint, unscored.mint]);
+
+        // Oldest policy ignores scores
+        let mut buf = CandidateBuffer::new(Duration::from_secs(10), 10);
+        assert!(buf.push_scored(low.clone(), 10));
+        assert!(buf.push_scored(tie_old.clone(), 80));
+        assert_eq!(buf.pop_best().unwrap().mint, low.mint);
+        assert_eq!(buf.pop_best().unwrap().mint, tie_old.mint);
+    }
+
+    #[test]
+    fn highest_score_eviction_skips_already_popped_entries() {
+        let mut buf = CandidateBuffer::new(Duration::from_secs(10), 2).with_policy(SelectionPolicy::HighestScore);
+        assert!(buf.push_scored(mk_candidate(40, 1), 1));
+        assert!(buf.push_scored(mk_candidate(41, 2), 50));
+        // Pops the newer, higher-scoring entry out of insertion order
+        assert_eq!(buf.pop_best().unwrap().mint, fixed_pubkey(41));
+
+        assert!(buf.push_scored(mk_candidate(42, 3), 5));
+        assert!(buf.push_scored(mk_candidate(43, 4), 7));
+        // Capacity evicted the oldest live entry (40), not the popped one
+        assert!(!buf.map.contains_key(&fixed_pubkey(40)));
+        assert_eq!(buf.pop_best().unwrap().mint, fixed_pubkey(43));
+        assert_eq!(buf.pop_best().unwrap().mint, fixed_pubkey(42));
+        assert!(buf.pop_best().is_none());
+    }
+
+    #[tokio::test]
+    async fn forwarding_hands_the_consumer_the_best_buffered_item() {
+        let buffer = new_shared_with_policy(Duration::from_secs(10), 10, SelectionPolicy::HighestScore);
+        let (tx, rx) = tokio::sync::mpsc::channel(8);
+        let (out_tx, mut out_rx) = tokio::sync::mpsc::channel(1);
+        // Queued while the consumer is busy: all buffered before the first is picked
+        for (byte, score) in [(50, 20), (51, 90), (52, 60), (51, 10), (53, 0)] {
+            tx.send((mk_candidate(byte, 1), score)).await.unwrap();
+        }
+        drop(tx);
+        let forwarder = tokio::spawn(forward_by_policy(rx, buffer.clone(), out_tx, |(candidate, score): &(PremintCandidate, u8)| {
+            // Unscored items are not forwarded
+            (*score > 0).then(|| (candidate.clone(), *score))
+        }));
+
+        let mut order = Vec::new();
+        while let Some((candidate, score)) = out_rx.recv().await {
+            order.push((candidate.mint, score));
+        }
+        forwarder.await.unwrap();
+        assert_eq!(order, vec![(fixed_pubkey(51), 90), (fixed_pubkey(52), 60), (fixed_pubkey(50), 20)]);
+        assert!(buffer.lock().await.map.is_empty());
+    }
+
+    #[test]
+    fn save_and_load_round_trip_drops_expired() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("candidates.json");
+
+        let mut buf = CandidateBuffer::new(Duration::from_secs(60), 10);
+        let stale = mk_candidate(50, 1);
+        let first = mk_candidate(51, 2);
+        let second = mk_candidate(52, 3);
+        assert!(buf.push(stale.clone()));
+        assert!(buf.push_scored(first.clone(), 20));
+        assert!(buf.push_scored(second.clone(), 90));
+        // Seen an hour ago: past the TTL by the time it is reloaded
+        buf.map.get_mut(&stale.mint).unwrap().1 = Instant::now() - Duration::from_secs(3600);
+        buf.save(&path).unwrap();
+
+        let mut loaded = CandidateBuffer::load(&path, Duration::from_secs(60), 10).unwrap();
+        assert_eq!(loaded.map.len(), 2);
+        assert!(!loaded.map.contains_key(&stale.mint));
+        assert!(!loaded.push(first.clone()), "reloaded mints still dedup");
+
+        // Sequence numbers continue after the restored entries
+        let third = mk_candidate(53, 4);
+        assert!(loaded.push(third.clone()));
+        let order: Vec<Pubkey> = std::iter::from_fn(|| loaded.pop_best()).map(|c| c.mint).collect();
+        assert_eq!(order, vec![first.mint, second.mint, third.mint]);
+
+        // An insertion time in the future has no valid age: dropped, not taken as fresh
+        let mut entries: Vec<SavedEntry> = serde_json::from_slice(&std::fs::read(&path).unwrap()).unwrap();
+        entries[1].inserted_ms = now_ms() + 3_600_000;
+        std::fs::write(&path, serde_json::to_vec(&entries).unwrap()).unwrap();
+        let skewed = CandidateBuffer::load(&path, Duration::from_secs(60), 10).unwrap();
+        assert_eq!(skewed.map.len(), 1);
+        assert!(!skewed.map.contains_key(&first.mint));
+        buf.save(&path).unwrap();
+
+        // Scores survive and are reindexed by with_policy
+        let mut scored = CandidateBuffer::load(&path, Duration::from_secs(60), 10)
+            .unwrap()
+            .with_policy(SelectionPolicy::HighestScore);
+        assert_eq!(scored.pop_best().unwrap().mint, second.mint);
+        assert_eq!(scored.pop_best().unwrap().mint, first.mint);
+    }
+
+    #[tokio::test]
+    async fn restore_shared_falls_back_to_an_empty_buffer() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("candidates.json");
+        let ttl = Duration::from_secs(60);
+
+        let missing = restore_shared(&path, ttl, 10, SelectionPolicy::HighestScore);
+        assert!(missing.lock().await.map.is_empty());
+
+        let mut buf = CandidateBuffer::new(ttl, 10);
+        buf.push_scored(mk_candidate(60, 1), 10);
+        buf.push_scored(mk_candidate(61, 2), 80);
+        buf.save(&path).unwrap();
+        let restored = restore_shared(&path, ttl, 10, SelectionPolicy::HighestScore);
+        assert_eq!(restored.lock().await.pop_best().unwrap().mint, fixed_pubkey(61));
+
+        fs::write(&path, b"not json").unwrap();
+        assert!(restore_shared(&path, ttl, 10, SelectionPolicy::Oldest).lock().await.map.is_empty());
+    }
+
     #[test]
     fn max_size_zero_protection() {
         let mut buf = CandidateBuffer::new(Duration::from_secs(30), 0);
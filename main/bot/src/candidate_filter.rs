@@ -0,0 +1,198 @@
+//! Which sniffed candidates the engine considers buying.
+//!
+//! A `CandidateFilter` accepts a candidate or rejects it with a reason for the
+//! decision trace. `from_config` combines the built-in filters the config enables:
+//! the `buy_programs` allowlist always, the Jito-bundle and minimum-age filters when
+//! set. Other heuristics plug in through `BuyEngine::with_candidate_filter`.
+
+use std::collections::HashMap;
+use std::time::Duration;
+
+use crate::config::Config;
+use crate::time_utils::now_ms;
+use crate::types::{DexProgram, PremintCandidate};
+
+/// Decides whether a candidate is a buy target.
+pub trait CandidateFilter: Send + Sync {
+    /// `Err` with the reason when `candidate` is not a buy target.
+    fn check(&self, candidate: &PremintCandidate) -> Result<(), String>;
+}
+
+/// Candidates whose program normalizes to one of the allowed DEXes.
+#[derive(Debug, Clone)]
+pub struct ProgramAllowlistFilter {
+    programs: Vec<DexProgram>,
+    aliases: HashMap<String, String>,
+}
+
+impl ProgramAllowlistFilter {
+    /// Allow `programs` (names or program ids), normalizing them and candidate
+    /// programs through `aliases`.
+    pub fn new(programs: &[String], aliases: HashMap<String, String>) -> Self {
+        let programs = programs.iter().map(|p| DexProgram::normalize(p, &aliases)).collect();
+        Self { programs, aliases }
+    }
+}
+
+impl CandidateFilter for ProgramAllowlistFilter {
+    fn check(&self, candidate: &PremintCandidate) -> Result<(), String> {
+        if self.programs.contains(&DexProgram::normalize(&candidate.program, &self.aliases)) {
+            Ok(())
+        } else {
+            Err(format!("Program '{}' is not a buy target", candidate.program))
+        }
+    }
+}
+
+/// Only candidates seen in a Jito bundle.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct JitoBundleOnlyFilter;
+
+impl CandidateFilter for JitoBundleOnlyFilter {
+    fn check(&self, candidate: &PremintCandidate) -> Result<(), String> {
+        match candidate.is_jito_bundle {
+            Some(true) => Ok(()),
+            _ => Err("Candidate was not seen in a Jito bundle".to_string()),
+        }
+    }
+}
+
+/// Candidates created at least `min_age` ago, by their (second-resolution) timestamp.
+#[derive(Debug, Clone, Copy)]
+pub struct MinAgeFilter {
+    min_age: Duration,
+}
+
+impl MinAgeFilter {
+    pub fn new(min_age: Duration) -> Self {
+        Self { min_age }
+    }
+}
+
+impl CandidateFilter for MinAgeFilter {
+    fn check(&self, candidate: &PremintCandidate) -> Result<(), String> {
+        let age_ms = now_ms().saturating_sub(candidate.timestamp.saturating_mul(1000));
+        if age_ms >= self.min_age.as_millis() as u64 {
+            Ok(())
+        } else {
+            Err(format!("Candidate is {}ms old, younger than {}ms", age_ms, self.min_age.as_millis()))
+        }
+    }
+}
+
+/// Candidates every one of its filters accepts; the first rejection is reported.
+#[derive(Default)]
+pub struct AllOf {
+    filters: Vec<Box<dyn CandidateFilter>>,
+}
+
+impl AllOf {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Also require `filter`.
+    pub fn and(mut self, filter: impl CandidateFilter + 'static) -> Self {
+        self.filters.push(Box::new(filter));
+        self
+    }
+}
+
+impl CandidateFilter for AllOf {
+    fn check(&self, candidate: &PremintCandidate) -> Result<(), String> {
+        self.filters.iter().try_for_each(|filter| filter.check(candidate))
+    }
+}
+
+/// The filters `config` enables. DEXes disabled in `dexes` are left out of the
+/// `buy_programs` allowlist.
+pub fn from_config(config: &Config) -> AllOf {
+    let programs: Vec<String> = config
+        .buy_programs
+        .iter()
+        .filter(|p| config.dex_enabled(&DexProgram::normalize(p, &config.program_aliases)))
+        .cloned()
+        .collect();
+    let mut filter = AllOf::new().and(ProgramAllowlistFilter::new(&programs, config.program_aliases.clone()));
+    if config.buy_jito_bundles_only {
+        filter = filter.and(JitoBundleOnlyFilter);
+    }
+    if config.min_candidate_age_ms > 0 {
+        filter = filter.and(MinAgeFilter::new(Duration::from_millis(config.min_candidate_age_ms)));
+    }
+    filter
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{LETSBONK_PROGRAM_ID, PUMP_FUN_PROGRAM_ID};
+    use solana_sdk::pubkey::Pubkey;
+
+    fn candidate(program: &str, is_jito_bundle: Option<bool>, timestamp: u64) -> PremintCandidate {
+        PremintCandidate {
+            mint: Pubkey::new_unique(),
+            creator: Pubkey::new_unique(),
+            program: program.to_string(),
+            slot: 0,
+            timestamp,
+            instruction_summary: None,
+            is_jito_bundle,
+            deadline_ms: None,
+        }
+    }
+
+    #[test]
+    fn program_allowlist_matches_names_ids_and_aliases() {
+        let aliases = HashMap::from([("bonk-fork".to_string(), "letsbonk".to_string())]);
+        let filter = ProgramAllowlistFilter::new(&["pump.fun".to_string(), LETSBONK_PROGRAM_ID.to_string()], aliases);
+
+        assert!(filter.check(&candidate("PumpFun", None, 0)).is_ok());
+        assert!(filter.check(&candidate(PUMP_FUN_PROGRAM_ID, None, 0)).is_ok());
+        assert!(filter.check(&candidate("letsbonk", None, 0)).is_ok());
+        assert!(filter.check(&candidate("bonk-fork", None, 0)).is_ok());
+        let rejected = filter.check(&candidate("raydium", None, 0)).unwrap_err();
+        assert!(rejected.contains("raydium"));
+    }
+
+    #[test]
+    fn jito_bundle_only_rejects_unbundled_and_unknown() {
+        assert!(JitoBundleOnlyFilter.check(&candidate("pump.fun", Some(true), 0)).is_ok());
+        assert!(JitoBundleOnlyFilter.check(&candidate("pump.fun", Some(false), 0)).is_err());
+        assert!(JitoBundleOnlyFilter.check(&candidate("pump.fun", None, 0)).is_err());
+    }
+
+    #[test]
+    fn min_age_waits_for_the_candidate_timestamp() {
+        let now_secs = now_ms() / 1000;
+        let filter = MinAgeFilter::new(Duration::from_secs(30));
+
+        assert!(filter.check(&candidate("pump.fun", None, now_secs - 60)).is_ok());
+        assert!(filter.check(&candidate("pump.fun", None, now_secs)).is_err());
+        // A timestamp in the future is as young as it gets
+        assert!(filter.check(&candidate("pump.fun", None, now_secs + 60)).is_err());
+    }
+
+    #[test]
+    fn all_of_requires_every_filter_and_reports_the_first_rejection() {
+        let now_secs = now_ms() / 1000;
+        let filter = AllOf::new()
+            .and(ProgramAllowlistFilter::new(&["pump.fun".to_string()], HashMap::new()))
+            .and(JitoBundleOnlyFilter)
+            .and(MinAgeFilter::new(Duration::from_secs(30)));
+
+        assert!(filter.check(&candidate("pump.fun", Some(true), now_secs - 60)).is_ok());
+        assert!(filter.check(&candidate("raydium", Some(false), now_secs)).unwrap_err().contains("raydium"));
+        assert!(filter.check(&candidate("pump.fun", Some(false), now_secs - 60)).unwrap_err().contains("Jito"));
+        assert!(filter.check(&candidate("pump.fun", Some(true), now_secs)).unwrap_err().contains("younger"));
+        // No filters accept everything
+        assert!(AllOf::new().check(&candidate("raydium", None, 0)).is_ok());
+
+        // From config: a disabled DEX drops out of the allowlist
+        let mut config = Config { buy_jito_bundles_only: true, ..Config::default() };
+        assert!(from_config(&config).check(&candidate("pump.fun", Some(true), 0)).is_ok());
+        assert!(from_config(&config).check(&candidate("pump.fun", None, 0)).is_err());
+        config.dexes.insert("pump.fun".to_string(), crate::config::DexConfig { enabled: false, ..Default::default() });
+        assert!(from_config(&config).check(&candidate("pump.fun", Some(true), 0)).is_err());
+    }
+}
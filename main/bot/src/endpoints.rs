@@ -1,14 +1,116 @@
-use crate::metrics::{metrics, MetricsSnapshot};
+use crate::buy_engine::EngineCommand;
+use crate::config::Config;
+use crate::metrics::{funnel, metrics};
+use crate::portfolio::portfolio;
+use crate::simulation::simulation_candidate;
+use crate::types::AppState;
+use anyhow::Context;
+use hyper::header::{AUTHORIZATION, CONTENT_TYPE};
+use hyper::service::{make_service_fn, service_fn};
+use hyper::{Body, Method, Request, Response, StatusCode};
 use serde_json::json;
+use solana_sdk::pubkey::Pubkey;
 use std::collections::HashMap;
+use std::convert::Infallible;
+use std::net::{SocketAddr, TcpListener};
+use std::str::FromStr;
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
 use std::sync::Arc;
-use tokio::sync::RwLock;
+use thiserror::Error;
+use std::time::Duration;
+use tokio::sync::{mpsc, oneshot, Mutex, RwLock};
+use tracing::info;
 
-/// Simple HTTP-like endpoint system for metrics and status
+/// Default cap on scoreboard entries (one per mint).
+pub const DEFAULT_SCOREBOARD_MAX_ENTRIES: usize = 1000;
+
+/// Content type of the JSON endpoints.
+pub const JSON_CONTENT_TYPE: &str = "application/json";
+/// Content type of `/metrics`, the Prometheus text exposition format.
+pub const PROMETHEUS_CONTENT_TYPE: &str = "text/plain; version=0.0.4";
+
+/// Longest `/simulate` waits for the engine, which answers between candidates.
+const SIMULATION_REPLY_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Metrics and status endpoints, served over HTTP by `serve`
 #[derive(Debug)]
 pub struct EndpointServer {
     /// Scoreboard data for ranking
     scoreboard: Arc<RwLock<HashMap<String, ScoreboardEntry>>>,
+    /// Cap on scoreboard entries; least-recently-updated entries are evicted beyond it
+    max_scoreboard_entries: AtomicUsize,
+    /// Monotonic update counter used for LRU ordering
+    update_seq: AtomicU64,
+    /// Totals of entries evicted from the scoreboard
+    evicted: Arc<RwLock<EvictedStats>>,
+    /// Per-program rollups of scoreboard updates
+    program_stats: Arc<RwLock<HashMap<String, ProgramStats>>>,
+    /// Engine config/state for `/simulate` and `/operations`; unset until the engine registers it
+    simulation: Arc<RwLock<Option<SimulationContext>>>,
+}
+
+/// What `/simulate` needs from the running engine.
+#[derive(Debug, Clone)]
+struct SimulationContext {
+    config: Config,
+    app_state: Arc<Mutex<AppState>>,
+    /// The engine's command channel; what-if queries run through its own buy checks
+    commands: mpsc::Sender<EngineCommand>,
+}
+
+/// Errors from endpoints that validate their request.
+#[derive(Debug, Error, PartialEq)]
+pub enum EndpointError {
+    #[error("unauthorized")]
+    Unauthorized,
+    #[error("bad request: {0}")]
+    BadRequest(String),
+    #[error("unavailable: {0}")]
+    Unavailable(String),
+}
+
+impl EndpointError {
+    fn status(&self) -> StatusCode {
+        match self {
+            EndpointError::Unauthorized => StatusCode::UNAUTHORIZED,
+            EndpointError::BadRequest(_) => StatusCode::BAD_REQUEST,
+            EndpointError::Unavailable(_) => StatusCode::SERVICE_UNAVAILABLE,
+        }
+    }
+}
+
+/// Aggregate of scoreboard entries dropped by the size cap.
+#[derive(Debug, Clone, Default, PartialEq, serde::Serialize)]
+pub struct EvictedStats {
+    pub entries: u64,
+    pub buy_attempts: u64,
+    pub buy_successes: u64,
+}
+
+/// Aggregate buy outcomes for one program (e.g. pump.fun vs Raydium).
+#[derive(Debug, Clone, Default, PartialEq, serde::Serialize)]
+pub struct ProgramStats {
+    pub program: String,
+    pub buy_attempts: u64,
+    pub buy_successes: u64,
+    pub success_rate: f64,
+    /// Average latency of successful buys; failures end at arbitrary points
+    /// (a rejected build, a timeout) and would skew it either way.
+    pub avg_latency_ms: f64,
+    #[serde(skip)]
+    total_success_latency_ms: u64,
+}
+
+impl ProgramStats {
+    fn record(&mut self, success: bool, latency_ms: u64) {
+        self.buy_attempts += 1;
+        if success {
+            self.buy_successes += 1;
+            self.total_success_latency_ms += latency_ms;
+            self.avg_latency_ms = self.total_success_latency_ms as f64 / self.buy_successes as f64;
+        }
+        self.success_rate = self.buy_successes as f64 / self.buy_attempts as f64;
+    }
 }
 
 #[derive(Debug, Clone, serde::Serialize)]
@@ -20,18 +122,66 @@ pub struct ScoreboardEntry {
     pub last_success_timestamp: Option<u64>,
     pub success_rate: f64,
     pub avg_latency_ms: f64,
+    #[serde(skip)]
+    update_seq: u64,
 }
 
 impl EndpointServer {
     pub fn new() -> Self {
+        Self::with_max_entries(DEFAULT_SCOREBOARD_MAX_ENTRIES)
+    }
+
+    pub fn with_max_entries(max_scoreboard_entries: usize) -> Self {
         Self {
             scoreboard: Arc::new(RwLock::new(HashMap::new())),
+            max_scoreboard_entries: AtomicUsize::new(max_scoreboard_entries.max(1)),
+            update_seq: AtomicU64::new(0),
+            evicted: Arc::new(RwLock::new(EvictedStats::default())),
+            program_stats: Arc::new(RwLock::new(HashMap::new())),
+            simulation: Arc::new(RwLock::new(None)),
         }
     }
 
+    /// Enable `/simulate` against the engine's config, state and command channel.
+    pub async fn enable_simulation(
+        &self,
+        config: Config,
+        app_state: Arc<Mutex<AppState>>,
+        commands: mpsc::Sender<EngineCommand>,
+    ) {
+        *self.simulation.write().await = Some(SimulationContext { config, app_state, commands });
+    }
+
+    /// Change the scoreboard cap; applies from the next update.
+    pub fn set_max_scoreboard_entries(&self, max_entries: usize) {
+        self.max_scoreboard_entries.store(max_entries.max(1), Ordering::Relaxed);
+    }
+
+    /// Totals of entries evicted so far.
+    pub async fn evicted_stats(&self) -> EvictedStats {
+        self.evicted.read().await.clone()
+    }
+
+    /// Per-program rollups, sorted by program name.
+    pub async fn program_stats(&self) -> Vec<ProgramStats> {
+        let mut stats: Vec<_> = self.program_stats.read().await.values().cloned().collect();
+        stats.sort_by(|a, b| a.program.cmp(&b.program));
+        stats
+    }
+
     /// Update scoreboard entry
     pub async fn update_scoreboard(&self, mint: &str, program: &str, success: bool, latency_ms: u64) {
+        self.program_stats
+            .write()
+            .await
+            .entry(program.to_string())
+            .or_insert_with(|| ProgramStats { program: program.to_string(), ..Default::default() })
+            .record(success, latency_ms);
+
         let mut scoreboard = self.scoreboard.write().await;
+        if !scoreboard.contains_key(mint) {
+            self.evict_for_insert(&mut scoreboard).await;
+        }
         let entry = scoreboard.entry(mint.to_string()).or_insert_with(|| ScoreboardEntry {
             mint: mint.to_string(),
             program: program.to_string(),
@@ -40,8 +190,10 @@ impl EndpointServer {
             last_success_timestamp: None,
             success_rate: 0.0,
             avg_latency_ms: 0.0,
+            update_seq: 0,
         });
 
+        entry.update_seq = self.update_seq.fetch_add(1, Ordering::Relaxed);
         entry.buy_attempts += 1;
         if success {
             entry.buy_successes += 1;
@@ -67,10 +219,34 @@ impl EndpointServer {
         }
     }
 
-    /// Get metrics endpoint response
+    /// Make room for one new entry, folding evicted entries into `evicted`.
+    async fn evict_for_insert(&self, scoreboard: &mut HashMap<String, ScoreboardEntry>) {
+        let max_entries = self.max_scoreboard_entries.load(Ordering::Relaxed);
+        if scoreboard.len() < max_entries {
+            return;
+        }
+
+        let mut by_age: Vec<(u64, String)> = scoreboard
+            .iter()
+            .map(|(mint, entry)| (entry.update_seq, mint.clone()))
+            .collect();
+        by_age.sort_unstable();
+
+        let excess = scoreboard.len() + 1 - max_entries;
+        let mut evicted = self.evicted.write().await;
+        for (_, mint) in by_age.into_iter().take(excess) {
+            if let Some(entry) = scoreboard.remove(&mint) {
+                evicted.entries += 1;
+                evicted.buy_attempts += entry.buy_attempts;
+                evicted.buy_successes += entry.buy_successes;
+            }
+        }
+        metrics().add_to_counter("scoreboard_evictions_total", excess as u64);
+    }
+
+    /// Get metrics endpoint response, in the Prometheus text format
     pub fn get_metrics_response(&self) -> String {
-        let metrics_snapshot = metrics().export_metrics();
-        self.format_prometheus_metrics(&metrics_snapshot)
+        metrics().render_prometheus()
     }
 
     /// Get health endpoint response
@@ -107,6 +283,145 @@ impl EndpointServer {
         }).to_string()
     }
 
+    /// Get candidate funnel endpoint response (`/funnel`)
+    pub fn get_funnel_response(&self) -> String {
+        json!({
+            "funnel": funnel().snapshot(),
+            "timestamp": std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_secs()
+        }).to_string()
+    }
+
+    /// Get unrealized portfolio value endpoint response (`/portfolio`)
+    pub fn get_portfolio_response(&self) -> String {
+        let portfolio = portfolio();
+        let total_lamports = portfolio.total_value_lamports();
+        json!({
+            "total_value_lamports": total_lamports,
+            "total_value_sol": total_lamports as f64 / 1_000_000_000.0,
+            "positions": portfolio.positions(),
+            "timestamp": std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_secs()
+        }).to_string()
+    }
+
+    /// Get per-program rollup endpoint response (`/programs`)
+    pub async fn get_programs_response(&self) -> String {
+        json!({
+            "programs": self.program_stats().await,
+            "timestamp": std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_secs()
+        }).to_string()
+    }
+
+    /// Get in-flight operations endpoint response (`/operations`), keyed by correlation id.
+    pub async fn get_operations_response(&self) -> Result<String, EndpointError> {
+        let app_state = self
+            .simulation
+            .read()
+            .await
+            .as_ref()
+            .map(|ctx| ctx.app_state.clone())
+            .ok_or_else(|| EndpointError::Unavailable("engine state not registered".to_string()))?;
+        let operations: Vec<_> = app_state
+            .lock()
+            .await
+            .active_operations()
+            .iter()
+            .map(|(id, op)| json!({
+                "correlation_id": id,
+                "kind": op.kind,
+                "mint": op.mint.to_string(),
+                "stage": op.stage,
+                "started_ms": op.started_ms,
+            }))
+            .collect();
+        Ok(json!({
+            "operations": operations,
+            "timestamp": std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_secs()
+        }).to_string())
+    }
+
+    /// Get what-if endpoint response (`/simulate?mint=<mint>[&creator=<creator>]`).
+    ///
+    /// Requires `auth_token` to match the configured `endpoint_auth_token`; with no
+    /// token configured the endpoint is refused. Nothing is built or broadcast.
+    pub async fn get_simulate_response(
+        &self,
+        mint: &str,
+        creator: Option<&str>,
+        auth_token: Option<&str>,
+    ) -> Result<String, EndpointError> {
+        let ctx = self
+            .simulation
+            .read()
+            .await
+            .clone()
+            .ok_or_else(|| EndpointError::Unavailable("simulation not enabled".to_string()))?;
+        if !auth_token_matches(ctx.config.endpoint_auth_token.as_deref(), auth_token) {
+            return Err(EndpointError::Unauthorized);
+        }
+
+        let mint = Pubkey::from_str(mint).map_err(|e| EndpointError::BadRequest(format!("mint: {}", e)))?;
+        let creator = creator
+            .map(Pubkey::from_str)
+            .transpose()
+            .map_err(|e| EndpointError::BadRequest(format!("creator: {}", e)))?;
+
+        let candidate = simulation_candidate(mint, creator, "pump.fun");
+        let (reply, result) = oneshot::channel();
+        let unavailable = || EndpointError::Unavailable("engine not responding".to_string());
+        ctx.commands
+            .send(EngineCommand::Simulate { candidate, reply })
+            .await
+            .map_err(|_| unavailable())?;
+        let simulation = tokio::time::timeout(SIMULATION_REPLY_TIMEOUT, result)
+            .await
+            .map_err(|_| unavailable())?
+            .map_err(|_| unavailable())?;
+        metrics().increment_counter("endpoint_simulations_total");
+
+        Ok(json!({
+            "simulation": simulation,
+            "timestamp": std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_secs()
+        }).to_string())
+    }
+
+    /// Get effective configuration endpoint response (`/config`), secrets redacted.
+    ///
+    /// Authenticated like `/simulate`.
+    pub async fn get_config_response(&self, auth_token: Option<&str>) -> Result<String, EndpointError> {
+        let ctx = self
+            .simulation
+            .read()
+            .await
+            .clone()
+            .ok_or_else(|| EndpointError::Unavailable("engine config not registered".to_string()))?;
+        if !auth_token_matches(ctx.config.endpoint_auth_token.as_deref(), auth_token) {
+            return Err(EndpointError::Unauthorized);
+        }
+
+        Ok(json!({
+            "config": ctx.config.sanitized(),
+            "timestamp": std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_secs()
+        }).to_string())
+    }
+
     /// Get scoreboard endpoint response
     pub async fn get_scoreboard_response(&self, limit: Option<usize>) -> String {
         let scoreboard = self.scoreboard.read().await;
@@ -126,6 +441,7 @@ impl EndpointServer {
         json!({
             "scoreboard": entries,
             "total_entries": scoreboard.len(),
+            "evicted": self.evicted_stats().await,
             "timestamp": std::time::SystemTime::now()
                 .duration_since(std::time::UNIX_EPOCH)
                 .unwrap_or_default()
@@ -133,38 +449,69 @@ impl EndpointServer {
         }).to_string()
     }
 
-    /// Format metrics in Prometheus format
-    fn format_prometheus_metrics(&self, snapshot: &MetricsSnapshot) -> String {
-        let mut output = String::new();
+    /// Serve the endpoints over HTTP on `addr`; runs until the task is aborted.
+    pub async fn serve(self: Arc<Self>, addr: SocketAddr) -> anyhow::Result<()> {
+        let listener = TcpListener::bind(addr).with_context(|| format!("bind endpoint server to {}", addr))?;
+        self.serve_listener(listener).await
+    }
 
-        // Format counters
-        for (name, value) in &snapshot.counters {
-            output.push_str(&format!("# TYPE {} counter\n", name));
-            output.push_str(&format!("{} {}\n", name, value));
-        }
+    /// Serve the endpoints on an already bound `listener`.
+    pub async fn serve_listener(self: Arc<Self>, listener: TcpListener) -> anyhow::Result<()> {
+        listener.set_nonblocking(true).context("set endpoint listener non-blocking")?;
+        let addr = listener.local_addr().context("endpoint listener address")?;
+        let make_service = make_service_fn(move |_conn| {
+            let server = self.clone();
+            async move {
+                Ok::<_, Infallible>(service_fn(move |request| {
+                    let server = server.clone();
+                    async move { Ok::<_, Infallible>(server.handle_request(request).await) }
+                }))
+            }
+        });
+        info!(%addr, "Endpoint server listening");
+        hyper::Server::from_tcp(listener)
+            .context("start endpoint server")?
+            .serve(make_service)
+            .await
+            .context("endpoint server failed")
+    }
 
-        // Format gauges
-        for (name, value) in &snapshot.gauges {
-            output.push_str(&format!("# TYPE {} gauge\n", name));
-            output.push_str(&format!("{} {}\n", name, value));
+    /// Route an HTTP request to its endpoint. `/simulate` and `/config` take the
+    /// auth token as `Authorization: Bearer <token>`.
+    pub async fn handle_request(&self, request: Request<Body>) -> Response<Body> {
+        if request.method() != Method::GET {
+            return error_response(StatusCode::METHOD_NOT_ALLOWED, "method not allowed");
         }
+        let query = parse_query(request.uri().query());
+        let auth_token = request
+            .headers()
+            .get(AUTHORIZATION)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.strip_prefix("Bearer "));
 
-        // Format histograms
-        for (name, stats) in &snapshot.histograms {
-            output.push_str(&format!("# TYPE {}_count counter\n", name));
-            output.push_str(&format!("{}_count {}\n", name, stats.count));
-            
-            output.push_str(&format!("# TYPE {} histogram\n", name));
-            output.push_str(&format!("{}_bucket{{le=\"50\"}} {}\n", name, stats.p50));
-            output.push_str(&format!("{}_bucket{{le=\"95\"}} {}\n", name, stats.p95));
-            output.push_str(&format!("{}_bucket{{le=\"99\"}} {}\n", name, stats.p99));
-            output.push_str(&format!("{}_bucket{{le=\"+Inf\"}} {}\n", name, stats.count));
-            
-            output.push_str(&format!("{}_min {}\n", name, stats.min));
-            output.push_str(&format!("{}_max {}\n", name, stats.max));
+        let result = match request.uri().path() {
+            "/health" => Ok(self.get_health_response()),
+            "/metrics" => return response(StatusCode::OK, PROMETHEUS_CONTENT_TYPE, self.get_metrics_response()),
+            "/status" => Ok(self.get_status_response().await),
+            "/scoreboard" => {
+                let limit = query.get("limit").and_then(|limit| limit.parse().ok());
+                Ok(self.get_scoreboard_response(limit).await)
+            }
+            "/funnel" => Ok(self.get_funnel_response()),
+            "/portfolio" => Ok(self.get_portfolio_response()),
+            "/programs" => Ok(self.get_programs_response().await),
+            "/operations" => self.get_operations_response().await,
+            "/simulate" => match query.get("mint") {
+                Some(mint) => self.get_simulate_response(mint, query.get("creator").copied(), auth_token).await,
+                None => Err(EndpointError::BadRequest("mint is required".to_string())),
+            },
+            "/config" => self.get_config_response(auth_token).await,
+            _ => return error_response(StatusCode::NOT_FOUND, "not found"),
+        };
+        match result {
+            Ok(body) => response(StatusCode::OK, JSON_CONTENT_TYPE, body),
+            Err(e) => error_response(e.status(), &e.to_string()),
         }
-
-        output
     }
 
     /// Cleanup old scoreboard entries
@@ -209,17 +556,53 @@ impl Default for EndpointServer {
     }
 }
 
+fn response(status: StatusCode, content_type: &'static str, body: String) -> Response<Body> {
+    let mut response = Response::new(Body::from(body));
+    *response.status_mut() = status;
+    response.headers_mut().insert(CONTENT_TYPE, hyper::header::HeaderValue::from_static(content_type));
+    response
+}
+
+fn error_response(status: StatusCode, error: &str) -> Response<Body> {
+    response(status, JSON_CONTENT_TYPE, json!({ "error": error }).to_string())
+}
+
+/// Query parameters as written; the endpoints take pubkeys and numbers, which
+/// need no percent-decoding.
+fn parse_query(query: Option<&str>) -> HashMap<&str, &str> {
+    query
+        .unwrap_or("")
+        .split('&')
+        .filter_map(|pair| pair.split_once('='))
+        .collect()
+}
+
 /// Global endpoint server instance
-static GLOBAL_ENDPOINT_SERVER: std::sync::OnceLock<EndpointServer> = std::sync::OnceLock::new();
+static GLOBAL_ENDPOINT_SERVER: std::sync::OnceLock<Arc<EndpointServer>> = std::sync::OnceLock::new();
 
 /// Get global endpoint server
-pub fn endpoint_server() -> &'static EndpointServer {
-    GLOBAL_ENDPOINT_SERVER.get_or_init(EndpointServer::new)
+pub fn endpoint_server() -> &'static Arc<EndpointServer> {
+    GLOBAL_ENDPOINT_SERVER.get_or_init(|| Arc::new(EndpointServer::new()))
+}
+
+/// Whether `given` is the configured `endpoint_auth_token`; with none configured
+/// nothing is. Compared in constant time, so response timing does not tell how much
+/// of a guess was right.
+fn auth_token_matches(expected: Option<&str>, given: Option<&str>) -> bool {
+    let (Some(expected), Some(given)) = (expected, given) else {
+        return false;
+    };
+    let (expected, given) = (expected.as_bytes(), given.as_bytes());
+    expected.len() == given.len() && expected.iter().zip(given).fold(0u8, |diff, (a, b)| diff | (a ^ b)) == 0
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::buy_engine::BuyEngine;
+    use crate::nonce_manager::NonceManager;
+    use crate::test_support::CountingBroadcaster;
+    use std::collections::BTreeMap;
 
     #[tokio::test]
     async fn test_scoreboard_updates() {
@@ -234,6 +617,165 @@ mod tests {
         assert!(response.contains("mint2"));
     }
 
+    #[tokio::test]
+    async fn test_scoreboard_cap_evicts_least_recently_updated() {
+        let server = EndpointServer::with_max_entries(3);
+
+        server.update_scoreboard("mint1", "pump.fun", true, 100).await;
+        server.update_scoreboard("mint2", "pump.fun", false, 100).await;
+        server.update_scoreboard("mint3", "pump.fun", true, 100).await;
+        // Touch mint1 so mint2 becomes the oldest
+        server.update_scoreboard("mint1", "pump.fun", true, 100).await;
+
+        server.update_scoreboard("mint4", "pump.fun", true, 100).await;
+        {
+            let scoreboard = server.scoreboard.read().await;
+            assert_eq!(scoreboard.len(), 3);
+            assert!(!scoreboard.contains_key("mint2"));
+            assert!(scoreboard.contains_key("mint1"));
+        }
+
+        server.update_scoreboard("mint5", "pump.fun", true, 100).await;
+        let scoreboard = server.scoreboard.read().await;
+        assert!(!scoreboard.contains_key("mint3"));
+        assert!(scoreboard.contains_key("mint5"));
+        drop(scoreboard);
+
+        assert_eq!(
+            server.evicted_stats().await,
+            EvictedStats { entries: 2, buy_attempts: 2, buy_successes: 1 }
+        );
+        assert!(server.get_scoreboard_response(None).await.contains("\"evicted\""));
+    }
+
+    #[tokio::test]
+    async fn test_program_rollups() {
+        let server = EndpointServer::new();
+
+        server.update_scoreboard("mint1", "pump.fun", true, 100).await;
+        server.update_scoreboard("mint1", "pump.fun", false, 300).await;
+        server.update_scoreboard("mint2", "pump.fun", true, 200).await;
+        server.update_scoreboard("mint3", "raydium", false, 400).await;
+        server.update_scoreboard("mint4", "raydium", true, 200).await;
+        server.update_scoreboard("mint4", "raydium", false, 300).await;
+        // A failure that ends at once does not pull the average down
+        server.update_scoreboard("mint4", "raydium", false, 0).await;
+        server.update_scoreboard("mint5", "orca", false, 250).await;
+
+        let stats = server.program_stats().await;
+        assert_eq!(stats.len(), 3);
+
+        // Only failures: no latency to average yet
+        let orca = &stats[0];
+        assert_eq!(orca.program, "orca");
+        assert_eq!((orca.buy_attempts, orca.buy_successes), (1, 0));
+        assert_eq!(orca.avg_latency_ms, 0.0);
+
+        let pump = &stats[1];
+        assert_eq!(pump.program, "pump.fun");
+        assert_eq!((pump.buy_attempts, pump.buy_successes), (3, 2));
+        assert!((pump.success_rate - 2.0 / 3.0).abs() < 1e-9);
+        assert_eq!(pump.avg_latency_ms, 150.0);
+
+        let raydium = &stats[2];
+        assert_eq!(raydium.program, "raydium");
+        assert_eq!((raydium.buy_attempts, raydium.buy_successes), (4, 1));
+        assert_eq!(raydium.success_rate, 0.25);
+        assert_eq!(raydium.avg_latency_ms, 200.0);
+
+        let response = server.get_programs_response().await;
+        assert!(response.contains("\"programs\""));
+        assert!(response.contains("raydium"));
+    }
+
+    #[tokio::test]
+    async fn test_simulate_requires_token_and_returns_decision() {
+        let server = EndpointServer::new();
+        let mint = Pubkey::new_unique().to_string();
+        let creator = Pubkey::new_unique().to_string();
+        assert!(matches!(
+            server.get_simulate_response(&mint, None, Some("secret")).await,
+            Err(EndpointError::Unavailable(_))
+        ));
+
+        let config = Config { endpoint_auth_token: Some("secret".to_string()), ..Config::default() };
+        let app_state = Arc::new(Mutex::new(AppState::sniffing()));
+        let (command_tx, command_rx) = mpsc::channel(1);
+        let (_candidate_tx, candidate_rx) = mpsc::channel(1);
+        let mut engine = BuyEngine::new(
+            Arc::new(CountingBroadcaster::new(0)),
+            Arc::new(NonceManager::new(1)),
+            candidate_rx,
+            app_state.clone(),
+            config.clone(),
+            None,
+        )
+        .with_commands(command_rx);
+        let engine_task = tokio::spawn(async move { engine.run().await });
+        server.enable_simulation(config, app_state, command_tx).await;
+
+        assert_eq!(server.get_simulate_response(&mint, None, None).await, Err(EndpointError::Unauthorized));
+        assert_eq!(server.get_simulate_response(&mint, None, Some("wrong")).await, Err(EndpointError::Unauthorized));
+        assert!(matches!(
+            server.get_simulate_response("not-a-mint", None, Some("secret")).await,
+            Err(EndpointError::BadRequest(_))
+        ));
+
+        let response = server.get_simulate_response(&mint, Some(&creator), Some("secret")).await.unwrap();
+        let body: serde_json::Value = serde_json::from_str(&response).unwrap();
+        assert_eq!(body["simulation"]["mint"], mint.as_str());
+        assert_eq!(body["simulation"]["would_buy"], true);
+        assert!(body["simulation"]["params"]["buy_amount_lamports"].as_u64().unwrap() > 0);
+        engine_task.abort();
+    }
+
+    #[tokio::test]
+    async fn test_config_response_redacts_secrets() {
+        let server = EndpointServer::new();
+        let config = Config {
+            keypair_path: Some("/home/op/id.json".to_string()),
+            endpoint_auth_token: Some("secret".to_string()),
+            dexes: BTreeMap::from([(
+                "pump.fun".to_string(),
+                crate::config::DexConfig {
+                    endpoint: Some("https://pumpportal.example/api".to_string()),
+                    api_key: Some("dex-key".to_string()),
+                    ..Default::default()
+                },
+            )]),
+            nonce_count: 7,
+            min_sol_reserve_lamports: 12_345,
+            ..Config::default()
+        };
+        server.enable_simulation(config, Arc::new(Mutex::new(AppState::sniffing())), mpsc::channel(1).0).await;
+
+        assert_eq!(server.get_config_response(None).await, Err(EndpointError::Unauthorized));
+
+        let response = server.get_config_response(Some("secret")).await.unwrap();
+        assert!(!response.contains("/home/op/id.json"));
+        assert!(!response.contains("dex-key"));
+        assert!(!response.contains("\"secret\""));
+
+        let body: serde_json::Value = serde_json::from_str(&response).unwrap();
+        let config = &body["config"];
+        assert_eq!(config["keypair_path"], crate::config::REDACTED);
+        assert_eq!(config["dexes"]["pump.fun"]["api_key"], crate::config::REDACTED);
+        assert_eq!(config["dexes"]["pump.fun"]["endpoint"], "https://pumpportal.example/api");
+        assert_eq!(config["nonce_count"], 7);
+        assert_eq!(config["min_sol_reserve_lamports"], 12_345);
+        assert!(config["sell_escalation_slippage_bps"].is_u64());
+        assert!(config["rpc_endpoints"].is_array());
+    }
+
+    #[test]
+    fn test_auth_token_comparison() {
+        assert!(auth_token_matches(Some("secret"), Some("secret")));
+        assert!(!auth_token_matches(Some("secret"), Some("secreT")));
+        assert!(!auth_token_matches(Some("secret"), Some("secret-and-more")));
+        assert!(!auth_token_matches(Some("secret"), None));
+        assert!(!auth_token_matches(None, Some("")));
+    }
+
     #[test]
     fn test_metrics_response() {
         let server = EndpointServer::new();
@@ -247,6 +789,23 @@ mod tests {
         assert!(response.contains("test_gauge"));
     }
 
+    #[test]
+    fn test_funnel_response() {
+        let server = EndpointServer::new();
+        let response = server.get_funnel_response();
+        assert!(response.contains("\"seen\""));
+        assert!(response.contains("\"succeeded\""));
+        assert!(response.contains("conversion_from_previous"));
+    }
+
+    #[test]
+    fn test_portfolio_response() {
+        let server = EndpointServer::new();
+        let response = server.get_portfolio_response();
+        assert!(response.contains("\"total_value_lamports\""));
+        assert!(response.contains("\"positions\""));
+    }
+
     #[test]
     fn test_health_response() {
         let server = EndpointServer::new();
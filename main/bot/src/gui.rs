@@ -1,40 +1,94 @@
+use std::cell::RefCell;
 use std::collections::VecDeque;
 use std::fs;
 use std::sync::Arc;
 use std::time::Duration;
-use anyhow::Result;
-use eframe::egui::{self, Key, Color32, RichText, ScrollArea, Stroke};
+use anyhow::{Context, Result};
+use eframe::egui::{self, Key, Color32, RichText, ScrollArea, Stroke, TextStyle};
 use eframe::{App, Frame};
+use solana_sdk::native_token::LAMPORTS_PER_SOL;
+use serde::{de::Error as _, Deserialize, Deserializer};
 use solana_sdk::pubkey::Pubkey;
 use tokio::sync::{mpsc::Sender, Mutex};
-use tracing::info;
-use crate::types::{AppState, Mode, QuantumCandidateGui};
+use tracing::field::{Field, Visit};
+use tracing::{info, Event, Level, Subscriber};
+use tracing_subscriber::layer::{Context as LayerContext, Layer};
+use crate::config::SnifferMode;
+use crate::time_utils::now_ms;
+use crate::types::{AppState, Mode, Notification, QuantumCandidateGui};
 
 // --- Zdarzenia i Typy ---
 
 #[derive(Clone, Debug)]
 pub enum GuiEvent {
 SellPercent(f64),
-Buy(Pubkey),
+Buy { mint: Pubkey, amount_lamports: u64 }, // Kwota z pola "Buy amount"
 ReloadStyle, // Nowe zdarzenie do przeładowania stylu
+SwitchSniffer(SnifferMode), // Podmiana źródła sniffera bez restartu
+ResetPnl, // Zerowanie zrealizowanego PnL i wydanego SOL
 }
 pub type GuiEventSender = Sender<GuiEvent>;
+// Największa kwota ręcznego zakupu z GUI; większe są przycinane
+pub const MAX_MANUAL_BUY_SOL: f64 = 5.0;
+// Styl wczytany poza GUI (np. po ReloadStyle), czekający na zastosowanie przy następnej klatce
+pub type GuiStyleSlot = Arc<std::sync::Mutex<Option<egui::Style>>>;
+
+// Wiersz tabeli pozycji; kwoty w SOL
+#[derive(Clone, Debug, PartialEq)]
+pub struct GuiPositionRow {
+pub mint: String,
+pub holdings_percent: f64,
+pub buy_price: Option<f64>,
+pub current_price: Option<f64>,
+pub realized_pnl_sol: f64,
+pub unrealized_pnl_sol: Option<f64>,
+pub price_chart: Vec<f64>, // Ceny od wejścia, od najstarszej
+}
+
+// Ile ostatnich zdarzeń pokazuje panel logów
+pub const LOG_EVENTS_CAPACITY: usize = 10;
 
 // Nowa, lekka struktura do przekazywania zdarzeń do logu w GUI
 #[derive(Clone, Debug)]
 pub struct GuiLogEvent {
 pub timestamp: String,
+pub timestamp_ms: u64, // Do porządkowania powiadomień i logów tracing
 pub message: String,
 pub level: String, // "INFO", "WARN", "ERROR"
 }
 
+impl GuiLogEvent {
+    fn new(timestamp_ms: u64, level: &str, message: String) -> Self {
+        let timestamp = chrono::DateTime::from_timestamp_millis(timestamp_ms as i64)
+            .map(|t| t.format("%H:%M:%S").to_string())
+            .unwrap_or_default();
+        Self { timestamp, timestamp_ms, message, level: level.to_string() }
+    }
+}
+
+impl From<&Notification> for GuiLogEvent {
+    fn from(n: &Notification) -> Self {
+        Self::new(n.timestamp_ms, n.level.as_str(), n.message.clone())
+    }
+}
+
 #[derive(Clone, Debug)]
 pub struct GuiState {
 pub mode: Mode,
 pub active_token_mint: Option<String>,
 pub last_buy_price: Option<f64>,
 pub holdings_percent: f64,
+// Pozostałe otwarte pozycje (poza pokazaną), od najnowszej, np. "<mint> (40%)"
+pub other_positions: Vec<String>,
+// Wszystkie otwarte pozycje z PnL, od najnowszej
+pub position_rows: Vec<GuiPositionRow>,
+// PnL od ostatniego resetu (zrealizowany) i otwartych pozycji (niezrealizowany), w SOL
+pub realized_pnl_sol: f64,
+pub unrealized_pnl_sol: f64,
+pub spent_sol: f64,
 pub quantum_suggestions: Vec<QuantumCandidateGui>,
+// Operacje w toku, np. "buy <mint> (confirming)"
+pub in_flight: Vec<String>,
 // Przechowuje ostatnie zdarzenia
 pub log_events: VecDeque<GuiLogEvent>,
 // Aktywny styl interfejsu
@@ -46,17 +100,58 @@ impl GuiState {
     pub fn from_app_state(app_state: &AppState) -> Self {
         let active_token_mint = app_state.active_token.as_ref()
             .map(|token| token.mint.to_string());
+
+        // Show the most recent notifications
+        let mut log_events = VecDeque::with_capacity(LOG_EVENTS_CAPACITY);
+        let skip = app_state.notifications.len().saturating_sub(LOG_EVENTS_CAPACITY);
+        log_events.extend(app_state.notifications.iter().skip(skip).map(GuiLogEvent::from));
+
+        let in_flight = app_state.active_operations().values()
+            .map(|op| format!("{} {} ({})", op.kind, op.mint, op.stage))
+            .collect();
+
+        let open_positions = app_state.open_positions();
+        let other_positions = open_positions.iter()
+            .filter(|p| Some(&p.token) != app_state.active_token.as_ref())
+            .map(|p| format!("{} ({:.0}%)", p.token.mint, p.holdings_percent * 100.0))
+            .collect();
+
+        let position_rows = open_positions.iter()
+            .map(|p| GuiPositionRow {
+                mint: p.token.mint.to_string(),
+                holdings_percent: p.holdings_percent,
+                buy_price: p.buy_price,
+                current_price: app_state.pnl.price(&p.token.mint),
+                realized_pnl_sol: to_sol(p.realized_pnl_lamports),
+                unrealized_pnl_sol: app_state.unrealized_pnl(p).map(to_sol),
+                price_chart: app_state.pnl.price_chart(&p.token.mint).to_vec(),
+            })
+            .collect();
         
         Self {
             mode: app_state.mode.clone(),
             active_token_mint,
             last_buy_price: app_state.last_buy_price,
             holdings_percent: app_state.holdings_percent,
-            quantum_suggestions: app_state.quantum_suggestions.clone(),
-            log_events: VecDeque::with_capacity(10), // Start with empty log events
+            other_positions,
+            position_rows,
+            realized_pnl_sol: to_sol(app_state.pnl.realized_lamports),
+            unrealized_pnl_sol: to_sol(app_state.unrealized_pnl_lamports()),
+            spent_sol: to_sol(app_state.pnl.spent_lamports as f64),
+            quantum_suggestions: app_state.quantum_suggestions.sorted(),
+            in_flight,
+            log_events,
             active_style: egui::Style::default(),
         }
     }
+
+    /// Merge `events` into the log panel by time, keeping the latest `LOG_EVENTS_CAPACITY`.
+    pub fn merge_log_events<'a>(&mut self, events: impl IntoIterator<Item = &'a GuiLogEvent>) {
+        let mut merged: Vec<GuiLogEvent> = self.log_events.drain(..).chain(events.into_iter().cloned()).collect();
+        merged.sort_by_key(|event| event.timestamp_ms);
+        let skip = merged.len().saturating_sub(LOG_EVENTS_CAPACITY);
+        self.log_events.extend(merged.into_iter().skip(skip));
+    }
 }
 
 impl Default for GuiState {
@@ -66,13 +161,115 @@ mode: Mode::Sniffing,
 active_token_mint: None,
 last_buy_price: None,
 holdings_percent: 0.0,
+other_positions: Vec::new(),
+position_rows: Vec::new(),
+realized_pnl_sol: 0.0,
+unrealized_pnl_sol: 0.0,
+spent_sol: 0.0,
 quantum_suggestions: Vec::new(),
-log_events: VecDeque::with_capacity(10), // Przechowuj np. 10 ostatnich logów
+in_flight: Vec::new(),
+log_events: VecDeque::with_capacity(LOG_EVENTS_CAPACITY), // Przechowuj ostatnie logi
 active_style: egui::Style::default(),
 }
 }
 }
 
+fn to_sol(lamports: f64) -> f64 {
+    lamports / LAMPORTS_PER_SOL as f64
+}
+
+/// Lamports for a buy amount typed in SOL, clamped to `MAX_MANUAL_BUY_SOL`.
+/// `Err` with the reason for input that is not a positive number of lamports.
+pub fn parse_buy_amount(input: &str) -> std::result::Result<u64, String> {
+    let sol: f64 = input.trim().parse().map_err(|_| format!("'{}' is not a number", input.trim()))?;
+    if !sol.is_finite() || sol <= 0.0 {
+        return Err("Amount must be positive".to_string());
+    }
+    let lamports = (sol.min(MAX_MANUAL_BUY_SOL) * LAMPORTS_PER_SOL as f64).round() as u64;
+    if lamports == 0 {
+        return Err("Amount is below 1 lamport".to_string());
+    }
+    Ok(lamports)
+}
+
+// Kolor kwoty PnL: zielony zysk, czerwona strata
+fn pnl_color(pnl: f64) -> Color32 {
+    if pnl > 0.0 {
+        Color32::from_rgb(100, 220, 100)
+    } else if pnl < 0.0 {
+        Color32::from_rgb(255, 80, 80)
+    } else {
+        Color32::from_gray(200)
+    }
+}
+
+// --- Logi tracing w panelu GUI ---
+
+pub type GuiLogReceiver = std::sync::mpsc::Receiver<GuiLogEvent>;
+
+/// `tracing` layer forwarding events at or above `min_level` to the GUI log panel.
+/// Sending never blocks: while the channel is full, new events are dropped.
+pub struct GuiLogLayer {
+    tx: std::sync::mpsc::SyncSender<GuiLogEvent>,
+    min_level: Level,
+}
+
+/// The layer and the receiver the GUI drains every frame; up to `capacity` events
+/// wait in between.
+pub fn gui_log_layer(min_level: Level, capacity: usize) -> (GuiLogLayer, GuiLogReceiver) {
+    let (tx, rx) = std::sync::mpsc::sync_channel(capacity);
+    (GuiLogLayer { tx, min_level }, rx)
+}
+
+impl<S: Subscriber> Layer<S> for GuiLogLayer {
+    // Filtered here rather than in `enabled`, which would hide the events from the other layers too
+    fn on_event(&self, event: &Event<'_>, _ctx: LayerContext<'_, S>) {
+        let level = *event.metadata().level();
+        if level > self.min_level {
+            return;
+        }
+        let mut visitor = LogMessageVisitor::default();
+        event.record(&mut visitor);
+        let level = match level {
+            Level::ERROR => "ERROR",
+            Level::WARN => "WARN",
+            _ => "INFO",
+        };
+        // Full (GUI behind) or disconnected (GUI closed): drop the event
+        let _ = self.tx.try_send(GuiLogEvent::new(now_ms(), level, visitor.message));
+    }
+}
+
+// Treść zdarzenia, a za nią pola jako "klucz=wartość"
+#[derive(Default)]
+struct LogMessageVisitor {
+    message: String,
+}
+
+impl LogMessageVisitor {
+    fn push(&mut self, field: &Field, value: &dyn std::fmt::Display) {
+        if field.name() == "message" {
+            // The message leads even when fields were recorded before it
+            self.message = format!("{} {}", value, self.message).trim_end().to_string();
+        } else {
+            if !self.message.is_empty() {
+                self.message.push(' ');
+            }
+            self.message.push_str(&format!("{}={}", field.name(), value));
+        }
+    }
+}
+
+impl Visit for LogMessageVisitor {
+    fn record_str(&mut self, field: &Field, value: &str) {
+        self.push(field, &value);
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn std::fmt::Debug) {
+        self.push(field, &format_args!("{:?}", value));
+    }
+}
+
 // --- Uruchomienie GUI ---
 
 pub fn launch_gui(
@@ -80,9 +277,12 @@ title: &str,
 app_state: Arc<Mutex<AppState>>,
 gui_tx: GuiEventSender,
 refresh: Duration,
+style: GuiStyleSlot,
+log_rx: GuiLogReceiver,
+default_buy_lamports: u64,
 ) -> Result<()> {
 let native_options = eframe::NativeOptions::default();
-let app = BotApp::new(app_state, gui_tx, refresh);
+let app = BotApp::new(app_state, gui_tx, refresh, style, log_rx, default_buy_lamports);
 eframe::run_native(title, native_options, Box::new(|_| Box::new(app)))
 .map_err(|e| anyhow::anyhow!("GUI error: {}", e))
 }
@@ -94,15 +294,32 @@ app_state_handle: Arc<Mutex<AppState>>,
 local_gui_state: GuiState,
 gui_tx: GuiEventSender,
 refresh: Duration,
+pending_style: GuiStyleSlot,
+log_rx: GuiLogReceiver,
+// Ostatnie zdarzenia z tracing, łączone z powiadomieniami przy odświeżeniu
+traced_log: VecDeque<GuiLogEvent>,
+// Kwota zakupu w SOL, jak wpisana; RefCell, bo draw_state rysuje przez &self
+buy_amount_input: RefCell<String>,
 }
 
 impl BotApp {
-fn new(app_state_handle: Arc<Mutex<AppState>>, gui_tx: GuiEventSender, refresh: Duration) -> Self {
+fn new(
+    app_state_handle: Arc<Mutex<AppState>>,
+    gui_tx: GuiEventSender,
+    refresh: Duration,
+    pending_style: GuiStyleSlot,
+    log_rx: GuiLogReceiver,
+    default_buy_lamports: u64,
+) -> Self {
 Self {
 app_state_handle,
 local_gui_state: GuiState::default(),
 gui_tx,
 refresh,
+pending_style,
+log_rx,
+traced_log: VecDeque::with_capacity(LOG_EVENTS_CAPACITY),
+buy_amount_input: RefCell::new(to_sol(default_buy_lamports as f64).to_string()),
 }
 }
 
@@ -127,7 +344,60 @@ fn draw_state(&self, ui: &mut egui::Ui, st: &GuiState) {
              ui.label(mint);  
              ui.end_row();  
         }  
+
+        for position in &st.other_positions {  
+             ui.label("Also held:");  
+             ui.label(position);  
+             ui.end_row();  
+        }  
+
+        for op in &st.in_flight {  
+             ui.label("In flight:");  
+             ui.label(op);  
+             ui.end_row();  
+        }  
+
+        ui.label("Realized PnL:");  
+        ui.label(RichText::new(format!("{:+.4} SOL", st.realized_pnl_sol)).color(pnl_color(st.realized_pnl_sol)));  
+        ui.end_row();  
+
+        ui.label("Unrealized PnL:");  
+        ui.label(RichText::new(format!("{:+.4} SOL", st.unrealized_pnl_sol)).color(pnl_color(st.unrealized_pnl_sol)));  
+        ui.end_row();  
+
+        ui.label("SOL spent:");  
+        ui.label(format!("{:.4} SOL", st.spent_sol));  
+        ui.end_row();  
     });  
+    if ui.button("Reset PnL").clicked() {  
+        let _ = self.gui_tx.try_send(GuiEvent::ResetPnl);  
+    }  
+
+    // --- Tabela pozycji ---  
+    if !st.position_rows.is_empty() {  
+        ui.add_space(5.0);  
+        egui::Grid::new("positions_grid").num_columns(7).striped(true).show(ui, |ui| {  
+            for header in ["Token", "Held", "Buy price", "Price", "Realized", "Unrealized", "Since entry"] {  
+                ui.label(RichText::new(header).strong());  
+            }  
+            ui.end_row();  
+
+            let price = |p: Option<f64>| p.map_or("-".to_string(), |p| format!("{:.3e}", p));  
+            for row in &st.position_rows {  
+                ui.label(&row.mint);  
+                ui.label(format!("{:.0}%", row.holdings_percent * 100.0));  
+                ui.label(price(row.buy_price));  
+                ui.label(price(row.current_price));  
+                ui.label(RichText::new(format!("{:+.4}", row.realized_pnl_sol)).color(pnl_color(row.realized_pnl_sol)));  
+                match row.unrealized_pnl_sol {  
+                    Some(pnl) => ui.label(RichText::new(format!("{:+.4}", pnl)).color(pnl_color(pnl))),  
+                    None => ui.label("-"),  
+                };  
+                draw_sparkline(ui, &row.price_chart, row.buy_price);  
+                ui.end_row();  
+            }  
+        });  
+    }  
       
     // ULEPSZENIE: Pasek postępu dla posiadanych tokenów  
     if st.holdings_percent > 0.0 {  
@@ -158,13 +428,23 @@ fn draw_state(&self, ui: &mut egui::Ui, st: &GuiState) {
 
     // --- Panel Sugestii Quantum ---  
     ui.heading("🎯 Quantum Suggestions");  
+    let buy_amount = {  
+        let mut input = self.buy_amount_input.borrow_mut();  
+        let buy_amount = parse_buy_amount(&input);  
+        ui.horizontal(|ui| {  
+            ui.label("Buy amount (SOL):");  
+            ui.add(egui::TextEdit::singleline(&mut *input).desired_width(80.0));  
+            match &buy_amount {  
+                Ok(lamports) => ui.label(format!("= {:.4} SOL (max {})", to_sol(*lamports as f64), MAX_MANUAL_BUY_SOL)),  
+                Err(reason) => ui.label(RichText::new(reason).color(Color32::from_rgb(255, 80, 80))),  
+            };  
+        });  
+        buy_amount  
+    };  
     ScrollArea::vertical().show(ui, |ui| {  
         if !st.quantum_suggestions.is_empty() {  
-            // ULEPSZENIE: Dynamiczne sortowanie sugestii  
-            let mut suggestions = st.quantum_suggestions.clone();  
-            suggestions.sort_by(|a, b| b.score.cmp(&a.score));  
-
-            for suggestion in suggestions {  
+            // Already best-first (see QuantumSuggestions::sorted)  
+            for suggestion in st.quantum_suggestions.iter().cloned() {  
                 // ULEPSZENIE: Kolorowe sygnały wizualne  
                 let score_color = get_color_for_score(suggestion.score);  
                 let frame = egui::Frame::group(ui.style()).stroke(Stroke::new(1.0, score_color));  
@@ -178,8 +458,9 @@ fn draw_state(&self, ui: &mut egui::Ui, st: &GuiState) {
                         });  
 
                         ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {  
-                            if ui.button(RichText::new("🛒 BUY").size(16.0)).clicked() {  
-                                let _ = self.gui_tx.try_send(GuiEvent::Buy(suggestion.mint));  
+                            let buy = ui.add_enabled(buy_amount.is_ok(), egui::Button::new(RichText::new("🛒 BUY").size(16.0)));  
+                            if let (true, Ok(amount_lamports)) = (buy.clicked(), &buy_amount) {  
+                                let _ = self.gui_tx.try_send(GuiEvent::Buy { mint: suggestion.mint, amount_lamports: *amount_lamports });  
                             }  
                         });  
                     });  
@@ -225,8 +506,21 @@ if i.key_pressed(Key::S) { let _ = self.gui_tx.try_send(GuiEvent::SellPercent(1.
 });
 
 // --- Nieblokujące pobieranie stanu ---  
+    for event in self.log_rx.try_iter() {  
+        if self.traced_log.len() >= LOG_EVENTS_CAPACITY {  
+            self.traced_log.pop_front();  
+        }  
+        self.traced_log.push_back(event);  
+    }  
     if let Ok(guard) = self.app_state_handle.try_lock() {  
+        // Styl nie pochodzi z AppState: zachowaj aktywny przy odświeżeniu  
+        let active_style = std::mem::take(&mut self.local_gui_state.active_style);  
         self.local_gui_state = GuiState::from_app_state(&guard);  
+        self.local_gui_state.active_style = active_style;  
+        self.local_gui_state.merge_log_events(&self.traced_log);  
+    }  
+    if let Some(style) = self.pending_style.lock().ok().and_then(|mut pending| pending.take()) {  
+        self.local_gui_state.active_style = style;  
     }  
 
     // --- ULEPSZENIE: Zastosowanie stylu ---  
@@ -242,6 +536,15 @@ if i.key_pressed(Key::S) { let _ = self.gui_tx.try_send(GuiEvent::SellPercent(1.
          if ui.button("🎨 Reload Style").clicked() {  
             let _ = self.gui_tx.try_send(GuiEvent::ReloadStyle);  
         }  
+        ui.horizontal(|ui| {  
+            ui.label("Sniffer:");  
+            if ui.button("Mock").clicked() {  
+                let _ = self.gui_tx.try_send(GuiEvent::SwitchSniffer(SnifferMode::Mock));  
+            }  
+            if ui.button("Real").clicked() {  
+                let _ = self.gui_tx.try_send(GuiEvent::SwitchSniffer(SnifferMode::Real));  
+            }  
+        });  
     });  
 
     ctx.request_repaint_after(self.refresh);  
@@ -255,6 +558,43 @@ fn on_exit(&mut self, _gl: Option<&eframe::glow::Context>) {
 
 // --- Funkcje pomocnicze ---
 
+// Wykres cen od wejścia: linia w kolorze zysku/straty, przerywana linia ceny zakupu
+fn draw_sparkline(ui: &mut egui::Ui, prices: &[f64], buy_price: Option<f64>) {
+    let (rect, _) = ui.allocate_exact_size(egui::vec2(120.0, 24.0), egui::Sense::hover());
+    let (Some(&first), Some(&last)) = (prices.first(), prices.last()) else {
+        return;
+    };
+    let (low, high) = prices
+        .iter()
+        .chain(buy_price.as_ref())
+        .fold((f64::MAX, f64::MIN), |(low, high), &p| (low.min(p), high.max(p)));
+    let range = (high - low).max(f64::EPSILON);
+    let y = |price: f64| rect.bottom() - ((price - low) / range) as f32 * rect.height();
+    let x_step = rect.width() / (prices.len().max(2) - 1) as f32;
+    let points: Vec<egui::Pos2> = prices
+        .iter()
+        .enumerate()
+        .map(|(i, &p)| egui::pos2(rect.left() + i as f32 * x_step, y(p)))
+        .collect();
+
+    let painter = ui.painter_at(rect);
+    if let Some(buy_price) = buy_price {
+        let at = y(buy_price);
+        painter.add(egui::Shape::dashed_line(
+            &[egui::pos2(rect.left(), at), egui::pos2(rect.right(), at)],
+            Stroke::new(1.0, Color32::from_gray(140)),
+            3.0,
+            2.0,
+        ));
+    }
+    let color = pnl_color(last - buy_price.unwrap_or(first));
+    if points.len() == 1 {
+        painter.circle_filled(points[0], 2.0, color);
+    } else {
+        painter.add(egui::Shape::line(points, Stroke::new(1.5, color)));
+    }
+}
+
 // Helper do kolorowania sugestii na podstawie wyniku
 fn get_color_for_score(score: u8) -> Color32 {
 match score {
@@ -265,19 +605,168 @@ _ => Color32::from_rgb(255, 69, 0),      // OrangeRed
 }
 }
 
-// Helper do wczytywania stylu z pliku
+// --- Motyw GUI ---
+
+/// GUI theme read from JSON. Every field is optional; missing ones keep the
+/// default egui style.
+#[derive(Clone, Debug, Default, PartialEq, Deserialize)]
+#[serde(default)]
+pub struct GuiTheme {
+    /// Start from egui's dark (true) or light (false) visuals
+    pub dark_mode: Option<bool>,
+    pub colors: ThemeColors,
+    pub spacing: ThemeSpacing,
+    pub font_sizes: ThemeFontSizes,
+}
+
+/// Colors as "#RRGGBB" or "#RRGGBBAA".
+#[derive(Clone, Debug, Default, PartialEq, Deserialize)]
+#[serde(default)]
+pub struct ThemeColors {
+    /// Overrides the color of all text
+    #[serde(deserialize_with = "hex_color")]
+    pub text: Option<Color32>,
+    #[serde(deserialize_with = "hex_color")]
+    pub panel: Option<Color32>,
+    #[serde(deserialize_with = "hex_color")]
+    pub window: Option<Color32>,
+    /// Background of text edits, scroll bars and progress bars
+    #[serde(deserialize_with = "hex_color")]
+    pub extreme_bg: Option<Color32>,
+    #[serde(deserialize_with = "hex_color")]
+    pub hyperlink: Option<Color32>,
+    #[serde(deserialize_with = "hex_color")]
+    pub selection: Option<Color32>,
+    /// Button backgrounds: idle, hovered and pressed
+    #[serde(deserialize_with = "hex_color")]
+    pub widget: Option<Color32>,
+    #[serde(deserialize_with = "hex_color")]
+    pub widget_hovered: Option<Color32>,
+    #[serde(deserialize_with = "hex_color")]
+    pub widget_active: Option<Color32>,
+    #[serde(deserialize_with = "hex_color")]
+    pub warn: Option<Color32>,
+    #[serde(deserialize_with = "hex_color")]
+    pub error: Option<Color32>,
+}
+
+/// Spacing in points.
+#[derive(Clone, Debug, Default, PartialEq, Deserialize)]
+#[serde(default)]
+pub struct ThemeSpacing {
+    /// Horizontal and vertical gap between widgets
+    pub item_spacing: Option<[f32; 2]>,
+    pub button_padding: Option<[f32; 2]>,
+    pub indent: Option<f32>,
+    pub window_margin: Option<f32>,
+}
+
+/// Font sizes in points per text style.
+#[derive(Clone, Debug, Default, PartialEq, Deserialize)]
+#[serde(default)]
+pub struct ThemeFontSizes {
+    pub small: Option<f32>,
+    pub body: Option<f32>,
+    pub button: Option<f32>,
+    pub heading: Option<f32>,
+    pub monospace: Option<f32>,
+}
+
+impl GuiTheme {
+    /// Apply the fields this theme sets onto `style`.
+    pub fn apply(&self, style: &mut egui::Style) {
+        fn set<T>(target: &mut T, value: Option<T>) {
+            if let Some(value) = value {
+                *target = value;
+            }
+        }
+
+        if let Some(dark_mode) = self.dark_mode {
+            style.visuals = if dark_mode { egui::Visuals::dark() } else { egui::Visuals::light() };
+        }
+
+        let colors = &self.colors;
+        let visuals = &mut style.visuals;
+        if colors.text.is_some() {
+            visuals.override_text_color = colors.text;
+        }
+        set(&mut visuals.panel_fill, colors.panel);
+        set(&mut visuals.window_fill, colors.window);
+        set(&mut visuals.extreme_bg_color, colors.extreme_bg);
+        set(&mut visuals.hyperlink_color, colors.hyperlink);
+        set(&mut visuals.selection.bg_fill, colors.selection);
+        set(&mut visuals.warn_fg_color, colors.warn);
+        set(&mut visuals.error_fg_color, colors.error);
+        for (widget, color) in [
+            (&mut visuals.widgets.inactive, colors.widget),
+            (&mut visuals.widgets.hovered, colors.widget_hovered),
+            (&mut visuals.widgets.active, colors.widget_active),
+        ] {
+            set(&mut widget.bg_fill, color);
+            set(&mut widget.weak_bg_fill, color);
+        }
+
+        let spacing = &self.spacing;
+        set(&mut style.spacing.item_spacing, spacing.item_spacing.map(egui::Vec2::from));
+        set(&mut style.spacing.button_padding, spacing.button_padding.map(egui::Vec2::from));
+        set(&mut style.spacing.indent, spacing.indent);
+        set(&mut style.spacing.window_margin, spacing.window_margin.map(egui::Margin::same));
+
+        let sizes = &self.font_sizes;
+        for (text_style, size) in [
+            (TextStyle::Small, sizes.small),
+            (TextStyle::Body, sizes.body),
+            (TextStyle::Button, sizes.button),
+            (TextStyle::Heading, sizes.heading),
+            (TextStyle::Monospace, sizes.monospace),
+        ] {
+            if let (Some(size), Some(font)) = (size, style.text_styles.get_mut(&text_style)) {
+                font.size = size;
+            }
+        }
+    }
+}
+
+// "#RRGGBB" lub "#RRGGBBAA"
+fn parse_hex_color(hex: &str) -> Option<Color32> {
+    let digits = hex.strip_prefix('#')?;
+    if !matches!(digits.len(), 6 | 8) || !digits.is_ascii() {
+        return None;
+    }
+    let channel = |i: usize| u8::from_str_radix(&digits[i * 2..i * 2 + 2], 16).ok();
+    let alpha = if digits.len() == 8 { channel(3)? } else { 255 };
+    Some(Color32::from_rgba_unmultiplied(channel(0)?, channel(1)?, channel(2)?, alpha))
+}
+
+fn hex_color<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<Color32>, D::Error> {
+    Option::<String>::deserialize(deserializer)?
+        .map(|hex| {
+            parse_hex_color(&hex)
+                .ok_or_else(|| D::Error::custom(format!("invalid color '{}', expected #RRGGBB or #RRGGBBAA", hex)))
+        })
+        .transpose()
+}
+
+/// The default egui style with the JSON theme `json` applied.
+pub fn parse_style(json: &str) -> Result<egui::Style> {
+    let theme: GuiTheme = serde_json::from_str(json)?;
+    let mut style = egui::Style::default();
+    theme.apply(&mut style);
+    Ok(style)
+}
+
+// Helper do wczytywania stylu z pliku (motyw JSON, patrz GuiTheme)
 pub fn load_style_from_file(path: &str) -> Result<egui::Style> {
-    // Since egui::Style doesn't implement Deserialize, we'll return the default style
-    // In a real implementation, you would manually parse style properties from JSON
-    let _style_json = fs::read_to_string(path)?;
+    let json = fs::read_to_string(path)?;
+    let style = parse_style(&json).with_context(|| format!("Invalid GUI theme in {}", path))?;
     info!("Style file loaded from: {}", path);
-    Ok(egui::Style::default())
+    Ok(style)
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::types::{AppState, Mode, QuantumCandidateGui, PremintCandidate};
+    use crate::types::{AppState, Mode, NotificationLevel, QuantumCandidateGui, PremintCandidate};
     use solana_sdk::pubkey::Pubkey;
     use std::collections::HashMap;
 
@@ -293,13 +782,18 @@ mod tests {
             timestamp: 1640995200,
         };
 
-        let app_state = AppState {
+        let mut app_state = AppState {
             mode: Mode::Sniffing,
             active_token: None,
             last_buy_price: Some(1.5),
             holdings_percent: 0.75,
-            quantum_suggestions: vec![quantum_candidate.clone()],
+            quantum_suggestions: Default::default(),
+            notifications: Default::default(),
+            operations: Default::default(),
+            positions: Default::default(),
+            pnl: Default::default(),
         };
+        app_state.quantum_suggestions.push(quantum_candidate.clone());
 
         // Convert to GuiState
         let gui_state = GuiState::from_app_state(&app_state);
@@ -325,6 +819,7 @@ mod tests {
             timestamp: 1640995200,
             instruction_summary: Some("Create token".to_string()),
             is_jito_bundle: Some(false),
+            deadline_ms: None,
         };
 
         let app_state = AppState {
@@ -332,7 +827,11 @@ mod tests {
             active_token: Some(active_token),
             last_buy_price: Some(2.0),
             holdings_percent: 0.5,
-            quantum_suggestions: vec![],
+            quantum_suggestions: Default::default(),
+            notifications: Default::default(),
+            operations: Default::default(),
+            positions: Default::default(),
+            pnl: Default::default(),
         };
 
         let gui_state = GuiState::from_app_state(&app_state);
@@ -342,6 +841,78 @@ mod tests {
         assert_eq!(gui_state.holdings_percent, 0.5);
     }
 
+    #[test]
+    fn test_gui_state_shows_latest_notifications() {
+        let mut app_state = AppState::sniffing();
+        for i in 0..12 {
+            app_state.notify(NotificationLevel::Info, format!("event {}", i));
+        }
+        app_state.notify(NotificationLevel::Error, "All RPC endpoints unreachable");
+
+        let gui_state = GuiState::from_app_state(&app_state);
+
+        assert_eq!(gui_state.log_events.len(), 10);
+        assert_eq!(gui_state.log_events[0].message, "event 3");
+        let last = gui_state.log_events.back().unwrap();
+        assert_eq!(last.message, "All RPC endpoints unreachable");
+        assert_eq!(last.level, "ERROR");
+    }
+
+    #[test]
+    fn test_parse_buy_amount_validates_and_clamps() {
+        assert_eq!(parse_buy_amount("0.25"), Ok(250_000_000));
+        assert_eq!(parse_buy_amount(" 1 "), Ok(LAMPORTS_PER_SOL));
+        assert_eq!(parse_buy_amount("0.000000001"), Ok(1));
+        // Above the max: clamped, not rejected
+        assert_eq!(parse_buy_amount("1000"), Ok((MAX_MANUAL_BUY_SOL * LAMPORTS_PER_SOL as f64) as u64));
+
+        assert!(parse_buy_amount("abc").unwrap_err().contains("not a number"));
+        assert!(parse_buy_amount("").is_err());
+        assert!(parse_buy_amount("0").is_err());
+        assert!(parse_buy_amount("-1").is_err());
+        assert!(parse_buy_amount("NaN").is_err());
+        assert!(parse_buy_amount("inf").is_err());
+        assert!(parse_buy_amount("0.0000000001").is_err());
+    }
+
+    #[test]
+    fn test_warn_events_reach_the_gui_log() {
+        use tracing_subscriber::prelude::*;
+        use tracing_subscriber::EnvFilter;
+
+        let (layer, rx) = gui_log_layer(Level::WARN, 2);
+        let subscriber = tracing_subscriber::registry().with(EnvFilter::new("debug")).with(layer);
+        tracing::subscriber::with_default(subscriber, || {
+            tracing::info!("below the threshold");
+            tracing::warn!(endpoint = "rpc-1", attempts = 3, "RPC endpoint slow");
+            tracing::error!("Sell failed");
+            // The channel holds two events: this one is dropped instead of blocking
+            tracing::error!("dropped");
+        });
+        let events: Vec<GuiLogEvent> = rx.try_iter().collect();
+        assert_eq!(events.len(), 2);
+        assert_eq!(events[0].level, "WARN");
+        assert_eq!(events[0].message, "RPC endpoint slow endpoint=rpc-1 attempts=3");
+        assert_eq!(events[1].level, "ERROR");
+        assert_eq!(events[1].message, "Sell failed");
+
+        // Events the EnvFilter rejects never reach the layer
+        let (layer, rx) = gui_log_layer(Level::INFO, 8);
+        let subscriber = tracing_subscriber::registry().with(EnvFilter::new("error")).with(layer);
+        tracing::subscriber::with_default(subscriber, || tracing::warn!("filtered out"));
+        assert!(rx.try_recv().is_err());
+
+        // Merged with the notifications by time, the newest LOG_EVENTS_CAPACITY kept
+        let mut gui_state = GuiState::default();
+        gui_state.log_events.extend((0..8).map(|i| GuiLogEvent::new(i * 10, "INFO", format!("notification {}", i))));
+        let traced: Vec<GuiLogEvent> = (0..4).map(|i| GuiLogEvent::new(i * 20 + 5, "WARN", format!("traced {}", i))).collect();
+        gui_state.merge_log_events(&traced);
+        let messages: Vec<&str> = gui_state.log_events.iter().map(|e| e.message.as_str()).collect();
+        assert_eq!(messages.len(), LOG_EVENTS_CAPACITY);
+        assert_eq!(&messages[..3], ["notification 1", "notification 2", "traced 1"]);
+        assert_eq!(&messages[8..], ["traced 3", "notification 7"]);
+    }
+
     #[test]
     fn test_load_style_from_file_returns_default() {
         // Test the load_style_from_file function
@@ -350,4 +921,53 @@ mod tests {
         // Should return error for non-existent file
         assert!(result.is_err());
     }
+
+    #[test]
+    fn test_theme_json_lands_on_style() {
+        let style = parse_style(
+            r##"{
+                "dark_mode": false,
+                "colors": { "panel": "#102030", "text": "#FFCC00", "widget": "#11223380", "error": "#ff0000" },
+                "spacing": { "item_spacing": [4.0, 6.0], "window_margin": 3.5 },
+                "font_sizes": { "body": 15.0, "heading": 22.0 }
+            }"##,
+        )
+        .unwrap();
+
+        assert!(!style.visuals.dark_mode);
+        assert_eq!(style.visuals.panel_fill, Color32::from_rgb(0x10, 0x20, 0x30));
+        assert_eq!(style.visuals.override_text_color, Some(Color32::from_rgb(255, 204, 0)));
+        assert_eq!(style.visuals.widgets.inactive.bg_fill, Color32::from_rgba_unmultiplied(0x11, 0x22, 0x33, 0x80));
+        assert_eq!(style.visuals.error_fg_color, Color32::from_rgb(255, 0, 0));
+        assert_eq!(style.spacing.item_spacing, egui::vec2(4.0, 6.0));
+        assert_eq!(style.spacing.window_margin, egui::Margin::same(3.5));
+        assert_eq!(style.text_styles[&TextStyle::Body].size, 15.0);
+        assert_eq!(style.text_styles[&TextStyle::Heading].size, 22.0);
+        // Fields the theme leaves out keep the light defaults
+        let light = egui::Visuals::light();
+        assert_eq!(style.visuals.window_fill, light.window_fill);
+        assert_eq!(style.visuals.widgets.hovered.bg_fill, light.widgets.hovered.bg_fill);
+        let default = egui::Style::default();
+        assert_eq!(style.text_styles[&TextStyle::Button], default.text_styles[&TextStyle::Button]);
+        assert_eq!(style.spacing.indent, default.spacing.indent);
+    }
+
+    #[test]
+    fn test_theme_missing_fields_default_and_bad_colors_fail() {
+        let default = egui::Style::default();
+        let empty = parse_style("{}").unwrap();
+        assert_eq!(empty.visuals, default.visuals);
+        assert_eq!(empty.spacing, default.spacing);
+        assert_eq!(empty.text_styles, default.text_styles);
+
+        assert!(parse_style(r#"{ "colors": { "panel": "102030" } }"#).is_err());
+        assert!(parse_style(r##"{ "colors": { "panel": "#1020zz" } }"##).is_err());
+        assert!(parse_style("not json").is_err());
+
+        let path = std::env::temp_dir().join(format!("gui_theme_{}.json", std::process::id()));
+        fs::write(&path, r##"{ "colors": { "window": "#000000" } }"##).unwrap();
+        let style = load_style_from_file(path.to_str().unwrap()).unwrap();
+        fs::remove_file(&path).unwrap();
+        assert_eq!(style.visuals.window_fill, Color32::BLACK);
+    }
 }
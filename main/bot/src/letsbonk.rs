@@ -0,0 +1,308 @@
+//! Native LetsBonk trades through the Raydium LaunchLab program.
+//!
+//! Derives the pool and vault accounts of a LetsBonk token, quotes trades from the
+//! pool's bonding curve and builds the `buy_exact_in` / `sell_exact_in` instructions
+//! with the token accounts and SOL wrapping around them, so trades do not depend on
+//! a third-party HTTP endpoint. Used by `TransactionBuilder` with the `letsbonk`
+//! feature.
+
+use solana_sdk::{
+    instruction::{AccountMeta, Instruction},
+    pubkey,
+    pubkey::Pubkey,
+};
+use solana_system_interface::instruction as system_instruction;
+use spl_associated_token_account::{
+    get_associated_token_address, instruction::create_associated_token_account_idempotent,
+};
+use spl_token::id as token_program_id;
+use spl_token::instruction::{close_account, sync_native};
+
+/// Raydium LaunchLab program (see `types::LETSBONK_PROGRAM_ID`).
+pub const LAUNCHLAB_PROGRAM_ID: Pubkey = pubkey!("LanMV9sAd7wArD4vJFi2qDdfnVhFxYSUg6eADduJ3uj");
+/// LaunchLab global config LetsBonk pools trade under.
+pub const GLOBAL_CONFIG: Pubkey = pubkey!("6s1xP3hpbAfFoNtUNF8mfHjjr2Bg7ZwCAJ1Tgd4GFKaB");
+/// The LetsBonk platform config.
+pub const LETSBONK_PLATFORM_CONFIG: Pubkey = pubkey!("FfYek5vEz23cMkWsdJwG2oa6EphsvXSHrGpdALN4g6W1");
+/// Wrapped SOL, the quote token of LetsBonk pools.
+pub const WSOL_MINT: Pubkey = pubkey!("So11111111111111111111111111111111111111112");
+
+/// Fees taken from the SOL paid in: 0.25% protocol trade fee plus 1% LetsBonk platform fee.
+pub const BUY_FEE_BPS: u64 = 125;
+/// Fees taken from the SOL paid out on sells, at the same rates.
+pub const SELL_FEE_BPS: u64 = BUY_FEE_BPS;
+
+/// Anchor discriminator of `buy_exact_in`.
+const BUY_EXACT_IN_DISCRIMINATOR: [u8; 8] = [250, 234, 13, 123, 213, 156, 19, 236];
+/// Anchor discriminator of `sell_exact_in`.
+const SELL_EXACT_IN_DISCRIMINATOR: [u8; 8] = [149, 39, 222, 155, 211, 124, 152, 26];
+/// Anchor discriminator of the `PoolState` account.
+const POOL_STATE_DISCRIMINATOR: [u8; 8] = [247, 237, 227, 245, 215, 195, 222, 70];
+/// Offset of `virtual_base` in `PoolState`, behind the discriminator, epoch, five
+/// single-byte fields, supply and total_base_sell.
+const POOL_RESERVES_OFFSET: usize = 37;
+
+/// Pool of `base_mint` traded against `quote_mint`.
+pub fn pool_state_pda(base_mint: &Pubkey, quote_mint: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[b"pool", base_mint.as_ref(), quote_mint.as_ref()], &LAUNCHLAB_PROGRAM_ID).0
+}
+
+/// Vault holding the pool's `mint` tokens.
+pub fn pool_vault_pda(pool: &Pubkey, mint: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[b"pool_vault", pool.as_ref(), mint.as_ref()], &LAUNCHLAB_PROGRAM_ID).0
+}
+
+/// Authority signing vault transfers.
+pub fn vault_authority_pda() -> Pubkey {
+    Pubkey::find_program_address(&[b"vault_auth_seed"], &LAUNCHLAB_PROGRAM_ID).0
+}
+
+/// Anchor event authority of the program.
+pub fn event_authority_pda() -> Pubkey {
+    Pubkey::find_program_address(&[b"__event_authority"], &LAUNCHLAB_PROGRAM_ID).0
+}
+
+/// Bonding-curve reserves of a LaunchLab pool.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PoolReserves {
+    pub virtual_base: u64,
+    pub virtual_quote: u64,
+    pub real_base: u64,
+    pub real_quote: u64,
+}
+
+impl PoolReserves {
+    /// Reserves from `PoolState` account data; `None` for other accounts.
+    pub fn from_account_data(data: &[u8]) -> Option<Self> {
+        if data.get(..8)? != POOL_STATE_DISCRIMINATOR {
+            return None;
+        }
+        let field = |index: usize| {
+            let start = POOL_RESERVES_OFFSET + index * 8;
+            data.get(start..start + 8).map(|bytes| u64::from_le_bytes(bytes.try_into().unwrap()))
+        };
+        Some(Self {
+            virtual_base: field(0)?,
+            virtual_quote: field(1)?,
+            real_base: field(2)?,
+            real_quote: field(3)?,
+        })
+    }
+
+    /// Tokens out for `quote_in` lamports, after `BUY_FEE_BPS` of fees, on the
+    /// constant-product curve over the virtual reserves net of what was traded.
+    pub fn tokens_out(&self, quote_in: u64) -> u64 {
+        let net_in = quote_in as u128 - quote_in as u128 * BUY_FEE_BPS as u128 / 10_000;
+        let base = self.virtual_base.saturating_sub(self.real_base) as u128;
+        let quote = self.virtual_quote as u128 + self.real_quote as u128;
+        if quote + net_in == 0 {
+            return 0;
+        }
+        (base * net_in / (quote + net_in)) as u64
+    }
+
+    /// Lamports out for selling `tokens_in` on the same curve, after `SELL_FEE_BPS`
+    /// of fees on the SOL paid out.
+    pub fn sol_out(&self, tokens_in: u64) -> u64 {
+        let base = self.virtual_base.saturating_sub(self.real_base) as u128;
+        let quote = self.virtual_quote as u128 + self.real_quote as u128;
+        if base + tokens_in as u128 == 0 {
+            return 0;
+        }
+        let gross = quote * tokens_in as u128 / (base + tokens_in as u128);
+        (gross - gross * SELL_FEE_BPS as u128 / 10_000) as u64
+    }
+
+    /// Current price in lamports per raw token; 0 when the curve holds no tokens.
+    pub fn price(&self) -> f64 {
+        let base = self.virtual_base.saturating_sub(self.real_base);
+        if base == 0 {
+            return 0.0;
+        }
+        (self.virtual_quote as f64 + self.real_quote as f64) / base as f64
+    }
+}
+
+/// Accounts of `buy_exact_in` and `sell_exact_in`, which take the same ones.
+fn swap_accounts(payer: &Pubkey, base_mint: &Pubkey) -> Vec<AccountMeta> {
+    let pool = pool_state_pda(base_mint, &WSOL_MINT);
+    vec![
+        AccountMeta::new(*payer, true),
+        AccountMeta::new_readonly(vault_authority_pda(), false),
+        AccountMeta::new_readonly(GLOBAL_CONFIG, false),
+        AccountMeta::new_readonly(LETSBONK_PLATFORM_CONFIG, false),
+        AccountMeta::new(pool, false),
+        AccountMeta::new(get_associated_token_address(payer, base_mint), false),
+        AccountMeta::new(get_associated_token_address(payer, &WSOL_MINT), false),
+        AccountMeta::new(pool_vault_pda(&pool, base_mint), false),
+        AccountMeta::new(pool_vault_pda(&pool, &WSOL_MINT), false),
+        AccountMeta::new_readonly(*base_mint, false),
+        AccountMeta::new_readonly(WSOL_MINT, false),
+        AccountMeta::new_readonly(token_program_id(), false),
+        AccountMeta::new_readonly(token_program_id(), false),
+        AccountMeta::new_readonly(event_authority_pda(), false),
+        AccountMeta::new_readonly(LAUNCHLAB_PROGRAM_ID, false),
+    ]
+}
+
+/// Swap instruction data: `discriminator`, amount in, minimum out, share fee rate.
+fn swap_data(discriminator: [u8; 8], amount_in: u64, minimum_amount_out: u64) -> Vec<u8> {
+    let mut data = Vec::with_capacity(32);
+    data.extend_from_slice(&discriminator);
+    data.extend_from_slice(&amount_in.to_le_bytes());
+    data.extend_from_slice(&minimum_amount_out.to_le_bytes());
+    // share_fee_rate: no referral share
+    data.extend_from_slice(&0u64.to_le_bytes());
+    data
+}
+
+/// `buy_exact_in` paying `amount_in` lamports from `payer`'s WSOL account for at
+/// least `minimum_amount_out` of `base_mint`. The WSOL and token accounts have to
+/// exist, the WSOL account holding the amount: see `buy_instructions`.
+pub fn buy_exact_in(payer: &Pubkey, base_mint: &Pubkey, amount_in: u64, minimum_amount_out: u64) -> Instruction {
+    Instruction {
+        program_id: LAUNCHLAB_PROGRAM_ID,
+        accounts: swap_accounts(payer, base_mint),
+        data: swap_data(BUY_EXACT_IN_DISCRIMINATOR, amount_in, minimum_amount_out),
+    }
+}
+
+/// `sell_exact_in` of `amount_in` raw `base_mint` tokens for at least
+/// `minimum_amount_out` lamports, paid into `payer`'s WSOL account (which has to
+/// exist: see `sell_instructions`).
+pub fn sell_exact_in(payer: &Pubkey, base_mint: &Pubkey, amount_in: u64, minimum_amount_out: u64) -> Instruction {
+    Instruction {
+        program_id: LAUNCHLAB_PROGRAM_ID,
+        accounts: swap_accounts(payer, base_mint),
+        data: swap_data(SELL_EXACT_IN_DISCRIMINATOR, amount_in, minimum_amount_out),
+    }
+}
+
+/// A whole buy: create the token and WSOL accounts unless they exist, wrap
+/// `amount_in` lamports, `buy_exact_in`, then close the WSOL account again.
+pub fn buy_instructions(payer: &Pubkey, base_mint: &Pubkey, amount_in: u64, minimum_amount_out: u64) -> Vec<Instruction> {
+    let wsol_account = get_associated_token_address(payer, &WSOL_MINT);
+    vec![
+        create_associated_token_account_idempotent(payer, payer, base_mint, &token_program_id()),
+        create_associated_token_account_idempotent(payer, payer, &WSOL_MINT, &token_program_id()),
+        system_instruction::transfer(payer, &wsol_account, amount_in),
+        sync_native(&token_program_id(), &wsol_account).expect("SPL token program id"),
+        buy_exact_in(payer, base_mint, amount_in, minimum_amount_out),
+        close_wsol_account(payer),
+    ]
+}
+
+/// A whole sell: create the WSOL account the proceeds are paid into unless it
+/// exists, `sell_exact_in`, then close the WSOL account, which unwraps the
+/// proceeds (and any WSOL held before).
+pub fn sell_instructions(payer: &Pubkey, base_mint: &Pubkey, amount_in: u64, minimum_amount_out: u64) -> Vec<Instruction> {
+    vec![
+        create_associated_token_account_idempotent(payer, payer, &WSOL_MINT, &token_program_id()),
+        sell_exact_in(payer, base_mint, amount_in, minimum_amount_out),
+        close_wsol_account(payer),
+    ]
+}
+
+/// Close `payer`'s WSOL account, returning its lamports to `payer`.
+fn close_wsol_account(payer: &Pubkey) -> Instruction {
+    let wsol_account = get_associated_token_address(payer, &WSOL_MINT);
+    close_account(&token_program_id(), &wsol_account, payer, payer, &[]).expect("SPL token program id")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::str::FromStr;
+
+    use crate::tx_builder::min_out_after_slippage;
+    use crate::types::LETSBONK_PROGRAM_ID;
+
+    #[test]
+    fn pdas_derive_from_the_launchlab_seeds() {
+        assert_eq!(LAUNCHLAB_PROGRAM_ID, Pubkey::from_str(LETSBONK_PROGRAM_ID).unwrap());
+
+        let mint = Pubkey::new_unique();
+        let pool = pool_state_pda(&mint, &WSOL_MINT);
+        let (expected, _) =
+            Pubkey::find_program_address(&[b"pool", mint.as_ref(), WSOL_MINT.as_ref()], &LAUNCHLAB_PROGRAM_ID);
+        assert_eq!(pool, expected);
+        // Base and quote are not interchangeable
+        assert_ne!(pool, pool_state_pda(&WSOL_MINT, &mint));
+        assert_ne!(pool_vault_pda(&pool, &mint), pool_vault_pda(&pool, &WSOL_MINT));
+        assert!(!pool.is_on_curve());
+        assert!(!vault_authority_pda().is_on_curve());
+
+        let payer = Pubkey::new_unique();
+        let ix = buy_exact_in(&payer, &mint, 1_000_000, 42);
+        assert_eq!(ix.accounts[4].pubkey, pool);
+        assert_eq!(ix.accounts[7].pubkey, pool_vault_pda(&pool, &mint));
+        assert!(ix.accounts[0].is_signer && ix.accounts.iter().skip(1).all(|meta| !meta.is_signer));
+        assert_eq!(&ix.data[8..16], &1_000_000u64.to_le_bytes());
+        assert_eq!(&ix.data[16..24], &42u64.to_le_bytes());
+    }
+
+    #[test]
+    fn buy_quotes_and_min_out_follow_the_curve() {
+        let mut data = POOL_STATE_DISCRIMINATOR.to_vec();
+        data.resize(POOL_RESERVES_OFFSET, 0);
+        for value in [1_073_025_605_596_382u64, 30_000_852_951, 73_025_605_596_382, 0] {
+            data.extend_from_slice(&value.to_le_bytes());
+        }
+        let reserves = PoolReserves::from_account_data(&data).unwrap();
+        assert_eq!(reserves.virtual_base - reserves.real_base, 1_000_000_000_000_000);
+        assert!(PoolReserves::from_account_data(&data[1..]).is_none());
+
+        // 0.1 SOL in, 1.25% fees: 98_750_000 lamports against 30_000_852_951 quote
+        let out = reserves.tokens_out(100_000_000);
+        assert_eq!(out as u128, 1_000_000_000_000_000u128 * 98_750_000 / (30_000_852_951 + 98_750_000));
+        assert!(out < reserves.tokens_out(200_000_000));
+        assert_eq!(reserves.tokens_out(0), 0);
+
+        // 5% slippage on the quote is the instruction's minimum out
+        assert_eq!(min_out_after_slippage(out, 500), out * 95 / 100);
+
+        // Selling the bought tokens back pays out less than went in, after fees both ways
+        let back = reserves.sol_out(out);
+        let gross = 30_000_852_951u128 * out as u128 / (1_000_000_000_000_000 + out as u128);
+        assert_eq!(back as u128, gross - gross * 125 / 10_000);
+        assert!(back < 100_000_000);
+        assert_eq!(reserves.sol_out(0), 0);
+    }
+
+    #[test]
+    fn trades_set_up_and_close_the_wsol_account_around_the_swap() {
+        let payer = Pubkey::new_unique();
+        let mint = Pubkey::new_unique();
+        let wsol_account = get_associated_token_address(&payer, &WSOL_MINT);
+        let close = close_account(&token_program_id(), &wsol_account, &payer, &payer, &[]).unwrap();
+
+        let buy = buy_instructions(&payer, &mint, 1_000_000, 42);
+        assert_eq!(
+            buy,
+            vec![
+                create_associated_token_account_idempotent(&payer, &payer, &mint, &token_program_id()),
+                create_associated_token_account_idempotent(&payer, &payer, &WSOL_MINT, &token_program_id()),
+                system_instruction::transfer(&payer, &wsol_account, 1_000_000),
+                sync_native(&token_program_id(), &wsol_account).unwrap(),
+                buy_exact_in(&payer, &mint, 1_000_000, 42),
+                close.clone(),
+            ]
+        );
+        assert_eq!(&buy[4].data[..8], &BUY_EXACT_IN_DISCRIMINATOR);
+
+        let sell = sell_instructions(&payer, &mint, 5_000, 7);
+        assert_eq!(
+            sell,
+            vec![
+                create_associated_token_account_idempotent(&payer, &payer, &WSOL_MINT, &token_program_id()),
+                sell_exact_in(&payer, &mint, 5_000, 7),
+                close,
+            ]
+        );
+        assert_eq!(&sell[1].data[..8], &SELL_EXACT_IN_DISCRIMINATOR);
+        assert_eq!(&sell[1].data[8..24], [5_000u64.to_le_bytes(), 7u64.to_le_bytes()].concat().as_slice());
+        // Same accounts as the buy: the proceeds land in the WSOL account that gets closed
+        assert_eq!(sell[1].accounts, buy[4].accounts);
+        assert_eq!(sell[1].accounts[6].pubkey, wsol_account);
+    }
+}
@@ -5,10 +5,24 @@ pub mod candidate_buffer;
 pub mod rpc_manager;
 pub mod nonce_manager;
 pub mod buy_engine;
+pub mod candidate_filter;
+pub mod balance;
 pub mod sniffer;
 pub mod gui;
 pub mod wallet;
 pub mod tx_builder;
+pub mod letsbonk;
+pub mod raydium;
+pub mod migration;
+pub mod reconciliation;
+pub mod recovery;
+pub mod portfolio;
+pub mod slippage;
+pub mod schedule;
+pub mod user_messages;
+pub mod audit;
+pub mod persistence;
+pub mod simulation;
 pub mod metrics;
 pub mod structured_logging;
 pub mod security;
@@ -17,9 +31,14 @@ pub mod quantum_selector;
 pub mod oracle;
 pub mod modes;
 pub mod observability;
+pub mod preflight;
 pub mod test_environment;
 pub mod market_maker;
 pub mod token_generator;
+pub mod warm_pool;
+
+#[cfg(test)]
+mod test_support;
 
 
 
@@ -1,50 +1,97 @@
 //! Application entry: wires sniffer (mock/real), buy engine, and GUI together.
 
+use std::net::SocketAddr;
+use std::path::Path;
 use std::sync::Arc;
 use std::time::Duration;
 
-use tokio::sync::{mpsc, Mutex};
-use tracing::{error, info};
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_sdk::commitment_config::CommitmentConfig;
+use solana_sdk::pubkey::Pubkey;
+use tokio::sync::{mpsc, oneshot, Mutex};
+use tracing::{error, info, warn, Level};
+use tracing_subscriber::prelude::*;
 use tracing_subscriber::EnvFilter;
 
-use sniffer_bot_light::buy_engine::BuyEngine;
+use sniffer_bot_light::audit::AuditLog;
+use sniffer_bot_light::balance::{BalancePoller, SolBalance};
+use sniffer_bot_light::candidate_buffer::{forward_by_policy, new_shared_with_policy, restore_shared};
+use sniffer_bot_light::buy_engine::{buy_transaction_config, BuyEngine, EngineCommand};
 use sniffer_bot_light::config::{Config, SnifferMode};
-use sniffer_bot_light::gui::{launch_gui, GuiEvent, GuiEventSender};
-use sniffer_bot_light::nonce_manager::NonceManager;
+use sniffer_bot_light::endpoints::endpoint_server;
+use sniffer_bot_light::gui::{
+    gui_log_layer, launch_gui, load_style_from_file, GuiEvent, GuiEventSender, GuiStyleSlot,
+};
+use sniffer_bot_light::migration::MigrationDetector;
+use sniffer_bot_light::reconciliation::{PositionReconciler, WalletTokenBalances};
+use sniffer_bot_light::recovery::{
+    audit_buy_mints, install_panic_hook, load_snapshot, recover_position, wallet_token_balances,
+    StateSnapshotter,
+};
+use sniffer_bot_light::nonce_manager::{ensure_durable_nonces, NonceManager};
+use sniffer_bot_light::preflight::run_preflight;
+use sniffer_bot_light::quantum_selector::{OracleConfig, PredictiveOracle, ScoredCandidate};
 use sniffer_bot_light::rpc_manager::{RpcBroadcaster, RpcManager};
-use sniffer_bot_light::sniffer;
-use sniffer_bot_light::sniffer::runner::SnifferRunner;
+use sniffer_bot_light::security::{validator, watch_list_files};
+use sniffer_bot_light::sniffer::supervisor::SnifferSupervisor;
 use sniffer_bot_light::tx_builder::{TransactionBuilder, TransactionConfig};
-use sniffer_bot_light::types::{AppState, CandidateReceiver, CandidateSender, Mode, ProgramLogEvent};
+use sniffer_bot_light::types::{
+    AppState, CandidateReceiver, CandidateSender, Mode, NotificationLevel, PnlLedger,
+    ProgramLogEvent, QuantumSuggestions,
+};
+use sniffer_bot_light::user_messages::user_message;
 use sniffer_bot_light::wallet::WalletManager;
+use sniffer_bot_light::warm_pool::WarmPool;
+
+/// Log events buffered for the GUI between frames; more are dropped.
+const GUI_LOG_CHANNEL_CAPACITY: usize = 256;
+
+/// GUI trade commands queued for the engine.
+const ENGINE_COMMAND_CAPACITY: usize = 16;
 
 #[tokio::main(flavor = "multi_thread")]
 async fn main() -> anyhow::Result<()> {
-    tracing_subscriber::fmt()
-        .with_env_filter(EnvFilter::from_default_env())
-        .with_target(false)
+    // Warnings and errors also go to the GUI event log
+    let (gui_log, gui_log_rx) = gui_log_layer(Level::WARN, GUI_LOG_CHANNEL_CAPACITY);
+    tracing_subscriber::registry()
+        .with(EnvFilter::from_default_env())
+        .with(tracing_subscriber::fmt::layer().with_target(false))
+        .with(gui_log)
         .init();
 
+    let args: Vec<String> = std::env::args().collect();
+    if let Some(at) = args.iter().position(|arg| arg == "--preflight") {
+        std::process::exit(preflight(args.get(at + 1).map(String::as_str)).await);
+    }
+
     let cfg = Config::load();
-    info!("Loaded config: {:?}", cfg);
+    info!("{}", cfg.loaded_log_line());
+    endpoint_server().set_max_scoreboard_entries(cfg.scoreboard_max_entries);
+    if let Err(e) = validator().load_lists(&cfg) {
+        warn!(error = %e, "Creator/mint blocklist file not loaded");
+    }
 
     let app_state = Arc::new(Mutex::new(AppState {
         mode: Mode::Sniffing,
         active_token: None,
         last_buy_price: None,
         holdings_percent: 0.0,
-        quantum_suggestions: Vec::new(),
+        quantum_suggestions: QuantumSuggestions::new(cfg.max_quantum_suggestions),
+        notifications: Default::default(),
+        operations: Default::default(),
+        positions: Default::default(),
+        pnl: PnlLedger::with_history_samples(cfg.price_history_samples),
     }));
 
     let (cand_tx, cand_rx): (CandidateSender, CandidateReceiver) = mpsc::channel(1024);
-    let (raw_tx, _raw_rx): (mpsc::Sender<ProgramLogEvent>, mpsc::Receiver<ProgramLogEvent>) =
+    let (raw_tx, raw_rx): (mpsc::Sender<ProgramLogEvent>, mpsc::Receiver<ProgramLogEvent>) =
         mpsc::channel(256);
     let (gui_tx, mut gui_rx): (GuiEventSender, mpsc::Receiver<GuiEvent>) = mpsc::channel(64);
 
 
     let prod = Arc::new(RpcManager::new_with_config(cfg.rpc_endpoints.clone(), cfg.clone()));
     let rpc: Arc<dyn RpcBroadcaster> = prod.clone();
-    let nonce_manager = Arc::new(NonceManager::new(cfg.nonce_count));
+    let mut nonce_manager = Arc::new(NonceManager::new(cfg.nonce_count));
 
     // Setup wallet and transaction builder if keypair is configured
     let tx_builder = if let Some(keypair_path) = &cfg.keypair_path {
@@ -53,13 +100,29 @@ async fn main() -> anyhow::Result<()> {
                 let primary_endpoint = cfg.rpc_endpoints.first()
                     .unwrap_or(&"https://api.devnet.solana.com".to_string()).clone();
                 let config = TransactionConfig::default();
+                // The engine and the builder share the nonce slots: buys are built on the
+                // slot the engine holds, so a durable slot's nonce reaches the builder
+                if cfg.durable_nonces && cfg.paper_trading {
+                    // Creating nonce accounts spends real SOL; paper trades never need them
+                    info!("Paper trading: skipping durable nonce setup, using recent blockhashes");
+                } else if cfg.durable_nonces {
+                    match ensure_durable_nonces(&RpcClient::new(primary_endpoint.clone()), &wallet, cfg.nonce_count).await {
+                        Ok(nonces) => nonce_manager = Arc::new(NonceManager::with_durable_nonces(nonces)),
+                        Err(e) => {
+                            error!("Failed to set up durable nonce accounts: {}", e);
+                            info!("Continuing with recent blockhashes");
+                        }
+                    }
+                }
                 match TransactionBuilder::new(
                     Arc::new(wallet), 
                     vec![primary_endpoint], 
                     nonce_manager.clone(), 
                     &config
                 ).await {
-                    Ok(builder) => Some(builder),
+                    Ok(builder) => Some(builder.with_warm_pool(Arc::new(WarmPool::new(
+                        if cfg.warm_pool_enabled { cfg.warm_pool_max_entries } else { 0 },
+                    )))),
                     Err(e) => {
                         error!("Failed to create transaction builder: {}", e);
                         info!("Continuing without transaction builder - will use placeholder transactions");
@@ -78,6 +141,93 @@ async fn main() -> anyhow::Result<()> {
         None
     };
 
+    let wallet_pubkey = tx_builder.as_ref().map(|builder| builder.wallet.pubkey());
+
+    let audit_log = match &cfg.audit_log_path {
+        Some(path) => match AuditLog::open(path) {
+            Ok(log) => log,
+            Err(e) => {
+                error!("Failed to open audit log {}: {}", path, e);
+                AuditLog::disabled()
+            }
+        },
+        None => AuditLog::disabled(),
+    };
+
+    let store = if cfg.state_snapshot_interval_ms > 0 {
+        match cfg.persistence.open() {
+            Ok(store) => Some(store),
+            Err(e) => {
+                error!("Failed to open persistence store: {}", e);
+                None
+            }
+        }
+    } else {
+        None
+    };
+
+    let snapshot = store.as_deref().and_then(load_snapshot);
+    let wallet_balances = match (cfg.recover_positions_from_chain, cfg.rpc_endpoints.first(), wallet_pubkey) {
+        (true, Some(endpoint), Some(owner)) => {
+            match wallet_token_balances(&RpcClient::new(endpoint.clone()), &owner).await {
+                Ok(balances) => balances,
+                Err(e) => {
+                    error!("Failed to scan wallet for position recovery: {}", e);
+                    Vec::new()
+                }
+            }
+        }
+        _ => Vec::new(),
+    };
+    let known_buys = cfg
+        .audit_log_path
+        .as_deref()
+        .and_then(|path| audit_buy_mints(path).ok())
+        .unwrap_or_default();
+    if let Some(source) = recover_position(&app_state, snapshot, &wallet_balances, &known_buys).await {
+        info!("Recovered held position from {:?}", source);
+    }
+    install_panic_hook(store.clone(), app_state.clone());
+
+    let sol_balance = Arc::new(SolBalance::new());
+    let balance_poller = match (cfg.rpc_endpoints.first(), wallet_pubkey) {
+        (Some(endpoint), Some(owner)) if cfg.min_sol_reserve_lamports > 0 => Some(BalancePoller::new(
+            Arc::new(RpcClient::new(endpoint.clone())),
+            owner,
+            sol_balance.clone(),
+            Duration::from_millis(cfg.balance_poll_interval_ms.max(1)),
+        )),
+        _ => None,
+    };
+
+    // With the oracle, sniffed candidates are scored before the engine sees them.
+    // Not buying unscored candidates the operator wanted gated, a failed start is fatal.
+    let (sniffer_tx, scored_rx) = if cfg.oracle_enabled {
+        let (oracle_tx, oracle_rx) = mpsc::channel(1024);
+        let (scored_tx, oracle_scored_rx) = mpsc::channel(256);
+        let oracle_config = OracleConfig { rpc_endpoints: cfg.rpc_endpoints.clone(), ..OracleConfig::default() };
+        let mut oracle = PredictiveOracle::new(oracle_rx, scored_tx, oracle_config)
+            .map_err(|e| anyhow::anyhow!("failed to start the quantum selector: {}", e))?;
+        tokio::spawn(async move { oracle.run().await });
+        // Scores waiting for the engine are taken in `sniffer_buffer_policy` order
+        let (engine_scored_tx, scored_rx) = mpsc::channel(1);
+        let scored_buffer = new_shared_with_policy(
+            Duration::from_millis(cfg.candidate_dedup_ttl_ms),
+            cfg.sniffer_buffer_capacity,
+            cfg.sniffer_buffer_policy,
+        );
+        tokio::spawn(forward_by_policy(
+            oracle_scored_rx,
+            scored_buffer,
+            engine_scored_tx,
+            |scored: &ScoredCandidate| Some((scored.candidate.clone(), scored.predicted_score)),
+        ));
+        info!("Quantum selector scoring candidates; buying scores >= {}", cfg.min_buy_score);
+        (oracle_tx, Some(scored_rx))
+    } else {
+        (cand_tx.clone(), None)
+    };
+
     let engine_state = app_state.clone();
     let mut engine = BuyEngine::new(
         rpc.clone(),
@@ -86,68 +236,138 @@ async fn main() -> anyhow::Result<()> {
         engine_state,
         cfg.clone(),
         tx_builder,
-    );
+    )
+    .with_audit_log(Arc::new(audit_log))
+    .with_rpc_health(prod.health());
+    if balance_poller.is_some() {
+        engine = engine.with_sol_balance(sol_balance);
+    }
+    if let Some(scored_rx) = scored_rx {
+        engine = engine.with_scored_candidates(scored_rx);
+    }
+    if let (Some(endpoint), Some(owner)) = (cfg.rpc_endpoints.first(), wallet_pubkey) {
+        // Fill checks read right after a buy lands: finalized balances would lag it
+        let rpc = RpcClient::new_with_commitment(endpoint.clone(), CommitmentConfig::confirmed());
+        let balances = WalletTokenBalances::new(Arc::new(rpc), owner);
+        engine = engine.with_token_balances(Arc::new(balances));
+    }
+    let checks_mints = cfg.sellability_check || cfg.require_renounced_authorities;
+    if let (true, Some(endpoint)) = (checks_mints, cfg.rpc_endpoints.first()) {
+        // Candidates are fresh mints: read them at processed commitment or they are not found yet
+        let rpc = RpcClient::new_with_commitment(endpoint.clone(), CommitmentConfig::processed());
+        engine = engine.with_mint_rpc(Arc::new(rpc));
+    }
 
-    let sniffer_handle = match cfg.sniffer_mode {
-        SnifferMode::Mock => {
-            info!("Starting MOCK sniffer");
-            sniffer::run_mock_sniffer(cand_tx.clone())
+    // One buffer across source switches, restored from the last shutdown when saved
+    let sniffer_buffer = cfg.sniffer_buffer_candidates.then(|| {
+        let ttl = Duration::from_millis(cfg.candidate_dedup_ttl_ms);
+        match cfg.sniffer_buffer_path.as_deref() {
+            Some(path) => restore_shared(Path::new(path), ttl, cfg.sniffer_buffer_capacity, cfg.sniffer_buffer_policy),
+            None => new_shared_with_policy(ttl, cfg.sniffer_buffer_capacity, cfg.sniffer_buffer_policy),
         }
-        SnifferMode::Real => {
-            info!("Starting REAL sniffer runner (WSS + HTTP fallback)");
-            let runner = SnifferRunner::new(cfg.clone());
-            tokio::spawn(async move {
-                runner.run(cand_tx.clone(), Some(raw_tx)).await;
-            })
-        }
-    };
+    });
+    let mut supervisor = SnifferSupervisor::new(cfg.clone(), sniffer_tx, Some(raw_tx));
+    if let Some(buffer) = &sniffer_buffer {
+        supervisor = supervisor.with_candidate_buffer(buffer.clone());
+    }
+    let sniffer = Arc::new(supervisor);
+    sniffer.switch_mode(cfg.sniffer_mode).await;
 
+    // GUI trades and `/simulate` queries run on the main engine, with its transaction builder and buy guards
+    let (command_tx, command_rx) = mpsc::channel::<EngineCommand>(ENGINE_COMMAND_CAPACITY);
+    engine = engine.with_commands(command_rx);
+    endpoint_server().enable_simulation(cfg.clone(), app_state.clone(), command_tx.clone()).await;
     let engine_app_state = app_state.clone();
-    let rpc_for_sell: Arc<dyn RpcBroadcaster> = rpc.clone();
-    let nonce_for_sell = nonce_manager.clone();
     let cfg_for_sell = cfg.clone();
+    let sniffer_for_gui = sniffer.clone();
+    let gui_style = GuiStyleSlot::default();
+    if let Some(path) = &cfg.gui_style_path {
+        match load_style_from_file(path) {
+            Ok(style) => *gui_style.lock().unwrap() = Some(style),
+            Err(e) => warn!(path = %path, error = %e, "GUI style not loaded, using the default"),
+        }
+    }
+    let style_for_gui = gui_style.clone();
     let sell_task = tokio::spawn(async move {
-        struct SellHandle {
-            rpc: Arc<dyn RpcBroadcaster>,
+        struct TradeHandle {
+            commands: mpsc::Sender<EngineCommand>,
             state: Arc<Mutex<AppState>>,
-            nonce: Arc<NonceManager>,
-            cfg: Config,
         }
-        impl SellHandle {
+        impl TradeHandle {
+            /// Sell `percent` of the position the GUI shows (the focused one).
             async fn sell(&self, percent: f64) -> anyhow::Result<()> {
-                let (_tx, rx) = mpsc::channel(1);
-                let engine = BuyEngine::new(
-                    self.rpc.clone(),
-                    self.nonce.clone(),
-                    rx,
-                    self.state.clone(),
-                    self.cfg.clone(),
-                    None, // No transaction builder needed for sell-only handle
-                );
-                engine.sell(percent).await?;
+                let (reply, result) = oneshot::channel();
+                self.commands.send(EngineCommand::Sell { percent, reply }).await?;
+                result.await??;
+                Ok(())
+            }
+
+            /// Buy `amount_lamports` worth of the suggested `mint`.
+            async fn buy(&self, mint: Pubkey, amount_lamports: u64) -> anyhow::Result<()> {
+                let (reply, result) = oneshot::channel();
+                self.commands.send(EngineCommand::Buy { mint, amount_lamports, reply }).await?;
+                result.await??;
                 Ok(())
             }
         }
-        let handle = SellHandle {
-            rpc: rpc_for_sell.clone(),
+        let handle = TradeHandle {
+            commands: command_tx,
             state: engine_app_state.clone(),
-            nonce: nonce_for_sell.clone(),
-            cfg: cfg_for_sell.clone(),
         };
         while let Some(ev) = gui_rx.recv().await {
             match ev {
                 GuiEvent::SellPercent(p) => {
                     if let Err(e) = handle.sell(p).await {
                         error!(percent=p, error=%e, "Sell failed");
+                        handle.state.lock().await.notify(
+                            NotificationLevel::Error,
+                            format!("Sell failed: {}", user_message(&e)),
+                        );
                     }
                 }
-                GuiEvent::Buy(pubkey) => {
-                    info!("GUI requested buy for pubkey: {}", pubkey);
-                    // Handle buy event if needed
+                GuiEvent::Buy { mint, amount_lamports } => {
+                    info!(mint = %mint, amount_lamports, "GUI requested buy");
+                    if let Err(e) = handle.buy(mint, amount_lamports).await {
+                        error!(mint = %mint, error = %e, "Buy failed");
+                        handle.state.lock().await.notify(
+                            NotificationLevel::Error,
+                            format!("Buy failed: {}", user_message(&e)),
+                        );
+                    }
                 }
                 GuiEvent::ReloadStyle => {
                     info!("GUI requested style reload");
-                    // Handle style reload if needed
+                    let Some(path) = cfg_for_sell.gui_style_path.as_deref() else {
+                        handle.state.lock().await.notify(NotificationLevel::Warn, "No gui_style_path configured");
+                        continue;
+                    };
+                    match load_style_from_file(path) {
+                        Ok(style) => {
+                            *style_for_gui.lock().unwrap() = Some(style);
+                            handle.state.lock().await.notify(
+                                NotificationLevel::Info,
+                                format!("Style reloaded from {}", path),
+                            );
+                        }
+                        Err(e) => {
+                            warn!(path = %path, error = %e, "Style reload failed");
+                            handle.state.lock().await.notify(
+                                NotificationLevel::Warn,
+                                format!("Style reload failed: {:#}", e),
+                            );
+                        }
+                    }
+                }
+                GuiEvent::ResetPnl => {
+                    info!("GUI requested PnL reset");
+                    handle.state.lock().await.reset_realized_pnl();
+                }
+                GuiEvent::SwitchSniffer(mode) => {
+                    sniffer_for_gui.switch_mode(mode).await;
+                    handle.state.lock().await.notify(
+                        NotificationLevel::Info,
+                        format!("Sniffer switched to {:?}", mode),
+                    );
                 }
             }
         }
@@ -157,16 +377,137 @@ async fn main() -> anyhow::Result<()> {
         engine.run().await;
     });
 
+    let health_probe_task = (cfg.rpc_health_probe_interval_ms > 0).then(|| {
+        tokio::spawn(prod.clone().run_health_probe(Duration::from_millis(cfg.rpc_health_probe_interval_ms)))
+    });
+
+    let balance_task = balance_poller.map(|poller| tokio::spawn(poller.run()));
+
+    let snapshot_task = store.map(|store| {
+        let snapshotter = StateSnapshotter::new(
+            store,
+            app_state.clone(),
+            Duration::from_millis(cfg.state_snapshot_interval_ms),
+        );
+        tokio::spawn(snapshotter.run())
+    });
+
+    let migration_task = match (cfg.sniffer_mode, cfg.rpc_endpoints.first()) {
+        (SnifferMode::Real, Some(endpoint)) if cfg.migration_check_interval_ms > 0 => {
+            let detector = MigrationDetector::new(
+                Arc::new(RpcClient::new(endpoint.clone())),
+                app_state.clone(),
+                Duration::from_millis(cfg.migration_check_interval_ms),
+            )
+            .with_log_events(raw_rx);
+            Some(tokio::spawn(detector.run()))
+        }
+        _ => {
+            // Nothing else reads the raw log stream; closing it keeps the sources from blocking on it
+            drop(raw_rx);
+            None
+        }
+    };
+
+    let has_list_files = cfg.creator_blocklist_path.is_some() || cfg.mint_blocklist_path.is_some();
+    let list_reload_task = (has_list_files && cfg.creator_blocklist_reload_ms > 0)
+        .then(|| tokio::spawn(watch_list_files(cfg.clone())));
+
+    let endpoint_task = cfg.endpoint_server_enabled.then(|| {
+        let addr = SocketAddr::from(([127, 0, 0, 1], cfg.endpoint_server_port));
+        tokio::spawn(async move {
+            if let Err(e) = endpoint_server().clone().serve(addr).await {
+                error!(error = %e, "Endpoint server stopped");
+            }
+        })
+    });
+
+    let reconcile_task = match (cfg.sniffer_mode, cfg.rpc_endpoints.first(), wallet_pubkey) {
+        // Paper positions hold no tokens on chain; reconciling would close them
+        (SnifferMode::Real, Some(endpoint), Some(owner)) if cfg.reconcile_interval_ms > 0 && !cfg.paper_trading => {
+            let reconciler = PositionReconciler::new(
+                Arc::new(RpcClient::new(endpoint.clone())),
+                app_state.clone(),
+                owner,
+                Duration::from_millis(cfg.reconcile_interval_ms),
+            );
+            Some(tokio::spawn(reconciler.run()))
+        }
+        _ => None,
+    };
+
     launch_gui(
         "Sniffer Bot (GUI)",
         app_state.clone(),
         gui_tx.clone(),
         Duration::from_millis(cfg.gui_update_interval_ms),
+        gui_style,
+        gui_log_rx,
+        buy_transaction_config(&cfg, false).buy_amount_lamports,
     )?;
 
-    sniffer_handle.abort();
+    sniffer.stop().await;
+    if let (Some(buffer), Some(path)) = (&sniffer_buffer, cfg.sniffer_buffer_path.as_deref()) {
+        match buffer.lock().await.save(Path::new(path)) {
+            Ok(()) => info!(path = %path, "Saved the sniffer candidate buffer"),
+            Err(e) => warn!(path = %path, error = %e, "Sniffer candidate buffer not saved"),
+        }
+    }
     engine_task.abort();
     sell_task.abort();
+    if let Some(task) = snapshot_task {
+        task.abort();
+    }
+    if let Some(task) = balance_task {
+        task.abort();
+    }
+    if let Some(task) = health_probe_task {
+        task.abort();
+    }
+    if let Some(task) = migration_task {
+        task.abort();
+    }
+    if let Some(task) = reconcile_task {
+        task.abort();
+    }
+    if let Some(task) = list_reload_task {
+        task.abort();
+    }
+    if let Some(task) = endpoint_task {
+        task.abort();
+    }
 
     Ok(())
-}
\ No newline at end of file
+}
+/// `--preflight <mint>`: build and sign, but never send, a buy of `mint` with the
+/// current config.toml. Prints GO or NO-GO with the failing step; returns the exit code.
+async fn preflight(mint: Option<&str>) -> i32 {
+    let mint = match mint.map(str::parse::<Pubkey>) {
+        Some(Ok(mint)) => mint,
+        Some(Err(e)) => {
+            println!("NO-GO: invalid mint: {}", e);
+            return 1;
+        }
+        None => {
+            println!("NO-GO: usage: --preflight <mint>");
+            return 1;
+        }
+    };
+    let cfg = match Config::try_load("config.toml") {
+        Ok(cfg) => cfg,
+        Err(e) => {
+            println!("NO-GO: config: {}", e);
+            return 1;
+        }
+    };
+    match run_preflight(&cfg, mint).await {
+        Ok(report) => {
+            println!("GO: {}", report);
+            0
+        }
+        Err(e) => {
+            println!("NO-GO: {}", e);
+            1
+        }
+    }
+}
@@ -20,6 +20,7 @@ use tracing::{debug, error, info, warn};
 use crate::token_generator::{TokenProfile, GeneratedToken};
 use crate::wallet::WalletManager;
 use crate::tx_builder::TransactionBuilder;
+use crate::types::DexProgram;
 
 /// Defines the current market phase for a token, driving the simulation's behavior.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -37,6 +38,7 @@ pub enum MarketPhase {
 pub struct TokenState {
     pub mint: Pubkey,
     pub profile: TokenProfile,
+    pub dex: DexProgram,
     pub created_at: Instant,
     pub activity_count: u32,
     pub is_active: bool,
@@ -119,6 +121,7 @@ impl MarketMaker {
         let token_state = TokenState {
             mint: token.mint,
             profile: token.profile,
+            dex: token.dex.clone(),
             created_at: Instant::now(),
             activity_count: 0,
             is_active: true,
@@ -126,7 +129,7 @@ impl MarketMaker {
             phase_start_time: Instant::now(),
         };
         self.live_tokens.write().await.insert(token.mint, token_state);
-        info!("📈 Added token {} with profile {:?} to MarketMaker, starting in Hype phase.", token.mint, token.profile);
+        info!("📈 Added token {} with profile {:?} on {} to MarketMaker, starting in Hype phase.", token.mint, token.profile, token.dex.canonical_name());
     }
 
     /// Get the number of tokens currently being managed
@@ -236,9 +239,16 @@ impl MarketMaker {
 
     async fn simulate_trader_activity(&self, token_state: &mut TokenState) {
         let trader = &self.trader_wallets[fastrand::usize(..self.trader_wallets.len())];
-        debug!("💎 Simulating trader activity for {} from wallet {}", token_state.mint, trader.pubkey());
+        debug!(
+            "💎 Simulating {} for {} on {} from wallet {}",
+            dex_activity(&token_state.dex),
+            token_state.mint,
+            token_state.dex.canonical_name(),
+            trader.pubkey()
+        );
         token_state.activity_count += 1;
-        // In a full implementation, this would call tx_builder to create a buy/sell tx.
+        // In a full implementation, this would call tx_builder to create a buy/sell tx
+        // against the token's DEX program.
     }
     
     // Simplified handlers for Rug and Trash
@@ -278,3 +288,13 @@ impl MarketMaker {
         }
     }
 }
+
+/// Kind of trade the simulator produces for tokens on `dex`.
+pub fn dex_activity(dex: &DexProgram) -> &'static str {
+    match dex {
+        DexProgram::PumpFun | DexProgram::LetsBonk => "bonding-curve trade",
+        DexProgram::Raydium => "AMM swap",
+        DexProgram::Orca => "whirlpool swap",
+        DexProgram::Unknown(_) => "transfer",
+    }
+}
@@ -1,17 +1,18 @@
 use std::{
-    collections::HashMap,
+    collections::{BTreeMap, HashMap, HashSet, VecDeque},
+    fmt::Write,
     sync::{
         atomic::{AtomicU64, Ordering},
         Arc, RwLock,
     },
-    time::{Duration, Instant},
+    time::Instant,
 };
 
 /// Basic metrics collection system for telemetry
 #[derive(Debug, Default)]
 pub struct MetricsRegistry {
     counters: RwLock<HashMap<String, Arc<AtomicU64>>>,
-    histograms: RwLock<HashMap<String, Arc<RwLock<Vec<u64>>>>>,
+    histograms: RwLock<HashMap<String, Arc<RwLock<Histogram>>>>,
     gauges: RwLock<HashMap<String, Arc<AtomicU64>>>,
 }
 
@@ -55,28 +56,37 @@ impl MetricsRegistry {
         }
     }
 
-    /// Record histogram value (duration in milliseconds)
-    pub fn record_histogram(&self, name: &str, duration: Duration) {
-        let millis = duration.as_millis() as u64;
+    /// Register histogram `name` with the given bucket upper bounds, replacing any
+    /// values recorded under it. Unregistered histograms use `DEFAULT_BUCKETS`.
+    pub fn register_histogram(&self, name: &str, bounds: &[f64]) {
+        self.histograms
+            .write()
+            .unwrap()
+            .insert(name.to_string(), Arc::new(RwLock::new(Histogram::new(bounds))));
+    }
+
+    /// Record histogram value (durations in seconds)
+    pub fn record_histogram(&self, name: &str, value: f64) {
         let histograms = self.histograms.read().unwrap();
         if let Some(histogram) = histograms.get(name) {
-            let mut hist = histogram.write().unwrap();
-            hist.push(millis);
-            // Keep only last 1000 values to prevent unbounded growth
-            if hist.len() > 1000 {
-                hist.drain(0..500);
-            }
+            histogram.write().unwrap().observe(value);
         } else {
             drop(histograms);
             let mut histograms = self.histograms.write().unwrap();
             let histogram = histograms
                 .entry(name.to_string())
-                .or_insert_with(|| Arc::new(RwLock::new(Vec::new())));
-            let mut hist = histogram.write().unwrap();
-            hist.push(millis);
+                .or_insert_with(|| Arc::new(RwLock::new(Histogram::new(DEFAULT_BUCKETS))));
+            histogram.write().unwrap().observe(value);
         }
     }
 
+    /// The `p`th percentile (0-100) of histogram `name`; `None` when nothing was recorded
+    pub fn percentile(&self, name: &str, p: f64) -> Option<f64> {
+        let histograms = self.histograms.read().unwrap();
+        let histogram = histograms.get(name)?.read().unwrap();
+        histogram.percentile(p)
+    }
+
     /// Get counter value
     pub fn get_counter(&self, name: &str) -> u64 {
         self.counters
@@ -100,23 +110,7 @@ impl MetricsRegistry {
     /// Get histogram statistics
     pub fn get_histogram_stats(&self, name: &str) -> Option<HistogramStats> {
         let histograms = self.histograms.read().unwrap();
-        histograms.get(name).and_then(|h| {
-            let hist = h.read().unwrap();
-            if hist.is_empty() {
-                return None;
-            }
-            let mut sorted = hist.clone();
-            sorted.sort_unstable();
-            let len = sorted.len();
-            Some(HistogramStats {
-                count: len as u64,
-                min: sorted[0],
-                max: sorted[len - 1],
-                p50: sorted[len / 2],
-                p95: sorted[len * 95 / 100],
-                p99: sorted[len * 99 / 100],
-            })
-        })
+        histograms.get(name).and_then(|h| h.read().unwrap().stats())
     }
 
     /// Export all metrics in a simple format
@@ -142,10 +136,7 @@ impl MetricsRegistry {
             .read()
             .unwrap()
             .iter()
-            .filter_map(|(k, _v)| {
-                self.get_histogram_stats(k)
-                    .map(|stats| (k.clone(), stats))
-            })
+            .filter_map(|(k, v)| v.read().unwrap().stats().map(|stats| (k.clone(), stats)))
             .collect();
 
         MetricsSnapshot {
@@ -154,16 +145,195 @@ impl MetricsRegistry {
             histograms,
         }
     }
+
+    /// All metrics in the Prometheus text exposition format, sorted by name.
+    /// Names are normalized to valid Prometheus identifiers (see `unique_prometheus_names`
+    /// for names that normalize alike); histograms render with cumulative buckets,
+    /// `_sum` and `_count`.
+    pub fn render_prometheus(&self) -> String {
+        let snapshot = self.export_metrics();
+        let mut taken = HashSet::new();
+        let mut output = String::new();
+
+        for (name, value) in unique_prometheus_names(snapshot.counters, &mut taken) {
+            let _ = writeln!(output, "# TYPE {} counter\n{} {}", name, name, value);
+        }
+        for (name, value) in unique_prometheus_names(snapshot.gauges, &mut taken) {
+            let _ = writeln!(output, "# TYPE {} gauge\n{} {}", name, name, value);
+        }
+
+        let histograms: Vec<(String, Histogram)> = self
+            .histograms
+            .read()
+            .unwrap()
+            .iter()
+            .map(|(name, histogram)| (name.clone(), histogram.read().unwrap().clone()))
+            .collect();
+        let histograms = unique_prometheus_names(histograms, &mut taken);
+        for (name, histogram) in histograms {
+            let _ = writeln!(output, "# TYPE {} histogram", name);
+            for (bound, count) in histogram.cumulative_buckets() {
+                let le = if bound.is_finite() { bound.to_string() } else { "+Inf".to_string() };
+                let _ = writeln!(output, "{}_bucket{{le=\"{}\"}} {}", name, le, count);
+            }
+            let _ = writeln!(output, "{}_sum {}\n{}_count {}", name, histogram.sum(), name, histogram.count());
+        }
+        output
+    }
+}
+
+/// `name` as a Prometheus metric name: characters outside `[a-zA-Z0-9_:]` become
+/// `_`, and a leading digit gets a `_` prefix.
+pub fn prometheus_name(name: &str) -> String {
+    let mut normalized: String = name
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() || c == '_' || c == ':' { c } else { '_' })
+        .collect();
+    if normalized.is_empty() || normalized.starts_with(|c: char| c.is_ascii_digit()) {
+        normalized.insert(0, '_');
+    }
+    normalized
+}
+
+/// Metrics keyed by Prometheus name. Raw names are taken in sorted order, and one whose
+/// normalized name is already in `taken` gets the first free `_2`, `_3`, ... suffix
+/// (with a warning), so no series is dropped and the suffixes stay stable across scrapes.
+fn unique_prometheus_names<T>(
+    metrics: impl IntoIterator<Item = (String, T)>,
+    taken: &mut HashSet<String>,
+) -> BTreeMap<String, T> {
+    let by_raw_name: BTreeMap<String, T> = metrics.into_iter().collect();
+    let mut named = BTreeMap::new();
+    for (raw, value) in by_raw_name {
+        let base = prometheus_name(&raw);
+        let mut name = base.clone();
+        let mut suffix = 2;
+        while taken.contains(&name) {
+            name = format!("{}_{}", base, suffix);
+            suffix += 1;
+        }
+        if name != base {
+            tracing::warn!(metric = %raw, exported_as = %name, "Metric name collides with another after normalization");
+        }
+        taken.insert(name.clone());
+        named.insert(name, value);
+    }
+    named
+}
+
+/// Bucket upper bounds, in seconds, of histograms recorded without registering
+pub const DEFAULT_BUCKETS: &[f64] = &[0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0];
+
+/// Recent values each histogram keeps for percentiles
+const PERCENTILE_WINDOW: usize = 1000;
+
+/// Bucketed histogram. Bucket counts, count and sum cover every recorded value;
+/// percentiles, min and max cover the last `PERCENTILE_WINDOW` values.
+#[derive(Debug, Clone)]
+pub struct Histogram {
+    bounds: Vec<f64>,
+    /// Values per bucket, the last one for values above every bound
+    buckets: Vec<u64>,
+    count: u64,
+    sum: f64,
+    recent: VecDeque<f64>,
+}
+
+impl Histogram {
+    /// Histogram with the given bucket upper bounds; they are sorted, and
+    /// non-finite and duplicate bounds dropped.
+    pub fn new(bounds: &[f64]) -> Self {
+        let mut bounds: Vec<f64> = bounds.iter().copied().filter(|bound| bound.is_finite()).collect();
+        bounds.sort_by(f64::total_cmp);
+        bounds.dedup();
+        Self {
+            buckets: vec![0; bounds.len() + 1],
+            bounds,
+            count: 0,
+            sum: 0.0,
+            recent: VecDeque::with_capacity(PERCENTILE_WINDOW),
+        }
+    }
+
+    /// Record `value`; NaN is ignored.
+    pub fn observe(&mut self, value: f64) {
+        if value.is_nan() {
+            return;
+        }
+        self.buckets[self.bounds.partition_point(|bound| *bound < value)] += 1;
+        self.count += 1;
+        self.sum += value;
+        if self.recent.len() == PERCENTILE_WINDOW {
+            self.recent.pop_front();
+        }
+        self.recent.push_back(value);
+    }
+
+    pub fn count(&self) -> u64 {
+        self.count
+    }
+
+    pub fn sum(&self) -> f64 {
+        self.sum
+    }
+
+    /// The `p`th percentile (0-100) of the recent values by nearest rank; `None` when empty.
+    pub fn percentile(&self, p: f64) -> Option<f64> {
+        nearest_rank(&self.sorted_recent(), p)
+    }
+
+    /// Each bucket upper bound, ending with +Inf, with the count of values at or below it
+    pub fn cumulative_buckets(&self) -> Vec<(f64, u64)> {
+        let mut total = 0;
+        self.bounds
+            .iter()
+            .copied()
+            .chain(std::iter::once(f64::INFINITY))
+            .zip(&self.buckets)
+            .map(|(bound, count)| {
+                total += count;
+                (bound, total)
+            })
+            .collect()
+    }
+
+    fn sorted_recent(&self) -> Vec<f64> {
+        let mut sorted: Vec<f64> = self.recent.iter().copied().collect();
+        sorted.sort_by(f64::total_cmp);
+        sorted
+    }
+
+    fn stats(&self) -> Option<HistogramStats> {
+        let sorted = self.sorted_recent();
+        Some(HistogramStats {
+            count: self.count,
+            sum: self.sum,
+            min: *sorted.first()?,
+            max: *sorted.last()?,
+            p50: nearest_rank(&sorted, 50.0)?,
+            p95: nearest_rank(&sorted, 95.0)?,
+            p99: nearest_rank(&sorted, 99.0)?,
+        })
+    }
+}
+
+/// The value at rank ceil(p% of n) of ascending `sorted`.
+fn nearest_rank(sorted: &[f64], p: f64) -> Option<f64> {
+    let rank = (p.clamp(0.0, 100.0) * sorted.len() as f64 / 100.0).ceil() as usize;
+    sorted.get(rank.saturating_sub(1)).copied()
 }
 
 #[derive(Debug, Clone, serde::Serialize)]
 pub struct HistogramStats {
+    /// Values recorded since start
     pub count: u64,
-    pub min: u64,
-    pub max: u64,
-    pub p50: u64,
-    pub p95: u64,
-    pub p99: u64,
+    /// Sum of the values recorded since start
+    pub sum: f64,
+    pub min: f64,
+    pub max: f64,
+    pub p50: f64,
+    pub p95: f64,
+    pub p99: f64,
 }
 
 #[derive(Debug)]
@@ -181,6 +351,126 @@ pub fn metrics() -> &'static MetricsRegistry {
     GLOBAL_METRICS.get_or_init(MetricsRegistry::new)
 }
 
+/// Stages of the candidate funnel, in pipeline order.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, serde::Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FunnelStage {
+    /// Candidate received by the engine while sniffing
+    Seen,
+    /// Candidate passed security validation
+    SecurityPassed,
+    /// Candidate passed rate limiting and the candidate filter
+    FilterPassed,
+    /// Candidate scored at or above the buy threshold
+    Scored,
+    /// Buy attempt started
+    Attempted,
+    /// Buy attempt succeeded
+    Succeeded,
+}
+
+impl FunnelStage {
+    pub const ALL: [FunnelStage; 6] = [
+        FunnelStage::Seen,
+        FunnelStage::SecurityPassed,
+        FunnelStage::FilterPassed,
+        FunnelStage::Scored,
+        FunnelStage::Attempted,
+        FunnelStage::Succeeded,
+    ];
+
+    /// Counter name mirrored into the global metrics registry
+    pub fn counter_name(&self) -> &'static str {
+        match self {
+            FunnelStage::Seen => "funnel_seen_total",
+            FunnelStage::SecurityPassed => "funnel_security_passed_total",
+            FunnelStage::FilterPassed => "funnel_filter_passed_total",
+            FunnelStage::Scored => "funnel_scored_total",
+            FunnelStage::Attempted => "funnel_attempted_total",
+            FunnelStage::Succeeded => "funnel_succeeded_total",
+        }
+    }
+
+    fn index(&self) -> usize {
+        *self as usize
+    }
+}
+
+/// Per-stage counts and conversion rates of the candidate funnel
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct FunnelStageStats {
+    pub stage: FunnelStage,
+    pub count: u64,
+    /// Fraction of the previous stage that reached this one
+    pub conversion_from_previous: f64,
+    /// Fraction of all seen candidates that reached this one
+    pub conversion_from_seen: f64,
+}
+
+/// Candidate funnel counters.
+///
+/// Every recorded stage is also mirrored into the global metrics registry so it shows up
+/// in the regular metrics export.
+#[derive(Debug, Default)]
+pub struct CandidateFunnel {
+    counts: [AtomicU64; 6],
+}
+
+impl CandidateFunnel {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record that a candidate reached the given stage
+    pub fn record(&self, stage: FunnelStage) {
+        self.counts[stage.index()].fetch_add(1, Ordering::Relaxed);
+        metrics().increment_counter(stage.counter_name());
+    }
+
+    /// Get count for a single stage
+    pub fn count(&self, stage: FunnelStage) -> u64 {
+        self.counts[stage.index()].load(Ordering::Relaxed)
+    }
+
+    /// Snapshot all stages with conversion rates
+    pub fn snapshot(&self) -> Vec<FunnelStageStats> {
+        let seen = self.count(FunnelStage::Seen);
+        let mut previous = seen;
+        FunnelStage::ALL
+            .iter()
+            .map(|stage| {
+                let count = self.count(*stage);
+                let stats = FunnelStageStats {
+                    stage: *stage,
+                    count,
+                    conversion_from_previous: ratio(count, previous),
+                    conversion_from_seen: ratio(count, seen),
+                };
+                previous = count;
+                stats
+            })
+            .collect()
+    }
+}
+
+fn ratio(num: u64, den: u64) -> f64 {
+    if den == 0 {
+        0.0
+    } else {
+        num as f64 / den as f64
+    }
+}
+
+/// Global candidate funnel instance
+static GLOBAL_FUNNEL: std::sync::OnceLock<Arc<CandidateFunnel>> = std::sync::OnceLock::new();
+
+/// Get global candidate funnel
+pub fn funnel() -> Arc<CandidateFunnel> {
+    GLOBAL_FUNNEL
+        .get_or_init(|| Arc::new(CandidateFunnel::new()))
+        .clone()
+}
+
 /// Timer helper for measuring duration
 pub struct Timer {
     name: String,
@@ -197,7 +487,7 @@ impl Timer {
 
     pub fn finish(self) {
         let duration = self.start.elapsed();
-        metrics().record_histogram(&self.name, duration);
+        metrics().record_histogram(&self.name, duration.as_secs_f64());
     }
 }
 
@@ -215,6 +505,7 @@ macro_rules! time_block {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use std::time::Duration;
 
     #[test]
     fn test_counter_operations() {
@@ -236,14 +527,122 @@ mod tests {
     #[test]
     fn test_histogram_operations() {
         let registry = MetricsRegistry::new();
-        registry.record_histogram("test_hist", Duration::from_millis(100));
-        registry.record_histogram("test_hist", Duration::from_millis(200));
-        registry.record_histogram("test_hist", Duration::from_millis(150));
+        registry.record_histogram("test_hist", 0.1);
+        registry.record_histogram("test_hist", 0.2);
+        registry.record_histogram("test_hist", 0.15);
 
         let stats = registry.get_histogram_stats("test_hist").unwrap();
         assert_eq!(stats.count, 3);
-        assert_eq!(stats.min, 100);
-        assert_eq!(stats.max, 200);
+        assert_eq!(stats.min, 0.1);
+        assert_eq!(stats.max, 0.2);
+    }
+
+    #[test]
+    fn test_histogram_percentiles_and_buckets() {
+        let registry = MetricsRegistry::new();
+        registry.register_histogram("tte_ms", &[100.0, 10.0, 50.0, 10.0, f64::INFINITY]);
+        assert_eq!(registry.percentile("tte_ms", 50.0), None);
+        // 1..=100 shuffled: p50 is 50 and p95 is 95 regardless of recording order
+        for value in (1..=100).map(|i| (i * 37) % 100 + 1) {
+            registry.record_histogram("tte_ms", value as f64);
+        }
+
+        assert_eq!(registry.percentile("tte_ms", 50.0), Some(50.0));
+        assert_eq!(registry.percentile("tte_ms", 95.0), Some(95.0));
+        assert_eq!(registry.percentile("tte_ms", 99.0), Some(99.0));
+        assert_eq!(registry.percentile("tte_ms", 0.0), Some(1.0));
+        assert_eq!(registry.percentile("tte_ms", 100.0), Some(100.0));
+        assert_eq!(registry.percentile("unknown", 50.0), None);
+
+        let stats = registry.get_histogram_stats("tte_ms").unwrap();
+        assert_eq!((stats.p50, stats.p95, stats.p99), (50.0, 95.0, 99.0));
+        assert_eq!(stats.sum, 5050.0);
+
+        let mut histogram = Histogram::new(&[100.0, 10.0, 50.0, 10.0, f64::INFINITY]);
+        for value in 1..=100 {
+            histogram.observe(value as f64);
+        }
+        // Bounds sorted and deduplicated; values equal to a bound fall in its bucket
+        assert_eq!(
+            histogram.cumulative_buckets(),
+            vec![(10.0, 10), (50.0, 50), (100.0, 100), (f64::INFINITY, 100)]
+        );
+        histogram.observe(1000.0);
+        histogram.observe(f64::NAN);
+        assert_eq!(histogram.cumulative_buckets().last(), Some(&(f64::INFINITY, 101)));
+        assert_eq!(histogram.count(), 101);
+    }
+
+    #[test]
+    fn test_prometheus_rendering_parses() {
+        let registry = MetricsRegistry::new();
+        registry.add_to_counter("buy_attempts_total", 3);
+        registry.increment_counter("rpc.errors-total");
+        registry.set_gauge("5xx_streak", 2);
+        registry.record_histogram("buy_latency_seconds", 0.25);
+        registry.record_histogram("buy_latency_seconds", 0.5);
+
+        let text = registry.render_prometheus();
+        let mut typed = Vec::new();
+        let mut samples = HashMap::new();
+        for line in text.lines() {
+            if let Some(declaration) = line.strip_prefix("# TYPE ") {
+                let (name, kind) = declaration.split_once(' ').unwrap();
+                assert!(["counter", "gauge", "histogram"].contains(&kind), "{}", line);
+                typed.push(name.to_string());
+                continue;
+            }
+            let (series, value) = line.rsplit_once(' ').unwrap();
+            let name = series.split('{').next().unwrap();
+            assert!(!name.starts_with(|c: char| c.is_ascii_digit()), "{}", line);
+            assert!(name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_' || c == ':'), "{}", line);
+            let family = name.trim_end_matches("_bucket").trim_end_matches("_sum").trim_end_matches("_count");
+            assert!(typed.iter().any(|t| t == name || t == family), "{} has no TYPE line", line);
+            samples.insert(series.to_string(), value.parse::<f64>().unwrap());
+        }
+
+        assert_eq!(samples["buy_attempts_total"], 3.0);
+        assert_eq!(samples["rpc_errors_total"], 1.0);
+        assert_eq!(samples["_5xx_streak"], 2.0);
+        assert_eq!(samples["buy_latency_seconds_count"], 2.0);
+        assert_eq!(samples["buy_latency_seconds_sum"], 0.75);
+        assert_eq!(samples["buy_latency_seconds_bucket{le=\"0.1\"}"], 0.0);
+        assert_eq!(samples["buy_latency_seconds_bucket{le=\"0.25\"}"], 1.0);
+        assert_eq!(samples["buy_latency_seconds_bucket{le=\"0.5\"}"], 2.0);
+        assert_eq!(samples["buy_latency_seconds_bucket{le=\"+Inf\"}"], 2.0);
+        assert!(text.contains("# TYPE buy_attempts_total counter\n"));
+    }
+
+    #[test]
+    fn test_prometheus_names_that_normalize_alike_are_kept_apart() {
+        let registry = MetricsRegistry::new();
+        registry.add_to_counter("rpc.errors", 1);
+        registry.add_to_counter("rpc_errors", 2);
+        registry.set_gauge("rpc-errors", 3);
+
+        let text = registry.render_prometheus();
+        assert!(text.contains("# TYPE rpc_errors counter\nrpc_errors 1\n"), "{}", text);
+        assert!(text.contains("# TYPE rpc_errors_2 counter\nrpc_errors_2 2\n"), "{}", text);
+        assert!(text.contains("# TYPE rpc_errors_3 gauge\nrpc_errors_3 3\n"), "{}", text);
+        assert_eq!(registry.render_prometheus(), text);
+    }
+
+    #[test]
+    fn test_funnel_conversion_rates() {
+        let funnel = CandidateFunnel::new();
+        for _ in 0..4 {
+            funnel.record(FunnelStage::Seen);
+        }
+        funnel.record(FunnelStage::SecurityPassed);
+        funnel.record(FunnelStage::SecurityPassed);
+
+        let snapshot = funnel.snapshot();
+        assert_eq!(snapshot.len(), FunnelStage::ALL.len());
+        assert_eq!(snapshot[0].count, 4);
+        assert_eq!(snapshot[1].conversion_from_previous, 0.5);
+        assert_eq!(snapshot[1].conversion_from_seen, 0.5);
+        assert_eq!(snapshot[2].count, 0);
+        assert_eq!(snapshot[3].conversion_from_previous, 0.0);
     }
 
     #[test]
@@ -254,6 +653,6 @@ mod tests {
             timer.finish();
         }
         let stats = metrics().get_histogram_stats("test_timer").unwrap();
-        assert!(stats.min >= 10); // Should be at least 10ms
+        assert!(stats.min >= 0.010); // Should be at least 10ms
     }
 }
\ No newline at end of file
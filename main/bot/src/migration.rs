@@ -0,0 +1,319 @@
+//! Detection of pump.fun → Raydium token migrations.
+//!
+//! A pump.fun token "graduates" once its bonding curve is complete; liquidity then
+//! moves to Raydium and sells must be routed there. The detector watches every held
+//! pump.fun token and, on migration, flips that position's program so its next
+//! sell targets Raydium, then checks that the new pool's LP is burned. Besides
+//! polling, a pump.fun log event describing a migration triggers an immediate check.
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use anyhow::{anyhow, Context, Result};
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_sdk::pubkey::Pubkey;
+use tokio::sync::{mpsc, Mutex};
+use tracing::{debug, info, warn};
+
+use crate::metrics::metrics;
+use crate::raydium;
+use crate::security::validator;
+use crate::types::{AppState, DexProgram, NotificationLevel, ProgramLogEvent, PUMP_FUN_PROGRAM_ID};
+
+/// Seed of the pump.fun bonding curve PDA (`["bonding-curve", mint]`).
+pub const BONDING_CURVE_SEED: &[u8] = b"bonding-curve";
+
+/// Offset of the `complete` flag in bonding curve account data:
+/// 8-byte discriminator followed by five u64 reserve/supply fields.
+pub const BONDING_CURVE_COMPLETE_OFFSET: usize = 8 + 5 * 8;
+
+/// Log lines emitted by pump.fun when liquidity is withdrawn for migration.
+const MIGRATION_LOG_MARKERS: [&str; 2] = ["Instruction: Migrate", "Instruction: Withdraw"];
+
+/// Address of the bonding curve account for a pump.fun mint.
+pub fn bonding_curve_pda(mint: &Pubkey) -> Pubkey {
+    let program: Pubkey = PUMP_FUN_PROGRAM_ID.parse().expect("invalid pump.fun program id");
+    Pubkey::find_program_address(&[BONDING_CURVE_SEED, mint.as_ref()], &program).0
+}
+
+/// Read the `complete` flag from raw bonding curve account data.
+pub fn parse_bonding_curve_complete(data: &[u8]) -> Option<bool> {
+    data.get(BONDING_CURVE_COMPLETE_OFFSET).map(|b| *b != 0)
+}
+
+/// Whether a pump.fun transaction's logs describe a migration.
+pub fn logs_indicate_migration(logs: &[String]) -> bool {
+    logs.iter()
+        .any(|line| MIGRATION_LOG_MARKERS.iter().any(|m| line.contains(m)))
+}
+
+/// Whether `event` is a pump.fun transaction whose logs describe a migration.
+fn is_migration_event(event: &ProgramLogEvent) -> bool {
+    DexProgram::from(event.program.as_str()) == DexProgram::PumpFun && logs_indicate_migration(&event.logs)
+}
+
+/// Switch the sell venue of the position in `mint` to Raydium if it is a held
+/// pump.fun token, focused or not.
+///
+/// Returns `true` when the sell program was changed.
+pub async fn apply_migration(app_state: &Mutex<AppState>, mint: &Pubkey) -> bool {
+    let mut st = app_state.lock().await;
+    let on_pump_fun = st
+        .position(mint)
+        .is_some_and(|p| DexProgram::from(p.token.program.as_str()) == DexProgram::PumpFun);
+    if !on_pump_fun || !st.set_position_program(mint, DexProgram::Raydium.canonical_name()) {
+        return false;
+    }
+
+    metrics().increment_counter("token_migrations_total");
+    info!(mint = %mint, "Token migrated from pump.fun; sell venue switched to Raydium");
+    true
+}
+
+/// Mints of the open positions still sold on pump.fun, focused or not.
+fn held_on_pump_fun(st: &AppState) -> Vec<Pubkey> {
+    st.open_positions()
+        .iter()
+        .filter(|p| DexProgram::from(p.token.program.as_str()) == DexProgram::PumpFun)
+        .map(|p| p.token.mint)
+        .collect()
+}
+
+/// Polls the bonding curves of the held pump.fun tokens and applies migrations.
+pub struct MigrationDetector {
+    rpc: Arc<RpcClient>,
+    app_state: Arc<Mutex<AppState>>,
+    poll_interval: Duration,
+    log_events: Option<mpsc::Receiver<ProgramLogEvent>>,
+}
+
+impl MigrationDetector {
+    pub fn new(rpc: Arc<RpcClient>, app_state: Arc<Mutex<AppState>>, poll_interval: Duration) -> Self {
+        Self { rpc, app_state, poll_interval, log_events: None }
+    }
+
+    /// Also check as soon as a pump.fun log event describes a migration, instead of
+    /// waiting for the next poll. Events that do not are drained and dropped.
+    pub fn with_log_events(mut self, events: mpsc::Receiver<ProgramLogEvent>) -> Self {
+        self.log_events = Some(events);
+        self
+    }
+
+    /// Whether the bonding curve for `mint` has completed.
+    pub async fn is_migrated(&self, mint: &Pubkey) -> Result<bool> {
+        let account = self
+            .rpc
+            .get_account(&bonding_curve_pda(mint))
+            .await
+            .context("fetch bonding curve account")?;
+        parse_bonding_curve_complete(&account.data)
+            .ok_or_else(|| anyhow!("bonding curve account data too short"))
+    }
+
+    /// Warn when the Raydium pool `mint` migrated to has its LP unburned, so its
+    /// liquidity can still be pulled.
+    async fn check_migrated_lp(&self, mint: &Pubkey) {
+        let pool = match raydium::find_sol_pool(&self.rpc, mint).await {
+            Ok(Some(pool)) => pool,
+            Ok(None) => {
+                debug!(mint = %mint, "No Raydium pool yet; LP not checked");
+                return;
+            }
+            Err(e) => {
+                warn!(mint = %mint, error = %e, "Raydium pool lookup failed; LP not checked");
+                return;
+            }
+        };
+        let report = validator().check_authorities_with_lp(mint, &self.rpc, Some(&pool.lp_mint)).await;
+        match report.lp_burned {
+            Some(false) => {
+                metrics().increment_counter("migrated_lp_not_burned_total");
+                warn!(mint = %mint, pool = %pool.address, "Migrated token's Raydium LP is not burned");
+                self.app_state
+                    .lock()
+                    .await
+                    .notify(NotificationLevel::Warn, format!("{} migrated to a Raydium pool whose LP is not burned", mint));
+            }
+            Some(true) => debug!(mint = %mint, pool = %pool.address, "Migrated token's Raydium LP is burned"),
+            None => debug!(mint = %mint, issues = ?report.issues, "Migrated token's Raydium LP not checked"),
+        }
+    }
+
+    /// Wait for the next poll, or for a migration log event when those are received.
+    async fn next_check(&mut self, ticker: &mut tokio::time::Interval) {
+        loop {
+            let event = match self.log_events.as_mut() {
+                Some(events) => tokio::select! {
+                    _ = ticker.tick() => return,
+                    event = events.recv() => event,
+                },
+                None => {
+                    ticker.tick().await;
+                    return;
+                }
+            };
+            match event {
+                Some(event) if is_migration_event(&event) => {
+                    debug!(signature = %event.signature, "Migration logged; checking the held tokens now");
+                    return;
+                }
+                Some(_) => {}
+                None => self.log_events = None,
+            }
+        }
+    }
+
+    /// Run until the task is aborted.
+    pub async fn run(mut self) {
+        let mut ticker = tokio::time::interval(self.poll_interval);
+        loop {
+            self.next_check(&mut ticker).await;
+
+            let held = held_on_pump_fun(&*self.app_state.lock().await);
+            for mint in held {
+                match self.is_migrated(&mint).await {
+                    Ok(true) => {
+                        if apply_migration(&self.app_state, &mint).await {
+                            self.check_migrated_lp(&mint).await;
+                        }
+                    }
+                    Ok(false) => debug!(mint = %mint, "Bonding curve not complete"),
+                    Err(e) => warn!(mint = %mint, error = %e, "Migration check failed"),
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::AccountsRpc;
+    use crate::types::{Mode, Position, PremintCandidate};
+
+    fn held_state(mint: Pubkey) -> Mutex<AppState> {
+        Mutex::new(AppState {
+            mode: Mode::PassiveToken(mint),
+            active_token: Some(PremintCandidate {
+                mint,
+                creator: Pubkey::new_unique(),
+                program: "pump.fun".to_string(),
+                slot: 0,
+                timestamp: 0,
+                instruction_summary: None,
+                is_jito_bundle: None,
+                deadline_ms: None,
+            }),
+            last_buy_price: None,
+            holdings_percent: 1.0,
+            quantum_suggestions: Default::default(),
+            notifications: Default::default(),
+            operations: Default::default(),
+            positions: Default::default(),
+            pnl: Default::default(),
+        })
+    }
+
+    #[tokio::test]
+    async fn migration_event_flips_sell_program_to_raydium() {
+        let mint = Pubkey::new_unique();
+        let state = held_state(mint);
+
+        let logs = vec![
+            format!("Program {} invoke [1]", PUMP_FUN_PROGRAM_ID),
+            "Program log: Instruction: Withdraw".to_string(),
+        ];
+        assert!(logs_indicate_migration(&logs));
+
+        let mut curve = vec![0u8; BONDING_CURVE_COMPLETE_OFFSET + 1];
+        assert_eq!(parse_bonding_curve_complete(&curve), Some(false));
+        curve[BONDING_CURVE_COMPLETE_OFFSET] = 1;
+        assert_eq!(parse_bonding_curve_complete(&curve), Some(true));
+
+        assert!(!apply_migration(&state, &Pubkey::new_unique()).await);
+        assert!(apply_migration(&state, &mint).await);
+        let program = state.lock().await.active_token.as_ref().unwrap().program.clone();
+        assert_eq!(DexProgram::from(program.as_str()), DexProgram::Raydium);
+
+        // Already migrated: nothing left to flip
+        assert!(!apply_migration(&state, &mint).await);
+    }
+
+    #[tokio::test]
+    async fn unfocused_position_migrates_without_touching_the_focused_one() {
+        let (older, newer) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let state = Mutex::new(AppState::sniffing());
+        for mint in [older, newer] {
+            let token = held_state(mint).lock().await.active_token.clone().unwrap();
+            state.lock().await.open_position(Position::new(token, Some(1e-6), 1.0));
+        }
+        assert_eq!(held_on_pump_fun(&*state.lock().await).len(), 2);
+
+        // The newer position is focused; the older one migrates
+        assert!(apply_migration(&state, &older).await);
+        let st = state.lock().await;
+        let program = |mint: &Pubkey| DexProgram::from(st.position(mint).unwrap().token.program.as_str());
+        assert_eq!(program(&older), DexProgram::Raydium);
+        assert_eq!(program(&newer), DexProgram::PumpFun);
+        assert!(matches!(st.mode, Mode::PassiveToken(mint) if mint == newer));
+        assert_eq!(st.active_token.as_ref().unwrap().program, "pump.fun");
+        assert_eq!(held_on_pump_fun(&st), vec![newer]);
+    }
+
+    #[tokio::test]
+    async fn unburned_lp_of_the_migrated_pool_is_flagged() {
+        use solana_sdk::program_pack::Pack;
+
+        let (mint, lp_mint) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let (_, mut accounts) = raydium::sol_pool_accounts(&mint, &lp_mint, 1_000_000, 1_000_000);
+        let mut lp = vec![0; spl_token::state::Mint::LEN];
+        spl_token::state::Mint { supply: 5_000_000_000, decimals: 9, is_initialized: true, ..Default::default() }
+            .pack_into_slice(&mut lp);
+        accounts.insert(lp_mint, (spl_token::id(), lp));
+        let rpc = RpcClient::new_sender(AccountsRpc { accounts, token_balance: 0 }, Default::default());
+        let detector = MigrationDetector::new(Arc::new(rpc), Arc::new(held_state(mint)), Duration::from_secs(3600));
+
+        let flagged_before = metrics().get_counter("migrated_lp_not_burned_total");
+        detector.check_migrated_lp(&mint).await;
+        assert!(metrics().get_counter("migrated_lp_not_burned_total") > flagged_before);
+        let st = detector.app_state.lock().await;
+        let notification = st.notifications.back().expect("warning");
+        assert_eq!(notification.level, NotificationLevel::Warn);
+        assert!(notification.message.contains("LP is not burned"));
+        drop(st);
+
+        // No pool found: nothing to flag
+        detector.check_migrated_lp(&Pubkey::new_unique()).await;
+        assert_eq!(detector.app_state.lock().await.notifications.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn migration_log_event_triggers_an_immediate_check() {
+        let (events, rx) = mpsc::channel(4);
+        let mut detector = MigrationDetector::new(
+            Arc::new(RpcClient::new("http://127.0.0.1:1".to_string())),
+            Arc::new(held_state(Pubkey::new_unique())),
+            Duration::from_secs(3600),
+        )
+        .with_log_events(rx);
+        let mut ticker = tokio::time::interval(Duration::from_secs(3600));
+        ticker.tick().await;
+
+        let event = |program: &str, instruction: &str| ProgramLogEvent {
+            slot: 0,
+            signature: "sig".to_string(),
+            program: program.to_string(),
+            logs: vec![format!("Program log: Instruction: {}", instruction)],
+            ts_ms: 0,
+        };
+        events.send(event(PUMP_FUN_PROGRAM_ID, "Buy")).await.unwrap();
+        events.send(event("raydium", "Withdraw")).await.unwrap();
+        let waited = tokio::time::timeout(Duration::from_millis(50), detector.next_check(&mut ticker)).await;
+        assert!(waited.is_err(), "unrelated log events must not trigger a check");
+
+        events.send(event(PUMP_FUN_PROGRAM_ID, "Withdraw")).await.unwrap();
+        tokio::time::timeout(Duration::from_secs(1), detector.next_check(&mut ticker))
+            .await
+            .expect("a pump.fun migration log should trigger a check");
+    }
+}
@@ -1,11 +1,26 @@
-use anyhow::{anyhow, Result};
-use solana_sdk::pubkey::Pubkey;
+use anyhow::{anyhow, Context, Result};
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_client::nonce_utils::nonblocking::{data_from_account, get_account_with_commitment};
+#[allow(deprecated)]
+use solana_sdk::{
+    commitment_config::CommitmentConfig,
+    hash::Hash,
+    nonce::State as NonceState,
+    pubkey::Pubkey,
+    system_program,
+    transaction::Transaction,
+};
+use solana_system_interface::instruction as system_instruction;
 
 use std::collections::{HashSet, VecDeque};
-use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
 use std::pin::Pin;
 use std::future::Future;
-use tokio::sync::{Mutex, Semaphore};
+use tokio::sync::Semaphore;
+use tracing::{info, warn};
+
+use crate::wallet::WalletManager;
 
 
 /// RAII lease for index slots that automatically releases on drop
@@ -47,6 +62,8 @@ pub trait SlotManager: Send + Sync + std::fmt::Debug {
     fn get_pubkey_for_index(&self, index: usize) -> Pubkey;
 }
 
+// Index bookkeeping uses std mutexes: they are never held across an await, which
+// keeps release synchronous (no spawned cleanup to wait for).
 #[derive(Debug)]
 struct NonceManagerInner {
     capacity: usize,
@@ -55,6 +72,23 @@ struct NonceManagerInner {
     allocated: Arc<Mutex<HashSet<usize>>>,
 }
 
+impl NonceManagerInner {
+    /// Return `idx` to the free list if it was allocated; false otherwise.
+    fn release(&self, idx: usize) -> bool {
+        // Same lock order as acquire: free, then allocated
+        let mut free = self.free.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        let mut allocated = self.allocated.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        if !allocated.remove(&idx) {
+            return false;
+        }
+        free.push_back(idx);
+        drop(allocated);
+        drop(free);
+        self.sem.add_permits(1);
+        true
+    }
+}
+
 /// Lightweight index slot manager:
 /// - Provides at most `capacity` parallel index slots
 /// - acquire_index() returns IndexLease that auto-releases on drop
@@ -66,6 +100,45 @@ pub struct IndexSlotManager {
     capacity: usize,
     sem: Arc<Semaphore>,
     inner: Arc<NonceManagerInner>,
+    /// Indices handed out / returned via the legacy API, for lease accounting
+    acquired_total: AtomicU64,
+    released_total: AtomicU64,
+    /// On-chain nonce account per index; empty in semaphore-only mode
+    durable: Mutex<Vec<DurableNonce>>,
+}
+
+/// A durable nonce account backing one slot.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DurableNonce {
+    pub account: Pubkey,
+    pub authority: Pubkey,
+    /// Last known nonce value; `None` once a transaction was built on it, since the
+    /// nonce advances when that transaction lands.
+    pub hash: Option<Hash>,
+}
+
+/// A slot from `acquire_slot`, released when dropped.
+pub struct NonceSlot<'a> {
+    manager: &'a IndexSlotManager,
+    account: Pubkey,
+    index: usize,
+}
+
+impl NonceSlot<'_> {
+    pub fn index(&self) -> usize {
+        self.index
+    }
+
+    /// The durable nonce account in durable mode, a placeholder key otherwise.
+    pub fn account(&self) -> Pubkey {
+        self.account
+    }
+}
+
+impl Drop for NonceSlot<'_> {
+    fn drop(&mut self) {
+        self.manager.release_nonce(self.index);
+    }
 }
 
 // Type alias for backward compatibility
@@ -84,16 +157,67 @@ impl IndexSlotManager {
         Self { 
             capacity,
             sem,
-            inner 
+            inner,
+            acquired_total: AtomicU64::new(0),
+            released_total: AtomicU64::new(0),
+            durable: Mutex::new(Vec::new()),
         }
     }
 
+    /// One slot per durable nonce account: transactions built on a slot use its
+    /// nonce instead of a recent blockhash, so they stay valid until the nonce advances.
+    pub fn with_durable_nonces(nonces: Vec<DurableNonce>) -> Self {
+        let manager = Self::new(nonces.len());
+        *manager.durable.lock().unwrap_or_else(|poisoned| poisoned.into_inner()) = nonces;
+        manager
+    }
+
+    pub fn is_durable(&self) -> bool {
+        !self.durable.lock().unwrap_or_else(|poisoned| poisoned.into_inner()).is_empty()
+    }
+
+    /// The durable nonce account behind slot `idx`, `None` in semaphore-only mode.
+    pub fn durable_nonce(&self, idx: usize) -> Option<DurableNonce> {
+        self.durable.lock().unwrap_or_else(|poisoned| poisoned.into_inner()).get(idx).cloned()
+    }
+
+    /// Record the current nonce value of slot `idx` (`None`: unknown, fetch before use).
+    pub fn set_nonce_hash(&self, idx: usize, hash: Option<Hash>) {
+        if let Some(nonce) = self.durable.lock().unwrap_or_else(|poisoned| poisoned.into_inner()).get_mut(idx) {
+            nonce.hash = hash;
+        }
+    }
+
+    /// Forget every slot's nonce value, so each is fetched again before its next use.
+    pub fn clear_nonce_hashes(&self) {
+        for nonce in self.durable.lock().unwrap_or_else(|poisoned| poisoned.into_inner()).iter_mut() {
+            nonce.hash = None;
+        }
+    }
+
+    /// Acquire a slot that is released when the returned guard is dropped.
+    pub async fn acquire_slot(&self) -> Result<NonceSlot<'_>> {
+        let (account, index) = self.acquire_nonce().await?;
+        Ok(NonceSlot { manager: self, account, index })
+    }
+
     /// Get number of available permits for monitoring
     pub fn available_permits(&self) -> usize {
         self.sem.available_permits()
     }
 
-    /// Legacy API - acquire nonce returns (dummy_pubkey, index)
+    /// Total (acquired, released) indices via `acquire_nonce`/`release_nonce`.
+    /// Equal counts mean no lease is outstanding.
+    pub fn lease_counts(&self) -> (u64, u64) {
+        (
+            self.acquired_total.load(Ordering::SeqCst),
+            self.released_total.load(Ordering::SeqCst),
+        )
+    }
+
+    /// Legacy API - acquire nonce returns (nonce_pubkey, index). The pubkey is the
+    /// slot's durable nonce account, or a dummy in semaphore-only mode; the nonce
+    /// value is in `durable_nonce(index)`.
     pub async fn acquire_nonce(&self) -> Result<(Pubkey, usize)> {
         // Acquire semaphore first
         let permit = self
@@ -104,8 +228,8 @@ impl IndexSlotManager {
             .map_err(|_| anyhow!("semaphore closed"))?;
 
         // Get next available index
-        let mut free_guard = self.inner.free.lock().await;
-        let mut allocated_guard = self.inner.allocated.lock().await;
+        let mut free_guard = self.inner.free.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        let mut allocated_guard = self.inner.allocated.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
         
         if let Some(idx) = free_guard.pop_front() {
             // Validate that index is in expected range
@@ -120,32 +244,26 @@ impl IndexSlotManager {
             
             // Release permit immediately since we're returning the index
             permit.forget();
+            self.acquired_total.fetch_add(1, Ordering::SeqCst);
             
-            // Generate a dummy pubkey for compatibility
-            let dummy_pubkey = Pubkey::new_unique();
-            
-            Ok((dummy_pubkey, idx))
+            let pubkey = self
+                .durable_nonce(idx)
+                .map_or_else(Pubkey::new_unique, |nonce| nonce.account);
+
+            Ok((pubkey, idx))
         } else {
             // This should not happen with proper semaphore usage
             Err(anyhow!("no free nonce index despite semaphore permit"))
         }
     }
 
-    /// Legacy API - release nonce by index
+    /// Legacy API - release nonce by index. Synchronous: the index and its permit
+    /// are available again when this returns. Unallocated indices are ignored.
     pub fn release_nonce(&self, idx: usize) {
-        // Remove the async spawn overhead by using blocking operations
-        // This assumes the calling context can handle potential blocking
-        if let Ok(mut guard) = self.inner.free.try_lock() {
-            guard.push_back(idx);
-            self.inner.sem.add_permits(1);
+        if self.inner.release(idx) {
+            self.released_total.fetch_add(1, Ordering::SeqCst);
         } else {
-            // Fallback to async spawn only if we can't get immediate lock
-            let free = self.inner.free.clone();
-            let sem = self.inner.sem.clone();
-            tokio::spawn(async move {
-                free.lock().await.push_back(idx);
-                sem.add_permits(1);
-            });
+            warn!(index = idx, "Ignoring release of a nonce index that is not allocated");
         }
     }
 }
@@ -161,8 +279,8 @@ impl SlotManager for NonceManagerInner {
                 .map_err(|_| anyhow!("semaphore closed"))?;
 
             // Get next available index
-            let mut free_guard = self.free.lock().await;
-            let mut allocated_guard = self.allocated.lock().await;
+            let mut free_guard = self.free.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+            let mut allocated_guard = self.allocated.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
             
             if let Some(idx) = free_guard.pop_front() {
                 // Validate that index is in expected range
@@ -188,12 +306,7 @@ impl SlotManager for NonceManagerInner {
     
     fn release_index(&self, index: usize) -> Pin<Box<dyn Future<Output = Result<()>> + Send + '_>> {
         Box::pin(async move {
-            let mut free_guard = self.free.lock().await;
-            let mut allocated_guard = self.allocated.lock().await;
-            
-            if allocated_guard.remove(&index) {
-                free_guard.push_back(index);
-                self.sem.add_permits(1);
+            if self.release(index) {
                 Ok(())
             } else {
                 Err(anyhow!("index {} was not allocated", index))
@@ -215,4 +328,55 @@ impl Clone for NonceManagerInner {
             allocated: self.allocated.clone(),
         }
     }
-}
\ No newline at end of file
+}
+
+/// Address of durable nonce account `index` owned by `authority`.
+pub fn durable_nonce_address(authority: &Pubkey, index: usize) -> Result<Pubkey> {
+    Pubkey::create_with_seed(authority, &format!("nonce-{}", index), &system_program::id())
+        .map_err(|e| anyhow!("cannot derive nonce account {}: {}", index, e))
+}
+
+/// Current value of the durable nonce in `account`.
+pub async fn fetch_nonce_hash(rpc: &RpcClient, account: &Pubkey) -> Result<Hash> {
+    let data = get_account_with_commitment(rpc, account, CommitmentConfig::confirmed())
+        .await
+        .map_err(|e| anyhow!("nonce account {}: {}", account, e))
+        .and_then(|acc| data_from_account(&acc).map_err(|e| anyhow!("nonce account {}: {}", account, e)))?;
+    Ok(data.blockhash())
+}
+
+/// Load `count` durable nonce accounts of `wallet`, creating the missing ones (the
+/// wallet pays rent and is the nonce authority). Addresses are derived from the
+/// wallet, so the same accounts are found again on the next start.
+pub async fn ensure_durable_nonces(rpc: &RpcClient, wallet: &WalletManager, count: usize) -> Result<Vec<DurableNonce>> {
+    let authority = wallet.pubkey();
+    let mut nonces = Vec::with_capacity(count);
+    for index in 0..count {
+        let account = durable_nonce_address(&authority, index)?;
+        let existing = rpc
+            .get_account_with_commitment(&account, CommitmentConfig::confirmed())
+            .await
+            .with_context(|| format!("fetching nonce account {}", account))?
+            .value;
+        if existing.is_none() {
+            let rent = rpc.get_minimum_balance_for_rent_exemption(NonceState::size()).await?;
+            let instructions = system_instruction::create_nonce_account_with_seed(
+                &authority,
+                &account,
+                &authority,
+                &format!("nonce-{}", index),
+                &authority,
+                rent,
+            );
+            let blockhash = rpc.get_latest_blockhash().await?;
+            let tx = Transaction::new_signed_with_payer(&instructions, Some(&authority), &[wallet.keypair()], blockhash);
+            rpc.send_and_confirm_transaction(&tx)
+                .await
+                .with_context(|| format!("creating nonce account {}", account))?;
+            info!(account = %account, index, "Created durable nonce account");
+        }
+        let hash = fetch_nonce_hash(rpc, &account).await?;
+        nonces.push(DurableNonce { account, authority, hash: Some(hash) });
+    }
+    Ok(nonces)
+}
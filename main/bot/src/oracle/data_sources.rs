@@ -9,6 +9,7 @@ use crate::oracle::types_old::{
     TokenData, Metadata, HolderData, LiquidityPool, VolumeData, CreatorHoldings,
     SocialActivity, PoolType, Attribute,
 };
+use crate::security::{untrusted_fetch_client, validator};
 use crate::types::{PremintCandidate, Pubkey};
 use anyhow::{anyhow, Context, Result};
 use chrono::Timelike; // For .hour() method
@@ -37,6 +38,8 @@ use tracing::{debug, warn, error, instrument};
 pub struct OracleDataSources {
     rpc_clients: Vec<Arc<RpcClient>>,
     http_client: Client,
+    /// Client for token metadata URIs; see `untrusted_fetch_client`
+    metadata_client: Client,
     config: OracleConfig,
 }
 
@@ -47,9 +50,13 @@ impl OracleDataSources {
         http_client: Client,
         config: OracleConfig,
     ) -> Self {
+        // Fails only where `Client::new` would panic too: no TLS backend
+        let metadata_client = untrusted_fetch_client(&config.metadata_allowed_hosts)
+            .expect("metadata HTTP client cannot be built");
         Self {
             rpc_clients,
             http_client,
+            metadata_client,
             config,
         }
     }
@@ -165,13 +172,19 @@ impl OracleDataSources {
         Ok(format!("https://example.com/metadata/{}.json", mint_address))
     }
 
-    /// Fetch metadata from URI.
+    /// Fetch metadata from URI, reading at most `metadata_max_bytes` of body.
     #[instrument(skip(self), fields(uri = %uri))]
     async fn fetch_metadata_from_uri(&self, uri: &str) -> Result<Metadata> {
-        let response = self
-            .http_client
-            .get(uri)
-            .timeout(Duration::from_secs(10))
+        // Token URIs are attacker-controlled; never let them reach internal services
+        let url = validator()
+            .validate_fetch_url(uri, &self.config.metadata_allowed_hosts)
+            .map_err(|e| anyhow!("Refusing metadata fetch: {}", e))?;
+
+        let max_bytes = self.config.metadata_max_bytes;
+        let mut response = self
+            .metadata_client
+            .get(url)
+            .timeout(Duration::from_millis(self.config.metadata_timeout_ms))
             .send()
             .await
             .context("Failed to fetch metadata")?;
@@ -180,9 +193,24 @@ impl OracleDataSources {
             return Err(anyhow!("Failed to fetch metadata: {}", response.status()));
         }
 
-        let metadata: Metadata = response
-            .json()
-            .await
+        // Refuse up front when the server announces an oversized body
+        if let Some(len) = response.content_length() {
+            if len > max_bytes as u64 {
+                return Err(anyhow!("Metadata body of {} bytes exceeds limit of {} bytes", len, max_bytes));
+            }
+        }
+
+        // Stream the body so an unannounced huge file is cut off at the limit
+        let mut body = Vec::new();
+        while let Some(chunk) = response.chunk().await.context("Failed to read metadata")? {
+            if body.len() + chunk.len() > max_bytes {
+                warn!("Metadata body exceeds limit of {} bytes; aborting fetch", max_bytes);
+                return Err(anyhow!("Metadata body exceeds limit of {} bytes", max_bytes));
+            }
+            body.extend_from_slice(&chunk);
+        }
+
+        let metadata: Metadata = serde_json::from_slice(&body)
             .context("Failed to parse metadata")?;
 
         debug!("Fetched metadata: {}", metadata.name);
@@ -524,6 +552,7 @@ mod tests {
             timestamp: 1640995200,
             instruction_summary: None,
             is_jito_bundle: Some(true),
+            deadline_ms: None,
         }
     }
 
@@ -569,4 +598,39 @@ mod tests {
         // Just verify construction works
         assert_eq!(data_sources.rpc_clients.len(), 0);
     }
+
+    /// Serve one HTTP response with `body` and no Content-Length, forcing a streamed read.
+    async fn serve_once(body: Vec<u8>) -> String {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            let (mut socket, _) = listener.accept().await.unwrap();
+            let mut request = [0u8; 1024];
+            let _ = socket.read(&mut request).await;
+            let head = "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nConnection: close\r\n\r\n";
+            let _ = socket.write_all(head.as_bytes()).await;
+            let _ = socket.write_all(&body).await;
+        });
+        format!("http://{}/metadata.json", addr)
+    }
+
+    #[tokio::test]
+    async fn test_oversized_metadata_is_rejected() {
+        let config = OracleConfig {
+            metadata_max_bytes: 1024,
+            metadata_allowed_hosts: vec!["127.0.0.1".to_string()],
+            ..create_test_config()
+        };
+        let data_sources = OracleDataSources::new(vec![], Client::new(), config);
+
+        let mut body = br#"{"name":""#.to_vec();
+        body.extend(std::iter::repeat(b'a').take(64 * 1024));
+        body.extend_from_slice(br#""}"#);
+        let uri = serve_once(body).await;
+
+        let err = data_sources.fetch_metadata_from_uri(&uri).await.unwrap_err();
+        assert!(err.to_string().contains("exceeds limit"), "unexpected error: {err}");
+    }
 }
\ No newline at end of file
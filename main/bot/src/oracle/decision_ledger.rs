@@ -232,6 +232,7 @@ impl DecisionLedger {
                     timestamp: row.timestamp_decision_made as u64,
                     instruction_summary: None,
                     is_jito_bundle: None,
+                    deadline_ms: None,
                 },
                 mint: row.mint.clone(),
                 predicted_score: row.score as u8,
@@ -339,6 +339,7 @@ mod tests {
             timestamp: 1640995200, // 2022-01-01
             instruction_summary: None,
             is_jito_bundle: Some(true),
+            deadline_ms: None,
         }
     }
 
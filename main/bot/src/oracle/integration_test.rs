@@ -27,6 +27,7 @@ async fn test_decision_ledger_basic_functionality() {
         timestamp: chrono::Utc::now().timestamp_millis() as u64,
         instruction_summary: Some("Test instruction".to_string()),
         is_jito_bundle: Some(true),
+        deadline_ms: None,
     };
 
     let scored_candidate = ScoredCandidate {
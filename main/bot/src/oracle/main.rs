@@ -191,6 +191,7 @@ async fn demo_decision_recording(
             timestamp: chrono::Utc::now().timestamp_millis() as u64,
             instruction_summary: Some("Token creation".to_string()),
             is_jito_bundle: Some(true),
+            deadline_ms: None,
         };
 
         let mut feature_scores = HashMap::new();
@@ -178,6 +178,7 @@ impl PerformanceMonitor {
                     timestamp: row.timestamp_decision_made as u64,
                     instruction_summary: None,
                     is_jito_bundle: None,
+                    deadline_ms: None,
                 },
                 mint: row.mint.clone(),
                 predicted_score: row.score as u8,
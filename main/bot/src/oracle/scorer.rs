@@ -433,6 +433,7 @@ mod tests {
             timestamp: 1640995200,
             instruction_summary: None,
             is_jito_bundle: Some(true),
+            deadline_ms: None,
         }
     }
 
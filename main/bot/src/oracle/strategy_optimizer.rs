@@ -196,6 +196,7 @@ impl StrategyOptimizer {
                     timestamp: row.timestamp_decision_made as u64,
                     instruction_summary: None,
                     is_jito_bundle: None,
+                    deadline_ms: None,
                 },
                 mint: row.mint.clone(),
                 predicted_score: row.score as u8,
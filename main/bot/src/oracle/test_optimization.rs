@@ -131,6 +131,7 @@ async fn create_poor_performance_data(sender: mpsc::Sender<TransactionRecord>) -
             timestamp: chrono::Utc::now().timestamp_millis() as u64,
             instruction_summary: Some("Losing token".to_string()),
             is_jito_bundle: Some(false),
+            deadline_ms: None,
         };
 
         let scored_candidate = ScoredCandidate {
@@ -357,6 +357,7 @@ mod tests {
                 timestamp: 1640995200,
                 instruction_summary: None,
                 is_jito_bundle: Some(true),
+                deadline_ms: None,
             },
             mint: Pubkey::new_unique(),
             predicted_score: score,
@@ -0,0 +1,299 @@
+//! Durable key/value storage for bot state.
+//!
+//! State snapshots and recorders store small serialized blobs under a fixed key
+//! (e.g. `"state"`) through the `Persistence` trait, so the storage can be swapped
+//! per deployment: a directory of files by default, sled or redis (behind the
+//! `sled`/`redis` features) when several instances share a host or a server.
+
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+use anyhow::{bail, Context, Result};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+
+/// Key/value store for persisted state.
+pub trait Persistence: Send + Sync + std::fmt::Debug {
+    /// Store `value` under `key`, replacing any previous value.
+    fn save(&self, key: &str, value: &[u8]) -> Result<()>;
+    /// Value stored under `key`, or `None` if there is none.
+    fn load(&self, key: &str) -> Result<Option<Vec<u8>>>;
+    /// Delete `key`; deleting a missing key is not an error.
+    fn remove(&self, key: &str) -> Result<()>;
+}
+
+/// Store `value` as JSON under `key`.
+pub fn save_json<T: Serialize>(store: &dyn Persistence, key: &str, value: &T) -> Result<()> {
+    let bytes = serde_json::to_vec(value).with_context(|| format!("encode {}", key))?;
+    store.save(key, &bytes)
+}
+
+/// Load a JSON value stored under `key`.
+pub fn load_json<T: DeserializeOwned>(store: &dyn Persistence, key: &str) -> Result<Option<T>> {
+    match store.load(key)? {
+        Some(bytes) => Ok(Some(serde_json::from_slice(&bytes).with_context(|| format!("decode {}", key))?)),
+        None => Ok(None),
+    }
+}
+
+/// Which store to use, e.g. `persistence = { type = "file", dir = "state" }`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum PersistenceBackend {
+    /// One file per key in `dir`
+    File { dir: String },
+    /// Process memory only; nothing survives a restart
+    Memory,
+    /// sled database at `path` (requires the `sled` feature)
+    Sled { path: String },
+    /// Redis server at `url`, keys prefixed with `prefix` (requires the `redis` feature)
+    Redis {
+        url: String,
+        #[serde(default = "default_redis_prefix")]
+        prefix: String,
+    },
+}
+
+fn default_redis_prefix() -> String {
+    "sniffer_bot:".to_string()
+}
+
+impl Default for PersistenceBackend {
+    fn default() -> Self {
+        PersistenceBackend::File { dir: "state".to_string() }
+    }
+}
+
+impl PersistenceBackend {
+    /// Open the configured store.
+    pub fn open(&self) -> Result<Arc<dyn Persistence>> {
+        match self {
+            PersistenceBackend::File { dir } => Ok(Arc::new(FilePersistence::new(dir)?)),
+            PersistenceBackend::Memory => Ok(Arc::new(MemoryPersistence::default())),
+            #[cfg(feature = "sled")]
+            PersistenceBackend::Sled { path } => Ok(Arc::new(SledPersistence::open(path)?)),
+            #[cfg(not(feature = "sled"))]
+            PersistenceBackend::Sled { .. } => bail!("sled persistence requires the `sled` feature"),
+            #[cfg(feature = "redis")]
+            PersistenceBackend::Redis { url, prefix } => Ok(Arc::new(RedisPersistence::open(url, prefix)?)),
+            #[cfg(not(feature = "redis"))]
+            PersistenceBackend::Redis { .. } => bail!("redis persistence requires the `redis` feature"),
+        }
+    }
+}
+
+/// Keys become file names, so they are restricted to a safe character set.
+fn check_key(key: &str) -> Result<()> {
+    let valid = !key.is_empty()
+        && !key.starts_with('.')
+        && key.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'));
+    if !valid {
+        bail!("invalid persistence key {:?}", key);
+    }
+    Ok(())
+}
+
+/// Files in a directory, written atomically (temp file + rename).
+#[derive(Debug)]
+pub struct FilePersistence {
+    dir: PathBuf,
+}
+
+impl FilePersistence {
+    /// Use `dir`, creating it if needed.
+    pub fn new<P: AsRef<Path>>(dir: P) -> Result<Self> {
+        let dir = dir.as_ref().to_path_buf();
+        fs::create_dir_all(&dir).with_context(|| format!("create state dir {}", dir.display()))?;
+        Ok(Self { dir })
+    }
+
+    fn path(&self, key: &str) -> Result<PathBuf> {
+        check_key(key)?;
+        Ok(self.dir.join(format!("{}.dat", key)))
+    }
+}
+
+impl Persistence for FilePersistence {
+    fn save(&self, key: &str, value: &[u8]) -> Result<()> {
+        let path = self.path(key)?;
+        let tmp = path.with_extension("dat.tmp");
+        fs::write(&tmp, value).with_context(|| format!("write {}", tmp.display()))?;
+        fs::rename(&tmp, &path).with_context(|| format!("replace {}", path.display()))
+    }
+
+    fn load(&self, key: &str) -> Result<Option<Vec<u8>>> {
+        let path = self.path(key)?;
+        match fs::read(&path) {
+            Ok(bytes) => Ok(Some(bytes)),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e).with_context(|| format!("read {}", path.display())),
+        }
+    }
+
+    fn remove(&self, key: &str) -> Result<()> {
+        let path = self.path(key)?;
+        match fs::remove_file(&path) {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => {
+                Err(e).with_context(|| format!("remove {}", path.display()))
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+/// In-memory store for tests and throwaway runs.
+#[derive(Debug, Default)]
+pub struct MemoryPersistence {
+    entries: Mutex<HashMap<String, Vec<u8>>>,
+}
+
+impl Persistence for MemoryPersistence {
+    fn save(&self, key: &str, value: &[u8]) -> Result<()> {
+        let mut entries = self.entries.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        entries.insert(key.to_string(), value.to_vec());
+        Ok(())
+    }
+
+    fn load(&self, key: &str) -> Result<Option<Vec<u8>>> {
+        let entries = self.entries.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        Ok(entries.get(key).cloned())
+    }
+
+    fn remove(&self, key: &str) -> Result<()> {
+        self.entries.lock().unwrap_or_else(|poisoned| poisoned.into_inner()).remove(key);
+        Ok(())
+    }
+}
+
+/// Embedded sled database.
+#[cfg(feature = "sled")]
+#[derive(Debug)]
+pub struct SledPersistence {
+    db: sled::Db,
+}
+
+#[cfg(feature = "sled")]
+impl SledPersistence {
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let path = path.as_ref();
+        let db = sled::open(path).with_context(|| format!("open sled db {}", path.display()))?;
+        Ok(Self { db })
+    }
+}
+
+#[cfg(feature = "sled")]
+impl Persistence for SledPersistence {
+    fn save(&self, key: &str, value: &[u8]) -> Result<()> {
+        self.db.insert(key, value).context("sled insert")?;
+        self.db.flush().context("sled flush")?;
+        Ok(())
+    }
+
+    fn load(&self, key: &str) -> Result<Option<Vec<u8>>> {
+        Ok(self.db.get(key).context("sled get")?.map(|v| v.to_vec()))
+    }
+
+    fn remove(&self, key: &str) -> Result<()> {
+        self.db.remove(key).context("sled remove")?;
+        Ok(())
+    }
+}
+
+/// Redis server shared between instances; `prefix` keeps their keys apart.
+#[cfg(feature = "redis")]
+pub struct RedisPersistence {
+    connection: Mutex<redis::Connection>,
+    prefix: String,
+}
+
+#[cfg(feature = "redis")]
+impl std::fmt::Debug for RedisPersistence {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("RedisPersistence").field("prefix", &self.prefix).finish()
+    }
+}
+
+#[cfg(feature = "redis")]
+impl RedisPersistence {
+    pub fn open(url: &str, prefix: &str) -> Result<Self> {
+        let client = redis::Client::open(url).context("parse redis url")?;
+        let connection = client.get_connection().context("connect to redis")?;
+        Ok(Self { connection: Mutex::new(connection), prefix: prefix.to_string() })
+    }
+
+    fn with_connection<T>(&self, f: impl FnOnce(&mut redis::Connection) -> redis::RedisResult<T>) -> Result<T> {
+        let mut connection = self.connection.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        f(&mut connection).context("redis command")
+    }
+}
+
+#[cfg(feature = "redis")]
+impl Persistence for RedisPersistence {
+    fn save(&self, key: &str, value: &[u8]) -> Result<()> {
+        use redis::Commands;
+        let key = format!("{}{}", self.prefix, key);
+        self.with_connection(|c| c.set(key, value))
+    }
+
+    fn load(&self, key: &str) -> Result<Option<Vec<u8>>> {
+        use redis::Commands;
+        let key = format!("{}{}", self.prefix, key);
+        self.with_connection(|c| c.get(key))
+    }
+
+    fn remove(&self, key: &str) -> Result<()> {
+        use redis::Commands;
+        let key = format!("{}{}", self.prefix, key);
+        self.with_connection(|c| c.del(key))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct Snapshot {
+        holdings_percent: f64,
+        mint: Option<String>,
+    }
+
+    fn round_trip(store: &dyn Persistence) {
+        assert_eq!(load_json::<Snapshot>(store, "state").unwrap(), None);
+
+        let snapshot = Snapshot { holdings_percent: 0.5, mint: Some("mint".to_string()) };
+        save_json(store, "state", &snapshot).unwrap();
+        assert_eq!(load_json::<Snapshot>(store, "state").unwrap(), Some(snapshot));
+
+        // Saving again replaces the value
+        let sold = Snapshot { holdings_percent: 0.0, mint: None };
+        save_json(store, "state", &sold).unwrap();
+        assert_eq!(load_json::<Snapshot>(store, "state").unwrap(), Some(sold));
+
+        store.remove("state").unwrap();
+        store.remove("state").unwrap();
+        assert_eq!(store.load("state").unwrap(), None);
+    }
+
+    #[test]
+    fn memory_store_round_trips() {
+        round_trip(&MemoryPersistence::default());
+    }
+
+    #[test]
+    fn file_store_round_trips_and_survives_reopen() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = PersistenceBackend::File { dir: dir.path().display().to_string() }.open().unwrap();
+        round_trip(store.as_ref());
+
+        store.save("ledger", b"entry").unwrap();
+        let reopened = FilePersistence::new(dir.path()).unwrap();
+        assert_eq!(reopened.load("ledger").unwrap(), Some(b"entry".to_vec()));
+
+        // Keys cannot escape the state directory
+        assert!(reopened.save("../escape", b"x").is_err());
+        assert!(reopened.load("").is_err());
+    }
+}
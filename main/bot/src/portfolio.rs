@@ -0,0 +1,155 @@
+//! Unrealized value of held positions.
+//!
+//! Token balances come from position reconciliation and prices (lamports per raw
+//! token unit) from the transaction builder's bonding-curve quotes. Every change
+//! republishes the total as the `portfolio_value_lamports` gauge.
+
+use std::collections::HashMap;
+use std::sync::{Arc, OnceLock, RwLock};
+
+use serde::Serialize;
+use solana_sdk::pubkey::Pubkey;
+
+use crate::metrics::metrics;
+
+/// One held position and its current value.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct HeldPosition {
+    pub mint: String,
+    pub token_balance: u64,
+    /// Last known price in lamports per raw token unit, if quoted yet
+    pub price_lamports: Option<f64>,
+    pub value_lamports: u64,
+}
+
+#[derive(Debug, Default, Clone, Copy)]
+struct PositionState {
+    token_balance: u64,
+    price_lamports: Option<f64>,
+}
+
+impl PositionState {
+    fn value_lamports(&self) -> u64 {
+        self.price_lamports
+            .map(|price| (self.token_balance as f64 * price).max(0.0).round() as u64)
+            .unwrap_or(0)
+    }
+}
+
+/// Held positions keyed by mint.
+#[derive(Debug, Default)]
+pub struct Portfolio {
+    positions: RwLock<HashMap<Pubkey, PositionState>>,
+}
+
+impl Portfolio {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the token balance held for `mint`; a zero balance drops the position.
+    pub fn set_balance(&self, mint: Pubkey, token_balance: u64) {
+        {
+            let mut positions = self.positions.write().unwrap_or_else(|poisoned| poisoned.into_inner());
+            if token_balance == 0 {
+                positions.remove(&mint);
+            } else {
+                positions.entry(mint).or_default().token_balance = token_balance;
+            }
+        }
+        self.publish();
+    }
+
+    /// Record the current price for `mint`; ignored for mints not held.
+    pub fn update_price(&self, mint: &Pubkey, price_lamports: f64) {
+        {
+            let mut positions = self.positions.write().unwrap_or_else(|poisoned| poisoned.into_inner());
+            match positions.get_mut(mint) {
+                Some(position) if price_lamports.is_finite() => position.price_lamports = Some(price_lamports),
+                _ => return,
+            }
+        }
+        self.publish();
+    }
+
+    /// Drop every position whose mint is not in `held`.
+    pub fn retain(&self, held: &[Pubkey]) {
+        self.positions
+            .write()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .retain(|mint, _| held.contains(mint));
+        self.publish();
+    }
+
+    /// Drop every position.
+    pub fn clear(&self) {
+        self.positions.write().unwrap_or_else(|poisoned| poisoned.into_inner()).clear();
+        self.publish();
+    }
+
+    /// Sum of unrealized position values (positions without a price count as 0).
+    pub fn total_value_lamports(&self) -> u64 {
+        self.positions
+            .read()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .values()
+            .map(PositionState::value_lamports)
+            .sum()
+    }
+
+    /// Held positions, sorted by mint.
+    pub fn positions(&self) -> Vec<HeldPosition> {
+        let positions = self.positions.read().unwrap_or_else(|poisoned| poisoned.into_inner());
+        let mut held: Vec<_> = positions
+            .iter()
+            .map(|(mint, state)| HeldPosition {
+                mint: mint.to_string(),
+                token_balance: state.token_balance,
+                price_lamports: state.price_lamports,
+                value_lamports: state.value_lamports(),
+            })
+            .collect();
+        held.sort_by(|a, b| a.mint.cmp(&b.mint));
+        held
+    }
+
+    fn publish(&self) {
+        metrics().set_gauge("portfolio_value_lamports", self.total_value_lamports());
+    }
+}
+
+/// Global portfolio instance
+static GLOBAL_PORTFOLIO: OnceLock<Arc<Portfolio>> = OnceLock::new();
+
+/// Get global portfolio
+pub fn portfolio() -> Arc<Portfolio> {
+    GLOBAL_PORTFOLIO.get_or_init(|| Arc::new(Portfolio::new())).clone()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn value_sums_positions_at_current_prices() {
+        let portfolio = Portfolio::new();
+        let a = Pubkey::new_unique();
+        let b = Pubkey::new_unique();
+
+        portfolio.set_balance(a, 1_000_000);
+        portfolio.set_balance(b, 2_000_000);
+        // Not priced yet
+        assert_eq!(portfolio.total_value_lamports(), 0);
+
+        portfolio.update_price(&a, 0.03);
+        portfolio.update_price(&b, 0.5);
+        assert_eq!(portfolio.total_value_lamports(), 30_000 + 1_000_000);
+
+        // Prices for mints not held are ignored; selling out drops the position
+        portfolio.update_price(&Pubkey::new_unique(), 10.0);
+        portfolio.set_balance(b, 0);
+        assert_eq!(portfolio.total_value_lamports(), 30_000);
+        assert_eq!(portfolio.positions().len(), 1);
+        assert_eq!(portfolio.positions()[0].value_lamports, 30_000);
+    }
+}
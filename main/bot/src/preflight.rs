@@ -0,0 +1,178 @@
+//! `--preflight`: a go/no-go check of the config-to-transaction pipeline.
+//!
+//! Loads the config, the wallet and the transaction builder the way startup does,
+//! then builds and signs a buy for one mint without broadcasting it. Any step that
+//! would stop the bot from buying fails here with the exact reason instead.
+
+use std::sync::Arc;
+
+use solana_sdk::{packet::PACKET_DATA_SIZE, pubkey::Pubkey, signature::Signature};
+use thiserror::Error;
+
+use crate::buy_engine::buy_transaction_config;
+use crate::config::Config;
+use crate::nonce_manager::NonceManager;
+use crate::tx_builder::{transaction_size, TransactionBuilder, TransactionBuilderError};
+use crate::types::PremintCandidate;
+use crate::wallet::WalletManager;
+
+#[derive(Debug, Error)]
+pub enum PreflightError {
+    #[error("config: {0}")]
+    Config(String),
+    #[error("wallet: {0}")]
+    Wallet(String),
+    #[error("transaction builder: {0}")]
+    Builder(TransactionBuilderError),
+    #[error("buy transaction: {0}")]
+    Build(TransactionBuilderError),
+    #[error("buy transaction: {0}")]
+    Invalid(String),
+}
+
+/// What a successful preflight built.
+#[derive(Debug, Clone)]
+pub struct PreflightReport {
+    pub wallet: Pubkey,
+    pub mint: Pubkey,
+    pub program: String,
+    pub signature: Signature,
+    pub size_bytes: usize,
+}
+
+impl std::fmt::Display for PreflightReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "signed {} buy of {} from {}: {} bytes, signature {}",
+            self.program, self.mint, self.wallet, self.size_bytes, self.signature
+        )
+    }
+}
+
+/// Run the whole pipeline for `cfg`: validate it, load the wallet, create the
+/// builder and build a signed buy of `mint`.
+pub async fn run_preflight(cfg: &Config, mint: Pubkey) -> Result<PreflightReport, PreflightError> {
+    cfg.validate().map_err(PreflightError::Config)?;
+    let keypair_path = cfg
+        .keypair_path
+        .as_deref()
+        .ok_or_else(|| PreflightError::Wallet("keypair_path is not set".to_string()))?;
+    let wallet = WalletManager::from_file(keypair_path).map_err(|e| PreflightError::Wallet(e.to_string()))?;
+    let builder = TransactionBuilder::new(
+        Arc::new(wallet),
+        cfg.rpc_endpoints.clone(),
+        Arc::new(NonceManager::new(cfg.nonce_count)),
+        &buy_transaction_config(cfg, false),
+    )
+    .await
+    .map_err(PreflightError::Builder)?;
+    preflight_build(&builder, cfg, mint).await
+}
+
+/// Build and sign, but never send, a pump.fun buy of `mint` with `builder`, then
+/// check the result would be accepted for broadcast.
+pub async fn preflight_build(
+    builder: &TransactionBuilder,
+    cfg: &Config,
+    mint: Pubkey,
+) -> Result<PreflightReport, PreflightError> {
+    let candidate = PremintCandidate {
+        mint,
+        creator: Pubkey::default(),
+        program: "pump.fun".to_string(),
+        slot: 0,
+        timestamp: 0,
+        instruction_summary: Some("preflight".to_string()),
+        is_jito_bundle: None,
+        deadline_ms: None,
+    };
+    let config = buy_transaction_config(cfg, false);
+    let tx = builder
+        .build_buy_transaction(&candidate, &config, true)
+        .await
+        .map_err(PreflightError::Build)?;
+
+    if !tx.verify_with_results().iter().all(|ok| *ok) {
+        return Err(PreflightError::Invalid("signature does not verify".to_string()));
+    }
+    let size_bytes = transaction_size(&tx.message);
+    if size_bytes > PACKET_DATA_SIZE {
+        return Err(PreflightError::Invalid(format!(
+            "{} bytes exceeds the {} byte packet limit",
+            size_bytes, PACKET_DATA_SIZE
+        )));
+    }
+
+    Ok(PreflightReport {
+        wallet: tx.message.static_account_keys()[0],
+        mint,
+        program: candidate.program,
+        signature: tx.signatures[0],
+        size_bytes,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::BTreeMap;
+
+    use solana_sdk::hash::Hash;
+    use tempfile::NamedTempFile;
+
+    use crate::config::DexConfig;
+
+    async fn mock_builder(cfg: &Config) -> TransactionBuilder {
+        let builder = TransactionBuilder::new(
+            Arc::new(WalletManager::new_random()),
+            cfg.rpc_endpoints.clone(),
+            Arc::new(NonceManager::new(cfg.nonce_count)),
+            &buy_transaction_config(cfg, false),
+        )
+        .await
+        .unwrap();
+        // No RPC in tests: the blockhash is the only network input of a pump.fun build
+        builder.inject_blockhash_for_tests(Hash::new_unique()).await;
+        builder
+    }
+
+    #[tokio::test]
+    async fn preflight_builds_a_signed_buy_with_a_valid_setup() {
+        let cfg = Config::default();
+        let builder = mock_builder(&cfg).await;
+        let mint = Pubkey::new_unique();
+
+        let report = preflight_build(&builder, &cfg, mint).await.unwrap();
+        assert_eq!(report.mint, mint);
+        assert_ne!(report.signature, Signature::default());
+        assert!(report.size_bytes <= PACKET_DATA_SIZE);
+    }
+
+    #[tokio::test]
+    async fn preflight_reports_the_failing_step_when_misconfigured() {
+        let mint = Pubkey::new_unique();
+
+        let invalid = Config { nonce_count: 0, ..Config::default() };
+        assert!(matches!(run_preflight(&invalid, mint).await, Err(PreflightError::Config(_))));
+
+        let no_wallet = Config { keypair_path: None, ..Config::default() };
+        assert!(matches!(run_preflight(&no_wallet, mint).await, Err(PreflightError::Wallet(_))));
+
+        let bad_keypair = NamedTempFile::new().unwrap();
+        std::fs::write(bad_keypair.path(), "not a keypair").unwrap();
+        let bad_wallet = Config {
+            keypair_path: Some(bad_keypair.path().display().to_string()),
+            ..Config::default()
+        };
+        assert!(matches!(run_preflight(&bad_wallet, mint).await, Err(PreflightError::Wallet(_))));
+
+        let pump_disabled = Config {
+            dexes: BTreeMap::from([("pump.fun".to_string(), DexConfig { enabled: false, ..DexConfig::default() })]),
+            ..Config::default()
+        };
+        let builder = mock_builder(&pump_disabled).await;
+        let err = preflight_build(&builder, &pump_disabled, mint).await.unwrap_err();
+        assert!(matches!(err, PreflightError::Build(TransactionBuilderError::DexDisabled(_))), "{}", err);
+    }
+}
@@ -39,6 +39,8 @@ pub struct ScoredCandidate {
     pub reason: String,
     pub timestamp: u64,
     pub calculation_time: u128,
+    /// The candidate that was scored, for consumers that buy it
+    pub candidate: PremintCandidate,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -54,6 +56,23 @@ pub struct OracleConfig {
     pub max_parallel_requests: usize,
     pub rate_limit_requests_per_second: u32,
     pub notify_threshold: u8, // GUI notification threshold (default 75)
+    /// Largest metadata document read from a token's URI; bigger bodies are rejected.
+    #[serde(default = "default_metadata_max_bytes")]
+    pub metadata_max_bytes: usize,
+    /// Timeout for a whole metadata fetch, body included.
+    #[serde(default = "default_metadata_timeout_ms")]
+    pub metadata_timeout_ms: u64,
+    /// Hosts exempt from the internal-address check on metadata URLs (local dev only).
+    #[serde(default)]
+    pub metadata_allowed_hosts: Vec<String>,
+}
+
+fn default_metadata_max_bytes() -> usize {
+    64 * 1024
+}
+
+fn default_metadata_timeout_ms() -> u64 {
+    3_000
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -391,6 +410,7 @@ impl OracleScorer {
             reason,
             timestamp: candidate.timestamp,
             calculation_time: 0,
+            candidate: candidate.clone(),
         })
     }
 }
@@ -440,6 +460,9 @@ impl Default for OracleConfig {
             max_parallel_requests: 10,
             rate_limit_requests_per_second: 20,
             notify_threshold: 75,
+            metadata_max_bytes: default_metadata_max_bytes(),
+            metadata_timeout_ms: default_metadata_timeout_ms(),
+            metadata_allowed_hosts: Vec::new(),
         }
     }
 }
\ No newline at end of file
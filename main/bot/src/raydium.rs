@@ -0,0 +1,375 @@
+//! Native Raydium AMM v4 trades, where pump.fun tokens trade once migrated.
+//!
+//! Finds the SOL pool of a token, quotes trades from the pool's vault balances and
+//! builds `swap_base_in_v2` instructions with the token accounts and SOL wrapping
+//! around them, so migrated positions can be bought and sold without the Raydium
+//! SDK. Used by `TransactionBuilder` with the `raydium` feature; the pool's LP mint
+//! also serves the LP-burn check in `security`.
+
+use solana_account_decoder_client_types::UiAccountEncoding;
+use solana_client::client_error::Result as ClientResult;
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_client::rpc_config::{RpcAccountInfoConfig, RpcProgramAccountsConfig};
+use solana_client::rpc_filter::{Memcmp, RpcFilterType};
+use solana_sdk::{
+    instruction::{AccountMeta, Instruction},
+    pubkey,
+    pubkey::Pubkey,
+};
+use solana_system_interface::instruction as system_instruction;
+use spl_associated_token_account::{
+    get_associated_token_address, instruction::create_associated_token_account_idempotent,
+};
+use spl_token::id as token_program_id;
+use spl_token::instruction::{close_account, sync_native};
+
+/// Raydium liquidity pool v4 program.
+pub const AMM_V4_PROGRAM_ID: Pubkey = pubkey!("675kPX9MHTjS2zt1qfr1NYHuzeLXfQM9H24wFSUt1Mp8");
+/// Authority of every AMM v4 pool's vaults.
+pub const AMM_AUTHORITY: Pubkey = pubkey!("5Q544fKrFoe6tsEbD7S8EmxGTJYAKtTVhAW5Q5pge4j1");
+/// Wrapped SOL, the pc (quote) side of the pools pump.fun migrates to.
+pub const WSOL_MINT: Pubkey = pubkey!("So11111111111111111111111111111111111111112");
+
+/// Size of an `AmmInfo` account.
+pub const AMM_INFO_LEN: usize = 752;
+/// Offsets into `AmmInfo`: sixteen u64 parameters, then `Fees` (eight u64, the
+/// swap fee last) and `StateData` (whose first fields are the unclaimed PnL),
+/// then the vault, mint and market keys.
+const SWAP_FEE_NUMERATOR_OFFSET: usize = 176;
+const SWAP_FEE_DENOMINATOR_OFFSET: usize = 184;
+const NEED_TAKE_PNL_COIN_OFFSET: usize = 192;
+const NEED_TAKE_PNL_PC_OFFSET: usize = 200;
+const COIN_VAULT_OFFSET: usize = 336;
+const PC_VAULT_OFFSET: usize = 368;
+const COIN_MINT_OFFSET: usize = 400;
+const PC_MINT_OFFSET: usize = 432;
+const LP_MINT_OFFSET: usize = 464;
+
+/// Instruction tag of `swap_base_in_v2`, the swap without OpenBook market accounts.
+const SWAP_BASE_IN_V2_TAG: u8 = 16;
+/// Offset of `amount` in an SPL token account.
+const TOKEN_ACCOUNT_AMOUNT_OFFSET: usize = 64;
+
+/// An AMM v4 pool as read from its `AmmInfo` account.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AmmPool {
+    pub address: Pubkey,
+    pub coin_vault: Pubkey,
+    pub pc_vault: Pubkey,
+    pub coin_mint: Pubkey,
+    pub pc_mint: Pubkey,
+    /// Mint of the pool's LP tokens; burning them locks the liquidity
+    pub lp_mint: Pubkey,
+    need_take_pnl_coin: u64,
+    need_take_pnl_pc: u64,
+    swap_fee_numerator: u64,
+    swap_fee_denominator: u64,
+}
+
+impl AmmPool {
+    /// Pool at `address` from its account data; `None` for other accounts.
+    pub fn from_account_data(address: Pubkey, data: &[u8]) -> Option<Self> {
+        if data.len() != AMM_INFO_LEN {
+            return None;
+        }
+        let u64_at = |offset: usize| u64::from_le_bytes(data[offset..offset + 8].try_into().unwrap());
+        let key_at = |offset: usize| Pubkey::new_from_array(data[offset..offset + 32].try_into().unwrap());
+        Some(Self {
+            address,
+            coin_vault: key_at(COIN_VAULT_OFFSET),
+            pc_vault: key_at(PC_VAULT_OFFSET),
+            coin_mint: key_at(COIN_MINT_OFFSET),
+            pc_mint: key_at(PC_MINT_OFFSET),
+            lp_mint: key_at(LP_MINT_OFFSET),
+            need_take_pnl_coin: u64_at(NEED_TAKE_PNL_COIN_OFFSET),
+            need_take_pnl_pc: u64_at(NEED_TAKE_PNL_PC_OFFSET),
+            swap_fee_numerator: u64_at(SWAP_FEE_NUMERATOR_OFFSET),
+            swap_fee_denominator: u64_at(SWAP_FEE_DENOMINATOR_OFFSET),
+        })
+    }
+
+    /// Reserves given the vault balances; PnL the pool owes its owner is not tradable.
+    pub fn reserves(&self, coin_vault_amount: u64, pc_vault_amount: u64) -> PoolReserves {
+        PoolReserves {
+            coin: coin_vault_amount.saturating_sub(self.need_take_pnl_coin),
+            pc: pc_vault_amount.saturating_sub(self.need_take_pnl_pc),
+            fee_numerator: self.swap_fee_numerator,
+            fee_denominator: self.swap_fee_denominator,
+        }
+    }
+}
+
+/// Tradable reserves of a pool, coin being the token and pc SOL.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PoolReserves {
+    pub coin: u64,
+    pub pc: u64,
+    fee_numerator: u64,
+    fee_denominator: u64,
+}
+
+impl PoolReserves {
+    /// Constant-product output for `amount_in`, after the swap fee is taken from the
+    /// input (rounded up, as the program does).
+    fn swap_out(&self, amount_in: u64, reserve_in: u64, reserve_out: u64) -> u64 {
+        let fee = match self.fee_denominator {
+            0 => 0,
+            denominator => (amount_in as u128 * self.fee_numerator as u128).div_ceil(denominator as u128),
+        };
+        let net_in = (amount_in as u128).saturating_sub(fee);
+        if reserve_in as u128 + net_in == 0 {
+            return 0;
+        }
+        (reserve_out as u128 * net_in / (reserve_in as u128 + net_in)) as u64
+    }
+
+    /// Tokens out for `sol_in` lamports.
+    pub fn tokens_out(&self, sol_in: u64) -> u64 {
+        self.swap_out(sol_in, self.pc, self.coin)
+    }
+
+    /// Lamports out for selling `tokens_in`.
+    pub fn sol_out(&self, tokens_in: u64) -> u64 {
+        self.swap_out(tokens_in, self.coin, self.pc)
+    }
+
+    /// Current price in lamports per raw token; 0 when the pool holds no tokens.
+    pub fn price(&self) -> f64 {
+        if self.coin == 0 {
+            return 0.0;
+        }
+        self.pc as f64 / self.coin as f64
+    }
+}
+
+/// `getProgramAccounts` filters selecting the pools of `mint` against SOL.
+fn sol_pool_filters(mint: &Pubkey) -> Vec<RpcFilterType> {
+    vec![
+        RpcFilterType::DataSize(AMM_INFO_LEN as u64),
+        RpcFilterType::Memcmp(Memcmp::new_base58_encoded(COIN_MINT_OFFSET, mint.as_ref())),
+        RpcFilterType::Memcmp(Memcmp::new_base58_encoded(PC_MINT_OFFSET, WSOL_MINT.as_ref())),
+    ]
+}
+
+/// The pool trading `mint` against SOL, if there is one. A migrated pump.fun token
+/// has exactly one; should there be more, the first returned is used.
+pub async fn find_sol_pool(rpc: &RpcClient, mint: &Pubkey) -> ClientResult<Option<AmmPool>> {
+    let config = RpcProgramAccountsConfig {
+        filters: Some(sol_pool_filters(mint)),
+        account_config: RpcAccountInfoConfig { encoding: Some(UiAccountEncoding::Base64), ..Default::default() },
+        ..Default::default()
+    };
+    let accounts = rpc.get_program_accounts_with_config(&AMM_V4_PROGRAM_ID, config).await?;
+    Ok(accounts
+        .iter()
+        .filter_map(|(address, account)| AmmPool::from_account_data(*address, &account.data))
+        .find(|pool| pool.coin_mint == *mint && pool.pc_mint == WSOL_MINT))
+}
+
+/// Current reserves of `pool`; `None` when a vault is missing or not a token account.
+pub async fn fetch_reserves(rpc: &RpcClient, pool: &AmmPool) -> ClientResult<Option<PoolReserves>> {
+    let vaults = rpc.get_multiple_accounts(&[pool.coin_vault, pool.pc_vault]).await?;
+    let amount = |index: usize| {
+        let data = &vaults.get(index)?.as_ref()?.data;
+        data.get(TOKEN_ACCOUNT_AMOUNT_OFFSET..TOKEN_ACCOUNT_AMOUNT_OFFSET + 8)
+            .map(|bytes| u64::from_le_bytes(bytes.try_into().unwrap()))
+    };
+    Ok(amount(0).zip(amount(1)).map(|(coin, pc)| pool.reserves(coin, pc)))
+}
+
+/// `swap_base_in_v2` of `amount_in` from `payer`'s `source` token account for at
+/// least `minimum_amount_out` into `destination`; both accounts have to exist.
+pub fn swap_base_in(
+    pool: &AmmPool,
+    payer: &Pubkey,
+    source: &Pubkey,
+    destination: &Pubkey,
+    amount_in: u64,
+    minimum_amount_out: u64,
+) -> Instruction {
+    let mut data = Vec::with_capacity(17);
+    data.push(SWAP_BASE_IN_V2_TAG);
+    data.extend_from_slice(&amount_in.to_le_bytes());
+    data.extend_from_slice(&minimum_amount_out.to_le_bytes());
+    Instruction {
+        program_id: AMM_V4_PROGRAM_ID,
+        accounts: vec![
+            AccountMeta::new_readonly(token_program_id(), false),
+            AccountMeta::new(pool.address, false),
+            AccountMeta::new_readonly(AMM_AUTHORITY, false),
+            AccountMeta::new(pool.coin_vault, false),
+            AccountMeta::new(pool.pc_vault, false),
+            AccountMeta::new(*source, false),
+            AccountMeta::new(*destination, false),
+            AccountMeta::new_readonly(*payer, true),
+        ],
+        data,
+    }
+}
+
+/// A whole buy: create the token and WSOL accounts unless they exist, wrap
+/// `amount_in` lamports, swap them for at least `minimum_amount_out` tokens, then
+/// close the WSOL account again.
+pub fn buy_instructions(pool: &AmmPool, payer: &Pubkey, amount_in: u64, minimum_amount_out: u64) -> Vec<Instruction> {
+    let token_account = get_associated_token_address(payer, &pool.coin_mint);
+    let wsol_account = get_associated_token_address(payer, &WSOL_MINT);
+    vec![
+        create_associated_token_account_idempotent(payer, payer, &pool.coin_mint, &token_program_id()),
+        create_associated_token_account_idempotent(payer, payer, &WSOL_MINT, &token_program_id()),
+        system_instruction::transfer(payer, &wsol_account, amount_in),
+        sync_native(&token_program_id(), &wsol_account).expect("SPL token program id"),
+        swap_base_in(pool, payer, &wsol_account, &token_account, amount_in, minimum_amount_out),
+        close_wsol_account(payer),
+    ]
+}
+
+/// A whole sell: create the WSOL account the proceeds are paid into unless it
+/// exists, swap `amount_in` tokens for at least `minimum_amount_out` lamports, then
+/// close the WSOL account, which unwraps the proceeds (and any WSOL held before).
+pub fn sell_instructions(pool: &AmmPool, payer: &Pubkey, amount_in: u64, minimum_amount_out: u64) -> Vec<Instruction> {
+    let token_account = get_associated_token_address(payer, &pool.coin_mint);
+    let wsol_account = get_associated_token_address(payer, &WSOL_MINT);
+    vec![
+        create_associated_token_account_idempotent(payer, payer, &WSOL_MINT, &token_program_id()),
+        swap_base_in(pool, payer, &token_account, &wsol_account, amount_in, minimum_amount_out),
+        close_wsol_account(payer),
+    ]
+}
+
+/// Close `payer`'s WSOL account, returning its lamports to `payer`.
+fn close_wsol_account(payer: &Pubkey) -> Instruction {
+    let wsol_account = get_associated_token_address(payer, &WSOL_MINT);
+    close_account(&token_program_id(), &wsol_account, payer, payer, &[]).expect("SPL token program id")
+}
+
+/// Accounts of a pool of `mint` against SOL holding `coin` tokens and `pc`
+/// lamports, with `lp_mint` and the usual 0.25% swap fee, keyed by address as
+/// `test_support::AccountsRpc` serves them; returns the pool's address too.
+#[cfg(test)]
+pub(crate) fn sol_pool_accounts(
+    mint: &Pubkey,
+    lp_mint: &Pubkey,
+    coin: u64,
+    pc: u64,
+) -> (Pubkey, std::collections::HashMap<Pubkey, (Pubkey, Vec<u8>)>) {
+    let (address, coin_vault, pc_vault) = (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+    let mut pool = vec![0u8; AMM_INFO_LEN];
+    let mut put = |offset: usize, bytes: &[u8]| pool[offset..offset + bytes.len()].copy_from_slice(bytes);
+    put(SWAP_FEE_NUMERATOR_OFFSET, &25u64.to_le_bytes());
+    put(SWAP_FEE_DENOMINATOR_OFFSET, &10_000u64.to_le_bytes());
+    put(COIN_VAULT_OFFSET, coin_vault.as_ref());
+    put(PC_VAULT_OFFSET, pc_vault.as_ref());
+    put(COIN_MINT_OFFSET, mint.as_ref());
+    put(PC_MINT_OFFSET, WSOL_MINT.as_ref());
+    put(LP_MINT_OFFSET, lp_mint.as_ref());
+    let vault = |amount: u64| {
+        let mut data = vec![0u8; 165];
+        data[TOKEN_ACCOUNT_AMOUNT_OFFSET..TOKEN_ACCOUNT_AMOUNT_OFFSET + 8].copy_from_slice(&amount.to_le_bytes());
+        (token_program_id(), data)
+    };
+    let accounts = std::collections::HashMap::from([
+        (address, (AMM_V4_PROGRAM_ID, pool)),
+        (coin_vault, vault(coin)),
+        (pc_vault, vault(pc)),
+    ]);
+    (address, accounts)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::test_support::AccountsRpc;
+    use crate::tx_builder::min_out_after_slippage;
+
+    fn pool() -> AmmPool {
+        let (address, accounts) = sol_pool_accounts(&Pubkey::new_unique(), &Pubkey::new_unique(), 0, 0);
+        let mut data = accounts[&address].1.clone();
+        data[NEED_TAKE_PNL_COIN_OFFSET..NEED_TAKE_PNL_COIN_OFFSET + 8].copy_from_slice(&1_000u64.to_le_bytes());
+        data[NEED_TAKE_PNL_PC_OFFSET..NEED_TAKE_PNL_PC_OFFSET + 8].copy_from_slice(&2_000u64.to_le_bytes());
+        AmmPool::from_account_data(address, &data).unwrap()
+    }
+
+    #[test]
+    fn pool_account_parses_and_quotes_net_of_pnl_and_fee() {
+        let pool = pool();
+        assert_eq!(pool.pc_mint, WSOL_MINT);
+        assert!(AmmPool::from_account_data(pool.address, &[0u8; AMM_INFO_LEN - 1]).is_none());
+
+        // The unclaimed PnL stays out of the reserves
+        let reserves = pool.reserves(200_000_001_000, 80_000_002_000);
+        assert_eq!((reserves.coin, reserves.pc), (200_000_000_000, 80_000_000_000));
+        assert_eq!(reserves.price(), 0.4);
+
+        // 1 SOL in, 0.25% fee off the input
+        let out = reserves.tokens_out(1_000_000_000);
+        assert_eq!(out as u128, 200_000_000_000u128 * 997_500_000 / (80_000_000_000 + 997_500_000));
+        // The fee rounds up: 1 lamport in pays a whole lamport of fee
+        assert_eq!(reserves.tokens_out(1), 0);
+
+        // Selling the tokens back pays out less than went in, after fees both ways
+        let back = reserves.sol_out(out);
+        assert!(back < 1_000_000_000 && back > 950_000_000, "{}", back);
+        assert_eq!(min_out_after_slippage(back, 100), back * 99 / 100);
+        assert_eq!(pool.reserves(0, 0).sol_out(5), 0);
+    }
+
+    #[test]
+    fn trades_swap_through_the_pool_vaults_between_the_wallet_accounts() {
+        let pool = pool();
+        let payer = Pubkey::new_unique();
+        let token_account = get_associated_token_address(&payer, &pool.coin_mint);
+        let wsol_account = get_associated_token_address(&payer, &WSOL_MINT);
+        let close = close_account(&token_program_id(), &wsol_account, &payer, &payer, &[]).unwrap();
+
+        let buy = buy_instructions(&pool, &payer, 1_000_000, 42);
+        assert_eq!(
+            buy,
+            vec![
+                create_associated_token_account_idempotent(&payer, &payer, &pool.coin_mint, &token_program_id()),
+                create_associated_token_account_idempotent(&payer, &payer, &WSOL_MINT, &token_program_id()),
+                system_instruction::transfer(&payer, &wsol_account, 1_000_000),
+                sync_native(&token_program_id(), &wsol_account).unwrap(),
+                swap_base_in(&pool, &payer, &wsol_account, &token_account, 1_000_000, 42),
+                close.clone(),
+            ]
+        );
+
+        let sell = sell_instructions(&pool, &payer, 5_000, 7);
+        assert_eq!(sell.len(), 3);
+        assert_eq!(sell[0], create_associated_token_account_idempotent(&payer, &payer, &WSOL_MINT, &token_program_id()));
+        assert_eq!(sell[2], close);
+        let swap = &sell[1];
+        assert_eq!(swap.program_id, AMM_V4_PROGRAM_ID);
+        assert_eq!(swap.data, [&[SWAP_BASE_IN_V2_TAG][..], &5_000u64.to_le_bytes(), &7u64.to_le_bytes()].concat());
+        let keys: Vec<Pubkey> = swap.accounts.iter().map(|meta| meta.pubkey).collect();
+        assert_eq!(
+            keys,
+            vec![token_program_id(), pool.address, AMM_AUTHORITY, pool.coin_vault, pool.pc_vault, token_account, wsol_account, payer]
+        );
+        assert!(swap.accounts[7].is_signer && swap.accounts.iter().take(7).all(|meta| !meta.is_signer));
+    }
+
+    #[tokio::test]
+    async fn sol_pool_is_found_by_mint_and_quoted_from_its_vaults() {
+        let (mint, lp_mint) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let (address, mut accounts) = sol_pool_accounts(&mint, &lp_mint, 300_000_000_000, 60_000_000_000);
+        // Another token's pool, and a look-alike account of another program
+        let (_, other) = sol_pool_accounts(&Pubkey::new_unique(), &lp_mint, 1, 1);
+        accounts.extend(other);
+        let mut foreign = accounts[&address].1.clone();
+        foreign[0] = 1;
+        accounts.insert(Pubkey::new_unique(), (Pubkey::new_unique(), foreign));
+        let rpc = RpcClient::new_sender(AccountsRpc { accounts, token_balance: 0 }, Default::default());
+
+        let pool = find_sol_pool(&rpc, &mint).await.unwrap().expect("pool");
+        assert_eq!((pool.address, pool.coin_mint, pool.lp_mint), (address, mint, lp_mint));
+        let reserves = fetch_reserves(&rpc, &pool).await.unwrap().expect("reserves");
+        assert_eq!((reserves.coin, reserves.pc), (300_000_000_000, 60_000_000_000));
+        assert_eq!(reserves.price(), 0.2);
+
+        assert_eq!(find_sol_pool(&rpc, &Pubkey::new_unique()).await.unwrap(), None);
+        let missing_vaults = AmmPool { coin_vault: Pubkey::new_unique(), ..pool };
+        assert_eq!(fetch_reserves(&rpc, &missing_vaults).await.unwrap(), None);
+    }
+}
@@ -0,0 +1,389 @@
+//! Reconciliation of the in-memory positions against the on-chain token balances.
+//!
+//! `holdings_percent` is only updated by our own sells, so it drifts when a sell
+//! the engine counted never landed or tokens are moved externally. The reconciler
+//! reads the wallet's token account for each held mint and corrects the state.
+//!
+//! A position is what the wallet holds beyond its balance before the buy
+//! (`Position::balance_before_buy`, read before the buy was sent). The full-position
+//! size is taken from the first such amount seen for a mint (amount / recorded
+//! percent); later amounts are compared against it.
+//!
+//! Buys that were not confirmed in time are held as unconfirmed positions; the
+//! reconciler confirms them once tokens or a successful signature status show up,
+//! and drops them when the buy failed or nothing landed within a grace period.
+
+use std::collections::HashMap;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::time::Duration;
+
+use anyhow::{anyhow, Context, Result};
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::Signature;
+use spl_associated_token_account::get_associated_token_address;
+use spl_token_2022::extension::StateWithExtensions;
+use spl_token_2022::state::Account as TokenAccount;
+use tokio::sync::Mutex;
+use tracing::{debug, info, warn};
+
+use crate::metrics::metrics;
+use crate::portfolio::portfolio;
+use crate::time_utils::now_ms;
+use crate::types::{AppState, NotificationLevel};
+
+/// Differences in holdings fraction below this are rounding, not drift.
+pub const HOLDINGS_TOLERANCE: f64 = 0.01;
+
+/// How long an unconfirmed buy showing neither tokens nor a signature status is
+/// kept before it is dropped.
+pub const UNCONFIRMED_BUY_GRACE: Duration = Duration::from_secs(180);
+
+/// What the chain shows for an unconfirmed buy.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BuyLanding {
+    /// Tokens arrived or the transaction succeeded
+    Landed,
+    /// The transaction failed on chain
+    Failed,
+    /// No tokens and no status yet
+    Unknown,
+}
+
+/// Holdings fraction implied by `balance` for a position of `full_position` tokens.
+pub fn holdings_from_balance(balance: u64, full_position: u64) -> f64 {
+    if full_position == 0 {
+        return 0.0;
+    }
+    (balance as f64 / full_position as f64).clamp(0.0, 1.0)
+}
+
+/// Full-position size implied by the first observed balance and the recorded holdings.
+pub fn full_position_from(balance: u64, recorded_holdings: f64) -> u64 {
+    if recorded_holdings <= f64::EPSILON {
+        return balance;
+    }
+    (balance as f64 / recorded_holdings).round() as u64
+}
+
+/// Correct the held position for `mint` to match the on-chain `balance`.
+///
+/// Returns the corrected holdings when they changed; an empty balance closes
+/// the position.
+pub async fn apply_reconciliation(
+    app_state: &Mutex<AppState>,
+    mint: &Pubkey,
+    balance: u64,
+    full_position: u64,
+) -> Option<f64> {
+    let mut st = app_state.lock().await;
+    let recorded = st.position(mint)?.holdings_percent;
+
+    let actual = holdings_from_balance(balance, full_position);
+    if (actual - recorded).abs() < HOLDINGS_TOLERANCE {
+        return None;
+    }
+
+    warn!(mint = %mint, recorded, actual, balance, "Position drifted from chain; correcting holdings");
+    metrics().increment_counter("position_reconciliations_total");
+    st.notify(
+        NotificationLevel::Warn,
+        format!("Holdings corrected from {:.0}% to {:.0}% (on-chain balance)", recorded * 100.0, actual * 100.0),
+    );
+
+    if st.set_position_holdings(mint, actual) {
+        info!(mint = %mint, "No tokens left on chain; position closed");
+    }
+    Some(actual)
+}
+
+/// Settle the unconfirmed buy of `mint`: a landed one is confirmed, a failed one, or
+/// one still unknown `grace` after it was sent, is discarded. Returns whether the
+/// position was settled either way.
+pub async fn settle_unconfirmed_buy(
+    app_state: &Mutex<AppState>,
+    mint: &Pubkey,
+    landing: BuyLanding,
+    grace: Duration,
+) -> bool {
+    let mut st = app_state.lock().await;
+    let Some(position) = st.position(mint).filter(|p| p.is_unconfirmed()) else {
+        return false;
+    };
+    let age = Duration::from_millis(now_ms().saturating_sub(position.opened_ms));
+    match landing {
+        BuyLanding::Landed => {
+            info!(mint = %mint, "Unconfirmed buy landed; exits armed");
+            metrics().increment_counter("unconfirmed_buys_landed_total");
+            st.confirm_position(mint);
+            st.notify(NotificationLevel::Info, format!("Buy of {} landed", mint));
+            true
+        }
+        BuyLanding::Unknown if age < grace => false,
+        landing => {
+            warn!(mint = %mint, ?landing, age_ms = age.as_millis() as u64, "Unconfirmed buy never landed; dropping position");
+            metrics().increment_counter("unconfirmed_buys_dropped_total");
+            st.discard_unlanded_position(mint);
+            st.notify(NotificationLevel::Warn, format!("Buy of {} never landed; position dropped", mint));
+            true
+        }
+    }
+}
+
+/// Reads the wallet's token balance for a mint.
+pub trait TokenBalances: Send + Sync {
+    fn token_balance<'a>(&'a self, mint: &'a Pubkey) -> Pin<Box<dyn Future<Output = Result<u64>> + Send + 'a>>;
+}
+
+/// Token balances from the owner's associated token accounts.
+pub struct WalletTokenBalances {
+    rpc: Arc<RpcClient>,
+    owner: Pubkey,
+}
+
+impl WalletTokenBalances {
+    pub fn new(rpc: Arc<RpcClient>, owner: Pubkey) -> Self {
+        Self { rpc, owner }
+    }
+}
+
+impl TokenBalances for WalletTokenBalances {
+    fn token_balance<'a>(&'a self, mint: &'a Pubkey) -> Pin<Box<dyn Future<Output = Result<u64>> + Send + 'a>> {
+        Box::pin(ata_token_balance(&self.rpc, &self.owner, mint))
+    }
+}
+
+/// Raw token balance of `owner`'s associated token account for `mint`; 0 while
+/// the account does not exist (before the first buy of a mint).
+async fn ata_token_balance(rpc: &RpcClient, owner: &Pubkey, mint: &Pubkey) -> Result<u64> {
+    let ata = get_associated_token_address(owner, mint);
+    let account = rpc
+        .get_account_with_commitment(&ata, rpc.commitment())
+        .await
+        .context("fetch token account")?
+        .value;
+    let Some(account) = account else {
+        return Ok(0);
+    };
+    let state = StateWithExtensions::<TokenAccount>::unpack(&account.data)
+        .map_err(|e| anyhow!("token account {} does not parse: {}", ata, e))?;
+    Ok(state.base.amount)
+}
+
+/// Periodically reconciles the held positions with the wallet's token accounts.
+pub struct PositionReconciler {
+    rpc: Arc<RpcClient>,
+    app_state: Arc<Mutex<AppState>>,
+    owner: Pubkey,
+    interval: Duration,
+}
+
+impl PositionReconciler {
+    pub fn new(rpc: Arc<RpcClient>, app_state: Arc<Mutex<AppState>>, owner: Pubkey, interval: Duration) -> Self {
+        Self { rpc, app_state, owner, interval }
+    }
+
+    /// Raw token balance of the owner's associated token account for `mint`.
+    pub async fn token_balance(&self, mint: &Pubkey) -> Result<u64> {
+        ata_token_balance(&self.rpc, &self.owner, mint).await
+    }
+
+    /// Whether the unconfirmed buy `signature` of `mint` landed: tokens in the wallet
+    /// settle it, else its signature status does.
+    async fn buy_landing(&self, mint: &Pubkey, signature: &Signature) -> BuyLanding {
+        if matches!(self.token_balance(mint).await, Ok(balance) if balance > 0) {
+            return BuyLanding::Landed;
+        }
+        match self.rpc.get_signature_statuses(&[*signature]).await {
+            Ok(response) => match response.value.into_iter().next().flatten() {
+                Some(status) if status.err.is_some() => BuyLanding::Failed,
+                Some(_) => BuyLanding::Landed,
+                None => BuyLanding::Unknown,
+            },
+            Err(e) => {
+                debug!(mint = %mint, sig = %signature, error = %e, "Unconfirmed buy status check failed");
+                BuyLanding::Unknown
+            }
+        }
+    }
+
+    /// Run until the task is aborted. The first tick fires immediately, so
+    /// positions present at startup are checked right away.
+    pub async fn run(self) {
+        let mut ticker = tokio::time::interval(self.interval);
+        let mut full_positions: HashMap<Pubkey, u64> = HashMap::new();
+        loop {
+            ticker.tick().await;
+
+            let held: Vec<(Pubkey, f64, Option<Signature>, u64)> = {
+                let st = self.app_state.lock().await;
+                st.open_positions()
+                    .iter()
+                    .map(|p| {
+                        (p.token.mint, p.holdings_percent, p.unconfirmed_signature, p.balance_before_buy.unwrap_or(0))
+                    })
+                    .collect()
+            };
+            full_positions.retain(|mint, _| held.iter().any(|(m, ..)| m == mint));
+            portfolio().retain(&held.iter().map(|(mint, ..)| *mint).collect::<Vec<_>>());
+
+            for (mint, recorded, unconfirmed, balance_before_buy) in held {
+                // Until its buy lands, a position's balance says nothing about drift
+                if let Some(signature) = unconfirmed {
+                    let landing = self.buy_landing(&mint, &signature).await;
+                    settle_unconfirmed_buy(&self.app_state, &mint, landing, UNCONFIRMED_BUY_GRACE).await;
+                    continue;
+                }
+                let balance = match self.token_balance(&mint).await {
+                    Ok(balance) => balance,
+                    Err(e) => {
+                        warn!(mint = %mint, error = %e, "Position reconciliation failed");
+                        continue;
+                    }
+                };
+
+                portfolio().set_balance(mint, balance);
+
+                // Tokens the wallet already held before the buy are not the position's
+                let position_tokens = balance.saturating_sub(balance_before_buy);
+                let full = *full_positions.entry(mint).or_insert_with(|| {
+                    let full = full_position_from(position_tokens, recorded);
+                    debug!(mint = %mint, balance, balance_before_buy, full, "Recorded full position size");
+                    full
+                });
+                apply_reconciliation(&self.app_state, &mint, position_tokens, full).await;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{Mode, Position, PremintCandidate};
+
+    fn held_state(mint: Pubkey, holdings_percent: f64) -> Mutex<AppState> {
+        Mutex::new(AppState {
+            mode: Mode::PassiveToken(mint),
+            active_token: Some(PremintCandidate {
+                mint,
+                creator: Pubkey::new_unique(),
+                program: "pump.fun".to_string(),
+                slot: 0,
+                timestamp: 0,
+                instruction_summary: None,
+                is_jito_bundle: None,
+                deadline_ms: None,
+            }),
+            last_buy_price: Some(1.0),
+            holdings_percent,
+            quantum_suggestions: Default::default(),
+            notifications: Default::default(),
+            operations: Default::default(),
+            positions: Default::default(),
+            pnl: Default::default(),
+        })
+    }
+
+    #[tokio::test]
+    async fn unconfirmed_buys_are_confirmed_or_dropped() {
+        let grace = Duration::from_secs(60);
+        let mint = Pubkey::new_unique();
+        let state = held_state(mint, 1.0);
+        {
+            let mut st = state.lock().await;
+            let token = st.active_token.clone().unwrap();
+            st.open_position(Position {
+                cost_lamports: 1_000,
+                unconfirmed_signature: Some(Signature::from([3; 64])),
+                ..Position::new(token, Some(1.0), 1.0)
+            });
+            st.record_buy(1_000);
+        }
+
+        // Nothing on chain yet, but still within the grace period
+        assert!(!settle_unconfirmed_buy(&state, &mint, BuyLanding::Unknown, grace).await);
+        assert!(state.lock().await.position(&mint).unwrap().is_unconfirmed());
+
+        assert!(settle_unconfirmed_buy(&state, &mint, BuyLanding::Landed, grace).await);
+        assert!(!state.lock().await.position(&mint).unwrap().is_unconfirmed());
+        // A confirmed position is left to the balance reconciliation
+        assert!(!settle_unconfirmed_buy(&state, &mint, BuyLanding::Failed, grace).await);
+
+        // A buy that failed on chain is dropped along with its cost
+        state.lock().await.positions.get_mut(&mint).unwrap().unconfirmed_signature = Some(Signature::from([4; 64]));
+        assert!(settle_unconfirmed_buy(&state, &mint, BuyLanding::Failed, grace).await);
+        let st = state.lock().await;
+        assert!(st.position(&mint).is_none());
+        assert!(st.is_sniffing());
+        assert_eq!(st.pnl.spent_lamports, 0);
+    }
+
+    #[tokio::test]
+    async fn unconfirmed_buys_with_no_trace_expire_after_the_grace_period() {
+        let mint = Pubkey::new_unique();
+        let state = held_state(mint, 1.0);
+        {
+            let mut st = state.lock().await;
+            let token = st.active_token.clone().unwrap();
+            st.open_position(Position {
+                opened_ms: now_ms() - 120_000,
+                unconfirmed_signature: Some(Signature::from([5; 64])),
+                ..Position::new(token, Some(1.0), 1.0)
+            });
+        }
+
+        assert!(settle_unconfirmed_buy(&state, &mint, BuyLanding::Unknown, Duration::from_secs(60)).await);
+        assert!(state.lock().await.position(&mint).is_none());
+    }
+
+    #[tokio::test]
+    async fn chain_balance_corrects_drifted_holdings() {
+        let mint = Pubkey::new_unique();
+        // Engine believes a 50% sell went through; chain still holds 80% of 1000
+        let state = held_state(mint, 0.5);
+        assert_eq!(full_position_from(1000, 1.0), 1000);
+
+        assert_eq!(apply_reconciliation(&state, &mint, 800, 1000).await, Some(0.8));
+        {
+            let st = state.lock().await;
+            assert_eq!(st.holdings_percent, 0.8);
+            assert_eq!(st.notifications.back().unwrap().level, NotificationLevel::Warn);
+        }
+
+        // Within tolerance: nothing to correct
+        assert_eq!(apply_reconciliation(&state, &mint, 805, 1000).await, None);
+
+        // Tokens moved out externally: position is gone
+        assert_eq!(apply_reconciliation(&state, &mint, 0, 1000).await, Some(0.0));
+        let st = state.lock().await;
+        assert!(st.is_sniffing());
+        assert!(st.active_token.is_none());
+    }
+
+    #[tokio::test]
+    async fn missing_token_account_reads_as_an_empty_balance() {
+        use solana_client::rpc_client::Mocks;
+        use solana_client::rpc_request::RpcRequest;
+        use solana_sdk::program_pack::Pack;
+
+        let (owner, mint) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let missing = serde_json::json!({ "context": { "slot": 1 }, "value": null });
+        let rpc = RpcClient::new_mock_with_mocks("succeeds".to_string(), Mocks::from([(RpcRequest::GetAccountInfo, missing)]));
+        assert_eq!(ata_token_balance(&rpc, &owner, &mint).await.unwrap(), 0);
+
+        let mut data = vec![0; spl_token::state::Account::LEN];
+        spl_token::state::Account {
+            mint,
+            owner,
+            amount: 1_234,
+            state: spl_token::state::AccountState::Initialized,
+            ..Default::default()
+        }
+        .pack_into_slice(&mut data);
+        let rpc = crate::test_support::rpc_with_mint(&spl_token::id(), &data);
+        assert_eq!(ata_token_balance(&rpc, &owner, &mint).await.unwrap(), 1_234);
+    }
+}
@@ -0,0 +1,390 @@
+//! Recovery of the held positions after a crash or restart.
+//!
+//! `StateSnapshotter` keeps the held positions in the configured persistence store.
+//! On startup the snapshot is restored; with `recover_positions_from_chain` set, a
+//! position missing from the snapshot (e.g. a buy that landed just before the
+//! crash) is rebuilt from the wallet's nonzero token balances. Only mints the bot
+//! likely bought are considered: buys recorded in the audit log and pump.fun mints
+//! (vanity addresses ending in `pump`).
+//!
+//! A recovered position starts at 100% of the current balance and without a buy
+//! price; position reconciliation keeps it in line with the chain from there.
+//!
+//! `install_panic_hook` saves one last snapshot when any thread or task panics, so
+//! a crash between two snapshot ticks does not lose the held position.
+
+use std::any::Any;
+use std::collections::HashSet;
+use std::io::Write;
+use std::str::FromStr;
+use std::sync::Arc;
+use std::time::Duration;
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_client::rpc_request::TokenAccountsFilter;
+use solana_sdk::pubkey::Pubkey;
+use tokio::sync::Mutex;
+use tracing::{error, info, warn};
+
+use crate::audit::{AuditEvent, AuditLog};
+use crate::metrics::metrics;
+use crate::persistence::{load_json, save_json, Persistence};
+use crate::time_utils::now_ms;
+use crate::types::{AppState, Mode, NotificationLevel, Position, PremintCandidate};
+
+/// Persistence key of the state snapshot.
+pub const STATE_KEY: &str = "state";
+
+/// The held positions as persisted.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct StateSnapshot {
+    /// Focused token; `None` while sniffing
+    pub active_token: Option<PremintCandidate>,
+    pub last_buy_price: Option<f64>,
+    pub holdings_percent: f64,
+    /// Every open position, most recent first; empty in snapshots that predate
+    /// concurrent positions
+    #[serde(default)]
+    pub positions: Vec<Position>,
+}
+
+impl StateSnapshot {
+    pub fn capture(st: &AppState) -> Self {
+        let positions = st.open_positions();
+        match st.mode {
+            Mode::PassiveToken(_) => Self {
+                active_token: st.active_token.clone(),
+                last_buy_price: st.last_buy_price,
+                holdings_percent: st.holdings_percent,
+                positions,
+            },
+            _ => Self { active_token: None, last_buy_price: None, holdings_percent: 0.0, positions },
+        }
+    }
+
+    /// Positions to restore, oldest first; a snapshot without `positions` holds
+    /// at most the focused one.
+    fn into_positions(self) -> Vec<Position> {
+        let mut positions = self.positions;
+        if positions.is_empty() {
+            positions.extend(
+                self.active_token
+                    .map(|token| Position::new(token, self.last_buy_price, self.holdings_percent)),
+            );
+        }
+        positions.retain(|p| p.holdings_percent > f64::EPSILON);
+        positions.sort_by_key(|p| (p.opened_ms, p.entry_slot));
+        positions
+    }
+}
+
+/// Nonzero token balance held by the wallet.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct WalletTokenBalance {
+    pub mint: Pubkey,
+    pub amount: u64,
+}
+
+/// Where a recovered position came from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RecoveredFrom {
+    Snapshot,
+    Wallet,
+}
+
+/// Whether the bot likely bought `mint`: recorded in `known_buys` or a pump.fun mint.
+pub fn likely_bot_mint(mint: &Pubkey, known_buys: &HashSet<Pubkey>) -> bool {
+    known_buys.contains(mint) || mint.to_string().ends_with("pump")
+}
+
+/// Mints of buys recorded in the audit log at `path`.
+pub fn audit_buy_mints(path: &str) -> Result<HashSet<Pubkey>> {
+    Ok(AuditLog::read_entries(path)?
+        .into_iter()
+        .filter_map(|entry| match entry.event {
+            AuditEvent::Broadcast { kind, mint, .. } if kind == "buy" => Pubkey::from_str(&mint).ok(),
+            _ => None,
+        })
+        .collect())
+}
+
+/// Nonzero SPL token balances of `owner`.
+pub async fn wallet_token_balances(rpc: &RpcClient, owner: &Pubkey) -> Result<Vec<WalletTokenBalance>> {
+    let accounts = rpc
+        .get_token_accounts_by_owner(owner, TokenAccountsFilter::ProgramId(spl_token::id()))
+        .await
+        .context("fetch wallet token accounts")?;
+    Ok(accounts
+        .into_iter()
+        .filter_map(|keyed| {
+            // jsonParsed: { "parsed": { "info": { "mint": .., "tokenAmount": { "amount": .. } } } }
+            let data = serde_json::to_value(&keyed.account.data).ok()?;
+            let info = data.get("parsed")?.get("info")?;
+            let mint = Pubkey::from_str(info.get("mint")?.as_str()?).ok()?;
+            let amount = info.get("tokenAmount")?.get("amount")?.as_str()?.parse().ok()?;
+            Some(WalletTokenBalance { mint, amount })
+        })
+        .filter(|balance| balance.amount > 0)
+        .collect())
+}
+
+/// Restore the held positions into a sniffing `app_state`: from `snapshot` when it
+/// holds any, otherwise from the largest likely-bought balance in `wallet` (pass
+/// an empty slice to skip on-chain recovery).
+pub async fn recover_position(
+    app_state: &Mutex<AppState>,
+    snapshot: Option<StateSnapshot>,
+    wallet: &[WalletTokenBalance],
+    known_buys: &HashSet<Pubkey>,
+) -> Option<RecoveredFrom> {
+    let mut st = app_state.lock().await;
+    if !st.is_sniffing() {
+        return None;
+    }
+
+    let restored = snapshot.map(StateSnapshot::into_positions).unwrap_or_default();
+    if !restored.is_empty() {
+        // Oldest first, so the most recent position ends up focused
+        for position in restored {
+            info!(mint = %position.token.mint, holdings_percent = position.holdings_percent, "Restored held position from snapshot");
+            metrics().increment_counter("position_recovered_snapshot_total");
+            st.notify(NotificationLevel::Info, format!("Restored position in {}", position.token.mint));
+            st.open_position(position);
+        }
+        return Some(RecoveredFrom::Snapshot);
+    }
+
+    let mut candidates: Vec<_> = wallet
+        .iter()
+        .filter(|b| b.amount > 0 && likely_bot_mint(&b.mint, known_buys))
+        .collect();
+    candidates.sort_by_key(|b| std::cmp::Reverse(b.amount));
+    let (recovered, unmanaged) = candidates.split_first()?;
+    for other in unmanaged {
+        warn!(mint = %other.mint, amount = other.amount, "Wallet holds another likely bot token; left unmanaged");
+    }
+
+    warn!(mint = %recovered.mint, amount = recovered.amount, "Recovered position from wallet balance (not in snapshot)");
+    metrics().increment_counter("position_recovered_wallet_total");
+    st.notify(
+        NotificationLevel::Warn,
+        format!("Recovered position in {} from wallet balance", recovered.mint),
+    );
+    let token = PremintCandidate {
+        mint: recovered.mint,
+        creator: Pubkey::default(),
+        program: "pump.fun".to_string(),
+        slot: 0,
+        timestamp: now_ms() / 1000,
+        instruction_summary: Some("recovered from wallet".to_string()),
+        is_jito_bundle: None,
+        deadline_ms: None,
+    };
+    st.open_position(Position::new(token, None, 1.0));
+    Some(RecoveredFrom::Wallet)
+}
+
+/// Load the snapshot from `store`; a missing or unreadable snapshot is `None`.
+pub fn load_snapshot(store: &dyn Persistence) -> Option<StateSnapshot> {
+    load_json(store, STATE_KEY)
+        .map_err(|e| warn!(error = %e, "State snapshot not loaded"))
+        .ok()
+        .flatten()
+}
+
+/// Periodically saves the held positions when they change.
+pub struct StateSnapshotter {
+    store: Arc<dyn Persistence>,
+    app_state: Arc<Mutex<AppState>>,
+    interval: Duration,
+}
+
+impl StateSnapshotter {
+    pub fn new(store: Arc<dyn Persistence>, app_state: Arc<Mutex<AppState>>, interval: Duration) -> Self {
+        Self { store, app_state, interval }
+    }
+
+    /// Run until the task is aborted.
+    pub async fn run(self) {
+        let mut ticker = tokio::time::interval(self.interval);
+        let mut last_saved: Option<StateSnapshot> = None;
+        loop {
+            ticker.tick().await;
+            let snapshot = StateSnapshot::capture(&*self.app_state.lock().await);
+            if last_saved.as_ref() == Some(&snapshot) {
+                continue;
+            }
+            match save_json(self.store.as_ref(), STATE_KEY, &snapshot) {
+                Ok(()) => last_saved = Some(snapshot),
+                Err(e) => warn!(error = %e, "State snapshot not saved"),
+            }
+        }
+    }
+}
+
+/// Install a panic hook that logs the panic as a structured error, saves a final
+/// state snapshot to `store` (when set) and flushes stdout/stderr, then runs the
+/// previously installed hook.
+pub fn install_panic_hook(store: Option<Arc<dyn Persistence>>, app_state: Arc<Mutex<AppState>>) {
+    let previous = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        let location = info.location().map(|l| l.to_string()).unwrap_or_default();
+        record_panic(info.payload(), &location, store.as_deref(), &app_state);
+        // The fmt subscriber writes straight to stdout; flush before unwinding
+        let _ = std::io::stdout().flush();
+        let _ = std::io::stderr().flush();
+        previous(info);
+    }));
+}
+
+/// What the panic hook does before handing over: count and log the panic, and
+/// save a snapshot of `app_state` to `store` (when set).
+fn record_panic(payload: &(dyn Any + Send), location: &str, store: Option<&dyn Persistence>, app_state: &Mutex<AppState>) {
+    metrics().increment_counter("panics_total");
+    // try_lock: the panicking task may be the one holding the state
+    let snapshot = app_state.try_lock().ok().map(|st| StateSnapshot::capture(&st));
+    error!(
+        panic = %panic_message(payload),
+        location = %location,
+        snapshot = ?snapshot,
+        "Panic; saving state snapshot"
+    );
+    match (store, &snapshot) {
+        (Some(store), Some(snapshot)) => {
+            if let Err(e) = save_json(store, STATE_KEY, snapshot) {
+                error!(error = %e, "State snapshot not saved on panic");
+            }
+        }
+        (Some(_), None) => warn!("State locked during panic; snapshot not saved"),
+        (None, _) => {}
+    }
+}
+
+fn panic_message(payload: &(dyn Any + Send)) -> String {
+    payload
+        .downcast_ref::<&str>()
+        .map(|s| s.to_string())
+        .or_else(|| payload.downcast_ref::<String>().cloned())
+        .unwrap_or_else(|| "non-string panic payload".to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::persistence::MemoryPersistence;
+
+    fn sniffing_state() -> Mutex<AppState> {
+        Mutex::new(AppState::sniffing())
+    }
+
+    #[tokio::test]
+    async fn wallet_balance_without_snapshot_is_recovered() {
+        let pump_mint = Pubkey::from_str("2qEHjDLDLbuBgRYvsxhc5D6uDWAivNFZGan56P1tpump").unwrap();
+        let unrelated = Pubkey::new_unique();
+        let wallet = [
+            WalletTokenBalance { mint: unrelated, amount: 5_000_000 },
+            WalletTokenBalance { mint: pump_mint, amount: 1_234_567 },
+        ];
+
+        let store = MemoryPersistence::default();
+        let state = sniffing_state();
+        let recovered = recover_position(&state, load_snapshot(&store), &wallet, &HashSet::new()).await;
+        assert_eq!(recovered, Some(RecoveredFrom::Wallet));
+        {
+            let st = state.lock().await;
+            assert!(matches!(st.mode, Mode::PassiveToken(m) if m == pump_mint));
+            assert_eq!(st.holdings_percent, 1.0);
+            assert_eq!(st.active_token.as_ref().unwrap().mint, pump_mint);
+        }
+
+        // A mint from the audit log counts as a bot buy even without the pump suffix
+        let state = sniffing_state();
+        let known = HashSet::from([unrelated]);
+        recover_position(&state, None, &wallet[..1], &known).await;
+        assert!(matches!(state.lock().await.mode, Mode::PassiveToken(m) if m == unrelated));
+
+        // Unrelated tokens alone are left alone
+        let state = sniffing_state();
+        assert_eq!(recover_position(&state, None, &wallet[..1], &HashSet::new()).await, None);
+        assert!(state.lock().await.is_sniffing());
+    }
+
+    #[tokio::test]
+    async fn snapshot_takes_precedence_over_wallet() {
+        let held = Pubkey::new_unique();
+        let store = MemoryPersistence::default();
+        let held_state = Mutex::new(AppState {
+            mode: Mode::PassiveToken(held),
+            active_token: Some(PremintCandidate {
+                mint: held,
+                creator: Pubkey::new_unique(),
+                program: "pump.fun".to_string(),
+                slot: 7,
+                timestamp: 0,
+                instruction_summary: None,
+                is_jito_bundle: None,
+                deadline_ms: None,
+            }),
+            last_buy_price: Some(0.5),
+            holdings_percent: 0.4,
+            quantum_suggestions: Default::default(),
+            notifications: Default::default(),
+            operations: Default::default(),
+            positions: Default::default(),
+            pnl: Default::default(),
+        });
+        save_json(&store, STATE_KEY, &StateSnapshot::capture(&*held_state.lock().await)).unwrap();
+
+        let wallet = [WalletTokenBalance {
+            mint: Pubkey::from_str("2qEHjDLDLbuBgRYvsxhc5D6uDWAivNFZGan56P1tpump").unwrap(),
+            amount: 10,
+        }];
+        let state = sniffing_state();
+        let recovered = recover_position(&state, load_snapshot(&store), &wallet, &HashSet::new()).await;
+        assert_eq!(recovered, Some(RecoveredFrom::Snapshot));
+        let st = state.lock().await;
+        assert!(matches!(st.mode, Mode::PassiveToken(m) if m == held));
+        assert_eq!(st.holdings_percent, 0.4);
+        assert_eq!(st.last_buy_price, Some(0.5));
+    }
+
+    #[test]
+    fn panic_hook_saves_snapshot() {
+        let held = Pubkey::new_unique();
+        let store = Arc::new(MemoryPersistence::default());
+        let state = Arc::new(Mutex::new(AppState {
+            mode: Mode::PassiveToken(held),
+            active_token: Some(PremintCandidate {
+                mint: held,
+                creator: Pubkey::new_unique(),
+                program: "pump.fun".to_string(),
+                slot: 3,
+                timestamp: 0,
+                instruction_summary: None,
+                is_jito_bundle: None,
+                deadline_ms: None,
+            }),
+            last_buy_price: Some(0.2),
+            holdings_percent: 0.75,
+            quantum_suggestions: Default::default(),
+            notifications: Default::default(),
+            operations: Default::default(),
+            positions: Default::default(),
+            pnl: Default::default(),
+        }));
+        let panics_before = metrics().get_counter("panics_total");
+
+        // The hook itself is process-wide; feed its body a real panic payload instead
+        let payload = std::panic::catch_unwind(|| panic!("task blew up")).unwrap_err();
+        assert_eq!(panic_message(payload.as_ref()), "task blew up");
+        record_panic(payload.as_ref(), "recovery.rs", Some(store.as_ref() as &dyn Persistence), &state);
+
+        assert!(metrics().get_counter("panics_total") > panics_before);
+        let snapshot = load_snapshot(store.as_ref()).expect("snapshot saved by the hook");
+        assert_eq!(snapshot.active_token.map(|c| c.mint), Some(held));
+        assert_eq!(snapshot.holdings_percent, 0.75);
+        assert_eq!(snapshot.last_buy_price, Some(0.2));
+    }
+}
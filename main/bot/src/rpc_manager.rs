@@ -15,36 +15,128 @@ use std::{collections::HashMap, future::Future, sync::Arc, time::Instant};
 use std::pin::Pin;
 use std::time::Duration;
 
-use tokio::{sync::RwLock, task::JoinSet, time::timeout};
+use tokio::{
+    sync::RwLock,
+    task::JoinSet,
+    time::{sleep, timeout},
+};
 use tracing::{debug, info, warn};
 
-use crate::config::Config;
+use crate::config::{Config, EndpointSendConfig, SendStrategy};
+use crate::metrics::metrics;
 use crate::observability::CorrelationId;
 
 /// Classification of RPC errors for handling logic
-#[derive(Debug, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub enum RpcErrorType {
     AlreadyProcessed,
     DuplicateSignature,
     BlockhashNotFound,
     RateLimited,
+    UnsupportedVersion,
+    /// The token account is frozen by the mint's freeze authority
+    AccountFrozen,
     Other(String),
 }
 
+/// Error text fragments RPC nodes use when rejecting v0 transactions.
+const UNSUPPORTED_VERSION_MARKERS: [&str; 3] = [
+    "unsupported transaction version",
+    "transaction version",
+    "versioned transactions are not supported",
+];
+
+fn mentions_unsupported_version(msg: &str) -> bool {
+    let msg = msg.to_lowercase();
+    UNSUPPORTED_VERSION_MARKERS.iter().any(|m| msg.contains(m))
+}
+
+/// Whether a broadcast error (including its context chain) is a transaction-version rejection.
+pub fn is_unsupported_version_error(error: &anyhow::Error) -> bool {
+    mentions_unsupported_version(&format!("{:#}", error))
+}
+
+fn mentions_blockhash_not_found(msg: &str) -> bool {
+    msg.to_lowercase().contains("blockhash not found")
+}
+
+/// Why every endpoint send of a broadcast failed, as returned by `send_on_many_rpc`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, thiserror::Error)]
+pub enum BroadcastFailure {
+    #[error("RpcManager: all sends failed: token account is frozen ({0} endpoints)")]
+    AccountFrozen(usize),
+    #[error("RpcManager: all sends failed: unsupported transaction version ({0} endpoints)")]
+    UnsupportedVersion(usize),
+    #[error("RpcManager: all sends failed: blockhash not found ({0} endpoints)")]
+    BlockhashNotFound(usize),
+    #[error("RpcManager: all sends failed (fatal_errors: {0})")]
+    Other(usize),
+}
+
+/// Whether a broadcast error (anywhere in its context chain) is a stale blockhash or
+/// durable nonce. A rebuild with a fresh one can land; resending the same tx cannot.
+pub fn is_blockhash_not_found_error(error: &anyhow::Error) -> bool {
+    error.chain().any(|cause| {
+        matches!(cause.downcast_ref::<BroadcastFailure>(), Some(BroadcastFailure::BlockhashNotFound(_)))
+            || cause
+                .downcast_ref::<ClientError>()
+                .is_some_and(|e| classify_rpc_error(e) == RpcErrorType::BlockhashNotFound)
+    })
+}
+
+/// SPL token program log when an instruction touches a frozen account (TokenError::AccountFrozen).
+const FROZEN_ACCOUNT_MARKER: &str = "account is frozen";
+
+fn mentions_frozen_account(msg: &str) -> bool {
+    msg.to_lowercase().contains(FROZEN_ACCOUNT_MARKER)
+}
+
+/// Whether a broadcast error (including its context chain and preflight logs) is a
+/// frozen token account. Resending cannot help until the account is thawed.
+pub fn is_frozen_account_error(error: &anyhow::Error) -> bool {
+    mentions_frozen_account(&format!("{:#}", error))
+}
+
+/// A send the endpoint did not answer within `rpc_timeout_sec`.
+#[derive(Debug, thiserror::Error)]
+#[error("RPC send timeout")]
+struct SendTimeout;
+
+/// Whether a failed send says something about the endpoint itself: unreachable,
+/// too slow or rate limiting. Only these count against its health and breaker; a
+/// rejected transaction (simulation failure, slippage, stale blockhash) does not.
+fn is_endpoint_failure(error: &anyhow::Error) -> bool {
+    if error.is::<SendTimeout>() {
+        return true;
+    }
+    match error.downcast_ref::<ClientError>() {
+        Some(e) => match e.kind() {
+            ClientErrorKind::Io(_) | ClientErrorKind::Reqwest(_) | ClientErrorKind::Middleware(_) => true,
+            _ => classify_rpc_error(e) == RpcErrorType::RateLimited,
+        },
+        None => false,
+    }
+}
+
 /// Classify a ClientError into an RpcErrorType for consistent handling
 pub fn classify_rpc_error(error: &ClientError) -> RpcErrorType {
     match error.kind() {
         ClientErrorKind::RpcError(rpc_error) => match rpc_error {
-            RpcError::RpcResponseError { message, .. } => {
+            RpcError::RpcResponseError { message, data, .. } => {
                 let msg = message.to_lowercase();
-                if msg.contains("already processed") {
+                if msg.contains("already processed") || msg.contains("already been processed") {
                     RpcErrorType::AlreadyProcessed
                 } else if msg.contains("duplicate signature") {
                     RpcErrorType::DuplicateSignature
-                } else if msg.contains("blockhash not found") {
+                } else if mentions_blockhash_not_found(&msg) {
                     RpcErrorType::BlockhashNotFound
                 } else if msg.contains("rate limit") || msg.contains("too many requests") {
                     RpcErrorType::RateLimited
+                } else if mentions_unsupported_version(&msg) {
+                    RpcErrorType::UnsupportedVersion
+                } else if mentions_frozen_account(&msg) || mentions_frozen_account(&data.to_string()) {
+                    // Preflight failures carry the token program's log in `data`
+                    RpcErrorType::AccountFrozen
                 } else {
                     RpcErrorType::Other(message.clone())
                 }
@@ -55,17 +147,50 @@ pub fn classify_rpc_error(error: &ClientError) -> RpcErrorType {
     }
 }
 
+/// Consecutive failed sends/probes after which an endpoint counts as unhealthy.
+const UNHEALTHY_AFTER_ERRORS: u32 = 3;
+
+/// Interval between signature status polls while confirming a transaction.
+const CONFIRM_POLL_INTERVAL: Duration = Duration::from_millis(200);
+
+/// Average latency at which an always-successful endpoint's score halves.
+const RANKING_LATENCY_SCALE_MS: f64 = 1000.0;
+
+/// Circuit breaker state of an endpoint.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BreakerState {
+    /// Sends go through
+    Closed,
+    /// Too many consecutive failures; sends skip the endpoint until the cooldown ends
+    Open,
+    /// Cooldown over; one probe send decides between closed and open again
+    HalfOpen,
+}
+
+impl BreakerState {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            BreakerState::Closed => "closed",
+            BreakerState::Open => "open",
+            BreakerState::HalfOpen => "half_open",
+        }
+    }
+}
+
 /// Endpoint performance metrics for adaptive ranking
-#[allow(dead_code)]
 #[derive(Debug, Clone)]
 struct EndpointMetrics {
     success_count: u64,
     error_count: u64,
     total_latency_ms: u64,
     last_success: Option<Instant>,
+    consecutive_errors: u32,
+    /// When the circuit breaker last opened; `None` while closed.
+    breaker_opened_at: Option<Instant>,
+    /// When the half-open probe was let through, if one is pending.
+    probe_started_at: Option<Instant>,
 }
 
-#[allow(dead_code)]
 impl EndpointMetrics {
     fn new() -> Self {
         Self {
@@ -73,6 +198,9 @@ impl EndpointMetrics {
             error_count: 0,
             total_latency_ms: 0,
             last_success: None,
+            consecutive_errors: 0,
+            breaker_opened_at: None,
+            probe_started_at: None,
         }
     }
 
@@ -94,24 +222,280 @@ impl EndpointMetrics {
     }
 
     fn record_success(&mut self, latency_ms: u64) {
+        self.record_probe_success(latency_ms);
+        self.breaker_opened_at = None;
+        self.probe_started_at = None;
+    }
+
+    /// A `getHealth` answer: moves health and ranking, but leaves the breaker to send
+    /// results, since an endpoint can answer probes while rate-limiting sends.
+    fn record_probe_success(&mut self, latency_ms: u64) {
         self.success_count += 1;
         self.total_latency_ms += latency_ms;
         self.last_success = Some(Instant::now());
+        self.consecutive_errors = 0;
     }
 
-    fn record_error(&mut self) {
+    fn record_probe_error(&mut self) {
         self.error_count += 1;
+        self.consecutive_errors += 1;
+    }
+
+    /// Count a failure; opens the breaker at `breaker.failure_threshold` consecutive
+    /// failures, or again when a half-open probe fails. Returns true when it opened.
+    fn record_error(&mut self, breaker: &BreakerSettings) -> bool {
+        self.error_count += 1;
+        self.consecutive_errors += 1;
+        let probe_failed = self.probe_started_at.take().is_some();
+        let tripped = breaker.enabled()
+            && (probe_failed
+                || (self.breaker_opened_at.is_none() && self.consecutive_errors >= breaker.failure_threshold));
+        if tripped {
+            self.breaker_opened_at = Some(Instant::now());
+        }
+        tripped
     }
+
+    fn breaker_state(&self, breaker: &BreakerSettings, now: Instant) -> BreakerState {
+        match self.breaker_opened_at {
+            None => BreakerState::Closed,
+            Some(opened) if now.duration_since(opened) < breaker.cooldown => BreakerState::Open,
+            Some(_) => BreakerState::HalfOpen,
+        }
+    }
+
+    /// Whether a send may use the endpoint now. Half-open lets one probe through at a
+    /// time; a probe with no result after another cooldown (e.g. an aborted send) is replaced.
+    fn admit(&mut self, breaker: &BreakerSettings, now: Instant) -> bool {
+        match self.breaker_state(breaker, now) {
+            BreakerState::Closed => true,
+            BreakerState::Open => false,
+            BreakerState::HalfOpen => {
+                let probe_pending = self
+                    .probe_started_at
+                    .is_some_and(|started| now.duration_since(started) < breaker.cooldown);
+                if probe_pending {
+                    return false;
+                }
+                self.probe_started_at = Some(now);
+                true
+            }
+        }
+    }
+
+    fn is_healthy(&self) -> bool {
+        self.consecutive_errors < UNHEALTHY_AFTER_ERRORS
+    }
+
+    /// Ranking score in 0.0..=1.0: success rate discounted by average latency. An
+    /// endpoint without results scores 1.0 so it is tried (and measured) early.
+    fn score(&self) -> f64 {
+        if self.success_count + self.error_count == 0 {
+            return 1.0;
+        }
+        self.success_rate() / (1.0 + self.avg_latency_ms() / RANKING_LATENCY_SCALE_MS)
+    }
+}
+
+/// Circuit breaker settings; a zero threshold disables the breaker.
+#[derive(Debug, Clone, Copy)]
+struct BreakerSettings {
+    failure_threshold: u32,
+    cooldown: Duration,
+}
+
+impl BreakerSettings {
+    fn enabled(&self) -> bool {
+        self.failure_threshold > 0
+    }
+}
+
+/// Per-endpoint health shared between the RPC manager and the engines that gate on it.
+///
+/// An endpoint is unhealthy after `UNHEALTHY_AFTER_ERRORS` consecutive failures and
+/// healthy again after its next success. Endpoints without results count as healthy.
+///
+/// With a circuit breaker (`with_breaker`), an endpoint with that many consecutive
+/// failures is skipped by sends for a cooldown, then gets one probe send: success
+/// closes the breaker, failure opens it for another cooldown.
+#[derive(Debug)]
+pub struct RpcHealth {
+    endpoints: Vec<String>,
+    metrics: std::sync::RwLock<HashMap<String, EndpointMetrics>>,
+    breaker: BreakerSettings,
+}
+
+impl RpcHealth {
+    pub fn new(endpoints: Vec<String>) -> Self {
+        Self {
+            endpoints,
+            metrics: std::sync::RwLock::new(HashMap::new()),
+            breaker: BreakerSettings { failure_threshold: 0, cooldown: Duration::ZERO },
+        }
+    }
+
+    /// Open an endpoint's breaker after `failure_threshold` consecutive failures
+    /// (0 disables) and keep it open for `cooldown`.
+    pub fn with_breaker(mut self, failure_threshold: u32, cooldown: Duration) -> Self {
+        self.breaker = BreakerSettings { failure_threshold, cooldown };
+        self
+    }
+
+    pub fn record_success(&self, endpoint: &str, latency_ms: u64) {
+        self.update(endpoint, |m| m.record_success(latency_ms));
+    }
+
+    pub fn record_error(&self, endpoint: &str) {
+        let breaker = self.breaker;
+        let mut tripped = false;
+        self.update(endpoint, |m| tripped = m.record_error(&breaker));
+        if tripped {
+            metrics().increment_counter("rpc_breaker_opened_total");
+            warn!("RpcManager: circuit breaker open for {} ({:?} cooldown)", endpoint, breaker.cooldown);
+        }
+    }
+
+    /// Health probe results; unlike sends they never open or close the breaker.
+    pub fn record_probe_success(&self, endpoint: &str, latency_ms: u64) {
+        self.update(endpoint, |m| m.record_probe_success(latency_ms));
+    }
+
+    pub fn record_probe_error(&self, endpoint: &str) {
+        self.update(endpoint, EndpointMetrics::record_probe_error);
+    }
+
+    fn update(&self, endpoint: &str, f: impl FnOnce(&mut EndpointMetrics)) {
+        {
+            let mut metrics = self.metrics.write().unwrap_or_else(|poisoned| poisoned.into_inner());
+            f(metrics.entry(endpoint.to_string()).or_insert_with(EndpointMetrics::new));
+        }
+        metrics().set_gauge("rpc_healthy_endpoints", self.healthy_count() as u64);
+        let open = self
+            .breaker_states()
+            .iter()
+            .filter(|(_, state)| *state != BreakerState::Closed)
+            .count();
+        metrics().set_gauge("rpc_open_breakers", open as u64);
+    }
+
+    /// Breaker state of every configured endpoint, in configured order.
+    pub fn breaker_states(&self) -> Vec<(String, BreakerState)> {
+        let now = Instant::now();
+        let metrics = self.metrics.read().unwrap_or_else(|poisoned| poisoned.into_inner());
+        self.endpoints
+            .iter()
+            .map(|e| {
+                let state = metrics.get(e).map_or(BreakerState::Closed, |m| m.breaker_state(&self.breaker, now));
+                (e.clone(), state)
+            })
+            .collect()
+    }
+
+    /// Whether a send may use `endpoint` now; claims the probe of a half-open breaker.
+    pub fn admit(&self, endpoint: &str) -> bool {
+        let now = Instant::now();
+        let mut metrics = self.metrics.write().unwrap_or_else(|poisoned| poisoned.into_inner());
+        metrics.get_mut(endpoint).is_none_or(|m| m.admit(&self.breaker, now))
+    }
+
+    /// Number of configured endpoints currently healthy.
+    pub fn healthy_count(&self) -> usize {
+        let metrics = self.metrics.read().unwrap_or_else(|poisoned| poisoned.into_inner());
+        self.endpoints
+            .iter()
+            .filter(|e| metrics.get(*e).is_none_or(EndpointMetrics::is_healthy))
+            .count()
+    }
+
+    pub fn endpoint_count(&self) -> usize {
+        self.endpoints.len()
+    }
+
+    /// Fraction of configured endpoints currently healthy (1.0 with no endpoints).
+    pub fn healthy_fraction(&self) -> f64 {
+        if self.endpoints.is_empty() {
+            return 1.0;
+        }
+        self.healthy_count() as f64 / self.endpoints.len() as f64
+    }
+
+    /// Configured endpoints with their ranking score, best first. Ties keep the
+    /// configured order.
+    pub fn rankings(&self) -> Vec<(String, f64)> {
+        let metrics = self.metrics.read().unwrap_or_else(|poisoned| poisoned.into_inner());
+        let mut ranked: Vec<(String, f64)> = self
+            .endpoints
+            .iter()
+            .map(|e| (e.clone(), metrics.get(e).map_or(1.0, EndpointMetrics::score)))
+            .collect();
+        ranked.sort_by(|a, b| b.1.total_cmp(&a.1));
+        ranked
+    }
+}
+
+/// A transaction accepted by one of the broadcast endpoints.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SendOutcome {
+    pub signature: Signature,
+    /// Endpoint that accepted the transaction; confirmation should be polled there first.
+    pub endpoint: String,
+    /// Time from send to the endpoint's answer.
+    pub latency_ms: u64,
+    /// The endpoint reported the transaction as already processed (a resend of a landed tx).
+    pub was_duplicate: bool,
+    /// Jito bundle the transaction was sent in, when it went through a block engine.
+    pub bundle_id: Option<String>,
+}
+
+/// Result of waiting for a broadcast transaction to reach a commitment.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ConfirmationStatus {
+    /// Reached the requested commitment; landed in `slot`
+    Confirmed { slot: u64 },
+    /// Landed but failed on chain
+    Failed(String),
+    /// Not at the requested commitment before the timeout
+    TimedOut,
 }
 
 /// Trait for broadcasting transactions. Allows injecting mock implementations for tests.
 pub trait RpcBroadcaster: Send + Sync + std::fmt::Debug {
-    /// Broadcast the prepared VersionedTransaction objects; return first successful Signature or Err.
+    /// Broadcast the prepared VersionedTransaction objects; return the first accepted send or Err.
     fn send_on_many_rpc<'a>(
         &'a self,
         txs: Vec<VersionedTransaction>,
         correlation_id: Option<CorrelationId>,
-    ) -> Pin<Box<dyn Future<Output = Result<Signature>> + Send + 'a>>;
+    ) -> Pin<Box<dyn Future<Output = Result<SendOutcome>> + Send + 'a>>;
+
+    /// `send_on_many_rpc` for a sell. Sells close or reduce held positions, so they
+    /// are never held back by endpoint circuit breakers.
+    fn send_sell_on_many_rpc<'a>(
+        &'a self,
+        txs: Vec<VersionedTransaction>,
+        correlation_id: Option<CorrelationId>,
+    ) -> Pin<Box<dyn Future<Output = Result<SendOutcome>> + Send + 'a>> {
+        self.send_on_many_rpc(txs, correlation_id)
+    }
+
+    /// Whether a broadcast transaction has landed. Broadcasters that cannot check
+    /// treat every accepted send as landed.
+    fn is_landed<'a>(
+        &'a self,
+        _outcome: &'a SendOutcome,
+    ) -> Pin<Box<dyn Future<Output = Result<bool>> + Send + 'a>> {
+        Box::pin(async { Ok(true) })
+    }
+
+    /// Wait until `signature` reaches `commitment`, fails or `limit` passes.
+    /// Broadcasters that cannot check report every signature as confirmed.
+    fn confirm_signature<'a>(
+        &'a self,
+        _signature: &'a Signature,
+        _commitment: CommitmentConfig,
+        _limit: Duration,
+    ) -> Pin<Box<dyn Future<Output = Result<ConfirmationStatus>> + Send + 'a>> {
+        Box::pin(async { Ok(ConfirmationStatus::Confirmed { slot: 0 }) })
+    }
 }
 
 
@@ -122,6 +506,8 @@ pub struct RpcManager {
     client_pool: Arc<RwLock<HashMap<String, Arc<RpcClient>>>>,
     // Configuration for RPC operations
     config: Config,
+    // Per-endpoint health from send results and probes
+    health: Arc<RpcHealth>,
 }
 
 impl std::fmt::Debug for RpcManager {
@@ -136,6 +522,10 @@ impl std::fmt::Debug for RpcManager {
 impl RpcManager {
     pub fn new(endpoints: Vec<String>, config: Config) -> Self {
         Self { 
+            health: Arc::new(RpcHealth::new(endpoints.clone()).with_breaker(
+                config.rpc_breaker_failure_threshold,
+                Duration::from_millis(config.rpc_breaker_cooldown_ms),
+            )),
             endpoints,
             client_pool: Arc::new(RwLock::new(HashMap::new())),
             config,
@@ -168,6 +558,169 @@ impl RpcManager {
         client
     }
 
+    /// Endpoint health, fed by every send and by `run_health_probe`.
+    pub fn health(&self) -> Arc<RpcHealth> {
+        self.health.clone()
+    }
+
+    /// Endpoints in the order the next send uses them, with their scores.
+    pub async fn endpoint_rankings(&self) -> Vec<(String, f64)> {
+        self.health.rankings()
+    }
+
+    /// Breaker state of every endpoint, in configured order.
+    pub fn breaker_states(&self) -> Vec<(String, BreakerState)> {
+        self.health.breaker_states()
+    }
+
+    /// Pair `txs` with endpoints, best-ranked endpoint first, skipping endpoints whose
+    /// circuit breaker is open unless `bypass_breakers`.
+    fn ranked_jobs(&self, txs: Vec<VersionedTransaction>, bypass_breakers: bool) -> Vec<(String, VersionedTransaction)> {
+        let wanted = txs.len();
+        self.health
+            .rankings()
+            .into_iter()
+            .map(|(endpoint, _)| endpoint)
+            // `take` after the filter, so no half-open probe is claimed for a job never sent
+            .filter(|endpoint| bypass_breakers || self.health.admit(endpoint))
+            .take(wanted)
+            .zip(txs)
+            .collect()
+    }
+
+    /// Probe every endpoint once with `getHealth` and record the results.
+    pub async fn probe_health(&self) {
+        for endpoint in &self.endpoints {
+            let client = self.get_or_create_client(endpoint, CommitmentConfig::confirmed()).await;
+            let start = Instant::now();
+            match timeout(Duration::from_secs(self.config.rpc_timeout_sec), client.get_health()).await {
+                Ok(Ok(())) => self.health.record_probe_success(endpoint, start.elapsed().as_millis() as u64),
+                Ok(Err(e)) => {
+                    debug!("RpcManager: health probe on {} failed: {}", endpoint, e);
+                    self.health.record_probe_error(endpoint);
+                }
+                Err(_elapsed) => {
+                    debug!("RpcManager: health probe on {} timed out", endpoint);
+                    self.health.record_probe_error(endpoint);
+                }
+            }
+        }
+    }
+
+    /// Probe endpoints every `interval` until the task is aborted, so health can
+    /// recover while no transactions are being sent.
+    pub async fn run_health_probe(self: Arc<Self>, interval: Duration) {
+        let mut ticker = tokio::time::interval(interval);
+        loop {
+            ticker.tick().await;
+            self.probe_health().await;
+        }
+    }
+
+    /// Send config for `endpoint`, honoring per-endpoint overrides from `Config`.
+    pub fn send_config_for(&self, endpoint: &str) -> RpcSendTransactionConfig {
+        Self::build_send_config(
+            self.config
+                .endpoint_send_configs
+                .get(endpoint)
+                .copied()
+                .unwrap_or_default(),
+        )
+    }
+
+    fn build_send_config(endpoint_cfg: EndpointSendConfig) -> RpcSendTransactionConfig {
+        // Fix commitment mismatch - use Confirmed consistently
+        RpcSendTransactionConfig {
+            skip_preflight: endpoint_cfg.skip_preflight,
+            preflight_commitment: Some(CommitmentLevel::Confirmed),
+            max_retries: endpoint_cfg.max_retries,
+            ..Default::default()
+        }
+    }
+
+    /// Endpoints to poll for confirmation: the accepting endpoint first, then the rest.
+    pub fn confirmation_order(&self, accepted_endpoint: &str) -> Vec<String> {
+        let mut order = Vec::with_capacity(self.endpoints.len() + 1);
+        order.push(accepted_endpoint.to_string());
+        order.extend(self.endpoints.iter().filter(|e| *e != accepted_endpoint).cloned());
+        order
+    }
+
+    /// Whether a broadcast transaction has landed.
+    ///
+    /// Asks the accepting endpoint first and falls back to the others only when an
+    /// endpoint cannot be reached. A transaction that landed but failed is an error.
+    pub async fn is_landed(&self, outcome: &SendOutcome) -> Result<bool> {
+        let mut last_err = None;
+        for endpoint in self.confirmation_order(&outcome.endpoint) {
+            let client = self.get_or_create_client(&endpoint, CommitmentConfig::confirmed()).await;
+            let status = timeout(
+                Duration::from_secs(self.config.rpc_timeout_sec),
+                client.get_signature_status(&outcome.signature),
+            )
+            .await;
+            match status {
+                Ok(Ok(Some(Ok(())))) => return Ok(true),
+                Ok(Ok(Some(Err(e)))) => {
+                    return Err(anyhow!("transaction {} failed: {}", outcome.signature, e))
+                }
+                Ok(Ok(None)) => return Ok(false),
+                Ok(Err(e)) => {
+                    debug!("RpcManager: status check on {} failed: {}", endpoint, e);
+                    last_err = Some(anyhow!(e));
+                }
+                Err(_elapsed) => {
+                    debug!("RpcManager: status check on {} timed out", endpoint);
+                    last_err = Some(anyhow!("RPC status timeout"));
+                }
+            }
+        }
+        Err(last_err.unwrap_or_else(|| anyhow!("no endpoints to confirm on")))
+    }
+
+    /// Poll `getSignatureStatuses` until `sig` reaches `commitment`, fails on chain, or
+    /// `limit` passes. Polls rotate over the endpoints so one lagging node cannot hold
+    /// the confirmation back; failed polls only cost time.
+    pub async fn confirm_signature(
+        &self,
+        sig: &Signature,
+        commitment: CommitmentConfig,
+        limit: Duration,
+    ) -> Result<ConfirmationStatus> {
+        if self.endpoints.is_empty() {
+            return Err(anyhow!("no endpoints to confirm on"));
+        }
+        let deadline = Instant::now() + limit;
+        for endpoint in self.endpoints.iter().cycle() {
+            let client = self.get_or_create_client(endpoint, commitment).await;
+            let statuses = timeout(
+                Duration::from_secs(self.config.rpc_timeout_sec),
+                client.get_signature_statuses(&[*sig]),
+            )
+            .await;
+            match statuses {
+                Ok(Ok(response)) => match response.value.into_iter().next().flatten() {
+                    Some(status) if status.err.is_some() => {
+                        let err = status.err.map(|e| e.to_string()).unwrap_or_default();
+                        return Ok(ConfirmationStatus::Failed(err));
+                    }
+                    Some(status) if status.satisfies_commitment(commitment) => {
+                        return Ok(ConfirmationStatus::Confirmed { slot: status.slot });
+                    }
+                    status => debug!("RpcManager: {} not at {:?} yet: {:?}", sig, commitment.commitment, status),
+                },
+                Ok(Err(e)) => debug!("RpcManager: status poll on {} failed: {}", endpoint, e),
+                Err(_elapsed) => debug!("RpcManager: status poll on {} timed out", endpoint),
+            }
+            let now = Instant::now();
+            if now >= deadline {
+                break;
+            }
+            sleep(CONFIRM_POLL_INTERVAL.min(deadline - now)).await;
+        }
+        Ok(ConfirmationStatus::TimedOut)
+    }
+
     /// Check if an error indicates a fatal condition that should trigger early cancellation
     fn is_fatal_error_type(error_msg: &str) -> bool {
         // Simple implementation - consider some common fatal errors
@@ -175,6 +728,7 @@ impl RpcManager {
             || error_msg.contains("account not found")
             || error_msg.contains("invalid signature")
             || error_msg.contains("transaction too large")
+            || mentions_frozen_account(error_msg)
     }
 }
 
@@ -184,6 +738,7 @@ impl Clone for RpcManager {
             endpoints: self.endpoints.clone(),
             client_pool: self.client_pool.clone(),
             config: self.config.clone(),
+            health: self.health.clone(),
         }
     }
 }
@@ -193,101 +748,715 @@ impl RpcBroadcaster for RpcManager {
         &'a self,
         txs: Vec<VersionedTransaction>,
         _correlation_id: Option<CorrelationId>,
-    ) -> Pin<Box<dyn Future<Output = Result<Signature>> + Send + 'a>> {
-        Box::pin(async move {
-            if self.endpoints.is_empty() || txs.is_empty() {
-                return Err(anyhow!(
-                    "send_on_many_rpc: no endpoints or no transactions to send (endpoints={}, txs={})",
-                    self.endpoints.len(),
-                    txs.len()
-                ));
-            }
+    ) -> Pin<Box<dyn Future<Output = Result<SendOutcome>> + Send + 'a>> {
+        Box::pin(self.send_ranked(txs, false))
+    }
+
+    fn send_sell_on_many_rpc<'a>(
+        &'a self,
+        txs: Vec<VersionedTransaction>,
+        _correlation_id: Option<CorrelationId>,
+    ) -> Pin<Box<dyn Future<Output = Result<SendOutcome>> + Send + 'a>> {
+        Box::pin(self.send_ranked(txs, true))
+    }
 
-            let timeout_duration = Duration::from_secs(self.config.rpc_timeout_sec);
-            
-            // Fix commitment mismatch - use Confirmed consistently
-            let send_cfg = RpcSendTransactionConfig {
-                skip_preflight: true,
-                preflight_commitment: Some(CommitmentLevel::Confirmed),
-                max_retries: Some(3),
-                ..Default::default()
-            };
-
-            let mut set: JoinSet<Result<Signature>> = JoinSet::new();
-            let mut fatal_errors = 0;
-
-            // Simple pairwise broadcast for now (minimal implementation)
-            let num_tasks = std::cmp::min(self.endpoints.len(), txs.len());
-            
-            for i in 0..num_tasks {
-                let endpoint = self.endpoints[i].clone();
-                let tx = txs[i].clone();
-                let client_pool = self.client_pool.clone();
-                let commitment = CommitmentConfig::confirmed();
+    fn is_landed<'a>(
+        &'a self,
+        outcome: &'a SendOutcome,
+    ) -> Pin<Box<dyn Future<Output = Result<bool>> + Send + 'a>> {
+        Box::pin(RpcManager::is_landed(self, outcome))
+    }
 
-                set.spawn(async move {
-                    // Use the pooled client instead of creating a new one
-                    let rpc_manager = RpcManager {
-                        endpoints: vec![endpoint.clone()],
-                        client_pool,
-                        config: Config::default(), // Use default config for spawned tasks
-                    };
-                    let client = rpc_manager.get_or_create_client(&endpoint, commitment).await;
-                    debug!("RpcManager: sending tx on endpoint[{}]: {}", i, endpoint);
-
-                    let start_time = Instant::now();
-                    let send_fut = client.send_transaction_with_config(&tx, send_cfg);
-                    match timeout(timeout_duration, send_fut).await {
-                        Ok(Ok(sig)) => {
-                            let latency_ms = start_time.elapsed().as_millis() as u64;
-                            info!("RpcManager: success on {}: {} ({}ms)", endpoint, sig, latency_ms);
-                            Ok(sig)
-                        }
-                        Ok(Err(e)) => {
-                            let error_msg = e.to_string();
-                            warn!("RpcManager: endpoint {} failed: {}", endpoint, error_msg);
-                            Err(anyhow!(e).context("RPC failed"))
-                        }
-                        Err(_elapsed) => {
-                            warn!("RpcManager: endpoint {} timed out after {:?}", endpoint, timeout_duration);
-                            Err(anyhow!("RPC send timeout"))
-                        }
+    fn confirm_signature<'a>(
+        &'a self,
+        signature: &'a Signature,
+        commitment: CommitmentConfig,
+        limit: Duration,
+    ) -> Pin<Box<dyn Future<Output = Result<ConfirmationStatus>> + Send + 'a>> {
+        Box::pin(RpcManager::confirm_signature(self, signature, commitment, limit))
+    }
+}
+
+impl RpcManager {
+    /// Send `txs` over the best-ranked endpoints; `bypass_breakers` also uses
+    /// endpoints whose circuit breaker is open.
+    async fn send_ranked(&self, txs: Vec<VersionedTransaction>, bypass_breakers: bool) -> Result<SendOutcome> {
+        if self.endpoints.is_empty() || txs.is_empty() {
+            return Err(anyhow!(
+                "send_on_many_rpc: no endpoints or no transactions to send (endpoints={}, txs={})",
+                self.endpoints.len(),
+                txs.len()
+            ));
+        }
+
+        let timeout_duration = Duration::from_secs(self.config.rpc_timeout_sec);
+        let send_cfgs: HashMap<String, RpcSendTransactionConfig> = self
+            .endpoints
+            .iter()
+            .map(|endpoint| (endpoint.clone(), self.send_config_for(endpoint)))
+            .collect();
+
+        let jobs = self.ranked_jobs(txs, bypass_breakers);
+        if jobs.is_empty() {
+            metrics().increment_counter("rpc_all_breakers_open_total");
+            return Err(anyhow!("RpcManager: all sends failed: every endpoint's circuit breaker is open"));
+        }
+
+        let client_pool = self.client_pool.clone();
+        let health = self.health.clone();
+        let send = move |endpoint: String, tx: VersionedTransaction| -> SendFuture {
+            let client_pool = client_pool.clone();
+            let health = health.clone();
+            let send_cfg = send_cfgs.get(&endpoint).copied().unwrap_or_default();
+            Box::pin(async move {
+                // Use the pooled client instead of creating a new one
+                let rpc_manager = RpcManager {
+                    endpoints: vec![endpoint.clone()],
+                    client_pool,
+                    config: Config::default(), // Use default config for spawned tasks
+                    health: health.clone(),
+                };
+                let client = rpc_manager
+                    .get_or_create_client(&endpoint, CommitmentConfig::confirmed())
+                    .await;
+                debug!("RpcManager: sending tx on endpoint {}", endpoint);
+
+                let start_time = Instant::now();
+                let send_fut = client.send_transaction_with_config(&tx, send_cfg);
+                let result = match timeout(timeout_duration, send_fut).await {
+                    Ok(result) => {
+                        let latency_ms = start_time.elapsed().as_millis() as u64;
+                        accept_send_result(&endpoint, &tx, result, latency_ms)
+                    }
+                    Err(_elapsed) => {
+                        warn!("RpcManager: endpoint {} timed out after {:?}", endpoint, timeout_duration);
+                        Err(anyhow::Error::new(SendTimeout))
                     }
+                };
+                record_send_result(&health, &endpoint, &result);
+                result
+            })
+        };
+
+        dispatch_sends(
+            self.config.send_strategy,
+            jobs,
+            self.config.early_cancel_threshold,
+            send,
+        )
+        .await
+    }
+}
+
+/// Successful answer from a single endpoint send.
+#[derive(Debug, Clone, Copy)]
+struct EndpointAccept {
+    signature: Signature,
+    latency_ms: u64,
+    was_duplicate: bool,
+}
+
+impl EndpointAccept {
+    fn into_outcome(self, endpoint: String) -> SendOutcome {
+        SendOutcome {
+            signature: self.signature,
+            endpoint,
+            latency_ms: self.latency_ms,
+            was_duplicate: self.was_duplicate,
+            bundle_id: None,
+        }
+    }
+}
+
+/// Interpret an endpoint's answer to a send.
+///
+/// "Already processed" means the tx landed earlier (e.g. a resend), so it counts as
+/// accepted and is flagged as a duplicate.
+fn accept_send_result(
+    endpoint: &str,
+    tx: &VersionedTransaction,
+    result: std::result::Result<Signature, ClientError>,
+    latency_ms: u64,
+) -> Result<EndpointAccept> {
+    match result {
+        Ok(signature) => {
+            info!("RpcManager: success on {}: {} ({}ms)", endpoint, signature, latency_ms);
+            Ok(EndpointAccept { signature, latency_ms, was_duplicate: false })
+        }
+        Err(e) if classify_rpc_error(&e) == RpcErrorType::AlreadyProcessed => {
+            let signature = tx.signatures.first().copied().unwrap_or_default();
+            info!("RpcManager: {} already processed {} ({}ms)", endpoint, signature, latency_ms);
+            metrics().increment_counter("rpc_already_processed_total");
+            Ok(EndpointAccept { signature, latency_ms, was_duplicate: true })
+        }
+        Err(e) => {
+            warn!("RpcManager: endpoint {} failed: {}", endpoint, e);
+            Err(anyhow!(e).context("RPC failed"))
+        }
+    }
+}
+
+/// Feed an endpoint's send result into its health and circuit breaker.
+fn record_send_result(health: &RpcHealth, endpoint: &str, result: &Result<EndpointAccept>) {
+    match result {
+        Ok(accept) => health.record_success(endpoint, accept.latency_ms),
+        Err(e) if is_endpoint_failure(e) => health.record_error(endpoint),
+        // Rejections of the transaction itself say nothing about the endpoint
+        Err(_) => {}
+    }
+}
+
+/// Future for a single endpoint send.
+type SendFuture = Pin<Box<dyn Future<Output = Result<EndpointAccept>> + Send>>;
+
+/// Failure bookkeeping across endpoint sends, turned into the final error.
+#[derive(Debug, Default)]
+struct SendFailures {
+    fatal_errors: usize,
+    version_errors: usize,
+    frozen_errors: usize,
+    blockhash_errors: usize,
+}
+
+impl SendFailures {
+    fn record(&mut self, error: &anyhow::Error) {
+        if is_unsupported_version_error(error) {
+            self.version_errors += 1;
+        }
+        if is_frozen_account_error(error) {
+            self.frozen_errors += 1;
+        }
+        if is_blockhash_not_found_error(error) {
+            self.blockhash_errors += 1;
+        }
+        if RpcManager::is_fatal_error_type(&error.to_string()) {
+            self.fatal_errors += 1;
+        }
+        debug!("RpcManager: task returned error: {:?}", error);
+    }
+
+    fn into_error(self) -> anyhow::Error {
+        let failure = if self.frozen_errors > 0 {
+            BroadcastFailure::AccountFrozen(self.frozen_errors)
+        } else if self.version_errors > 0 {
+            BroadcastFailure::UnsupportedVersion(self.version_errors)
+        } else if self.blockhash_errors > 0 {
+            BroadcastFailure::BlockhashNotFound(self.blockhash_errors)
+        } else {
+            BroadcastFailure::Other(self.fatal_errors)
+        };
+        anyhow::Error::new(failure)
+    }
+}
+
+/// Run `(endpoint, tx)` jobs with the given strategy; first success wins and is
+/// reported together with its endpoint.
+///
+/// Spray fans every job out at once on a `JoinSet`; sequential tries them in order
+/// and stops at the first success. Both stop early once `early_cancel_threshold`
+/// fatal errors were seen.
+async fn dispatch_sends<F>(
+    strategy: SendStrategy,
+    jobs: Vec<(String, VersionedTransaction)>,
+    early_cancel_threshold: usize,
+    send: F,
+) -> Result<SendOutcome>
+where
+    F: Fn(String, VersionedTransaction) -> SendFuture,
+{
+    let mut failures = SendFailures::default();
+
+    match strategy {
+        SendStrategy::Spray => {
+            let mut set: JoinSet<Result<SendOutcome>> = JoinSet::new();
+            for (endpoint, tx) in jobs {
+                let send_fut = send(endpoint.clone(), tx);
+                set.spawn(async move {
+                    Ok(send_fut.await?.into_outcome(endpoint))
                 });
             }
 
             // Wait for results with early cancellation
             while let Some(join_res) = set.join_next().await {
                 match join_res {
-                    Ok(Ok(sig)) => {
+                    Ok(Ok(outcome)) => {
                         set.abort_all();
-                        return Ok(sig);
+                        return Ok(outcome);
                     }
                     Ok(Err(e)) => {
-                        let error_str = e.to_string();
-                        if Self::is_fatal_error_type(&error_str) {
-                            fatal_errors += 1;
-                            debug!("RpcManager: fatal error count: {}/{}", fatal_errors, self.config.early_cancel_threshold);
-                            
-                            // Early cancellation if too many fatal errors
-                            if fatal_errors >= self.config.early_cancel_threshold {
-                                warn!("RpcManager: cancelling remaining tasks due to {} fatal errors", fatal_errors);
-                                set.abort_all();
-                                break;
-                            }
+                        failures.record(&e);
+                        if failures.fatal_errors >= early_cancel_threshold {
+                            warn!("RpcManager: cancelling remaining tasks due to {} fatal errors", failures.fatal_errors);
+                            set.abort_all();
+                            break;
                         }
-                        debug!("RpcManager: task returned error: {:?}", e);
                     }
                     Err(join_err) => {
                         warn!("RpcManager: task join error: {}", join_err);
                     }
                 }
             }
+        }
+        SendStrategy::Sequential => {
+            for (endpoint, tx) in jobs {
+                match send(endpoint.clone(), tx).await {
+                    Ok(accept) => return Ok(accept.into_outcome(endpoint)),
+                    Err(e) => {
+                        failures.record(&e);
+                        if failures.fatal_errors >= early_cancel_threshold {
+                            warn!("RpcManager: stopping sequential send after {} fatal errors", failures.fatal_errors);
+                            break;
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    Err(failures.into_error())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use solana_sdk::{message::Message, pubkey::Pubkey, transaction::Transaction};
+    use std::sync::Mutex as StdMutex;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    fn dummy_tx() -> VersionedTransaction {
+        let payer = Pubkey::new_unique();
+        VersionedTransaction::from(Transaction::new_unsigned(Message::new(&[], Some(&payer))))
+    }
+
+    fn jobs(endpoints: &[&str]) -> Vec<(String, VersionedTransaction)> {
+        endpoints.iter().map(|e| (e.to_string(), dummy_tx())).collect()
+    }
+
+    /// Fake endpoint send: "ok*" endpoints succeed after `delay_ms`, others fail.
+    fn fake_send(
+        calls: Arc<StdMutex<Vec<String>>>,
+        delay_ms: u64,
+    ) -> impl Fn(String, VersionedTransaction) -> SendFuture {
+        move |endpoint, _tx| {
+            let calls = calls.clone();
+            Box::pin(async move {
+                calls.lock().unwrap().push(endpoint.clone());
+                tokio::time::sleep(Duration::from_millis(delay_ms)).await;
+                if endpoint.starts_with("ok") {
+                    Ok(EndpointAccept {
+                        signature: Signature::from([endpoint.len() as u8; 64]),
+                        latency_ms: delay_ms,
+                        was_duplicate: false,
+                    })
+                } else {
+                    Err(anyhow!("connection refused by {}", endpoint))
+                }
+            })
+        }
+    }
 
-            Err(anyhow!(
-                "RpcManager: all sends failed (fatal_errors: {})", 
-                fatal_errors
-            ))
+    #[test]
+    fn endpoint_with_preflight_gets_its_own_send_config() {
+        let mut config = Config::default();
+        config.endpoint_send_configs.insert(
+            "https://preflight.example".to_string(),
+            EndpointSendConfig { skip_preflight: false, max_retries: Some(0) },
+        );
+        let manager = RpcManager::new(
+            vec!["https://preflight.example".to_string(), "https://fast.example".to_string()],
+            config,
+        );
+
+        let preflight = manager.send_config_for("https://preflight.example");
+        assert!(!preflight.skip_preflight);
+        assert_eq!(preflight.max_retries, Some(0));
+
+        let default = manager.send_config_for("https://fast.example");
+        assert!(default.skip_preflight);
+        assert_eq!(default.max_retries, Some(3));
+        assert_eq!(default.preflight_commitment, Some(CommitmentLevel::Confirmed));
+    }
+
+    #[tokio::test]
+    async fn spray_sends_to_every_endpoint_at_once() {
+        let calls = Arc::new(StdMutex::new(Vec::new()));
+        let outcome = dispatch_sends(
+            SendStrategy::Spray,
+            jobs(&["bad-1", "ok-2", "bad-3"]),
+            usize::MAX,
+            fake_send(calls.clone(), 20),
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(outcome.signature, Signature::from([4u8; 64]));
+        // All sends started before the first one completed
+        assert_eq!(calls.lock().unwrap().len(), 3);
+    }
+
+    #[tokio::test]
+    async fn sequential_stops_at_first_success() {
+        let calls = Arc::new(StdMutex::new(Vec::new()));
+        let outcome = dispatch_sends(
+            SendStrategy::Sequential,
+            jobs(&["bad-1", "ok-22", "ok-3", "bad-4"]),
+            usize::MAX,
+            fake_send(calls.clone(), 1),
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(outcome.signature, Signature::from([5u8; 64]));
+        assert_eq!(*calls.lock().unwrap(), vec!["bad-1".to_string(), "ok-22".to_string()]);
+    }
+
+    #[tokio::test]
+    async fn accepting_endpoint_is_reported_and_polled_first() {
+        for strategy in [SendStrategy::Spray, SendStrategy::Sequential] {
+            let calls = Arc::new(StdMutex::new(Vec::new()));
+            let outcome = dispatch_sends(
+                strategy,
+                jobs(&["bad-1", "ok-accepting", "bad-3"]),
+                usize::MAX,
+                fake_send(calls, 1),
+            )
+            .await
+            .unwrap();
+            assert_eq!(outcome.endpoint, "ok-accepting", "{:?}", strategy);
+            assert_eq!(outcome.latency_ms, 1);
+            assert!(!outcome.was_duplicate);
+        }
+
+        let manager = RpcManager::new(
+            vec!["bad-1".to_string(), "ok-accepting".to_string(), "bad-3".to_string()],
+            Config::default(),
+        );
+        assert_eq!(
+            manager.confirmation_order("ok-accepting"),
+            vec!["ok-accepting".to_string(), "bad-1".to_string(), "bad-3".to_string()]
+        );
+    }
+
+    #[test]
+    fn already_processed_answer_counts_as_duplicate_accept() {
+        let tx = dummy_tx();
+        let already_processed = ClientError::from(ClientErrorKind::RpcError(RpcError::RpcResponseError {
+            code: -32002,
+            message: "Transaction simulation failed: This transaction has already been processed".to_string(),
+            data: solana_client::rpc_request::RpcResponseErrorData::Empty,
+        }));
+
+        let accept = accept_send_result("https://a.example", &tx, Err(already_processed), 12).unwrap();
+        assert!(accept.was_duplicate);
+        assert_eq!(accept.signature, tx.signatures[0]);
+        assert_eq!(accept.into_outcome("https://a.example".to_string()).latency_ms, 12);
+
+        let fresh = accept_send_result("https://a.example", &tx, Ok(Signature::from([1u8; 64])), 5).unwrap();
+        assert!(!fresh.was_duplicate);
+
+        let refused = ClientError::from(ClientErrorKind::Custom("connection refused".to_string()));
+        assert!(accept_send_result("https://a.example", &tx, Err(refused), 5).is_err());
+    }
+
+    #[tokio::test]
+    async fn both_strategies_fail_when_every_endpoint_fails() {
+        for strategy in [SendStrategy::Spray, SendStrategy::Sequential] {
+            let calls = Arc::new(StdMutex::new(Vec::new()));
+            let err = dispatch_sends(strategy, jobs(&["bad-1", "bad-2"]), usize::MAX, fake_send(calls.clone(), 1))
+                .await
+                .unwrap_err();
+
+            assert!(err.to_string().contains("all sends failed"), "{:?}: {}", strategy, err);
+            assert_eq!(calls.lock().unwrap().len(), 2);
+        }
+    }
+
+    #[tokio::test]
+    async fn frozen_account_preflight_failure_is_classified() {
+        use solana_client::rpc_request::RpcResponseErrorData;
+        use solana_client::rpc_response::RpcSimulateTransactionResult;
+
+        let frozen = || {
+            ClientError::from(ClientErrorKind::RpcError(RpcError::RpcResponseError {
+                code: -32002,
+                message: "Transaction simulation failed: Error processing Instruction 2: custom program error: 0x11"
+                    .to_string(),
+                data: RpcResponseErrorData::SendTransactionPreflightFailure(
+                    serde_json::from_value::<RpcSimulateTransactionResult>(serde_json::json!({
+                        "logs": [
+                            "Program TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA invoke [2]",
+                            "Program log: Error: Account is frozen",
+                        ]
+                    }))
+                    .unwrap(),
+                ),
+            }))
+        };
+        assert_eq!(classify_rpc_error(&frozen()), RpcErrorType::AccountFrozen);
+
+        let tx = dummy_tx();
+        let send = move |endpoint: String, _tx: VersionedTransaction| -> SendFuture {
+            let result = accept_send_result(&endpoint, &tx, Err(frozen()), 3);
+            Box::pin(async move { result })
+        };
+        let err = dispatch_sends(SendStrategy::Spray, jobs(&["a", "b"]), usize::MAX, send).await.unwrap_err();
+        assert!(is_frozen_account_error(&err), "{}", err);
+    }
+
+    #[tokio::test]
+    async fn blockhash_not_found_surfaces_from_all_failed_sends() {
+        let stale = || {
+            ClientError::from(ClientErrorKind::RpcError(RpcError::RpcResponseError {
+                code: -32002,
+                message: "Transaction simulation failed: Blockhash not found".to_string(),
+                data: solana_client::rpc_request::RpcResponseErrorData::Empty,
+            }))
+        };
+        assert_eq!(classify_rpc_error(&stale()), RpcErrorType::BlockhashNotFound);
+
+        let tx = dummy_tx();
+        let send = move |endpoint: String, _tx: VersionedTransaction| -> SendFuture {
+            let result = accept_send_result(&endpoint, &tx, Err(stale()), 3);
+            Box::pin(async move { result })
+        };
+        let err = dispatch_sends(SendStrategy::Sequential, jobs(&["a", "b"]), usize::MAX, send).await.unwrap_err();
+        assert!(is_blockhash_not_found_error(&err), "{}", err);
+        assert_eq!(err.downcast_ref::<BroadcastFailure>(), Some(&BroadcastFailure::BlockhashNotFound(2)));
+        assert!(err.to_string().contains("all sends failed"), "{}", err);
+        // Classified by type: a look-alike message is not a stale blockhash
+        assert!(!is_blockhash_not_found_error(&anyhow!("note: blockhash not found in cache")));
+    }
+
+    #[tokio::test]
+    async fn rate_limited_endpoint_trips_breaker_then_recovers_after_cooldown() {
+        let manager = RpcManager::new(
+            vec!["limited".to_string(), "ok".to_string()],
+            Config { rpc_breaker_failure_threshold: 3, rpc_breaker_cooldown_ms: 50, ..Config::default() },
+        );
+        let health = manager.health();
+        let rate_limited = || {
+            ClientError::from(ClientErrorKind::RpcError(RpcError::RpcResponseError {
+                code: 429,
+                message: "Too many requests for a specific RPC call".to_string(),
+                data: solana_client::rpc_request::RpcResponseErrorData::Empty,
+            }))
+        };
+        assert_eq!(classify_rpc_error(&rate_limited()), RpcErrorType::RateLimited);
+        let tx = dummy_tx();
+        let fail = |endpoint: &str| {
+            record_send_result(&health, endpoint, &accept_send_result(endpoint, &tx, Err(rate_limited()), 1));
+        };
+        let job_endpoints = |manager: &RpcManager| -> Vec<String> {
+            manager.ranked_jobs(vec![dummy_tx(), dummy_tx()], false).into_iter().map(|(e, _)| e).collect()
+        };
+
+        // Below the threshold the endpoint is still used
+        fail("limited");
+        fail("limited");
+        assert_eq!(manager.breaker_states()[0].1, BreakerState::Closed);
+        assert_eq!(job_endpoints(&manager).len(), 2);
+
+        // Third consecutive rate limit opens the breaker: sends skip the endpoint
+        fail("limited");
+        assert_eq!(
+            manager.breaker_states(),
+            vec![("limited".to_string(), BreakerState::Open), ("ok".to_string(), BreakerState::Closed)]
+        );
+        assert_eq!(job_endpoints(&manager), vec!["ok".to_string()]);
+
+        // After the cooldown one probe is let through, not more
+        tokio::time::sleep(Duration::from_millis(60)).await;
+        assert_eq!(manager.breaker_states()[0].1, BreakerState::HalfOpen);
+        assert!(job_endpoints(&manager).contains(&"limited".to_string()));
+        assert_eq!(job_endpoints(&manager), vec!["ok".to_string()]);
+
+        // A failed probe reopens it at once; a successful one closes it
+        fail("limited");
+        assert_eq!(manager.breaker_states()[0].1, BreakerState::Open);
+        tokio::time::sleep(Duration::from_millis(60)).await;
+        assert!(job_endpoints(&manager).contains(&"limited".to_string()));
+        health.record_success("limited", 5);
+        assert_eq!(manager.breaker_states()[0].1, BreakerState::Closed);
+        assert_eq!(job_endpoints(&manager).len(), 2);
+    }
+
+    #[tokio::test]
+    async fn health_probes_leave_an_open_breaker_open() {
+        let manager = RpcManager::new(
+            vec!["limited".to_string()],
+            Config { rpc_breaker_failure_threshold: 3, rpc_breaker_cooldown_ms: 60_000, ..Config::default() },
+        );
+        // getHealth answers fine while sendTransaction is rate-limited
+        let mut mocks = solana_client::rpc_client::Mocks::default();
+        mocks.insert(solana_client::rpc_request::RpcRequest::GetHealth, serde_json::json!("ok"));
+        let client = RpcClient::new_mock_with_mocks("succeeds".to_string(), mocks);
+        manager.client_pool.write().await.insert("limited".to_string(), Arc::new(client));
+        let health = manager.health();
+        let tx = dummy_tx();
+        let io = || ClientError::from(ClientErrorKind::Io(std::io::Error::other("connection reset")));
+        for _ in 0..3 {
+            record_send_result(&health, "limited", &accept_send_result("limited", &tx, Err(io()), 1));
+        }
+        assert_eq!(health.healthy_count(), 0);
+        assert_eq!(manager.breaker_states()[0].1, BreakerState::Open);
+
+        manager.probe_health().await;
+        assert_eq!(health.healthy_count(), 1);
+        assert_eq!(manager.breaker_states()[0].1, BreakerState::Open);
+        assert!(manager.ranked_jobs(vec![dummy_tx()], false).is_empty());
+
+        // Nor does a failed probe trip a closed one
+        let closed = RpcHealth::new(vec!["a".to_string()]).with_breaker(1, Duration::from_secs(60));
+        closed.record_probe_error("a");
+        assert_eq!(closed.breaker_states()[0].1, BreakerState::Closed);
+    }
+
+    #[test]
+    fn rejected_transactions_do_not_trip_the_breaker_and_sells_bypass_it() {
+        let manager = RpcManager::new(
+            vec!["a".to_string()],
+            Config { rpc_breaker_failure_threshold: 2, ..Config::default() },
+        );
+        let health = manager.health();
+        let tx = dummy_tx();
+        let rejected = |message: &str| {
+            ClientError::from(ClientErrorKind::RpcError(RpcError::RpcResponseError {
+                code: -32002,
+                message: message.to_string(),
+                data: solana_client::rpc_request::RpcResponseErrorData::Empty,
+            }))
+        };
+        for message in ["Transaction simulation failed: slippage exceeded", "Blockhash not found"] {
+            for _ in 0..3 {
+                record_send_result(&health, "a", &accept_send_result("a", &tx, Err(rejected(message)), 1));
+            }
+        }
+        assert_eq!(manager.breaker_states()[0].1, BreakerState::Closed);
+
+        // Timeouts and transport failures do count
+        record_send_result(&health, "a", &Err(anyhow::Error::new(SendTimeout)));
+        let io = ClientError::from(ClientErrorKind::Io(std::io::Error::other("connection reset")));
+        record_send_result(&health, "a", &accept_send_result("a", &tx, Err(io), 1));
+        assert_eq!(manager.breaker_states()[0].1, BreakerState::Open);
+        assert!(manager.ranked_jobs(vec![dummy_tx()], false).is_empty());
+        assert_eq!(manager.ranked_jobs(vec![dummy_tx()], true).len(), 1);
+    }
+
+    #[test]
+    fn zero_threshold_disables_breaker() {
+        let health = RpcHealth::new(vec!["a".to_string()]);
+        for _ in 0..10 {
+            health.record_error("a");
+        }
+        assert_eq!(health.breaker_states(), vec![("a".to_string(), BreakerState::Closed)]);
+        assert!(health.admit("a"));
+    }
+
+    /// Manager whose only endpoint answers status polls with `statuses`, in order.
+    async fn manager_with_statuses(statuses: Vec<serde_json::Value>) -> RpcManager {
+        use solana_client::rpc_request::RpcRequest;
+        let manager = RpcManager::new(vec!["mock".to_string()], Config::default());
+        let mocks = statuses.into_iter().map(|status| {
+            let response = serde_json::json!({ "context": { "slot": 1 }, "value": [status] });
+            (RpcRequest::GetSignatureStatuses, response)
+        });
+        let client = RpcClient::new_mock_with_mocks_map("succeeds".to_string(), mocks.collect());
+        manager.client_pool.write().await.insert("mock".to_string(), Arc::new(client));
+        manager
+    }
+
+    fn status(slot: u64, commitment: &str, err: serde_json::Value) -> serde_json::Value {
+        serde_json::json!({
+            "slot": slot,
+            "confirmations": null,
+            "status": if err.is_null() { serde_json::json!({ "Ok": null }) } else { serde_json::json!({ "Err": err }) },
+            "err": err,
+            "confirmationStatus": commitment,
         })
     }
-}
\ No newline at end of file
+
+    #[tokio::test]
+    async fn confirm_signature_polls_until_commitment_is_reached() {
+        let manager = manager_with_statuses(vec![
+            serde_json::Value::Null,
+            status(7, "processed", serde_json::Value::Null),
+            status(7, "confirmed", serde_json::Value::Null),
+        ])
+        .await;
+
+        let result = manager
+            .confirm_signature(&Signature::default(), CommitmentConfig::confirmed(), Duration::from_secs(5))
+            .await
+            .unwrap();
+
+        assert_eq!(result, ConfirmationStatus::Confirmed { slot: 7 });
+    }
+
+    #[tokio::test]
+    async fn confirm_signature_reports_on_chain_failure() {
+        let err = serde_json::json!({ "InstructionError": [0, { "Custom": 6001 }] });
+        let manager = manager_with_statuses(vec![status(9, "processed", err)]).await;
+
+        let result = manager
+            .confirm_signature(&Signature::default(), CommitmentConfig::confirmed(), Duration::from_secs(5))
+            .await
+            .unwrap();
+
+        assert!(matches!(result, ConfirmationStatus::Failed(e) if e.contains("custom program error")));
+    }
+
+    #[tokio::test]
+    async fn confirm_signature_times_out_while_pending() {
+        let manager = manager_with_statuses(vec![serde_json::Value::Null; 8]).await;
+
+        let result = manager
+            .confirm_signature(&Signature::default(), CommitmentConfig::finalized(), Duration::from_millis(300))
+            .await
+            .unwrap();
+
+        assert_eq!(result, ConfirmationStatus::TimedOut);
+    }
+
+    #[tokio::test]
+    async fn fast_endpoint_is_tried_before_a_failing_one() {
+        let manager = RpcManager::new(
+            vec!["bad-failing".to_string(), "ok-fast".to_string(), "ok-new".to_string()],
+            Config::default(),
+        );
+        let health = manager.health();
+        for _ in 0..5 {
+            health.record_error("bad-failing");
+            health.record_success("ok-fast", 20);
+        }
+
+        let rankings = manager.endpoint_rankings().await;
+        let order: Vec<&str> = rankings.iter().map(|(e, _)| e.as_str()).collect();
+        // Never-measured endpoints start optimistic so they get probed
+        assert_eq!(order, vec!["ok-new", "ok-fast", "bad-failing"]);
+        assert_eq!(rankings[2].1, 0.0);
+
+        health.record_success("ok-new", 900);
+        let calls = Arc::new(StdMutex::new(Vec::new()));
+        let jobs = manager.ranked_jobs(vec![dummy_tx(), dummy_tx(), dummy_tx()], false);
+        let outcome = dispatch_sends(SendStrategy::Sequential, jobs, usize::MAX, fake_send(calls.clone(), 1))
+            .await
+            .unwrap();
+        assert_eq!(outcome.endpoint, "ok-fast");
+        assert_eq!(*calls.lock().unwrap(), vec!["ok-fast".to_string()]);
+    }
+
+    #[tokio::test]
+    async fn sequential_stops_after_fatal_error_threshold() {
+        let attempts = Arc::new(AtomicUsize::new(0));
+        let counter = attempts.clone();
+        let send = move |_endpoint: String, _tx: VersionedTransaction| -> SendFuture {
+            counter.fetch_add(1, Ordering::SeqCst);
+            Box::pin(async { Err(anyhow!("insufficient funds for fee")) })
+        };
+
+        let result = dispatch_sends(SendStrategy::Sequential, jobs(&["a", "b", "c"]), 1, send).await;
+
+        assert!(result.is_err());
+        assert_eq!(attempts.load(Ordering::SeqCst), 1);
+    }
+}
@@ -0,0 +1,110 @@
+//! UTC time windows during which auto-buying is allowed.
+//!
+//! Outside every window the engine keeps sniffing and filtering candidates but does
+//! not buy. No windows configured means buying is always allowed. A window whose
+//! `end` is before its `start` runs past midnight into the next day; `days` are the
+//! days it starts on.
+
+use chrono::{DateTime, Datelike, NaiveTime, Utc, Weekday};
+use serde::{Deserialize, Serialize};
+
+/// One buy window, e.g. `{ days = ["mon", "fri"], start = "13:00", end = "21:00" }`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BuyWindow {
+    /// Days the window starts on (empty = every day)
+    #[serde(default)]
+    pub days: Vec<Weekday>,
+    /// Start time (UTC, inclusive)
+    pub start: NaiveTime,
+    /// End time (UTC, exclusive); equal to `start` covers the whole day
+    pub end: NaiveTime,
+}
+
+impl BuyWindow {
+    fn starts_on(&self, day: Weekday) -> bool {
+        self.days.is_empty() || self.days.contains(&day)
+    }
+
+    pub fn contains(&self, at: DateTime<Utc>) -> bool {
+        let time = at.time();
+        let day = at.weekday();
+        if self.start == self.end {
+            self.starts_on(day)
+        } else if self.start < self.end {
+            self.starts_on(day) && time >= self.start && time < self.end
+        } else {
+            // Overnight: the evening part on the start day, the morning part on the next
+            (self.starts_on(day) && time >= self.start) || (self.starts_on(day.pred()) && time < self.end)
+        }
+    }
+}
+
+/// Whether auto-buying is allowed at `at` under `windows`.
+pub fn buying_allowed(windows: &[BuyWindow], at: DateTime<Utc>) -> bool {
+    windows.is_empty() || windows.iter().any(|w| w.contains(at))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn at(day: u32, hour: u32, minute: u32) -> DateTime<Utc> {
+        // 2024-01-01 was a Monday
+        Utc.with_ymd_and_hms(2024, 1, day, hour, minute, 0).unwrap()
+    }
+
+    fn window(days: &[Weekday], start: &str, end: &str) -> BuyWindow {
+        BuyWindow {
+            days: days.to_vec(),
+            start: start.parse().unwrap(),
+            end: end.parse().unwrap(),
+        }
+    }
+
+    #[test]
+    fn daytime_window_on_selected_days() {
+        let windows = [window(&[Weekday::Mon, Weekday::Tue], "13:00", "21:00")];
+
+        assert!(buying_allowed(&windows, at(1, 13, 0)));
+        assert!(buying_allowed(&windows, at(2, 20, 59)));
+        assert!(!buying_allowed(&windows, at(1, 12, 59)));
+        assert!(!buying_allowed(&windows, at(1, 21, 0)));
+        // Wednesday is not selected
+        assert!(!buying_allowed(&windows, at(3, 15, 0)));
+        // No windows: always allowed
+        assert!(buying_allowed(&[], at(3, 3, 0)));
+    }
+
+    #[test]
+    fn overnight_window_crosses_day_boundary() {
+        // Friday 22:00 until Saturday 02:00
+        let windows = [window(&[Weekday::Fri], "22:00", "02:00")];
+
+        assert!(buying_allowed(&windows, at(5, 22, 0)));
+        assert!(buying_allowed(&windows, at(5, 23, 59)));
+        assert!(buying_allowed(&windows, at(6, 0, 0)));
+        assert!(buying_allowed(&windows, at(6, 1, 59)));
+        assert!(!buying_allowed(&windows, at(6, 2, 0)));
+        // Friday morning belongs to a Thursday-night window, which is not selected
+        assert!(!buying_allowed(&windows, at(5, 1, 0)));
+        assert!(!buying_allowed(&windows, at(6, 22, 30)));
+
+        // Sunday night into Monday wraps the week
+        let sunday = [window(&[Weekday::Sun], "23:00", "01:00")];
+        assert!(buying_allowed(&sunday, at(7, 23, 30)));
+        assert!(buying_allowed(&sunday, at(8, 0, 30)));
+        assert!(!buying_allowed(&sunday, at(8, 1, 0)));
+    }
+
+    #[test]
+    fn window_parses_from_toml() {
+        #[derive(Deserialize)]
+        struct Wrapper {
+            buy_schedule: Vec<BuyWindow>,
+        }
+        let parsed: Wrapper =
+            toml::from_str(r#"buy_schedule = [{ days = ["mon", "fri"], start = "13:00", end = "21:00" }]"#).unwrap();
+        assert_eq!(parsed.buy_schedule, vec![window(&[Weekday::Mon, Weekday::Fri], "13:00", "21:00")]);
+    }
+}
@@ -1,18 +1,41 @@
+use crate::config::Config;
+use crate::metrics::metrics;
+use crate::raydium;
+use crate::sniffer::creator_filter::{file_version, load_key_file, parse_keys, CreatorVerdict};
 use crate::types::PremintCandidate;
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_client::rpc_config::RpcSimulateTransactionConfig;
+use solana_sdk::account::Account;
 use solana_sdk::pubkey::Pubkey;
+use solana_sdk::transaction::VersionedTransaction;
+use spl_token_2022::extension::transfer_fee::TransferFeeConfig;
+use spl_token_2022::extension::{BaseStateWithExtensions, StateWithExtensions};
+use spl_token_2022::state::Mint;
 use std::collections::{HashMap, HashSet};
-use std::sync::{Arc, Mutex};
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, RwLock};
 use std::time::{Duration, Instant};
+use tracing::{info, warn};
 
 /// Validation and security checks for candidates and operations
 #[derive(Debug, Default)]
 pub struct SecurityValidator {
-    /// Track seen signatures to detect duplicates
-    seen_signatures: Arc<Mutex<HashSet<String>>>,
+    /// Track seen signatures (and when last seen) to detect duplicates
+    seen_signatures: Arc<Mutex<HashMap<String, Instant>>>,
     /// Rate limiting per mint to prevent spam
     mint_rate_limiter: Arc<Mutex<HashMap<Pubkey, Vec<Instant>>>>,
     /// Last seen slot for monotonic validation
     last_slot: Arc<Mutex<u64>>,
+    /// Creators whose candidates are rejected (known ruggers)
+    creator_blacklist: RwLock<HashSet<Pubkey>>,
+    /// Mints that are never bought
+    mint_blacklist: RwLock<HashSet<Pubkey>>,
+    /// Creators that pass even when blacklisted
+    creator_allowlist: RwLock<HashSet<Pubkey>>,
+    /// Reject every creator not on the allowlist
+    allowlist_only: AtomicBool,
 }
 
 impl SecurityValidator {
@@ -33,6 +56,18 @@ impl SecurityValidator {
             issues.push("Invalid creator pubkey: default/zero pubkey".to_string());
         }
 
+        if self.is_blacklisted_mint(&candidate.mint) {
+            issues.push(format!("Mint {} is blacklisted", candidate.mint));
+        }
+
+        match self.creator_verdict(&candidate.creator) {
+            CreatorVerdict::Blocked => issues.push(format!("Creator {} is blacklisted", candidate.creator)),
+            CreatorVerdict::Unlisted if self.allowlist_only() => {
+                issues.push(format!("Creator {} is not on the allowlist", candidate.creator))
+            }
+            _ => {}
+        }
+
         // Slot monotonic validation (slots should generally increase)
         {
             let mut last_slot = self.last_slot.lock().unwrap();
@@ -79,6 +114,68 @@ impl SecurityValidator {
         ValidationResult { issues }
     }
 
+    pub fn is_blacklisted_creator(&self, creator: &Pubkey) -> bool {
+        read_list(&self.creator_blacklist).contains(creator)
+    }
+
+    pub fn is_blacklisted_mint(&self, mint: &Pubkey) -> bool {
+        read_list(&self.mint_blacklist).contains(mint)
+    }
+
+    pub fn is_allowlisted_creator(&self, creator: &Pubkey) -> bool {
+        read_list(&self.creator_allowlist).contains(creator)
+    }
+
+    /// How `creator` is treated by the creator lists; the allowlist wins over the blacklist.
+    pub fn creator_verdict(&self, creator: &Pubkey) -> CreatorVerdict {
+        if self.is_allowlisted_creator(creator) {
+            CreatorVerdict::Allowlisted
+        } else if self.is_blacklisted_creator(creator) {
+            CreatorVerdict::Blocked
+        } else {
+            CreatorVerdict::Unlisted
+        }
+    }
+
+    /// Whether creators not on the allowlist are rejected.
+    pub fn allowlist_only(&self) -> bool {
+        self.allowlist_only.load(Ordering::Relaxed)
+    }
+
+    /// Replace the creator blacklist.
+    pub fn set_creator_blacklist(&self, creators: HashSet<Pubkey>) {
+        *write_list(&self.creator_blacklist) = creators;
+    }
+
+    /// Replace the mint blacklist.
+    pub fn set_mint_blacklist(&self, mints: HashSet<Pubkey>) {
+        *write_list(&self.mint_blacklist) = mints;
+    }
+
+    /// Replace the creator allowlist; with `allowlist_only`, unlisted creators are rejected.
+    pub fn set_creator_allowlist(&self, creators: HashSet<Pubkey>, allowlist_only: bool) {
+        *write_list(&self.creator_allowlist) = creators;
+        self.allowlist_only.store(allowlist_only, Ordering::Relaxed);
+    }
+
+    /// Load the creator and mint lists from `cfg`: the inline lists plus the files at
+    /// `creator_blocklist_path` and `mint_blocklist_path`. A list whose file cannot be
+    /// read keeps what it held, and the first such error is returned.
+    pub fn load_lists(&self, cfg: &Config) -> anyhow::Result<()> {
+        let creators = load_list(&cfg.creator_blocklist, cfg.creator_blocklist_path.as_deref(), &self.creator_blacklist);
+        let mints = load_list(&cfg.mint_blocklist, cfg.mint_blocklist_path.as_deref(), &self.mint_blacklist);
+        self.set_creator_allowlist(
+            parse_keys(cfg.creator_allowlist.iter().map(String::as_str)),
+            cfg.creator_allowlist_only,
+        );
+        creators.and(mints)
+    }
+
+    /// Highest slot seen by `validate_candidate`.
+    pub fn last_slot(&self) -> u64 {
+        *self.last_slot.lock().unwrap()
+    }
+
     /// Check if a mint is being spammed (rate limiting)
     pub fn check_mint_rate_limit(&self, mint: &Pubkey, window_secs: u64, max_per_window: usize) -> bool {
         let mut rate_limiter = self.mint_rate_limiter.lock().unwrap();
@@ -100,23 +197,38 @@ impl SecurityValidator {
         true
     }
 
+    /// Whether `mint` is at its rate limit, without recording a sighting.
+    pub fn mint_rate_limited(&self, mint: &Pubkey, window_secs: u64, max_per_window: usize) -> bool {
+        let rate_limiter = self.mint_rate_limiter.lock().unwrap();
+        let window_duration = Duration::from_secs(window_secs);
+        rate_limiter
+            .get(mint)
+            .map_or(0, |timestamps| timestamps.iter().filter(|t| t.elapsed() < window_duration).count())
+            >= max_per_window
+    }
+
     /// Check for duplicate signature attempts
     pub fn check_duplicate_signature(&self, signature: &str) -> bool {
+        self.check_duplicate_signature_within(signature, Duration::MAX)
+    }
+
+    /// Check for duplicate signature attempts seen within `window`; older sightings count as new.
+    pub fn check_duplicate_signature_within(&self, signature: &str, window: Duration) -> bool {
+        let now = Instant::now();
         let mut seen = self.seen_signatures.lock().unwrap();
-        if seen.contains(signature) {
-            return false; // Duplicate detected
+        if let Some(last_seen) = seen.get(signature) {
+            if now.duration_since(*last_seen) < window {
+                return false; // Duplicate detected
+            }
         }
-        seen.insert(signature.to_string());
+        seen.insert(signature.to_string(), now);
 
         // Prevent unbounded growth by cleaning old signatures periodically
         if seen.len() > 10000 {
-            // Keep only last 5000 signatures
-            let mut sigs: Vec<_> = seen.iter().cloned().collect();
-            sigs.sort();
-            seen.clear();
-            for sig in sigs.into_iter().skip(5000) {
-                seen.insert(sig);
-            }
+            // Keep only the 5000 most recently seen signatures
+            let mut sigs: Vec<_> = seen.drain().collect();
+            sigs.sort_by_key(|(_, seen_at)| std::cmp::Reverse(*seen_at));
+            seen.extend(sigs.into_iter().take(5000));
         }
 
         true
@@ -154,6 +266,149 @@ impl SecurityValidator {
         Ok(amount)
     }
 
+    /// Validate a URL taken from untrusted data (e.g. token metadata) before fetching it.
+    ///
+    /// Only http(s) is allowed, and hosts that are loopback, private, link-local or cloud
+    /// metadata endpoints are refused unless listed in `allowed_hosts` (meant for local dev).
+    /// Hostnames are checked as written; fetch through `untrusted_fetch_client` so the
+    /// addresses they resolve to and every redirect are checked too.
+    pub fn validate_fetch_url(&self, url: &str, allowed_hosts: &[String]) -> Result<reqwest::Url, String> {
+        let parsed = reqwest::Url::parse(url).map_err(|e| format!("Invalid URL '{}': {}", url, e))?;
+
+        if !matches!(parsed.scheme(), "http" | "https") {
+            return Err(format!("URL scheme '{}' is not allowed", parsed.scheme()));
+        }
+
+        let host = parsed
+            .host_str()
+            .ok_or_else(|| format!("URL '{}' has no host", url))?
+            .trim_start_matches('[')
+            .trim_end_matches(']')
+            .to_ascii_lowercase();
+
+        if allowed_hosts.iter().any(|allowed| allowed.eq_ignore_ascii_case(&host)) {
+            return Ok(parsed);
+        }
+
+        let internal = match host.parse::<IpAddr>() {
+            Ok(ip) => is_internal_ip(ip),
+            Err(_) => {
+                host == "localhost"
+                    || host.ends_with(".localhost")
+                    || host.ends_with(".internal")
+                    || host.ends_with(".local")
+            }
+        };
+        if internal {
+            return Err(format!("URL host '{}' is an internal address", host));
+        }
+
+        Ok(parsed)
+    }
+
+    /// Validate a configured API endpoint (e.g. a `dexes` entry) that requests are
+    /// sent to with an API key: http(s) with a host, and https unless the host is
+    /// loopback, so the key never travels in the clear.
+    pub fn validate_api_endpoint(&self, url: &str) -> Result<reqwest::Url, String> {
+        let parsed = reqwest::Url::parse(url).map_err(|e| format!("Invalid URL '{}': {}", url, e))?;
+        let host = parsed
+            .host_str()
+            .ok_or_else(|| format!("URL '{}' has no host", url))?
+            .trim_start_matches('[')
+            .trim_end_matches(']');
+        let loopback = match host.parse::<IpAddr>() {
+            Ok(ip) => ip.is_loopback(),
+            Err(_) => host.eq_ignore_ascii_case("localhost"),
+        };
+        match parsed.scheme() {
+            "https" => Ok(parsed),
+            "http" if loopback => Ok(parsed),
+            "http" => Err(format!("URL '{}' must use https", url)),
+            scheme => Err(format!("URL scheme '{}' is not allowed", scheme)),
+        }
+    }
+
+    /// Check whether tokens of `mint` could be sold again: a freeze authority or a
+    /// steep Token-2022 transfer fee make it risky. A mint account that cannot be
+    /// read is reported as unchecked, not as unsellable.
+    pub async fn check_sellability(&self, mint: &Pubkey, rpc: &RpcClient) -> SellabilityReport {
+        self.check_sellability_with_sell(mint, rpc, None).await
+    }
+
+    /// `check_sellability`, also simulating `sell_tx` (a tiny sell of the token)
+    /// when given. Signatures are not verified and the blockhash is replaced, so
+    /// an unsigned transaction will do.
+    pub async fn check_sellability_with_sell(
+        &self,
+        mint: &Pubkey,
+        rpc: &RpcClient,
+        sell_tx: Option<&VersionedTransaction>,
+    ) -> SellabilityReport {
+        let mut report = SellabilityReport::from_mint(&self.read_mint(mint, rpc).await);
+
+        if let Some(tx) = sell_tx {
+            let config = RpcSimulateTransactionConfig {
+                sig_verify: false,
+                replace_recent_blockhash: true,
+                ..Default::default()
+            };
+            match rpc.simulate_transaction_with_config(tx, config).await {
+                Ok(response) => {
+                    if let Some(err) = &response.value.err {
+                        report.issues.push(format!("Simulated sell failed: {}", err));
+                    }
+                    report.sell_simulated = Some(response.value.err.is_none());
+                }
+                Err(e) => report.issues.push(format!("Sell simulation unavailable: {}", e)),
+            }
+        }
+
+        report.risk_score = report.score();
+        report
+    }
+
+    /// Check whether the mint and freeze authorities of `mint` are renounced. A mint
+    /// account that cannot be read or parsed is reported as unchecked.
+    pub async fn check_authorities(&self, mint: &Pubkey, rpc: &RpcClient) -> AuthorityReport {
+        self.check_authorities_with_lp(mint, rpc, None).await
+    }
+
+    /// `check_authorities`, also checking whether the LP tokens of the token's AMM
+    /// pool, minted as `lp_mint`, are burned: less than one whole LP token is left.
+    pub async fn check_authorities_with_lp(
+        &self,
+        mint: &Pubkey,
+        rpc: &RpcClient,
+        lp_mint: Option<&Pubkey>,
+    ) -> AuthorityReport {
+        let mut report = AuthorityReport::from_mint(&self.read_mint(mint, rpc).await);
+        if let Some(lp_mint) = lp_mint {
+            report.check_lp(&self.read_lp_mint(lp_mint, rpc).await);
+        }
+        report
+    }
+
+    /// Read the LP mint account `lp_mint`, for `AuthorityReport::check_lp`.
+    pub async fn read_lp_mint(&self, lp_mint: &Pubkey, rpc: &RpcClient) -> MintAccount {
+        rpc.get_account(lp_mint).await.map_err(|e| format!("LP mint account not readable: {}", e))
+    }
+
+    /// Read the LP mint of the Raydium SOL pool of `mint`, where migrated pump.fun
+    /// tokens trade, for `AuthorityReport::check_lp`.
+    pub async fn read_raydium_lp_mint(&self, mint: &Pubkey, rpc: &RpcClient) -> MintAccount {
+        let pool = raydium::find_sol_pool(rpc, mint)
+            .await
+            .map_err(|e| format!("Raydium pool not readable: {}", e))?
+            .ok_or_else(|| format!("No Raydium SOL pool for {}", mint))?;
+        self.read_lp_mint(&pool.lp_mint, rpc).await
+    }
+
+    /// Read the mint account of `mint` once for both `SellabilityReport::from_mint`
+    /// and `AuthorityReport::from_mint`.
+    pub async fn read_mint(&self, mint: &Pubkey, rpc: &RpcClient) -> MintAccount {
+        rpc.get_account(mint).await.map_err(|e| format!("Mint account not readable: {}", e))
+    }
+
     /// Clear old data periodically for memory management
     pub fn cleanup_old_data(&self) {
         let now = Instant::now();
@@ -171,10 +426,11 @@ impl SecurityValidator {
         {
             let mut seen = self.seen_signatures.lock().unwrap();
             if seen.len() > 10000 {
+                // Drop the oldest sightings down to 5000
                 let to_remove = seen.len() - 5000;
-                let mut sigs: Vec<_> = seen.iter().cloned().collect();
-                sigs.sort();
-                for sig in sigs.into_iter().take(to_remove) {
+                let mut sigs: Vec<_> = seen.iter().map(|(sig, seen_at)| (sig.clone(), *seen_at)).collect();
+                sigs.sort_by_key(|(_, seen_at)| *seen_at);
+                for (sig, _) in sigs.into_iter().take(to_remove) {
                     seen.remove(&sig);
                 }
             }
@@ -182,6 +438,138 @@ impl SecurityValidator {
     }
 }
 
+fn read_list(list: &RwLock<HashSet<Pubkey>>) -> std::sync::RwLockReadGuard<'_, HashSet<Pubkey>> {
+    list.read().unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+fn write_list(list: &RwLock<HashSet<Pubkey>>) -> std::sync::RwLockWriteGuard<'_, HashSet<Pubkey>> {
+    list.write().unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+/// Replace `list` with the `inline` keys plus those in the file at `path`. When the
+/// file cannot be read the keys loaded from it before are kept.
+fn load_list(inline: &[String], path: Option<&str>, list: &RwLock<HashSet<Pubkey>>) -> anyhow::Result<()> {
+    let mut keys = parse_keys(inline.iter().map(String::as_str));
+    let loaded = match path {
+        Some(path) => match load_key_file(path) {
+            Ok(file_keys) => {
+                keys.extend(file_keys);
+                Ok(())
+            }
+            Err(e) => {
+                keys.extend(read_list(list).iter().copied());
+                Err(e)
+            }
+        },
+        None => Ok(()),
+    };
+    *write_list(list) = keys;
+    loaded
+}
+
+/// Reload the global validator's lists (which the sniffer's `CreatorFilter` reads
+/// too) from `cfg` whenever one of the list files changes, checking every `creator_blocklist_reload_ms`. Runs until the task is aborted.
+pub async fn watch_list_files(cfg: Config) {
+    let paths: Vec<&Path> = [&cfg.creator_blocklist_path, &cfg.mint_blocklist_path]
+        .into_iter()
+        .flatten()
+        .map(Path::new)
+        .collect();
+    let versions = || paths.iter().map(|path| file_version(path)).collect::<Vec<_>>();
+    let mut last_seen = versions();
+    let mut ticker = tokio::time::interval(Duration::from_millis(cfg.creator_blocklist_reload_ms.max(1)));
+    loop {
+        ticker.tick().await;
+        let current = versions();
+        // A missing file is left alone until it reappears
+        if current == last_seen || current.iter().any(Option::is_none) {
+            continue;
+        }
+        match validator().load_lists(&cfg) {
+            Ok(()) => {
+                info!("Creator and mint lists reloaded");
+                metrics().increment_counter("creator_mint_list_reloads_total");
+                last_seen = current;
+            }
+            Err(e) => warn!(error = %e, "Creator and mint list reload failed"),
+        }
+    }
+}
+
+/// Redirects followed by `untrusted_fetch_client`.
+const MAX_UNTRUSTED_REDIRECTS: usize = 5;
+
+/// HTTP client for URLs from untrusted data. Every redirect target passes
+/// `validate_fetch_url` again, and hostnames are resolved by `PublicOnlyResolver`, so
+/// a public name pointing at an internal address is refused at connect time, on the
+/// addresses that were checked. Hosts in `allowed_hosts` are exempt (local dev).
+pub fn untrusted_fetch_client(allowed_hosts: &[String]) -> reqwest::Result<reqwest::Client> {
+    let redirect_hosts = allowed_hosts.to_vec();
+    let redirects = reqwest::redirect::Policy::custom(move |attempt| {
+        if attempt.previous().len() >= MAX_UNTRUSTED_REDIRECTS {
+            return attempt.error(format!("more than {} redirects", MAX_UNTRUSTED_REDIRECTS));
+        }
+        match validator().validate_fetch_url(attempt.url().as_str(), &redirect_hosts) {
+            Ok(_) => attempt.follow(),
+            Err(e) => attempt.error(format!("redirect refused: {}", e)),
+        }
+    });
+    reqwest::Client::builder()
+        .redirect(redirects)
+        .dns_resolver(Arc::new(PublicOnlyResolver { allowed_hosts: allowed_hosts.to_vec() }))
+        .build()
+}
+
+/// DNS resolver refusing names with an internal address among their records.
+#[derive(Debug)]
+struct PublicOnlyResolver {
+    allowed_hosts: Vec<String>,
+}
+
+impl reqwest::dns::Resolve for PublicOnlyResolver {
+    fn resolve(&self, name: hyper::client::connect::dns::Name) -> reqwest::dns::Resolving {
+        let host = name.as_str().to_ascii_lowercase();
+        let allowed = self.allowed_hosts.iter().any(|allowed| allowed.eq_ignore_ascii_case(&host));
+        Box::pin(async move {
+            let addrs: Vec<std::net::SocketAddr> = tokio::net::lookup_host((host.as_str(), 0)).await?.collect();
+            if !allowed {
+                if let Some(addr) = addrs.iter().find(|addr| is_internal_ip(addr.ip())) {
+                    return Err(format!("host '{}' resolves to internal address {}", host, addr.ip()).into());
+                }
+            }
+            let addrs: reqwest::dns::Addrs = Box::new(addrs.into_iter());
+            Ok(addrs)
+        })
+    }
+}
+
+/// Addresses a fetch of untrusted URLs must never reach.
+fn is_internal_ip(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(v4) => is_internal_ipv4(v4),
+        IpAddr::V6(v6) => {
+            if let Some(v4) = v6.to_ipv4_mapped() {
+                return is_internal_ipv4(v4);
+            }
+            let first = v6.segments()[0];
+            v6 == Ipv6Addr::LOCALHOST
+                || v6 == Ipv6Addr::UNSPECIFIED
+                || (first & 0xfe00) == 0xfc00 // unique local fc00::/7
+                || (first & 0xffc0) == 0xfe80 // link-local fe80::/10
+        }
+    }
+}
+
+fn is_internal_ipv4(ip: Ipv4Addr) -> bool {
+    let [a, b, ..] = ip.octets();
+    ip.is_loopback()
+        || ip.is_private()
+        || ip.is_link_local() // includes the 169.254.169.254 metadata endpoint
+        || ip.is_unspecified()
+        || ip.is_broadcast()
+        || (a == 100 && (64..128).contains(&b)) // carrier-grade NAT 100.64.0.0/10
+}
+
 #[derive(Debug)]
 pub struct ValidationResult {
     pub issues: Vec<String>,
@@ -198,6 +586,173 @@ impl ValidationResult {
     }
 }
 
+/// A mint account as read by `SecurityValidator::read_mint`, or why it could not be read.
+pub type MintAccount = std::result::Result<Account, String>;
+
+/// Transfer fee from which a token counts as not sellable (10%).
+pub const MAX_SELLABLE_TRANSFER_FEE_BPS: u16 = 1_000;
+
+/// What `SecurityValidator::check_sellability` found out about a mint.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SellabilityReport {
+    /// The mint account was read and parsed; the mint flags are meaningless otherwise
+    pub mint_checked: bool,
+    /// A freeze authority is set, so the token accounts of holders can be frozen
+    pub freeze_authority: bool,
+    /// The mint is owned by the Token-2022 program
+    pub token_2022: bool,
+    /// Highest of the current and scheduled Token-2022 transfer fees
+    pub transfer_fee_bps: u16,
+    /// Whether the simulated sell succeeded; `None` when no sell was simulated
+    pub sell_simulated: Option<bool>,
+    /// 0 (nothing found) to 100; unsellable from 60
+    pub risk_score: u8,
+    pub issues: Vec<String>,
+}
+
+impl SellabilityReport {
+    /// Nothing found makes the token unsellable.
+    pub fn is_sellable(&self) -> bool {
+        !self.freeze_authority
+            && self.transfer_fee_bps < MAX_SELLABLE_TRANSFER_FEE_BPS
+            && self.sell_simulated != Some(false)
+    }
+
+    /// Report on a mint account read by `SecurityValidator::read_mint`; no sell is simulated.
+    pub fn from_mint(account: &MintAccount) -> Self {
+        let mut report = match account {
+            Ok(account) => Self::from_mint_account(&account.owner, &account.data),
+            Err(issue) => Self { issues: vec![issue.clone()], ..Default::default() },
+        };
+        report.risk_score = report.score();
+        report
+    }
+
+    fn from_mint_account(owner: &Pubkey, data: &[u8]) -> Self {
+        let token_2022 = *owner == spl_token_2022::id();
+        let mint = match unpack_mint(owner, data) {
+            Ok(mint) => mint,
+            Err(issue) => return Self { token_2022, issues: vec![issue], ..Default::default() },
+        };
+
+        let mut report = Self { mint_checked: true, token_2022, ..Default::default() };
+        if mint.base.freeze_authority.is_some() {
+            report.freeze_authority = true;
+            report.issues.push("Mint has a freeze authority".to_string());
+        }
+        if let Ok(fee) = mint.get_extension::<TransferFeeConfig>() {
+            report.transfer_fee_bps = u16::from(fee.older_transfer_fee.transfer_fee_basis_points)
+                .max(u16::from(fee.newer_transfer_fee.transfer_fee_basis_points));
+            if report.transfer_fee_bps > 0 {
+                report.issues.push(format!("Transfer fee of {} bps", report.transfer_fee_bps));
+            }
+        }
+        report
+    }
+
+    /// A failed sell is conclusive; a freeze authority or a fee at the limit reach
+    /// the unsellable 60 on their own, smaller fees stay below it.
+    fn score(&self) -> u8 {
+        let mut score: u32 = 0;
+        if self.freeze_authority {
+            score += 60;
+        }
+        if self.transfer_fee_bps > 0 {
+            score += (20 + u32::from(self.transfer_fee_bps) * 40 / u32::from(MAX_SELLABLE_TRANSFER_FEE_BPS)).min(60);
+        }
+        if self.sell_simulated == Some(false) {
+            score += 100;
+        }
+        score.min(100) as u8
+    }
+}
+
+/// What `SecurityValidator::check_authorities` found out about a mint.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct AuthorityReport {
+    /// The mint account was read and parsed; the authority flags are meaningless otherwise
+    pub mint_checked: bool,
+    /// No mint authority is left, so the supply is fixed
+    pub mint_authority_renounced: bool,
+    /// No freeze authority is left, so holders cannot be frozen
+    pub freeze_authority_renounced: bool,
+    /// Whether the LP tokens of the token's pool are effectively burned; `None` when
+    /// no pool was checked (bonding-curve tokens have no LP)
+    pub lp_burned: Option<bool>,
+    pub issues: Vec<String>,
+}
+
+impl AuthorityReport {
+    /// Both authorities are known to be renounced.
+    pub fn authorities_renounced(&self) -> bool {
+        self.mint_checked && self.mint_authority_renounced && self.freeze_authority_renounced
+    }
+
+    /// Authorities renounced and, when a pool was checked, its LP not left unburned.
+    pub fn rug_resistant(&self) -> bool {
+        self.authorities_renounced() && self.lp_burned != Some(false)
+    }
+
+    /// Report on a mint account read by `SecurityValidator::read_mint`.
+    pub fn from_mint(account: &MintAccount) -> Self {
+        match account {
+            Ok(account) => Self::from_mint_account(&account.owner, &account.data),
+            Err(issue) => Self { issues: vec![issue.clone()], ..Default::default() },
+        }
+    }
+
+    /// Add whether the pool's LP is burned, from its LP mint read by
+    /// `SecurityValidator::read_lp_mint`.
+    pub fn check_lp(&mut self, lp_account: &MintAccount) {
+        let burned = match lp_account {
+            Ok(account) => lp_burned(&account.owner, &account.data),
+            Err(issue) => Err(issue.clone()),
+        };
+        match burned {
+            Ok(burned) => self.lp_burned = Some(burned),
+            Err(issue) => self.issues.push(issue),
+        }
+        if self.lp_burned == Some(false) {
+            self.issues.push("LP not burned".to_string());
+        }
+    }
+
+    fn from_mint_account(owner: &Pubkey, data: &[u8]) -> Self {
+        let mint = match unpack_mint(owner, data) {
+            Ok(mint) => mint,
+            Err(issue) => return Self { issues: vec![issue], ..Default::default() },
+        };
+        let mut report = Self {
+            mint_checked: true,
+            mint_authority_renounced: mint.base.mint_authority.is_none(),
+            freeze_authority_renounced: mint.base.freeze_authority.is_none(),
+            ..Default::default()
+        };
+        if !report.mint_authority_renounced {
+            report.issues.push("Mint authority not renounced".to_string());
+        }
+        if !report.freeze_authority_renounced {
+            report.issues.push("Freeze authority not renounced".to_string());
+        }
+        report
+    }
+}
+
+/// Whether less than one whole LP token is left in the LP mint account `data`.
+fn lp_burned(owner: &Pubkey, data: &[u8]) -> std::result::Result<bool, String> {
+    let lp = unpack_mint(owner, data)?;
+    Ok(lp.base.supply < 10u64.checked_pow(u32::from(lp.base.decimals)).unwrap_or(u64::MAX))
+}
+
+/// Parse mint account `data` owned by `owner`; legacy mints parse as Token-2022
+/// mints without extensions.
+fn unpack_mint<'a>(owner: &Pubkey, data: &'a [u8]) -> std::result::Result<StateWithExtensions<'a, Mint>, String> {
+    if *owner != spl_token::id() && *owner != spl_token_2022::id() {
+        return Err(format!("Mint account is owned by {}, not a token program", owner));
+    }
+    StateWithExtensions::<Mint>::unpack(data).map_err(|e| format!("Mint account does not parse: {}", e))
+}
+
 /// Global security validator instance
 static GLOBAL_VALIDATOR: std::sync::OnceLock<SecurityValidator> = std::sync::OnceLock::new();
 
@@ -209,6 +764,191 @@ pub fn validator() -> &'static SecurityValidator {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::test_support::{rpc_with_mint, AccountsRpc};
+    use solana_sdk::program_option::COption;
+    use solana_sdk::program_pack::Pack;
+    use spl_token_2022::extension::{ExtensionType, StateWithExtensionsMut, BaseStateWithExtensionsMut};
+
+    fn mint_state(freeze_authority: Option<Pubkey>) -> Mint {
+        Mint {
+            mint_authority: COption::None,
+            supply: 1_000_000_000_000_000,
+            decimals: 6,
+            is_initialized: true,
+            freeze_authority: freeze_authority.into(),
+        }
+    }
+
+    fn legacy_mint(freeze_authority: Option<Pubkey>) -> Vec<u8> {
+        let mut data = vec![0; Mint::LEN];
+        mint_state(freeze_authority).pack_into_slice(&mut data);
+        data
+    }
+
+    fn token_2022_mint_with_fee(fee_bps: u16) -> Vec<u8> {
+        let len = ExtensionType::try_calculate_account_len::<Mint>(&[ExtensionType::TransferFeeConfig]).unwrap();
+        let mut data = vec![0; len];
+        let mut state = StateWithExtensionsMut::<Mint>::unpack_uninitialized(&mut data).unwrap();
+        let fee = state.init_extension::<TransferFeeConfig>(true).unwrap();
+        fee.newer_transfer_fee.transfer_fee_basis_points = fee_bps.into();
+        fee.newer_transfer_fee.maximum_fee = u64::MAX.into();
+        state.base = mint_state(None);
+        state.pack_base();
+        state.init_account_type().unwrap();
+        data
+    }
+
+    fn mint_with_authorities(mint_authority: Option<Pubkey>, freeze_authority: Option<Pubkey>, supply: u64) -> Vec<u8> {
+        let mut data = vec![0; Mint::LEN];
+        Mint { mint_authority: mint_authority.into(), supply, ..mint_state(freeze_authority) }.pack_into_slice(&mut data);
+        data
+    }
+
+    #[tokio::test]
+    async fn renounced_authorities_pass() {
+        let rpc = rpc_with_mint(&spl_token::id(), &legacy_mint(None));
+        let report = SecurityValidator::new().check_authorities(&Pubkey::new_unique(), &rpc).await;
+
+        assert!(report.mint_checked);
+        assert!(report.mint_authority_renounced && report.freeze_authority_renounced);
+        assert_eq!(report.lp_burned, None);
+        assert!(report.issues.is_empty());
+        assert!(report.authorities_renounced());
+    }
+
+    #[tokio::test]
+    async fn remaining_authorities_are_reported() {
+        let validator = SecurityValidator::new();
+
+        let data = mint_with_authorities(Some(Pubkey::new_unique()), None, 1_000);
+        let report = validator.check_authorities(&Pubkey::new_unique(), &rpc_with_mint(&spl_token::id(), &data)).await;
+        assert!(!report.mint_authority_renounced && report.freeze_authority_renounced);
+        assert_eq!(report.issues, vec!["Mint authority not renounced".to_string()]);
+        assert!(!report.authorities_renounced());
+
+        let data = mint_with_authorities(Some(Pubkey::new_unique()), Some(Pubkey::new_unique()), 1_000);
+        let report = validator.check_authorities(&Pubkey::new_unique(), &rpc_with_mint(&spl_token::id(), &data)).await;
+        assert!(!report.mint_authority_renounced && !report.freeze_authority_renounced);
+        assert_eq!(report.issues.len(), 2);
+    }
+
+    #[tokio::test]
+    async fn non_mint_accounts_are_unchecked() {
+        let validator = SecurityValidator::new();
+
+        // A token account (165 bytes) owned by the token program is not a mint
+        let rpc = rpc_with_mint(&spl_token::id(), &[7u8; 165]);
+        let report = validator.check_authorities(&Pubkey::new_unique(), &rpc).await;
+        assert!(!report.mint_checked);
+        assert!(report.issues[0].contains("does not parse"));
+        assert!(!report.authorities_renounced());
+
+        let rpc = rpc_with_mint(&Pubkey::default(), &legacy_mint(None));
+        let report = validator.check_authorities(&Pubkey::new_unique(), &rpc).await;
+        assert!(!report.mint_checked && !report.authorities_renounced());
+    }
+
+    #[test]
+    fn lp_counts_as_burned_below_one_whole_token() {
+        // 6 decimals: 999_999 raw units are less than one LP token
+        let burned = mint_with_authorities(None, None, 999_999);
+        assert_eq!(lp_burned(&spl_token::id(), &burned), Ok(true));
+        let held = mint_with_authorities(None, None, 5_000_000_000);
+        assert_eq!(lp_burned(&spl_token::id(), &held), Ok(false));
+        assert!(lp_burned(&Pubkey::default(), &burned).is_err());
+    }
+
+    #[tokio::test]
+    async fn unburned_lp_fails_the_rug_check() {
+        let (mint, lp_mint) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let rpc = |lp_supply: u64| {
+            let accounts = HashMap::from([
+                (mint, (spl_token::id(), legacy_mint(None))),
+                (lp_mint, (spl_token::id(), mint_with_authorities(None, None, lp_supply))),
+            ]);
+            RpcClient::new_sender(AccountsRpc { accounts, token_balance: 0 }, Default::default())
+        };
+        let validator = SecurityValidator::new();
+
+        let report = validator.check_authorities_with_lp(&mint, &rpc(5_000_000_000), Some(&lp_mint)).await;
+        assert!(report.authorities_renounced());
+        assert_eq!(report.lp_burned, Some(false));
+        assert_eq!(report.issues, vec!["LP not burned".to_string()]);
+        assert!(!report.rug_resistant());
+
+        let report = validator.check_authorities_with_lp(&mint, &rpc(0), Some(&lp_mint)).await;
+        assert_eq!(report.lp_burned, Some(true));
+        assert!(report.rug_resistant());
+
+        // An LP mint that cannot be read is an issue, not a verdict
+        let report = validator.check_authorities_with_lp(&mint, &rpc(0), Some(&Pubkey::new_unique())).await;
+        assert_eq!(report.lp_burned, None);
+        assert_eq!(report.issues.len(), 1);
+        assert!(report.issues[0].starts_with("LP mint account not readable"), "{:?}", report.issues);
+    }
+
+    #[tokio::test]
+    async fn clean_mint_is_sellable() {
+        let rpc = rpc_with_mint(&spl_token::id(), &legacy_mint(None));
+        let report = SecurityValidator::new().check_sellability(&Pubkey::new_unique(), &rpc).await;
+
+        assert!(report.mint_checked);
+        assert!(!report.freeze_authority && !report.token_2022);
+        assert_eq!(report.transfer_fee_bps, 0);
+        assert_eq!(report.sell_simulated, None);
+        assert_eq!(report.risk_score, 0);
+        assert!(report.issues.is_empty());
+        assert!(report.is_sellable());
+
+        // Mocked responses are used up once served
+        let rpc = rpc_with_mint(&spl_token::id(), &legacy_mint(None));
+        let sell = VersionedTransaction::default();
+        let report = SecurityValidator::new()
+            .check_sellability_with_sell(&Pubkey::new_unique(), &rpc, Some(&sell))
+            .await;
+        assert!(report.mint_checked);
+        assert_eq!(report.sell_simulated, Some(true));
+        assert!(report.is_sellable());
+    }
+
+    #[tokio::test]
+    async fn frozen_authority_mint_is_not_sellable() {
+        let rpc = rpc_with_mint(&spl_token::id(), &legacy_mint(Some(Pubkey::new_unique())));
+        let report = SecurityValidator::new().check_sellability(&Pubkey::new_unique(), &rpc).await;
+
+        assert!(report.mint_checked && report.freeze_authority);
+        assert!(report.risk_score >= 60);
+        assert!(report.issues[0].contains("freeze authority"));
+        assert!(!report.is_sellable());
+    }
+
+    #[tokio::test]
+    async fn transfer_fee_counts_from_the_limit() {
+        let validator = SecurityValidator::new();
+
+        let rpc = rpc_with_mint(&spl_token_2022::id(), &token_2022_mint_with_fee(100));
+        let report = validator.check_sellability(&Pubkey::new_unique(), &rpc).await;
+        assert!(report.mint_checked && report.token_2022);
+        assert_eq!(report.transfer_fee_bps, 100);
+        assert!(report.risk_score > 0 && report.risk_score < 60);
+        assert!(report.is_sellable());
+
+        let rpc = rpc_with_mint(&spl_token_2022::id(), &token_2022_mint_with_fee(MAX_SELLABLE_TRANSFER_FEE_BPS));
+        let report = validator.check_sellability(&Pubkey::new_unique(), &rpc).await;
+        assert_eq!(report.risk_score, 60);
+        assert!(!report.is_sellable());
+    }
+
+    #[tokio::test]
+    async fn unreadable_mint_is_unchecked_not_unsellable() {
+        // Owned by the system program: not a mint at all
+        let rpc = rpc_with_mint(&Pubkey::default(), &legacy_mint(Some(Pubkey::new_unique())));
+        let report = SecurityValidator::new().check_sellability(&Pubkey::new_unique(), &rpc).await;
+
+        assert!(!report.mint_checked && !report.freeze_authority);
+        assert!(report.issues[0].contains("not a token program"));
+        assert!(report.is_sellable());
+    }
 
     #[test]
     fn test_candidate_validation() {
@@ -225,6 +965,7 @@ mod tests {
                 .as_secs(),
             instruction_summary: Some("Test instruction".to_string()),
             is_jito_bundle: Some(false),
+            deadline_ms: None,
         };
 
         let result = validator.validate_candidate(&valid_candidate);
@@ -238,6 +979,7 @@ mod tests {
             timestamp: 0,
             instruction_summary: None,
             is_jito_bundle: None,
+            deadline_ms: None,
         };
 
         let result = validator.validate_candidate(&invalid_candidate);
@@ -245,6 +987,139 @@ mod tests {
         assert!(result.issues.len() >= 2); // Should have mint and creator issues
     }
 
+    fn fresh_candidate() -> PremintCandidate {
+        PremintCandidate {
+            mint: Pubkey::new_unique(),
+            creator: Pubkey::new_unique(),
+            program: "pump.fun".to_string(),
+            slot: 0,
+            timestamp: 0,
+            instruction_summary: None,
+            is_jito_bundle: None,
+            deadline_ms: None,
+        }
+    }
+
+    #[test]
+    fn test_empty_lists_pass_every_candidate() {
+        let validator = SecurityValidator::new();
+        validator.load_lists(&Config::default()).unwrap();
+
+        let candidate = fresh_candidate();
+        assert!(!validator.is_blacklisted_creator(&candidate.creator));
+        assert!(!validator.is_blacklisted_mint(&candidate.mint));
+        assert!(validator.validate_candidate(&candidate).is_valid());
+    }
+
+    #[test]
+    fn test_blacklisted_creator_and_mint_are_rejected() {
+        let validator = SecurityValidator::new();
+        let from_rugger = fresh_candidate();
+        let blacklisted_mint = fresh_candidate();
+        validator.set_creator_blacklist(HashSet::from([from_rugger.creator]));
+        validator.set_mint_blacklist(HashSet::from([blacklisted_mint.mint]));
+
+        assert!(validator.is_blacklisted_creator(&from_rugger.creator));
+        let result = validator.validate_candidate(&from_rugger);
+        assert!(result.issues.iter().any(|issue| issue.contains("Creator") && issue.contains("blacklisted")));
+
+        assert!(validator.is_blacklisted_mint(&blacklisted_mint.mint));
+        let result = validator.validate_candidate(&blacklisted_mint);
+        assert!(result.issues.iter().any(|issue| issue.contains("Mint") && issue.contains("blacklisted")));
+
+        assert!(validator.validate_candidate(&fresh_candidate()).is_valid());
+
+        // Allowlisted creators pass despite the blacklist
+        validator.set_creator_allowlist(HashSet::from([from_rugger.creator]), false);
+        assert!(validator.validate_candidate(&from_rugger).is_valid());
+    }
+
+    #[test]
+    fn test_allowlist_only_rejects_unlisted_creators() {
+        let validator = SecurityValidator::new();
+        let listed = fresh_candidate();
+        validator.set_creator_allowlist(HashSet::from([listed.creator]), true);
+
+        assert!(validator.validate_candidate(&listed).is_valid());
+        let result = validator.validate_candidate(&fresh_candidate());
+        assert!(result.issues.iter().any(|issue| issue.contains("not on the allowlist")));
+
+        // Without allowlist-only mode the same creator passes
+        validator.set_creator_allowlist(HashSet::from([listed.creator]), false);
+        assert!(validator.validate_candidate(&fresh_candidate()).is_valid());
+    }
+
+    #[test]
+    fn test_lists_load_from_config_files_and_reload() {
+        use std::io::Write;
+
+        let inline_creator = Pubkey::new_unique();
+        let file_mint = Pubkey::new_unique();
+        let mut mints = tempfile::NamedTempFile::new().unwrap();
+        writeln!(mints, "# known honeypots\n{}", file_mint).unwrap();
+        let cfg = Config {
+            creator_blocklist: vec![inline_creator.to_string()],
+            mint_blocklist_path: Some(mints.path().display().to_string()),
+            ..Config::default()
+        };
+
+        let validator = SecurityValidator::new();
+        validator.load_lists(&cfg).unwrap();
+        assert!(validator.is_blacklisted_creator(&inline_creator));
+        assert!(validator.is_blacklisted_mint(&file_mint));
+
+        let appended = Pubkey::new_unique();
+        writeln!(mints, "{}", appended).unwrap();
+        mints.flush().unwrap();
+        validator.load_lists(&cfg).unwrap();
+        assert!(validator.is_blacklisted_mint(&appended));
+
+        // An unreadable file keeps the mints loaded before
+        let missing = Config { mint_blocklist_path: Some("/nonexistent/mints.txt".to_string()), ..cfg };
+        assert!(validator.load_lists(&missing).is_err());
+        assert!(validator.is_blacklisted_mint(&file_mint));
+        assert!(validator.is_blacklisted_creator(&inline_creator));
+    }
+
+    #[tokio::test]
+    async fn test_appended_creator_reaches_the_sniffer_filter_without_restart() {
+        use crate::sniffer::creator_filter::CreatorFilter;
+        use std::io::Write;
+
+        let known = Pubkey::new_unique();
+        let newly_found = Pubkey::new_unique();
+        let mut file = tempfile::NamedTempFile::new().unwrap();
+        writeln!(file, "# known ruggers\n{}", known).unwrap();
+        let cfg = Config {
+            creator_blocklist_path: Some(file.path().display().to_string()),
+            creator_blocklist_reload_ms: 10,
+            ..Config::default()
+        };
+        validator().load_lists(&cfg).unwrap();
+        let filter = CreatorFilter::shared();
+        assert!(!filter.admit(&known));
+        assert!(filter.admit(&newly_found));
+
+        let handle = tokio::spawn(watch_list_files(cfg));
+        // Let the watcher record the file's current version first
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        writeln!(file, "{}", newly_found).unwrap();
+        file.flush().unwrap();
+
+        let mut blocked = false;
+        for _ in 0..100 {
+            if !filter.admit(&newly_found) {
+                blocked = true;
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        handle.abort();
+
+        assert!(blocked, "appended creator should be blocked after reload");
+        assert!(!filter.admit(&known));
+    }
+
     #[test]
     fn test_rate_limiting() {
         let validator = SecurityValidator::new();
@@ -268,6 +1143,16 @@ mod tests {
         assert!(validator.check_duplicate_signature("sig2")); // New signature
     }
 
+    #[test]
+    fn test_duplicate_signature_window() {
+        let validator = SecurityValidator::new();
+
+        assert!(validator.check_duplicate_signature_within("sig1", Duration::from_secs(60)));
+        assert!(!validator.check_duplicate_signature_within("sig1", Duration::from_secs(60)));
+        // Zero window: every sighting counts as new
+        assert!(validator.check_duplicate_signature_within("sig1", Duration::ZERO));
+    }
+
     #[test]
     fn test_holdings_validation() {
         let validator = SecurityValidator::new();
@@ -281,4 +1166,74 @@ mod tests {
         assert!(validator.validate_holdings_percent(f64::INFINITY).is_err());
         assert!(validator.validate_holdings_percent(f64::NAN).is_err());
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_fetch_url_blocks_internal_addresses() {
+        let validator = SecurityValidator::new();
+
+        for url in [
+            "http://127.0.0.1/meta.json",
+            "http://localhost:8080/meta.json",
+            "http://169.254.169.254/latest/meta-data/",
+            "http://metadata.google.internal/computeMetadata/v1/",
+            "http://10.0.0.5/meta.json",
+            "http://192.168.1.1/meta.json",
+            "http://[::1]/meta.json",
+            "http://[::ffff:127.0.0.1]/meta.json",
+            "http://[fd00::1]/meta.json",
+            "file:///etc/passwd",
+        ] {
+            assert!(validator.validate_fetch_url(url, &[]).is_err(), "{} should be refused", url);
+        }
+    }
+
+    #[test]
+    fn test_fetch_url_allows_public_and_allowlisted_hosts() {
+        let validator = SecurityValidator::new();
+
+        assert!(validator.validate_fetch_url("https://arweave.net/abc", &[]).is_ok());
+        assert!(validator.validate_fetch_url("https://8.8.8.8/meta.json", &[]).is_ok());
+
+        let dev = vec!["localhost".to_string(), "127.0.0.1".to_string()];
+        assert!(validator.validate_fetch_url("http://localhost:8080/meta.json", &dev).is_ok());
+        assert!(validator.validate_fetch_url("http://127.0.0.1/meta.json", &dev).is_ok());
+        assert!(validator.validate_fetch_url("http://10.0.0.5/meta.json", &dev).is_err());
+    }
+
+    #[tokio::test]
+    async fn untrusted_fetches_recheck_redirects_and_resolved_addresses() {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        // A local (allowlisted) host redirecting to the cloud metadata endpoint
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            while let Ok((mut socket, _)) = listener.accept().await {
+                let mut request = [0u8; 1024];
+                let _ = socket.read(&mut request).await;
+                let response = "HTTP/1.1 302 Found\r\nLocation: http://169.254.169.254/latest/meta-data/\r\n\
+                                Content-Length: 0\r\nConnection: close\r\n\r\n";
+                let _ = socket.write_all(response.as_bytes()).await;
+            }
+        });
+        let error_chain = |error: reqwest::Error| {
+            let mut messages = vec![error.to_string()];
+            let mut source = std::error::Error::source(&error);
+            while let Some(cause) = source {
+                messages.push(cause.to_string());
+                source = cause.source();
+            }
+            messages.join(": ")
+        };
+
+        let client = untrusted_fetch_client(&["127.0.0.1".to_string()]).unwrap();
+        let error = client.get(format!("http://{}/meta.json", addr)).send().await.unwrap_err();
+        assert!(error.is_redirect());
+        assert!(error_chain(error).contains("internal address"));
+
+        // A name resolving to loopback is refused on its resolved address
+        let client = untrusted_fetch_client(&[]).unwrap();
+        let error = client.get(format!("http://localhost:{}/meta.json", addr.port())).send().await.unwrap_err();
+        assert!(error_chain(error).contains("resolves to internal address"));
+    }
+}
@@ -0,0 +1,79 @@
+//! Dry run of the buy pipeline for a single candidate ("what would the bot do?").
+//!
+//! The engine runs the candidate through its own buy checks (see
+//! `BuyEngine::simulate_candidate`) and reports the decision together with the
+//! transaction parameters a buy would use. Nothing is built, signed or broadcast,
+//! and no rate-limit or duplicate state is recorded.
+
+use serde::Serialize;
+use solana_sdk::pubkey::Pubkey;
+
+use crate::security::validator;
+use crate::structured_logging::DecisionTrace;
+use crate::tx_builder::TransactionConfig;
+use crate::types::PremintCandidate;
+
+/// Parameters a buy of the candidate would be built with.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct BuyParams {
+    pub program: String,
+    pub buy_amount_lamports: u64,
+    pub slippage_bps: u64,
+    pub priority_fee_lamports: u64,
+    pub compute_unit_limit: u32,
+    pub legacy_message: bool,
+}
+
+impl BuyParams {
+    pub fn new(program: &str, config: &TransactionConfig) -> Self {
+        Self {
+            program: program.to_string(),
+            buy_amount_lamports: config.buy_amount_lamports,
+            slippage_bps: config.slippage_model.effective_bps(config.slippage_bps, &[]),
+            priority_fee_lamports: config.priority_fee_lamports,
+            compute_unit_limit: config.compute_unit_limit,
+            legacy_message: config.legacy_message,
+        }
+    }
+}
+
+/// Decision of a simulated buy.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct BuySimulation {
+    pub mint: String,
+    pub would_buy: bool,
+    /// Pipeline stage that rejected the candidate, if any.
+    pub rejected_at: Option<String>,
+    pub reasons: Vec<String>,
+    /// Would-be transaction parameters; present only when `would_buy`.
+    pub params: Option<BuyParams>,
+}
+
+impl BuySimulation {
+    /// Decision recorded in `trace`; `params` are kept only when nothing rejected it.
+    pub fn from_trace(trace: &DecisionTrace, params: Option<BuyParams>) -> Self {
+        let would_buy = trace.rejected_at.is_none() && params.is_some();
+        Self {
+            mint: trace.mint.clone(),
+            would_buy,
+            rejected_at: trace.rejected_at.clone(),
+            reasons: trace.reason.iter().cloned().collect(),
+            params: params.filter(|_| would_buy),
+        }
+    }
+}
+
+/// Candidate for a what-if query on `mint`. The slot is the validator's last seen
+/// slot so the check does not move its monotonic slot tracking.
+pub fn simulation_candidate(mint: Pubkey, creator: Option<Pubkey>, program: &str) -> PremintCandidate {
+    PremintCandidate {
+        mint,
+        creator: creator.unwrap_or_default(),
+        program: program.to_string(),
+        slot: validator().last_slot(),
+        timestamp: 0,
+        instruction_summary: Some("simulation".to_string()),
+        is_jito_bundle: None,
+        deadline_ms: None,
+    }
+}
@@ -0,0 +1,123 @@
+//! Slippage selection for buy/sell builds.
+//!
+//! A fixed `slippage_bps` is too tight in fast markets (missed fills) and too
+//! loose in calm ones (bad prices). The dynamic model widens the base slippage
+//! with the volatility of the token's recent prices, within configured bounds.
+
+use std::collections::{HashMap, VecDeque};
+
+use serde::{Deserialize, Serialize};
+use solana_sdk::pubkey::Pubkey;
+
+/// Recent prices kept per mint for volatility estimates.
+pub const PRICE_HISTORY_LEN: usize = 20;
+
+/// How the effective slippage for a build is chosen.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum SlippageModel {
+    /// Always the configured `slippage_bps`
+    #[default]
+    Fixed,
+    /// `slippage_bps` plus `volatility_multiplier` x volatility, clamped to `[min_bps, max_bps]`
+    Dynamic {
+        min_bps: u64,
+        max_bps: u64,
+        volatility_multiplier: f64,
+    },
+}
+
+impl SlippageModel {
+    /// Effective slippage for a build, given the base `slippage_bps` and recent prices (oldest first).
+    pub fn effective_bps(&self, base_bps: u64, recent_prices: &[f64]) -> u64 {
+        match *self {
+            SlippageModel::Fixed => base_bps,
+            SlippageModel::Dynamic { min_bps, max_bps, volatility_multiplier } => {
+                let widening = price_volatility(recent_prices) * volatility_multiplier * 10_000.0;
+                let bps = base_bps as f64 + widening.max(0.0);
+                (bps.round() as u64).clamp(min_bps, max_bps.max(min_bps)).min(10_000)
+            }
+        }
+    }
+}
+
+/// Standard deviation of consecutive relative price changes (0.0 with fewer than 3 prices).
+pub fn price_volatility(prices: &[f64]) -> f64 {
+    let returns: Vec<f64> = prices
+        .windows(2)
+        .filter(|w| w[0] > 0.0 && w[0].is_finite() && w[1].is_finite())
+        .map(|w| (w[1] - w[0]) / w[0])
+        .collect();
+    if returns.len() < 2 {
+        return 0.0;
+    }
+    let mean = returns.iter().sum::<f64>() / returns.len() as f64;
+    let variance = returns.iter().map(|r| (r - mean).powi(2)).sum::<f64>() / returns.len() as f64;
+    variance.sqrt()
+}
+
+/// Bounded per-mint price history.
+#[derive(Debug, Default)]
+pub struct PriceHistory {
+    prices: HashMap<Pubkey, VecDeque<f64>>,
+}
+
+impl PriceHistory {
+    pub fn record(&mut self, mint: Pubkey, price: f64) {
+        let history = self.prices.entry(mint).or_default();
+        if history.len() == PRICE_HISTORY_LEN {
+            history.pop_front();
+        }
+        history.push_back(price);
+    }
+
+    /// Recent prices for `mint`, oldest first.
+    pub fn recent(&self, mint: &Pubkey) -> Vec<f64> {
+        self.prices
+            .get(mint)
+            .map(|h| h.iter().copied().collect())
+            .unwrap_or_default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DYNAMIC: SlippageModel = SlippageModel::Dynamic {
+        min_bps: 300,
+        max_bps: 2500,
+        volatility_multiplier: 2.0,
+    };
+
+    #[test]
+    fn high_volatility_widens_slippage_within_bounds() {
+        let calm = [1.0, 1.001, 1.0, 1.001, 1.0];
+        let wild = [1.0, 1.08, 0.97, 1.12, 0.95];
+
+        assert_eq!(SlippageModel::Fixed.effective_bps(1000, &wild), 1000);
+
+        let calm_bps = DYNAMIC.effective_bps(500, &calm);
+        let wild_bps = DYNAMIC.effective_bps(500, &wild);
+        assert!(calm_bps < 550, "calm={}", calm_bps);
+        assert!(wild_bps > calm_bps + 1000, "calm={} wild={}", calm_bps, wild_bps);
+
+        // Extreme swings are capped; no history falls back to the base (but not below min)
+        let crash = [1.0, 2.0, 0.5, 3.0, 0.2];
+        assert_eq!(DYNAMIC.effective_bps(500, &crash), 2500);
+        assert_eq!(DYNAMIC.effective_bps(100, &[]), 300);
+    }
+
+    #[test]
+    fn price_history_is_bounded() {
+        let mint = Pubkey::new_unique();
+        let mut history = PriceHistory::default();
+        for i in 0..(PRICE_HISTORY_LEN + 5) {
+            history.record(mint, i as f64);
+        }
+        let recent = history.recent(&mint);
+        assert_eq!(recent.len(), PRICE_HISTORY_LEN);
+        assert_eq!(recent[0], 5.0);
+        assert!(history.recent(&Pubkey::new_unique()).is_empty());
+    }
+}
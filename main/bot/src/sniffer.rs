@@ -1,17 +1,27 @@
-//! Sniffer entrypoint coordinating Mock or Real (WSS + HTTP fallback) sources.
+//! Sniffer entrypoint coordinating Mock or Real (WSS + HTTP fallback, optional Geyser) sources.
 
 pub mod real;
+pub mod creator_filter;
 pub mod source;
 pub mod wss_source;
 pub mod http_source;
+#[cfg(feature = "geyser")]
+pub mod geyser_source;
+#[cfg(feature = "geyser")]
+pub mod yellowstone;
 pub mod runner;
+pub mod supervisor;
+#[cfg(any(test, feature = "test_utils"))]
+pub mod scripted;
 
 use crate::config::{Config, SnifferMode};
 use crate::sniffer::runner::SnifferRunner;
+use crate::sniffer::source::candidate_deadline;
+use crate::time_utils::now_ms;
 use crate::types::CandidateSender;
 use crate::types::PremintCandidate;
 use std::collections::HashMap;
-use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use std::time::{Duration, Instant};
 use tokio::{task::JoinHandle, time};
 use tracing::{debug, info, warn};
 
@@ -21,8 +31,62 @@ use solana_sdk::{pubkey::Pubkey, signature::{Keypair, Signer}};
 const CANDIDATE_TTL: Duration = Duration::from_secs(5);
 /// Minimal spacing between emitted candidates (debounce).
 const DEBOUNCE_DELAY: Duration = Duration::from_millis(300);
-/// Maximum allowed age of a candidate based on its timestamp.
-const MAX_CANDIDATE_AGE: Duration = Duration::from_secs(5);
+/// Slot duration used to backdate mock candidates' slots.
+const MOCK_SLOT_MS: u64 = 400;
+
+/// Age of the mock sniffer's candidates. Timestamps, slots and ingest deadlines are
+/// backdated as if each candidate had been seen that long ago.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct MockSnifferOptions {
+    /// Age of every candidate.
+    pub age_ms: u64,
+    /// Every Nth candidate is stale (0 = none)...
+    pub stale_every: u32,
+    /// ...by this much on top of `age_ms`.
+    pub stale_age_ms: u64,
+    /// Slot of a fresh candidate; older candidates get correspondingly older slots.
+    pub slot: u64,
+    /// Ingest deadline budget, as for the real sources (0 = no deadline).
+    pub deadline_budget_ms: u64,
+}
+
+impl MockSnifferOptions {
+    pub fn from_config(cfg: &Config) -> Self {
+        Self {
+            age_ms: cfg.mock_candidate_age_ms,
+            stale_every: cfg.mock_stale_candidate_every,
+            stale_age_ms: cfg.mock_stale_candidate_age_ms,
+            slot: 0,
+            deadline_budget_ms: cfg.candidate_deadline_ms,
+        }
+    }
+
+    /// Age of the `index`-th candidate (1-based).
+    fn age_of(&self, index: u64) -> u64 {
+        let stale = self.stale_every > 0 && index.is_multiple_of(u64::from(self.stale_every));
+        if stale {
+            self.age_ms.saturating_add(self.stale_age_ms)
+        } else {
+            self.age_ms
+        }
+    }
+
+    /// The `index`-th candidate (1-based) as seen at `now_ms`.
+    fn candidate(&self, index: u64, now_ms: u64) -> PremintCandidate {
+        let age_ms = self.age_of(index);
+        let seen_ms = now_ms.saturating_sub(age_ms);
+        PremintCandidate {
+            mint: Keypair::new().pubkey(),
+            creator: Keypair::new().pubkey(),
+            program: "pump.fun".to_string(),
+            slot: self.slot.saturating_sub(age_ms / MOCK_SLOT_MS),
+            timestamp: seen_ms / 1000,
+            instruction_summary: Some("Mock candidate".to_string()),
+            is_jito_bundle: None,
+            deadline_ms: candidate_deadline(self.deadline_budget_ms, seen_ms),
+        }
+    }
+}
 
 /// Start the sniffer in the given mode.
 /// Returns a JoinHandle that can be aborted to stop the sniffer.
@@ -32,9 +96,9 @@ pub async fn run_sniffer(
     config: &Config,
 ) -> JoinHandle<()> {
     match mode {
-        SnifferMode::Mock => run_mock_sniffer(sender),
+        SnifferMode::Mock => run_mock_sniffer_with(sender, MockSnifferOptions::from_config(config)),
         SnifferMode::Real => {
-            let runner = SnifferRunner::new(config.clone());
+            let runner = SnifferRunner::from_config(config.clone());
             tokio::spawn(async move {
                 runner.run(sender, None).await;
             })
@@ -42,12 +106,17 @@ pub async fn run_sniffer(
     }
 }
 
-/// Mock sniffer: emits a fabricated PremintCandidate with TTL/debounce/age filtering.
+/// Mock sniffer: emits fresh fabricated PremintCandidates with TTL/debounce filtering.
 pub fn run_mock_sniffer(sender: CandidateSender) -> JoinHandle<()> {
+    run_mock_sniffer_with(sender, MockSnifferOptions::default())
+}
+
+/// Mock sniffer emitting candidates aged per `options`.
+pub fn run_mock_sniffer_with(sender: CandidateSender, options: MockSnifferOptions) -> JoinHandle<()> {
     tokio::spawn(async move {
         info!(
-            "Starting MOCK sniffer with TTL={:?}, debounce={:?}, max_age={:?}",
-            CANDIDATE_TTL, DEBOUNCE_DELAY, MAX_CANDIDATE_AGE
+            "Starting MOCK sniffer with TTL={:?}, debounce={:?}, options={:?}",
+            CANDIDATE_TTL, DEBOUNCE_DELAY, options
         );
 
         let mut seen: HashMap<Pubkey, Instant> = HashMap::new();
@@ -55,6 +124,7 @@ pub fn run_mock_sniffer(sender: CandidateSender) -> JoinHandle<()> {
 
         let mut ticker = time::interval(Duration::from_millis(500));
         let mut burst_left: u8 = 0;
+        let mut index: u64 = 0;
 
         loop {
             ticker.tick().await;
@@ -67,33 +137,12 @@ pub fn run_mock_sniffer(sender: CandidateSender) -> JoinHandle<()> {
                 time::sleep(Duration::from_millis(75)).await;
             }
 
-            let mint = Keypair::new().pubkey();
-            let creator = Keypair::new().pubkey();
-            let now_secs = SystemTime::now()
-                .duration_since(UNIX_EPOCH)
-                .unwrap_or_default()
-                .as_secs();
-
-            let candidate = PremintCandidate {
-                mint,
-                creator,
-                program: "pump.fun".to_string(),
-                slot: 0,
-                timestamp: now_secs,
-                instruction_summary: Some("Mock candidate".to_string()),
-                is_jito_bundle: None,
-            };
+            let candidate = options.candidate(index + 1, now_ms());
 
             let now = Instant::now();
 
             seen.retain(|_, seen_at| now.duration_since(*seen_at) < CANDIDATE_TTL);
 
-            let candidate_age = Duration::from_secs(0);
-            if candidate_age > MAX_CANDIDATE_AGE {
-                debug!(mint=%candidate.mint, age=?candidate_age, "Dropping candidate: too old");
-                continue;
-            }
-
             if let Some(seen_at) = seen.get(&candidate.mint) {
                 if now.duration_since(*seen_at) < CANDIDATE_TTL {
                     debug!(mint=%candidate.mint, "Skipping due to TTL window");
@@ -108,6 +157,7 @@ pub fn run_mock_sniffer(sender: CandidateSender) -> JoinHandle<()> {
 
             seen.insert(candidate.mint, now);
             last_emit = now;
+            index += 1;
 
             info!(
                 target: "sniffer.mock",
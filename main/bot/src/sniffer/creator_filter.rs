@@ -0,0 +1,139 @@
+//! Creator blocklist/allowlist consulted by the sniffer sources.
+//!
+//! Candidates from blocklisted creators (known ruggers) are dropped before they
+//! reach the buffer or engine. Allowlisted creators always pass; with
+//! `allowlist_only` set, every other creator is dropped as well.
+//!
+//! By default the filter consults the global `SecurityValidator`, the single
+//! owner of the creator lists: `main` loads them from config and the optional
+//! list file (one base58 pubkey per line, `#` starts a comment) and reloads that
+//! file when it changes, so newly discovered ruggers are blocked without a restart.
+
+use std::collections::HashSet;
+use std::path::Path;
+use std::str::FromStr;
+use std::time::SystemTime;
+
+use anyhow::{Context, Result};
+use solana_sdk::pubkey::Pubkey;
+use tracing::warn;
+
+use crate::metrics::metrics;
+use crate::security::validator;
+
+/// How a creator is treated.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CreatorVerdict {
+    Blocked,
+    Allowlisted,
+    Unlisted,
+}
+
+#[derive(Debug)]
+struct FixedLists {
+    blocked: HashSet<Pubkey>,
+    allowed: HashSet<Pubkey>,
+    allowlist_only: bool,
+}
+
+/// Creator filter shared by the sniffer sources.
+#[derive(Debug, Default)]
+pub struct CreatorFilter {
+    /// Lists of its own; `None` consults the global validator's lists.
+    fixed: Option<FixedLists>,
+}
+
+impl CreatorFilter {
+    /// Filter with fixed lists of its own, independent of the validator.
+    pub fn new(blocked: HashSet<Pubkey>, allowed: HashSet<Pubkey>, allowlist_only: bool) -> Self {
+        Self { fixed: Some(FixedLists { blocked, allowed, allowlist_only }) }
+    }
+
+    /// Filter following the global validator's creator lists, including reloads.
+    pub fn shared() -> Self {
+        Self::default()
+    }
+
+    pub fn verdict(&self, creator: &Pubkey) -> CreatorVerdict {
+        match &self.fixed {
+            Some(lists) if lists.allowed.contains(creator) => CreatorVerdict::Allowlisted,
+            Some(lists) if lists.blocked.contains(creator) => CreatorVerdict::Blocked,
+            Some(_) => CreatorVerdict::Unlisted,
+            None => validator().creator_verdict(creator),
+        }
+    }
+
+    fn allowlist_only(&self) -> bool {
+        match &self.fixed {
+            Some(lists) => lists.allowlist_only,
+            None => validator().allowlist_only(),
+        }
+    }
+
+    /// Whether a candidate from `creator` may be emitted; records the decision in metrics.
+    pub fn admit(&self, creator: &Pubkey) -> bool {
+        match self.verdict(creator) {
+            CreatorVerdict::Blocked => {
+                metrics().increment_counter("sniffer_creator_blocked_total");
+                false
+            }
+            CreatorVerdict::Allowlisted => {
+                metrics().increment_counter("sniffer_creator_allowlisted_total");
+                true
+            }
+            CreatorVerdict::Unlisted if self.allowlist_only() => {
+                metrics().increment_counter("sniffer_creator_unlisted_dropped_total");
+                false
+            }
+            CreatorVerdict::Unlisted => true,
+        }
+    }
+}
+
+/// Parse pubkeys, skipping (and logging) invalid entries.
+pub(crate) fn parse_keys<'a>(keys: impl Iterator<Item = &'a str>) -> HashSet<Pubkey> {
+    keys.filter_map(|k| {
+        Pubkey::from_str(k)
+            .map_err(|e| warn!(target: "sniffer", key = k, error = %e, "Ignoring invalid pubkey in list"))
+            .ok()
+    })
+    .collect()
+}
+
+/// Load a creator or mint list file: one pubkey per line, blank lines and `#`
+/// comments ignored.
+pub fn load_key_file<P: AsRef<Path>>(path: P) -> Result<HashSet<Pubkey>> {
+    let path = path.as_ref();
+    let contents = std::fs::read_to_string(path)
+        .with_context(|| format!("read pubkey list {}", path.display()))?;
+    Ok(parse_keys(
+        contents
+            .lines()
+            .map(|line| line.split('#').next().unwrap_or("").trim())
+            .filter(|line| !line.is_empty()),
+    ))
+}
+
+pub(crate) fn file_version(path: &Path) -> Option<(SystemTime, u64)> {
+    let meta = std::fs::metadata(path).ok()?;
+    Some((meta.modified().ok()?, meta.len()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fixed_lists_block_and_allowlist() {
+        let rugger = Pubkey::new_unique();
+        let trusted = Pubkey::new_unique();
+        let filter = CreatorFilter::new(HashSet::from([rugger, trusted]), HashSet::from([trusted]), false);
+        assert_eq!(filter.verdict(&rugger), CreatorVerdict::Blocked);
+        assert_eq!(filter.verdict(&trusted), CreatorVerdict::Allowlisted);
+        assert!(filter.admit(&Pubkey::new_unique()));
+
+        let strict = CreatorFilter::new(HashSet::new(), HashSet::from([trusted]), true);
+        assert!(strict.admit(&trusted));
+        assert!(!strict.admit(&Pubkey::new_unique()));
+    }
+}
@@ -0,0 +1,358 @@
+use std::{
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use async_trait::async_trait;
+use solana_sdk::pubkey::Pubkey;
+use tokio::{
+    sync::{
+        mpsc::{Receiver, Sender},
+        Notify, RwLock,
+    },
+    time,
+};
+use tracing::{debug, error, info, warn};
+
+use crate::config::Config;
+use crate::metrics::metrics;
+use crate::sniffer::creator_filter::CreatorFilter;
+use crate::sniffer::real::{classify_pump_event, fetch_meta_from_rpc, jito_bundle_flag, parse_pump_logs};
+use crate::sniffer::source::{
+    candidate_deadline, candidate_program_name, emit_candidate, pump_fun_program_pk, CandidateDedup,
+    CandidateSource, ReconnectBackoff,
+};
+use crate::time_utils::now_ms;
+use crate::types::{PremintCandidate, ProgramLogEvent};
+
+/// Transaction from a Yellowstone `SubscribeUpdateTransaction`, already decoded.
+#[derive(Debug, Clone, PartialEq)]
+pub struct GeyserTxUpdate {
+    pub slot: u64,
+    pub signature: String,
+    pub account_keys: Vec<Pubkey>,
+    pub log_messages: Vec<String>,
+    pub failed: bool,
+}
+
+/// Message on a Geyser subscription.
+#[derive(Debug, Clone, PartialEq)]
+pub enum GeyserUpdate {
+    Transaction(GeyserTxUpdate),
+    /// Server keep-alive; only refreshes the heartbeat.
+    Ping,
+}
+
+/// Opens Yellowstone gRPC subscriptions. `YellowstoneTransport` is the gRPC client;
+/// the trait keeps the source itself testable without a server.
+#[async_trait]
+pub trait GeyserTransport: Send + Sync {
+    /// Subscribe to non-vote transactions mentioning `program`. The receiver closes
+    /// when the stream ends.
+    async fn subscribe(
+        &self,
+        url: &str,
+        x_token: Option<&str>,
+        program: &Pubkey,
+    ) -> anyhow::Result<Receiver<GeyserUpdate>>;
+}
+
+/// What a transaction update turns into.
+#[derive(Debug, Clone, PartialEq)]
+pub enum UpdateMapping {
+    Candidate(PremintCandidate),
+    /// A candidate event whose logs lack the mint or creator; needs a meta fetch.
+    NeedsMeta { jito: Option<bool> },
+    Skip,
+}
+
+/// Map a decoded update to a candidate the same way the WSS and HTTP sources do.
+pub fn map_update(cfg: &Config, program_name: &str, update: &GeyserTxUpdate, ts_ms: u64) -> UpdateMapping {
+    if update.failed {
+        return UpdateMapping::Skip;
+    }
+    if !classify_pump_event(&update.log_messages).is_candidate(cfg.sniffer_emit_trades) {
+        metrics().increment_counter("sniffer_non_create_skipped_total");
+        return UpdateMapping::Skip;
+    }
+    let (maybe_mint, maybe_creator, log_keys) = parse_pump_logs(&update.log_messages);
+    let jito = jito_bundle_flag(&update.account_keys, &log_keys);
+    match (maybe_mint, maybe_creator) {
+        (Some(mint), Some(creator)) => UpdateMapping::Candidate(geyser_candidate(
+            cfg,
+            program_name,
+            mint,
+            creator,
+            update.slot,
+            jito,
+            ts_ms,
+        )),
+        _ => UpdateMapping::NeedsMeta { jito },
+    }
+}
+
+fn geyser_candidate(
+    cfg: &Config,
+    program_name: &str,
+    mint: Pubkey,
+    creator: Pubkey,
+    slot: u64,
+    jito: Option<bool>,
+    ts_ms: u64,
+) -> PremintCandidate {
+    PremintCandidate {
+        mint,
+        creator,
+        program: program_name.to_string(),
+        slot,
+        timestamp: ts_ms / 1000,
+        instruction_summary: Some("Geyser mint".to_string()),
+        is_jito_bundle: jito,
+        deadline_ms: candidate_deadline(cfg.candidate_deadline_ms, ts_ms),
+    }
+}
+
+/// Candidate source streaming pump.fun transactions from a Yellowstone gRPC endpoint.
+///
+/// Heartbeat, silence timeout and reconnect backoff use the `wss_*` settings, so
+/// it is healthy under the same conditions as `WssSource`.
+pub struct GeyserSource {
+    cfg: Config,
+    transport: Arc<dyn GeyserTransport>,
+    last_heartbeat: Arc<RwLock<Instant>>,
+    stop_notify: Arc<Notify>,
+    creators: Arc<CreatorFilter>,
+    dedup: Arc<CandidateDedup>,
+}
+
+impl GeyserSource {
+    pub fn new(cfg: Config, transport: Arc<dyn GeyserTransport>) -> Self {
+        let creators = Arc::new(CreatorFilter::shared());
+        let dedup = Arc::new(CandidateDedup::from_config(&cfg));
+        Self {
+            cfg,
+            transport,
+            last_heartbeat: Arc::new(RwLock::new(Instant::now())),
+            stop_notify: Arc::new(Notify::new()),
+            creators,
+            dedup,
+        }
+    }
+
+    /// Share `creators` with other sources so list updates reach all of them.
+    pub fn with_creator_filter(mut self, creators: Arc<CreatorFilter>) -> Self {
+        self.creators = creators;
+        self
+    }
+
+    /// Share `dedup` with other sources so a mint they both deliver is emitted once.
+    pub fn with_candidate_dedup(mut self, dedup: Arc<CandidateDedup>) -> Self {
+        self.dedup = dedup;
+        self
+    }
+
+    async fn update_heartbeat(&self) {
+        *self.last_heartbeat.write().await = Instant::now();
+    }
+
+    fn healthy_window(&self) -> Duration {
+        Duration::from_millis(self.cfg.wss_max_silent_ms)
+    }
+
+    async fn handle_transaction(
+        &self,
+        update: GeyserTxUpdate,
+        program: &Pubkey,
+        program_name: &str,
+        cand_tx: &Sender<PremintCandidate>,
+        raw_log_tx: Option<&Sender<ProgramLogEvent>>,
+    ) {
+        let ts_ms = now_ms();
+        if let Some(tx) = raw_log_tx {
+            let _ = tx
+                .send(ProgramLogEvent {
+                    slot: update.slot,
+                    signature: update.signature.clone(),
+                    program: program.to_string(),
+                    logs: update.log_messages.clone(),
+                    ts_ms,
+                })
+                .await;
+        }
+
+        let candidate = match map_update(&self.cfg, program_name, &update, ts_ms) {
+            UpdateMapping::Candidate(candidate) => candidate,
+            UpdateMapping::Skip => return,
+            UpdateMapping::NeedsMeta { jito } => {
+                let Some(endpoint) = self.cfg.rpc_endpoints.first().filter(|_| self.cfg.meta_fetch_enabled) else {
+                    return;
+                };
+                let commitment = self.cfg.meta_fetch_commitment.as_deref().unwrap_or("confirmed");
+                match fetch_meta_from_rpc(endpoint, &update.signature, commitment).await {
+                    Ok((Some(mint), Some(creator))) => {
+                        geyser_candidate(&self.cfg, program_name, mint, creator, update.slot, jito, ts_ms)
+                    }
+                    _ => return,
+                }
+            }
+        };
+        emit_candidate(cand_tx, &self.creators, &self.dedup, candidate).await;
+    }
+}
+
+#[async_trait]
+impl CandidateSource for GeyserSource {
+    async fn run(
+        &self,
+        cand_tx: Sender<PremintCandidate>,
+        raw_log_tx: Option<Sender<ProgramLogEvent>>,
+    ) {
+        let Some(url) = self.cfg.geyser_grpc_url.clone() else {
+            warn!(target: "sniffer", "Geyser source: no geyser_grpc_url configured");
+            self.stop_notify.notified().await;
+            return;
+        };
+
+        let program = pump_fun_program_pk();
+        let program_name = candidate_program_name(&self.cfg, &program);
+        let mut backoff = ReconnectBackoff::new(
+            Duration::from_millis(self.cfg.wss_reconnect_backoff_ms),
+            Duration::from_millis(self.cfg.wss_reconnect_backoff_max_ms),
+            Duration::from_millis(self.cfg.wss_min_connection_lifetime_ms),
+        );
+
+        loop {
+            let notified = self.stop_notify.notified();
+            tokio::pin!(notified);
+
+            debug!(target: "sniffer", "Geyser connecting…");
+            match self
+                .transport
+                .subscribe(&url, self.cfg.geyser_x_token.as_deref(), &program)
+                .await
+            {
+                Ok(mut updates) => {
+                    info!(target: "sniffer", "Geyser subscribed at {}", url);
+                    self.update_heartbeat().await;
+                    let connected_at = Instant::now();
+
+                    loop {
+                        tokio::select! {
+                            _ = &mut notified => {
+                                warn!(target: "sniffer", "Geyser stop requested");
+                                return;
+                            }
+                            msg = updates.recv() => {
+                                match msg {
+                                    Some(update) => {
+                                        self.update_heartbeat().await;
+                                        if let GeyserUpdate::Transaction(tx) = update {
+                                            self.handle_transaction(tx, &program, &program_name, &cand_tx, raw_log_tx.as_ref()).await;
+                                        }
+                                    }
+                                    None => {
+                                        warn!(target: "sniffer", "Geyser stream ended");
+                                        break;
+                                    }
+                                }
+                            }
+                            _ = time::sleep(Duration::from_millis(self.cfg.wss_heartbeat_ms)) => {
+                                let last = *self.last_heartbeat.read().await;
+                                if last.elapsed() > self.healthy_window() {
+                                    warn!(target: "sniffer", "Geyser heartbeat timeout (silent too long)");
+                                    break;
+                                }
+                            }
+                        }
+                    }
+
+                    let lifetime = connected_at.elapsed();
+                    if !backoff.connection_closed(lifetime) {
+                        metrics().increment_counter("geyser_short_lived_connections_total");
+                        warn!(target: "sniffer", lifetime_ms = lifetime.as_millis() as u64, "Geyser connection dropped early; backing off");
+                    }
+                }
+                Err(e) => {
+                    error!(target: "sniffer", ?e, "Geyser subscribe failed");
+                }
+            }
+
+            tokio::select! {
+                _ = &mut notified => return,
+                _ = time::sleep(backoff.next_delay()) => {}
+            }
+        }
+    }
+
+    fn is_healthy(&self) -> bool {
+        let last = futures::executor::block_on(self.last_heartbeat.read());
+        last.elapsed() < self.healthy_window()
+    }
+
+    fn request_stop(&self) {
+        self.stop_notify.notify_waiters();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::str::FromStr;
+
+    const MINT: &str = "4Nd1mBQtrMJVYVfKf2PJy9NZUZdTAsp7D4xWLs4gDB4T";
+    const CREATOR: &str = "9WzDXwBbmkg8ZTbNMqUxvQRAyrZzDsGYdLVL9zYtAWWM";
+
+    fn update(logs: &[&str], account_keys: Vec<Pubkey>) -> GeyserTxUpdate {
+        GeyserTxUpdate {
+            slot: 42,
+            signature: "sig".to_string(),
+            account_keys,
+            log_messages: logs.iter().map(|l| l.to_string()).collect(),
+            failed: false,
+        }
+    }
+
+    #[test]
+    fn decoded_update_maps_to_candidate() {
+        let cfg = Config { candidate_deadline_ms: 500, ..Config::default() };
+        let create = [
+            "Program log: Instruction: Create",
+            &format!("Program log: create mint {MINT}"),
+            &format!("Program log: metadata creator {CREATOR}"),
+        ];
+        let tip = Pubkey::from_str("96gYZGLnJYVFmbjzopPSU6QiEV5fGqZNyN9nmNhvrZU5").unwrap();
+
+        let UpdateMapping::Candidate(candidate) = map_update(&cfg, "pump.fun", &update(&create, vec![tip]), 10_000) else {
+            panic!("expected a candidate");
+        };
+        assert_eq!(candidate.mint, Pubkey::from_str(MINT).unwrap());
+        assert_eq!(candidate.creator, Pubkey::from_str(CREATOR).unwrap());
+        assert_eq!(candidate.slot, 42);
+        assert_eq!(candidate.timestamp, 10);
+        assert_eq!(candidate.is_jito_bundle, Some(true));
+        assert_eq!(candidate.deadline_ms, Some(10_500));
+        assert_eq!(candidate.instruction_summary.as_deref(), Some("Geyser mint"));
+
+        // Creation without mint/creator in the logs goes through meta fetch
+        let bare = update(&["Program log: Instruction: Create"], Vec::new());
+        assert_eq!(map_update(&cfg, "pump.fun", &bare, 0), UpdateMapping::NeedsMeta { jito: None });
+
+        // Only a transaction whose accounts were all seen is known not to be bundled
+        let UpdateMapping::Candidate(unbundled) =
+            map_update(&cfg, "pump.fun", &update(&create, vec![Pubkey::new_unique()]), 0)
+        else {
+            panic!("expected a candidate");
+        };
+        assert_eq!(unbundled.is_jito_bundle, Some(false));
+        let UpdateMapping::Candidate(unknown) = map_update(&cfg, "pump.fun", &update(&create, Vec::new()), 0) else {
+            panic!("expected a candidate");
+        };
+        assert_eq!(unknown.is_jito_bundle, None);
+
+        // Failed transactions and plain trades are skipped
+        let failed = GeyserTxUpdate { failed: true, ..update(&create, Vec::new()) };
+        assert_eq!(map_update(&cfg, "pump.fun", &failed, 0), UpdateMapping::Skip);
+        let buy = update(&["Program log: Instruction: Buy"], Vec::new());
+        assert_eq!(map_update(&cfg, "pump.fun", &buy, 0), UpdateMapping::Skip);
+    }
+}
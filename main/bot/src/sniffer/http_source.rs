@@ -1,5 +1,5 @@
 use std::{
-    collections::VecDeque,
+    collections::{HashSet, VecDeque},
     sync::Arc,
     time::Duration,
 };
@@ -22,38 +22,112 @@ use solana_sdk::{
 use solana_transaction_status::UiTransactionEncoding;
 
 use crate::config::Config;
-use crate::sniffer::real::parse_pump_logs;
-use crate::sniffer::source::{pump_fun_program_pk, CandidateSource};
+use crate::metrics::metrics;
+use crate::sniffer::creator_filter::CreatorFilter;
+use crate::sniffer::real::{classify_pump_event, jito_bundle_flag, parse_pump_logs, transaction_account_keys};
+use crate::sniffer::source::{
+    candidate_deadline, candidate_program_name, emit_candidate, pump_fun_program_pk, CandidateDedup,
+    CandidateSource,
+};
 use crate::time_utils::now_ms;
 use crate::types::{PremintCandidate, ProgramLogEvent};
 
+/// Signatures remembered between polls so overlapping pages are fetched once.
+const SEEN_SIGNATURES_CAPACITY: usize = 2048;
+
+/// Bounded set of recently handled signatures; the oldest are forgotten first.
+#[derive(Debug)]
+pub struct SeenSignatures {
+    capacity: usize,
+    order: VecDeque<Signature>,
+    set: HashSet<Signature>,
+}
+
+impl SeenSignatures {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity: capacity.max(1),
+            order: VecDeque::with_capacity(capacity),
+            set: HashSet::with_capacity(capacity),
+        }
+    }
+
+    pub fn contains(&self, sig: &Signature) -> bool {
+        self.set.contains(sig)
+    }
+
+    /// Record `sig`; false when it was already seen.
+    pub fn insert(&mut self, sig: Signature) -> bool {
+        if !self.set.insert(sig) {
+            return false;
+        }
+        if self.order.len() >= self.capacity {
+            if let Some(oldest) = self.order.pop_front() {
+                self.set.remove(&oldest);
+            }
+        }
+        self.order.push_back(sig);
+        true
+    }
+
+    /// Signatures of `sigs` not seen yet, in order and without repeats.
+    pub fn unseen(&self, sigs: impl IntoIterator<Item = Signature>) -> Vec<Signature> {
+        let mut batch = HashSet::new();
+        sigs.into_iter()
+            .filter(|s| !self.contains(s) && batch.insert(*s))
+            .collect()
+    }
+
+    pub fn len(&self) -> usize {
+        self.order.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.order.is_empty()
+    }
+}
+
 pub struct HttpSource {
     cfg: Config,
-    last_seen: Arc<RwLock<VecDeque<Signature>>>, // simple recent signatures queue
+    last_seen: Arc<RwLock<SeenSignatures>>,
     stop_notify: Arc<Notify>,
+    creators: Arc<CreatorFilter>,
+    dedup: Arc<CandidateDedup>,
     healthy: Arc<RwLock<bool>>,
 }
 
 impl HttpSource {
     pub fn new(cfg: Config) -> Self {
+        let creators = Arc::new(CreatorFilter::shared());
+        let dedup = Arc::new(CandidateDedup::from_config(&cfg));
         Self {
             cfg,
-            last_seen: Arc::new(RwLock::new(VecDeque::with_capacity(2048))),
+            last_seen: Arc::new(RwLock::new(SeenSignatures::new(SEEN_SIGNATURES_CAPACITY))),
             stop_notify: Arc::new(Notify::new()),
+            creators,
+            dedup,
             healthy: Arc::new(RwLock::new(false)),
         }
     }
 
+    /// Share `creators` with other sources so list updates reach all of them.
+    pub fn with_creator_filter(mut self, creators: Arc<CreatorFilter>) -> Self {
+        self.creators = creators;
+        self
+    }
+
+    /// Share `dedup` with other sources so a mint they both deliver is emitted once.
+    pub fn with_candidate_dedup(mut self, dedup: Arc<CandidateDedup>) -> Self {
+        self.dedup = dedup;
+        self
+    }
+
     async fn mark_healthy(&self, val: bool) {
         *self.healthy.write().await = val;
     }
 
     async fn push_seen(&self, sig: Signature) {
-        let mut q = self.last_seen.write().await;
-        if q.len() >= 2048 {
-            q.pop_front();
-        }
-        q.push_back(sig);
+        self.last_seen.write().await.insert(sig);
     }
 
     fn commitment_config(&self) -> CommitmentConfig {
@@ -94,6 +168,7 @@ impl CandidateSource for HttpSource {
         }
 
         let program = pump_fun_program_pk();
+        let program_name = candidate_program_name(&self.cfg, &program);
         let http = RpcClient::new_with_commitment(
             self.cfg.rpc_endpoints[0].clone(),
             self.commitment_config(),
@@ -131,10 +206,7 @@ impl CandidateSource for HttpSource {
 
                     if sigs.is_empty() { continue; }
 
-                    let new_sigs = {
-                        let seen = self.last_seen.read().await;
-                        sigs.into_iter().filter(|s| !seen.contains(s)).collect_vec()
-                    };
+                    let new_sigs = self.last_seen.read().await.unseen(sigs);
 
                     if new_sigs.is_empty() { continue; }
 
@@ -146,6 +218,11 @@ impl CandidateSource for HttpSource {
                         let raw_log_tx = raw_log_tx.clone();
                         let cand_tx = cand_tx.clone();
                         let program_str = program.to_string();
+                        let program_name = program_name.clone();
+                        let deadline_budget_ms = self.cfg.candidate_deadline_ms;
+                        let emit_trades = self.cfg.sniffer_emit_trades;
+                        let creators = self.creators.clone();
+                        let dedup = self.dedup.clone();
                         let commitment = self.commitment_config();
 
                         tasks.push(tokio::spawn(async move {
@@ -163,6 +240,7 @@ impl CandidateSource for HttpSource {
 
                             if let Ok(txres) = tx {
                                 let slot = txres.slot;
+                                let account_keys = transaction_account_keys(&txres.transaction.transaction);
                                 if let Some(meta) = txres.transaction.meta {
                                     if let Some(logs) = Option::<Vec<String>>::from(meta.log_messages) {
                                         let ts_ms = now_ms();
@@ -177,16 +255,23 @@ impl CandidateSource for HttpSource {
                                             }).await;
                                         }
 
-                                        let (maybe_mint, maybe_creator, _k) = parse_pump_logs(&logs);
+                                        if !classify_pump_event(&logs).is_candidate(emit_trades) {
+                                            metrics().increment_counter("sniffer_non_create_skipped_total");
+                                            return sig;
+                                        }
+
+                                        let (maybe_mint, maybe_creator, log_keys) = parse_pump_logs(&logs);
+                                        let jito = jito_bundle_flag(&account_keys, &log_keys);
                                         if let (Some(mint), Some(creator)) = (maybe_mint, maybe_creator) {
-                                            let _ = cand_tx.send(PremintCandidate {
+                                            emit_candidate(&cand_tx, &creators, &dedup, PremintCandidate {
                                                 mint,
                                                 creator,
-                                                program: program_str.clone(),
+                                                program: program_name.clone(),
                                                 slot,
                                                 timestamp: ts_ms / 1000,
                                                 instruction_summary: Some("HTTP mint".to_string()),
-                                                is_jito_bundle: None,
+                                                is_jito_bundle: jito,
+                                                deadline_ms: candidate_deadline(deadline_budget_ms, ts_ms),
                                             }).await;
                                         }
                                     }
@@ -216,4 +301,32 @@ impl CandidateSource for HttpSource {
     fn request_stop(&self) {
         self.stop_notify.notify_waiters();
     }
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sig(n: u8) -> Signature {
+        Signature::from([n; 64])
+    }
+
+    #[test]
+    fn seen_signatures_dedup_and_evict_oldest() {
+        let mut seen = SeenSignatures::new(3);
+        assert!(seen.insert(sig(1)));
+        assert!(!seen.insert(sig(1)));
+        assert!(seen.insert(sig(2)));
+        assert!(seen.insert(sig(3)));
+
+        // A poll overlapping the previous page only yields the new signatures, once each
+        let page = vec![sig(4), sig(3), sig(4), sig(2), sig(5)];
+        assert_eq!(seen.unseen(page), vec![sig(4), sig(5)]);
+
+        // At capacity the oldest signature is forgotten
+        assert!(seen.insert(sig(4)));
+        assert_eq!(seen.len(), 3);
+        assert!(!seen.contains(&sig(1)));
+        assert!(seen.contains(&sig(2)) && seen.contains(&sig(4)));
+        assert_eq!(seen.unseen(vec![sig(1)]), vec![sig(1)]);
+    }
+}
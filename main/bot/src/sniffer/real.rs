@@ -6,9 +6,91 @@ use solana_client::rpc_config::RpcTransactionConfig;
 use solana_sdk::commitment_config::{CommitmentConfig, CommitmentLevel};
 use solana_sdk::pubkey::Pubkey;
 use solana_sdk::signature::Signature;
-use solana_transaction_status::UiTransactionEncoding;
+use solana_transaction_status::{EncodedTransaction, UiMessage, UiTransactionEncoding};
 use std::str::FromStr;
 
+/// Jito tip payment accounts (mainnet). A transaction touching one of these was
+/// submitted as part of a Jito bundle.
+pub const JITO_TIP_ACCOUNTS: [&str; 8] = [
+    "96gYZGLnJYVFmbjzopPSU6QiEV5fGqZNyN9nmNhvrZU5",
+    "HFqU5x63VTqvQss8hp11i4wVV8bD44PvwucfZ2bU7gRe",
+    "Cw8CFyM9FkoMi7K7Crf6HNQqf4uEMzpKw6QNghXLvLkY",
+    "ADaUMid9yfUytqMBgopwjb2DTLSokTSzL1zt6iGPaS49",
+    "DfXygSm4jCyNCybVYYK6DwvWqjKee8pbDmJGcLWNDXjh",
+    "ADuUkR4vqLUMWXxW9gh6D6L8pMSawimctcNZ5pGwDcEt",
+    "DttWaMuVvTiduZRnguLF7jNxTgiMBZ1hyAumKUiL2KRL",
+    "3AVi9Tg9Uo68tJfuvoKvqKNWKkC5wPdSSdeBnizKZ6jT",
+];
+
+/// True when any of `keys` is a Jito tip account.
+pub fn is_jito_bundle(keys: &[Pubkey]) -> bool {
+    keys.iter().any(|k| JITO_TIP_ACCOUNTS.contains(&k.to_string().as_str()))
+}
+
+/// Jito bundle flag for a candidate: `Some(true)` when a tip account shows up in
+/// `account_keys` or `log_keys`, `Some(false)` only when the transaction's full
+/// `account_keys` were available and none is a tip account, `None` when unknown.
+pub fn jito_bundle_flag(account_keys: &[Pubkey], log_keys: &[Pubkey]) -> Option<bool> {
+    if is_jito_bundle(account_keys) || is_jito_bundle(log_keys) {
+        Some(true)
+    } else if account_keys.is_empty() {
+        None
+    } else {
+        Some(false)
+    }
+}
+
+/// Account keys of a JSON-encoded transaction (empty for other encodings).
+pub fn transaction_account_keys(tx: &EncodedTransaction) -> Vec<Pubkey> {
+    let EncodedTransaction::Json(ui_tx) = tx else {
+        return Vec::new();
+    };
+    let keys: &[String] = match &ui_tx.message {
+        UiMessage::Raw(raw) => &raw.account_keys,
+        UiMessage::Parsed(_) => return Vec::new(),
+    };
+    keys.iter().filter_map(|k| Pubkey::from_str(k).ok()).collect()
+}
+
+/// Kind of pump.fun instruction a transaction's logs describe.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PumpEventKind {
+    Create,
+    Buy,
+    Sell,
+    Unknown,
+}
+
+impl PumpEventKind {
+    /// Whether the event should become a candidate. Only creations are new tokens;
+    /// trades on live tokens pass only when `emit_trades` is set.
+    pub fn is_candidate(self, emit_trades: bool) -> bool {
+        match self {
+            PumpEventKind::Create => true,
+            PumpEventKind::Buy | PumpEventKind::Sell => emit_trades,
+            PumpEventKind::Unknown => false,
+        }
+    }
+}
+
+/// Classify pump.fun logs by their `Program log: Instruction: <Name>` lines.
+/// A creation wins over the dev buy bundled into the same transaction.
+pub fn classify_pump_event(logs: &[String]) -> PumpEventKind {
+    let mut kind = PumpEventKind::Unknown;
+    for line in logs {
+        let Some((_, name)) = line.split_once("Instruction: ") else {
+            continue;
+        };
+        match name.trim() {
+            "Create" | "CreateV2" => return PumpEventKind::Create,
+            "Buy" if kind == PumpEventKind::Unknown => kind = PumpEventKind::Buy,
+            "Sell" if kind == PumpEventKind::Unknown => kind = PumpEventKind::Sell,
+            _ => {}
+        }
+    }
+    kind
+}
+
 /// Extract potential mint and creator from logs using pump.fun-like patterns.
 /// Returns (maybe_mint, maybe_creator, all_pubkeys_seen)
 pub fn parse_pump_logs(logs: &[String]) -> (Option<Pubkey>, Option<Pubkey>, Vec<Pubkey>) {
@@ -118,4 +200,58 @@ pub async fn fetch_meta_from_rpc(
     }
 
     Ok((mint, creator))
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn logs(lines: &[&str]) -> Vec<String> {
+        lines.iter().map(|l| l.to_string()).collect()
+    }
+
+    #[test]
+    fn jito_flag_is_unknown_without_the_account_list() {
+        let tip = Pubkey::from_str(JITO_TIP_ACCOUNTS[0]).unwrap();
+        let other = Pubkey::new_unique();
+        assert_eq!(jito_bundle_flag(&[], &[tip]), Some(true));
+        assert_eq!(jito_bundle_flag(&[other, tip], &[]), Some(true));
+        assert_eq!(jito_bundle_flag(&[other], &[]), Some(false));
+        assert_eq!(jito_bundle_flag(&[], &[other]), None);
+    }
+
+    #[test]
+    fn classifies_create_buy_and_sell_logs() {
+        let create = logs(&[
+            "Program 6EF8rrecthR5Dkzon8Nwu78hRvfCKubJ14M5uBEwF6P invoke [1]",
+            "Program log: Instruction: Create",
+            "Program TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA invoke [2]",
+            "Program log: Instruction: InitializeMint2",
+            "Program log: Instruction: Buy",
+            "Program 6EF8rrecthR5Dkzon8Nwu78hRvfCKubJ14M5uBEwF6P success",
+        ]);
+        let buy = logs(&[
+            "Program 6EF8rrecthR5Dkzon8Nwu78hRvfCKubJ14M5uBEwF6P invoke [1]",
+            "Program log: Instruction: Buy",
+            "Program TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA invoke [2]",
+            "Program log: Instruction: Transfer",
+            "Program 6EF8rrecthR5Dkzon8Nwu78hRvfCKubJ14M5uBEwF6P success",
+        ]);
+        let sell = logs(&[
+            "Program 6EF8rrecthR5Dkzon8Nwu78hRvfCKubJ14M5uBEwF6P invoke [1]",
+            "Program log: Instruction: Sell",
+            "Program log: Instruction: Transfer",
+            "Program 6EF8rrecthR5Dkzon8Nwu78hRvfCKubJ14M5uBEwF6P success",
+        ]);
+
+        assert_eq!(classify_pump_event(&create), PumpEventKind::Create);
+        assert_eq!(classify_pump_event(&buy), PumpEventKind::Buy);
+        assert_eq!(classify_pump_event(&sell), PumpEventKind::Sell);
+        assert_eq!(classify_pump_event(&logs(&["Program log: hello"])), PumpEventKind::Unknown);
+
+        assert!(PumpEventKind::Create.is_candidate(false));
+        assert!(!PumpEventKind::Buy.is_candidate(false));
+        assert!(!PumpEventKind::Sell.is_candidate(false));
+        assert!(PumpEventKind::Sell.is_candidate(true));
+        assert!(!PumpEventKind::Unknown.is_candidate(true));
+    }
+}
@@ -1,14 +1,21 @@
 use std::sync::Arc;
 use tokio::{
-    sync::mpsc::Sender,
+    sync::mpsc::{self, Receiver, Sender},
+    task::JoinHandle,
     time::{self, Duration},
 };
 use tracing::{debug, warn};
 
+use crate::candidate_buffer::{forward_by_policy, new_shared_with_policy, SharedCandidateBuffer};
 use crate::config::Config;
+use crate::sniffer::creator_filter::CreatorFilter;
+#[cfg(feature = "geyser")]
+use crate::sniffer::geyser_source::GeyserSource;
 use crate::sniffer::http_source::HttpSource;
-use crate::sniffer::source::CandidateSource;
+use crate::sniffer::source::{CandidateDedup, CandidateSource};
 use crate::sniffer::wss_source::WssSource;
+#[cfg(feature = "geyser")]
+use crate::sniffer::yellowstone::YellowstoneTransport;
 use crate::types::{PremintCandidate, ProgramLogEvent};
 
 /// Orchestrator that prefers WSS and falls back to HTTP poller on WSS silence/unhealth.
@@ -16,13 +23,44 @@ use crate::types::{PremintCandidate, ProgramLogEvent};
 /// - If WSS is silent longer than cfg.wss_max_silent_ms and fallback is enabled (and not required),
 ///   it starts HTTP poller
 /// - When WSS recovers, it stops HTTP and returns to WSS-only
+/// - With cfg.http_poll_alongside_wss both run from the start and HTTP is never stopped;
+///   their candidates share one channel and one mint dedup
+/// - With cfg.geyser_grpc_url (and the `geyser` feature) a Geyser source runs alongside
+///   WSS the whole time, sharing the same channel and dedup
+///
+/// Aborting the task running `run` also stops every source it spawned, so the
+/// runner can be swapped out at runtime.
 pub struct SnifferRunner {
     cfg: Config,
+    buffer: Option<SharedCandidateBuffer>,
 }
 
+/// Capacity of the channel between the sources and the buffer.
+const FUNNEL_CHANNEL_CAPACITY: usize = 1024;
+
 impl SnifferRunner {
     pub fn new(cfg: Config) -> Self {
-        Self { cfg }
+        Self { cfg, buffer: None }
+    }
+
+    /// Runner for `cfg`, funnelled through a candidate buffer when
+    /// `sniffer_buffer_candidates` is set.
+    pub fn from_config(cfg: Config) -> Self {
+        let buffer = cfg.sniffer_buffer_candidates.then(|| {
+            new_shared_with_policy(
+                Duration::from_millis(cfg.candidate_dedup_ttl_ms),
+                cfg.sniffer_buffer_capacity,
+                cfg.sniffer_buffer_policy,
+            )
+        });
+        Self { cfg, buffer }
+    }
+
+    /// Funnel every source through `buffer` (see `forward_unique`), so TTL dedup,
+    /// capacity eviction and the selection policy apply in one place.
+    pub fn with_candidate_buffer(mut self, buffer: SharedCandidateBuffer) -> Self {
+        self.buffer = Some(buffer);
+        self
     }
 
     pub async fn run(
@@ -30,17 +68,61 @@ impl SnifferRunner {
         cand_tx: Sender<PremintCandidate>,
         raw_log_tx: Option<Sender<ProgramLogEvent>>,
     ) {
-        let wss = Arc::new(WssSource::new(self.cfg.clone()));
-        let http = Arc::new(HttpSource::new(self.cfg.clone()));
+        let mut tasks = SpawnedTasks::default();
+        let cand_tx = match self.buffer.clone() {
+            Some(buffer) => {
+                let (funnel_tx, funnel_rx) = mpsc::channel(FUNNEL_CHANNEL_CAPACITY);
+                tasks.0.push(tokio::spawn(forward_unique(funnel_rx, buffer, cand_tx)));
+                funnel_tx
+            }
+            None => cand_tx,
+        };
+        let creators = Arc::new(CreatorFilter::shared());
+        let dedup = Arc::new(CandidateDedup::from_config(&self.cfg));
+        let wss = Arc::new(
+            WssSource::new(self.cfg.clone())
+                .with_creator_filter(creators.clone())
+                .with_candidate_dedup(dedup.clone()),
+        );
+        #[cfg(feature = "geyser")]
+        if self.cfg.geyser_grpc_url.is_some() {
+            let geyser = GeyserSource::new(self.cfg.clone(), Arc::new(YellowstoneTransport))
+                .with_creator_filter(creators.clone())
+                .with_candidate_dedup(dedup.clone());
+            let cand_tx_geyser = cand_tx.clone();
+            let raw_log_tx_geyser = raw_log_tx.clone();
+            tasks.0.push(tokio::spawn(async move {
+                geyser.run(cand_tx_geyser, raw_log_tx_geyser).await;
+            }));
+        }
+        #[cfg(not(feature = "geyser"))]
+        if self.cfg.geyser_grpc_url.is_some() {
+            warn!(target: "sniffer", "geyser_grpc_url is set but the bot was built without the `geyser` feature; ignoring it");
+        }
+        let http = Arc::new(
+            HttpSource::new(self.cfg.clone())
+                .with_creator_filter(creators)
+                .with_candidate_dedup(dedup),
+        );
 
         // start WSS
         {
             let wss_cloned = wss.clone();
             let cand_tx_wss = cand_tx.clone();
             let raw_log_tx_wss = raw_log_tx.clone();
-            tokio::spawn(async move {
+            tasks.0.push(tokio::spawn(async move {
                 wss_cloned.run(cand_tx_wss, raw_log_tx_wss).await;
-            });
+            }));
+        }
+
+        if self.cfg.http_poll_alongside_wss {
+            let http_cloned = http.clone();
+            let cand_tx_http = cand_tx.clone();
+            let raw_log_tx_http = raw_log_tx.clone();
+            debug!(target:"sniffer", poll_interval_ms = self.cfg.http_poll_interval_ms, "HTTP poller running alongside WSS");
+            tasks.0.push(tokio::spawn(async move {
+                http_cloned.run(cand_tx_http, raw_log_tx_http).await;
+            }));
         }
 
         // watchdog loop
@@ -51,6 +133,11 @@ impl SnifferRunner {
             let wss_ok = wss.is_healthy();
             debug!(target:"sniffer", wss_ok, "Runner watchdog tick");
 
+            if self.cfg.http_poll_alongside_wss {
+                // both sources always on, nothing to switch
+                continue;
+            }
+
             if wss_ok {
                 if http.is_healthy() {
                     // stop HTTP fallback
@@ -68,11 +155,97 @@ impl SnifferRunner {
                 let http_cloned = http.clone();
                 let cand_tx_http = cand_tx.clone();
                 let raw_log_tx_http = raw_log_tx.clone();
-                warn!(target:"sniffer", "Switch: WSS -> HTTP (fallback starting)");
-                tokio::spawn(async move {
+                warn!(target:"sniffer", poll_interval_ms = self.cfg.http_poll_interval_ms, "Switch: WSS -> HTTP (fallback starting)");
+                tasks.0.retain(|task| !task.is_finished());
+                tasks.0.push(tokio::spawn(async move {
                     http_cloned.run(cand_tx_http, raw_log_tx_http).await;
-                });
+                }));
             }
         }
     }
-}
\ No newline at end of file
+}
+
+/// Forward candidates from `rx` to `out` through `buffer`, once per mint within the
+/// buffer's TTL, in the order its policy picks when `out` falls behind. Sniffed
+/// candidates have no score yet, so `highest_score` ranks them by age here; the
+/// quantum selector's scores are applied to its own output (see `forward_by_policy`).
+pub async fn forward_unique(
+    rx: Receiver<PremintCandidate>,
+    buffer: SharedCandidateBuffer,
+    out: Sender<PremintCandidate>,
+) {
+    let seen = CandidateDedup::new(buffer.lock().await.ttl);
+    forward_by_policy(rx, buffer, out, |candidate: &PremintCandidate| {
+        seen.first_seen(&candidate.mint).then(|| (candidate.clone(), 0))
+    })
+    .await
+}
+
+/// Tasks spawned by a runner; aborted when the runner's future is dropped.
+#[derive(Default)]
+struct SpawnedTasks(Vec<JoinHandle<()>>);
+
+impl Drop for SpawnedTasks {
+    fn drop(&mut self) {
+        for task in &self.0 {
+            task.abort();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::candidate_buffer::new_shared;
+    use solana_sdk::pubkey::Pubkey;
+
+    fn candidate(mint: Pubkey, source: &str) -> PremintCandidate {
+        PremintCandidate {
+            mint,
+            creator: Pubkey::new_unique(),
+            program: "pump.fun".to_string(),
+            slot: 1,
+            timestamp: 0,
+            instruction_summary: Some(source.to_string()),
+            is_jito_bundle: None,
+            deadline_ms: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn duplicate_mints_from_two_sources_are_forwarded_once() {
+        let buffer = new_shared(Duration::from_secs(60), 16);
+        let (funnel_tx, funnel_rx) = mpsc::channel(16);
+        let (out_tx, mut out_rx) = mpsc::channel(16);
+        let forwarder = tokio::spawn(forward_unique(funnel_rx, buffer.clone(), out_tx));
+
+        let shared = Pubkey::new_unique();
+        let only_http = Pubkey::new_unique();
+        let sources = [("WSS mint", vec![shared]), ("HTTP mint", vec![shared, only_http])]
+            .into_iter()
+            .map(|(name, mints)| {
+                let tx = funnel_tx.clone();
+                tokio::spawn(async move {
+                    for mint in mints {
+                        tx.send(candidate(mint, name)).await.unwrap();
+                    }
+                })
+            })
+            .collect::<Vec<_>>();
+        for source in sources {
+            source.await.unwrap();
+        }
+        drop(funnel_tx);
+        forwarder.await.unwrap();
+
+        let mut forwarded = Vec::new();
+        while let Some(c) = out_rx.recv().await {
+            forwarded.push(c.mint);
+        }
+        forwarded.sort();
+        let mut expected = vec![shared, only_http];
+        expected.sort();
+        assert_eq!(forwarded, expected);
+        assert!(buffer.lock().await.map.is_empty());
+    }
+}
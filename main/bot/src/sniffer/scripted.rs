@@ -0,0 +1,137 @@
+//! Deterministic sniffer for tests: emits a fixed candidate sequence on a script.
+//!
+//! Emission times are offsets on tokio's clock from when the sniffer is spawned,
+//! so a test running with paused time (`#[tokio::test(start_paused = true)]`)
+//! drives the whole pipeline without real waiting or timing flakiness. The
+//! sender is dropped after the last candidate, which stops a `BuyEngine` reading
+//! from the channel.
+
+use std::time::Duration;
+
+use tokio::task::JoinHandle;
+use tokio::time::{sleep_until, Instant};
+use tracing::{debug, warn};
+
+use crate::types::{CandidateSender, PremintCandidate};
+
+/// One scripted emission: `candidate` is sent `at` after the sniffer starts.
+#[derive(Debug, Clone)]
+pub struct ScriptedCandidate {
+    pub at: Duration,
+    pub candidate: PremintCandidate,
+}
+
+/// Sniffer emitting a scripted candidate sequence.
+#[derive(Debug, Clone, Default)]
+pub struct ScriptedSniffer {
+    script: Vec<ScriptedCandidate>,
+}
+
+impl ScriptedSniffer {
+    /// Emit each entry at its offset; entries are sent in offset order.
+    pub fn new(mut script: Vec<ScriptedCandidate>) -> Self {
+        script.sort_by_key(|entry| entry.at);
+        Self { script }
+    }
+
+    /// Emit `candidates` in order, `interval` apart, starting after one interval.
+    pub fn every(interval: Duration, candidates: Vec<PremintCandidate>) -> Self {
+        Self::new(
+            candidates
+                .into_iter()
+                .zip(1u32..)
+                .map(|(candidate, n)| ScriptedCandidate { at: interval * n, candidate })
+                .collect(),
+        )
+    }
+
+    /// Start emitting to `sender`; the task ends after the last candidate.
+    pub fn spawn(self, sender: CandidateSender) -> JoinHandle<()> {
+        tokio::spawn(async move {
+            let start = Instant::now();
+            for entry in self.script {
+                sleep_until(start + entry.at).await;
+                debug!(mint = %entry.candidate.mint, at = ?entry.at, "Scripted sniffer emitting candidate");
+                if sender.send(entry.candidate).await.is_err() {
+                    warn!("Scripted sniffer receiver closed; stopping");
+                    return;
+                }
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+
+    use solana_sdk::pubkey::Pubkey;
+    use tokio::sync::{mpsc, Mutex};
+
+    use crate::audit::{AuditEvent, AuditLog};
+    use crate::buy_engine::BuyEngine;
+    use crate::config::Config;
+    use crate::nonce_manager::NonceManager;
+    use crate::test_support::CountingBroadcaster;
+    use crate::types::{AppState, Mode};
+
+    fn candidate(program: &str) -> PremintCandidate {
+        PremintCandidate {
+            mint: Pubkey::new_unique(),
+            creator: Pubkey::new_unique(),
+            program: program.to_string(),
+            slot: 0,
+            timestamp: 0,
+            instruction_summary: None,
+            is_jito_bundle: None,
+            deadline_ms: None,
+        }
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn scripted_sequence_drives_engine_deterministically() {
+        // Filtered out, then bought, then ignored while the position is held
+        let filtered = candidate("orca");
+        let bought = candidate("pump.fun");
+        let ignored = candidate("pump.fun");
+        let sniffer = ScriptedSniffer::every(
+            Duration::from_millis(100),
+            vec![filtered, bought.clone(), ignored],
+        );
+
+        let dir = tempfile::tempdir().unwrap();
+        let audit_path = dir.path().join("audit.jsonl");
+        let app_state = Arc::new(Mutex::new(AppState::sniffing()));
+        let broadcaster = Arc::new(CountingBroadcaster::new(0x6b));
+        let (tx, rx) = mpsc::channel(8);
+        let mut engine = BuyEngine::new(
+            broadcaster.clone(),
+            Arc::new(NonceManager::new(1)),
+            rx,
+            app_state.clone(),
+            Config { nonce_count: 1, ..Config::default() },
+            None,
+        )
+        .with_audit_log(Arc::new(AuditLog::open(&audit_path).unwrap()));
+
+        sniffer.spawn(tx);
+        // Returns once the script ends and the channel closes
+        engine.run().await;
+
+        assert_eq!(broadcaster.calls(), 1);
+        let transitions: Vec<_> = AuditLog::read_entries(&audit_path)
+            .unwrap()
+            .into_iter()
+            .filter_map(|entry| match entry.event {
+                AuditEvent::ModeTransition { from, to } => Some((from, to)),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(transitions, vec![("sniffing".to_string(), format!("passive_token:{}", bought.mint))]);
+
+        let st = app_state.lock().await;
+        assert!(matches!(st.mode, Mode::PassiveToken(m) if m == bought.mint));
+        assert_eq!(st.active_token.as_ref().map(|c| c.mint), Some(bought.mint));
+    }
+}
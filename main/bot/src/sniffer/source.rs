@@ -1,8 +1,15 @@
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
 use async_trait::async_trait;
 use solana_sdk::pubkey::Pubkey;
 use tokio::sync::mpsc::Sender;
 
-use crate::types::{PremintCandidate, ProgramLogEvent};
+use crate::config::Config;
+use crate::metrics::metrics;
+use crate::sniffer::creator_filter::CreatorFilter;
+use crate::types::{DexProgram, PremintCandidate, ProgramLogEvent, PUMP_FUN_PROGRAM_ID};
 
 #[async_trait]
 pub trait CandidateSource: Send + Sync {
@@ -18,9 +25,292 @@ pub trait CandidateSource: Send + Sync {
 }
 
 // Pump.fun program (constant)
-pub const PUMP_FUN_PROGRAM: &str = "6EF8rrecthR5Dkzon8Nwu78hRvfCKubJ14M5uBEwF6P";
+pub const PUMP_FUN_PROGRAM: &str = PUMP_FUN_PROGRAM_ID;
 pub fn pump_fun_program_pk() -> Pubkey {
     PUMP_FUN_PROGRAM
         .parse::<Pubkey>()
         .expect("invalid pump.fun program id")
-}
\ No newline at end of file
+}
+
+/// Canonical program name for candidates emitted from logs of the given program id.
+pub fn candidate_program_name(cfg: &Config, program: &Pubkey) -> String {
+    DexProgram::normalize(&program.to_string(), &cfg.program_aliases)
+        .canonical_name()
+        .to_string()
+}
+
+/// Ingest deadline for a candidate seen at `ingest_ms`, or `None` when `budget_ms` is 0.
+pub fn candidate_deadline(budget_ms: u64, ingest_ms: u64) -> Option<u64> {
+    (budget_ms > 0).then(|| ingest_ms.saturating_add(budget_ms))
+}
+
+/// Send `candidate` unless its creator is filtered out or its mint was already
+/// emitted by any source sharing `dedup`. Returns true when sent; a failed send
+/// releases the mint so a later delivery of it is not suppressed.
+pub async fn emit_candidate(
+    cand_tx: &Sender<PremintCandidate>,
+    creators: &CreatorFilter,
+    dedup: &CandidateDedup,
+    candidate: PremintCandidate,
+) -> bool {
+    if !creators.admit(&candidate.creator) {
+        return false;
+    }
+    if !dedup.first_seen(&candidate.mint) {
+        metrics().increment_counter("sniffer_duplicate_candidates_total");
+        return false;
+    }
+    let mint = candidate.mint;
+    if cand_tx.send(candidate).await.is_err() {
+        dedup.forget(&mint);
+        return false;
+    }
+    true
+}
+
+/// Mints recently emitted by any source, shared between WSS and the HTTP fallback
+/// so a mint delivered by both while they overlap becomes a single candidate.
+#[derive(Debug)]
+pub struct CandidateDedup {
+    ttl: Duration,
+    seen: Mutex<HashMap<Pubkey, Instant>>,
+}
+
+impl CandidateDedup {
+    pub fn new(ttl: Duration) -> Self {
+        Self { ttl, seen: Mutex::new(HashMap::new()) }
+    }
+
+    pub fn from_config(cfg: &Config) -> Self {
+        Self::new(Duration::from_millis(cfg.candidate_dedup_ttl_ms))
+    }
+
+    /// Record `mint`; true unless it was already recorded within the TTL.
+    pub fn first_seen(&self, mint: &Pubkey) -> bool {
+        let now = Instant::now();
+        let mut seen = self.seen.lock().unwrap_or_else(|e| e.into_inner());
+        seen.retain(|_, at| now.duration_since(*at) < self.ttl);
+        if seen.contains_key(mint) {
+            return false;
+        }
+        seen.insert(*mint, now);
+        true
+    }
+
+    /// Drop the record of `mint`, e.g. when the candidate it admitted was never delivered.
+    pub fn forget(&self, mint: &Pubkey) {
+        self.seen.lock().unwrap_or_else(|e| e.into_inner()).remove(mint);
+    }
+}
+
+/// Skips backlog events delivered right after a (re)connect.
+///
+/// For `window` after creation, events whose slot is more than `max_slot_lag` behind
+/// the reference slot (current slot at connect, raised by any newer slot seen) are skipped.
+#[derive(Debug, Clone)]
+pub struct WarmupFilter {
+    started: Instant,
+    window: Duration,
+    max_slot_lag: u64,
+    reference_slot: u64,
+}
+
+impl WarmupFilter {
+    pub fn new(window: Duration, max_slot_lag: u64, current_slot: Option<u64>) -> Self {
+        Self {
+            started: Instant::now(),
+            window,
+            max_slot_lag,
+            reference_slot: current_slot.unwrap_or(0),
+        }
+    }
+
+    pub fn should_skip(&mut self, slot: u64) -> bool {
+        self.should_skip_at(slot, Instant::now())
+    }
+
+    fn should_skip_at(&mut self, slot: u64, now: Instant) -> bool {
+        self.reference_slot = self.reference_slot.max(slot);
+        if now.duration_since(self.started) >= self.window {
+            return false;
+        }
+        self.reference_slot - slot > self.max_slot_lag
+    }
+}
+
+/// Reconnect delays for a streaming source.
+///
+/// The delay doubles per failure up to `max`. A connection only resets it when it
+/// stayed up for at least `min_lifetime`; one that drops sooner counts as a failure,
+/// so a flapping endpoint is not reconnected to in a tight loop.
+#[derive(Debug, Clone)]
+pub struct ReconnectBackoff {
+    initial: Duration,
+    max: Duration,
+    min_lifetime: Duration,
+    current: Duration,
+}
+
+impl ReconnectBackoff {
+    pub fn new(initial: Duration, max: Duration, min_lifetime: Duration) -> Self {
+        Self { initial, max, min_lifetime, current: initial }
+    }
+
+    /// Delay before the next attempt; doubles the following one.
+    pub fn next_delay(&mut self) -> Duration {
+        let delay = self.current;
+        self.current = self.current.saturating_mul(2).min(self.max.max(self.initial));
+        delay
+    }
+
+    /// Record a connection that ended after `lifetime`. Returns true when it was
+    /// long-lived enough to reset the backoff.
+    pub fn connection_closed(&mut self, lifetime: Duration) -> bool {
+        let healthy = lifetime >= self.min_lifetime;
+        if healthy {
+            self.current = self.initial;
+        }
+        healthy
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashSet;
+    use tokio::sync::mpsc;
+
+    fn candidate(creator: Pubkey) -> PremintCandidate {
+        PremintCandidate {
+            mint: Pubkey::new_unique(),
+            creator,
+            program: "pump.fun".to_string(),
+            slot: 1,
+            timestamp: 0,
+            instruction_summary: None,
+            is_jito_bundle: None,
+            deadline_ms: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn blocklisted_creator_is_never_emitted() {
+        let rugger = Pubkey::new_unique();
+        let honest = Pubkey::new_unique();
+        let creators = CreatorFilter::new(HashSet::from([rugger]), HashSet::new(), false);
+        let dedup = CandidateDedup::new(Duration::from_secs(60));
+        let (tx, mut rx) = mpsc::channel(8);
+
+        assert!(!emit_candidate(&tx, &creators, &dedup, candidate(rugger)).await);
+        assert!(emit_candidate(&tx, &creators, &dedup, candidate(honest)).await);
+        assert!(!emit_candidate(&tx, &creators, &dedup, candidate(rugger)).await);
+        drop(tx);
+
+        let mut emitted = Vec::new();
+        while let Some(c) = rx.recv().await {
+            emitted.push(c.creator);
+        }
+        assert_eq!(emitted, vec![honest]);
+
+        // Allowlist-only: unlisted creators are dropped, allowlisted ones pass
+        let strict = CreatorFilter::new(HashSet::new(), HashSet::from([honest]), true);
+        let (tx, _rx) = mpsc::channel(8);
+        assert!(emit_candidate(&tx, &strict, &dedup, candidate(honest)).await);
+        assert!(!emit_candidate(&tx, &strict, &dedup, candidate(Pubkey::new_unique())).await);
+    }
+
+    #[tokio::test]
+    async fn overlapping_wss_and_http_delivery_yields_one_candidate() {
+        let creators = CreatorFilter::new(HashSet::new(), HashSet::new(), false);
+        let dedup = CandidateDedup::new(Duration::from_secs(60));
+        let (tx, mut rx) = mpsc::channel(8);
+
+        let from_wss = PremintCandidate {
+            instruction_summary: Some("WSS mint".to_string()),
+            ..candidate(Pubkey::new_unique())
+        };
+        let from_http = PremintCandidate {
+            instruction_summary: Some("HTTP mint".to_string()),
+            ..from_wss.clone()
+        };
+
+        assert!(emit_candidate(&tx, &creators, &dedup, from_wss.clone()).await);
+        assert!(!emit_candidate(&tx, &creators, &dedup, from_http).await);
+        drop(tx);
+
+        let mut emitted = Vec::new();
+        while let Some(c) = rx.recv().await {
+            emitted.push(c);
+        }
+        assert_eq!(emitted, vec![from_wss]);
+    }
+
+    #[tokio::test]
+    async fn failed_send_does_not_suppress_a_later_delivery() {
+        let creators = CreatorFilter::new(HashSet::new(), HashSet::new(), false);
+        let dedup = CandidateDedup::new(Duration::from_secs(60));
+        let mint_candidate = candidate(Pubkey::new_unique());
+
+        let (closed_tx, closed_rx) = mpsc::channel(8);
+        drop(closed_rx);
+        assert!(!emit_candidate(&closed_tx, &creators, &dedup, mint_candidate.clone()).await);
+
+        let (tx, mut rx) = mpsc::channel(8);
+        assert!(emit_candidate(&tx, &creators, &dedup, mint_candidate.clone()).await);
+        assert_eq!(rx.recv().await, Some(mint_candidate));
+    }
+
+    #[test]
+    fn warmup_skips_stale_slots_only_during_window() {
+        let mut filter = WarmupFilter::new(Duration::from_secs(5), 10, Some(1_000));
+        let start = filter.started;
+
+        // Backlog right after connect
+        assert!(filter.should_skip_at(900, start));
+        assert!(filter.should_skip_at(989, start + Duration::from_secs(1)));
+        // Within the allowed lag
+        assert!(!filter.should_skip_at(990, start + Duration::from_secs(1)));
+        assert!(!filter.should_skip_at(1_001, start + Duration::from_secs(1)));
+
+        // Warm-up over: old slots pass through again
+        assert!(!filter.should_skip_at(900, start + Duration::from_secs(5)));
+    }
+
+    #[test]
+    fn warmup_uses_newest_seen_slot_without_reference() {
+        let mut filter = WarmupFilter::new(Duration::from_secs(5), 10, None);
+        let start = filter.started;
+
+        assert!(!filter.should_skip_at(500, start));
+        assert!(!filter.should_skip_at(600, start));
+        assert!(filter.should_skip_at(550, start));
+    }
+
+    #[test]
+    fn rapid_connect_drop_cycles_engage_backoff() {
+        let mut backoff = ReconnectBackoff::new(
+            Duration::from_millis(500),
+            Duration::from_millis(4_000),
+            Duration::from_secs(5),
+        );
+
+        // Connect, drop within 100ms, repeat: delays keep growing to the cap
+        let delays: Vec<u64> = (0..5)
+            .map(|_| {
+                assert!(!backoff.connection_closed(Duration::from_millis(100)));
+                backoff.next_delay().as_millis() as u64
+            })
+            .collect();
+        assert_eq!(delays, vec![500, 1_000, 2_000, 4_000, 4_000]);
+
+        // A connection that stayed up resets to the initial delay
+        assert!(backoff.connection_closed(Duration::from_secs(6)));
+        assert_eq!(backoff.next_delay(), Duration::from_millis(500));
+    }
+
+    #[test]
+    fn zero_window_disables_warmup() {
+        let mut filter = WarmupFilter::new(Duration::ZERO, 10, Some(1_000));
+        assert!(!filter.should_skip(1));
+    }
+}
@@ -0,0 +1,147 @@
+//! Owns the running sniffer source and swaps it at runtime (e.g. Real -> Mock
+//! during an incident) without restarting the bot.
+
+use tokio::sync::{mpsc::Sender, Mutex};
+use tokio::task::JoinHandle;
+use tracing::info;
+
+use crate::candidate_buffer::SharedCandidateBuffer;
+use crate::config::{Config, SnifferMode};
+use crate::metrics::metrics;
+use crate::sniffer::{run_mock_sniffer_with, MockSnifferOptions};
+use crate::sniffer::runner::SnifferRunner;
+use crate::types::{CandidateSender, ProgramLogEvent};
+
+struct ActiveSniffer {
+    label: String,
+    task: JoinHandle<()>,
+}
+
+/// Runs at most one sniffer source at a time, all feeding the same candidate channel.
+pub struct SnifferSupervisor {
+    cfg: Config,
+    cand_tx: CandidateSender,
+    raw_log_tx: Option<Sender<ProgramLogEvent>>,
+    /// Buffer every real runner funnels through, kept across source switches
+    buffer: Option<SharedCandidateBuffer>,
+    active: Mutex<Option<ActiveSniffer>>,
+}
+
+impl SnifferSupervisor {
+    pub fn new(cfg: Config, cand_tx: CandidateSender, raw_log_tx: Option<Sender<ProgramLogEvent>>) -> Self {
+        Self { cfg, cand_tx, raw_log_tx, buffer: None, active: Mutex::new(None) }
+    }
+
+    /// Funnel real sources through `buffer` instead of a fresh one per runner.
+    pub fn with_candidate_buffer(mut self, buffer: SharedCandidateBuffer) -> Self {
+        self.buffer = Some(buffer);
+        self
+    }
+
+    /// Stop the current source (if any) and start the one for `mode`.
+    pub async fn switch_mode(&self, mode: SnifferMode) {
+        match mode {
+            SnifferMode::Mock => {
+                let options = MockSnifferOptions::from_config(&self.cfg);
+                self.switch_to("mock", move |cand_tx| run_mock_sniffer_with(cand_tx, options)).await
+            }
+            SnifferMode::Real => {
+                let mut runner = SnifferRunner::from_config(self.cfg.clone());
+                if let Some(buffer) = &self.buffer {
+                    runner = runner.with_candidate_buffer(buffer.clone());
+                }
+                let raw_log_tx = self.raw_log_tx.clone();
+                self.switch_to("real", move |cand_tx| {
+                    tokio::spawn(async move { runner.run(cand_tx, raw_log_tx).await })
+                })
+                .await
+            }
+        }
+    }
+
+    /// Stop the current source (if any) and start `spawn` under `label`. The new
+    /// source only starts once the old one's task has ended.
+    pub async fn switch_to<F>(&self, label: &str, spawn: F)
+    where
+        F: FnOnce(CandidateSender) -> JoinHandle<()>,
+    {
+        let mut active = self.active.lock().await;
+        if let Some(previous) = active.take() {
+            previous.task.abort();
+            let _ = previous.task.await;
+            info!(from = %previous.label, to = %label, "Switching sniffer source");
+            metrics().increment_counter("sniffer_switches_total");
+        } else {
+            info!(source = %label, "Starting sniffer source");
+        }
+        *active = Some(ActiveSniffer { label: label.to_string(), task: spawn(self.cand_tx.clone()) });
+    }
+
+    /// Label of the running source, `None` when stopped.
+    pub async fn current(&self) -> Option<String> {
+        self.active.lock().await.as_ref().map(|active| active.label.clone())
+    }
+
+    /// Stop the running source.
+    pub async fn stop(&self) {
+        if let Some(previous) = self.active.lock().await.take() {
+            previous.task.abort();
+            let _ = previous.task.await;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    use solana_sdk::pubkey::Pubkey;
+    use tokio::sync::mpsc;
+
+    use crate::sniffer::scripted::ScriptedSniffer;
+    use crate::types::PremintCandidate;
+
+    fn candidate() -> PremintCandidate {
+        PremintCandidate {
+            mint: Pubkey::new_unique(),
+            creator: Pubkey::new_unique(),
+            program: "pump.fun".to_string(),
+            slot: 0,
+            timestamp: 0,
+            instruction_summary: Some("scripted".to_string()),
+            is_jito_bundle: None,
+            deadline_ms: None,
+        }
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn switches_from_mock_to_scripted_at_runtime() {
+        let (tx, mut rx) = mpsc::channel(16);
+        let supervisor = SnifferSupervisor::new(Config::default(), tx, None);
+
+        supervisor.switch_mode(SnifferMode::Mock).await;
+        assert_eq!(supervisor.current().await.as_deref(), Some("mock"));
+        let mocked = rx.recv().await.unwrap();
+        assert_eq!(mocked.instruction_summary.as_deref(), Some("Mock candidate"));
+
+        let scripted = vec![candidate(), candidate()];
+        let sniffer = ScriptedSniffer::every(Duration::from_millis(100), scripted.clone());
+        supervisor.switch_to("scripted", |tx| sniffer.spawn(tx)).await;
+        assert_eq!(supervisor.current().await.as_deref(), Some("scripted"));
+
+        // Anything the mock queued before it stopped, then only scripted candidates
+        let mut received = Vec::new();
+        while received.len() < scripted.len() {
+            let c = rx.recv().await.unwrap();
+            if c.instruction_summary.as_deref() != Some("Mock candidate") {
+                received.push(c);
+            }
+        }
+        assert_eq!(received, scripted);
+
+        // The mock is gone: nothing else arrives
+        tokio::time::sleep(Duration::from_secs(5)).await;
+        assert!(rx.try_recv().is_err());
+    }
+}
@@ -12,12 +12,19 @@ use tokio::{
 use tracing::{debug, error, info, warn};
 
 use solana_client::nonblocking::pubsub_client::PubsubClient;
+use solana_client::nonblocking::rpc_client::RpcClient;
 use solana_client::rpc_config::{RpcTransactionLogsConfig, RpcTransactionLogsFilter};
 use solana_sdk::commitment_config::{CommitmentConfig, CommitmentLevel};
 
 use crate::config::Config;
-use crate::sniffer::real::{fetch_meta_from_rpc, parse_pump_logs};
-use crate::sniffer::source::{pump_fun_program_pk, CandidateSource};
+use crate::metrics::metrics;
+use crate::sniffer::creator_filter::CreatorFilter;
+use crate::sniffer::real::{classify_pump_event, fetch_meta_from_rpc, jito_bundle_flag, parse_pump_logs};
+use crate::sniffer::source::{
+    candidate_deadline, candidate_program_name, emit_candidate, pump_fun_program_pk, CandidateDedup,
+    CandidateSource,
+    ReconnectBackoff, WarmupFilter,
+};
 use crate::time_utils::now_ms;
 use crate::types::{PremintCandidate, ProgramLogEvent};
 
@@ -25,17 +32,35 @@ pub struct WssSource {
     cfg: Config,
     last_heartbeat: Arc<RwLock<Instant>>,
     stop_notify: Arc<Notify>,
+    creators: Arc<CreatorFilter>,
+    dedup: Arc<CandidateDedup>,
 }
 
 impl WssSource {
     pub fn new(cfg: Config) -> Self {
+        let creators = Arc::new(CreatorFilter::shared());
+        let dedup = Arc::new(CandidateDedup::from_config(&cfg));
         Self {
             cfg,
             last_heartbeat: Arc::new(RwLock::new(Instant::now())),
             stop_notify: Arc::new(Notify::new()),
+            creators,
+            dedup,
         }
     }
 
+    /// Share `creators` with other sources so list updates reach all of them.
+    pub fn with_creator_filter(mut self, creators: Arc<CreatorFilter>) -> Self {
+        self.creators = creators;
+        self
+    }
+
+    /// Share `dedup` with other sources so a mint they both deliver is emitted once.
+    pub fn with_candidate_dedup(mut self, dedup: Arc<CandidateDedup>) -> Self {
+        self.dedup = dedup;
+        self
+    }
+
     fn update_heartbeat(&self) {
         let lh = self.last_heartbeat.clone();
         tokio::spawn(async move {
@@ -47,6 +72,25 @@ impl WssSource {
         Duration::from_millis(self.cfg.wss_max_silent_ms)
     }
 
+    /// Warm-up filter for a fresh connection, anchored at the current slot when available.
+    async fn warmup_filter(&self) -> WarmupFilter {
+        let current_slot = match self.cfg.rpc_endpoints.first() {
+            Some(endpoint) if self.cfg.wss_warmup_ms > 0 => {
+                RpcClient::new_with_commitment(endpoint.clone(), self.commitment_config())
+                    .get_slot()
+                    .await
+                    .map_err(|e| debug!(target: "sniffer", error = %e, "WSS warm-up: current slot unavailable"))
+                    .ok()
+            }
+            _ => None,
+        };
+        WarmupFilter::new(
+            Duration::from_millis(self.cfg.wss_warmup_ms),
+            self.cfg.wss_warmup_max_slot_lag,
+            current_slot,
+        )
+    }
+
     fn commitment_config(&self) -> CommitmentConfig {
         let level = match self
             .cfg
@@ -85,8 +129,12 @@ impl CandidateSource for WssSource {
         }
 
         let program = pump_fun_program_pk();
-        let mut backoff = self.cfg.wss_reconnect_backoff_ms;
-        let max_backoff = self.cfg.wss_reconnect_backoff_max_ms;
+        let program_name = candidate_program_name(&self.cfg, &program);
+        let mut backoff = ReconnectBackoff::new(
+            Duration::from_millis(self.cfg.wss_reconnect_backoff_ms),
+            Duration::from_millis(self.cfg.wss_reconnect_backoff_max_ms),
+            Duration::from_millis(self.cfg.wss_min_connection_lifetime_ms),
+        );
 
         loop {
             let notified = self.stop_notify.notified();
@@ -110,14 +158,14 @@ impl CandidateSource for WssSource {
                         Ok((s, u)) => (s, u),
                         Err(e) => {
                             error!(target: "sniffer", ?e, "logs_subscribe failed");
-                            time::sleep(Duration::from_millis(backoff)).await;
-                            backoff = (backoff.saturating_mul(2)).min(max_backoff);
+                            time::sleep(backoff.next_delay()).await;
                             continue;
                         }
                     };
 
                     self.update_heartbeat();
-                    backoff = self.cfg.wss_reconnect_backoff_ms;
+                    let connected_at = Instant::now();
+                    let mut warmup = self.warmup_filter().await;
 
                     loop {
                         tokio::select! {
@@ -146,7 +194,21 @@ impl CandidateSource for WssSource {
                                             }).await;
                                         }
 
-                                        let (maybe_mint, maybe_creator, _keys) = parse_pump_logs(&logs);
+                                        if warmup.should_skip(slot) {
+                                            metrics().increment_counter("sniffer_warmup_skipped_total");
+                                            debug!(target: "sniffer", slot, "WSS warm-up: skipping backlog event");
+                                            continue;
+                                        }
+
+                                        let kind = classify_pump_event(&logs);
+                                        if !kind.is_candidate(self.cfg.sniffer_emit_trades) {
+                                            metrics().increment_counter("sniffer_non_create_skipped_total");
+                                            continue;
+                                        }
+
+                                        let (maybe_mint, maybe_creator, keys) = parse_pump_logs(&logs);
+                                        // Log notifications carry no account list, so only a tip is conclusive
+                                        let jito = jito_bundle_flag(&[], &keys);
                                         if maybe_mint.is_none() || maybe_creator.is_none() {
                                             if self.cfg.meta_fetch_enabled {
                                                 if let Ok((m, c)) = fetch_meta_from_rpc(
@@ -155,14 +217,15 @@ impl CandidateSource for WssSource {
                                                     self.cfg.meta_fetch_commitment.as_deref().unwrap_or("confirmed"),
                                                 ).await {
                                                     if let (Some(mint), Some(creator)) = (m, c) {
-                                                        let _ = cand_tx.send(PremintCandidate {
+                                                        emit_candidate(&cand_tx, &self.creators, &self.dedup, PremintCandidate {
                                                             mint,
                                                             creator,
-                                                            program: program.to_string(),
+                                                            program: program_name.clone(),
                                                             slot,
                                                             timestamp: ts_ms / 1000,
                                                             instruction_summary: Some("WSS mint".to_string()),
-                                                            is_jito_bundle: None,
+                                                            is_jito_bundle: jito,
+                                                            deadline_ms: candidate_deadline(self.cfg.candidate_deadline_ms, ts_ms),
                                                         }).await;
                                                         continue;
                                                     }
@@ -171,14 +234,15 @@ impl CandidateSource for WssSource {
                                             continue;
                                         }
 
-                                        let _ = cand_tx.send(PremintCandidate {
+                                        emit_candidate(&cand_tx, &self.creators, &self.dedup, PremintCandidate {
                                             mint: maybe_mint.unwrap(),
                                             creator: maybe_creator.unwrap(),
-                                            program: program.to_string(),
+                                            program: program_name.clone(),
                                             slot,
                                             timestamp: ts_ms / 1000,
                                             instruction_summary: Some("WSS mint".to_string()),
-                                            is_jito_bundle: None,
+                                            is_jito_bundle: jito,
+                                            deadline_ms: candidate_deadline(self.cfg.candidate_deadline_ms, ts_ms),
                                         }).await;
                                     }
                                     None => {
@@ -197,14 +261,19 @@ impl CandidateSource for WssSource {
                             }
                         }
                     }
+
+                    let lifetime = connected_at.elapsed();
+                    if !backoff.connection_closed(lifetime) {
+                        metrics().increment_counter("wss_short_lived_connections_total");
+                        warn!(target: "sniffer", lifetime_ms = lifetime.as_millis() as u64, "WSS connection dropped early; backing off");
+                    }
                 }
                 Err(e) => {
                     error!(target: "sniffer", ?e, "WSS connect failed");
                 }
             }
 
-            time::sleep(Duration::from_millis(backoff)).await;
-            backoff = (backoff.saturating_mul(2)).min(max_backoff);
+            time::sleep(backoff.next_delay()).await;
         }
     }
 
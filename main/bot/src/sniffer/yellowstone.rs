@@ -0,0 +1,285 @@
+//! Yellowstone gRPC client behind `GeyserTransport`.
+//!
+//! Only the parts of `geyser.proto` / `solana-storage.proto` the source reads are
+//! declared; prost skips the other fields of each message.
+
+use std::{collections::HashMap, time::Duration};
+
+use anyhow::Context;
+use async_trait::async_trait;
+use futures::channel::mpsc::{unbounded, UnboundedSender};
+use solana_sdk::pubkey::Pubkey;
+use tokio::sync::mpsc::{self, Receiver};
+use tonic::codec::ProstCodec;
+use tonic::codegen::http::uri::PathAndQuery;
+use tonic::metadata::MetadataValue;
+use tonic::transport::{ClientTlsConfig, Endpoint};
+use tracing::{debug, warn};
+
+use crate::sniffer::geyser_source::{GeyserTransport, GeyserTxUpdate, GeyserUpdate};
+
+const SUBSCRIBE_PATH: &str = "/geyser.Geyser/Subscribe";
+/// Filter name the transaction subscription is registered under.
+const FILTER_NAME: &str = "pump";
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(10);
+/// Capacity of the channel between the gRPC stream and the source.
+const UPDATE_CHANNEL_CAPACITY: usize = 1024;
+/// `CommitmentLevel::PROCESSED`: candidates are worth most before confirmation.
+const COMMITMENT_PROCESSED: i32 = 0;
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct SubscribeRequest {
+    #[prost(map = "string, message", tag = "3")]
+    pub transactions: HashMap<String, SubscribeRequestFilterTransactions>,
+    #[prost(int32, optional, tag = "6")]
+    pub commitment: Option<i32>,
+    #[prost(message, optional, tag = "9")]
+    pub ping: Option<SubscribeRequestPing>,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct SubscribeRequestFilterTransactions {
+    #[prost(bool, optional, tag = "1")]
+    pub vote: Option<bool>,
+    #[prost(bool, optional, tag = "2")]
+    pub failed: Option<bool>,
+    #[prost(string, repeated, tag = "3")]
+    pub account_include: Vec<String>,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct SubscribeRequestPing {
+    #[prost(int32, tag = "1")]
+    pub id: i32,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct SubscribeUpdate {
+    #[prost(oneof = "UpdateOneof", tags = "4, 6")]
+    pub update_oneof: Option<UpdateOneof>,
+}
+
+#[derive(Clone, PartialEq, prost::Oneof)]
+pub enum UpdateOneof {
+    #[prost(message, tag = "4")]
+    Transaction(SubscribeUpdateTransaction),
+    #[prost(message, tag = "6")]
+    Ping(SubscribeUpdatePing),
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct SubscribeUpdatePing {}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct SubscribeUpdateTransaction {
+    #[prost(message, optional, tag = "1")]
+    pub transaction: Option<SubscribeUpdateTransactionInfo>,
+    #[prost(uint64, tag = "2")]
+    pub slot: u64,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct SubscribeUpdateTransactionInfo {
+    #[prost(bytes = "vec", tag = "1")]
+    pub signature: Vec<u8>,
+    #[prost(message, optional, tag = "3")]
+    pub transaction: Option<Transaction>,
+    #[prost(message, optional, tag = "4")]
+    pub meta: Option<TransactionStatusMeta>,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct Transaction {
+    #[prost(message, optional, tag = "2")]
+    pub message: Option<Message>,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct Message {
+    #[prost(bytes = "vec", repeated, tag = "2")]
+    pub account_keys: Vec<Vec<u8>>,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct TransactionStatusMeta {
+    #[prost(message, optional, tag = "1")]
+    pub err: Option<TransactionError>,
+    #[prost(string, repeated, tag = "6")]
+    pub log_messages: Vec<String>,
+    #[prost(bytes = "vec", repeated, tag = "12")]
+    pub loaded_writable_addresses: Vec<Vec<u8>>,
+    #[prost(bytes = "vec", repeated, tag = "13")]
+    pub loaded_readonly_addresses: Vec<Vec<u8>>,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct TransactionError {
+    #[prost(bytes = "vec", tag = "1")]
+    pub err: Vec<u8>,
+}
+
+/// Request subscribing to successful and failed non-vote transactions mentioning `program`.
+pub fn subscribe_request(program: &Pubkey) -> SubscribeRequest {
+    let filter = SubscribeRequestFilterTransactions {
+        vote: Some(false),
+        failed: None,
+        account_include: vec![program.to_string()],
+    };
+    SubscribeRequest {
+        transactions: HashMap::from([(FILTER_NAME.to_string(), filter)]),
+        commitment: Some(COMMITMENT_PROCESSED),
+        ping: None,
+    }
+}
+
+/// Decode a stream message; `None` for updates the source does not use.
+pub fn decode_update(update: SubscribeUpdate) -> Option<GeyserUpdate> {
+    match update.update_oneof? {
+        UpdateOneof::Ping(_) => Some(GeyserUpdate::Ping),
+        UpdateOneof::Transaction(tx) => {
+            let info = tx.transaction?;
+            let meta = info.meta.unwrap_or_default();
+            let static_keys = info
+                .transaction
+                .and_then(|t| t.message)
+                .map(|m| m.account_keys)
+                .unwrap_or_default();
+            let account_keys = static_keys
+                .iter()
+                .chain(&meta.loaded_writable_addresses)
+                .chain(&meta.loaded_readonly_addresses)
+                .filter_map(|key| Pubkey::try_from(key.as_slice()).ok())
+                .collect();
+            Some(GeyserUpdate::Transaction(GeyserTxUpdate {
+                slot: tx.slot,
+                signature: bs58::encode(&info.signature).into_string(),
+                account_keys,
+                log_messages: meta.log_messages,
+                failed: meta.err.is_some(),
+            }))
+        }
+    }
+}
+
+/// `GeyserTransport` over a Yellowstone gRPC `Subscribe` stream. `https` URLs use
+/// TLS with the platform roots.
+#[derive(Debug, Default, Clone)]
+pub struct YellowstoneTransport;
+
+#[async_trait]
+impl GeyserTransport for YellowstoneTransport {
+    async fn subscribe(
+        &self,
+        url: &str,
+        x_token: Option<&str>,
+        program: &Pubkey,
+    ) -> anyhow::Result<Receiver<GeyserUpdate>> {
+        let mut endpoint = Endpoint::from_shared(url.to_string())
+            .context("invalid geyser_grpc_url")?
+            .connect_timeout(CONNECT_TIMEOUT)
+            .tcp_nodelay(true);
+        if url.starts_with("https://") {
+            endpoint = endpoint.tls_config(ClientTlsConfig::new().with_enabled_roots())?;
+        }
+        let mut grpc = tonic::client::Grpc::new(endpoint.connect().await?);
+        grpc.ready().await.context("geyser channel not ready")?;
+
+        let (request_tx, request_rx) = unbounded();
+        request_tx.unbounded_send(subscribe_request(program))?;
+        let mut request = tonic::Request::new(request_rx);
+        if let Some(token) = x_token {
+            let token: MetadataValue<_> = token.parse().context("invalid geyser_x_token")?;
+            request.metadata_mut().insert("x-token", token);
+        }
+        let mut stream = grpc
+            .streaming(
+                request,
+                PathAndQuery::from_static(SUBSCRIBE_PATH),
+                ProstCodec::<SubscribeRequest, SubscribeUpdate>::default(),
+            )
+            .await?
+            .into_inner();
+
+        let (update_tx, update_rx) = mpsc::channel(UPDATE_CHANNEL_CAPACITY);
+        tokio::spawn(async move {
+            loop {
+                let update = match stream.message().await {
+                    Ok(Some(update)) => update,
+                    Ok(None) => break,
+                    Err(status) => {
+                        warn!(target: "sniffer", %status, "Geyser stream error");
+                        break;
+                    }
+                };
+                let Some(update) = decode_update(update) else {
+                    continue;
+                };
+                if update == GeyserUpdate::Ping && !answer_ping(&request_tx) {
+                    break;
+                }
+                if update_tx.send(update).await.is_err() {
+                    break;
+                }
+            }
+            debug!(target: "sniffer", "Geyser stream task ended");
+        });
+        Ok(update_rx)
+    }
+}
+
+/// Keep the stream alive through proxies that drop idle client halves.
+fn answer_ping(request_tx: &UnboundedSender<SubscribeRequest>) -> bool {
+    let ping = SubscribeRequest { ping: Some(SubscribeRequestPing { id: 1 }), ..Default::default() };
+    request_tx.unbounded_send(ping).is_ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use prost::Message as _;
+
+    #[test]
+    fn transaction_update_decodes_from_the_wire() {
+        let static_key = Pubkey::new_unique();
+        let loaded_key = Pubkey::new_unique();
+        let wire = SubscribeUpdate {
+            update_oneof: Some(UpdateOneof::Transaction(SubscribeUpdateTransaction {
+                transaction: Some(SubscribeUpdateTransactionInfo {
+                    signature: vec![1; 64],
+                    transaction: Some(Transaction {
+                        message: Some(Message { account_keys: vec![static_key.to_bytes().to_vec()] }),
+                    }),
+                    meta: Some(TransactionStatusMeta {
+                        err: None,
+                        log_messages: vec!["Program log: Instruction: Create".to_string()],
+                        loaded_writable_addresses: vec![loaded_key.to_bytes().to_vec()],
+                        loaded_readonly_addresses: Vec::new(),
+                    }),
+                }),
+                slot: 7,
+            })),
+        }
+        .encode_to_vec();
+
+        let decoded = decode_update(SubscribeUpdate::decode(wire.as_slice()).unwrap());
+        assert_eq!(
+            decoded,
+            Some(GeyserUpdate::Transaction(GeyserTxUpdate {
+                slot: 7,
+                signature: bs58::encode([1u8; 64]).into_string(),
+                account_keys: vec![static_key, loaded_key],
+                log_messages: vec!["Program log: Instruction: Create".to_string()],
+                failed: false,
+            }))
+        );
+
+        let ping = SubscribeUpdate { update_oneof: Some(UpdateOneof::Ping(SubscribeUpdatePing {})) };
+        assert_eq!(decode_update(ping), Some(GeyserUpdate::Ping));
+        assert_eq!(decode_update(SubscribeUpdate::default()), None);
+
+        let request = SubscribeRequest::decode(subscribe_request(&static_key).encode_to_vec().as_slice()).unwrap();
+        let filter = &request.transactions[FILTER_NAME];
+        assert_eq!(filter.vote, Some(false));
+        assert_eq!(filter.account_include, vec![static_key.to_string()]);
+    }
+}
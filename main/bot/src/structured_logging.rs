@@ -1,7 +1,10 @@
+use serde::Serialize;
 use serde_json::json;
 use std::sync::atomic::{AtomicU64, Ordering};
 use tracing::{info, warn, error, debug};
 
+use crate::types::PremintCandidate;
+
 /// Global correlation ID generator
 static CORRELATION_ID_COUNTER: AtomicU64 = AtomicU64::new(1);
 
@@ -194,6 +197,77 @@ impl PipelineContext {
     }
 }
 
+/// One pipeline check in a candidate's decision trace.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct TraceStep {
+    pub stage: String,
+    pub passed: bool,
+    /// Rejection reason, or what the check found
+    pub detail: Option<String>,
+}
+
+/// Every decision taken for one candidate, emitted as one structured log line
+/// (`candidate_decision`) once the candidate is bought, rejected or failed.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct DecisionTrace {
+    pub mint: String,
+    pub program: String,
+    pub steps: Vec<TraceStep>,
+    /// Candidate score, when the pipeline scores candidates
+    pub score: Option<f64>,
+    /// Liquidity seen for the candidate, when known
+    pub liquidity_lamports: Option<u64>,
+    /// Final action: `"buy"`, `"skip"` or `"buy_failed"`
+    pub action: Option<String>,
+    /// Stage that rejected the candidate, if any
+    pub rejected_at: Option<String>,
+    pub reason: Option<String>,
+}
+
+impl DecisionTrace {
+    pub fn new(candidate: &PremintCandidate) -> Self {
+        Self {
+            mint: candidate.mint.to_string(),
+            program: candidate.program.clone(),
+            steps: Vec::new(),
+            score: None,
+            liquidity_lamports: None,
+            action: None,
+            rejected_at: None,
+            reason: None,
+        }
+    }
+
+    /// Record a passed check.
+    pub fn pass(&mut self, stage: &str) {
+        self.steps.push(TraceStep { stage: stage.to_string(), passed: true, detail: None });
+    }
+
+    /// Record the check that rejected the candidate; the final action is `"skip"`.
+    pub fn reject(&mut self, stage: &str, reason: impl Into<String>) {
+        let reason = reason.into();
+        self.steps.push(TraceStep { stage: stage.to_string(), passed: false, detail: Some(reason.clone()) });
+        self.rejected_at = Some(stage.to_string());
+        self.action = Some("skip".to_string());
+        self.reason = Some(reason);
+    }
+
+    /// Record the final action taken after every check passed.
+    pub fn finish(&mut self, action: &str, reason: impl Into<String>) {
+        self.action = Some(action.to_string());
+        self.reason = Some(reason.into());
+    }
+
+    pub fn emit(&self, logger: &StructuredLogger) {
+        let fields = serde_json::to_value(self).unwrap_or_default();
+        if self.rejected_at.is_some() {
+            logger.debug("candidate_decision", fields);
+        } else {
+            logger.info("candidate_decision", fields);
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
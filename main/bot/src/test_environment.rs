@@ -15,15 +15,16 @@ use std::time::{Duration, Instant};
 
 use anyhow::{anyhow, Context, Result};
 use chrono;
+use solana_client::nonblocking::rpc_client::RpcClient as NonblockingRpcClient;
 use solana_client::rpc_client::RpcClient;
 #[allow(deprecated)]
 use solana_sdk::{
     commitment_config::CommitmentConfig,
     pubkey::Pubkey,
     signature::{Keypair, Signer},
-    system_instruction,
     transaction::Transaction,
 };
+use solana_system_interface::instruction as system_instruction;
 use tempfile::TempDir;
 use tokio::time::sleep;
 use tracing::{debug, info, warn};
@@ -32,7 +33,8 @@ use crate::config::Config;
 use crate::types::PremintCandidate;
 use crate::rpc_manager::RpcManager;
 // Removed unused import: use crate::buy_engine::BuyEngine;
-use crate::nonce_manager::NonceManager;
+use crate::nonce_manager::{ensure_durable_nonces, fetch_nonce_hash, NonceManager};
+use crate::wallet::WalletManager;
 use crate::market_maker::{MarketMaker, MarketMakerConfig};
 
 /// Configuration for the test validator environment
@@ -281,6 +283,9 @@ impl TestEnvironment {
         // Test 4: Nonce management
         results.add_test("nonce_management", self.test_nonce_management(&bot_config).await);
 
+        // Test 4b: Durable nonce accounts on the validator
+        results.add_test("durable_nonces", self.test_durable_nonces().await);
+
         // Test 5: Mock sniffer functionality
         results.add_test("mock_sniffer", self.test_mock_sniffer(&bot_config).await);
 
@@ -372,6 +377,55 @@ impl TestEnvironment {
         Ok(())
     }
 
+    /// Test durable nonce accounts: created once, found again, and advanced by a
+    /// transaction built on them
+    #[allow(deprecated)]
+    async fn test_durable_nonces(&self) -> Result<()> {
+        let client = self.rpc_client.as_ref()
+            .ok_or_else(|| anyhow!("RPC client not initialized"))?;
+        let keypair = self.test_keypair.as_ref()
+            .ok_or_else(|| anyhow!("Test keypair not set"))?;
+
+        info!("🔐 Testing durable nonce accounts");
+
+        let nonblocking = NonblockingRpcClient::new_with_commitment(
+            self.config.rpc_url.clone(),
+            CommitmentConfig::confirmed(),
+        );
+        let wallet = WalletManager::from_keypair(keypair.insecure_clone());
+        let nonces = ensure_durable_nonces(&nonblocking, &wallet, 2).await?;
+        let again = ensure_durable_nonces(&nonblocking, &wallet, 2).await?;
+        if nonces != again {
+            return Err(anyhow!("Durable nonce accounts were not found again"));
+        }
+
+        let nonce_manager = NonceManager::with_durable_nonces(nonces);
+        let slot = nonce_manager.acquire_slot().await?;
+        let nonce = nonce_manager
+            .durable_nonce(slot.index())
+            .ok_or_else(|| anyhow!("Slot {} has no durable nonce", slot.index()))?;
+        let nonce_hash = nonce.hash.ok_or_else(|| anyhow!("Nonce value not loaded"))?;
+
+        // Nonce value instead of a recent blockhash, advance instruction first
+        let instructions = [
+            system_instruction::advance_nonce_account(&nonce.account, &nonce.authority),
+            system_instruction::transfer(&keypair.pubkey(), &Pubkey::new_unique(), 1_000_000),
+        ];
+        let transaction = Transaction::new_signed_with_payer(
+            &instructions,
+            Some(&keypair.pubkey()),
+            &[keypair],
+            nonce_hash,
+        );
+        let signature = client.send_and_confirm_transaction(&transaction)?;
+        info!("✅ Durable nonce transaction confirmed: {}", signature);
+
+        if fetch_nonce_hash(&nonblocking, &nonce.account).await? == nonce_hash {
+            return Err(anyhow!("Nonce {} did not advance", nonce.account));
+        }
+        Ok(())
+    }
+
     /// Test mock sniffer functionality
     async fn test_mock_sniffer(&self, _bot_config: &Config) -> Result<()> {
         info!("🎯 Testing mock sniffer");
@@ -388,6 +442,7 @@ impl TestEnvironment {
                 .as_secs(),
             instruction_summary: Some("Test instruction".to_string()),
             is_jito_bundle: Some(false),
+            deadline_ms: None,
         };
 
         info!("✅ Mock candidate created: {}", mock_candidate.mint);
@@ -441,6 +496,7 @@ impl TestEnvironment {
                 crate::types::TokenProfile::RugPull => crate::token_generator::TokenProfile::Rug,
                 crate::types::TokenProfile::Trash => crate::token_generator::TokenProfile::Trash,
             },
+            dex: crate::types::DexProgram::PumpFun,
             creator: Keypair::new().pubkey(), // Mock creator
             created_at: chrono::Utc::now().timestamp() as u64,
             initial_supply: 1_000_000,
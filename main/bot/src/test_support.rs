@@ -0,0 +1,281 @@
+//! Fixtures shared by the unit tests of several modules.
+
+use std::collections::HashMap;
+use std::future::Future;
+use std::pin::Pin;
+use std::str::FromStr;
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::Arc;
+
+use anyhow::Result;
+use base64::Engine;
+use solana_client::client_error::{ClientErrorKind, Result as ClientResult};
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_client::rpc_client::Mocks;
+use solana_client::rpc_filter::RpcFilterType;
+use solana_client::rpc_request::RpcRequest;
+use solana_client::rpc_sender::{RpcSender, RpcTransportStats};
+use solana_sdk::hash::Hash;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::Signature;
+use solana_sdk::transaction::VersionedTransaction;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::sync::mpsc;
+
+use crate::nonce_manager::NonceManager;
+use crate::observability::CorrelationId;
+use crate::rpc_manager::{RpcBroadcaster, SendOutcome};
+use crate::tx_builder::{TransactionBuilder, TransactionConfig};
+use crate::types::{AppState, Mode, PremintCandidate};
+use crate::wallet::WalletManager;
+
+/// Block engine answering successive HTTP requests with `responses` (status line,
+/// JSON body), in order. Each request received is sent on the returned channel.
+pub(crate) async fn serve_block_engine(
+    responses: Vec<(&'static str, serde_json::Value)>,
+) -> (String, mpsc::UnboundedReceiver<String>) {
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    let (request_tx, request_rx) = mpsc::unbounded_channel();
+    tokio::spawn(async move {
+        for (status, body) in responses {
+            let (mut socket, _) = listener.accept().await.unwrap();
+            let mut request = Vec::new();
+            let mut chunk = [0u8; 4096];
+            // Read the head, then the Content-Length bytes of body behind it
+            loop {
+                let n = socket.read(&mut chunk).await.unwrap();
+                request.extend_from_slice(&chunk[..n]);
+                let text = String::from_utf8_lossy(&request).to_ascii_lowercase();
+                let Some(head_end) = text.find("\r\n\r\n") else {
+                    if n == 0 {
+                        break;
+                    }
+                    continue;
+                };
+                let length = text[..head_end]
+                    .lines()
+                    .find_map(|line| line.strip_prefix("content-length:").map(|v| v.trim().parse::<usize>().unwrap()))
+                    .unwrap_or(0);
+                if n == 0 || request.len() >= head_end + 4 + length {
+                    break;
+                }
+            }
+            let body = body.to_string();
+            let response = format!(
+                "HTTP/1.1 {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                status,
+                body.len(),
+                body
+            );
+            let _ = socket.write_all(response.as_bytes()).await;
+            let _ = request_tx.send(String::from_utf8_lossy(&request).into_owned());
+        }
+    });
+    (format!("http://{}/api/v1/bundles", addr), request_rx)
+}
+
+/// Builder for a random wallet with one nonce slot and an unreachable RPC endpoint,
+/// holding a fresh blockhash so builds that need nothing else from the RPC work.
+pub(crate) async fn offline_builder(config: &TransactionConfig) -> TransactionBuilder {
+    let builder = TransactionBuilder::new(
+        Arc::new(WalletManager::new_random()),
+        vec!["http://127.0.0.1:1".to_string()],
+        Arc::new(NonceManager::new(1)),
+        config,
+    )
+    .await
+    .unwrap();
+    builder.inject_blockhash_for_tests(Hash::new_unique()).await;
+    builder
+}
+
+/// A successful JSON-RPC response carrying `result`.
+pub(crate) fn rpc_result(result: serde_json::Value) -> (&'static str, serde_json::Value) {
+    ("200 OK", serde_json::json!({ "jsonrpc": "2.0", "id": 1, "result": result }))
+}
+
+/// RPC answering `getAccountInfo` with `data` owned by `owner`.
+pub(crate) fn rpc_with_mint(owner: &Pubkey, data: &[u8]) -> RpcClient {
+    let account = serde_json::json!({
+        "context": { "slot": 1 },
+        "value": {
+            "lamports": 1_461_600,
+            "data": [base64::engine::general_purpose::STANDARD.encode(data), "base64"],
+            "owner": owner.to_string(),
+            "executable": false,
+            "rentEpoch": 0,
+            "space": data.len(),
+        }
+    });
+    RpcClient::new_mock_with_mocks("succeeds".to_string(), Mocks::from([(RpcRequest::GetAccountInfo, account)]))
+}
+
+/// RPC serving `accounts` (owner, data) by address to `getAccountInfo` and
+/// `getMultipleAccounts`, and by owning program to `getProgramAccounts` (data size
+/// and memcmp filters applied); a balance of `token_balance` for every token
+/// account, and epoch 0.
+pub(crate) struct AccountsRpc {
+    pub(crate) accounts: HashMap<Pubkey, (Pubkey, Vec<u8>)>,
+    pub(crate) token_balance: u64,
+}
+
+#[async_trait::async_trait]
+impl RpcSender for AccountsRpc {
+    async fn send(&self, request: RpcRequest, params: serde_json::Value) -> ClientResult<serde_json::Value> {
+        let encode = |(owner, data): &(Pubkey, Vec<u8>)| {
+            serde_json::json!({
+                "lamports": 1_000_000,
+                "data": [base64::engine::general_purpose::STANDARD.encode(data), "base64"],
+                "owner": owner.to_string(),
+                "executable": false,
+                "rentEpoch": 0,
+                "space": data.len(),
+            })
+        };
+        let account = |key: &serde_json::Value| {
+            let found = key.as_str().and_then(|key| Pubkey::from_str(key).ok()).and_then(|key| self.accounts.get(&key));
+            found.map_or(serde_json::Value::Null, encode)
+        };
+        let value = match request {
+            RpcRequest::GetAccountInfo => account(&params[0]),
+            RpcRequest::GetMultipleAccounts => params[0].as_array().into_iter().flatten().map(account).collect(),
+            RpcRequest::GetProgramAccounts => {
+                let program = params[0].as_str().and_then(|key| Pubkey::from_str(key).ok());
+                let filters: Vec<RpcFilterType> =
+                    serde_json::from_value(params[1]["filters"].clone()).unwrap_or_default();
+                let allows = |data: &[u8]| {
+                    filters.iter().all(|filter| match filter {
+                        RpcFilterType::DataSize(size) => data.len() as u64 == *size,
+                        RpcFilterType::Memcmp(compare) => compare.bytes_match(data),
+                        RpcFilterType::TokenAccountState => false,
+                    })
+                };
+                let keyed: Vec<serde_json::Value> = self
+                    .accounts
+                    .iter()
+                    .filter(|(_, (owner, data))| Some(*owner) == program && allows(data))
+                    .map(|(key, account)| serde_json::json!({ "pubkey": key.to_string(), "account": encode(account) }))
+                    .collect();
+                return Ok(keyed.into());
+            }
+            RpcRequest::GetTokenAccountBalance => serde_json::json!({
+                "amount": self.token_balance.to_string(),
+                "decimals": 9,
+                "uiAmount": null,
+                "uiAmountString": "",
+            }),
+            RpcRequest::GetEpochInfo => {
+                return Ok(serde_json::json!({
+                    "epoch": 0,
+                    "slotIndex": 0,
+                    "slotsInEpoch": 432_000,
+                    "absoluteSlot": 0,
+                    "blockHeight": 0,
+                }))
+            }
+            other => return Err(ClientErrorKind::Custom(format!("{} not stubbed", other)).into()),
+        };
+        Ok(serde_json::json!({ "context": { "slot": 1 }, "value": value }))
+    }
+
+    fn get_transport_stats(&self) -> RpcTransportStats {
+        Default::default()
+    }
+
+    fn url(&self) -> String {
+        "accounts".to_string()
+    }
+}
+
+/// A send accepted by a mock endpoint, with signature `[byte; 64]`.
+pub(crate) fn mock_outcome(byte: u8) -> SendOutcome {
+    SendOutcome {
+        signature: Signature::from([byte; 64]),
+        endpoint: "mock".to_string(),
+        latency_ms: 0,
+        was_duplicate: false,
+        bundle_id: None,
+    }
+}
+
+/// Broadcaster accepting every send as `mock_outcome(byte)` and counting them.
+#[derive(Debug, Default)]
+pub(crate) struct CountingBroadcaster {
+    pub(crate) calls: AtomicU32,
+    pub(crate) byte: u8,
+}
+
+impl CountingBroadcaster {
+    pub(crate) fn new(byte: u8) -> Self {
+        Self { calls: AtomicU32::new(0), byte }
+    }
+
+    pub(crate) fn calls(&self) -> u32 {
+        self.calls.load(Ordering::SeqCst)
+    }
+}
+
+impl RpcBroadcaster for CountingBroadcaster {
+    fn send_on_many_rpc<'a>(
+        &'a self,
+        _txs: Vec<VersionedTransaction>,
+        _correlation_id: Option<CorrelationId>,
+    ) -> Pin<Box<dyn Future<Output = Result<SendOutcome>> + Send + 'a>> {
+        self.calls.fetch_add(1, Ordering::SeqCst);
+        let outcome = mock_outcome(self.byte);
+        Box::pin(async move { Ok(outcome) })
+    }
+}
+
+/// Broadcaster accepting every send under a fresh signature, so repeated sends
+/// never look like duplicates of one another.
+#[derive(Debug, Default)]
+pub(crate) struct UniqueSigBroadcaster;
+
+impl RpcBroadcaster for UniqueSigBroadcaster {
+    fn send_on_many_rpc<'a>(
+        &'a self,
+        _txs: Vec<VersionedTransaction>,
+        _correlation_id: Option<CorrelationId>,
+    ) -> Pin<Box<dyn Future<Output = Result<SendOutcome>> + Send + 'a>> {
+        Box::pin(async { Ok(SendOutcome { signature: Signature::new_unique(), ..mock_outcome(0) }) })
+    }
+}
+
+impl AppState {
+    /// Sniffing state with nothing held, as at startup.
+    pub(crate) fn sniffing() -> Self {
+        Self {
+            mode: Mode::Sniffing,
+            active_token: None,
+            last_buy_price: None,
+            holdings_percent: 0.0,
+            quantum_suggestions: Default::default(),
+            notifications: Default::default(),
+            operations: Default::default(),
+            positions: Default::default(),
+            pnl: Default::default(),
+        }
+    }
+
+    /// Holding all of a pump.fun `mint` bought at `buy_price`.
+    pub(crate) fn holding(mint: Pubkey, buy_price: f64) -> Self {
+        Self {
+            mode: Mode::PassiveToken(mint),
+            active_token: Some(PremintCandidate {
+                mint,
+                creator: Pubkey::new_unique(),
+                program: "pump.fun".to_string(),
+                slot: 0,
+                timestamp: 0,
+                instruction_summary: None,
+                is_jito_bundle: None,
+                deadline_ms: None,
+            }),
+            last_buy_price: Some(buy_price),
+            holdings_percent: 1.0,
+            ..Self::sniffing()
+        }
+    }
+}
@@ -6,6 +6,8 @@
 use solana_sdk::pubkey::Pubkey;
 use fastrand;
 
+use crate::types::DexProgram;
+
 /// Token profile types with associated probabilities
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum TokenProfile {
@@ -47,6 +49,53 @@ impl TokenProfile {
     }
 }
 
+/// Relative weights used to assign a DEX to each generated token
+#[derive(Debug, Clone, PartialEq)]
+pub struct DexWeights {
+    pub weights: Vec<(DexProgram, u32)>,
+}
+
+impl Default for DexWeights {
+    fn default() -> Self {
+        Self {
+            weights: vec![
+                (DexProgram::PumpFun, 70),
+                (DexProgram::Raydium, 15),
+                (DexProgram::Orca, 10),
+                (DexProgram::LetsBonk, 5),
+            ],
+        }
+    }
+}
+
+impl DexWeights {
+    /// Only pump.fun, matching the generator's original behavior
+    pub fn pump_fun_only() -> Self {
+        Self { weights: vec![(DexProgram::PumpFun, 1)] }
+    }
+
+    /// Pick a DEX with probability proportional to its weight
+    pub fn pick(&self, rng: &mut fastrand::Rng) -> DexProgram {
+        let total: u32 = self.weights.iter().map(|(_, w)| w).sum();
+        if total == 0 {
+            return DexProgram::PumpFun;
+        }
+        let mut roll = rng.u32(0..total);
+        for (dex, weight) in &self.weights {
+            if roll < *weight {
+                return dex.clone();
+            }
+            roll -= weight;
+        }
+        unreachable!("roll is below the total weight")
+    }
+
+    /// Pick a DEX using the thread-local generator
+    pub fn random(&self) -> DexProgram {
+        self.pick(&mut fastrand::Rng::new())
+    }
+}
+
 /// Represents a generated token with its metadata
 #[derive(Debug, Clone)]
 pub struct GeneratedToken {
@@ -54,6 +103,8 @@ pub struct GeneratedToken {
     pub mint: Pubkey,
     /// The profile type of this token
     pub profile: TokenProfile,
+    /// DEX the token is launched on
+    pub dex: DexProgram,
     /// The creator wallet pubkey
     pub creator: Pubkey,
     /// Timestamp when this token was created
@@ -64,4 +115,36 @@ pub struct GeneratedToken {
     pub liquidity_lamports: u64,
     /// Metadata URI (if any)
     pub metadata_uri: Option<String>,
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn generated_dex_distribution_matches_weights() {
+        let weights = DexWeights {
+            weights: vec![
+                (DexProgram::PumpFun, 50),
+                (DexProgram::Raydium, 30),
+                (DexProgram::Orca, 20),
+                (DexProgram::LetsBonk, 0),
+            ],
+        };
+        let mut rng = fastrand::Rng::with_seed(7);
+        let draws = 20_000;
+        let mut counts = [0u32; 4];
+        for _ in 0..draws {
+            let picked = weights.pick(&mut rng);
+            let idx = weights.weights.iter().position(|(dex, _)| *dex == picked).unwrap();
+            counts[idx] += 1;
+        }
+
+        for ((dex, weight), count) in weights.weights.iter().zip(counts) {
+            let expected = *weight as f64 / 100.0;
+            let actual = count as f64 / draws as f64;
+            assert!((actual - expected).abs() < 0.02, "{:?}: expected {}, got {}", dex, expected, actual);
+        }
+        assert_eq!(counts[3], 0);
+    }
+}
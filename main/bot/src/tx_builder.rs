@@ -1,11 +1,12 @@
 //! tx_builder.rs
 //! Production-ready TransactionBuilder for Solana sniper bot
 //! - supports pump.fun integration (via `pumpfun` crate if enabled, or HTTP PumpPortal/Moralis fallback)
-//! - supports LetsBonk (external HTTP provider) for liquidity/quote lookup
+//! - supports LetsBonk natively (LaunchLab program, `letsbonk` feature) or via an external HTTP provider
+//! - supports Raydium AMM v4 natively (`raydium` feature), where migrated pump.fun tokens trade
 //! - validates config values
 //! - retry/backoff + multi-RPC fallback for blockhash
 //! - signs VersionedTransaction via WalletManager
-//! - prepares simple Jito bundle wrapper (struct) for later submission
+//! - prepares Jito bundles (tip on the first transaction) and submits them to a block engine
 //! - careful logging and safe fallbacks (memo fallback when no program integration)
 //
 // Integration with other components:
@@ -13,32 +14,40 @@
 // - NonceManager for parallel transaction preparation
 // - RpcBroadcaster for transaction broadcasting
 // - Security validator for pre-transaction checks
-// - supports pump.fun integration (via `pumpfun` crate if enabled, or HTTP PumpPortal/Moralis fallback)
-// - supports LetsBonk (external HTTP provider) for liquidity/quote lookup
-// - validates config values
-// - retry/backoff + multi-RPC fallback for blockhash
-// - signs VersionedTransaction via WalletManager
-// - prepares simple Jito bundle wrapper (struct) for later submission
-// - careful logging and safe fallbacks (memo fallback when no program integration
 
 use anyhow::anyhow;
 use reqwest::Client;
 use serde::{Deserialize, Serialize};
 use solana_client::nonblocking::rpc_client::RpcClient;
+#[cfg(any(test, feature = "test_utils"))]
+use solana_client::nonblocking::rpc_client::Mocks;
+use solana_client::rpc_config::RpcSimulateTransactionConfig;
 use solana_sdk::{
+    commitment_config::CommitmentConfig,
     compute_budget::ComputeBudgetInstruction,
     hash::Hash,
     instruction::{AccountMeta, Instruction},
-    message::{v0::Message as MessageV0, VersionedMessage},
+    message::{
+        v0::Message as MessageV0, AddressLookupTableAccount, CompileError, Message as LegacyMessage,
+        VersionedMessage,
+    },
+    packet::PACKET_DATA_SIZE,
     pubkey::Pubkey,
     signature::Signature,
     transaction::VersionedTransaction,
 };
+#[allow(deprecated)]
+use solana_sdk::address_lookup_table::state::AddressLookupTable;
+use solana_system_interface::instruction as system_instruction;
+use std::borrow::Cow;
+use std::future::Future;
+use std::pin::Pin;
+use std::collections::{BTreeMap, HashMap};
 use std::str::FromStr;
 use std::sync::atomic::{AtomicUsize, Ordering};
 use std::{sync::Arc, time::Duration};
 use thiserror::Error;
-use tokio::sync::RwLock;
+use tokio::sync::{Mutex, RwLock};
 use tokio_retry::{
     strategy::{jitter, ExponentialBackoff},
     Retry,
@@ -46,19 +55,32 @@ use tokio_retry::{
 use tracing::{debug, info, warn};
 use base64::{Engine as _, engine::general_purpose};
 
-use crate::nonce_manager::NonceManager;
+use crate::config::{find_dex_config, DexConfig};
+#[cfg(feature = "letsbonk")]
+use crate::letsbonk;
+#[cfg(feature = "raydium")]
+use crate::raydium;
+use crate::metrics::metrics;
+use crate::nonce_manager::{fetch_nonce_hash, DurableNonce, NonceManager};
+use crate::portfolio::portfolio;
+use crate::slippage::{PriceHistory, SlippageModel};
 use crate::types::PremintCandidate;
+pub use crate::types::DexProgram;
 use crate::wallet::WalletManager;
+use crate::warm_pool::{WarmKey, WarmPool};
 
 // Optional integration: `pumpfun` crate
 #[cfg(feature = "pumpfun")]
 use pumpfun::{accounts::BondingCurveAccount, common::types::{Cluster, PriorityFee}, PumpFun};
 
-// Optional integrations: Raydium/Orca (behind feature flags)
-#[cfg(feature = "raydium")]
-use raydium_sdk_v2::AmmSwapClient;
+// Optional integration: Orca (behind a feature flag)
+#[cfg(feature = "orca")]
+use orca_whirlpools::{
+    order_mints, set_native_mint_wrapping_strategy, swap_instructions, NativeMintWrappingStrategy, SwapQuote,
+    SwapType, SPLASH_POOL_TICK_SPACING, WHIRLPOOLS_CONFIG_ADDRESS,
+};
 #[cfg(feature = "orca")]
-use orca_whirlpools::{SwapInput, WhirlpoolClient};
+use orca_whirlpools_client::get_whirlpool_address;
 
 use spl_associated_token_account::get_associated_token_address;
 use spl_token::id as token_program_id;
@@ -70,12 +92,23 @@ use spl_token::instruction::close_account;
 pub struct TransactionConfig {
     /// Compute unit price in micro-lamports per CU (for priority fees)
     pub priority_fee_lamports: u64,
+    /// Price buys from recent prioritization fees on their writable accounts instead
+    /// of `priority_fee_lamports` (which stays the fallback when the estimate fails)
+    pub dynamic_priority_fee: bool,
+    /// Percentile (0-100) of recent fees used as the dynamic price
+    pub priority_fee_percentile: u8,
+    /// Upper bound on the dynamic price, micro-lamports per CU (0 = uncapped)
+    pub max_priority_fee_lamports: u64,
     /// Compute unit limit for the transaction
     pub compute_unit_limit: u32,
     /// Amount to buy in SOL lamports
     pub buy_amount_lamports: u64,
     /// Slippage tolerance in basis points (bps, 100 = 1%)
     pub slippage_bps: u64,
+    /// Added to the slippage after the DEX's own is applied (sell escalation); see `for_dex`
+    pub slippage_escalation_bps: u64,
+    /// Fixed slippage or widened by recent price volatility
+    pub slippage_model: SlippageModel,
     /// RPC endpoints for rotation/fallback
     pub rpc_endpoints: Vec<String>,
     /// Max attempts per endpoint
@@ -90,12 +123,24 @@ pub struct TransactionConfig {
     pub letsbonk_api_key: Option<String>,
     /// Jito bundle toggle
     pub jito_bundle_enabled: bool,
+    /// Account the Jito tip is transferred to (one of the block engine's tip accounts)
+    pub jito_tip_account: Pubkey,
     /// Optional signer keypair index (for multi-signer wallets)
     pub signer_keypair_index: Option<usize>,
     /// Nonce semaphore capacity (parallel builds control)
     pub nonce_count: usize,
     /// Allowlist of programs (empty = allow all)
     pub allowed_programs: Vec<Pubkey>,
+    /// Compile legacy messages instead of v0 (fallback for nodes rejecting versioned txs)
+    pub legacy_message: bool,
+    /// Address lookup tables v0 buys are compiled against (empty = none)
+    pub address_lookup_tables: Vec<Pubkey>,
+    /// Sells leaving at most this many raw tokens sell the whole balance instead
+    pub sell_dust_tokens: u64,
+    /// Referral/fee-sharing account sent to the external buy APIs
+    pub referral_account: Option<Pubkey>,
+    /// Per-DEX enablement, endpoint, API key and slippage (see `Config::dexes`)
+    pub dexes: BTreeMap<String, DexConfig>,
     /// Cluster configuration for pumpfun SDK
     #[cfg(feature = "pumpfun")]
     pub cluster: Cluster,
@@ -105,9 +150,14 @@ impl Default for TransactionConfig {
     fn default() -> Self {
         Self {
             priority_fee_lamports: 10_000,
+            dynamic_priority_fee: false,
+            priority_fee_percentile: 75,
+            max_priority_fee_lamports: 1_000_000,
             compute_unit_limit: 200_000,
             buy_amount_lamports: 10_000_000,
             slippage_bps: 1000, // 10%
+            slippage_escalation_bps: 0,
+            slippage_model: SlippageModel::Fixed,
             rpc_endpoints: vec!["https://api.mainnet-beta.solana.com".to_string()],
             rpc_retry_attempts: 3,
             rpc_timeout_ms: 8_000,
@@ -116,9 +166,15 @@ impl Default for TransactionConfig {
             letsbonk_api_url: None,
             letsbonk_api_key: None,
             jito_bundle_enabled: false,
+            jito_tip_account: Pubkey::from_str(DEFAULT_JITO_TIP_ACCOUNT).unwrap(),
             signer_keypair_index: None,
             nonce_count: 5,
             allowed_programs: vec![],
+            legacy_message: false,
+            address_lookup_tables: vec![],
+            sell_dust_tokens: 0,
+            referral_account: None,
+            dexes: BTreeMap::new(),
             #[cfg(feature = "pumpfun")]
             cluster: Cluster::mainnet(Default::default(), Default::default()),
         }
@@ -147,12 +203,103 @@ impl TransactionConfig {
                 "nonce_count must be > 0".to_string(),
             ));
         }
+        if self.priority_fee_percentile > 100 {
+            return Err(TransactionBuilderError::ConfigValidation(
+                "priority_fee_percentile must be <= 100".to_string(),
+            ));
+        }
         Ok(())
     }
 
     pub fn is_program_allowed(&self, program_id: &Pubkey) -> bool {
         self.allowed_programs.is_empty() || self.allowed_programs.contains(program_id)
     }
+
+    /// Settings for a build on `dex`: its `dexes` entry replaces the flat endpoint, API
+    /// key and slippage, then `slippage_escalation_bps` is added on top (once; the
+    /// result has none left). Fails for a disabled DEX.
+    pub fn for_dex(&self, dex: &DexProgram) -> Result<Cow<'_, Self>, TransactionBuilderError> {
+        let entry = find_dex_config(&self.dexes, dex);
+        if entry.is_some_and(|entry| !entry.enabled) {
+            return Err(TransactionBuilderError::DexDisabled(dex.canonical_name().to_string()));
+        }
+        if entry.is_none() && self.slippage_escalation_bps == 0 {
+            return Ok(Cow::Borrowed(self));
+        }
+
+        let mut config = self.clone();
+        if let Some(entry) = entry {
+            if let Some(bps) = entry.slippage_bps {
+                config.slippage_bps = bps;
+            }
+            if entry.endpoint.is_some() {
+                match dex {
+                    DexProgram::PumpFun => {
+                        config.pumpportal_url = entry.endpoint.clone();
+                        config.pumpportal_api_key = entry.api_key.clone();
+                    }
+                    DexProgram::LetsBonk => {
+                        config.letsbonk_api_url = entry.endpoint.clone();
+                        config.letsbonk_api_key = entry.api_key.clone();
+                    }
+                    DexProgram::Raydium | DexProgram::Orca | DexProgram::Unknown(_) => {}
+                }
+            }
+        }
+        config.slippage_bps = (config.slippage_bps + config.slippage_escalation_bps).min(10_000);
+        config.slippage_escalation_bps = 0;
+        Ok(Cow::Owned(config))
+    }
+}
+
+/// Reusable instruction list that keeps a precomputed compute-budget prefix.
+///
+/// Each build truncates back to the prefix and appends the program instructions, so
+/// the compute-budget instructions are neither re-serialized nor re-cloned and the
+/// list only reallocates when a tail is longer than any before it.
+#[derive(Debug, Clone)]
+pub struct InstructionBuffer {
+    compute_unit_limit: u32,
+    priority_fee_lamports: u64,
+    prefix_len: usize,
+    instructions: Vec<Instruction>,
+}
+
+impl InstructionBuffer {
+    pub fn new(compute_unit_limit: u32, priority_fee_lamports: u64) -> Self {
+        let mut instructions = compute_budget_instructions(compute_unit_limit, priority_fee_lamports);
+        instructions.reserve_exact(1);
+        Self {
+            compute_unit_limit,
+            priority_fee_lamports,
+            prefix_len: instructions.len(),
+            instructions,
+        }
+    }
+
+    pub fn matches(&self, config: &TransactionConfig) -> bool {
+        self.compute_unit_limit == config.compute_unit_limit
+            && self.priority_fee_lamports == config.priority_fee_lamports
+    }
+
+    /// Replace the previous program instructions with `tail` and hand the full list to `f`.
+    pub fn with_tail<R>(&mut self, tail: Vec<Instruction>, f: impl FnOnce(&[Instruction]) -> R) -> R {
+        self.instructions.truncate(self.prefix_len);
+        self.instructions.extend(tail);
+        f(&self.instructions)
+    }
+}
+
+/// Compute-budget instructions for the given limit and price (zero values are omitted).
+pub fn compute_budget_instructions(compute_unit_limit: u32, priority_fee_lamports: u64) -> Vec<Instruction> {
+    let mut instructions = Vec::with_capacity(2);
+    if compute_unit_limit > 0 {
+        instructions.push(ComputeBudgetInstruction::set_compute_unit_limit(compute_unit_limit));
+    }
+    if priority_fee_lamports > 0 {
+        instructions.push(ComputeBudgetInstruction::set_compute_unit_price(priority_fee_lamports));
+    }
+    instructions
 }
 
 // Jito bundle representation
@@ -163,6 +310,48 @@ pub struct JitoBundleCandidate {
     pub target_slot: Option<u64>,
 }
 
+/// Where a submitted Jito bundle stands.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BundleStatus {
+    /// Landed but only processed; it may still be dropped.
+    Pending,
+    /// Confirmed or finalized in `slot`.
+    Landed { slot: u64 },
+    /// Landed with an error.
+    Failed,
+    /// The block engine does not know the bundle (not landed yet, or dropped).
+    Unknown,
+}
+
+/// Map the `result` of a single-bundle `getBundleStatuses` call.
+pub fn bundle_status_from_result(result: &serde_json::Value) -> Result<BundleStatus, TransactionBuilderError> {
+    let entries = result
+        .get("value")
+        .and_then(|value| value.as_array())
+        .ok_or_else(|| TransactionBuilderError::JitoBundle("getBundleStatuses result has no value list".to_string()))?;
+    let Some(entry) = entries.first().filter(|entry| !entry.is_null()) else {
+        return Ok(BundleStatus::Unknown);
+    };
+    // A clean landing reports `"err": {"Ok": null}`
+    let failed = entry.get("err").is_some_and(|err| !err.is_null() && err.get("Ok").is_none());
+    if failed {
+        return Ok(BundleStatus::Failed);
+    }
+    match (entry.get("confirmation_status").and_then(|s| s.as_str()), entry.get("slot").and_then(|s| s.as_u64())) {
+        (Some("confirmed" | "finalized"), Some(slot)) => Ok(BundleStatus::Landed { slot }),
+        _ => Ok(BundleStatus::Pending),
+    }
+}
+
+/// Transactions the block engine accepts in one bundle.
+pub const MAX_JITO_BUNDLE_TRANSACTIONS: usize = 5;
+
+/// One of the Jito mainnet tip accounts.
+pub const DEFAULT_JITO_TIP_ACCOUNT: &str = "96gYZGLnJYVFmbjzopPSU6QiEV5fGqZNyN9nmNhvrZU5";
+
+/// A loaded address lookup table, or why it failed to load.
+type LookupTableLoad = Result<AddressLookupTableAccount, String>;
+
 // TransactionBuilder errors
 #[derive(Debug, Error)]
 pub enum TransactionBuilderError {
@@ -182,30 +371,349 @@ pub enum TransactionBuilderError {
     Serialization(String),
     #[error("Program {0} is not allowed by configuration")]
     ProgramNotAllowed(Pubkey),
+    #[error("DEX {0} is disabled by configuration")]
+    DexDisabled(String),
     #[error("Feature not enabled: {feature} for {action}")]
     FeatureNotEnabled { feature: String, action: String },
+    #[error("Jito bundle failed: {0}")]
+    JitoBundle(String),
+}
+
+/// How much of the held token a sell targets.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SellAmount {
+    /// Fraction (0.0..=1.0) of the current token balance.
+    Fraction(f64),
+    /// Tokens worth this many lamports at the current quote.
+    SolValue(u64),
+}
+
+impl SellAmount {
+    fn clamped(self) -> Self {
+        match self {
+            SellAmount::Fraction(pct) => SellAmount::Fraction(pct.clamp(0.0, 1.0)),
+            other => other,
+        }
+    }
+}
+
+impl std::fmt::Display for SellAmount {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SellAmount::Fraction(pct) => write!(f, "{:.6}", pct),
+            SellAmount::SolValue(lamports) => write!(f, "{}lamports", lamports),
+        }
+    }
 }
 
-// Supported DEX programs (Meteora removed)
-#[derive(Debug, Clone, PartialEq, Eq)]
-pub enum DexProgram {
-    PumpFun,
-    LetsBonk,
-    Raydium,
-    Orca,
-    Unknown(String),
+/// What an RPC node reported for a simulated transaction.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SimulationOutcome {
+    pub units_consumed: Option<u64>,
+    pub logs: Vec<String>,
+    /// Transaction or program error; `None` when the simulation succeeded
+    pub error: Option<String>,
 }
 
-impl From<&str> for DexProgram {
-    fn from(s: &str) -> Self {
-        match s.to_lowercase().as_str() {
-            "pump.fun" | "pumpfun" | "pumpportal" => DexProgram::PumpFun,
-            "letsbonk.fun" | "letsbonk" | "bonk" => DexProgram::LetsBonk,
-            "raydium" => DexProgram::Raydium,
-            "orca" => DexProgram::Orca,
-            _ => DexProgram::Unknown(s.to_string()),
+impl SimulationOutcome {
+    pub fn succeeded(&self) -> bool {
+        self.error.is_none()
+    }
+}
+
+/// Sell-side quote for a held token on a constant-product bonding curve.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SellQuote {
+    pub token_balance: u64,
+    pub virtual_token_reserves: u64,
+    pub virtual_sol_reserves: u64,
+}
+
+impl SellQuote {
+    /// Lamports received for selling `tokens_in` (before fees).
+    pub fn sol_out(&self, tokens_in: u64) -> u64 {
+        let denominator = self.virtual_token_reserves as u128 + tokens_in as u128;
+        if denominator == 0 {
+            return 0;
+        }
+        (tokens_in as u128 * self.virtual_sol_reserves as u128 / denominator) as u64
+    }
+
+    /// Tokens to sell for `lamports` out, capped at the balance.
+    pub fn tokens_for_sol(&self, lamports: u64) -> u64 {
+        if lamports >= self.virtual_sol_reserves {
+            return self.token_balance;
+        }
+        // Invert sol_out: t = sol * vt / (vs - sol), rounded up so the target is reached
+        let numerator = lamports as u128 * self.virtual_token_reserves as u128;
+        let denominator = (self.virtual_sol_reserves - lamports) as u128;
+        (numerator.div_ceil(denominator) as u64).min(self.token_balance)
+    }
+
+    /// Fraction of the balance to sell for `lamports` out.
+    pub fn fraction_for_sol(&self, lamports: u64) -> f64 {
+        if self.token_balance == 0 {
+            return 0.0;
+        }
+        self.tokens_for_sol(lamports) as f64 / self.token_balance as f64
+    }
+
+    /// Token amount for a sell of `amount`.
+    pub fn token_amount(&self, amount: SellAmount) -> u64 {
+        match amount {
+            SellAmount::Fraction(pct) => fraction_of(self.token_balance, pct),
+            SellAmount::SolValue(lamports) => self.tokens_for_sol(lamports),
         }
     }
+
+    /// Token amount for a sell of `amount`, selling the whole balance when at most
+    /// `dust_tokens` would be left over (a remainder too small to sell later).
+    pub fn token_amount_without_dust(&self, amount: SellAmount, dust_tokens: u64) -> u64 {
+        let tokens = self.token_amount(amount);
+        if tokens > 0 && self.token_balance - tokens <= dust_tokens {
+            self.token_balance
+        } else {
+            tokens
+        }
+    }
+}
+
+/// Tokens of `token_balance` to sell for `amount` in a DEX pool, where selling the
+/// whole balance is quoted at `balance_out` lamports. A SOL-value sell is priced at
+/// the whole balance's rate, which never undershoots the target (smaller sells move
+/// the price less). Sells the whole balance when at most `dust_tokens` would be left.
+pub fn pool_sell_amount(token_balance: u64, amount: SellAmount, balance_out: u64, dust_tokens: u64) -> u64 {
+    let tokens = match amount.clamped() {
+        SellAmount::Fraction(pct) => fraction_of(token_balance, pct),
+        SellAmount::SolValue(lamports) if lamports >= balance_out => token_balance,
+        SellAmount::SolValue(lamports) => {
+            (token_balance as u128 * lamports as u128).div_ceil(balance_out as u128) as u64
+        }
+    };
+    if tokens > 0 && token_balance - tokens <= dust_tokens {
+        token_balance
+    } else {
+        tokens
+    }
+}
+
+#[cfg(feature = "raydium")]
+fn raydium_error(reason: String) -> TransactionBuilderError {
+    TransactionBuilderError::InstructionBuild { program: "raydium".to_string(), reason }
+}
+
+/// Instructions of an Orca swap with its quoted and slipped minimum output.
+#[cfg(feature = "orca")]
+struct OrcaSwap {
+    instructions: Vec<Instruction>,
+    expected_out: u64,
+    min_out: u64,
+}
+
+#[cfg(feature = "orca")]
+fn orca_error(reason: String) -> TransactionBuilderError {
+    TransactionBuilderError::InstructionBuild { program: "orca".to_string(), reason }
+}
+
+/// The Orca splash pool (the full-range pool tokens launch into) pairing `mint`
+/// with SOL.
+#[cfg(feature = "orca")]
+fn orca_splash_pool(mint: &Pubkey) -> Result<Pubkey, TransactionBuilderError> {
+    let [token_a, token_b] = order_mints(spl_token::native_mint::ID, *mint);
+    let whirlpools_config = *WHIRLPOOLS_CONFIG_ADDRESS.try_lock().map_err(|e| orca_error(e.to_string()))?;
+    get_whirlpool_address(&whirlpools_config, &token_a, &token_b, SPLASH_POOL_TICK_SPACING)
+        .map(|(address, _)| address)
+        .map_err(|e| orca_error(e.to_string()))
+}
+
+/// Add the referral account to an external buy API payload. The on-chain pump.fun
+/// buy has no referral account, so only API-built buys carry it.
+fn add_referral(payload: &mut serde_json::Value, referral: Option<Pubkey>) {
+    if let (Some(referral), Some(fields)) = (referral, payload.as_object_mut()) {
+        fields.insert("referral".to_string(), serde_json::Value::String(referral.to_string()));
+    }
+}
+
+/// Fixed-point scale for sell fractions (1e-9 precision).
+const FRACTION_SCALE: u128 = 1_000_000_000;
+
+/// `pct` (clamped to 0.0..=1.0) of `amount` in integer math, rounded to the nearest
+/// token. A fraction of 1.0 is always exactly `amount`.
+pub fn fraction_of(amount: u64, pct: f64) -> u64 {
+    let pct = if pct.is_nan() { 0.0 } else { pct.clamp(0.0, 1.0) };
+    if pct >= 1.0 {
+        return amount;
+    }
+    let scaled = (pct * FRACTION_SCALE as f64).round() as u128;
+    ((amount as u128 * scaled + FRACTION_SCALE / 2) / FRACTION_SCALE) as u64
+}
+
+/// Minimum output after `slippage_bps` of slippage on `expected`, rounded down so
+/// an exact fill at the quote is never rejected. Slippage above 100% yields 0.
+pub fn min_out_after_slippage(expected: u64, slippage_bps: u64) -> u64 {
+    let keep_bps = 10_000u128.saturating_sub(slippage_bps as u128);
+    (expected as u128 * keep_bps / 10_000u128) as u64
+}
+
+/// Accounts and percentile a priority-fee estimate was made for.
+type FeeEstimateKey = (Vec<Pubkey>, u8);
+
+/// Latest estimate for one key and when it was fetched; locked while fetching.
+type FeeEstimateSlot = Arc<Mutex<Option<(std::time::Instant, u64)>>>;
+
+/// TTL-bounded priority-fee estimates per account set and percentile, shareable
+/// across builders.
+///
+/// Builds within the TTL reuse one `getRecentPrioritizationFees` result instead of
+/// each querying the RPC. Concurrent misses for one key wait for its single
+/// in-flight fetch; other keys are not held up by it.
+#[derive(Debug)]
+pub struct FeeEstimateCache {
+    entries: std::sync::Mutex<HashMap<FeeEstimateKey, FeeEstimateSlot>>,
+    ttl: Duration,
+}
+
+impl FeeEstimateCache {
+    pub fn new(ttl: Duration) -> Self {
+        Self { entries: std::sync::Mutex::new(HashMap::new()), ttl }
+    }
+
+    /// Cached estimate (micro-lamports per CU) for `accounts` at `percentile` while
+    /// fresh, otherwise the result of `fetch`. A failed fetch is not cached.
+    pub async fn get_or_fetch<F, Fut>(&self, accounts: &[Pubkey], percentile: u8, fetch: F) -> Result<u64, TransactionBuilderError>
+    where
+        F: FnOnce() -> Fut,
+        Fut: std::future::Future<Output = Result<u64, TransactionBuilderError>>,
+    {
+        let mut key_accounts = accounts.to_vec();
+        key_accounts.sort_unstable();
+        key_accounts.dedup();
+        let key = (key_accounts, percentile);
+
+        let slot = {
+            let mut entries = self.entries.lock().unwrap_or_else(|e| e.into_inner());
+            // Slots being fetched are locked and kept
+            entries.retain(|_, slot| {
+                slot.try_lock().map_or(true, |cached| cached.is_some_and(|(fetched_at, _)| fetched_at.elapsed() < self.ttl))
+            });
+            entries.entry(key).or_default().clone()
+        };
+
+        let mut cached = slot.lock().await;
+        if let Some((fetched_at, estimate)) = *cached {
+            if fetched_at.elapsed() < self.ttl {
+                metrics().increment_counter("priority_fee_cache_hits_total");
+                return Ok(estimate);
+            }
+        }
+
+        metrics().increment_counter("priority_fee_cache_misses_total");
+        let estimate = fetch().await?;
+        *cached = Some((std::time::Instant::now(), estimate));
+        Ok(estimate)
+    }
+}
+
+impl Default for FeeEstimateCache {
+    fn default() -> Self {
+        Self::new(Duration::from_secs(10))
+    }
+}
+
+/// Accounts whose recent prioritization fees price `instructions`: the swap
+/// programs and the writable accounts every buy through them contends for. The
+/// account setup around a swap (system, token and associated-token programs) is
+/// shared by every market and left out, as are the mint, signers and off-curve
+/// accounts (PDAs and token accounts, derived per mint or per wallet): a fresh
+/// token's accounts have no fee history, and keeping them would give every mint its
+/// own estimate and cache entry.
+fn fee_market_accounts(instructions: &[Instruction], mint: &Pubkey) -> Vec<Pubkey> {
+    let mut accounts: Vec<Pubkey> = Vec::new();
+    for instruction in instructions.iter().filter(|ix| !is_account_setup_program(&ix.program_id)) {
+        let keys = std::iter::once(instruction.program_id).chain(
+            instruction
+                .accounts
+                .iter()
+                .filter(|meta| meta.is_writable && !meta.is_signer && meta.pubkey != *mint && meta.pubkey.is_on_curve())
+                .map(|meta| meta.pubkey),
+        );
+        for key in keys {
+            if !accounts.contains(&key) {
+                accounts.push(key);
+            }
+        }
+    }
+    accounts
+}
+
+/// Programs that only create, fund or close the wallet's accounts around a swap.
+fn is_account_setup_program(program_id: &Pubkey) -> bool {
+    *program_id == solana_system_interface::program::ID
+        || *program_id == spl_token::ID
+        || *program_id == spl_token_2022::ID
+        || *program_id == spl_associated_token_account::ID
+}
+
+/// Accounts `getRecentPrioritizationFees` accepts in one query.
+const MAX_PRIORITIZATION_FEE_ACCOUNTS: usize = 128;
+
+/// How long a recorded buy quote is kept for a fill check. Outlives a blockhash, so
+/// a prebuilt buy is sent (or discarded) well before its quote goes.
+const BUY_MIN_OUT_TTL: Duration = Duration::from_secs(180);
+
+/// `percentile` (0.0..=1.0) of the non-zero `fees`; 0 when no recent slot paid a fee.
+pub fn fee_percentile(fees: &[u64], percentile: f64) -> u64 {
+    let mut paid: Vec<u64> = fees.iter().copied().filter(|fee| *fee > 0).collect();
+    if paid.is_empty() {
+        return 0;
+    }
+    paid.sort_unstable();
+    let rank = ((paid.len() - 1) as f64 * percentile.clamp(0.0, 1.0)).round() as usize;
+    paid[rank]
+}
+
+/// Closes the wallet's WSOL account, returning its SOL; implemented by `TransactionBuilder`.
+pub trait WsolUnwrapper: Send + Sync {
+    fn unwrap_wsol<'a>(
+        &'a self,
+        config: &'a TransactionConfig,
+    ) -> Pin<Box<dyn Future<Output = Result<Signature, TransactionBuilderError>> + Send + 'a>>;
+}
+
+impl WsolUnwrapper for TransactionBuilder {
+    fn unwrap_wsol<'a>(
+        &'a self,
+        config: &'a TransactionConfig,
+    ) -> Pin<Box<dyn Future<Output = Result<Signature, TransactionBuilderError>> + Send + 'a>> {
+        Box::pin(TransactionBuilder::unwrap_wsol(self, config))
+    }
+}
+
+/// Current price of a held token, in the units of `AppState::last_buy_price`;
+/// implemented by `TransactionBuilder` from the pump.fun bonding curve.
+pub trait PriceSource: Send + Sync {
+    fn current_price<'a>(
+        &'a self,
+        mint: &'a Pubkey,
+    ) -> Pin<Box<dyn Future<Output = Result<f64, TransactionBuilderError>> + Send + 'a>>;
+}
+
+impl PriceSource for TransactionBuilder {
+    fn current_price<'a>(
+        &'a self,
+        mint: &'a Pubkey,
+    ) -> Pin<Box<dyn Future<Output = Result<f64, TransactionBuilderError>> + Send + 'a>> {
+        Box::pin(async move {
+            let quote = self.quote_sell(mint).await?;
+            if quote.virtual_token_reserves == 0 {
+                return Err(TransactionBuilderError::InstructionBuild {
+                    program: "pumpfun".to_string(),
+                    reason: format!("no liquidity to price {}", mint),
+                });
+            }
+            Ok(quote.virtual_sol_reserves as f64 / quote.virtual_token_reserves as f64)
+        })
+    }
 }
 
 // TransactionBuilder
@@ -217,8 +725,21 @@ pub struct TransactionBuilder {
     blockhash_cache: RwLock<Option<(std::time::Instant, Hash)>>,
     // Reduced to 15s as requested
     blockhash_cache_ttl: Duration,
+    // Priority-fee estimate, possibly shared with other builders
+    fee_estimate_cache: Arc<FeeEstimateCache>,
+    // Address lookup tables by address, as loaded or the reason they failed to load
+    lookup_table_cache: std::sync::Mutex<HashMap<Pubkey, (std::time::Instant, LookupTableLoad)>>,
+    lookup_table_cache_ttl: Duration,
     nonce_manager: Arc<NonceManager>,
     rpc_clients: Vec<Arc<RpcClient>>,
+    // Reused instruction list for the last seen (limit, price)
+    instruction_buffer: std::sync::Mutex<Option<InstructionBuffer>>,
+    // Recent quote prices per mint, for the dynamic slippage model
+    price_history: std::sync::Mutex<PriceHistory>,
+    // Minimum token output of the last buy built per mint (and when), for post-buy fill checks
+    buy_min_out: std::sync::Mutex<HashMap<Pubkey, (u64, std::time::Instant)>>,
+    // Unsigned buys built ahead of the trigger, valid for the cached blockhash
+    warm_pool: Arc<WarmPool>,
     #[cfg(feature = "pumpfun")]
     pumpfun_client: PumpFun,
 }
@@ -261,13 +782,163 @@ impl TransactionBuilder {
             rpc_rotation_index: AtomicUsize::new(0),
             blockhash_cache: RwLock::new(None),
             blockhash_cache_ttl: Duration::from_secs(15),
+            fee_estimate_cache: Arc::new(FeeEstimateCache::default()),
+            lookup_table_cache: std::sync::Mutex::new(HashMap::new()),
+            lookup_table_cache_ttl: Duration::from_secs(30),
             nonce_manager,
             rpc_clients,
+            instruction_buffer: std::sync::Mutex::new(None),
+            price_history: std::sync::Mutex::new(PriceHistory::default()),
+            buy_min_out: std::sync::Mutex::new(HashMap::new()),
+            warm_pool: Arc::new(WarmPool::default()),
             #[cfg(feature = "pumpfun")]
             pumpfun_client,
         })
     }
 
+    /// Share a priority-fee estimate cache with other builders.
+    pub fn with_fee_estimate_cache(mut self, cache: Arc<FeeEstimateCache>) -> Self {
+        self.fee_estimate_cache = cache;
+        self
+    }
+
+    /// Keep pre-built buys in `pool` (see `prebuild_buy`).
+    pub fn with_warm_pool(mut self, pool: Arc<WarmPool>) -> Self {
+        self.warm_pool = pool;
+        self
+    }
+
+    /// Compile the compute-budget prefix plus the `tail` instructions into a message.
+    ///
+    /// Uses the shared instruction buffer when free; concurrent builds fall back to a
+    /// freshly assembled list.
+    fn compile_with_budget(
+        &self,
+        config: &TransactionConfig,
+        tail: Vec<Instruction>,
+        recent_blockhash: Hash,
+        lookup_tables: &[AddressLookupTableAccount],
+    ) -> Result<VersionedMessage, CompileError> {
+        let payer = self.wallet.pubkey();
+        if let Ok(mut guard) = self.instruction_buffer.try_lock() {
+            if !guard.as_ref().is_some_and(|buffer| buffer.matches(config)) {
+                *guard = Some(InstructionBuffer::new(
+                    config.compute_unit_limit,
+                    config.priority_fee_lamports,
+                ));
+            }
+            if let Some(buffer) = guard.as_mut() {
+                return buffer.with_tail(tail, |instructions| {
+                    compile_message_with_tables(
+                        &payer,
+                        instructions,
+                        recent_blockhash,
+                        config.legacy_message,
+                        lookup_tables,
+                    )
+                });
+            }
+        }
+
+        let mut instructions =
+            compute_budget_instructions(config.compute_unit_limit, config.priority_fee_lamports);
+        instructions.extend(tail);
+        compile_message_with_tables(&payer, &instructions, recent_blockhash, config.legacy_message, lookup_tables)
+    }
+
+    /// `compile_with_budget` behind the `advance_nonce_account` instruction a
+    /// durable-nonce transaction has to start with.
+    fn compile_with_nonce(
+        &self,
+        config: &TransactionConfig,
+        nonce: &DurableNonce,
+        tail: Vec<Instruction>,
+        nonce_hash: Hash,
+        lookup_tables: &[AddressLookupTableAccount],
+    ) -> Result<VersionedMessage, CompileError> {
+        let mut instructions = vec![system_instruction::advance_nonce_account(&nonce.account, &nonce.authority)];
+        instructions.extend(compute_budget_instructions(config.compute_unit_limit, config.priority_fee_lamports));
+        instructions.extend(tail);
+        compile_message_with_tables(
+            &self.wallet.pubkey(),
+            &instructions,
+            nonce_hash,
+            config.legacy_message,
+            lookup_tables,
+        )
+    }
+
+    /// The lookup tables in `config.address_lookup_tables`, loaded within the cache
+    /// TTL or fetched. A table that fails to load is left out with a warning, so the
+    /// message compiles without it, and is not fetched again until the TTL passes.
+    async fn address_lookup_tables(&self, config: &TransactionConfig) -> Vec<AddressLookupTableAccount> {
+        let mut tables = Vec::with_capacity(config.address_lookup_tables.len());
+        for key in &config.address_lookup_tables {
+            match self.address_lookup_table(key).await {
+                Ok(table) => tables.push(table),
+                Err(e) => debug!(table = %key, error = %e, "Compiling without address lookup table"),
+            }
+        }
+        tables
+    }
+
+    /// The lookup table at `key`, from the cache within its TTL or fetched. Failed
+    /// loads are cached as well, so a missing table costs one fetch per TTL.
+    async fn address_lookup_table(&self, key: &Pubkey) -> Result<AddressLookupTableAccount, TransactionBuilderError> {
+        let cached = self
+            .lookup_table_cache
+            .lock()
+            .unwrap()
+            .get(key)
+            .filter(|(loaded_at, _)| loaded_at.elapsed() < self.lookup_table_cache_ttl)
+            .map(|(_, table)| table.clone());
+        if let Some(table) = cached {
+            return table.map_err(TransactionBuilderError::RpcConnection);
+        }
+        let table = self.fetch_address_lookup_table(key).await;
+        if let Err(e) = &table {
+            metrics().increment_counter("address_lookup_table_load_failures_total");
+            warn!(table = %key, error = %e, "Address lookup table failed to load; compiling without it");
+        }
+        self.lookup_table_cache
+            .lock()
+            .unwrap()
+            .insert(*key, (std::time::Instant::now(), table.as_ref().cloned().map_err(ToString::to_string)));
+        table
+    }
+
+    /// Fetch and deserialize the address lookup table account at `key`.
+    async fn fetch_address_lookup_table(&self, key: &Pubkey) -> Result<AddressLookupTableAccount, TransactionBuilderError> {
+        let index = self.rpc_rotation_index.fetch_add(1, Ordering::Relaxed);
+        let account = self
+            .rpc_client_for(index)
+            .get_account(key)
+            .await
+            .map_err(|e| TransactionBuilderError::RpcConnection(e.to_string()))?;
+        let table = AddressLookupTable::deserialize(&account.data)
+            .map_err(|e| TransactionBuilderError::Serialization(format!("lookup table {}: {}", key, e)))?;
+        Ok(AddressLookupTableAccount { key: *key, addresses: table.addresses.to_vec() })
+    }
+
+    /// Current value of the durable nonce in slot `index`, fetched when unknown.
+    async fn durable_nonce_hash(&self, index: usize, nonce: &DurableNonce) -> Result<Hash, TransactionBuilderError> {
+        if let Some(hash) = nonce.hash {
+            return Ok(hash);
+        }
+        let hash = fetch_nonce_hash(&self.rpc_client_for(index), &nonce.account)
+            .await
+            .map_err(|e| TransactionBuilderError::BlockhashFetch(e.to_string()))?;
+        self.nonce_manager.set_nonce_hash(index, Some(hash));
+        Ok(hash)
+    }
+
+    /// Drop the cached recent blockhash and durable nonce values, so the next build
+    /// fetches fresh ones. Used after a send is rejected with "blockhash not found".
+    pub async fn invalidate_blockhash(&self) {
+        *self.blockhash_cache.write().await = None;
+        self.nonce_manager.clear_nonce_hashes();
+    }
+
     pub async fn get_recent_blockhash(
         &self,
         config: &TransactionConfig,
@@ -309,6 +980,10 @@ impl TransactionBuilder {
                     // Update cache
                     let mut cache = self.blockhash_cache.write().await;
                     *cache = Some((std::time::Instant::now(), hash));
+                    let dropped = self.warm_pool.invalidate(&hash);
+                    if dropped > 0 {
+                        debug!(dropped, "New blockhash; dropped pre-built buys");
+                    }
                     return Ok(hash);
                 }
                 Err(e) => {
@@ -329,63 +1004,180 @@ impl TransactionBuilder {
         )))
     }
 
+    /// Simulate `tx` on a pooled RPC client without sending it, trying up to
+    /// `rpc_retry_attempts` endpoints. Signatures are not verified and the blockhash
+    /// is replaced, so unsigned buys simulate too. A transaction the node rejects is
+    /// an outcome with `error` set; only unreachable endpoints are an `Err`.
+    pub async fn simulate_transaction(
+        &self,
+        tx: &VersionedTransaction,
+        config: &TransactionConfig,
+    ) -> Result<SimulationOutcome, TransactionBuilderError> {
+        let simulation_config = RpcSimulateTransactionConfig {
+            sig_verify: false,
+            replace_recent_blockhash: true,
+            commitment: Some(CommitmentConfig::processed()),
+            ..RpcSimulateTransactionConfig::default()
+        };
+        let mut last_err = None;
+        for _ in 0..config.rpc_retry_attempts.max(1) {
+            let index = self.rpc_rotation_index.fetch_add(1, Ordering::Relaxed) % self.rpc_clients.len();
+            match self.rpc_clients[index]
+                .simulate_transaction_with_config(tx, simulation_config.clone())
+                .await
+            {
+                Ok(response) => {
+                    let result = response.value;
+                    return Ok(SimulationOutcome {
+                        units_consumed: result.units_consumed,
+                        logs: result.logs.unwrap_or_default(),
+                        error: result.err.map(|e| e.to_string()),
+                    });
+                }
+                Err(e) => {
+                    debug!(endpoint = %self.rpc_endpoints[index], error = %e, "Simulation request failed");
+                    last_err = Some(e);
+                }
+            }
+        }
+        Err(TransactionBuilderError::RpcConnection(format!(
+            "simulation failed on all attempts: {:?}",
+            last_err
+        )))
+    }
+
+    /// Priority fee (micro-lamports per CU) at `percentile` (0-100) of the recent
+    /// prioritization fees paid to write `accounts`, cached for the fee cache TTL.
+    pub async fn estimate_priority_fee(&self, accounts: &[Pubkey], percentile: u8) -> Result<u64, TransactionBuilderError> {
+        // The RPC accepts at most this many accounts per query
+        let accounts = &accounts[..accounts.len().min(MAX_PRIORITIZATION_FEE_ACCOUNTS)];
+        self.fee_estimate_cache
+            .get_or_fetch(accounts, percentile, || async {
+                let index = self.rpc_rotation_index.fetch_add(1, Ordering::Relaxed);
+                let fees = self
+                    .rpc_client_for(index)
+                    .get_recent_prioritization_fees(accounts)
+                    .await
+                    .map_err(|e| TransactionBuilderError::RpcConnection(e.to_string()))?;
+                let fees: Vec<u64> = fees.iter().map(|fee| fee.prioritization_fee).collect();
+                let estimate = fee_percentile(&fees, f64::from(percentile.min(100)) / 100.0);
+                debug!(samples = fees.len(), accounts = accounts.len(), estimate, "Fetched priority fee estimate");
+                Ok(estimate)
+            })
+            .await
+    }
+
+    /// `config` with its compute-unit price taken from the estimate for the fee
+    /// market of `instructions` (see `fee_market_accounts`) when `dynamic_priority_fee`
+    /// is set, clamped to `max_priority_fee_lamports`. A failed estimate keeps the
+    /// static price.
+    async fn with_dynamic_priority_fee<'c>(
+        &self,
+        config: &'c TransactionConfig,
+        instructions: &[Instruction],
+        mint: &Pubkey,
+    ) -> Cow<'c, TransactionConfig> {
+        if !config.dynamic_priority_fee {
+            return Cow::Borrowed(config);
+        }
+        let accounts = fee_market_accounts(instructions, mint);
+        match self.estimate_priority_fee(&accounts, config.priority_fee_percentile).await {
+            Ok(estimate) => {
+                let price = match config.max_priority_fee_lamports {
+                    0 => estimate,
+                    max => estimate.min(max),
+                };
+                metrics().set_gauge("priority_fee_dynamic_micro_lamports", price);
+                Cow::Owned(TransactionConfig { priority_fee_lamports: price, ..config.clone() })
+            }
+            Err(e) => {
+                metrics().increment_counter("priority_fee_estimate_failures_total");
+                warn!(error = %e, fallback = config.priority_fee_lamports, "Priority fee estimate failed; using the static price");
+                Cow::Borrowed(config)
+            }
+        }
+    }
+
     pub async fn build_buy_transaction(
         &self,
         candidate: &PremintCandidate,
         config: &TransactionConfig,
         sign: bool,
+    ) -> Result<VersionedTransaction, TransactionBuilderError> {
+        // Acquire nonce for parallel transaction preparation
+        let nonce_slot = self
+            .nonce_manager
+            .acquire_slot()
+            .await
+            .map_err(|e| TransactionBuilderError::NonceAcquisition(e.to_string()))?;
+        self.build_buy_transaction_in_slot(candidate, config, sign, nonce_slot.index()).await
+    }
+
+    /// `build_buy_transaction` on nonce slot `slot`, which the caller holds and
+    /// releases. With durable nonces the buy is built on that slot's nonce account.
+    pub async fn build_buy_transaction_in_slot(
+        &self,
+        candidate: &PremintCandidate,
+        config: &TransactionConfig,
+        sign: bool,
+        slot: usize,
     ) -> Result<VersionedTransaction, TransactionBuilderError> {
         config.validate()?;
+        let dex_program = DexProgram::from(candidate.program.as_str());
+        let dex_config = config.for_dex(&dex_program)?;
+        let config = dex_config.as_ref();
         info!(
             mint = %candidate.mint,
             program = %candidate.program,
             "Building buy transaction"
         );
 
-        // Acquire nonce for parallel transaction preparation
-        let _nonce_guard = self
-            .nonce_manager
-            .acquire_nonce()
-            .await
-            .map_err(|e| TransactionBuilderError::NonceAcquisition(e.to_string()))?;
-
-        let recent_blockhash = self.get_recent_blockhash(config).await?;
-
-        let mut instructions: Vec<Instruction> = Vec::with_capacity(4);
+        // A durable nonce replaces the recent blockhash, so the buy does not expire
+        let durable = self.nonce_manager.durable_nonce(slot);
+        let recent_blockhash = match &durable {
+            Some(nonce) => self.durable_nonce_hash(slot, nonce).await?,
+            None => self.get_recent_blockhash(config).await?,
+        };
 
-        // Compute budget instructions
-        if config.compute_unit_limit > 0 {
-            instructions.push(ComputeBudgetInstruction::set_compute_unit_limit(
-                config.compute_unit_limit,
-            ));
-        }
-        if config.priority_fee_lamports > 0 {
-            instructions.push(ComputeBudgetInstruction::set_compute_unit_price(
-                config.priority_fee_lamports,
-            ));
+        if let Some(mut tx) = self.warm_pool.take(&WarmKey::for_buy(candidate.mint, config), &recent_blockhash) {
+            if sign {
+                self.wallet
+                    .sign_transaction(&mut tx)
+                    .map_err(|e| TransactionBuilderError::SigningFailed(e.to_string()))?;
+            }
+            debug!(mint = %candidate.mint, "Using pre-built buy transaction");
+            return Ok(tx);
         }
 
-        // Build program-specific instruction
-        let dex_program = DexProgram::from(candidate.program.as_str());
-        let buy_instruction = match dex_program {
-            DexProgram::PumpFun => self.build_pumpfun_instruction(candidate, config).await,
-            DexProgram::LetsBonk => self.build_letsbonk_instruction(candidate, config).await,
-            DexProgram::Raydium => self.build_raydium_instruction(candidate, config).await,
-            DexProgram::Orca => self.build_orca_instruction(candidate, config).await,
-            DexProgram::Unknown(_) => self.build_placeholder_buy_instruction(candidate, config).await,
+        // Compute budget prefix + the program instructions
+        // Build program-specific instructions
+        let buy_instructions = match dex_program {
+            DexProgram::PumpFun => self.build_pumpfun_instruction(candidate, config).await.map(|ix| vec![ix]),
+            DexProgram::LetsBonk => self.build_letsbonk_instructions(candidate, config).await,
+            DexProgram::Raydium => self.build_raydium_instructions(candidate, config).await,
+            DexProgram::Orca => self.build_orca_instructions(candidate, config).await,
+            DexProgram::Unknown(_) => {
+                self.build_placeholder_buy_instruction(candidate, config).await.map(|ix| vec![ix])
+            }
         }?;
+        let config = self.with_dynamic_priority_fee(config, &buy_instructions, &candidate.mint).await;
+        let config = config.as_ref();
+        let lookup_tables = if config.legacy_message { Vec::new() } else { self.address_lookup_tables(config).await };
+
+        // Compile compute budget prefix + buy instructions (V0, or legacy when configured)
+        let versioned_message = match &durable {
+            Some(nonce) => self.compile_with_nonce(config, nonce, buy_instructions, recent_blockhash, &lookup_tables),
+            None => self.compile_with_budget(config, buy_instructions, recent_blockhash, &lookup_tables),
+        }
+        .map_err(|e| TransactionBuilderError::InstructionBuild {
+            program: candidate.program.clone(),
+            reason: format!("Failed to compile message: {}", e),
+        })?;
+        if durable.is_some() {
+            // Landing this buy advances the nonce; fetch it again before the next use
+            self.nonce_manager.set_nonce_hash(slot, None);
+        }
 
-        instructions.push(buy_instruction);
-
-        // Compile message (V0)
-        let payer = self.wallet.pubkey();
-        let message_v0 = MessageV0::try_compile(&payer, &instructions, &[], recent_blockhash)
-            .map_err(|e| TransactionBuilderError::InstructionBuild {
-                program: candidate.program.clone(),
-                reason: format!("Failed to compile message: {}", e),
-            })?;
-
-        let versioned_message = VersionedMessage::V0(message_v0);
         let mut tx = VersionedTransaction {
             signatures: vec![],
             message: versioned_message,
@@ -405,66 +1197,50 @@ impl TransactionBuilder {
         Ok(tx)
     }
 
+    /// Build an unsigned buy of `candidate` now and park it in the warm pool, so a
+    /// later `build_buy_transaction` for the same mint only has to sign it.
+    pub async fn prebuild_buy(
+        &self,
+        candidate: &PremintCandidate,
+        config: &TransactionConfig,
+    ) -> Result<(), TransactionBuilderError> {
+        let tx = self.build_buy_transaction(candidate, config, false).await?;
+        // Keyed like the lookup in `build_buy_transaction`: after the DEX overrides
+        let dex_config = config.for_dex(&DexProgram::from(candidate.program.as_str()))?;
+        self.warm_pool.insert(WarmKey::for_buy(candidate.mint, &dex_config), tx);
+        metrics().increment_counter("warm_pool_prebuilt_total");
+        Ok(())
+    }
+
     pub async fn build_sell_transaction(
         &self,
         mint: &Pubkey,
         program: &str,
-        sell_percent: f64,
+        amount: SellAmount,
         config: &TransactionConfig,
         sign: bool,
     ) -> Result<VersionedTransaction, TransactionBuilderError> {
         config.validate()?;
-        let sell_percent = sell_percent.clamp(0.0, 1.0);
+        let amount = amount.clamped();
         info!(mint = %mint, "Building sell transaction");
 
-        let _nonce_guard = self
+        let _nonce_slot = self
             .nonce_manager
-            .acquire_nonce()
+            .acquire_slot()
             .await
             .map_err(|e| TransactionBuilderError::NonceAcquisition(e.to_string()))?;
 
         let recent_blockhash = self.get_recent_blockhash(config).await?;
 
-        let mut instructions: Vec<Instruction> = Vec::new();
-
-        if config.compute_unit_limit > 0 {
-            instructions.push(ComputeBudgetInstruction::set_compute_unit_limit(
-                config.compute_unit_limit,
-            ));
-        }
-        if config.priority_fee_lamports > 0 {
-            instructions.push(ComputeBudgetInstruction::set_compute_unit_price(
-                config.priority_fee_lamports,
-            ));
-        }
-
-        let dex_program = DexProgram::from(program);
-        let sell_instruction = match dex_program {
-            DexProgram::PumpFun => {
-                self.build_pumpfun_sell_instruction(mint, sell_percent, config).await
-            }
-            DexProgram::LetsBonk => {
-                self.build_letsbonk_sell_instruction(mint, sell_percent, config).await
-            }
-            DexProgram::Raydium => {
-                self.build_raydium_sell_instruction(mint, sell_percent, config).await
-            }
-            DexProgram::Orca => self.build_orca_sell_instruction(mint, sell_percent, config).await,
-            DexProgram::Unknown(_) => {
-                self.build_placeholder_sell_instruction(mint, sell_percent, config).await
-            }
-        }?;
-
-        instructions.push(sell_instruction);
+        let sell_instructions = self.build_sell_instructions(mint, program, amount, config).await?;
 
-        let payer = self.wallet.pubkey();
-        let message_v0 = MessageV0::try_compile(&payer, &instructions, &[], recent_blockhash)
+        let versioned_message = self
+            .compile_with_budget(config, sell_instructions, recent_blockhash, &[])
             .map_err(|e| TransactionBuilderError::InstructionBuild {
                 program: program.to_string(),
                 reason: format!("Failed to compile sell message: {}", e),
             })?;
 
-        let versioned_message = VersionedMessage::V0(message_v0);
         let mut tx = VersionedTransaction {
             signatures: vec![],
             message: versioned_message,
@@ -483,17 +1259,230 @@ impl TransactionBuilder {
         Ok(tx)
     }
 
-    pub fn prepare_jito_bundle(
+    /// Sell several positions with as few transactions as possible.
+    ///
+    /// Each sell's instructions are packed into shared transactions while they fit the
+    /// packet size and compute limits (`compute_unit_limit` is budgeted per sell);
+    /// whatever does not fit goes into further transactions, down to one per mint.
+    /// Each transaction comes with the mints it sells.
+    pub async fn build_batch_sell_transactions(
+        &self,
+        sells: &[(Pubkey, String, SellAmount)],
+        config: &TransactionConfig,
+        sign: bool,
+    ) -> Result<Vec<(VersionedTransaction, Vec<Pubkey>)>, TransactionBuilderError> {
+        config.validate()?;
+        info!(sells = sells.len(), "Building batched sell transactions");
+
+        let _nonce_slot = self
+            .nonce_manager
+            .acquire_slot()
+            .await
+            .map_err(|e| TransactionBuilderError::NonceAcquisition(e.to_string()))?;
+
+        let recent_blockhash = self.get_recent_blockhash(config).await?;
+
+        let mut instructions = Vec::with_capacity(sells.len());
+        for (mint, program, amount) in sells {
+            instructions.push(self.build_sell_instructions(mint, program, amount.clamped(), config).await?);
+        }
+
+        let payer = self.wallet.pubkey();
+        // Groups keep the order of `sells`, so each takes the next mints in line
+        let mut mints = sells.iter().map(|(mint, _, _)| *mint);
+        let mut txs = Vec::new();
+        for group in pack_instructions(&payer, instructions, config, recent_blockhash) {
+            let sold: Vec<Pubkey> = mints.by_ref().take(group.len()).collect();
+            let message = compile_batch(&payer, &group, config, recent_blockhash).map_err(|e| {
+                TransactionBuilderError::InstructionBuild {
+                    program: "batch_sell".to_string(),
+                    reason: format!("Failed to compile batched sell message: {}", e),
+                }
+            })?;
+            let mut tx = VersionedTransaction { signatures: vec![], message };
+            if sign {
+                self.wallet
+                    .sign_transaction(&mut tx)
+                    .map_err(|e| TransactionBuilderError::SigningFailed(e.to_string()))?;
+            } else {
+                let required = tx.message.header().num_required_signatures as usize;
+                tx.signatures = vec![Signature::default(); required];
+            }
+            txs.push((tx, sold));
+        }
+
+        debug!(sells = sells.len(), transactions = txs.len(), "Batched sell transactions built");
+        Ok(txs)
+    }
+
+    /// Instructions selling `amount` of `mint` on `program`. A LetsBonk, Raydium or
+    /// Orca sell built without its feature fails with `FeatureNotEnabled` instead of
+    /// falling back to a memo, which would look like a sold position while the
+    /// tokens stay in the wallet.
+    async fn build_sell_instructions(
+        &self,
+        mint: &Pubkey,
+        program: &str,
+        amount: SellAmount,
+        config: &TransactionConfig,
+    ) -> Result<Vec<Instruction>, TransactionBuilderError> {
+        let dex_program = DexProgram::from(program);
+        let dex_config = config.for_dex(&dex_program)?;
+        let config = dex_config.as_ref();
+        match dex_program {
+            DexProgram::PumpFun => {
+                self.build_pumpfun_sell_instruction(mint, amount, config).await.map(|ix| vec![ix])
+            }
+            DexProgram::LetsBonk => {
+                self.build_letsbonk_sell_instructions(mint, amount, config).await
+            }
+            DexProgram::Raydium => self.build_raydium_sell_instructions(mint, amount, config).await,
+            DexProgram::Orca => self.build_orca_sell_instructions(mint, amount, config).await,
+            DexProgram::Unknown(_) => {
+                self.build_placeholder_sell_instruction(mint, amount, config).await.map(|ix| vec![ix])
+            }
+        }
+    }
+
+    /// Bundle `txs` for the Jito block engine. With `jito_bundle_enabled`, the first
+    /// transaction is rebuilt and re-signed with a transfer of `max_total_cost_lamports`
+    /// to `jito_tip_account` in front of its instructions (after a durable-nonce advance).
+    pub async fn prepare_jito_bundle(
         &self,
-        txs: Vec<VersionedTransaction>,
+        mut txs: Vec<VersionedTransaction>,
         max_total_cost_lamports: u64,
         target_slot: Option<u64>,
-    ) -> JitoBundleCandidate {
-        JitoBundleCandidate {
+        config: &TransactionConfig,
+    ) -> Result<JitoBundleCandidate, TransactionBuilderError> {
+        if config.jito_bundle_enabled && max_total_cost_lamports > 0 {
+            if let Some(first) = txs.first_mut() {
+                *first = self.with_jito_tip(first, max_total_cost_lamports, config).await?;
+            }
+        }
+        Ok(JitoBundleCandidate {
             transactions: txs,
             max_total_cost_lamports,
             target_slot,
+        })
+    }
+
+    /// `tx` recompiled with a `tip_lamports` transfer to the tip account as its
+    /// first instruction, signed by the wallet.
+    async fn with_jito_tip(
+        &self,
+        tx: &VersionedTransaction,
+        tip_lamports: u64,
+        config: &TransactionConfig,
+    ) -> Result<VersionedTransaction, TransactionBuilderError> {
+        let tip_error = |reason: String| TransactionBuilderError::InstructionBuild { program: "jito_tip".to_string(), reason };
+        let message = &tx.message;
+        let mut lookup_tables = Vec::new();
+        for lookup in message.address_table_lookups().unwrap_or_default() {
+            lookup_tables.push(self.address_lookup_table(&lookup.account_key).await?);
         }
+        let mut instructions = decompile_instructions(message, &lookup_tables).map_err(tip_error)?;
+
+        let payer = self.wallet.pubkey();
+        let tip = system_instruction::transfer(&payer, &config.jito_tip_account, tip_lamports);
+        let advance_nonce = system_instruction::advance_nonce_account(&Pubkey::default(), &Pubkey::default()).data;
+        // advance_nonce_account has to stay the first instruction of a durable-nonce transaction
+        let at = usize::from(instructions.first().is_some_and(|ix| {
+            ix.program_id == solana_sdk::system_program::id() && ix.data == advance_nonce
+        }));
+        instructions.insert(at, tip);
+
+        let legacy = matches!(message, VersionedMessage::Legacy(_));
+        let recompiled =
+            compile_message_with_tables(&payer, &instructions, *message.recent_blockhash(), legacy, &lookup_tables)
+                .map_err(|e| tip_error(format!("Failed to compile message: {}", e)))?;
+        let mut tipped = VersionedTransaction { signatures: vec![], message: recompiled };
+        self.wallet
+            .sign_transaction(&mut tipped)
+            .map_err(|e| TransactionBuilderError::SigningFailed(e.to_string()))?;
+        Ok(tipped)
+    }
+
+    /// Send `bundle` to the Jito block engine at `block_engine_url` (its
+    /// `/api/v1/bundles` endpoint) with `sendBundle` and return the bundle id.
+    pub async fn submit_jito_bundle(
+        &self,
+        bundle: &JitoBundleCandidate,
+        block_engine_url: &str,
+    ) -> Result<String, TransactionBuilderError> {
+        let count = bundle.transactions.len();
+        if count == 0 || count > MAX_JITO_BUNDLE_TRANSACTIONS {
+            return Err(TransactionBuilderError::JitoBundle(format!(
+                "a bundle holds 1 to {} transactions, got {}",
+                MAX_JITO_BUNDLE_TRANSACTIONS, count
+            )));
+        }
+        let encoded = bundle
+            .transactions
+            .iter()
+            .map(|tx| bincode::serialize(tx).map(|bytes| general_purpose::STANDARD.encode(bytes)))
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| TransactionBuilderError::Serialization(e.to_string()))?;
+        let params = serde_json::json!([encoded, { "encoding": "base64" }]);
+
+        let result = self.block_engine_request(block_engine_url, "sendBundle", params).await.and_then(|result| {
+            result
+                .as_str()
+                .map(str::to_string)
+                .ok_or_else(|| TransactionBuilderError::JitoBundle("response has no bundle id".to_string()))
+        });
+
+        match &result {
+            Ok(bundle_id) => {
+                metrics().increment_counter("jito_bundles_submitted_total");
+                info!(bundle_id = %bundle_id, transactions = count, "Submitted Jito bundle");
+            }
+            Err(e) => {
+                metrics().increment_counter("jito_bundle_failures_total");
+                warn!(error = %e, transactions = count, "Jito bundle submission failed");
+            }
+        }
+        result
+    }
+
+    /// Where bundle `bundle_id` stands, from the block engine's `getBundleStatuses`.
+    pub async fn get_bundle_status(
+        &self,
+        bundle_id: &str,
+        block_engine_url: &str,
+    ) -> Result<BundleStatus, TransactionBuilderError> {
+        let result = self
+            .block_engine_request(block_engine_url, "getBundleStatuses", serde_json::json!([[bundle_id]]))
+            .await?;
+        bundle_status_from_result(&result)
+    }
+
+    /// POST a JSON-RPC `method` call to the block engine and return its `result`.
+    async fn block_engine_request(
+        &self,
+        block_engine_url: &str,
+        method: &str,
+        params: serde_json::Value,
+    ) -> Result<serde_json::Value, TransactionBuilderError> {
+        let payload = serde_json::json!({ "jsonrpc": "2.0", "id": 1, "method": method, "params": params });
+        let resp = self
+            .http
+            .post(block_engine_url)
+            .json(&payload)
+            .send()
+            .await
+            .map_err(|e| TransactionBuilderError::RpcConnection(e.to_string()))?;
+        let status = resp.status();
+        let mut body: serde_json::Value = resp.json().await.map_err(|e| {
+            TransactionBuilderError::JitoBundle(format!("block engine returned {} with unreadable body: {}", status, e))
+        })?;
+        if let Some(error) = body.get("error") {
+            let message = error.get("message").and_then(|m| m.as_str()).unwrap_or("unknown error");
+            return Err(TransactionBuilderError::JitoBundle(format!("block engine rejected {}: {}", method, message)));
+        }
+        if !status.is_success() {
+            return Err(TransactionBuilderError::JitoBundle(format!("block engine returned {} for {}", status, method)));
+        }
+        Ok(body["result"].take())
     }
 
     pub fn rpc_client_for(&self, idx: usize) -> Arc<RpcClient> {
@@ -501,6 +1490,40 @@ impl TransactionBuilder {
         self.rpc_clients[index].clone()
     }
 
+    /// Record an observed price for `mint` (feeds the dynamic slippage model and
+    /// the portfolio value).
+    pub fn record_price(&self, mint: Pubkey, price: f64) {
+        portfolio().update_price(&mint, price);
+        if let Ok(mut history) = self.price_history.lock() {
+            history.record(mint, price);
+        }
+    }
+
+    /// Remember the slippage-adjusted minimum output of a buy built for `mint`.
+    /// Quotes older than `BUY_MIN_OUT_TTL` (prebuilt buys never sent) are dropped.
+    pub fn record_buy_min_out(&self, mint: Pubkey, min_out: u64) {
+        if let Ok(mut quotes) = self.buy_min_out.lock() {
+            let now = std::time::Instant::now();
+            quotes.retain(|_, (_, at)| now.duration_since(*at) < BUY_MIN_OUT_TTL);
+            quotes.insert(mint, (min_out, now));
+        }
+    }
+
+    /// Minimum output of the last buy built for `mint`, removing it.
+    pub fn take_buy_min_out(&self, mint: &Pubkey) -> Option<u64> {
+        self.buy_min_out.lock().ok().and_then(|mut quotes| quotes.remove(mint)).map(|(min_out, _)| min_out)
+    }
+
+    /// Effective slippage for a build on `mint` under `config.slippage_model`.
+    pub fn slippage_bps_for(&self, mint: &Pubkey, config: &TransactionConfig) -> u64 {
+        let recent = self
+            .price_history
+            .lock()
+            .map(|history| history.recent(mint))
+            .unwrap_or_default();
+        config.slippage_model.effective_bps(config.slippage_bps, &recent)
+    }
+
     // --- Instruction builders ---
 
     async fn build_pumpfun_instruction(
@@ -520,11 +1543,17 @@ impl TransactionBuilder {
                     reason: e.to_string(),
                 })?;
 
+            if bonding_curve.virtual_token_reserves > 0 {
+                self.record_price(
+                    candidate.mint,
+                    bonding_curve.virtual_sol_reserves as f64 / bonding_curve.virtual_token_reserves as f64,
+                );
+            }
+            let slippage_bps = self.slippage_bps_for(&candidate.mint, config);
             let expected_tokens =
                 calculate_expected_tokens(&bonding_curve, config.buy_amount_lamports);
-            let min_token_out = ((expected_tokens as u128)
-                * (10000u128 - config.slippage_bps as u128)
-                / 10000u128) as u64;
+            let min_token_out = min_out_after_slippage(expected_tokens, slippage_bps);
+            self.record_buy_min_out(candidate.mint, min_token_out);
 
             // Buduj tx i wyciągnij instrukcję buy (ostatnia w tx)
             let priority_fee = PriorityFee {
@@ -560,150 +1589,163 @@ impl TransactionBuilder {
         self.build_pumpportal_or_memo(candidate, config).await
     }
 
-    async fn build_letsbonk_instruction(
+    async fn build_letsbonk_instructions(
         &self,
         candidate: &PremintCandidate,
         config: &TransactionConfig,
-    ) -> Result<Instruction, TransactionBuilderError> {
-        if let Some(url) = &config.letsbonk_api_url {
-            let payload = serde_json::json!({
+    ) -> Result<Vec<Instruction>, TransactionBuilderError> {
+        #[cfg(feature = "letsbonk")]
+        {
+            return self.build_letsbonk_native_instructions(candidate, config).await;
+        }
+
+        // Without the native path the HTTP API is the only way to buy; a memo would
+        // look like a bought position
+        #[cfg(not(feature = "letsbonk"))]
+        {
+            let letsbonk_error = |reason: String| TransactionBuilderError::InstructionBuild {
+                program: "letsbonk".to_string(),
+                reason,
+            };
+            let Some(url) = &config.letsbonk_api_url else {
+                return Err(TransactionBuilderError::FeatureNotEnabled {
+                    feature: "letsbonk".to_string(),
+                    action: "LetsBonk buy without letsbonk_api_url".to_string(),
+                });
+            };
+            let mut payload = serde_json::json!({
                 "mint": candidate.mint.to_string(),
                 "amount": config.buy_amount_lamports,
-                "slippage": config.slippage_bps as f64 / 100.0,
+                "slippage": self.slippage_bps_for(&candidate.mint, config) as f64 / 100.0,
                 "payer": self.wallet.pubkey().to_string(),
             });
+            add_referral(&mut payload, config.referral_account);
 
             let mut req = self.http.post(url).json(&payload);
             if let Some(k) = &config.letsbonk_api_key {
                 req = req.header("X-API-KEY", k);
             }
 
-            match req.send().await {
-                Ok(resp) if resp.status().is_success() => {
-                    let j: serde_json::Value = resp.json().await.map_err(|e| {
-                        TransactionBuilderError::InstructionBuild {
-                            program: "letsbonk".to_string(),
-                            reason: format!("JSON parse error: {}", e),
-                        }
-                    })?;
-                    return self.parse_external_api_response(&j, "letsbonk", config);
-                }
-                Ok(resp) => {
-                    warn!("LetsBonk API error: {}", resp.status());
-                }
-                Err(e) => {
-                    warn!("LetsBonk request error: {}", e);
-                }
+            let resp = req.send().await.map_err(|e| letsbonk_error(format!("request error: {}", e)))?;
+            if !resp.status().is_success() {
+                return Err(letsbonk_error(format!("API error: {}", resp.status())));
             }
+            let j: serde_json::Value =
+                resp.json().await.map_err(|e| letsbonk_error(format!("JSON parse error: {}", e)))?;
+            self.parse_external_api_response(&j, "letsbonk", config).map(|ix| vec![ix])
         }
-
-        self.build_placeholder_buy_instruction(candidate, config).await
     }
 
-    async fn build_raydium_instruction(
+    /// LetsBonk buy built against the LaunchLab pool read from chain, with the minimum
+    /// output from the pool's curve and the slippage setting, wrapped in the token
+    /// and WSOL account setup it needs (see `letsbonk::buy_instructions`).
+    #[cfg(feature = "letsbonk")]
+    async fn build_letsbonk_native_instructions(
         &self,
-        _candidate: &PremintCandidate,
-        _config: &TransactionConfig,
-    ) -> Result<Instruction, TransactionBuilderError> {
-        #[cfg(feature = "raydium")]
-        {
-            let sol_mint =
-                Pubkey::from_str("So11111111111111111111111111111111111111112").unwrap();
-            let raydium_client = AmmSwapClient::new(
-                self.rpc_client_for(0).clone(),
-                sol_mint,
-                candidate.mint,
-                self.wallet.clone(),
-            );
-
-            let expected_tokens = raydium_client
-                .get_swap_amount_out(config.buy_amount_lamports, true) // true = SOL -> token
-                .await
-                .map_err(|e| TransactionBuilderError::InstructionBuild {
-                    program: "raydium".to_string(),
-                    reason: e.to_string(),
-                })?;
-
-            let min_token_out = ((expected_tokens as u128)
-                * (10000u128 - config.slippage_bps as u128)
-                / 10000u128) as u64;
-
-            let tx = raydium_client
-                .swap(config.buy_amount_lamports, min_token_out, true)
-                .await
-                .map_err(|e| TransactionBuilderError::InstructionBuild {
-                    program: "raydium".to_string(),
-                    reason: e.to_string(),
-                })?;
+        candidate: &PremintCandidate,
+        config: &TransactionConfig,
+    ) -> Result<Vec<Instruction>, TransactionBuilderError> {
+        let letsbonk_error = |reason: String| TransactionBuilderError::InstructionBuild {
+            program: "letsbonk".to_string(),
+            reason,
+        };
+        let pool = letsbonk::pool_state_pda(&candidate.mint, &letsbonk::WSOL_MINT);
+        let account = self
+            .rpc_client_for(0)
+            .get_account(&pool)
+            .await
+            .map_err(|e| letsbonk_error(format!("pool {}: {}", pool, e)))?;
+        let reserves = letsbonk::PoolReserves::from_account_data(&account.data)
+            .ok_or_else(|| letsbonk_error(format!("{} is not a LaunchLab pool", pool)))?;
 
-            if let Some(ix) = tx.message.instructions.last() {
-                return Ok(ix.clone());
-            } else {
-                return Err(TransactionBuilderError::InstructionBuild {
-                    program: "raydium".to_string(),
-                    reason: "No instruction in tx".to_string(),
-                });
-            }
+        let price = reserves.price();
+        if price > 0.0 {
+            self.record_price(candidate.mint, price);
         }
+        let expected_tokens = reserves.tokens_out(config.buy_amount_lamports);
+        let min_token_out = min_out_after_slippage(expected_tokens, self.slippage_bps_for(&candidate.mint, config));
+        self.record_buy_min_out(candidate.mint, min_token_out);
 
-        #[cfg(not(feature = "raydium"))]
-        {
-            Err(TransactionBuilderError::FeatureNotEnabled {
-                feature: "raydium".to_string(),
-                action: "Raydium buy instruction".to_string(),
-            })
-        }
+        Ok(letsbonk::buy_instructions(
+            &self.wallet.pubkey(),
+            &candidate.mint,
+            config.buy_amount_lamports,
+            min_token_out,
+        ))
     }
 
-    async fn build_orca_instruction(
+    /// Raydium buy through the SOL pool of the candidate's mint, with the minimum
+    /// output from the pool's reserves and the slippage setting, wrapped in the token
+    /// and WSOL account setup it needs (see `raydium::buy_instructions`).
+    async fn build_raydium_instructions(
         &self,
-        _candidate: &PremintCandidate,
-        _config: &TransactionConfig,
-    ) -> Result<Instruction, TransactionBuilderError> {
-        #[cfg(feature = "orca")]
+        candidate: &PremintCandidate,
+        config: &TransactionConfig,
+    ) -> Result<Vec<Instruction>, TransactionBuilderError> {
+        #[cfg(feature = "raydium")]
         {
-            let client = WhirlpoolClient::new(self.rpc_client_for(0).clone());
-            let whirlpool_address = client.derive_whirlpool_pda(
-                Pubkey::from_str("So11111111111111111111111111111111111111112").unwrap(),
-                candidate.mint,
-            );
-            let whirlpool = client
-                .get_whirlpool(&whirlpool_address)
-                .await
-                .map_err(|e| TransactionBuilderError::InstructionBuild {
-                    program: "orca".to_string(),
-                    reason: e.to_string(),
-                })?;
-
-            let quote = client
-                .swap_quote_a_to_b(config.buy_amount_lamports, false, &whirlpool) // false => exact in
-                .await
-                .map_err(|e| TransactionBuilderError::InstructionBuild {
-                    program: "orca".to_string(),
-                    reason: e.to_string(),
-                })?;
+            let (pool, reserves) = self.raydium_pool(&candidate.mint).await?;
+            let price = reserves.price();
+            if price > 0.0 {
+                self.record_price(candidate.mint, price);
+            }
+            let expected_tokens = reserves.tokens_out(config.buy_amount_lamports);
+            let min_token_out =
+                min_out_after_slippage(expected_tokens, self.slippage_bps_for(&candidate.mint, config));
+            self.record_buy_min_out(candidate.mint, min_token_out);
 
-            let min_token_out = ((quote.amount_out as u128)
-                * (10000u128 - config.slippage_bps as u128)
-                / 10000u128) as u64;
+            Ok(raydium::buy_instructions(&pool, &self.wallet.pubkey(), config.buy_amount_lamports, min_token_out))
+        }
 
-            let swap_input = SwapInput {
-                amount: config.buy_amount_lamports,
-                other_amount_threshold: min_token_out,
-                sqrt_price_limit: quote.sqrt_price_limit,
-                amount_specified_is_input: true,
-                a_to_b: true,
-            };
+        #[cfg(not(feature = "raydium"))]
+        {
+            let _ = (candidate, config);
+            Err(TransactionBuilderError::FeatureNotEnabled {
+                feature: "raydium".to_string(),
+                action: "Raydium buy instruction".to_string(),
+            })
+        }
+    }
 
-            let ix = client
-                .build_swap_ix(&whirlpool_address, &swap_input, &self.wallet.pubkey())
-                .instruction;
+    /// The Raydium SOL pool of `mint` with its current reserves.
+    #[cfg(feature = "raydium")]
+    async fn raydium_pool(
+        &self,
+        mint: &Pubkey,
+    ) -> Result<(raydium::AmmPool, raydium::PoolReserves), TransactionBuilderError> {
+        let rpc = self.rpc_client_for(0);
+        let pool = raydium::find_sol_pool(&rpc, mint)
+            .await
+            .map_err(|e| raydium_error(e.to_string()))?
+            .ok_or_else(|| raydium_error(format!("no Raydium SOL pool for {}", mint)))?;
+        let reserves = raydium::fetch_reserves(&rpc, &pool)
+            .await
+            .map_err(|e| raydium_error(e.to_string()))?
+            .ok_or_else(|| raydium_error(format!("vaults of pool {} not readable", pool.address)))?;
+        Ok((pool, reserves))
+    }
 
-            Ok(ix)
+    /// Orca buy through the SOL splash pool of the candidate's mint, with the token
+    /// and WSOL account setup the swap needs (see `orca_swap`).
+    async fn build_orca_instructions(
+        &self,
+        candidate: &PremintCandidate,
+        config: &TransactionConfig,
+    ) -> Result<Vec<Instruction>, TransactionBuilderError> {
+        #[cfg(feature = "orca")]
+        {
+            let whirlpool = orca_splash_pool(&candidate.mint)?;
+            let slippage_bps = self.slippage_bps_for(&candidate.mint, config);
+            let swap = self
+                .orca_swap(whirlpool, config.buy_amount_lamports, spl_token::native_mint::ID, slippage_bps)
+                .await?;
+            self.record_buy_min_out(candidate.mint, swap.min_out);
+            Ok(swap.instructions)
         }
 
         #[cfg(not(feature = "orca"))]
         {
+            let _ = (candidate, config);
             Err(TransactionBuilderError::FeatureNotEnabled {
                 feature: "orca".to_string(),
                 action: "Orca buy instruction".to_string(),
@@ -711,18 +1753,56 @@ impl TransactionBuilder {
         }
     }
 
+    /// Exact-in swap of `amount` of `input_mint` through `whirlpool` for the wallet,
+    /// with the quote's minimum out slipped by `slippage_bps`. SOL goes through the
+    /// wallet's WSOL account rather than a throwaway keypair, so the wallet stays the
+    /// only signer.
+    #[cfg(feature = "orca")]
+    async fn orca_swap(
+        &self,
+        whirlpool: Pubkey,
+        amount: u64,
+        input_mint: Pubkey,
+        slippage_bps: u64,
+    ) -> Result<OrcaSwap, TransactionBuilderError> {
+        set_native_mint_wrapping_strategy(NativeMintWrappingStrategy::Ata).map_err(|e| orca_error(e.to_string()))?;
+        let rpc = self.rpc_client_for(0);
+        // The SDK's swap future is large enough to overflow a 2 MiB thread stack
+        // nested in a build in debug builds, so it lives on the heap
+        let swap = Box::pin(swap_instructions(
+            &rpc,
+            whirlpool,
+            amount,
+            input_mint,
+            SwapType::ExactIn,
+            Some(slippage_bps.min(10_000) as u16),
+            Some(self.wallet.pubkey()),
+        ))
+        .await
+        .map_err(|e| orca_error(format!("pool {}: {}", whirlpool, e)))?;
+        match swap.quote {
+            SwapQuote::ExactIn(quote) => Ok(OrcaSwap {
+                instructions: swap.instructions,
+                expected_out: quote.token_est_out,
+                min_out: quote.token_min_out,
+            }),
+            SwapQuote::ExactOut(_) => Err(orca_error("exact-out quote for an exact-in swap".to_string())),
+        }
+    }
+
     async fn build_pumpportal_or_memo(
         &self,
         candidate: &PremintCandidate,
         config: &TransactionConfig,
     ) -> Result<Instruction, TransactionBuilderError> {
         if let Some(url) = &config.pumpportal_url {
-            let payload = serde_json::json!({
+            let mut payload = serde_json::json!({
                 "mint": candidate.mint.to_string(),
                 "buy_amount": config.buy_amount_lamports,
-                "slippage": config.slippage_bps as f64 / 100.0,
+                "slippage": self.slippage_bps_for(&candidate.mint, config) as f64 / 100.0,
                 "payer": self.wallet.pubkey().to_string(),
             });
+            add_referral(&mut payload, config.referral_account);
 
             let mut req = self.http.post(url).json(&payload);
             if let Some(k) = &config.pumpportal_api_key {
@@ -931,24 +2011,19 @@ impl TransactionBuilder {
     async fn build_placeholder_sell_instruction(
         &self,
         mint: &Pubkey,
-        sell_percent: f64,
+        amount: SellAmount,
         _config: &TransactionConfig,
     ) -> Result<Instruction, TransactionBuilderError> {
         debug!(mint = %mint, "Creating placeholder sell memo");
-        let memo_data = format!("PLACEHOLDER_SELL:{}:{:.6}", mint, sell_percent);
+        let memo_data = format!("PLACEHOLDER_SELL:{}:{}", mint, amount);
         Ok(spl_memo::build_memo(
             memo_data.as_bytes(),
             &[&self.wallet.pubkey()],
         ))
     }
 
-    // Sell instruction builders (placeholder implementations)
-    async fn build_pumpfun_sell_instruction(
-        &self,
-        mint: &Pubkey,
-        sell_percent: f64,
-        config: &TransactionConfig,
-    ) -> Result<Instruction, TransactionBuilderError> {
+    /// Current pump.fun sell quote for the wallet's balance of `mint`.
+    pub async fn quote_sell(&self, mint: &Pubkey) -> Result<SellQuote, TransactionBuilderError> {
         #[cfg(feature = "pumpfun")]
         {
             let ata = get_associated_token_address(&self.wallet.pubkey(), mint);
@@ -961,8 +2036,6 @@ impl TransactionBuilder {
                     reason: e.to_string(),
                 })?
                 .unwrap_or(0);
-            let sell_amount = ((token_balance as f64) * sell_percent) as u64;
-
             let bonding_curve = self
                 .pumpfun_client
                 .get_bonding_curve(*mint)
@@ -971,10 +2044,42 @@ impl TransactionBuilder {
                     program: "pumpfun".to_string(),
                     reason: e.to_string(),
                 })?;
-            let expected_sol = calculate_expected_sol(&bonding_curve, sell_amount);
-            let min_sol_out = ((expected_sol as u128)
-                * (10000u128 - config.slippage_bps as u128)
-                / 10000u128) as u64;
+            if bonding_curve.virtual_token_reserves > 0 {
+                self.record_price(
+                    *mint,
+                    bonding_curve.virtual_sol_reserves as f64 / bonding_curve.virtual_token_reserves as f64,
+                );
+            }
+            Ok(SellQuote {
+                token_balance,
+                virtual_token_reserves: bonding_curve.virtual_token_reserves,
+                virtual_sol_reserves: bonding_curve.virtual_sol_reserves,
+            })
+        }
+        #[cfg(not(feature = "pumpfun"))]
+        {
+            let _ = mint;
+            Err(TransactionBuilderError::FeatureNotEnabled {
+                feature: "pumpfun".to_string(),
+                action: "Sell quote".to_string(),
+            })
+        }
+    }
+
+    // Sell instruction builders (placeholder implementations)
+    async fn build_pumpfun_sell_instruction(
+        &self,
+        mint: &Pubkey,
+        amount: SellAmount,
+        config: &TransactionConfig,
+    ) -> Result<Instruction, TransactionBuilderError> {
+        #[cfg(feature = "pumpfun")]
+        {
+            let quote = self.quote_sell(mint).await?;
+            let slippage_bps = self.slippage_bps_for(mint, config);
+            let sell_amount = quote.token_amount_without_dust(amount, config.sell_dust_tokens);
+            let expected_sol = quote.sol_out(sell_amount);
+            let min_sol_out = min_out_after_slippage(expected_sol, slippage_bps);
 
             let priority_fee = PriorityFee {
                 unit_limit: Some(config.compute_unit_limit as u64),
@@ -1000,38 +2105,152 @@ impl TransactionBuilder {
             }
         }
 
-        self.build_placeholder_sell_instruction(mint, sell_percent, config)
+        self.build_placeholder_sell_instruction(mint, amount, config)
             .await
     }
 
-    async fn build_letsbonk_sell_instruction(
+    /// LetsBonk sell of `amount` out of the wallet's token balance, priced on the
+    /// LaunchLab pool's curve and paid out through a temporary WSOL account (see
+    /// `letsbonk::sell_instructions`).
+    async fn build_letsbonk_sell_instructions(
         &self,
         mint: &Pubkey,
-        sell_percent: f64,
+        amount: SellAmount,
         config: &TransactionConfig,
-    ) -> Result<Instruction, TransactionBuilderError> {
-        self.build_placeholder_sell_instruction(mint, sell_percent, config)
-            .await
+    ) -> Result<Vec<Instruction>, TransactionBuilderError> {
+        #[cfg(feature = "letsbonk")]
+        {
+            let letsbonk_error = |reason: String| TransactionBuilderError::InstructionBuild {
+                program: "letsbonk".to_string(),
+                reason,
+            };
+            let ata = get_associated_token_address(&self.wallet.pubkey(), mint);
+            let token_balance = self
+                .rpc_client_for(0)
+                .get_token_account_balance(&ata)
+                .await
+                .map_err(|e| letsbonk_error(e.to_string()))?
+                .amount
+                .parse::<u64>()
+                .map_err(|e| letsbonk_error(format!("invalid token balance: {}", e)))?;
+            if token_balance == 0 {
+                return Err(letsbonk_error(format!("no {} tokens to sell", mint)));
+            }
+
+            let pool = letsbonk::pool_state_pda(mint, &letsbonk::WSOL_MINT);
+            let account = self
+                .rpc_client_for(0)
+                .get_account(&pool)
+                .await
+                .map_err(|e| letsbonk_error(format!("pool {}: {}", pool, e)))?;
+            let reserves = letsbonk::PoolReserves::from_account_data(&account.data)
+                .ok_or_else(|| letsbonk_error(format!("{} is not a LaunchLab pool", pool)))?;
+
+            let balance_sol = reserves.sol_out(token_balance);
+            let sell_amount = pool_sell_amount(token_balance, amount, balance_sol, config.sell_dust_tokens);
+            let min_sol_out =
+                min_out_after_slippage(reserves.sol_out(sell_amount), self.slippage_bps_for(mint, config));
+
+            Ok(letsbonk::sell_instructions(&self.wallet.pubkey(), mint, sell_amount, min_sol_out))
+        }
+
+        #[cfg(not(feature = "letsbonk"))]
+        {
+            let _ = (mint, amount, config);
+            Err(TransactionBuilderError::FeatureNotEnabled {
+                feature: "letsbonk".to_string(),
+                action: "LetsBonk sell instruction".to_string(),
+            })
+        }
     }
 
-    async fn build_raydium_sell_instruction(
+    /// Raydium sell of `amount` out of the wallet's token balance, priced on the
+    /// pool's reserves and paid out through a temporary WSOL account (see
+    /// `raydium::sell_instructions`). Migrated pump.fun positions sell here.
+    async fn build_raydium_sell_instructions(
         &self,
         mint: &Pubkey,
-        sell_percent: f64,
+        amount: SellAmount,
         config: &TransactionConfig,
-    ) -> Result<Instruction, TransactionBuilderError> {
-        self.build_placeholder_sell_instruction(mint, sell_percent, config)
-            .await
+    ) -> Result<Vec<Instruction>, TransactionBuilderError> {
+        #[cfg(feature = "raydium")]
+        {
+            let ata = get_associated_token_address(&self.wallet.pubkey(), mint);
+            let token_balance = self
+                .rpc_client_for(0)
+                .get_token_account_balance(&ata)
+                .await
+                .map_err(|e| raydium_error(e.to_string()))?
+                .amount
+                .parse::<u64>()
+                .map_err(|e| raydium_error(format!("invalid token balance: {}", e)))?;
+            if token_balance == 0 {
+                return Err(raydium_error(format!("no {} tokens to sell", mint)));
+            }
+
+            let (pool, reserves) = self.raydium_pool(mint).await?;
+            let balance_sol = reserves.sol_out(token_balance);
+            let sell_amount = pool_sell_amount(token_balance, amount, balance_sol, config.sell_dust_tokens);
+            let min_sol_out =
+                min_out_after_slippage(reserves.sol_out(sell_amount), self.slippage_bps_for(mint, config));
+
+            Ok(raydium::sell_instructions(&pool, &self.wallet.pubkey(), sell_amount, min_sol_out))
+        }
+
+        #[cfg(not(feature = "raydium"))]
+        {
+            let _ = (mint, amount, config);
+            Err(TransactionBuilderError::FeatureNotEnabled {
+                feature: "raydium".to_string(),
+                action: "Raydium sell instruction".to_string(),
+            })
+        }
     }
 
-    async fn build_orca_sell_instruction(
+    /// Orca sell of `amount` out of the wallet's token balance through the mint's
+    /// SOL splash pool.
+    async fn build_orca_sell_instructions(
         &self,
         mint: &Pubkey,
-        sell_percent: f64,
+        amount: SellAmount,
         config: &TransactionConfig,
-    ) -> Result<Instruction, TransactionBuilderError> {
-        self.build_placeholder_sell_instruction(mint, sell_percent, config)
-            .await
+    ) -> Result<Vec<Instruction>, TransactionBuilderError> {
+        #[cfg(feature = "orca")]
+        {
+            let ata = get_associated_token_address(&self.wallet.pubkey(), mint);
+            let token_balance = self
+                .rpc_client_for(0)
+                .get_token_account_balance(&ata)
+                .await
+                .map_err(|e| orca_error(e.to_string()))?
+                .amount
+                .parse::<u64>()
+                .map_err(|e| orca_error(format!("invalid token balance: {}", e)))?;
+            if token_balance == 0 {
+                return Err(orca_error(format!("no {} tokens to sell", mint)));
+            }
+
+            let whirlpool = orca_splash_pool(mint)?;
+            let slippage_bps = self.slippage_bps_for(mint, config);
+            // Quoting the whole balance prices the sell amount; selling all of it
+            // reuses that swap
+            let whole = self.orca_swap(whirlpool, token_balance, *mint, slippage_bps).await?;
+            let sell_amount = pool_sell_amount(token_balance, amount, whole.expected_out, config.sell_dust_tokens);
+            if sell_amount == token_balance {
+                return Ok(whole.instructions);
+            }
+            let swap = self.orca_swap(whirlpool, sell_amount, *mint, slippage_bps).await?;
+            Ok(swap.instructions)
+        }
+
+        #[cfg(not(feature = "orca"))]
+        {
+            let _ = (mint, amount, config);
+            Err(TransactionBuilderError::FeatureNotEnabled {
+                feature: "orca".to_string(),
+                action: "Orca sell instruction".to_string(),
+            })
+        }
     }
 
     /// Unwrap WSOL ATA back to native SOL
@@ -1058,13 +2277,13 @@ impl TransactionBuilder {
 
         let instructions = vec![close_ix];
         let payer = self.wallet.pubkey();
-        let message_v0 = MessageV0::try_compile(&payer, &instructions, &[], recent_blockhash)
-            .map_err(|e| TransactionBuilderError::InstructionBuild {
-                program: "unwrap_wsol".to_string(),
-                reason: format!("Failed to compile message: {}", e),
-            })?;
+        let versioned_message =
+            compile_message(&payer, &instructions, recent_blockhash, config.legacy_message)
+                .map_err(|e| TransactionBuilderError::InstructionBuild {
+                    program: "unwrap_wsol".to_string(),
+                    reason: format!("Failed to compile message: {}", e),
+                })?;
 
-        let versioned_message = VersionedMessage::V0(message_v0);
         let tx = VersionedTransaction {
             signatures: vec![],
             message: versioned_message,
@@ -1085,6 +2304,33 @@ impl TransactionBuilder {
         Ok(signature)
     }
 
+    /// Test helper: answer every RPC call from the solana-client mock sender.
+    #[cfg(any(test, feature = "test_utils"))]
+    pub fn use_mock_rpc_for_tests(self) -> Self {
+        self.use_mock_rpc_responses_for_tests(Mocks::new())
+    }
+
+    /// Test helper: answer RPC calls with `mocks` first (each used once), then the
+    /// solana-client mock sender defaults.
+    #[cfg(any(test, feature = "test_utils"))]
+    pub fn use_mock_rpc_responses_for_tests(mut self, mocks: Mocks) -> Self {
+        self.rpc_clients = self
+            .rpc_endpoints
+            .iter()
+            .map(|_| Arc::new(RpcClient::new_mock_with_mocks("succeeds".to_string(), mocks.clone())))
+            .collect();
+        self
+    }
+
+    /// Test helper: answer every RPC call through `client`, e.g. one built on a stub
+    /// `RpcSender`.
+    #[cfg(any(test, feature = "test_utils"))]
+    pub fn use_rpc_client_for_tests(mut self, client: RpcClient) -> Self {
+        let client = Arc::new(client);
+        self.rpc_clients = self.rpc_endpoints.iter().map(|_| client.clone()).collect();
+        self
+    }
+
     /// Test helper: inject a fresh blockhash to avoid RPC calls in unit/integration tests.
     #[cfg(any(test, feature = "test_utils"))]
     pub async fn inject_blockhash_for_tests(&self, hash: Hash) {
@@ -1093,6 +2339,145 @@ impl TransactionBuilder {
     }
 }
 
+/// Compile `instructions` into a v0 message, or a legacy one when `legacy` is set.
+pub fn compile_message(
+    payer: &Pubkey,
+    instructions: &[Instruction],
+    recent_blockhash: Hash,
+    legacy: bool,
+) -> Result<VersionedMessage, CompileError> {
+    compile_message_with_tables(payer, instructions, recent_blockhash, legacy, &[])
+}
+
+/// `compile_message` with the accounts found in `lookup_tables` loaded through them
+/// instead of listed in a v0 message. Legacy messages cannot use lookup tables.
+pub fn compile_message_with_tables(
+    payer: &Pubkey,
+    instructions: &[Instruction],
+    recent_blockhash: Hash,
+    legacy: bool,
+    lookup_tables: &[AddressLookupTableAccount],
+) -> Result<VersionedMessage, CompileError> {
+    if legacy {
+        return Ok(VersionedMessage::Legacy(LegacyMessage::new_with_blockhash(
+            instructions,
+            Some(payer),
+            &recent_blockhash,
+        )));
+    }
+    MessageV0::try_compile(payer, instructions, lookup_tables, recent_blockhash).map(VersionedMessage::V0)
+}
+
+/// The instructions of `message`, with accounts it loads through address lookup
+/// tables resolved from `lookup_tables`.
+fn decompile_instructions(
+    message: &VersionedMessage,
+    lookup_tables: &[AddressLookupTableAccount],
+) -> Result<Vec<Instruction>, String> {
+    let mut keys = message.static_account_keys().to_vec();
+    let lookups = message.address_table_lookups().unwrap_or_default();
+    let resolve = |indexes: fn(&solana_sdk::message::v0::MessageAddressTableLookup) -> &[u8]| {
+        let mut loaded = Vec::new();
+        for lookup in lookups {
+            let table = lookup_tables
+                .iter()
+                .find(|table| table.key == lookup.account_key)
+                .ok_or_else(|| format!("lookup table {} is not loaded", lookup.account_key))?;
+            for index in indexes(lookup) {
+                let address = table.addresses.get(*index as usize).ok_or_else(|| {
+                    format!("lookup table {} has no address at index {}", lookup.account_key, index)
+                })?;
+                loaded.push(*address);
+            }
+        }
+        Ok::<_, String>(loaded)
+    };
+    // Loaded writable accounts come before loaded readonly ones in the index space
+    let writable = resolve(|lookup| &lookup.writable_indexes)?;
+    let writable_end = keys.len() + writable.len();
+    keys.extend(writable);
+    keys.extend(resolve(|lookup| &lookup.readonly_indexes)?);
+
+    let key_at = |index: u8| keys.get(index as usize).copied().ok_or_else(|| format!("account index {} out of range", index));
+    message
+        .instructions()
+        .iter()
+        .map(|ix| {
+            let accounts = ix
+                .accounts
+                .iter()
+                .map(|&index| {
+                    let i = index as usize;
+                    let is_writable = if i < message.static_account_keys().len() {
+                        message.is_maybe_writable(i, None)
+                    } else {
+                        i < writable_end
+                    };
+                    Ok(AccountMeta { pubkey: key_at(index)?, is_signer: message.is_signer(i), is_writable })
+                })
+                .collect::<Result<Vec<_>, String>>()?;
+            Ok(Instruction { program_id: key_at(ix.program_id_index)?, accounts, data: ix.data.clone() })
+        })
+        .collect()
+}
+
+/// Compute units a single transaction may request.
+pub const MAX_TRANSACTION_COMPUTE_UNITS: u32 = 1_400_000;
+
+/// Compute-budget prefix for `group` (`compute_unit_limit` per sell) plus the
+/// instructions of every sell in it.
+fn compile_batch(
+    payer: &Pubkey,
+    group: &[Vec<Instruction>],
+    config: &TransactionConfig,
+    recent_blockhash: Hash,
+) -> Result<VersionedMessage, CompileError> {
+    let units = (config.compute_unit_limit as u64 * group.len() as u64).min(MAX_TRANSACTION_COMPUTE_UNITS as u64);
+    let mut instructions = compute_budget_instructions(units as u32, config.priority_fee_lamports);
+    instructions.extend(group.iter().flatten().cloned());
+    compile_message(payer, &instructions, recent_blockhash, config.legacy_message)
+}
+
+/// Wire size of a transaction carrying `message` with its required signatures.
+pub fn transaction_size(message: &VersionedMessage) -> usize {
+    let signatures = message.header().num_required_signatures as usize;
+    // Signature count is a compact-u16: one byte below 128
+    let count_len = if signatures < 0x80 { 1 } else { 2 };
+    count_len + signatures * 64 + message.serialize().len()
+}
+
+/// Group `sells` (the instructions of one sell each) greedily, in order, into
+/// transactions that stay within the packet size and compute limits. A sell is
+/// never split across transactions; one too large even on its own gets a group of
+/// its own.
+pub fn pack_instructions(
+    payer: &Pubkey,
+    sells: Vec<Vec<Instruction>>,
+    config: &TransactionConfig,
+    recent_blockhash: Hash,
+) -> Vec<Vec<Vec<Instruction>>> {
+    let max_per_tx = (MAX_TRANSACTION_COMPUTE_UNITS / config.compute_unit_limit.max(1)).max(1) as usize;
+    let fits = |group: &[Vec<Instruction>]| {
+        group.len() <= max_per_tx
+            && compile_batch(payer, group, config, recent_blockhash)
+                .is_ok_and(|message| transaction_size(&message) <= PACKET_DATA_SIZE)
+    };
+
+    let mut groups: Vec<Vec<Vec<Instruction>>> = Vec::new();
+    let mut current: Vec<Vec<Instruction>> = Vec::new();
+    for sell in sells {
+        current.push(sell);
+        if current.len() > 1 && !fits(&current) {
+            let overflow = current.pop().expect("just pushed");
+            groups.push(std::mem::replace(&mut current, vec![overflow]));
+        }
+    }
+    if !current.is_empty() {
+        groups.push(current);
+    }
+    groups
+}
+
 // Pomocnicze funkcje obliczeniowe dla pump.fun
 #[cfg(feature = "pumpfun")]
 fn calculate_expected_tokens(curve: &BondingCurveAccount, sol_in: u64) -> u64 {
@@ -1101,13 +2486,6 @@ fn calculate_expected_tokens(curve: &BondingCurveAccount, sol_in: u64) -> u64 {
     (sol_in * virtual_tokens) / (virtual_sol + sol_in)
 }
 
-#[cfg(feature = "pumpfun")]
-fn calculate_expected_sol(curve: &BondingCurveAccount, tokens_in: u64) -> u64 {
-    let virtual_sol = curve.virtual_sol_reserves;
-    let virtual_tokens = curve.virtual_token_reserves;
-    (tokens_in * virtual_sol) / (virtual_tokens + tokens_in)
-}
-
 // SPL Memo helper
 mod spl_memo {
     use solana_sdk::{
@@ -1126,4 +2504,772 @@ mod spl_memo {
 
         Instruction::new_with_bytes(MEMO_PROGRAM_ID, data, metas)
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use solana_client::rpc_request::RpcRequest;
+    use crate::test_support::{offline_builder, rpc_result, serve_block_engine};
+
+    #[test]
+    fn sol_value_sell_fraction_yields_requested_sol() {
+        let quote = SellQuote {
+            token_balance: 50_000_000_000,
+            virtual_token_reserves: 1_000_000_000_000,
+            virtual_sol_reserves: 30_000_000_000,
+        };
+        // Position worth ~1.46 SOL; take out exactly 0.5 SOL
+        let target = 500_000_000;
+        let fraction = quote.fraction_for_sol(target);
+        assert!(fraction > 0.0 && fraction < 1.0);
+
+        let tokens = quote.token_amount(SellAmount::Fraction(fraction));
+        let sol_out = quote.sol_out(tokens);
+        assert!(sol_out.abs_diff(target) <= target / 1000, "sol_out={}", sol_out);
+
+        // Asking for more than the position is worth sells everything
+        assert_eq!(quote.fraction_for_sol(10_000_000_000), 1.0);
+    }
+
+    #[test]
+    fn full_sell_of_edge_balances_leaves_no_dust() {
+        for balance in [1, 3, 7, 999_999_999, 123_456_789_012_345, u64::MAX] {
+            let quote = SellQuote {
+                token_balance: balance,
+                virtual_token_reserves: 1_000_000_000_000,
+                virtual_sol_reserves: 30_000_000_000,
+            };
+            assert_eq!(quote.token_amount(SellAmount::Fraction(1.0)), balance);
+            assert_eq!(quote.token_amount(SellAmount::Fraction(1.5)), balance);
+            assert_eq!(quote.token_amount(SellAmount::Fraction(0.0)), 0);
+        }
+
+        // Odd balances round to the nearest token rather than truncating
+        assert_eq!(fraction_of(3, 0.5), 2);
+        assert_eq!(fraction_of(1, 0.5), 1);
+        assert_eq!(fraction_of(1, 0.4), 0);
+        assert_eq!(fraction_of(1_000_000_001, 0.3), 300_000_000);
+        // 0.29 * 100 is 28.999999999999996 in f64; truncation would give 28
+        assert_eq!(fraction_of(100, 0.29), 29);
+
+        // Two half sells of an odd balance: the second sells what is left
+        let mut quote = SellQuote { token_balance: 7, virtual_token_reserves: 1_000, virtual_sol_reserves: 1_000 };
+        quote.token_balance -= quote.token_amount(SellAmount::Fraction(0.5));
+        assert_eq!(quote.token_balance, 3);
+        assert_eq!(quote.token_amount(SellAmount::Fraction(1.0)), 3);
+
+        // A remainder within the dust threshold is sold along with the rest
+        let quote = SellQuote { token_balance: 1_000_003, virtual_token_reserves: 1_000, virtual_sol_reserves: 1_000 };
+        assert_eq!(quote.token_amount_without_dust(SellAmount::Fraction(0.999999), 5), 1_000_003);
+        assert_eq!(quote.token_amount_without_dust(SellAmount::Fraction(0.5), 5), 500_002);
+        assert_eq!(quote.token_amount_without_dust(SellAmount::Fraction(0.0), 5), 0);
+    }
+
+    #[tokio::test]
+    async fn buys_within_ttl_share_one_fee_fetch() {
+        let cache = Arc::new(FeeEstimateCache::new(Duration::from_secs(60)));
+        let fetches = AtomicUsize::new(0);
+        let fetch = || async {
+            fetches.fetch_add(1, Ordering::SeqCst);
+            Ok(fee_percentile(&[0, 5_000, 20_000, 10_000], 0.75))
+        };
+        let (a, b) = (Pubkey::new_unique(), Pubkey::new_unique());
+
+        // Two buys, each asking for an estimate (account order does not matter)
+        let first = cache.get_or_fetch(&[a, b], 75, fetch).await.unwrap();
+        let second = cache.get_or_fetch(&[b, a], 75, fetch).await.unwrap();
+        assert_eq!(first, 20_000);
+        assert_eq!(second, first);
+        assert_eq!(fetches.load(Ordering::SeqCst), 1);
+
+        // Other accounts or another percentile are estimated separately
+        cache.get_or_fetch(&[a], 75, fetch).await.unwrap();
+        cache.get_or_fetch(&[a, b], 50, fetch).await.unwrap();
+        assert_eq!(fetches.load(Ordering::SeqCst), 3);
+
+        // Past the TTL the estimate is fetched again
+        let expired = FeeEstimateCache::new(Duration::ZERO);
+        expired.get_or_fetch(&[a], 75, fetch).await.unwrap();
+        expired.get_or_fetch(&[a], 75, fetch).await.unwrap();
+        assert_eq!(fetches.load(Ordering::SeqCst), 5);
+    }
+
+    #[tokio::test]
+    async fn slow_fee_fetch_holds_up_only_its_own_key() {
+        let cache = Arc::new(FeeEstimateCache::new(Duration::from_secs(60)));
+        let (release_tx, release_rx) = tokio::sync::oneshot::channel::<()>();
+        let slow_key = Pubkey::new_unique();
+        let slow = tokio::spawn({
+            let cache = cache.clone();
+            async move {
+                cache
+                    .get_or_fetch(&[slow_key], 75, || async {
+                        release_rx.await.ok();
+                        Ok(1)
+                    })
+                    .await
+            }
+        });
+        tokio::task::yield_now().await;
+
+        let other_key = [Pubkey::new_unique()];
+        let other = cache.get_or_fetch(&other_key, 75, || async { Ok(2) });
+        assert_eq!(tokio::time::timeout(Duration::from_secs(1), other).await.unwrap().unwrap(), 2);
+
+        release_tx.send(()).unwrap();
+        assert_eq!(slow.await.unwrap().unwrap(), 1);
+        assert_eq!(cache.get_or_fetch(&[slow_key], 75, || async { Ok(3) }).await.unwrap(), 1);
+    }
+
+    #[test]
+    fn fee_market_leaves_out_mint_specific_accounts() {
+        use solana_sdk::signature::{Keypair, Signer};
+
+        let program = Pubkey::new_unique();
+        let fee_recipient = Keypair::new().pubkey();
+        let buy = |mint: Pubkey| {
+            let (bonding_curve, _) = Pubkey::find_program_address(&[b"bonding-curve", mint.as_ref()], &program);
+            Instruction::new_with_bytes(
+                program,
+                &[0],
+                vec![
+                    AccountMeta::new_readonly(Keypair::new().pubkey(), false),
+                    AccountMeta::new(fee_recipient, false),
+                    AccountMeta::new(mint, false),
+                    AccountMeta::new(bonding_curve, false),
+                    AccountMeta::new(Keypair::new().pubkey(), true),
+                ],
+            )
+        };
+
+        let (first, second) = (Keypair::new().pubkey(), Keypair::new().pubkey());
+        assert_eq!(fee_market_accounts(&[buy(first)], &first), vec![program, fee_recipient]);
+        // Buys of two mints through one program share an estimate
+        assert_eq!(fee_market_accounts(&[buy(second)], &second), fee_market_accounts(&[buy(first)], &first));
+
+        // Account setup around the swap is not its market, and accounts count once
+        let payer = Keypair::new().pubkey();
+        let wrapped = vec![
+            system_instruction::transfer(&payer, &Keypair::new().pubkey(), 1),
+            buy(first),
+            close_account(&token_program_id(), &Keypair::new().pubkey(), &payer, &payer, &[]).unwrap(),
+            buy(first),
+        ];
+        assert_eq!(fee_market_accounts(&wrapped, &first), vec![program, fee_recipient]);
+    }
+
+    #[test]
+    fn fee_percentile_ignores_unpaid_slots() {
+        assert_eq!(fee_percentile(&[], 0.75), 0);
+        assert_eq!(fee_percentile(&[0, 0], 0.75), 0);
+        assert_eq!(fee_percentile(&[0, 100, 300, 200, 400], 0.5), 300);
+        assert_eq!(fee_percentile(&[0, 100, 300, 200, 400], 1.0), 400);
+    }
+
+    #[test]
+    fn dex_entries_override_flat_settings_and_disabled_dexes_are_refused() {
+        let config = TransactionConfig {
+            pumpportal_url: Some("https://flat.example".to_string()),
+            dexes: BTreeMap::from([
+                (
+                    "pump.fun".to_string(),
+                    DexConfig {
+                        endpoint: Some("https://pumpportal.example".to_string()),
+                        api_key: Some("key".to_string()),
+                        slippage_bps: Some(1_500),
+                        ..DexConfig::default()
+                    },
+                ),
+                ("letsbonk".to_string(), DexConfig { enabled: false, ..DexConfig::default() }),
+            ]),
+            ..TransactionConfig::default()
+        };
+
+        let pump = config.for_dex(&DexProgram::PumpFun).unwrap();
+        assert_eq!(pump.pumpportal_url.as_deref(), Some("https://pumpportal.example"));
+        assert_eq!(pump.pumpportal_api_key.as_deref(), Some("key"));
+        assert_eq!(pump.slippage_bps, 1_500);
+
+        assert!(matches!(
+            config.for_dex(&DexProgram::LetsBonk),
+            Err(TransactionBuilderError::DexDisabled(name)) if name == "letsbonk"
+        ));
+        // No entry: flat settings, borrowed as-is
+        assert!(matches!(config.for_dex(&DexProgram::Orca).unwrap(), Cow::Borrowed(_)));
+
+        // Escalation widens the DEX's slippage instead of being replaced by it
+        let escalated = TransactionConfig { slippage_escalation_bps: 500, ..config.clone() };
+        let pump = escalated.for_dex(&DexProgram::PumpFun).unwrap();
+        assert_eq!(pump.slippage_bps, 2_000);
+        let orca = escalated.for_dex(&DexProgram::Orca).unwrap();
+        assert_eq!(orca.slippage_bps, config.slippage_bps + 500);
+    }
+
+    #[test]
+    fn referral_account_is_sent_to_external_buy_apis() {
+        let referral = Pubkey::new_unique();
+        let mut payload = serde_json::json!({ "mint": "m" });
+        add_referral(&mut payload, Some(referral));
+        assert_eq!(payload["referral"], referral.to_string());
+
+        let mut payload = serde_json::json!({ "mint": "m" });
+        add_referral(&mut payload, None);
+        assert!(payload.get("referral").is_none());
+    }
+
+    #[test]
+    fn small_sells_share_a_transaction_and_large_sets_split() {
+        let payer = Pubkey::new_unique();
+        let config = TransactionConfig::default();
+        let sell_ix = |data_len: usize| {
+            Instruction::new_with_bytes(
+                Pubkey::new_unique(),
+                &vec![7u8; data_len],
+                (0..4).map(|_| AccountMeta::new(Pubkey::new_unique(), false)).collect(),
+            )
+        };
+
+        let groups = pack_instructions(&payer, vec![vec![sell_ix(24)], vec![sell_ix(24)]], &config, Hash::default());
+        assert_eq!(groups.len(), 1);
+        assert_eq!(groups[0].len(), 2);
+
+        // Three ~450 byte sells (data + accounts) cannot share one 1232-byte packet
+        let sells = (0..3).map(|_| vec![sell_ix(300)]).collect();
+        let groups = pack_instructions(&payer, sells, &config, Hash::default());
+        assert_eq!(groups.iter().map(Vec::len).collect::<Vec<_>>(), vec![2, 1]);
+        for group in &groups {
+            let message = compile_batch(&payer, group, &config, Hash::default()).unwrap();
+            assert!(transaction_size(&message) <= PACKET_DATA_SIZE);
+        }
+
+        // A sell's instructions stay together, and the compute budget counts sells
+        let sells = vec![vec![sell_ix(8), sell_ix(8)], vec![sell_ix(8)], vec![sell_ix(300), sell_ix(300)]];
+        let groups = pack_instructions(&payer, sells, &config, Hash::default());
+        let shape: Vec<Vec<usize>> = groups.iter().map(|group| group.iter().map(Vec::len).collect()).collect();
+        assert_eq!(shape, vec![vec![2, 1], vec![2]]);
+        let message = compile_batch(&payer, &groups[0], &config, Hash::default()).unwrap();
+        assert_eq!(message.instructions().len(), 2 + 3);
+        assert_eq!(
+            message.instructions()[0].data,
+            ComputeBudgetInstruction::set_compute_unit_limit(2 * config.compute_unit_limit).data
+        );
+
+        // The compute limit caps sells per transaction as well
+        let heavy = TransactionConfig { compute_unit_limit: 700_000, ..TransactionConfig::default() };
+        let groups = pack_instructions(&payer, (0..3).map(|_| vec![sell_ix(8)]).collect(), &heavy, Hash::default());
+        assert_eq!(groups.iter().map(Vec::len).collect::<Vec<_>>(), vec![2, 1]);
+    }
+
+    #[test]
+    fn min_out_rounds_down_and_saturates() {
+        assert_eq!(min_out_after_slippage(1_000, 100), 990);
+        // 999 * 0.99 = 989.01
+        assert_eq!(min_out_after_slippage(999, 100), 989);
+        assert_eq!(min_out_after_slippage(1, 1), 0);
+        assert_eq!(min_out_after_slippage(u64::MAX, 0), u64::MAX);
+        assert_eq!(min_out_after_slippage(1_000, 20_000), 0);
+    }
+
+    #[tokio::test]
+    async fn durable_nonce_buy_advances_the_nonce_and_releases_its_slot() {
+        let wallet = Arc::new(crate::wallet::WalletManager::new_random());
+        let nonce = DurableNonce {
+            account: Pubkey::new_unique(),
+            authority: wallet.pubkey(),
+            hash: Some(Hash::new_unique()),
+        };
+        let manager = Arc::new(NonceManager::with_durable_nonces(vec![nonce.clone()]));
+        let config = TransactionConfig::default();
+        let builder = TransactionBuilder::new(wallet, vec!["http://127.0.0.1:1".to_string()], manager.clone(), &config)
+            .await
+            .unwrap();
+        let candidate = PremintCandidate {
+            mint: Pubkey::new_unique(),
+            creator: Pubkey::new_unique(),
+            program: "pump.fun".to_string(),
+            slot: 0,
+            timestamp: 0,
+            instruction_summary: None,
+            is_jito_bundle: None,
+            deadline_ms: None,
+        };
+
+        let tx = builder.build_buy_transaction(&candidate, &config, true).await.unwrap();
+        assert_eq!(*tx.message.recent_blockhash(), nonce.hash.unwrap());
+        let first = &tx.message.instructions()[0];
+        let keys = tx.message.static_account_keys();
+        assert_eq!(keys[first.program_id_index as usize], Pubkey::default(), "system program first");
+        assert_eq!(keys[first.accounts[0] as usize], nonce.account);
+        assert!(tx.verify_with_results().iter().all(|ok| *ok));
+
+        // The slot is free again, and its nonce is fetched before the next build
+        assert_eq!(manager.available_permits(), 1);
+        assert_eq!(manager.durable_nonce(0).unwrap().hash, None);
+    }
+
+    #[tokio::test]
+    async fn external_api_memo_instruction_simulates_successfully() {
+        let config = TransactionConfig::default();
+        let builder = offline_builder(&config).await.use_mock_rpc_for_tests();
+        let wallet = builder.wallet.clone();
+
+        let response = serde_json::json!({
+            "program_id": spl_memo::MEMO_PROGRAM_ID.to_string(),
+            "data": general_purpose::STANDARD.encode(b"simulate me"),
+        });
+        let memo = builder.parse_external_api_response(&response, "pumpportal", &config).unwrap();
+        let message = compile_message(&wallet.pubkey(), &[memo], Hash::new_unique(), false).unwrap();
+        let mut tx = VersionedTransaction { signatures: vec![], message };
+        wallet.sign_transaction(&mut tx).unwrap();
+
+        let outcome = builder.simulate_transaction(&tx, &config).await.unwrap();
+        assert!(outcome.succeeded(), "{:?}", outcome.error);
+    }
+
+    #[tokio::test]
+    async fn dynamic_priority_fee_prices_buys_from_recent_fees() {
+        let fees = serde_json::json!([
+            { "slot": 1, "prioritizationFee": 0 },
+            { "slot": 2, "prioritizationFee": 5_000 },
+            { "slot": 3, "prioritizationFee": 20_000 },
+            { "slot": 4, "prioritizationFee": 10_000 },
+        ]);
+        let config = TransactionConfig { dynamic_priority_fee: true, max_priority_fee_lamports: 15_000, ..TransactionConfig::default() };
+        let builder = offline_builder(&config)
+            .await
+            .use_mock_rpc_responses_for_tests(Mocks::from([(RpcRequest::GetRecentPrioritizationFees, fees)]));
+
+        let accounts = [Pubkey::new_unique()];
+        assert_eq!(builder.estimate_priority_fee(&accounts, 75).await.unwrap(), 20_000);
+        // Cached: the mocked response is used up, a refetch would see the 10_000 default
+        assert_eq!(builder.estimate_priority_fee(&accounts, 75).await.unwrap(), 20_000);
+        assert_eq!(builder.estimate_priority_fee(&[Pubkey::new_unique()], 75).await.unwrap(), 10_000);
+
+        // The buy's compute-unit price is the estimate, clamped to the max
+        let candidate = PremintCandidate {
+            mint: Pubkey::new_unique(),
+            creator: Pubkey::new_unique(),
+            program: "pump.fun".to_string(),
+            slot: 0,
+            timestamp: 0,
+            instruction_summary: None,
+            is_jito_bundle: None,
+            deadline_ms: None,
+        };
+        let price_ix = |price: u64| ComputeBudgetInstruction::set_compute_unit_price(price).data;
+        let tx = builder.build_buy_transaction(&candidate, &config, false).await.unwrap();
+        let prices: Vec<_> = tx.message.instructions().iter().map(|ix| ix.data.clone()).collect();
+        assert!(prices.contains(&price_ix(10_000)), "estimate below the max is used as is");
+
+        let capped = TransactionConfig { max_priority_fee_lamports: 8_000, ..config };
+        let tx = builder.build_buy_transaction(&candidate, &capped, false).await.unwrap();
+        assert!(tx.message.instructions().iter().any(|ix| ix.data == price_ix(8_000)));
+    }
+
+    #[tokio::test]
+    async fn lookup_tables_shrink_messages_and_failed_loads_are_cached() {
+        // A swap-like instruction: a handful of pool and mint accounts besides the payer
+        let payer = Pubkey::new_unique();
+        let pool_accounts: Vec<Pubkey> = (0..8).map(|_| Pubkey::new_unique()).collect();
+        let mut metas = vec![AccountMeta::new(payer, true)];
+        metas.extend(pool_accounts.iter().map(|key| AccountMeta::new(*key, false)));
+        let swap = Instruction::new_with_bytes(Pubkey::new_unique(), &[1, 2, 3], metas);
+
+        // A stub table holding the pool accounts; the compiler moves the ones it may
+        // (not signers or invoked programs) out of the static keys
+        #[allow(deprecated)]
+        let table = solana_sdk::address_lookup_table::state::AddressLookupTable {
+            meta: Default::default(),
+            addresses: Cow::Owned(pool_accounts.clone()),
+        };
+        let data = general_purpose::STANDARD.encode(table.serialize_for_tests().unwrap());
+        let account = serde_json::json!({
+            "context": { "slot": 1 },
+            "value": {
+                "lamports": 1_000_000,
+                "data": [data, "base64"],
+                "owner": Pubkey::new_unique().to_string(),
+                "executable": false,
+                "rentEpoch": 0,
+                "space": 0,
+            },
+        });
+        let table_key = Pubkey::new_unique();
+        // Only the first lookup gets the mocked account; the second table is not found
+        let missing_key = Pubkey::new_unique();
+        let config = TransactionConfig {
+            address_lookup_tables: vec![table_key, missing_key],
+            ..TransactionConfig::default()
+        };
+        let builder = offline_builder(&config)
+            .await
+            .use_mock_rpc_responses_for_tests(Mocks::from([(RpcRequest::GetAccountInfo, account)]));
+
+        let tables = builder.address_lookup_tables(&config).await;
+        assert_eq!(tables.len(), 1);
+        assert_eq!(tables[0].key, table_key);
+
+        let blockhash = Hash::new_unique();
+        let plain = compile_message(&payer, std::slice::from_ref(&swap), blockhash, false).unwrap();
+        let shrunk = compile_message_with_tables(&payer, &[swap], blockhash, false, &tables).unwrap();
+        let lookups = shrunk.address_table_lookups().unwrap();
+        assert_eq!(lookups.len(), 1);
+        assert_eq!(lookups[0].account_key, table_key);
+        assert!(transaction_size(&shrunk) < transaction_size(&plain));
+
+        // Cached: the mocked account is used up, a refetch would find no table
+        let again = builder.address_lookup_tables(&config).await;
+        assert_eq!(again.len(), 1);
+        // The missing table is remembered as failed instead of refetched on every build
+        let cache = builder.lookup_table_cache.lock().unwrap();
+        assert!(matches!(cache.get(&missing_key), Some((_, Err(_)))));
+        assert!(matches!(cache.get(&table_key), Some((_, Ok(_)))));
+    }
+
+    fn signed_memo_tx(builder: &TransactionBuilder) -> VersionedTransaction {
+        let memo = Instruction::new_with_bytes(Pubkey::new_unique(), b"bundle", vec![]);
+        let message = compile_message(&builder.wallet.pubkey(), &[memo], Hash::new_unique(), false).unwrap();
+        let mut tx = VersionedTransaction { signatures: vec![], message };
+        builder.wallet.sign_transaction(&mut tx).unwrap();
+        tx
+    }
+
+    #[tokio::test]
+    async fn jito_bundle_is_submitted_and_rejections_surface() {
+        let config = TransactionConfig::default();
+        let builder = offline_builder(&config).await;
+        let txs = vec![signed_memo_tx(&builder), signed_memo_tx(&builder)];
+        let bundle = builder.prepare_jito_bundle(txs.clone(), 10_000, None, &config).await.unwrap();
+
+        let (url, mut requests) = serve_block_engine(vec![rpc_result(serde_json::json!("bundle-123"))]).await;
+        assert_eq!(builder.submit_jito_bundle(&bundle, &url).await.unwrap(), "bundle-123");
+        let request = requests.recv().await.unwrap();
+        let body: serde_json::Value = serde_json::from_str(&request[request.find("\r\n\r\n").unwrap() + 4..]).unwrap();
+        assert_eq!(body["method"], "sendBundle");
+        assert_eq!(body["params"][1]["encoding"], "base64");
+        let sent = general_purpose::STANDARD.decode(body["params"][0][1].as_str().unwrap()).unwrap();
+        assert_eq!(bincode::deserialize::<VersionedTransaction>(&sent).unwrap(), txs[1]);
+
+        let (url, _) = serve_block_engine(vec![(
+            "400 Bad Request",
+            serde_json::json!({ "jsonrpc": "2.0", "id": 1, "error": { "code": -32602, "message": "bundle already processed" } }),
+        )])
+        .await;
+        let err = builder.submit_jito_bundle(&bundle, &url).await.unwrap_err();
+        assert!(err.to_string().contains("bundle already processed"), "{}", err);
+
+        // Over the bundle limit: rejected before anything is sent
+        let oversized = JitoBundleCandidate {
+            transactions: vec![txs[0].clone(); MAX_JITO_BUNDLE_TRANSACTIONS + 1],
+            max_total_cost_lamports: 10_000,
+            target_slot: None,
+        };
+        let err = builder.submit_jito_bundle(&oversized, "http://127.0.0.1:1").await.unwrap_err();
+        assert!(matches!(err, TransactionBuilderError::JitoBundle(_)), "{}", err);
+    }
+
+    #[tokio::test]
+    async fn jito_tip_is_prepended_to_the_first_bundle_transaction() {
+        let config = TransactionConfig { jito_bundle_enabled: true, ..TransactionConfig::default() };
+        let builder = offline_builder(&config).await;
+        let txs = vec![signed_memo_tx(&builder), signed_memo_tx(&builder)];
+
+        let bundle = builder.prepare_jito_bundle(txs.clone(), 25_000, Some(7), &config).await.unwrap();
+        let first = &bundle.transactions[0];
+        let tip = system_instruction::transfer(&builder.wallet.pubkey(), &config.jito_tip_account, 25_000);
+        let instructions = decompile_instructions(&first.message, &[]).unwrap();
+        assert_eq!(instructions[0], tip);
+        assert_eq!(instructions[1].data, b"bundle");
+        assert_eq!(first.message.recent_blockhash(), txs[0].message.recent_blockhash());
+        assert!(first.verify_with_results().iter().all(|ok| *ok));
+        assert_eq!(bundle.transactions[1], txs[1]);
+
+        let untipped = TransactionConfig { jito_bundle_enabled: false, ..config };
+        let bundle = builder.prepare_jito_bundle(txs.clone(), 25_000, None, &untipped).await.unwrap();
+        assert_eq!(bundle.transactions, txs);
+    }
+
+    #[test]
+    fn bundle_statuses_map_from_block_engine_payloads() {
+        let status = |entry: serde_json::Value| {
+            bundle_status_from_result(&serde_json::json!({ "context": { "slot": 242_806_119 }, "value": [entry] })).unwrap()
+        };
+        let landed = |confirmation: &str, err: serde_json::Value| {
+            status(serde_json::json!({
+                "bundle_id": "892b79ed49138bfb3aa5441f0df6e06ef34f9ee8f3976c15b323605bae0cf51d",
+                "transactions": ["3bC2M9fiACSjkTXZDgeNAuQ4ScTsdKGwR42ytFdhUvikqTmBheUxfsR1fDVsM5ADCMMspuwGkdm1uKbU246x5aE3"],
+                "slot": 242_804_011,
+                "confirmation_status": confirmation,
+                "err": err,
+            }))
+        };
+
+        assert_eq!(landed("finalized", serde_json::json!({ "Ok": null })), BundleStatus::Landed { slot: 242_804_011 });
+        assert_eq!(landed("confirmed", serde_json::json!({ "Ok": null })), BundleStatus::Landed { slot: 242_804_011 });
+        assert_eq!(landed("processed", serde_json::json!({ "Ok": null })), BundleStatus::Pending);
+        assert_eq!(
+            landed("confirmed", serde_json::json!({ "Err": { "InstructionError": [0, { "Custom": 6001 }] } })),
+            BundleStatus::Failed
+        );
+        assert_eq!(status(serde_json::Value::Null), BundleStatus::Unknown);
+        assert_eq!(
+            bundle_status_from_result(&serde_json::json!({ "context": { "slot": 1 }, "value": [] })).unwrap(),
+            BundleStatus::Unknown
+        );
+        assert!(matches!(
+            bundle_status_from_result(&serde_json::Value::Null),
+            Err(TransactionBuilderError::JitoBundle(_))
+        ));
+    }
+
+    #[cfg(not(feature = "raydium"))]
+    #[tokio::test]
+    async fn raydium_sell_without_the_feature_is_an_error_not_a_memo() {
+        let config = TransactionConfig::default();
+        let builder = offline_builder(&config).await;
+
+        let err = builder
+            .build_sell_transaction(&Pubkey::new_unique(), "raydium", SellAmount::Fraction(1.0), &config, false)
+            .await
+            .unwrap_err();
+        assert!(
+            matches!(&err, TransactionBuilderError::FeatureNotEnabled { feature, .. } if feature == "raydium"),
+            "{}",
+            err
+        );
+    }
+
+    #[cfg(feature = "raydium")]
+    #[tokio::test]
+    async fn raydium_sell_prices_the_balance_on_the_pool_reserves() {
+        use crate::test_support::AccountsRpc;
+
+        let mint = Pubkey::new_unique();
+        let (address, accounts) = raydium::sol_pool_accounts(&mint, &Pubkey::new_unique(), 300_000_000_000, 60_000_000_000);
+        let rpc = || RpcClient::new_sender(AccountsRpc { accounts: accounts.clone(), token_balance: 1_000_000 }, Default::default());
+        let config = TransactionConfig::default();
+        let builder = offline_builder(&config).await.use_rpc_client_for_tests(rpc());
+
+        let instructions =
+            builder.build_sell_instructions(&mint, "raydium", SellAmount::Fraction(0.5), &config).await.unwrap();
+
+        let pool = raydium::find_sol_pool(&rpc(), &mint).await.unwrap().unwrap();
+        assert_eq!(pool.address, address);
+        let reserves = pool.reserves(300_000_000_000, 60_000_000_000);
+        let min_sol_out = min_out_after_slippage(reserves.sol_out(500_000), builder.slippage_bps_for(&mint, &config));
+        assert!(min_sol_out > 0);
+        assert_eq!(instructions, raydium::sell_instructions(&pool, &builder.wallet.pubkey(), 500_000, min_sol_out));
+
+        // A mint without a pool is an error, not a memo
+        let err = builder
+            .build_sell_instructions(&Pubkey::new_unique(), "raydium", SellAmount::Fraction(1.0), &config)
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("no Raydium SOL pool"), "{}", err);
+    }
+
+    #[test]
+    fn pool_sell_amounts_follow_the_whole_balance_quote() {
+        // 1_000_000 tokens quote at 2 SOL when sold at once
+        let balance = 1_000_000;
+        let balance_out = 2_000_000_000;
+        assert_eq!(pool_sell_amount(balance, SellAmount::Fraction(0.25), balance_out, 0), 250_000);
+        assert_eq!(pool_sell_amount(balance, SellAmount::Fraction(1.5), balance_out, 0), balance);
+        assert_eq!(pool_sell_amount(balance, SellAmount::SolValue(500_000_000), balance_out, 0), 250_000);
+        // Rounded up so the target is reached, and capped at the balance
+        assert_eq!(pool_sell_amount(balance, SellAmount::SolValue(1_000), balance_out, 0), 1);
+        assert_eq!(pool_sell_amount(balance, SellAmount::SolValue(3_000_000_000), balance_out, 0), balance);
+        // A remainder within the dust threshold is sold too
+        assert_eq!(pool_sell_amount(balance, SellAmount::Fraction(0.9995), balance_out, 500), balance);
+        assert_eq!(pool_sell_amount(balance, SellAmount::Fraction(0.999), balance_out, 500), 999_000);
+
+        // The swap's other_amount_threshold is the slipped quote of the sell amount
+        assert_eq!(min_out_after_slippage(500_000_000, 300), 485_000_000);
+    }
+
+    #[cfg(not(feature = "orca"))]
+    #[tokio::test]
+    async fn orca_sell_without_the_feature_is_an_error_not_a_memo() {
+        let config = TransactionConfig::default();
+        let builder = offline_builder(&config).await;
+
+        let err = builder
+            .build_sell_transaction(&Pubkey::new_unique(), "orca", SellAmount::Fraction(1.0), &config, false)
+            .await
+            .unwrap_err();
+        assert!(
+            matches!(&err, TransactionBuilderError::FeatureNotEnabled { feature, .. } if feature == "orca"),
+            "{}",
+            err
+        );
+    }
+
+    /// RPC holding the SOL splash pool of `mint` (price 1, no adaptive fee oracle,
+    /// tick arrays uninitialized), both mints, and `token_balance` of `mint` in the
+    /// wallet; the wallet's token accounts themselves are missing.
+    #[cfg(feature = "orca")]
+    fn orca_pool_rpc(mint: &Pubkey, token_balance: u64) -> RpcClient {
+        use crate::test_support::AccountsRpc;
+        use solana_sdk::program_pack::Pack;
+
+        let [token_a, token_b] = order_mints(spl_token::native_mint::ID, *mint);
+        let mut pool = orca_whirlpools_client::WHIRLPOOL_DISCRIMINATOR.to_vec();
+        pool.extend_from_slice(WHIRLPOOLS_CONFIG_ADDRESS.try_lock().unwrap().as_ref());
+        pool.push(255);
+        pool.extend_from_slice(&SPLASH_POOL_TICK_SPACING.to_le_bytes());
+        // A fee tier seed equal to the tick spacing: no adaptive fee oracle to fetch
+        pool.extend_from_slice(&SPLASH_POOL_TICK_SPACING.to_le_bytes());
+        pool.extend_from_slice(&1_000u16.to_le_bytes());
+        pool.extend_from_slice(&0u16.to_le_bytes());
+        pool.extend_from_slice(&1_000_000_000_000u128.to_le_bytes());
+        pool.extend_from_slice(&(1u128 << 64).to_le_bytes());
+        pool.extend_from_slice(&0i32.to_le_bytes());
+        pool.extend_from_slice(&[0; 16]);
+        for (token, vault) in [(token_a, [1; 32]), (token_b, [2; 32])] {
+            pool.extend_from_slice(token.as_ref());
+            pool.extend_from_slice(&vault);
+            pool.extend_from_slice(&[0; 16]);
+        }
+        pool.resize(orca_whirlpools_client::Whirlpool::LEN, 0);
+        assert_eq!(orca_whirlpools_client::Whirlpool::from_bytes(&pool).unwrap().token_mint_b, token_b);
+
+        let mut mint_data = vec![0; spl_token::state::Mint::LEN];
+        spl_token::state::Mint { decimals: 9, is_initialized: true, ..Default::default() }.pack_into_slice(&mut mint_data);
+        let accounts = HashMap::from([
+            (orca_splash_pool(mint).unwrap(), (orca_whirlpools_client::ID, pool)),
+            (token_a, (spl_token::ID, mint_data.clone())),
+            (token_b, (spl_token::ID, mint_data)),
+        ]);
+        RpcClient::new_sender(AccountsRpc { accounts, token_balance }, Default::default())
+    }
+
+    #[cfg(feature = "orca")]
+    #[tokio::test]
+    async fn orca_sell_swaps_b_to_a_with_the_slipped_quote_as_threshold() {
+        // Sorts after the native mint: SOL is token A, so a sell swaps b -> a
+        let mint = Pubkey::new_from_array([0xee; 32]);
+        let config = TransactionConfig::default();
+        let builder = offline_builder(&config).await.use_rpc_client_for_tests(orca_pool_rpc(&mint, 1_000_000));
+
+        let instructions =
+            builder.build_sell_instructions(&mint, "orca", SellAmount::Fraction(0.5), &config).await.unwrap();
+
+        // The SDK's own swap of the same amount through the same pool
+        let slippage_bps = builder.slippage_bps_for(&mint, &config);
+        let whirlpool = orca_splash_pool(&mint).unwrap();
+        let expected = swap_instructions(
+            &orca_pool_rpc(&mint, 1_000_000),
+            whirlpool,
+            500_000,
+            mint,
+            SwapType::ExactIn,
+            Some(slippage_bps as u16),
+            Some(builder.wallet.pubkey()),
+        )
+        .await
+        .unwrap();
+        assert_eq!(instructions, expected.instructions);
+        // Only the wallet signs: SOL is unwrapped through its WSOL account
+        assert!(expected.additional_signers.is_empty());
+        let SwapQuote::ExactIn(quote) = expected.quote else { panic!("exact-in quote") };
+        assert!(quote.token_est_out > 0);
+
+        // SwapV2 data: discriminator, amount, other_amount_threshold, sqrt_price_limit,
+        // amount_specified_is_input, a_to_b
+        let swap = instructions.iter().find(|ix| ix.program_id == orca_whirlpools_client::ID).unwrap();
+        assert!(swap.accounts.iter().any(|meta| meta.pubkey == whirlpool && meta.is_writable));
+        assert_eq!(&swap.data[8..16], &500_000u64.to_le_bytes());
+        assert_eq!(&swap.data[16..24], &min_out_after_slippage(quote.token_est_out, slippage_bps).to_le_bytes());
+        assert_eq!(swap.data[40], 1);
+        assert_eq!(swap.data[41], 0);
+    }
+
+    #[cfg(not(feature = "letsbonk"))]
+    #[tokio::test]
+    async fn letsbonk_sell_without_the_feature_is_an_error_not_a_memo() {
+        let config = TransactionConfig::default();
+        let builder = offline_builder(&config).await;
+
+        let err = builder
+            .build_sell_transaction(&Pubkey::new_unique(), "letsbonk", SellAmount::Fraction(1.0), &config, false)
+            .await
+            .unwrap_err();
+        assert!(
+            matches!(&err, TransactionBuilderError::FeatureNotEnabled { feature, .. } if feature == "letsbonk"),
+            "{}",
+            err
+        );
+    }
+
+    #[cfg(feature = "letsbonk")]
+    #[tokio::test]
+    async fn letsbonk_sell_prices_the_balance_on_the_pool_curve() {
+        let mut pool = vec![247, 237, 227, 245, 215, 195, 222, 70];
+        pool.resize(37, 0);
+        for value in [1_073_025_605_596_382u64, 30_000_852_951, 73_025_605_596_382, 0] {
+            pool.extend_from_slice(&value.to_le_bytes());
+        }
+        let reserves = letsbonk::PoolReserves::from_account_data(&pool).unwrap();
+        let mocks = Mocks::from([
+            (
+                RpcRequest::GetTokenAccountBalance,
+                serde_json::json!({
+                    "context": { "slot": 1 },
+                    "value": { "amount": "1000000", "decimals": 6, "uiAmount": 1.0, "uiAmountString": "1" },
+                }),
+            ),
+            (
+                RpcRequest::GetAccountInfo,
+                serde_json::json!({
+                    "context": { "slot": 1 },
+                    "value": {
+                        "lamports": 1_000_000,
+                        "data": [general_purpose::STANDARD.encode(&pool), "base64"],
+                        "owner": letsbonk::LAUNCHLAB_PROGRAM_ID.to_string(),
+                        "executable": false,
+                        "rentEpoch": 0,
+                        "space": pool.len(),
+                    },
+                }),
+            ),
+        ]);
+        let config = TransactionConfig { slippage_bps: 500, ..TransactionConfig::default() };
+        let builder = offline_builder(&config).await.use_mock_rpc_responses_for_tests(mocks);
+        let mint = Pubkey::new_unique();
+
+        let instructions =
+            builder.build_sell_instructions(&mint, "letsbonk", SellAmount::Fraction(0.5), &config).await.unwrap();
+        let min_sol_out = min_out_after_slippage(reserves.sol_out(500_000), builder.slippage_bps_for(&mint, &config));
+        assert_eq!(instructions, letsbonk::sell_instructions(&builder.wallet.pubkey(), &mint, 500_000, min_sol_out));
+    }
+
+    #[cfg(not(feature = "letsbonk"))]
+    #[tokio::test]
+    async fn letsbonk_buy_without_native_path_or_api_is_an_error_not_a_memo() {
+        let config = TransactionConfig::default();
+        let builder = offline_builder(&config).await;
+        let candidate = PremintCandidate {
+            mint: Pubkey::new_unique(),
+            creator: Pubkey::new_unique(),
+            program: "letsbonk".to_string(),
+            slot: 0,
+            timestamp: 0,
+            instruction_summary: None,
+            is_jito_bundle: None,
+            deadline_ms: None,
+        };
+
+        let err = builder.build_buy_transaction(&candidate, &config, false).await.unwrap_err();
+        assert!(
+            matches!(&err, TransactionBuilderError::FeatureNotEnabled { feature, .. } if feature == "letsbonk"),
+            "{}",
+            err
+        );
+
+        // An unreachable API fails the buy too
+        let with_api = TransactionConfig { letsbonk_api_url: Some("http://127.0.0.1:1/buy".to_string()), ..config };
+        let err = builder.build_buy_transaction(&candidate, &with_api, false).await.unwrap_err();
+        assert!(matches!(&err, TransactionBuilderError::InstructionBuild { program, .. } if program == "letsbonk"), "{}", err);
+    }
+}
@@ -1,13 +1,18 @@
 use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::Signature;
 use solana_sdk::transaction::VersionedTransaction;
 use solana_sdk::message::{v0, VersionedMessage};
 use solana_sdk::instruction::Instruction;
 use solana_sdk::hash::Hash;
 use tokio::sync::mpsc;
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::cmp::{Ordering, Reverse};
+use std::collections::{BTreeMap, BinaryHeap, HashMap, VecDeque};
+use std::str::FromStr;
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+use crate::time_utils::now_ms;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct PremintCandidate {
     pub mint: Pubkey,
     pub creator: Pubkey,
@@ -16,6 +21,92 @@ pub struct PremintCandidate {
     pub timestamp: u64,
     pub instruction_summary: Option<String>,
     pub is_jito_bundle: Option<bool>,
+    /// Unix ms after which the candidate is too stale to buy; stamped at ingest.
+    #[serde(default)]
+    pub deadline_ms: Option<u64>,
+}
+
+impl PremintCandidate {
+    /// Stamp a deadline `budget_ms` from now unless one is already set.
+    pub fn stamp_deadline(&mut self, budget_ms: u64) {
+        if self.deadline_ms.is_none() {
+            self.deadline_ms = Some(now_ms().saturating_add(budget_ms));
+        }
+    }
+
+    /// Whether the candidate's deadline has passed at `now_ms`.
+    pub fn is_past_deadline(&self, now_ms: u64) -> bool {
+        self.deadline_ms.is_some_and(|deadline| now_ms > deadline)
+    }
+}
+
+// Supported DEX programs (Meteora removed)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DexProgram {
+    PumpFun,
+    LetsBonk,
+    Raydium,
+    Orca,
+    Unknown(String),
+}
+
+/// On-chain program ids of the supported DEXes
+pub const PUMP_FUN_PROGRAM_ID: &str = "6EF8rrecthR5Dkzon8Nwu78hRvfCKubJ14M5uBEwF6P";
+pub const LETSBONK_PROGRAM_ID: &str = "LanMV9sAd7wArD4vJFi2qDdfnVhFxYSUg6eADduJ3uj";
+pub const RAYDIUM_AMM_PROGRAM_ID: &str = "675kPX9MHTjS2zt1qfr1NYHuzeLXfQM9H24wFSUt1Mp8";
+pub const ORCA_WHIRLPOOL_PROGRAM_ID: &str = "whirLbMiicVdio4qvUfM5KAg6Ct8VwpYzGff3uctyCc";
+
+impl DexProgram {
+    /// Canonical name used for `PremintCandidate.program` throughout the pipeline
+    pub fn canonical_name(&self) -> &str {
+        match self {
+            DexProgram::PumpFun => "pump.fun",
+            DexProgram::LetsBonk => "letsbonk",
+            DexProgram::Raydium => "raydium",
+            DexProgram::Orca => "orca",
+            DexProgram::Unknown(s) => s.as_str(),
+        }
+    }
+
+    /// On-chain program id, if known
+    pub fn program_id(&self) -> Option<Pubkey> {
+        let id = match self {
+            DexProgram::PumpFun => PUMP_FUN_PROGRAM_ID,
+            DexProgram::LetsBonk => LETSBONK_PROGRAM_ID,
+            DexProgram::Raydium => RAYDIUM_AMM_PROGRAM_ID,
+            DexProgram::Orca => ORCA_WHIRLPOOL_PROGRAM_ID,
+            DexProgram::Unknown(s) => s.as_str(),
+        };
+        Pubkey::from_str(id).ok()
+    }
+
+    /// Normalize a friendly name or program id, consulting configured aliases first.
+    /// Aliases map an arbitrary name or program id to a canonical name understood by `From<&str>`.
+    pub fn normalize(program: &str, aliases: &HashMap<String, String>) -> Self {
+        match aliases.get(program) {
+            Some(canonical) => DexProgram::from(canonical.as_str()),
+            None => DexProgram::from(program),
+        }
+    }
+}
+
+impl From<&str> for DexProgram {
+    fn from(s: &str) -> Self {
+        match s {
+            PUMP_FUN_PROGRAM_ID => return DexProgram::PumpFun,
+            LETSBONK_PROGRAM_ID => return DexProgram::LetsBonk,
+            RAYDIUM_AMM_PROGRAM_ID => return DexProgram::Raydium,
+            ORCA_WHIRLPOOL_PROGRAM_ID => return DexProgram::Orca,
+            _ => {}
+        }
+        match s.to_lowercase().as_str() {
+            "pump.fun" | "pumpfun" | "pumpportal" => DexProgram::PumpFun,
+            "letsbonk.fun" | "letsbonk" | "bonk" => DexProgram::LetsBonk,
+            "raydium" => DexProgram::Raydium,
+            "orca" => DexProgram::Orca,
+            _ => DexProgram::Unknown(s.to_string()),
+        }
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -27,6 +118,102 @@ pub struct QuantumCandidateGui {
     pub timestamp: u64,
 }
 
+/// Default number of quantum suggestions kept in AppState.
+pub const DEFAULT_MAX_QUANTUM_SUGGESTIONS: usize = 50;
+
+/// Suggestion ordered by score, newer first among equal scores.
+#[derive(Debug, Clone)]
+struct RankedSuggestion(QuantumCandidateGui);
+
+impl RankedSuggestion {
+    fn rank(&self) -> (u8, u64) {
+        (self.0.score, self.0.timestamp)
+    }
+}
+
+impl PartialEq for RankedSuggestion {
+    fn eq(&self, other: &Self) -> bool {
+        self.rank() == other.rank()
+    }
+}
+
+impl Eq for RankedSuggestion {}
+
+impl PartialOrd for RankedSuggestion {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for RankedSuggestion {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.rank().cmp(&other.rank())
+    }
+}
+
+/// The best `max` quantum suggestions by score, one per mint.
+///
+/// A min-heap: the lowest-scoring suggestion is on top, so a better one replaces
+/// it in O(log n) once full.
+#[derive(Debug, Clone)]
+pub struct QuantumSuggestions {
+    max: usize,
+    heap: BinaryHeap<Reverse<RankedSuggestion>>,
+}
+
+impl QuantumSuggestions {
+    pub fn new(max: usize) -> Self {
+        Self { max, heap: BinaryHeap::with_capacity(max) }
+    }
+
+    /// Add `suggestion`, replacing an earlier one for the same mint. Returns the
+    /// suggestion dropped to stay within the cap (possibly `suggestion` itself).
+    pub fn push(&mut self, suggestion: QuantumCandidateGui) -> Option<QuantumCandidateGui> {
+        self.heap.retain(|Reverse(ranked)| ranked.0.mint != suggestion.mint);
+        if self.max == 0 {
+            return Some(suggestion);
+        }
+        if self.heap.len() < self.max {
+            self.heap.push(Reverse(RankedSuggestion(suggestion)));
+            return None;
+        }
+        let candidate = RankedSuggestion(suggestion);
+        match self.heap.peek() {
+            Some(Reverse(lowest)) if candidate > *lowest => {
+                let dropped = self.heap.pop().map(|Reverse(ranked)| ranked.0);
+                self.heap.push(Reverse(candidate));
+                dropped
+            }
+            _ => Some(candidate.0),
+        }
+    }
+
+    /// Suggestions best-first.
+    pub fn sorted(&self) -> Vec<QuantumCandidateGui> {
+        let mut ranked: Vec<_> = self.heap.iter().map(|Reverse(ranked)| ranked.clone()).collect();
+        ranked.sort_by(|a, b| b.cmp(a));
+        ranked.into_iter().map(|ranked| ranked.0).collect()
+    }
+
+    pub fn len(&self) -> usize {
+        self.heap.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.heap.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.heap.clear();
+    }
+}
+
+impl Default for QuantumSuggestions {
+    fn default() -> Self {
+        Self::new(DEFAULT_MAX_QUANTUM_SUGGESTIONS)
+    }
+}
+
 pub type CandidateSender = mpsc::Sender<PremintCandidate>;
 pub type CandidateReceiver = mpsc::Receiver<PremintCandidate>;
 
@@ -37,19 +224,449 @@ pub enum Mode {
     QuantumManual,
 }
 
+/// Severity of an operator notification.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum NotificationLevel {
+    Info,
+    Warn,
+    Error,
+}
+
+impl NotificationLevel {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            NotificationLevel::Info => "INFO",
+            NotificationLevel::Warn => "WARN",
+            NotificationLevel::Error => "ERROR",
+        }
+    }
+}
+
+/// Operator-facing event shown in the GUI event log.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Notification {
+    pub timestamp_ms: u64,
+    pub level: NotificationLevel,
+    pub message: String,
+}
+
+/// Number of notifications kept in AppState.
+pub const MAX_NOTIFICATIONS: usize = 50;
+
+/// A buy or sell currently in flight, keyed in AppState by its correlation id.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct InFlightOperation {
+    pub kind: String,
+    pub mint: Pubkey,
+    pub stage: String,
+    pub started_ms: u64,
+}
+
+/// An open position, keyed in AppState by its mint.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Position {
+    pub token: PremintCandidate,
+    pub buy_price: Option<f64>,
+    pub holdings_percent: f64,
+    /// Slot of the candidate the position was bought from
+    pub entry_slot: u64,
+    pub opened_ms: u64,
+    /// SOL (lamports) paid for the whole position; 0 when unknown (e.g. recovered)
+    #[serde(default)]
+    pub cost_lamports: u64,
+    /// PnL (lamports) realized by the sells so far
+    #[serde(default)]
+    pub realized_pnl_lamports: f64,
+    /// Average sell price so far, weighted by the fraction each sell sold
+    #[serde(default)]
+    pub avg_sell_price: Option<f64>,
+    /// Signature of a buy that was sent but not confirmed in time; the position is
+    /// held back from exits until the reconciler sees it land or drops it.
+    #[serde(default)]
+    pub unconfirmed_signature: Option<Signature>,
+    /// Wallet balance of the token read before the buy was sent; the position is
+    /// what the wallet holds beyond it. `None` when not read (paper, recovered).
+    #[serde(default)]
+    pub balance_before_buy: Option<u64>,
+}
+
+impl Position {
+    pub fn new(token: PremintCandidate, buy_price: Option<f64>, holdings_percent: f64) -> Self {
+        Self {
+            entry_slot: token.slot,
+            token,
+            buy_price,
+            holdings_percent,
+            opened_ms: now_ms(),
+            cost_lamports: 0,
+            realized_pnl_lamports: 0.0,
+            avg_sell_price: None,
+            unconfirmed_signature: None,
+            balance_before_buy: None,
+        }
+    }
+
+    /// Whether the buy that opened the position is still unconfirmed.
+    pub fn is_unconfirmed(&self) -> bool {
+        self.unconfirmed_signature.is_some()
+    }
+
+    /// PnL (lamports) of `fraction` of the original position at `price` against
+    /// the buy price; 0 without a buy price.
+    pub fn pnl_at(&self, fraction: f64, price: f64) -> f64 {
+        match self.buy_price {
+            Some(buy_price) if buy_price > 0.0 && price.is_finite() => {
+                self.cost_lamports as f64 * fraction * (price / buy_price - 1.0)
+            }
+            _ => 0.0,
+        }
+    }
+}
+
+/// Default number of price samples kept per held mint for the GUI chart.
+pub const DEFAULT_PRICE_HISTORY_SAMPLES: usize = 120;
+
+/// Prices of a held mint since entry, in at most `capacity` samples. When full,
+/// every other sample is dropped and later prices are sampled half as often, so
+/// the chart keeps spanning the whole position at a coarser resolution.
+#[derive(Debug, Clone, PartialEq)]
+pub struct EntryPriceChart {
+    samples: Vec<f64>,
+    capacity: usize,
+    /// Prices recorded per sample kept
+    stride: usize,
+    /// Prices recorded since the last sample kept
+    pending: usize,
+}
+
+impl EntryPriceChart {
+    pub fn new(capacity: usize) -> Self {
+        Self { samples: Vec::with_capacity(capacity), capacity: capacity.max(2), stride: 1, pending: 0 }
+    }
+
+    /// Record `price`; only every `stride`th one is kept.
+    pub fn push(&mut self, price: f64) {
+        self.pending += 1;
+        if self.pending < self.stride {
+            return;
+        }
+        self.pending = 0;
+        if self.samples.len() >= self.capacity {
+            downsample_in_half(&mut self.samples);
+            self.stride *= 2;
+        }
+        self.samples.push(price);
+    }
+
+    /// Samples, oldest first.
+    pub fn samples(&self) -> &[f64] {
+        &self.samples
+    }
+}
+
+/// Keep every other sample of `samples`, starting with the first, in order.
+pub fn downsample_in_half(samples: &mut Vec<f64>) {
+    let mut index = 0;
+    samples.retain(|_| {
+        index += 1;
+        index % 2 == 1
+    });
+}
+
+/// Realized PnL and spending across positions, plus the latest price of each
+/// held mint for unrealized PnL. Kept until the operator resets it.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct PnlLedger {
+    /// PnL (lamports) realized by every sell since the last reset
+    pub realized_lamports: f64,
+    /// SOL (lamports) spent on buys since the last reset
+    pub spent_lamports: u64,
+    prices: HashMap<Pubkey, f64>,
+    /// Samples kept per price chart; 0 keeps none
+    history_samples: usize,
+    charts: HashMap<Pubkey, EntryPriceChart>,
+}
+
+impl PnlLedger {
+    /// Ledger keeping up to `samples` prices per held mint (0: no charts).
+    pub fn with_history_samples(samples: usize) -> Self {
+        Self { history_samples: samples, ..Self::default() }
+    }
+
+    /// Latest price recorded for `mint`.
+    pub fn price(&self, mint: &Pubkey) -> Option<f64> {
+        self.prices.get(mint).copied()
+    }
+
+    /// Prices recorded for `mint` since entry, oldest first; empty without a chart.
+    pub fn price_chart(&self, mint: &Pubkey) -> &[f64] {
+        self.charts.get(mint).map_or(&[], EntryPriceChart::samples)
+    }
+}
+
+/// Shared bot state.
+///
+/// `positions` holds every open position. `mode`, `active_token`,
+/// `last_buy_price` and `holdings_percent` mirror the focused one (the most
+/// recently opened, unless refocused) for single-token views such as the GUI;
+/// change positions through the methods below so both stay in sync. A position
+/// set only through those fields (older snapshots, recovery) counts as open and
+/// is adopted into `positions` on the next change.
 #[derive(Debug, Clone)]
 pub struct AppState {
     pub mode: Mode,
     pub active_token: Option<PremintCandidate>,
     pub last_buy_price: Option<f64>,
     pub holdings_percent: f64,
-    pub quantum_suggestions: Vec<QuantumCandidateGui>,
+    pub quantum_suggestions: QuantumSuggestions,
+    pub notifications: VecDeque<Notification>,
+    pub operations: BTreeMap<u64, InFlightOperation>,
+    pub positions: HashMap<Pubkey, Position>,
+    pub pnl: PnlLedger,
 }
 
 impl AppState {
     pub fn is_sniffing(&self) -> bool {
         matches!(self.mode, Mode::Sniffing)
     }
+
+    /// Whether another position may be opened with at most `max_positions` open.
+    pub fn accepts_buys(&self, max_positions: usize) -> bool {
+        !matches!(self.mode, Mode::QuantumManual) && self.position_count() < max_positions
+    }
+
+    /// The position set through the single-token fields, if any.
+    fn focused_position(&self) -> Option<Position> {
+        match (&self.mode, &self.active_token) {
+            (Mode::PassiveToken(mint), Some(token)) if token.mint == *mint => {
+                let mut position = self
+                    .positions
+                    .get(mint)
+                    .cloned()
+                    .unwrap_or_else(|| Position::new(token.clone(), self.last_buy_price, self.holdings_percent));
+                position.holdings_percent = self.holdings_percent;
+                Some(position)
+            }
+            _ => None,
+        }
+    }
+
+    pub fn position(&self, mint: &Pubkey) -> Option<Position> {
+        match self.focused_position() {
+            Some(focused) if focused.token.mint == *mint => Some(focused),
+            _ => self.positions.get(mint).cloned(),
+        }
+    }
+
+    /// Open positions, most recent first.
+    pub fn open_positions(&self) -> Vec<Position> {
+        let mut positions: Vec<Position> = self.positions.values().cloned().collect();
+        if let Some(focused) = self.focused_position() {
+            positions.retain(|p| p.token.mint != focused.token.mint);
+            positions.push(focused);
+        }
+        positions.sort_by_key(|p| std::cmp::Reverse((p.opened_ms, p.entry_slot)));
+        positions
+    }
+
+    pub fn position_count(&self) -> usize {
+        let adopted = self
+            .focused_position()
+            .is_some_and(|focused| !self.positions.contains_key(&focused.token.mint));
+        self.positions.len() + usize::from(adopted)
+    }
+
+    /// Record a bought position and focus it.
+    pub fn open_position(&mut self, position: Position) {
+        self.adopt_focused_position();
+        let mint = position.token.mint;
+        self.positions.insert(mint, position);
+        self.focus(Some(mint));
+    }
+
+    /// Set what is left of the position in `mint`, closing it at zero. Returns
+    /// whether the position was closed.
+    pub fn set_position_holdings(&mut self, mint: &Pubkey, holdings_percent: f64) -> bool {
+        self.adopt_focused_position();
+        if holdings_percent <= f64::EPSILON {
+            return self.close_position(mint).is_some();
+        }
+        let Some(position) = self.positions.get_mut(mint) else {
+            return false;
+        };
+        position.holdings_percent = holdings_percent;
+        if matches!(self.mode, Mode::PassiveToken(focused) if focused == *mint) {
+            self.holdings_percent = holdings_percent;
+        }
+        false
+    }
+
+    /// Route later trades of the position in `mint` through `program`, e.g. once the
+    /// token migrated. Returns whether the position is held.
+    pub fn set_position_program(&mut self, mint: &Pubkey, program: &str) -> bool {
+        self.adopt_focused_position();
+        let Some(position) = self.positions.get_mut(mint) else {
+            return false;
+        };
+        position.token.program = program.to_string();
+        if let Some(token) = self.active_token.as_mut().filter(|token| token.mint == *mint) {
+            token.program = program.to_string();
+        }
+        true
+    }
+
+    /// Book a buy of `cost_lamports` in the ledger.
+    pub fn record_buy(&mut self, cost_lamports: u64) {
+        self.pnl.spent_lamports += cost_lamports;
+    }
+
+    /// Mark the unconfirmed buy of `mint` as landed. Returns whether it was unconfirmed.
+    pub fn confirm_position(&mut self, mint: &Pubkey) -> bool {
+        self.adopt_focused_position();
+        self.positions
+            .get_mut(mint)
+            .is_some_and(|position| position.unconfirmed_signature.take().is_some())
+    }
+
+    /// Close the position of an unconfirmed buy that never landed, taking its cost
+    /// back out of the ledger. Returns the discarded position.
+    pub fn discard_unlanded_position(&mut self, mint: &Pubkey) -> Option<Position> {
+        if !self.position(mint)?.is_unconfirmed() {
+            return None;
+        }
+        let position = self.close_position(mint)?;
+        self.pnl.spent_lamports = self.pnl.spent_lamports.saturating_sub(position.cost_lamports);
+        Some(position)
+    }
+
+    /// Book selling `fraction` of the original position in `mint` at `price`: its
+    /// PnL is added to the position and the ledger, and the position's average sell
+    /// price moves by the fraction sold. Call before reducing the holdings. Returns
+    /// the PnL realized.
+    pub fn record_sell(&mut self, mint: &Pubkey, fraction: f64, price: f64) -> f64 {
+        self.adopt_focused_position();
+        let Some(position) = self.positions.get_mut(mint) else {
+            return 0.0;
+        };
+        let realized = position.pnl_at(fraction, price);
+        let sold_before = (1.0 - position.holdings_percent).max(0.0);
+        position.avg_sell_price = Some(match position.avg_sell_price {
+            Some(avg) if sold_before + fraction > 0.0 => {
+                (avg * sold_before + price * fraction) / (sold_before + fraction)
+            }
+            _ => price,
+        });
+        position.realized_pnl_lamports += realized;
+        self.pnl.realized_lamports += realized;
+        realized
+    }
+
+    /// Record the current price of held `mint`, for unrealized PnL.
+    pub fn mark_price(&mut self, mint: Pubkey, price: f64) {
+        if price.is_finite() {
+            self.pnl.prices.insert(mint, price);
+            if self.pnl.history_samples > 0 {
+                let samples = self.pnl.history_samples;
+                self.pnl.charts.entry(mint).or_insert_with(|| EntryPriceChart::new(samples)).push(price);
+            }
+        }
+    }
+
+    /// Unrealized PnL (lamports) of what is still held of the position in `mint`
+    /// at its recorded price; `None` without a price.
+    pub fn unrealized_pnl(&self, position: &Position) -> Option<f64> {
+        let price = self.pnl.price(&position.token.mint)?;
+        Some(position.pnl_at(position.holdings_percent, price))
+    }
+
+    /// Unrealized PnL (lamports) over the open positions with a recorded price.
+    pub fn unrealized_pnl_lamports(&self) -> f64 {
+        self.open_positions().iter().filter_map(|p| self.unrealized_pnl(p)).sum()
+    }
+
+    /// Start realized PnL and spending over (operator action); open positions keep
+    /// their own realized PnL.
+    pub fn reset_realized_pnl(&mut self) {
+        self.pnl.realized_lamports = 0.0;
+        self.pnl.spent_lamports = 0;
+    }
+
+    /// Drop the position in `mint`; focus moves to the most recent one left.
+    pub fn close_position(&mut self, mint: &Pubkey) -> Option<Position> {
+        self.adopt_focused_position();
+        let closed = self.positions.remove(mint);
+        self.pnl.prices.remove(mint);
+        self.pnl.charts.remove(mint);
+        if matches!(self.mode, Mode::PassiveToken(focused) if focused == *mint) {
+            let next = self
+                .positions
+                .values()
+                .max_by_key(|p| (p.opened_ms, p.entry_slot))
+                .map(|p| p.token.mint);
+            self.focus(next);
+        }
+        closed
+    }
+
+    /// Show the position in `mint` (or none) through the single-token fields.
+    pub fn focus(&mut self, mint: Option<Pubkey>) {
+        let position = mint.and_then(|m| self.positions.get(&m).cloned());
+        if !matches!(self.mode, Mode::QuantumManual) {
+            self.mode = match &position {
+                Some(p) => Mode::PassiveToken(p.token.mint),
+                None => Mode::Sniffing,
+            };
+        }
+        self.last_buy_price = position.as_ref().and_then(|p| p.buy_price);
+        self.holdings_percent = position.as_ref().map_or(0.0, |p| p.holdings_percent);
+        self.active_token = position.map(|p| p.token);
+    }
+
+    fn adopt_focused_position(&mut self) {
+        if let Some(focused) = self.focused_position() {
+            self.positions.insert(focused.token.mint, focused);
+        }
+    }
+
+    /// Record an operator notification, keeping the newest `MAX_NOTIFICATIONS`.
+    pub fn notify(&mut self, level: NotificationLevel, message: impl Into<String>) {
+        if self.notifications.len() >= MAX_NOTIFICATIONS {
+            self.notifications.pop_front();
+        }
+        self.notifications.push_back(Notification {
+            timestamp_ms: now_ms(),
+            level,
+            message: message.into(),
+        });
+    }
+
+    /// Register an in-flight operation under its pipeline correlation id.
+    pub fn start_operation(&mut self, correlation_id: u64, kind: &str, mint: Pubkey) {
+        self.operations.insert(
+            correlation_id,
+            InFlightOperation {
+                kind: kind.to_string(),
+                mint,
+                stage: "started".to_string(),
+                started_ms: now_ms(),
+            },
+        );
+    }
+
+    /// Update the stage of an in-flight operation; unknown ids are ignored.
+    pub fn set_operation_stage(&mut self, correlation_id: u64, stage: &str) {
+        if let Some(op) = self.operations.get_mut(&correlation_id) {
+            op.stage = stage.to_string();
+        }
+    }
+
+    pub fn finish_operation(&mut self, correlation_id: u64) -> Option<InFlightOperation> {
+        self.operations.remove(&correlation_id)
+    }
+
+    pub fn active_operations(&self) -> &BTreeMap<u64, InFlightOperation> {
+        &self.operations
+    }
 }
 
 #[derive(Clone, Debug)]
@@ -96,4 +713,184 @@ pub fn create_versioned_transaction(
         signatures: vec![solana_sdk::signature::Signature::default()],
         message: VersionedMessage::V0(message),
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn friendly_name_and_program_id_normalize_to_same_variant() {
+        let cases = [
+            ("pump.fun", PUMP_FUN_PROGRAM_ID, DexProgram::PumpFun),
+            ("letsbonk", LETSBONK_PROGRAM_ID, DexProgram::LetsBonk),
+            ("raydium", RAYDIUM_AMM_PROGRAM_ID, DexProgram::Raydium),
+            ("orca", ORCA_WHIRLPOOL_PROGRAM_ID, DexProgram::Orca),
+        ];
+        for (name, id, expected) in cases {
+            assert_eq!(DexProgram::from(name), expected);
+            assert_eq!(DexProgram::from(id), expected);
+            assert_eq!(DexProgram::from(expected.canonical_name()), expected);
+            assert_eq!(expected.program_id().unwrap().to_string(), id);
+        }
+    }
+
+    #[test]
+    fn aliases_take_precedence_over_builtin_mapping() {
+        let mut aliases = HashMap::new();
+        aliases.insert("MyForkProgram111".to_string(), "pump.fun".to_string());
+
+        assert_eq!(DexProgram::normalize("MyForkProgram111", &aliases), DexProgram::PumpFun);
+        assert_eq!(DexProgram::normalize("PumpFun", &aliases), DexProgram::PumpFun);
+        assert_eq!(
+            DexProgram::normalize("something-else", &aliases),
+            DexProgram::Unknown("something-else".to_string())
+        );
+    }
+
+    #[test]
+    fn starting_and_finishing_operation_tracks_correlation_id() {
+        let mut state = AppState {
+            mode: Mode::Sniffing,
+            active_token: None,
+            last_buy_price: None,
+            holdings_percent: 0.0,
+            quantum_suggestions: QuantumSuggestions::default(),
+            notifications: VecDeque::new(),
+            operations: BTreeMap::new(),
+            positions: HashMap::new(),
+            pnl: PnlLedger::default(),
+        };
+        let mint = Pubkey::new_unique();
+
+        state.start_operation(42, "buy", mint);
+        state.set_operation_stage(42, "confirming");
+        let op = &state.active_operations()[&42];
+        assert_eq!((op.kind.as_str(), op.mint, op.stage.as_str()), ("buy", mint, "confirming"));
+
+        assert!(state.finish_operation(42).is_some());
+        assert!(!state.active_operations().contains_key(&42));
+    }
+
+    #[test]
+    fn partial_sells_at_different_prices_accumulate_weighted_pnl() {
+        let mut state = AppState {
+            mode: Mode::Sniffing,
+            active_token: None,
+            last_buy_price: None,
+            holdings_percent: 0.0,
+            quantum_suggestions: QuantumSuggestions::default(),
+            notifications: VecDeque::new(),
+            operations: BTreeMap::new(),
+            positions: HashMap::new(),
+            pnl: PnlLedger::default(),
+        };
+        let token = PremintCandidate {
+            mint: Pubkey::new_unique(),
+            creator: Pubkey::new_unique(),
+            program: "pump.fun".to_string(),
+            slot: 0,
+            timestamp: 0,
+            instruction_summary: None,
+            is_jito_bundle: None,
+            deadline_ms: None,
+        };
+        let mint = token.mint;
+        // 1 SOL in at 2.0
+        state.open_position(Position { cost_lamports: 1_000_000_000, ..Position::new(token, Some(2.0), 1.0) });
+        state.record_buy(1_000_000_000);
+        state.mark_price(mint, 3.0);
+        assert!((state.unrealized_pnl_lamports() - 500_000_000.0).abs() < 1e-3);
+
+        // 40% at 3.0 (+50%), then 60% at 1.0 (-50%)
+        assert!((state.record_sell(&mint, 0.4, 3.0) - 200_000_000.0).abs() < 1e-3);
+        state.set_position_holdings(&mint, 0.6);
+        let position = state.position(&mint).unwrap();
+        assert!((state.unrealized_pnl(&position).unwrap() - 300_000_000.0).abs() < 1e-3);
+        assert!((state.record_sell(&mint, 0.6, 1.0) + 300_000_000.0).abs() < 1e-3);
+        let position = state.position(&mint).unwrap();
+        assert!((position.avg_sell_price.unwrap() - 1.8).abs() < 1e-9);
+        assert!((position.realized_pnl_lamports + 100_000_000.0).abs() < 1e-3);
+        assert!(state.set_position_holdings(&mint, 0.0));
+
+        // Closing keeps the realized PnL; only the reset clears it
+        assert!((state.pnl.realized_lamports + 100_000_000.0).abs() < 1e-3);
+        assert_eq!(state.pnl.spent_lamports, 1_000_000_000);
+        assert_eq!(state.unrealized_pnl_lamports(), 0.0);
+        state.reset_realized_pnl();
+        assert_eq!(state.pnl, PnlLedger::default());
+    }
+
+    #[test]
+    fn entry_price_chart_stays_bounded_and_in_order() {
+        let mut samples: Vec<f64> = (0..7).map(f64::from).collect();
+        downsample_in_half(&mut samples);
+        assert_eq!(samples, vec![0.0, 2.0, 4.0, 6.0]);
+
+        let mut chart = EntryPriceChart::new(8);
+        for price in 0..100 {
+            chart.push(f64::from(price));
+            assert!(chart.samples().len() <= 8);
+        }
+        let kept = chart.samples();
+        // Still starts at the entry price, strictly increasing, evenly spaced
+        assert_eq!(kept[0], 0.0);
+        assert!(kept.windows(2).all(|pair| pair[0] < pair[1]));
+        let step = kept[1] - kept[0];
+        assert!(kept.windows(2).all(|pair| pair[1] - pair[0] == step));
+        assert!(*kept.last().unwrap() >= 100.0 - step);
+
+        // Charts follow the held mints when the ledger keeps them
+        let state = |pnl: PnlLedger| AppState {
+            mode: Mode::Sniffing,
+            active_token: None,
+            last_buy_price: None,
+            holdings_percent: 0.0,
+            quantum_suggestions: QuantumSuggestions::default(),
+            notifications: VecDeque::new(),
+            operations: BTreeMap::new(),
+            positions: HashMap::new(),
+            pnl,
+        };
+        let mut tracked = state(PnlLedger::with_history_samples(4));
+        let mint = Pubkey::new_unique();
+        for price in [1.0, 2.0, f64::NAN, 3.0] {
+            tracked.mark_price(mint, price);
+        }
+        assert_eq!(tracked.pnl.price_chart(&mint), [1.0, 2.0, 3.0]);
+        tracked.close_position(&mint);
+        assert!(tracked.pnl.price_chart(&mint).is_empty());
+        let mut untracked = state(PnlLedger::default());
+        untracked.mark_price(mint, 1.0);
+        assert!(untracked.pnl.price_chart(&mint).is_empty());
+    }
+
+    #[test]
+    fn full_suggestion_list_keeps_top_scores() {
+        let suggestion = |score: u8| QuantumCandidateGui {
+            mint: Pubkey::new_unique(),
+            score,
+            reason: format!("score {}", score),
+            feature_scores: HashMap::new(),
+            timestamp: 0,
+        };
+        let mut suggestions = QuantumSuggestions::new(3);
+        let mut dropped = Vec::new();
+        for score in [40, 90, 10, 75, 60, 95, 20] {
+            dropped.extend(suggestions.push(suggestion(score)).map(|s| s.score));
+        }
+
+        assert_eq!(suggestions.len(), 3);
+        let kept: Vec<u8> = suggestions.sorted().iter().map(|s| s.score).collect();
+        assert_eq!(kept, vec![95, 90, 75]);
+        dropped.sort();
+        assert_eq!(dropped, vec![10, 20, 40, 60]);
+
+        // A new score for a kept mint replaces its entry
+        let mut update = suggestions.sorted()[2].clone();
+        update.score = 99;
+        assert!(suggestions.push(update.clone()).is_none());
+        assert_eq!(suggestions.len(), 3);
+        assert_eq!(suggestions.sorted()[0].mint, update.mint);
+    }
+}
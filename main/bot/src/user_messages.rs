@@ -0,0 +1,211 @@
+//! Mapping of internal errors to concise, actionable messages for operators.
+//!
+//! Raw errors (e.g. "RpcManager: all sends failed (fatal_errors: 0)") stay in the
+//! tracing logs; the GUI event log and notifications show the mapped message.
+
+use solana_client::client_error::ClientError;
+
+use crate::buy_engine::{EngineError, SellFailureReason};
+use crate::rpc_manager::{classify_rpc_error, is_frozen_account_error, is_unsupported_version_error, RpcErrorType};
+use crate::tx_builder::TransactionBuilderError;
+
+/// User-facing message for a transaction builder error.
+pub fn builder_error_message(error: &TransactionBuilderError) -> String {
+    match error {
+        TransactionBuilderError::ConfigValidation(reason) => {
+            format!("Invalid transaction settings: {}", reason)
+        }
+        TransactionBuilderError::RpcConnection(_) => "RPC endpoint unreachable".to_string(),
+        TransactionBuilderError::InstructionBuild { program, .. } => {
+            format!("Could not build {} transaction", program)
+        }
+        TransactionBuilderError::SigningFailed(_) => {
+            "Wallet signing failed; check the keypair".to_string()
+        }
+        TransactionBuilderError::BlockhashFetch(_) => {
+            "Could not fetch a recent blockhash; RPC endpoints may be down".to_string()
+        }
+        TransactionBuilderError::NonceAcquisition(_) => {
+            "No free nonce slots; too many transactions in flight".to_string()
+        }
+        TransactionBuilderError::Serialization(_) => "Transaction encoding failed".to_string(),
+        TransactionBuilderError::ProgramNotAllowed(program) => {
+            format!("Program {} is not allowed by configuration", program)
+        }
+        TransactionBuilderError::DexDisabled(dex) => {
+            format!("Trading on {} is disabled in the DEX settings", dex)
+        }
+        TransactionBuilderError::FeatureNotEnabled { feature, action } => {
+            format!("{} requires the '{}' feature", action, feature)
+        }
+        TransactionBuilderError::JitoBundle(reason) => format!("Jito bundle not accepted: {}", reason),
+    }
+}
+
+/// User-facing message for a classified RPC error.
+pub fn rpc_error_message(error: &RpcErrorType) -> String {
+    match error {
+        RpcErrorType::AlreadyProcessed => "Transaction already processed".to_string(),
+        RpcErrorType::DuplicateSignature => "Transaction already submitted".to_string(),
+        RpcErrorType::BlockhashNotFound => "Blockhash expired; retry the transaction".to_string(),
+        RpcErrorType::RateLimited => "RPC rate limit hit; slow down or add endpoints".to_string(),
+        RpcErrorType::UnsupportedVersion => {
+            "RPC rejected v0 transaction; enable legacy_tx_fallback".to_string()
+        }
+        RpcErrorType::AccountFrozen => {
+            "Token account is frozen by the mint's freeze authority; it cannot be sold until thawed".to_string()
+        }
+        RpcErrorType::Other(_) => "Unexpected RPC error".to_string(),
+    }
+}
+
+/// User-facing message for a sell or operator buy that the engine refused or failed.
+pub fn engine_error_message(error: &EngineError) -> String {
+    match error {
+        EngineError::Halted(reason) => format!("Buying is halted: {}", reason),
+        EngineError::RateLimited(_) => "Too many buys of this token; wait a minute".to_string(),
+        EngineError::InsufficientFunds(_) => "Insufficient SOL balance".to_string(),
+        EngineError::NoBuilder => "Trading is not configured; no transaction builder".to_string(),
+        EngineError::BuyInProgress => "Another trade is in progress; try again shortly".to_string(),
+        EngineError::NotSniffing => "Position limit reached; sell a position first".to_string(),
+        EngineError::NotHolding => "No token held to sell".to_string(),
+        EngineError::AlreadyHolding(_) => "Already holding this token".to_string(),
+        EngineError::UnknownCandidate(_) => "Token no longer among recent candidates".to_string(),
+        EngineError::InvalidAmount(reason) => format!("Invalid amount: {}", reason),
+        EngineError::SellFailed { reason, error } => match reason {
+            SellFailureReason::Slippage => "Sell exceeded the slippage limit; retry or widen slippage".to_string(),
+            SellFailureReason::NoLiquidity => "No liquidity left to sell into".to_string(),
+            SellFailureReason::AccountFrozen => rpc_error_message(&RpcErrorType::AccountFrozen),
+            SellFailureReason::InsufficientFunds => "Insufficient SOL balance".to_string(),
+            SellFailureReason::Unconfirmed => {
+                "Sell not confirmed; check the wallet before selling again".to_string()
+            }
+            SellFailureReason::Rpc(rpc) if !matches!(rpc, RpcErrorType::Other(_)) => rpc_error_message(rpc),
+            _ => user_message(error),
+        },
+        EngineError::Other(e) => user_message(e),
+    }
+}
+
+/// Known error texts (lowercase) and the message shown for them, checked in order.
+const TEXT_MESSAGES: [(&str, &str); 9] = [
+    ("insufficient funds", "Insufficient SOL balance"),
+    ("insufficient lamports", "Insufficient SOL balance"),
+    ("no endpoints", "No RPC endpoints configured"),
+    ("all sends failed", "All RPC endpoints unreachable"),
+    ("timeout", "RPC request timed out"),
+    ("deadline exceeded", "Candidate too old; stale snipe skipped"),
+    ("no nonces acquired", "No free nonce slots; too many transactions in flight"),
+    ("not in passivetoken mode", "No token held to sell"),
+    ("operation in progress", "Another trade is in progress; try again shortly"),
+];
+
+/// User-facing message for any engine/broadcast error.
+///
+/// Typed errors anywhere in the chain win over text matching; unknown errors fall
+/// back to their top-level message.
+pub fn user_message(error: &anyhow::Error) -> String {
+    for cause in error.chain() {
+        if let Some(e) = cause.downcast_ref::<TransactionBuilderError>() {
+            return builder_error_message(e);
+        }
+        if let Some(e) = cause.downcast_ref::<EngineError>() {
+            return engine_error_message(e);
+        }
+        if let Some(e) = cause.downcast_ref::<ClientError>() {
+            let classified = classify_rpc_error(e);
+            if !matches!(classified, RpcErrorType::Other(_)) {
+                return rpc_error_message(&classified);
+            }
+        }
+    }
+
+    if is_unsupported_version_error(error) {
+        return rpc_error_message(&RpcErrorType::UnsupportedVersion);
+    }
+    if is_frozen_account_error(error) {
+        return rpc_error_message(&RpcErrorType::AccountFrozen);
+    }
+
+    let text = format!("{:#}", error).to_lowercase();
+    TEXT_MESSAGES
+        .iter()
+        .find(|(pattern, _)| text.contains(pattern))
+        .map(|(_, message)| message.to_string())
+        .unwrap_or_else(|| error.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::anyhow;
+    use solana_client::client_error::ClientErrorKind;
+    use solana_client::rpc_request::{RpcError, RpcResponseErrorData};
+
+    fn rpc_response_error(message: &str) -> ClientError {
+        ClientError::from(ClientErrorKind::RpcError(RpcError::RpcResponseError {
+            code: -32002,
+            message: message.to_string(),
+            data: RpcResponseErrorData::Empty,
+        }))
+    }
+
+    #[test]
+    fn maps_builder_errors() {
+        let err = anyhow::Error::new(TransactionBuilderError::BlockhashFetch("boom".into()))
+            .context("Transaction build failed");
+        assert_eq!(
+            user_message(&err),
+            "Could not fetch a recent blockhash; RPC endpoints may be down"
+        );
+
+        let err = anyhow::Error::new(TransactionBuilderError::FeatureNotEnabled {
+            feature: "orca".into(),
+            action: "Orca sell".into(),
+        });
+        assert_eq!(user_message(&err), "Orca sell requires the 'orca' feature");
+    }
+
+    #[test]
+    fn maps_classified_rpc_errors() {
+        let err = anyhow::Error::new(rpc_response_error("Blockhash not found")).context("RPC failed");
+        assert_eq!(user_message(&err), "Blockhash expired; retry the transaction");
+
+        let err = anyhow::Error::new(rpc_response_error("Too many requests"));
+        assert_eq!(user_message(&err), "RPC rate limit hit; slow down or add endpoints");
+    }
+
+    #[test]
+    fn maps_broadcast_error_texts() {
+        let err = anyhow!("RpcManager: all sends failed (fatal_errors: 0)").context("broadcast BUY failed");
+        assert_eq!(user_message(&err), "All RPC endpoints unreachable");
+
+        let err = anyhow!("RpcManager: all sends failed: unsupported transaction version (2 endpoints)");
+        assert_eq!(user_message(&err), "RPC rejected v0 transaction; enable legacy_tx_fallback");
+
+        let err = anyhow!("Transaction simulation failed: insufficient funds for fee");
+        assert_eq!(user_message(&err), "Insufficient SOL balance");
+
+        let err = anyhow!("not in PassiveToken mode");
+        assert_eq!(user_message(&err), "No token held to sell");
+    }
+
+    #[test]
+    fn engine_errors_map_by_variant() {
+        assert_eq!(engine_error_message(&EngineError::NotHolding), "No token held to sell");
+        assert_eq!(
+            engine_error_message(&EngineError::BuyInProgress),
+            "Another trade is in progress; try again shortly"
+        );
+        let wrapped = anyhow::Error::new(EngineError::InsufficientFunds("reserve".into())).context("buy failed");
+        assert_eq!(user_message(&wrapped), "Insufficient SOL balance");
+        let other = EngineError::Other(anyhow!("RpcManager: all sends failed"));
+        assert_eq!(engine_error_message(&other), "All RPC endpoints unreachable");
+    }
+
+    #[test]
+    fn unknown_errors_fall_back_to_top_level_message() {
+        let err = anyhow!("inner detail").context("something odd happened");
+        assert_eq!(user_message(&err), "something odd happened");
+    }
+}
@@ -1,7 +1,7 @@
 //! Wallet management for keypair loading and transaction signing.
 
 use anyhow::{anyhow, Result};
-use solana_sdk::{pubkey::Pubkey, signature::{Keypair, Signer}, transaction::VersionedTransaction};
+use solana_sdk::{pubkey::Pubkey, signature::{Keypair, Signature, Signer}, transaction::VersionedTransaction};
 use std::{fs, path::Path};
 use tracing::{info, debug};
 
@@ -42,11 +42,19 @@ impl WalletManager {
         self.keypair.pubkey()
     }
 
-    /// Sign a transaction
+    /// Sign a transaction's message in this wallet's signer slot
     pub fn sign_transaction(&self, tx: &mut VersionedTransaction) -> Result<()> {
-        // Simple signature placeholder - in production, proper signing would be implemented
-        tx.signatures[0] = self.keypair.sign_message(b"placeholder_message");
-        debug!("Transaction signed with pubkey: {}", self.keypair.pubkey());
+        let pubkey = self.keypair.pubkey();
+        let required = tx.message.header().num_required_signatures as usize;
+        let slot = tx.message.static_account_keys()[..required.min(tx.message.static_account_keys().len())]
+            .iter()
+            .position(|key| *key == pubkey)
+            .ok_or_else(|| anyhow!("Wallet {} is not a signer of this transaction", pubkey))?;
+        if tx.signatures.len() != required {
+            tx.signatures = vec![Signature::default(); required];
+        }
+        tx.signatures[slot] = self.keypair.sign_message(&tx.message.serialize());
+        debug!("Transaction signed with pubkey: {}", pubkey);
         Ok(())
     }
 
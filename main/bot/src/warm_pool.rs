@@ -0,0 +1,216 @@
+//! Pre-built unsigned buy transactions for mints likely to be bought soon.
+//!
+//! Building a buy costs RPC round trips (quotes, account lookups) before anything
+//! is signed. For high-score quantum suggestions the engine can build the buy ahead
+//! of the trigger and park it here; the trigger then only has to sign it. An entry
+//! only serves a buy with the same build parameters (amount, slippage, priority fee,
+//! message format), and only for the blockhash it was compiled with: once the builder
+//! sees a new blockhash the entry is dropped and the next buy of that mint is built cold.
+
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+
+use solana_sdk::{hash::Hash, pubkey::Pubkey, transaction::VersionedTransaction};
+
+use crate::metrics::metrics;
+use crate::tx_builder::TransactionConfig;
+
+/// What a buy was built for; a pre-built buy only serves a buy with the same key.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct WarmKey {
+    pub mint: Pubkey,
+    pub buy_amount_lamports: u64,
+    pub slippage_bps: u64,
+    pub priority_fee_lamports: u64,
+    pub legacy: bool,
+}
+
+impl WarmKey {
+    /// Key of a buy of `mint` built with `config`.
+    pub fn for_buy(mint: Pubkey, config: &TransactionConfig) -> Self {
+        Self {
+            mint,
+            buy_amount_lamports: config.buy_amount_lamports,
+            slippage_bps: config.slippage_bps,
+            priority_fee_lamports: config.priority_fee_lamports,
+            legacy: config.legacy_message,
+        }
+    }
+}
+
+struct Prebuilt {
+    tx: VersionedTransaction,
+    // Insertion order, for evicting the oldest entry
+    seq: u64,
+}
+
+/// Unsigned buy transactions keyed by mint and build parameters, at most
+/// `max_entries` at a time.
+pub struct WarmPool {
+    max_entries: usize,
+    entries: Mutex<HashMap<WarmKey, Prebuilt>>,
+    next_seq: AtomicU64,
+}
+
+impl WarmPool {
+    pub fn new(max_entries: usize) -> Self {
+        Self { max_entries, entries: Mutex::new(HashMap::new()), next_seq: AtomicU64::new(0) }
+    }
+
+    /// Park an unsigned buy built for `key`, replacing any earlier one of the same
+    /// mint. When full, the oldest entry makes room.
+    pub fn insert(&self, key: WarmKey, tx: VersionedTransaction) {
+        if self.max_entries == 0 {
+            return;
+        }
+        let mut entries = self.entries.lock().unwrap();
+        entries.retain(|held, _| held.mint != key.mint);
+        if entries.len() >= self.max_entries {
+            if let Some(oldest) = entries.iter().min_by_key(|(_, entry)| entry.seq).map(|(key, _)| *key) {
+                entries.remove(&oldest);
+            }
+        }
+        let seq = self.next_seq.fetch_add(1, Ordering::Relaxed);
+        entries.insert(key, Prebuilt { tx, seq });
+        metrics().set_gauge("warm_pool_entries", entries.len() as u64);
+    }
+
+    /// Take the pre-built buy for `key` if it was compiled with `blockhash`. Any
+    /// entry of the mint is used up: one built with other parameters or for an
+    /// older blockhash is dropped.
+    pub fn take(&self, key: &WarmKey, blockhash: &Hash) -> Option<VersionedTransaction> {
+        let mut entries = self.entries.lock().unwrap();
+        let held = entries.keys().find(|held| held.mint == key.mint).copied()?;
+        let entry = entries.remove(&held)?;
+        metrics().set_gauge("warm_pool_entries", entries.len() as u64);
+        if held != *key || entry.tx.message.recent_blockhash() != blockhash {
+            metrics().increment_counter("warm_pool_stale_total");
+            return None;
+        }
+        metrics().increment_counter("warm_pool_hits_total");
+        Some(entry.tx)
+    }
+
+    /// Drop every entry not compiled with `blockhash`; returns how many were dropped.
+    pub fn invalidate(&self, blockhash: &Hash) -> usize {
+        let mut entries = self.entries.lock().unwrap();
+        let before = entries.len();
+        entries.retain(|_, entry| entry.tx.message.recent_blockhash() == blockhash);
+        metrics().set_gauge("warm_pool_entries", entries.len() as u64);
+        before - entries.len()
+    }
+
+    pub fn contains(&self, mint: &Pubkey) -> bool {
+        self.entries.lock().unwrap().keys().any(|key| key.mint == *mint)
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.lock().unwrap().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl Default for WarmPool {
+    /// An empty pool that never holds anything (warm pool disabled).
+    fn default() -> Self {
+        Self::new(0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+    use std::time::{Duration, Instant};
+
+    use solana_sdk::{
+        message::{Message, VersionedMessage},
+        signature::Signature,
+    };
+    use solana_system_interface::instruction as system_instruction;
+
+    use crate::test_support::offline_builder;
+    use crate::types::PremintCandidate;
+
+    fn build_unsigned(payer: &Pubkey, blockhash: Hash) -> VersionedTransaction {
+        let ix = system_instruction::transfer(payer, &Pubkey::new_unique(), 1);
+        let message = VersionedMessage::Legacy(Message::new_with_blockhash(&[ix], Some(payer), &blockhash));
+        VersionedTransaction { signatures: vec![Signature::default()], message }
+    }
+
+    fn key(mint: Pubkey) -> WarmKey {
+        WarmKey::for_buy(mint, &TransactionConfig { legacy_message: true, ..TransactionConfig::default() })
+    }
+
+    // Without the pumpfun feature, pump.fun buys build offline
+    #[cfg(not(feature = "pumpfun"))]
+    #[tokio::test]
+    async fn prebuilt_tx_is_finalized_faster_than_cold_build() {
+        // A cold build estimates the priority fee of each new mint; a pre-built one already has
+        let config = TransactionConfig { dynamic_priority_fee: true, ..TransactionConfig::default() };
+        let pool = Arc::new(WarmPool::new(4));
+        let builder = offline_builder(&config).await.with_warm_pool(pool.clone());
+        let candidate = || PremintCandidate {
+            mint: Pubkey::new_unique(),
+            creator: Pubkey::new_unique(),
+            program: "pump.fun".to_string(),
+            slot: 0,
+            timestamp: 0, instruction_summary: None, is_jito_bundle: None, deadline_ms: None,
+        };
+
+        // Best of a few rounds each, so scheduling noise does not decide the comparison
+        let (mut cold, mut warm) = (Duration::MAX, Duration::MAX);
+        for _ in 0..3 {
+            let start = Instant::now();
+            let tx = builder.build_buy_transaction(&candidate(), &config, true).await.unwrap();
+            cold = cold.min(start.elapsed());
+            assert!(tx.verify_with_results().iter().all(|ok| *ok));
+
+            // Built ahead of the trigger, so only signing is left
+            let pending = candidate();
+            builder.prebuild_buy(&pending, &config).await.unwrap();
+            let start = Instant::now();
+            let tx = builder.build_buy_transaction(&pending, &config, true).await.unwrap();
+            warm = warm.min(start.elapsed());
+            assert!(tx.verify_with_results().iter().all(|ok| *ok));
+            assert!(pool.is_empty(), "the pre-built buy was used");
+        }
+        assert!(warm < cold, "warm {:?} vs cold {:?}", warm, cold);
+    }
+
+    #[test]
+    fn new_blockhash_invalidates_prebuilt_txs() {
+        let payer = Pubkey::new_unique();
+        let (first, second) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let old = Hash::new_unique();
+        let new = Hash::new_unique();
+        let pool = WarmPool::new(4);
+
+        pool.insert(key(first), build_unsigned(&payer, old));
+        pool.insert(key(second), build_unsigned(&payer, new));
+        assert_eq!(pool.invalidate(&new), 1);
+        assert!(!pool.contains(&first));
+
+        // Other build parameters or blockhash at trigger time: built cold instead
+        let bigger = WarmKey { buy_amount_lamports: key(second).buy_amount_lamports * 2, ..key(second) };
+        assert!(pool.take(&bigger, &new).is_none());
+        assert!(pool.is_empty());
+        pool.insert(key(second), build_unsigned(&payer, new));
+        assert!(pool.take(&WarmKey { legacy: false, ..key(second) }, &new).is_none());
+        pool.insert(key(second), build_unsigned(&payer, new));
+        assert!(pool.take(&key(second), &old).is_none());
+        assert!(pool.is_empty());
+
+        // Full pool: the oldest entry makes room
+        let small = WarmPool::new(2);
+        for mint in [first, second, Pubkey::new_unique()] {
+            small.insert(key(mint), build_unsigned(&payer, new));
+        }
+        assert_eq!(small.len(), 2);
+        assert!(!small.contains(&first));
+    }
+}
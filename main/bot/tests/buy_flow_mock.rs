@@ -13,7 +13,7 @@ use sniffer_bot_light::buy_engine::BuyEngine;
 use sniffer_bot_light::config::Config;
 use sniffer_bot_light::nonce_manager::NonceManager;
 use sniffer_bot_light::observability::CorrelationId;
-use sniffer_bot_light::rpc_manager::RpcBroadcaster;
+use sniffer_bot_light::rpc_manager::{RpcBroadcaster, SendOutcome};
 use sniffer_bot_light::types::{AppState, CandidateReceiver, CandidateSender, Mode, PremintCandidate};
 
 #[derive(Clone, Debug)]
@@ -30,7 +30,7 @@ impl RpcBroadcaster for PatternBroadcaster {
         &'a self,
         txs: Vec<VersionedTransaction>,
         _correlation_id: Option<CorrelationId>,
-    ) -> Pin<Box<dyn Future<Output = anyhow::Result<Signature>> + Send + 'a>> {
+    ) -> Pin<Box<dyn Future<Output = anyhow::Result<SendOutcome>> + Send + 'a>> {
         Box::pin(async move {
             let n = txs.len();
             if let Some((i, _)) = self
@@ -42,7 +42,7 @@ impl RpcBroadcaster for PatternBroadcaster {
             {
                 let mut b = [0u8; 64];
                 b[0] = (i as u8) + 1;
-                return Ok(Signature::from(b));
+                return Ok(SendOutcome { signature: Signature::from(b), endpoint: format!("endpoint-{}", i), latency_ms: 0, was_duplicate: false, bundle_id: None });
             }
             anyhow::bail!("PatternBroadcaster: all endpoints failing for {} tx(s)", n);
         })
@@ -56,8 +56,10 @@ impl RpcBroadcaster for AlwaysOk {
         &'a self,
         _txs: Vec<VersionedTransaction>,
         _correlation_id: Option<CorrelationId>,
-    ) -> Pin<Box<dyn Future<Output = anyhow::Result<Signature>> + Send + 'a>> {
-        Box::pin(async move { Ok(Signature::from([9u8; 64])) })
+    ) -> Pin<Box<dyn Future<Output = anyhow::Result<SendOutcome>> + Send + 'a>> {
+        Box::pin(async move {
+            Ok(SendOutcome { signature: Signature::from([9u8; 64]), endpoint: "mock".to_string(), latency_ms: 0, was_duplicate: false, bundle_id: None })
+        })
     }
 }
 
@@ -94,6 +96,9 @@ async fn buy_first_success_pattern_and_state_transitions() {
         program: "pump.fun".to_string(),
         slot: 0,
         timestamp: 0,
+        instruction_summary: None,
+        is_jito_bundle: None,
+        deadline_ms: None,
     };
 
     // Call private logic indirectly by simulating state update on success:
@@ -118,7 +123,7 @@ async fn buy_first_success_pattern_and_state_transitions() {
     );
 
     engine_for_sell
-        .sell(1.0)
+        .sell(candidate.mint, 1.0)
         .await
         .expect("sell should succeed with AlwaysOk broadcaster");
 
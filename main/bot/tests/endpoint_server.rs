@@ -0,0 +1,60 @@
+//! The endpoint server answers over real HTTP: started on an ephemeral port, its
+//! health, metrics and scoreboard routes respond with their content types.
+
+use std::net::TcpListener;
+use std::sync::Arc;
+
+use sniffer_bot_light::endpoints::{EndpointServer, JSON_CONTENT_TYPE, PROMETHEUS_CONTENT_TYPE};
+use sniffer_bot_light::metrics::metrics;
+
+async fn get(client: &reqwest::Client, url: String) -> (u16, String, String) {
+    let response = client.get(url).send().await.expect("endpoint server should answer");
+    let status = response.status().as_u16();
+    let content_type = response
+        .headers()
+        .get(reqwest::header::CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .unwrap_or_default()
+        .to_string();
+    (status, content_type, response.text().await.unwrap())
+}
+
+#[tokio::test]
+async fn health_metrics_and_scoreboard_are_served_over_http() {
+    let server = Arc::new(EndpointServer::new());
+    server.update_scoreboard("mint-slow", "pump.fun", false, 300).await;
+    server.update_scoreboard("mint-fast", "pump.fun", true, 100).await;
+    metrics().increment_counter("endpoint_server_test_total");
+
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let base = format!("http://{}", listener.local_addr().unwrap());
+    let handle = tokio::spawn(server.serve_listener(listener));
+    let client = reqwest::Client::new();
+
+    let (status, content_type, body) = get(&client, format!("{}/health", base)).await;
+    assert_eq!(status, 200);
+    assert_eq!(content_type, JSON_CONTENT_TYPE);
+    assert!(body.contains("healthy"));
+
+    let (status, content_type, body) = get(&client, format!("{}/metrics", base)).await;
+    assert_eq!(status, 200);
+    assert_eq!(content_type, PROMETHEUS_CONTENT_TYPE);
+    assert!(body.contains("# TYPE endpoint_server_test_total counter"));
+
+    let (status, content_type, body) = get(&client, format!("{}/scoreboard", base)).await;
+    assert_eq!(status, 200);
+    assert_eq!(content_type, JSON_CONTENT_TYPE);
+    let body: serde_json::Value = serde_json::from_str(&body).unwrap();
+    // Sorted by success rate
+    assert_eq!(body["scoreboard"][0]["mint"], "mint-fast");
+    assert_eq!(body["scoreboard"][1]["mint"], "mint-slow");
+
+    let (status, _, _) = get(&client, format!("{}/scoreboard?limit=1", base)).await;
+    assert_eq!(status, 200);
+    let (status, _, _) = get(&client, format!("{}/nope", base)).await;
+    assert_eq!(status, 404);
+    let (status, _, _) = get(&client, format!("{}/config", base)).await;
+    assert_eq!(status, 503, "config is unavailable until the engine registers it");
+
+    handle.abort();
+}
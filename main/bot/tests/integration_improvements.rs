@@ -50,6 +50,9 @@ fn test_security_validation_integration() {
             .duration_since(std::time::UNIX_EPOCH)
             .unwrap()
             .as_secs(),
+        instruction_summary: None,
+        is_jito_bundle: None,
+        deadline_ms: None,
     };
 
     let validation = validator().validate_candidate(&candidate);
@@ -106,6 +109,9 @@ async fn test_performance_optimizations() {
             program: "pump.fun".to_string(),
             slot: i as u64,
             timestamp: i as u64,
+            instruction_summary: None,
+            is_jito_bundle: None,
+            deadline_ms: None,
         };
         buffer.push(candidate);
     }
@@ -20,6 +20,9 @@ async fn test_complete_transaction_workflow() {
         program: "pump.fun".to_string(),
         slot: 12345,
         timestamp: 1699999999,
+        instruction_summary: None,
+        is_jito_bundle: None,
+        deadline_ms: None,
     };
     
     // Create transaction config with conservative settings
@@ -115,6 +118,9 @@ async fn test_transaction_builder_with_different_programs() {
             program: program.to_string(),
             slot: 12345,
             timestamp: 1699999999,
+            instruction_summary: None,
+            is_jito_bundle: None,
+            deadline_ms: None,
         };
         
         let result = builder.build_buy_transaction(
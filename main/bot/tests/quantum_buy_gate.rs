@@ -0,0 +1,125 @@
+//! The engine fed by the quantum selector buys only candidates scoring at least
+//! `min_buy_score`. Buys without a transaction builder need placeholder transactions.
+#![cfg(feature = "mock-mode")]
+
+use std::collections::HashMap;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::time::Duration;
+
+use solana_sdk::{pubkey::Pubkey, signature::Signature, transaction::VersionedTransaction};
+use tokio::sync::{mpsc, Mutex};
+
+use sniffer_bot_light::buy_engine::BuyEngine;
+use sniffer_bot_light::config::Config;
+use sniffer_bot_light::nonce_manager::NonceManager;
+use sniffer_bot_light::observability::CorrelationId;
+use sniffer_bot_light::quantum_selector::ScoredCandidate;
+use sniffer_bot_light::rpc_manager::{RpcBroadcaster, SendOutcome};
+use sniffer_bot_light::types::{AppState, Mode, PremintCandidate};
+
+#[derive(Debug)]
+struct AlwaysOk;
+impl RpcBroadcaster for AlwaysOk {
+    fn send_on_many_rpc<'a>(
+        &'a self,
+        _txs: Vec<VersionedTransaction>,
+        _correlation_id: Option<CorrelationId>,
+    ) -> Pin<Box<dyn Future<Output = anyhow::Result<SendOutcome>> + Send + 'a>> {
+        Box::pin(async move {
+            Ok(SendOutcome {
+                signature: Signature::new_unique(),
+                endpoint: "mock".to_string(),
+                latency_ms: 0,
+                was_duplicate: false,
+                bundle_id: None,
+            })
+        })
+    }
+}
+
+/// Stands in for `PredictiveOracle`: scores each candidate from `scores` and
+/// forwards it, closing the scored channel once the candidates run out.
+fn spawn_stub_oracle(
+    mut candidates: mpsc::Receiver<PremintCandidate>,
+    scored: mpsc::Sender<ScoredCandidate>,
+    scores: HashMap<Pubkey, u8>,
+) {
+    tokio::spawn(async move {
+        while let Some(candidate) = candidates.recv().await {
+            let predicted_score = scores.get(&candidate.mint).copied().unwrap_or(0);
+            let scored_candidate = ScoredCandidate {
+                mint: candidate.mint,
+                predicted_score,
+                feature_scores: HashMap::new(),
+                reason: "stub".to_string(),
+                timestamp: candidate.timestamp,
+                calculation_time: 0,
+                candidate,
+            };
+            if scored.send(scored_candidate).await.is_err() {
+                break;
+            }
+        }
+    });
+}
+
+fn candidate() -> PremintCandidate {
+    PremintCandidate {
+        mint: Pubkey::new_unique(),
+        creator: Pubkey::new_unique(),
+        program: "pump.fun".to_string(),
+        slot: 0,
+        timestamp: 0,
+        instruction_summary: None,
+        is_jito_bundle: None,
+        deadline_ms: None,
+    }
+}
+
+#[tokio::test]
+async fn only_candidates_scoring_the_threshold_are_bought() {
+    let app_state = Arc::new(Mutex::new(AppState {
+        mode: Mode::Sniffing,
+        active_token: None,
+        last_buy_price: None,
+        holdings_percent: 0.0,
+        quantum_suggestions: Default::default(),
+        notifications: Default::default(),
+        operations: Default::default(),
+        positions: Default::default(),
+        pnl: Default::default(),
+    }));
+    let low = candidate();
+    let high = candidate();
+
+    let (sniffer_tx, oracle_rx) = mpsc::channel(8);
+    let (scored_tx, scored_rx) = mpsc::channel(8);
+    spawn_stub_oracle(oracle_rx, scored_tx, HashMap::from([(low.mint, 40), (high.mint, 90)]));
+
+    // The direct channel stays idle: candidates reach the engine through the oracle
+    let (_direct_tx, direct_rx) = mpsc::channel(1);
+    let mut engine = BuyEngine::new(
+        Arc::new(AlwaysOk),
+        Arc::new(NonceManager::new(1)),
+        direct_rx,
+        app_state.clone(),
+        Config { nonce_count: 1, oracle_enabled: true, min_buy_score: 75, ..Config::default() },
+        None,
+    )
+    .with_scored_candidates(scored_rx);
+
+    sniffer_tx.send(low.clone()).await.unwrap();
+    sniffer_tx.send(high.clone()).await.unwrap();
+    drop(sniffer_tx);
+    // The engine stops once the oracle closes the scored channel
+    tokio::time::timeout(Duration::from_secs(10), engine.run())
+        .await
+        .expect("engine should stop when the scored channel closes");
+
+    let st = app_state.lock().await;
+    assert!(st.position(&high.mint).is_some(), "candidate scoring 90 should be bought");
+    assert!(st.position(&low.mint).is_none(), "candidate scoring 40 should be skipped");
+    assert!(matches!(st.mode, Mode::PassiveToken(mint) if mint == high.mint));
+}
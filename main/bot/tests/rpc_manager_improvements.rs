@@ -5,8 +5,8 @@ use std::sync::Arc;
 use solana_sdk::{
     hash::Hash,
     pubkey::Pubkey,
-    system_instruction,
 };
+use solana_system_interface::instruction as system_instruction;
 
 #[tokio::test]
 async fn test_broadcast_mode_configuration() {
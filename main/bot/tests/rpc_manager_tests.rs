@@ -7,8 +7,8 @@ use solana_sdk::{
     transaction::VersionedTransaction,
     pubkey::Pubkey,
     message::{Message, VersionedMessage},
-    system_instruction,
 };
+use solana_system_interface::instruction as system_instruction;
 use std::future::Future;
 use std::pin::Pin;
 